@@ -285,6 +285,110 @@ pub mod sp1 {
     }
 }
 
+/// Adapter for Groth16 (BN254) proofs produced outside of the SDK, e.g. by snarkjs or gnark.
+/// The proving itself is delegated to a user-provided function, which receives the borsh-encoded
+/// commitment metadata and calldatas and must return a [Groth16Proof] whose public inputs
+/// follow the layout documented in `hyle_verifiers::noir_utils::parse_hyle_output_fields`.
+pub mod groth16 {
+    use std::future::Future;
+
+    use anyhow::{bail, Context};
+    use sdk::{verifiers::Groth16Proof, ProofMetadata};
+
+    use super::*;
+
+    type ProveFn = dyn Fn(Vec<u8>, Vec<u8>) -> Pin<Box<dyn Future<Output = Result<Groth16Proof>> + Send>>
+        + Send
+        + Sync;
+
+    pub struct Groth16Prover {
+        name: String,
+        prove_fn: Box<ProveFn>,
+    }
+
+    impl Groth16Prover {
+        /// `prove_fn` is called with the commitment metadata and the borsh-encoded calldatas.
+        pub fn new<F, Fut>(name: impl Into<String>, prove_fn: F) -> Self
+        where
+            F: Fn(Vec<u8>, Vec<u8>) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<Groth16Proof>> + Send + 'static,
+        {
+            Self {
+                name: name.into(),
+                prove_fn: Box::new(move |commitment_metadata, calldatas| {
+                    Box::pin(prove_fn(commitment_metadata, calldatas))
+                }),
+            }
+        }
+
+        pub async fn prove<T: BorshSerialize>(
+            &self,
+            commitment_metadata: Vec<u8>,
+            calldatas: T,
+        ) -> Result<Proof> {
+            let calldatas = borsh::to_vec(&calldatas)?;
+            let proof = (self.prove_fn)(commitment_metadata, calldatas)
+                .await
+                .context("External Groth16 prover failed")?;
+            Ok(Proof {
+                data: ProofData(borsh::to_vec(&proof)?),
+                metadata: ProofMetadata {
+                    cycles: None,
+                    prover: Some(self.name.clone()),
+                    id: None,
+                },
+            })
+        }
+    }
+
+    impl<T: BorshSerialize + Send + 'static> ClientSdkProver<T> for Groth16Prover {
+        fn prove(
+            &self,
+            commitment_metadata: Vec<u8>,
+            calldatas: T,
+        ) -> Pin<Box<dyn std::future::Future<Output = Result<Proof>> + Send + '_>> {
+            Box::pin(self.prove(commitment_metadata, calldatas))
+        }
+        fn info(&self) -> ProverInfo {
+            ProverInfo {
+                name: self.name.clone(),
+                zkvm: sdk::verifiers::GROTH16_BN254.to_string(),
+                version: "1.0.0".to_string(),
+            }
+        }
+    }
+
+    /// Converts public signals as output by snarkjs (decimal strings) into
+    /// 32-byte big-endian public inputs.
+    pub fn public_inputs_from_decimal(signals: &[String]) -> Result<Vec<[u8; 32]>> {
+        signals
+            .iter()
+            .map(|signal| {
+                let mut value = [0u8; 32];
+                if signal.is_empty() {
+                    bail!("Empty public signal");
+                }
+                for c in signal.chars() {
+                    let Some(digit) = c.to_digit(10) else {
+                        bail!("Invalid public signal {signal}");
+                    };
+                    // value = value * 10 + digit
+                    let mut carry = digit;
+                    for byte in value.iter_mut().rev() {
+                        let v = *byte as u32 * 10 + carry;
+                        *byte = v as u8;
+                        carry = v >> 8;
+                    }
+                    if carry != 0 {
+                        bail!("Public signal {signal} does not fit in 32 bytes");
+                    }
+                }
+                Ok(value)
+            })
+            .collect()
+    }
+}
+
 pub mod test {
     use borsh::BorshDeserialize;
     use sdk::{ProofMetadata, TransactionalZkContract, ZkContract};
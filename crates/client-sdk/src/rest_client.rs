@@ -2,18 +2,20 @@ use std::{
     future::Future,
     ops::{Deref, DerefMut},
     pin::Pin,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result};
 use hyle_net::http::HttpClient;
 use sdk::{
     api::{
-        APIBlob, APIBlock, APIContract, APINodeContract, APIRegisterContract, APIStaking,
-        APITransaction, NodeInfo, TransactionWithBlobs,
+        APIBlob, APIBlock, APIContract, APIContractHistory, APINodeContract, APIRegisterContract,
+        APISettlementSimulation, APISettlementSimulationRequest, APIStaking, APITransaction,
+        APITxStatus, NodeInfo, TransactionWithBlobs, TxStatusKind,
     },
-    BlobIndex, BlobTransaction, BlockHash, BlockHeight, ConsensusInfo, Contract, ContractName,
-    ProofTransaction, TxHash, UnsettledBlobTransaction, ValidatorPublicKey,
+    BlobIndex, BlobTransaction, BlockHash, BlockHeight, ConsensusInfo, Contract,
+    ContractCodeVersion, ContractInclusionProof, ContractName, ProofTransaction, TxHash,
+    TxInclusionProof, UnsettledBlobTransaction, ValidatorPublicKey,
 };
 
 #[derive(Clone)]
@@ -189,6 +191,16 @@ pub trait NodeApiClient {
         contract_name: ContractName,
     ) -> Pin<Box<dyn Future<Output = Result<APINodeContract>> + Send + '_>>;
 
+    fn get_contract_proof(
+        &self,
+        contract_name: ContractName,
+    ) -> Pin<Box<dyn Future<Output = Result<ContractInclusionProof>> + Send + '_>>;
+
+    fn get_contract_history(
+        &self,
+        contract_name: ContractName,
+    ) -> Pin<Box<dyn Future<Output = Result<APIContractHistory>> + Send + '_>>;
+
     fn get_settled_height(
         &self,
         contract_name: ContractName,
@@ -198,8 +210,40 @@ pub trait NodeApiClient {
         &self,
         blob_tx_hash: TxHash,
     ) -> Pin<Box<dyn Future<Output = Result<UnsettledBlobTransaction>> + Send + '_>>;
+
+    /// Inclusion proof of a transaction in the block it was sequenced in.
+    fn get_tx_inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> Pin<Box<dyn Future<Output = Result<TxInclusionProof>> + Send + '_>>;
+
+    /// Runs a blob transaction through the node state without submitting it.
+    fn simulate_settlement(
+        &self,
+        request: APISettlementSimulationRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<APISettlementSimulation>> + Send + '_>>;
+
+    /// Lifecycle status of a transaction, as seen by the node.
+    /// With `after_version`, the node waits up to `wait` for the status to move past that version.
+    fn get_tx_status(
+        &self,
+        tx_hash: TxHash,
+        after_version: Option<u64>,
+        wait: Duration,
+    ) -> Pin<Box<dyn Future<Output = Result<APITxStatus>> + Send + '_>>;
+
+    /// Waits until the transaction reaches `kind`, or a final status.
+    fn wait_for_tx_status(
+        &self,
+        tx_hash: TxHash,
+        kind: TxStatusKind,
+        timeout: Duration,
+    ) -> Pin<Box<dyn Future<Output = Result<APITxStatus>> + Send + '_>>;
 }
 
+/// Longest time the node holds a tx status request.
+const MAX_TX_STATUS_WAIT: Duration = Duration::from_secs(30);
+
 impl NodeApiHttpClient {
     pub fn new(url: String) -> Result<Self> {
         Ok(NodeApiHttpClient {
@@ -311,6 +355,30 @@ impl NodeApiClient for NodeApiHttpClient {
         })
     }
 
+    fn get_contract_proof(
+        &self,
+        contract_name: ContractName,
+    ) -> Pin<Box<dyn Future<Output = Result<ContractInclusionProof>> + Send + '_>> {
+        Box::pin(async move {
+            self.get(&format!("v1/contract/{contract_name}/proof"))
+                .await
+                .context(format!(
+                    "getting inclusion proof for contract {contract_name}"
+                ))
+        })
+    }
+
+    fn get_contract_history(
+        &self,
+        contract_name: ContractName,
+    ) -> Pin<Box<dyn Future<Output = Result<APIContractHistory>> + Send + '_>> {
+        Box::pin(async move {
+            self.get(&format!("v1/contract/{contract_name}/history"))
+                .await
+                .context(format!("getting history for contract {contract_name}"))
+        })
+    }
+
     fn get_unsettled_tx(
         &self,
         blob_tx_hash: TxHash,
@@ -322,6 +390,28 @@ impl NodeApiClient for NodeApiHttpClient {
         })
     }
 
+    fn get_tx_inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> Pin<Box<dyn Future<Output = Result<TxInclusionProof>> + Send + '_>> {
+        Box::pin(async move {
+            self.get(&format!("v1/da/tx/{tx_hash}/proof"))
+                .await
+                .context(format!("getting inclusion proof for tx {tx_hash}"))
+        })
+    }
+
+    fn simulate_settlement(
+        &self,
+        request: APISettlementSimulationRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<APISettlementSimulation>> + Send + '_>> {
+        Box::pin(async move {
+            self.post_json("v1/tx/simulate_settlement", &request)
+                .await
+                .context("Simulating settlement")
+        })
+    }
+
     fn get_settled_height(
         &self,
         contract_name: ContractName,
@@ -334,6 +424,58 @@ impl NodeApiClient for NodeApiHttpClient {
                 ))
         })
     }
+
+    fn get_tx_status(
+        &self,
+        tx_hash: TxHash,
+        after_version: Option<u64>,
+        wait: Duration,
+    ) -> Pin<Box<dyn Future<Output = Result<APITxStatus>> + Send + '_>> {
+        Box::pin(async move {
+            let endpoint = match after_version {
+                Some(version) => format!(
+                    "v1/tx/{tx_hash}/status?after_version={version}&wait_ms={}",
+                    wait.as_millis()
+                ),
+                None => format!("v1/tx/{tx_hash}/status"),
+            };
+            self.get(&endpoint)
+                .await
+                .context(format!("getting status of tx {tx_hash}"))
+        })
+    }
+
+    fn wait_for_tx_status(
+        &self,
+        tx_hash: TxHash,
+        kind: TxStatusKind,
+        timeout: Duration,
+    ) -> Pin<Box<dyn Future<Output = Result<APITxStatus>> + Send + '_>> {
+        Box::pin(async move {
+            let deadline = Instant::now() + timeout;
+            let mut after_version = None;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let status = self
+                    .get_tx_status(
+                        tx_hash.clone(),
+                        after_version,
+                        remaining.min(MAX_TX_STATUS_WAIT),
+                    )
+                    .await?;
+                if status.status.has_reached(kind) {
+                    return Ok(status);
+                }
+                if remaining.is_zero() {
+                    anyhow::bail!(
+                        "Timeout waiting for tx {tx_hash} to reach {kind:?}, last status: {:?}",
+                        status.status
+                    );
+                }
+                after_version = Some(status.version);
+            }
+        })
+    }
 }
 impl Deref for NodeApiHttpClient {
     type Target = HttpClient;
@@ -350,7 +492,7 @@ impl DerefMut for NodeApiHttpClient {
 
 #[allow(dead_code)]
 pub mod test {
-    use sdk::{hyle_model_utils::TimestampMs, Hashed, TimeoutWindow};
+    use sdk::{api::TxStatus, hyle_model_utils::TimestampMs, Hashed, TimeoutWindow};
 
     use super::*;
     use std::sync::{Arc, Mutex};
@@ -366,6 +508,7 @@ pub mod test {
         pub unsettled_txs: Arc<Mutex<std::collections::HashMap<TxHash, UnsettledBlobTransaction>>>,
         pub pending_proofs: Arc<Mutex<Vec<ProofTransaction>>>,
         pub pending_blobs: Arc<Mutex<Vec<BlobTransaction>>>,
+        pub tx_statuses: Arc<Mutex<std::collections::HashMap<TxHash, APITxStatus>>>,
     }
 
     impl NodeApiMockClient {
@@ -376,9 +519,12 @@ pub mod test {
                 consensus_info: Arc::new(Mutex::new(ConsensusInfo {
                     slot: 0,
                     view: 0,
+                    epoch: 0,
                     round_leader: ValidatorPublicKey::default(),
                     last_timestamp: TimestampMs::default(),
                     validators: vec![],
+                    stakes: Default::default(),
+                    total_bond: 0,
                 })),
                 node_info: Arc::new(Mutex::new(NodeInfo {
                     id: "mock_node_id".to_string(),
@@ -390,6 +536,7 @@ pub mod test {
                 unsettled_txs: Arc::new(Mutex::new(std::collections::HashMap::new())),
                 pending_proofs: Arc::new(Mutex::new(vec![])),
                 pending_blobs: Arc::new(Mutex::new(vec![])),
+                tx_statuses: Arc::new(Mutex::new(std::collections::HashMap::new())),
             }
         }
 
@@ -423,6 +570,32 @@ pub mod test {
         pub fn add_unsettled_tx(&self, tx_hash: TxHash, tx: UnsettledBlobTransaction) {
             self.unsettled_txs.lock().unwrap().insert(tx_hash, tx);
         }
+
+        pub fn set_tx_status(&self, tx_hash: TxHash, status: TxStatus) {
+            let mut statuses = self.tx_statuses.lock().unwrap();
+            let version = statuses.get(&tx_hash).map_or(1, |s| s.version + 1);
+            statuses.insert(
+                tx_hash.clone(),
+                APITxStatus {
+                    tx_hash,
+                    version,
+                    status,
+                },
+            );
+        }
+
+        fn tx_status(&self, tx_hash: TxHash) -> APITxStatus {
+            self.tx_statuses
+                .lock()
+                .unwrap()
+                .get(&tx_hash)
+                .cloned()
+                .unwrap_or(APITxStatus {
+                    tx_hash,
+                    version: 0,
+                    status: TxStatus::Unknown,
+                })
+        }
     }
 
     impl Default for NodeApiMockClient {
@@ -508,6 +681,45 @@ pub mod test {
             })
         }
 
+        fn get_contract_proof(
+            &self,
+            contract_name: ContractName,
+        ) -> Pin<Box<dyn Future<Output = Result<ContractInclusionProof>> + Send + '_>> {
+            Box::pin(async move {
+                let block_height = *self.block_height.lock().unwrap();
+                ContractInclusionProof::new(
+                    &self.contracts.lock().unwrap(),
+                    &contract_name,
+                    block_height,
+                )
+                .ok_or_else(|| anyhow::anyhow!("Contract not found"))
+            })
+        }
+
+        fn get_contract_history(
+            &self,
+            contract_name: ContractName,
+        ) -> Pin<Box<dyn Future<Output = Result<APIContractHistory>> + Send + '_>> {
+            Box::pin(async move {
+                let contract = self
+                    .contracts
+                    .lock()
+                    .unwrap()
+                    .get(&contract_name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Contract not found"))?;
+                Ok(APIContractHistory {
+                    contract_name,
+                    pending_upgrade: None,
+                    history: vec![ContractCodeVersion {
+                        program_id: contract.program_id,
+                        verifier: contract.verifier,
+                        effective_height: BlockHeight(0),
+                    }],
+                })
+            })
+        }
+
         fn get_unsettled_tx(
             &self,
             blob_tx_hash: TxHash,
@@ -522,11 +734,58 @@ pub mod test {
             })
         }
 
+        fn get_tx_inclusion_proof(
+            &self,
+            _tx_hash: TxHash,
+        ) -> Pin<Box<dyn Future<Output = Result<TxInclusionProof>> + Send + '_>> {
+            Box::pin(async move { Err(anyhow::anyhow!("Transaction not found")) })
+        }
+
         fn get_settled_height(
             &self,
             _contract_name: ContractName,
         ) -> Pin<Box<dyn Future<Output = Result<BlockHeight>> + Send + '_>> {
             Box::pin(async move { Ok(*self.settled_height.lock().unwrap()) })
         }
+
+        fn simulate_settlement(
+            &self,
+            _request: APISettlementSimulationRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<APISettlementSimulation>> + Send + '_>> {
+            Box::pin(async move {
+                Err(anyhow::anyhow!(
+                    "Settlement simulation is not supported by the mock client"
+                ))
+            })
+        }
+
+        fn get_tx_status(
+            &self,
+            tx_hash: TxHash,
+            _after_version: Option<u64>,
+            _wait: Duration,
+        ) -> Pin<Box<dyn Future<Output = Result<APITxStatus>> + Send + '_>> {
+            Box::pin(async move { Ok(self.tx_status(tx_hash)) })
+        }
+
+        fn wait_for_tx_status(
+            &self,
+            tx_hash: TxHash,
+            kind: TxStatusKind,
+            _timeout: Duration,
+        ) -> Pin<Box<dyn Future<Output = Result<APITxStatus>> + Send + '_>> {
+            Box::pin(async move {
+                let status = self.tx_status(tx_hash);
+                if status.status.has_reached(kind) {
+                    Ok(status)
+                } else {
+                    Err(anyhow::anyhow!(
+                        "Mock tx {} did not reach {:?}",
+                        status.tx_hash,
+                        kind
+                    ))
+                }
+            })
+        }
     }
 }
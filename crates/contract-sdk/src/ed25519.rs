@@ -0,0 +1,74 @@
+use alloc::string::String;
+use hyle_model::{verifiers::Ed25519Blob, BlobIndex, Calldata, ContractName};
+
+/// This struct allows to check the existence of an ed25519 blob in the calldata.
+/// It will check:
+/// - the identity of the blob.
+/// - the contract name of the blob.
+/// - that the signed message is exactly the expected data.
+///
+/// As for secp256k1, the signature itself is natively verified by the node.
+/// Example usage:
+/// ```rust,no_run,compile_fail
+/// let calldata = Calldata::default();
+/// let expected_data = b"expected data";
+///
+/// let check = CheckEd25519::new(&calldata, expected_data);
+/// let blob = check.expect().unwrap();
+/// ```
+pub struct CheckEd25519<'a> {
+    calldata: &'a Calldata,
+    expected_data: &'a [u8],
+    blob_index: Option<BlobIndex>,
+}
+
+impl<'a> CheckEd25519<'a> {
+    pub fn new(calldata: &'a Calldata, expected_data: &'a [u8]) -> Self {
+        Self {
+            calldata,
+            expected_data,
+            blob_index: None,
+        }
+    }
+
+    pub fn with_blob_index(mut self, blob_index: BlobIndex) -> Self {
+        self.blob_index = Some(blob_index);
+        self
+    }
+
+    pub fn expect(self) -> Result<Ed25519Blob, &'static str> {
+        let ed_blob = match self.blob_index {
+            Some(idx) => {
+                let blob = self
+                    .calldata
+                    .blobs
+                    .get(&idx)
+                    .ok_or("Invalid blob index for ed25519")?;
+                if blob.contract_name != ContractName(String::from("ed25519")) {
+                    return Err("Invalid contract name for Ed25519Blob");
+                }
+                blob
+            }
+            None => self
+                .calldata
+                .blobs
+                .iter()
+                .map(|(_, b)| b)
+                .find(|b| b.contract_name == ContractName(String::from("ed25519")))
+                .ok_or("Missing Ed25519Blob")?,
+        };
+
+        let ed_data: Ed25519Blob =
+            borsh::from_slice(&ed_blob.data.0).map_err(|_| "Failed to decode Ed25519Blob")?;
+
+        if ed_data.identity != self.calldata.identity {
+            return Err("Ed25519Blob identity does not match");
+        }
+
+        if ed_data.data != self.expected_data {
+            return Err("Ed25519Blob data does not match");
+        }
+
+        Ok(ed_data)
+    }
+}
@@ -0,0 +1,75 @@
+use alloc::string::String;
+use hyle_model::{verifiers::ShaBlob, BlobIndex, Calldata, ContractName};
+
+/// This struct allows to get the keccak256 hash of some data from a blob of the native
+/// keccak256 contract, instead of hashing it inside the zkvm.
+/// It will check:
+/// - the identity of the blob.
+/// - the contract name of the blob.
+/// - that the hashed data is exactly the expected data.
+///
+/// The hash itself is natively verified by the node, so the returned
+/// [ShaBlob::sha] can be trusted once the transaction settles.
+/// Example usage:
+/// ```rust,no_run,compile_fail
+/// let calldata = Calldata::default();
+/// let data = b"data to hash";
+///
+/// let hash = CheckKeccak256::new(&calldata, data).expect().unwrap().sha;
+/// ```
+pub struct CheckKeccak256<'a> {
+    calldata: &'a Calldata,
+    expected_data: &'a [u8],
+    blob_index: Option<BlobIndex>,
+}
+
+impl<'a> CheckKeccak256<'a> {
+    pub fn new(calldata: &'a Calldata, expected_data: &'a [u8]) -> Self {
+        Self {
+            calldata,
+            expected_data,
+            blob_index: None,
+        }
+    }
+
+    pub fn with_blob_index(mut self, blob_index: BlobIndex) -> Self {
+        self.blob_index = Some(blob_index);
+        self
+    }
+
+    pub fn expect(self) -> Result<ShaBlob, &'static str> {
+        let keccak_blob = match self.blob_index {
+            Some(idx) => {
+                let blob = self
+                    .calldata
+                    .blobs
+                    .get(&idx)
+                    .ok_or("Invalid blob index for keccak256")?;
+                if blob.contract_name != ContractName(String::from("keccak256")) {
+                    return Err("Invalid contract name for keccak256 ShaBlob");
+                }
+                blob
+            }
+            None => self
+                .calldata
+                .blobs
+                .iter()
+                .map(|(_, b)| b)
+                .find(|b| b.contract_name == ContractName(String::from("keccak256")))
+                .ok_or("Missing keccak256 ShaBlob")?,
+        };
+
+        let sha_data: ShaBlob = borsh::from_slice(&keccak_blob.data.0)
+            .map_err(|_| "Failed to decode keccak256 ShaBlob")?;
+
+        if sha_data.identity != self.calldata.identity {
+            return Err("Keccak256 ShaBlob identity does not match");
+        }
+
+        if sha_data.data != self.expected_data {
+            return Err("Keccak256 ShaBlob data does not match");
+        }
+
+        Ok(sha_data)
+    }
+}
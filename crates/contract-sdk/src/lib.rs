@@ -30,10 +30,13 @@ use alloc::string::String;
 use alloc::vec::Vec;
 
 pub mod caller;
+pub mod ed25519;
 pub mod guest;
+pub mod keccak256;
 #[cfg(feature = "smt")]
 pub mod merkle_utils;
 pub mod secp256k1;
+pub mod secp256r1;
 pub mod utils;
 
 use caller::ExecutionContext;
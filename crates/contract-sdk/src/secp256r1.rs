@@ -0,0 +1,83 @@
+use alloc::string::String;
+use hyle_model::{verifiers::Secp256r1Blob, BlobIndex, Calldata, ContractName};
+use sha2::{Digest, Sha256};
+
+/// This struct allows to check the existence of a secp256r1 blob in the calldata.
+/// It will check:
+/// - the identity of the blob.
+/// - the contract name of the blob.
+/// - that the blob data is the sha256 of the expected data.
+///
+/// The blob can carry a plain ECDSA signature or a WebAuthn (passkey) assertion,
+/// in which case the expected data hash is the WebAuthn challenge.
+/// The signature and the WebAuthn client data are natively verified by the node.
+/// Contracts that care about the authenticator (origin, RP ID) can inspect
+/// [Secp256r1Blob::webauthn] in the returned blob.
+/// Example usage:
+/// ```rust,no_run,compile_fail
+/// let calldata = Calldata::default();
+/// let expected_data = b"expected data";
+///
+/// let check = CheckSecp256r1::new(&calldata, expected_data);
+/// let blob = check.expect().unwrap();
+/// ```
+pub struct CheckSecp256r1<'a> {
+    calldata: &'a Calldata,
+    expected_data: &'a [u8],
+    blob_index: Option<BlobIndex>,
+}
+
+impl<'a> CheckSecp256r1<'a> {
+    pub fn new(calldata: &'a Calldata, expected_data: &'a [u8]) -> Self {
+        Self {
+            calldata,
+            expected_data,
+            blob_index: None,
+        }
+    }
+
+    pub fn with_blob_index(mut self, blob_index: BlobIndex) -> Self {
+        self.blob_index = Some(blob_index);
+        self
+    }
+
+    pub fn expect(self) -> Result<Secp256r1Blob, &'static str> {
+        let secp_blob = match self.blob_index {
+            Some(idx) => {
+                let blob = self
+                    .calldata
+                    .blobs
+                    .get(&idx)
+                    .ok_or("Invalid blob index for secp256r1")?;
+                if blob.contract_name != ContractName(String::from("secp256r1")) {
+                    return Err("Invalid contract name for Secp256r1Blob");
+                }
+                blob
+            }
+            None => self
+                .calldata
+                .blobs
+                .iter()
+                .map(|(_, b)| b)
+                .find(|b| b.contract_name == ContractName(String::from("secp256r1")))
+                .ok_or("Missing Secp256r1Blob")?,
+        };
+
+        let secp_data: Secp256r1Blob =
+            borsh::from_slice(&secp_blob.data.0).map_err(|_| "Failed to decode Secp256r1Blob")?;
+
+        if secp_data.identity != self.calldata.identity {
+            return Err("Secp256r1Blob identity does not match");
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.expected_data);
+        let message_hash: [u8; 32] = hasher.finalize().into();
+
+        if secp_data.data != message_hash {
+            return Err("Secp256r1Blob data does not match");
+        }
+
+        Ok(secp_data)
+    }
+}
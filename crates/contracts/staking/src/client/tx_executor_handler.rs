@@ -3,16 +3,17 @@ use client_sdk::{
     helpers::risc0::Risc0Prover,
     transaction_builder::{ProvableBlobTx, StateUpdater, TxExecutorBuilder, TxExecutorHandler},
 };
+use hyllar::HyllarAction;
 use sdk::{
-    api::{APIFees, APIFeesBalance, APIStaking},
+    api::{APIEpochValidators, APIFees, APIFeesBalance, APIStaking},
     utils::as_hyle_output,
-    Blob, Calldata, ContractName, RegisterContractEffect, StakingAction, StateCommitment,
-    ValidatorPublicKey, ZkContract,
+    Blob, BlobIndex, Calldata, ContractName, RegisterContractEffect, StakingAction,
+    StateCommitment, ValidatorPublicKey, ZkContract,
 };
 
 use crate::{
     fees::{Fees, ValidatorFeeState},
-    state::Staking,
+    state::{EpochValidators, Staking},
 };
 
 pub mod metadata {
@@ -65,6 +66,14 @@ impl From<Staking> for APIStaking {
             bonded: val.bonded,
             delegations: val.delegations,
             total_bond: val.total_bond,
+            unbonding: val.unbonding,
+            unbond_requests: val.unbond_requests,
+            operators: val.operators,
+            epoch: val.epoch.map(|e| APIEpochValidators {
+                epoch: e.epoch,
+                stakes: e.stakes,
+                total_bond: e.total_bond,
+            }),
             fees: val.fees.into(),
         }
     }
@@ -98,6 +107,14 @@ impl From<APIStaking> for Staking {
             bonded: val.bonded,
             delegations: val.delegations,
             total_bond: val.total_bond,
+            unbonding: val.unbonding,
+            unbond_requests: val.unbond_requests,
+            operators: val.operators,
+            epoch: val.epoch.map(|e| EpochValidators {
+                epoch: e.epoch,
+                stakes: e.stakes,
+                total_bond: e.total_bond,
+            }),
             fees: val.fees.into(),
         }
     }
@@ -178,3 +195,47 @@ pub fn delegate(builder: &mut ProvableBlobTx, validator: ValidatorPublicKey) ->
     )?;
     Ok(())
 }
+
+pub fn unbond(
+    builder: &mut ProvableBlobTx,
+    contract_name: ContractName,
+    validator: ValidatorPublicKey,
+) -> anyhow::Result<()> {
+    builder.add_action(
+        contract_name,
+        StakingAction::Unbond { validator },
+        None,
+        None,
+        None,
+    )?;
+    Ok(())
+}
+
+/// Withdraws `amount` of stake, transferred back by the hyllar contract.
+/// It must be the whole stake of the identity, partial withdrawals are refused.
+pub fn withdraw(
+    builder: &mut ProvableBlobTx,
+    contract_name: ContractName,
+    hyllar_contract_name: ContractName,
+    amount: u128,
+) -> anyhow::Result<()> {
+    let index = BlobIndex(builder.blobs.len());
+    builder.add_action(
+        contract_name,
+        StakingAction::Withdraw { amount },
+        None,
+        None,
+        Some(vec![index + 1]),
+    )?;
+    builder.add_action(
+        hyllar_contract_name,
+        HyllarAction::Transfer {
+            recipient: builder.identity.0.clone(),
+            amount,
+        },
+        None,
+        Some(index),
+        None,
+    )?;
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use hyllar::HyllarAction;
 use sdk::{
-    utils::parse_calldata, BlobIndex, Calldata, IndexedBlobs, RunResult, StakingAction, ZkContract,
+    utils::parse_calldata, BlobIndex, BlockHeight, Calldata, IndexedBlobs, RunResult,
+    StakingAction, ZkContract,
 };
 use sha2::{Digest, Sha256};
 use state::Staking;
@@ -30,6 +31,15 @@ impl ZkContract for Staking {
                 check_transfer_blob(&calldata.blobs, calldata.index + 1, amount)?;
                 self.deposit_for_fees(holder, amount)
             }
+            StakingAction::Unbond { validator } => {
+                let height = tx_block_height(calldata)?;
+                self.request_unbond(&execution_ctx.caller, validator, height)
+            }
+            StakingAction::Withdraw { amount } => {
+                let height = tx_block_height(calldata)?;
+                check_withdraw_blob(calldata, &execution_ctx.caller, amount)?;
+                self.withdraw(&execution_ctx.caller, amount, height)
+            }
         };
 
         match output {
@@ -59,6 +69,20 @@ impl ZkContract for Staking {
                 hasher.update(i.0.to_le_bytes());
             }
         }
+        for u in self.unbonding.iter() {
+            hasher.update(&u.0 .0);
+            hasher.update(u.1 .0.to_le_bytes());
+        }
+        for r in self.unbond_requests.iter() {
+            hasher.update(&r.0 .0);
+            for i in r.1 {
+                hasher.update(&i.0);
+            }
+        }
+        for o in self.operators.iter() {
+            hasher.update(&o.0 .0);
+            hasher.update(&o.1 .0);
+        }
         sdk::StateCommitment(hasher.finalize().to_vec())
     }
 }
@@ -98,3 +122,53 @@ fn check_transfer_blob(blobs: &IndexedBlobs, index: BlobIndex, amount: u128) ->
         )),
     }
 }
+
+fn tx_block_height(calldata: &Calldata) -> Result<BlockHeight, String> {
+    calldata
+        .tx_ctx
+        .as_ref()
+        .map(|tx_ctx| tx_ctx.block_height)
+        .ok_or("Missing tx context".to_string())
+}
+
+/// The stake is released by a hyllar transfer from the staking contract, called by the Withdraw blob
+fn check_withdraw_blob(
+    calldata: &Calldata,
+    staker: &sdk::Identity,
+    amount: u128,
+) -> Result<(), String> {
+    let index = calldata.index + 1;
+    let transfer_action =
+        sdk::utils::parse_structured_blob::<HyllarAction>(&calldata.blobs, &index)
+            .ok_or("No transfer blob found".to_string())?;
+    if transfer_action.data.caller != Some(calldata.index) {
+        return Err("The transfer blob should be called by the Withdraw blob".to_string());
+    }
+    let transfer_contract = transfer_action.contract_name;
+    if transfer_contract.0 != "hyllar" {
+        return Err(format!(
+            "Only hyllar token are released by staking. Got {transfer_contract}."
+        ));
+    }
+    match transfer_action.data.parameters {
+        HyllarAction::Transfer {
+            recipient,
+            amount: transfer_amount,
+        } => {
+            if recipient != staker.0 {
+                return Err(format!(
+                    "Transfer recipient should be {staker} but was {recipient}"
+                ));
+            }
+            if amount != transfer_amount {
+                return Err(format!(
+                    "Transfer amount {transfer_amount} mismatch Withdraw amount {amount}"
+                ));
+            }
+            Ok(())
+        }
+        els => Err(format!(
+            "Wrong HyllarAction, should be a transfer {amount:?} to {staker} but was {els:?}"
+        )),
+    }
+}
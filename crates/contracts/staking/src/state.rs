@@ -1,4 +1,7 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Read,
+};
 
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -7,7 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::fees::Fees;
 
-#[derive(Debug, Serialize, Deserialize, Clone, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, BorshSerialize, PartialEq, Eq)]
 pub struct Staking {
     pub(crate) stakes: BTreeMap<Identity, u128>,
     pub(crate) delegations: BTreeMap<ValidatorPublicKey, Vec<Identity>>,
@@ -21,11 +24,80 @@ pub struct Staking {
 
     /// Struct to handle fees
     pub(crate) fees: Fees,
+
+    // Fields below were appended after `fees`, see the BorshDeserialize implementation
+    /// Validators that asked to leave consensus, with the height from which
+    /// the stake delegated to them can be withdrawn
+    pub(crate) unbonding: BTreeMap<ValidatorPublicKey, BlockHeight>,
+    /// Delegators that asked a validator to leave consensus, until they hold a majority of its stake
+    pub(crate) unbond_requests: BTreeMap<ValidatorPublicKey, BTreeSet<Identity>>,
+    /// Validator set frozen at the start of the current epoch, once consensus started one
+    pub(crate) epoch: Option<EpochValidators>,
+    /// Identity whose delegation first staked for each validator, the one operating it.
+    /// It can unbond the validator on its own.
+    pub(crate) operators: BTreeMap<ValidatorPublicKey, Identity>,
+}
+
+/// States serialized before validators could unbond end right after `fees`, and the ones
+/// serialized before operators were recorded end right after `epoch`.
+/// The fields appended since are read as empty for them.
+impl BorshDeserialize for Staking {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let stakes = BorshDeserialize::deserialize_reader(reader)?;
+        let delegations = BorshDeserialize::deserialize_reader(reader)?;
+        let rewarded = BorshDeserialize::deserialize_reader(reader)?;
+        let bonded = BorshDeserialize::deserialize_reader(reader)?;
+        let total_bond = BorshDeserialize::deserialize_reader(reader)?;
+        let fees = BorshDeserialize::deserialize_reader(reader)?;
+
+        let mut next = [0u8; 1];
+        let (unbonding, unbond_requests, epoch, operators) = match reader.read(&mut next)? {
+            0 => Default::default(),
+            _ => {
+                let mut reader = next.as_slice().chain(reader);
+                let unbonding = BorshDeserialize::deserialize_reader(&mut reader)?;
+                let unbond_requests = BorshDeserialize::deserialize_reader(&mut reader)?;
+                let epoch = BorshDeserialize::deserialize_reader(&mut reader)?;
+                let mut next = [0u8; 1];
+                let operators = match reader.read(&mut next)? {
+                    0 => Default::default(),
+                    _ => BorshDeserialize::deserialize_reader(
+                        &mut next.as_slice().chain(&mut reader),
+                    )?,
+                };
+                (unbonding, unbond_requests, epoch, operators)
+            }
+        };
+        Ok(Staking {
+            stakes,
+            delegations,
+            rewarded,
+            bonded,
+            total_bond,
+            fees,
+            unbonding,
+            unbond_requests,
+            epoch,
+            operators,
+        })
+    }
+}
+
+/// Stake of the bonded validators, as of the start of an epoch.
+/// Votes are weighted with it until the next epoch, even if stakes change meanwhile.
+#[derive(Debug, Serialize, Deserialize, Clone, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct EpochValidators {
+    pub epoch: u64,
+    pub stakes: BTreeMap<ValidatorPublicKey, u128>,
+    pub total_bond: u128,
 }
 
 /// Minimal stake necessary to be part of consensus
 pub const MIN_STAKE: u128 = 32;
 
+/// Number of blocks between an unbonding request and the withdrawal of the stake
+pub const UNBONDING_DELAY: u64 = 1_000;
+
 impl Staking {
     pub fn new() -> Self {
         Staking {
@@ -35,6 +107,10 @@ impl Staking {
             bonded: Vec::new(),
             total_bond: 0,
             fees: Fees::default(),
+            unbonding: BTreeMap::new(),
+            unbond_requests: BTreeMap::new(),
+            epoch: None,
+            operators: BTreeMap::new(),
         }
     }
 
@@ -49,6 +125,49 @@ impl Staking {
     pub fn total_bond(&self) -> u128 {
         self.total_bond
     }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch.as_ref().map(|e| e.epoch).unwrap_or(0)
+    }
+
+    /// Freeze the validators and their stake for the epoch
+    /// This function is meant to be called by the consensus, with the stakes it committed to
+    pub fn start_epoch(&mut self, epoch: u64, stakes: BTreeMap<ValidatorPublicKey, u128>) {
+        let total_bond = stakes.values().sum();
+        self.epoch = Some(EpochValidators {
+            epoch,
+            stakes,
+            total_bond,
+        });
+    }
+
+    pub fn frozen_epoch(&self) -> Option<&EpochValidators> {
+        self.epoch.as_ref()
+    }
+
+    /// Current stake of the bonded validators
+    pub fn bonded_stakes(&self) -> BTreeMap<ValidatorPublicKey, u128> {
+        self.bonded
+            .iter()
+            .map(|v| (v.clone(), self.get_stake(v).unwrap_or(0)))
+            .collect()
+    }
+
+    /// Stakes votes are weighted with: the ones of the epoch, or the bonded ones if no epoch started
+    pub fn voting_stakes(&self) -> BTreeMap<ValidatorPublicKey, u128> {
+        match &self.epoch {
+            Some(epoch) => epoch.stakes.clone(),
+            None => self.bonded_stakes(),
+        }
+    }
+
+    /// Validators of the current epoch, or the bonded ones if no epoch started
+    pub fn epoch_validators(&self) -> Vec<ValidatorPublicKey> {
+        match &self.epoch {
+            Some(epoch) => epoch.stakes.keys().cloned().collect(),
+            None => self.bonded.clone(),
+        }
+    }
     pub fn is_bonded(&self, pubkey: &ValidatorPublicKey) -> bool {
         self.bonded.iter().any(|v| v == pubkey)
     }
@@ -58,6 +177,9 @@ impl Staking {
         if self.is_bonded(&validator) {
             return Err("Validator already bonded".to_string());
         }
+        if self.is_unbonding(&validator) {
+            return Err("Validator is unbonding".to_string());
+        }
 
         info!("🔐 Bonded validator {}", validator);
         if let Some(stake) = self.get_stake(&validator) {
@@ -73,11 +195,185 @@ impl Staking {
         }
     }
 
-    /// Compute f value
+    /// Remove a validator that signed conflicting messages from consensus,
+    /// and burn the stake delegated to it. Returns the burnt stake.
+    pub fn slash(&mut self, validator: &ValidatorPublicKey) -> Result<u128, String> {
+        if !self.is_bonded(validator) {
+            return Err("Validator is not bonded".to_string());
+        }
+
+        info!("⚔️ Slashed validator {}", validator);
+        let stake = self.get_stake(validator).unwrap_or(0);
+        self.bonded.retain(|v| v != validator);
+        self.total_bond = self.total_bond.saturating_sub(stake);
+        // Unlike bonds and unbonds, slashing can't wait for the end of the epoch
+        if let Some(epoch) = &mut self.epoch {
+            if let Some(frozen) = epoch.stakes.remove(validator) {
+                epoch.total_bond = epoch.total_bond.saturating_sub(frozen);
+            }
+        }
+        // Stakes are kept at zero: they stay frozen and cannot be bonded again
+        for delegator in self.delegations.get(validator).into_iter().flatten() {
+            if let Some(delegated) = self.stakes.get_mut(delegator) {
+                *delegated = 0;
+            }
+        }
+        Ok(stake)
+    }
+
+    pub fn is_unbonding(&self, validator: &ValidatorPublicKey) -> bool {
+        self.unbonding.contains_key(validator)
+    }
+
+    /// Bonded validators that asked to leave consensus
+    pub fn unbonding_validators(&self) -> Vec<ValidatorPublicKey> {
+        self.bonded
+            .iter()
+            .filter(|v| self.is_unbonding(v))
+            .cloned()
+            .collect()
+    }
+
+    /// Ask a validator to leave consensus, on behalf of its operator or of its delegators.
+    /// Requests of delegators add up until they hold a majority of the stake delegated to it.
+    /// The stake can be withdrawn [UNBONDING_DELAY] blocks after the request that starts the unbonding.
+    pub fn request_unbond(
+        &mut self,
+        staker: &Identity,
+        validator: ValidatorPublicKey,
+        height: BlockHeight,
+    ) -> Result<String, String> {
+        let own_request = self.operators.get(&validator) == Some(staker);
+        if !own_request
+            && !self
+                .delegations
+                .get(&validator)
+                .is_some_and(|delegators| delegators.contains(staker))
+        {
+            return Err(format!("{staker} does not delegate to {validator}"));
+        }
+        if self.is_unbonding(&validator) {
+            return Err("Validator is already unbonding".to_string());
+        }
+        if self.is_bonded(&validator) {
+            // Votes keep their epoch weights, the remaining validators must still reach a quorum
+            let leaving = self.unbonding_validators().len() + 1;
+            let leaving_stake: u128 = self
+                .unbonding_validators()
+                .iter()
+                .chain(std::iter::once(&validator))
+                .flat_map(|v| self.get_stake(v))
+                .sum();
+            let remaining_stake = self.total_bond.saturating_sub(leaving_stake);
+            if leaving >= self.bonded.len()
+                || remaining_stake.saturating_mul(3) <= self.total_bond.saturating_mul(2)
+            {
+                return Err(format!(
+                    "Unbonding {validator} would leave consensus without a quorum"
+                ));
+            }
+        }
+
+        if !own_request {
+            let delegated = self.get_stake(&validator).unwrap_or(0);
+            let requests = self.unbond_requests.entry(validator.clone()).or_default();
+            requests.insert(staker.clone());
+            let requested: u128 = requests
+                .iter()
+                .map(|delegator| self.stakes.get(delegator).copied().unwrap_or(0))
+                .sum();
+            if requested.saturating_mul(2) <= delegated {
+                info!(
+                    "👋 Unbonding of validator {} requested by {} of its {} stake",
+                    validator, requested, delegated
+                );
+                return Ok("Unbonding requested".to_string());
+            }
+        }
+
+        info!("👋 Unbonding validator {}", validator);
+        self.unbond_requests.remove(&validator);
+        self.unbonding.insert(validator, height + UNBONDING_DELAY);
+        Ok("Unbonding".to_string())
+    }
+
+    /// Remove an unbonding validator from consensus
+    /// This function is meant to be called by the consensus
+    pub fn unbond(&mut self, validator: &ValidatorPublicKey) -> Result<(), String> {
+        if !self.is_bonded(validator) {
+            return Err("Validator is not bonded".to_string());
+        }
+        if !self.is_unbonding(validator) {
+            return Err("Validator did not ask to unbond".to_string());
+        }
+
+        info!("🔓 Unbonded validator {}", validator);
+        let stake = self.get_stake(validator).unwrap_or(0);
+        self.bonded.retain(|v| v != validator);
+        self.total_bond = self.total_bond.saturating_sub(stake);
+        Ok(())
+    }
+
+    /// Release the whole stake of a staker, once its validator left consensus and the unbonding delay is over.
+    /// The staker can then stake again.
+    pub fn withdraw(
+        &mut self,
+        staker: &Identity,
+        amount: u128,
+        height: BlockHeight,
+    ) -> Result<String, String> {
+        let Some(stake) = self.stakes.get(staker).copied() else {
+            return Err(format!("{staker} has no stake"));
+        };
+        if amount != stake {
+            return Err(format!(
+                "Withdraw amount {amount} mismatch the stake {stake} of {staker}"
+            ));
+        }
+
+        let validator = self
+            .delegations
+            .iter()
+            .find(|(_, delegators)| delegators.contains(staker))
+            .map(|(validator, _)| validator.clone());
+        if let Some(validator) = validator {
+            if self.is_bonded(&validator) {
+                return Err(format!("Validator {validator} is still bonded"));
+            }
+            match self.unbonding.get(&validator) {
+                None => return Err(format!("Validator {validator} did not ask to unbond")),
+                Some(release) if height < *release => {
+                    return Err(format!("Stake is locked until block {}", release.0));
+                }
+                Some(_) => {}
+            }
+            if let Some(delegators) = self.delegations.get_mut(&validator) {
+                delegators.retain(|d| d != staker);
+                if delegators.is_empty() {
+                    self.delegations.remove(&validator);
+                    self.unbonding.remove(&validator);
+                }
+            }
+            if self.operators.get(&validator) == Some(staker) {
+                self.operators.remove(&validator);
+            }
+        }
+
+        info!("💸 Withdrawing {} of stake for {}", amount, staker);
+        self.stakes.remove(staker);
+        Ok("Withdrawn".to_string())
+    }
+
+    /// Compute f value, on the validator set of the epoch
     pub fn compute_f(&self) -> u128 {
-        self.total_bond().div_euclid(3)
+        match &self.epoch {
+            Some(epoch) => epoch.total_bond,
+            None => self.total_bond(),
+        }
+        .div_euclid(3)
     }
 
+    /// Voting power of the validators, with their stake as of the start of the epoch
     pub fn compute_voting_power(&self, validators: &[ValidatorPublicKey]) -> u128 {
         // Deduplicate validators before computing voting power
         let mut unique_validators = validators.to_vec();
@@ -85,10 +381,18 @@ impl Staking {
         unique_validators.dedup();
         unique_validators
             .iter()
-            .flat_map(|v| self.get_stake(v))
+            .flat_map(|v| self.epoch_stake(v))
             .sum::<u128>()
     }
 
+    /// Stake of a validator as of the start of the epoch, or its current stake if no epoch started
+    pub fn epoch_stake(&self, validator: &ValidatorPublicKey) -> Option<u128> {
+        match &self.epoch {
+            Some(epoch) => epoch.stakes.get(validator).copied(),
+            None => self.get_stake(validator),
+        }
+    }
+
     pub fn get_stake(&self, validator: &ValidatorPublicKey) -> Option<u128> {
         self.delegations.get(validator).map(|delegations| {
             delegations
@@ -114,7 +418,8 @@ impl Staking {
         Ok("Staked".to_string())
     }
 
-    /// Delegate to a validator, or fail if already delegated to another validator
+    /// Delegate to a validator, or fail if already delegated to another validator.
+    /// The first identity to delegate to a validator becomes its operator.
     pub fn delegate_to(
         &mut self,
         staker: Identity,
@@ -124,7 +429,13 @@ impl Staking {
         if self.delegations.values().flatten().any(|v| v == &staker) {
             return Err("Already delegated".to_string());
         }
+        if self.is_unbonding(&validator) {
+            return Err("Validator is unbonding".to_string());
+        }
 
+        if !self.delegations.contains_key(&validator) {
+            self.operators.insert(validator.clone(), staker.clone());
+        }
         self.delegations
             .entry(validator)
             .and_modify(|e| e.push(staker.clone()))
@@ -160,10 +471,11 @@ impl Staking {
         Ok(())
     }
 
-    /// Distribute the fees to the bonded validators
+    /// Distribute the fees to the validators of the epoch
     /// This function is meant to be called by the consensus
     pub fn distribute(&mut self) -> Result<(), String> {
-        self.fees.distribute(&self.bonded)
+        let validators = self.epoch_validators();
+        self.fees.distribute(&validators)
     }
 
     /// Update the state of staking with staking actions in a block
@@ -182,11 +494,29 @@ impl Staking {
                 (_identity, StakingAction::DepositForFees { holder, amount }) => {
                     self.deposit_for_fees(holder, amount)?;
                 }
+                (identity, StakingAction::Unbond { validator }) => {
+                    self.request_unbond(&identity, validator, block.block_height)?;
+                }
+                (identity, StakingAction::Withdraw { amount }) => {
+                    self.withdraw(&identity, amount, block.block_height)?;
+                }
             }
         }
         for validator in block.new_bounded_validators.iter() {
             self.bond(validator.clone())?;
         }
+        for validator in block.slashed_validators.iter() {
+            // The consensus may already have slashed it when committing the proposal
+            if self.is_bonded(validator) {
+                self.slash(validator)?;
+            }
+        }
+        for validator in block.unbonded_validators.iter() {
+            // Same as slashing, the consensus already unbonded it when committing the proposal
+            if self.is_bonded(validator) {
+                self.unbond(validator)?;
+            }
+        }
         Ok(())
     }
 }
@@ -196,3 +526,251 @@ impl Default for Staking {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validator(id: u8) -> ValidatorPublicKey {
+        ValidatorPublicKey(vec![id])
+    }
+
+    /// Bonded validators 1 to 4, each with a stake of 100 from its operator,
+    /// and validator 5 with a stake of 110 from its operator and two delegators
+    fn staking() -> Staking {
+        let mut staking = Staking::new();
+        for id in 1..=4 {
+            let identity = Identity(format!("0{id}@hydentity"));
+            staking.stake(identity.clone(), 100).unwrap();
+            staking.delegate_to(identity, validator(id)).unwrap();
+            staking.bond(validator(id)).unwrap();
+        }
+        for (delegator, amount) in [("05", 10), ("alice", 40), ("bob", 60)] {
+            let identity = Identity(format!("{delegator}@hydentity"));
+            staking.stake(identity.clone(), amount).unwrap();
+            staking.delegate_to(identity, validator(5)).unwrap();
+        }
+        staking
+    }
+
+    #[test]
+    fn test_request_unbond_needs_a_delegation() {
+        let mut staking = staking();
+        let stranger = Identity("carol@hydentity".into());
+        assert!(staking
+            .request_unbond(&stranger, validator(1), BlockHeight(1))
+            .is_err());
+        assert!(staking
+            .request_unbond(
+                &Identity("02@hydentity".into()),
+                validator(1),
+                BlockHeight(1)
+            )
+            .is_err());
+        // Identities named after the validator key don't operate it
+        assert!(staking
+            .request_unbond(&Identity("01@x".into()), validator(1), BlockHeight(1))
+            .is_err());
+        assert!(!staking.is_unbonding(&validator(1)));
+
+        staking
+            .request_unbond(
+                &Identity("01@hydentity".into()),
+                validator(1),
+                BlockHeight(1),
+            )
+            .unwrap();
+        assert_eq!(
+            staking.unbonding.get(&validator(1)),
+            Some(&BlockHeight(1 + UNBONDING_DELAY))
+        );
+        assert!(staking
+            .request_unbond(
+                &Identity("01@hydentity".into()),
+                validator(1),
+                BlockHeight(2)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_request_unbond_needs_a_stake_majority() {
+        let mut staking = staking();
+        staking.bond(validator(5)).unwrap();
+        let alice = Identity("alice@hydentity".into());
+        let bob = Identity("bob@hydentity".into());
+
+        // 40 out of 110 is not enough
+        staking
+            .request_unbond(&alice, validator(5), BlockHeight(1))
+            .unwrap();
+        assert!(!staking.is_unbonding(&validator(5)));
+        // Requesting again doesn't count twice
+        staking
+            .request_unbond(&alice, validator(5), BlockHeight(2))
+            .unwrap();
+        assert!(!staking.is_unbonding(&validator(5)));
+
+        staking
+            .request_unbond(&bob, validator(5), BlockHeight(3))
+            .unwrap();
+        assert_eq!(
+            staking.unbonding.get(&validator(5)),
+            Some(&BlockHeight(3 + UNBONDING_DELAY))
+        );
+        assert!(staking.unbond_requests.is_empty());
+    }
+
+    #[test]
+    fn test_request_unbond_keeps_a_quorum() {
+        let mut staking = staking();
+        staking
+            .request_unbond(
+                &Identity("01@hydentity".into()),
+                validator(1),
+                BlockHeight(1),
+            )
+            .unwrap();
+        // 2 out of 4 equal validators leaving would leave no quorum
+        assert!(staking
+            .request_unbond(
+                &Identity("02@hydentity".into()),
+                validator(2),
+                BlockHeight(1)
+            )
+            .is_err());
+        assert!(!staking.is_unbonding(&validator(2)));
+
+        // A single validator can't leave
+        let mut staking = Staking::new();
+        let identity = Identity("01@hydentity".into());
+        staking.stake(identity.clone(), 100).unwrap();
+        staking.delegate_to(identity.clone(), validator(1)).unwrap();
+        staking.bond(validator(1)).unwrap();
+        assert!(staking
+            .request_unbond(&identity, validator(1), BlockHeight(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_unbond_and_withdraw() {
+        let mut staking = staking();
+        let identity = Identity("01@hydentity".into());
+        staking
+            .request_unbond(&identity, validator(1), BlockHeight(1))
+            .unwrap();
+        // Stake is locked while the validator is bonded
+        assert!(staking
+            .withdraw(&identity, 100, BlockHeight(1 + UNBONDING_DELAY))
+            .is_err());
+
+        staking.unbond(&validator(1)).unwrap();
+        assert!(!staking.is_bonded(&validator(1)));
+        assert_eq!(staking.total_bond(), 300);
+        assert!(staking
+            .withdraw(&identity, 100, BlockHeight(UNBONDING_DELAY))
+            .is_err());
+        assert!(staking
+            .withdraw(&identity, 50, BlockHeight(1 + UNBONDING_DELAY))
+            .is_err());
+        staking
+            .withdraw(&identity, 100, BlockHeight(1 + UNBONDING_DELAY))
+            .unwrap();
+        assert_eq!(staking.get_stake(&validator(1)), None);
+        assert!(!staking.is_unbonding(&validator(1)));
+    }
+
+    #[test]
+    fn test_borsh_before_unbonding() {
+        #[derive(BorshSerialize)]
+        struct StakingBeforeUnbonding {
+            stakes: BTreeMap<Identity, u128>,
+            delegations: BTreeMap<ValidatorPublicKey, Vec<Identity>>,
+            rewarded: BTreeMap<ValidatorPublicKey, Vec<BlockHeight>>,
+            bonded: Vec<ValidatorPublicKey>,
+            total_bond: u128,
+            fees: Fees,
+        }
+
+        let mut staking = staking();
+        staking
+            .request_unbond(
+                &Identity("alice@hydentity".into()),
+                validator(5),
+                BlockHeight(1),
+            )
+            .unwrap();
+        staking.start_epoch(1, staking.bonded_stakes());
+        assert_eq!(
+            borsh::from_slice::<Staking>(&borsh::to_vec(&staking).unwrap()).unwrap(),
+            staking
+        );
+
+        let before = StakingBeforeUnbonding {
+            stakes: staking.stakes.clone(),
+            delegations: staking.delegations.clone(),
+            rewarded: staking.rewarded.clone(),
+            bonded: staking.bonded.clone(),
+            total_bond: staking.total_bond,
+            fees: staking.fees.clone(),
+        };
+        let decoded: Staking = borsh::from_slice(&borsh::to_vec(&before).unwrap()).unwrap();
+        assert_eq!(decoded.bonded, staking.bonded);
+        assert!(decoded.unbond_requests.is_empty());
+        assert!(decoded.epoch.is_none());
+        assert!(decoded.operators.is_empty());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_api_staking_roundtrip() {
+        use sdk::{api::APIStaking, ZkContract};
+
+        let mut staking = staking();
+        staking
+            .request_unbond(
+                &Identity("alice@hydentity".into()),
+                validator(5),
+                BlockHeight(1),
+            )
+            .unwrap();
+        let api: APIStaking = staking.clone().into();
+        let rebuilt: Staking = api.into();
+        assert_eq!(rebuilt.unbond_requests, staking.unbond_requests);
+        assert_eq!(rebuilt.operators, staking.operators);
+        assert_eq!(rebuilt.commit(), staking.commit());
+    }
+
+    #[test]
+    fn test_borsh_before_operators() {
+        #[derive(BorshSerialize)]
+        struct StakingBeforeOperators {
+            stakes: BTreeMap<Identity, u128>,
+            delegations: BTreeMap<ValidatorPublicKey, Vec<Identity>>,
+            rewarded: BTreeMap<ValidatorPublicKey, Vec<BlockHeight>>,
+            bonded: Vec<ValidatorPublicKey>,
+            total_bond: u128,
+            fees: Fees,
+            unbonding: BTreeMap<ValidatorPublicKey, BlockHeight>,
+            unbond_requests: BTreeMap<ValidatorPublicKey, BTreeSet<Identity>>,
+            epoch: Option<EpochValidators>,
+        }
+
+        let mut staking = staking();
+        staking.start_epoch(1, staking.bonded_stakes());
+        let before = StakingBeforeOperators {
+            stakes: staking.stakes.clone(),
+            delegations: staking.delegations.clone(),
+            rewarded: staking.rewarded.clone(),
+            bonded: staking.bonded.clone(),
+            total_bond: staking.total_bond,
+            fees: staking.fees.clone(),
+            unbonding: staking.unbonding.clone(),
+            unbond_requests: staking.unbond_requests.clone(),
+            epoch: staking.epoch.clone(),
+        };
+        let decoded: Staking = borsh::from_slice(&borsh::to_vec(&before).unwrap()).unwrap();
+        assert_eq!(decoded.epoch, staking.epoch);
+        assert!(decoded.operators.is_empty());
+    }
+}
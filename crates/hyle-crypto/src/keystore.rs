@@ -0,0 +1,175 @@
+//! Validator secret keys encrypted with a passphrase.
+//!
+//! The encryption key is derived from the passphrase with argon2id, and the BLS secret key
+//! is encrypted with ChaCha20-Poly1305. Keystores are stored as JSON files.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use blst::min_pk::SecretKey;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const KEYSTORE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    /// Hex-encoded public key, telling which validator the keystore is for without decrypting it
+    pub pubkey: String,
+    pub kdf: KdfParams,
+    /// Hex-encoded
+    pub nonce: String,
+    /// Hex-encoded encrypted secret key
+    pub ciphertext: String,
+}
+
+/// Argon2id parameters the encryption key was derived with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Hex-encoded
+    pub salt: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    fn derive_key(&self, passphrase: &str) -> Result<Key> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| anyhow!("Invalid keystore KDF parameters: {e}"))?;
+        let mut key = Key::default();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &hex::decode(&self.salt)?, &mut key)
+            .map_err(|e| anyhow!("Could not derive keystore key: {e}"))?;
+        Ok(key)
+    }
+}
+
+impl Keystore {
+    /// Encrypts a new random secret key
+    pub fn generate(passphrase: &str) -> Result<Self> {
+        let mut ikm = [0u8; 32];
+        rand::rng().fill(&mut ikm);
+        Self::encrypt(&ikm, passphrase)
+    }
+
+    /// Encrypts the secret key generated from the given key material,
+    /// e.g. the content of `HYLE_VALIDATOR_SECRET`.
+    pub fn encrypt(ikm: &[u8], passphrase: &str) -> Result<Self> {
+        let sk =
+            SecretKey::key_gen(ikm, &[]).map_err(|e| anyhow!("Could not generate key: {:?}", e))?;
+
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        rand::rng().fill(&mut salt);
+        rand::rng().fill(&mut nonce);
+        let params = Params::default();
+        let kdf = KdfParams {
+            salt: hex::encode(salt),
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+        };
+
+        let ciphertext = ChaCha20Poly1305::new(&kdf.derive_key(passphrase)?)
+            .encrypt(Nonce::from_slice(&nonce), sk.to_bytes().as_slice())
+            .map_err(|_| anyhow!("Could not encrypt secret key"))?;
+
+        Ok(Keystore {
+            version: KEYSTORE_VERSION,
+            pubkey: hex::encode(sk.sk_to_pk().compress()),
+            kdf,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    pub(crate) fn decrypt(&self, passphrase: &str) -> Result<SecretKey> {
+        if self.version != KEYSTORE_VERSION {
+            bail!("Unsupported keystore version {}", self.version);
+        }
+        let nonce = hex::decode(&self.nonce)?;
+        if nonce.len() != 12 {
+            bail!("Invalid keystore nonce");
+        }
+        let secret = ChaCha20Poly1305::new(&self.kdf.derive_key(passphrase)?)
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                hex::decode(&self.ciphertext)?.as_slice(),
+            )
+            .map_err(|_| anyhow!("Could not decrypt keystore, wrong passphrase?"))?;
+        let sk = SecretKey::from_bytes(&secret)
+            .map_err(|e| anyhow!("Invalid secret key in keystore: {:?}", e))?;
+        if hex::encode(sk.sk_to_pk().compress()) != self.pubkey {
+            bail!("Keystore secret key does not match its public key");
+        }
+        Ok(sk)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Reading keystore {}", path.display()))?;
+        serde_json::from_str(&content).context("Parsing keystore")
+    }
+
+    /// Writes the keystore, only readable by its owner
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options
+            .open(path)
+            .with_context(|| format!("Creating keystore {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// Reads the keystore passphrase from `HYLE_KEYSTORE_PASSPHRASE`,
+/// or from the file `HYLE_KEYSTORE_PASSPHRASE_FILE` points to.
+pub fn passphrase_from_env() -> Result<String> {
+    if let Ok(passphrase) = std::env::var("HYLE_KEYSTORE_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let path = std::env::var("HYLE_KEYSTORE_PASSPHRASE_FILE").map_err(|_| {
+        anyhow!("Neither HYLE_KEYSTORE_PASSPHRASE nor HYLE_KEYSTORE_PASSPHRASE_FILE are set")
+    })?;
+    let passphrase = fs::read_to_string(&path)
+        .with_context(|| format!("Reading keystore passphrase from {path}"))?;
+    Ok(passphrase.trim_end_matches(['\n', '\r']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+
+        let keystore = Keystore::encrypt(&[7u8; 32], "correct horse").unwrap();
+        keystore.save(&path).unwrap();
+        // Existing keystores are never overwritten
+        assert!(keystore.save(&path).is_err());
+
+        let loaded = Keystore::load(&path).unwrap();
+        assert_eq!(loaded, keystore);
+        let sk = loaded.decrypt("correct horse").unwrap();
+        assert_eq!(
+            sk.to_bytes(),
+            SecretKey::key_gen(&[7u8; 32], &[]).unwrap().to_bytes()
+        );
+        assert!(loaded.decrypt("battery staple").is_err());
+    }
+}
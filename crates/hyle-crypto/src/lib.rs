@@ -15,9 +15,14 @@
 //!
 //! ### Non-Test Environment
 //!
-//! This module load the private key seed from the environment variable `HYLE_VALIDATOR_SECRET`.
+//! If `HYLE_REMOTE_SIGNER` is set to the path of a unix socket, signing requests are sent to the
+//! signer process listening on it, which holds the secret key and enforces slashing protection.
+//!
+//! Otherwise this module load the private key seed from the environment variable `HYLE_VALIDATOR_SECRET`.
 //! The content of the variable must be a hexadecimal string.
-//! If the variable is not set but HYLE_USE_KEYRING is set to 'true', it tries to load the key from the keyring.
+//! If the variable is not set but `HYLE_KEYSTORE` is, the key is decrypted from that keystore file,
+//! with the passphrase from `HYLE_KEYSTORE_PASSPHRASE` or the file `HYLE_KEYSTORE_PASSPHRASE_FILE` points to.
+//! If none is set but HYLE_USE_KEYRING is set to 'true', it tries to load the key from the keyring.
 //! Otherwise it generates a private key from the validator name, which is highly unsecure.
 //!
 //! Note: you can use tools like seahorse (<https://wiki.gnome.org/Apps/Seahorse>) to manage your keyring
@@ -39,11 +44,28 @@ pub use hyle_model::{
     AggregateSignature, Signed, SignedByValidator, ValidatorPublicKey, ValidatorSignature,
 };
 
+pub mod keystore;
+#[cfg(unix)]
+pub mod remote_signer;
+pub mod signer;
+
+use keystore::Keystore;
+#[cfg(unix)]
+use remote_signer::RemoteSigner;
+use signer::SignerMessage;
+
 #[derive(Clone)]
 pub struct BlstCrypto {
-    sk: SecretKey,
+    signer: Signer,
     validator_pubkey: ValidatorPublicKey,
 }
+
+#[derive(Clone)]
+enum Signer {
+    Local(SecretKey),
+    #[cfg(unix)]
+    Remote(Arc<RemoteSigner>),
+}
 pub type SharedBlstCrypto = Arc<BlstCrypto>;
 
 #[derive(Default)]
@@ -59,7 +81,15 @@ pub const SIG_SIZE: usize = 48;
 impl BlstCrypto {
     #[cfg(not(test))]
     pub fn new(validator_name: &str) -> Result<Self> {
+        if let Ok(socket) = std::env::var("HYLE_REMOTE_SIGNER") {
+            return Self::remote(std::path::Path::new(&socket));
+        }
         let sk = Self::load_from_env().or_else(|err| {
+            if let Ok(path) = std::env::var("HYLE_KEYSTORE") {
+                println!("Loading secret key from keystore {path}...");
+                return Keystore::load(std::path::Path::new(&path))?
+                    .decrypt(&keystore::passphrase_from_env()?);
+            }
             if let Ok(use_keyring) = std::env::var("HYLE_USE_KEYRING") {
                 if use_keyring == "true" {
                     #[cfg(feature = "keyring")]
@@ -83,12 +113,7 @@ impl BlstCrypto {
             SecretKey::key_gen(&ikm, &[]).map_err(|e| anyhow!("Could not generate key: {:?}", e))
         })?;
 
-        let validator_pubkey = as_validator_pubkey(sk.sk_to_pk());
-
-        Ok(BlstCrypto {
-            sk,
-            validator_pubkey,
-        })
+        Ok(Self::from_secret_key(sk))
     }
 
     /// Load the secret key from the environment variable `HYLE_VALIDATOR_SECRET`.
@@ -133,14 +158,36 @@ impl BlstCrypto {
 
         let sk = SecretKey::key_gen(&ikm, &[])
             .map_err(|e| anyhow!("Could not generate key: {:?}", e))?;
+        Ok(Self::from_secret_key(sk))
+    }
+
+    fn from_secret_key(sk: SecretKey) -> Self {
         let validator_pubkey = as_validator_pubkey(sk.sk_to_pk());
+        BlstCrypto {
+            signer: Signer::Local(sk),
+            validator_pubkey,
+        }
+    }
+
+    pub fn from_keystore(keystore: &Keystore, passphrase: &str) -> Result<Self> {
+        Ok(Self::from_secret_key(keystore.decrypt(passphrase)?))
+    }
 
+    /// Signs through the signer process listening on the given socket
+    #[cfg(unix)]
+    pub fn remote(socket: &std::path::Path) -> Result<Self> {
+        let remote = RemoteSigner::connect(socket)?;
+        let validator_pubkey = remote.public_key()?;
         Ok(BlstCrypto {
-            sk,
+            signer: Signer::Remote(Arc::new(remote)),
             validator_pubkey,
         })
     }
 
+    pub fn is_remote(&self) -> bool {
+        !matches!(self.signer, Signer::Local(_))
+    }
+
     pub fn secret_from_name(validator_name: &str) -> [u8; 32] {
         let validator_name_bytes = validator_name.as_bytes();
         let mut ikm = [0u8; 32];
@@ -165,18 +212,53 @@ impl BlstCrypto {
         &self.validator_pubkey
     }
 
+    /// Signs with the local key, remote signers only sign typed messages (see [Self::sign_typed])
     pub fn sign<T>(&self, msg: T) -> Result<Signed<T, ValidatorSignature>, Error>
     where
         T: borsh::BorshSerialize,
     {
-        let signature = self.sign_msg(&msg)?.into();
-        Ok(Signed {
+        let signature = self.sign_msg(&msg)?;
+        Ok(self.signed(msg, signature))
+    }
+
+    /// Signs a message that remote signers derive themselves from the typed message,
+    /// which is only built for them.
+    pub fn sign_typed<T>(
+        &self,
+        msg: T,
+        message: impl FnOnce() -> Result<SignerMessage>,
+    ) -> Result<Signed<T, ValidatorSignature>, Error>
+    where
+        T: borsh::BorshSerialize,
+    {
+        let signature = match &self.signer {
+            Signer::Local(_) => self.sign_msg(&msg)?,
+            #[cfg(unix)]
+            Signer::Remote(remote) => {
+                let signature = remote.sign(message()?)?;
+                let pk = PublicKey::uncompress(&self.validator_pubkey.0)
+                    .map_err(|e| anyhow!("Could not parse PublicKey: {:?}", e))?;
+                if !Self::verify_bytes(&borsh::to_vec(&msg)?, &signature, &pk) {
+                    bail!("Remote signer did not sign the message it was sent");
+                }
+                signature
+            }
+        };
+        Ok(self.signed(msg, signature))
+    }
+
+    fn signed<T: borsh::BorshSerialize>(
+        &self,
+        msg: T,
+        signature: BlstSignature,
+    ) -> Signed<T, ValidatorSignature> {
+        Signed {
             msg,
             signature: ValidatorSignature {
-                signature,
+                signature: signature.into(),
                 validator: self.validator_pubkey.clone(),
             },
-        })
+        }
     }
 
     pub fn verify<T>(msg: &SignedByValidator<T>) -> Result<bool, Error>
@@ -274,11 +356,15 @@ impl BlstCrypto {
         T: borsh::BorshSerialize,
     {
         let encoded = borsh::to_vec(msg)?;
-        Ok(self.sign_bytes(encoded.as_slice()))
+        self.sign_bytes(encoded.as_slice())
     }
 
-    fn sign_bytes(&self, msg: &[u8]) -> BlstSignature {
-        self.sk.sign(msg, DST, &[])
+    fn sign_bytes(&self, msg: &[u8]) -> Result<BlstSignature> {
+        match &self.signer {
+            Signer::Local(sk) => Ok(sk.sign(msg, DST, &[])),
+            #[cfg(unix)]
+            Signer::Remote(_) => bail!("Remote signers only sign typed messages"),
+        }
     }
 
     fn verify_bytes(msg: &[u8], sig: &BlstSignature, pk: &PublicKey) -> bool {
@@ -343,15 +429,15 @@ mod tests {
     fn test_sign_bytes() {
         let crypto = BlstCrypto::new_random().unwrap();
         let msg = b"hello";
-        let sig = crypto.sign_bytes(msg);
-        let valid = BlstCrypto::verify_bytes(msg, &sig, &crypto.sk.sk_to_pk());
+        let sig = crypto.sign_bytes(msg).unwrap();
+        let pk = PublicKey::uncompress(&crypto.validator_pubkey.0).unwrap();
+        let valid = BlstCrypto::verify_bytes(msg, &sig, &pk);
         assert!(valid);
     }
 
     #[test]
     fn test_sign() {
         let crypto = BlstCrypto::new_random().unwrap();
-        let pub_key = ValidatorPublicKey(crypto.sk.sk_to_pk().to_bytes().as_slice().to_vec());
         let msg = Data::default();
         let signed = crypto.sign(&msg).unwrap();
         let valid = BlstCrypto::verify(&signed).unwrap();
@@ -362,7 +448,6 @@ mod tests {
         msg: T,
     ) -> (SignedByValidator<T>, ValidatorPublicKey) {
         let crypto = BlstCrypto::new_random().unwrap();
-        let pub_key = ValidatorPublicKey(crypto.sk.sk_to_pk().to_bytes().as_slice().to_vec());
         (crypto.sign(msg).unwrap(), crypto.validator_pubkey.clone())
     }
 
@@ -0,0 +1,303 @@
+//! Signing over a local unix socket, with the secret key held by a separate signer process.
+//!
+//! Requests and responses are borsh-encoded, each prefixed by its length as a u32 LE.
+//! The signer only signs typed messages: it derives what it signs, and the slot and view
+//! checked against its slashing protection, from the message itself.
+
+use std::{
+    io::{Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use blst::min_pk::Signature as BlstSignature;
+use borsh::{BorshDeserialize, BorshSerialize};
+use hyle_model::ValidatorPublicKey;
+use tokio::runtime::RuntimeFlavor;
+use tracing::warn;
+
+use crate::{
+    signer::{SignerMessage, SigningPolicy, SlashingProtection},
+    BlstCrypto,
+};
+
+/// Signing requests are small, anything bigger is not one
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum SignerRequest {
+    PublicKey,
+    Sign(SignerMessage),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum SignerResponse {
+    PublicKey(ValidatorPublicKey),
+    Signature(Vec<u8>),
+    Refused(String),
+}
+
+fn write_frame<T: BorshSerialize>(stream: &mut UnixStream, value: &T) -> Result<()> {
+    let encoded = borsh::to_vec(value)?;
+    stream.write_all(&(encoded.len() as u32).to_le_bytes())?;
+    stream.write_all(&encoded)?;
+    Ok(())
+}
+
+/// Returns None when the peer closed the connection
+fn read_frame<T: BorshDeserialize>(stream: &mut UnixStream) -> Result<Option<T>> {
+    let mut len = [0u8; 4];
+    if let Err(e) = stream.read_exact(&mut len) {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            return Ok(None);
+        }
+        return Err(e.into());
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_SIZE {
+        bail!("Signer frame of {len} bytes is too big");
+    }
+    let mut buf = vec![0u8; len];
+    stream.read_exact(&mut buf)?;
+    Ok(Some(borsh::from_slice(&buf)?))
+}
+
+/// Client side, used by the node. Requests are blocking, the socket being local: when made from
+/// an async task, the runtime first moves its other tasks to another worker thread.
+pub struct RemoteSigner {
+    socket: PathBuf,
+    stream: Mutex<Option<UnixStream>>,
+}
+
+impl RemoteSigner {
+    pub fn connect(socket: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(socket)
+            .with_context(|| format!("Connecting to remote signer at {}", socket.display()))?;
+        Ok(RemoteSigner {
+            socket: socket.to_path_buf(),
+            stream: Mutex::new(Some(stream)),
+        })
+    }
+
+    fn request(&self, request: &SignerRequest) -> Result<SignerResponse> {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| self.round_trip(request))
+            }
+            // Single threaded runtimes can't hand their tasks over, as in tests
+            _ => self.round_trip(request),
+        }
+    }
+
+    fn round_trip(&self, request: &SignerRequest) -> Result<SignerResponse> {
+        let mut stream = self
+            .stream
+            .lock()
+            .map_err(|_| anyhow!("Remote signer connection poisoned"))?;
+        // Reconnect once if the signer restarted since the last request.
+        // Sending a request twice is harmless, signing the same record again being allowed.
+        let mut error = anyhow!("Could not reach remote signer");
+        for _ in 0..2 {
+            let conn = match stream.as_mut() {
+                Some(conn) => conn,
+                None => stream.insert(
+                    UnixStream::connect(&self.socket).context("Reconnecting to remote signer")?,
+                ),
+            };
+            match write_frame(conn, request).and_then(|_| read_frame(conn)) {
+                Ok(Some(response)) => return Ok(response),
+                Ok(None) => error = anyhow!("Remote signer closed the connection"),
+                Err(e) => error = e.context("Requesting remote signer"),
+            }
+            *stream = None;
+        }
+        Err(error)
+    }
+
+    pub fn public_key(&self) -> Result<ValidatorPublicKey> {
+        match self.request(&SignerRequest::PublicKey)? {
+            SignerResponse::PublicKey(pubkey) => Ok(pubkey),
+            other => bail!("Unexpected remote signer response: {:?}", other),
+        }
+    }
+
+    pub(crate) fn sign(&self, message: SignerMessage) -> Result<BlstSignature> {
+        match self.request(&SignerRequest::Sign(message))? {
+            SignerResponse::Signature(signature) => BlstSignature::uncompress(&signature)
+                .map_err(|e| anyhow!("Could not parse remote signature: {:?}", e)),
+            SignerResponse::Refused(reason) => bail!("Remote signer refused to sign: {reason}"),
+            other => bail!("Unexpected remote signer response: {:?}", other),
+        }
+    }
+}
+
+/// Signer process side, holding the secret key.
+pub struct SignerServer<P: SigningPolicy> {
+    crypto: BlstCrypto,
+    protection: SlashingProtection,
+    policy: P,
+}
+
+impl<P: SigningPolicy> SignerServer<P> {
+    pub fn new(crypto: BlstCrypto, protection: SlashingProtection, policy: P) -> Result<Self> {
+        if crypto.is_remote() {
+            bail!("A signer needs the secret key, not another remote signer");
+        }
+        Ok(SignerServer {
+            crypto,
+            protection,
+            policy,
+        })
+    }
+
+    fn sign(&mut self, message: &SignerMessage) -> Result<BlstSignature> {
+        let (data, record) = self.policy.signable(message)?;
+        if let Some(record) = record {
+            self.protection.record(record)?;
+        }
+        self.crypto.sign_bytes(&data)
+    }
+
+    pub fn handle(&mut self, request: SignerRequest) -> SignerResponse {
+        match request {
+            SignerRequest::PublicKey => {
+                SignerResponse::PublicKey(self.crypto.validator_pubkey().clone())
+            }
+            SignerRequest::Sign(message) => match self.sign(&message) {
+                Ok(signature) => SignerResponse::Signature(signature.compress().to_vec()),
+                Err(e) => {
+                    warn!("🔏 Refused to sign {:?}: {e:#}", message);
+                    SignerResponse::Refused(format!("{e:#}"))
+                }
+            },
+        }
+    }
+
+    /// Answers the requests of one node at a time, until the listener fails.
+    pub fn serve(&mut self, listener: UnixListener) -> Result<()> {
+        for stream in listener.incoming() {
+            let mut stream = stream.context("Accepting signer connection")?;
+            if let Err(e) = self.serve_connection(&mut stream) {
+                warn!("Signer connection closed: {e:#}");
+            }
+        }
+        Ok(())
+    }
+
+    fn serve_connection(&mut self, stream: &mut UnixStream) -> Result<()> {
+        while let Some(request) = read_frame(stream)? {
+            let response = self.handle(request);
+            write_frame(stream, &response)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyle_model::{ConsensusProposalHash, ValidatorCandidacy};
+
+    use super::*;
+    use crate::signer::{SignedKind, SignedRecord};
+
+    /// Signs candidacies as they are, and votes as their proposal hash
+    struct TestPolicy;
+
+    impl SigningPolicy for TestPolicy {
+        fn signable(&self, message: &SignerMessage) -> Result<(Vec<u8>, Option<SignedRecord>)> {
+            match message {
+                SignerMessage::Candidacy(candidacy) => Ok((borsh::to_vec(candidacy)?, None)),
+                SignerMessage::Round(record) => {
+                    Ok((borsh::to_vec(&record.hash)?, Some(record.clone())))
+                }
+                _ => bail!("Unexpected message"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_remote_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let local = BlstCrypto::new_random().unwrap();
+        let mut server =
+            SignerServer::new(local.clone(), SlashingProtection::default(), TestPolicy).unwrap();
+        std::thread::spawn(move || server.serve(listener));
+
+        let remote = BlstCrypto::remote(&socket).unwrap();
+        assert_eq!(remote.validator_pubkey(), local.validator_pubkey());
+        assert!(remote.is_remote());
+
+        // Raw data is never sent to the signer
+        assert!(remote.sign("hello".to_string()).is_err());
+
+        let candidacy = ValidatorCandidacy {
+            peer_address: "127.0.0.1:1234".to_string(),
+        };
+        let signed = remote
+            .sign_typed(candidacy.clone(), || {
+                Ok(SignerMessage::Candidacy(candidacy.clone()))
+            })
+            .unwrap();
+        assert!(BlstCrypto::verify(&signed).unwrap());
+
+        // The signature is over what the signer derives, not what the node claims
+        let err = remote
+            .sign_typed("something else".to_string(), || {
+                Ok(SignerMessage::Candidacy(candidacy.clone()))
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("did not sign"));
+        let err = remote
+            .sign_typed(candidacy.clone(), || {
+                Ok(SignerMessage::Handshake(borsh::to_vec(&candidacy)?))
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("refused"));
+
+        let vote = |hash: &str| {
+            SignerMessage::Round(SignedRecord {
+                kind: SignedKind::PrepareVote,
+                slot: 3,
+                view: 0,
+                hash: ConsensusProposalHash(hash.to_string()),
+            })
+        };
+        let hash_a = ConsensusProposalHash("a".to_string());
+        let signed = remote.sign_typed(hash_a.clone(), || Ok(vote("a"))).unwrap();
+        assert!(BlstCrypto::verify(&signed).unwrap());
+        assert!(remote.sign_typed(hash_a, || Ok(vote("a"))).is_ok());
+        let err = remote
+            .sign_typed(ConsensusProposalHash("b".to_string()), || Ok(vote("b")))
+            .unwrap_err();
+        assert!(err.to_string().contains("refused"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_remote_signer_from_async_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let mut server = SignerServer::new(
+            BlstCrypto::new_random().unwrap(),
+            SlashingProtection::default(),
+            TestPolicy,
+        )
+        .unwrap();
+        std::thread::spawn(move || server.serve(listener));
+        // Requests hand the only worker thread over to the runtime while they wait
+        let remote = BlstCrypto::remote(&socket).unwrap();
+        let candidacy = ValidatorCandidacy {
+            peer_address: "127.0.0.1:1234".to_string(),
+        };
+        let signed = remote
+            .sign_typed(candidacy.clone(), || {
+                Ok(SignerMessage::Candidacy(candidacy.clone()))
+            })
+            .unwrap();
+        assert!(BlstCrypto::verify(&signed).unwrap());
+    }
+}
@@ -0,0 +1,190 @@
+//! Slashing protection of the consensus messages a validator signs.
+//!
+//! Prepares, votes and timeouts are signed for a slot and view. Signing two different
+//! messages of the same kind for the same slot and view is an equivocation, so signers
+//! remember the last one of each kind and refuse conflicting or older ones.
+//!
+//! Signers are sent typed [SignerMessage]s, never raw bytes: their [SigningPolicy] decodes
+//! each message and derives both the bytes to sign and the record to check from it.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hyle_model::{
+    ConsensusProposalHash, DataProposalHash, LaneBytesSize, Slot, ValidatorCandidacy, View,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, BorshSerialize, BorshDeserialize)]
+pub enum SignedKind {
+    Prepare,
+    PrepareVote,
+    ConfirmAck,
+    Timeout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SignedRecord {
+    pub kind: SignedKind,
+    pub slot: Slot,
+    pub view: View,
+    /// Proposal hash for prepares and votes, parent hash for timeouts
+    pub hash: ConsensusProposalHash,
+}
+
+impl SignedRecord {
+    /// Fails if the record conflicts with, or goes back before, the last one of its kind.
+    /// Returns whether it is new.
+    pub fn check_after(&self, last: Option<&SignedRecord>) -> Result<bool> {
+        let Some(last) = last else {
+            return Ok(true);
+        };
+        if (self.slot, self.view) < (last.slot, last.view) {
+            bail!(
+                "Refusing to sign {:?} for slot {} view {}, already signed for slot {} view {}",
+                self.kind,
+                self.slot,
+                self.view,
+                last.slot,
+                last.view
+            );
+        }
+        if (self.slot, self.view) == (last.slot, last.view) {
+            if self.hash != last.hash {
+                bail!(
+                    "Refusing to sign conflicting {:?} for slot {} view {}: already signed {}, now {}",
+                    self.kind,
+                    self.slot,
+                    self.view,
+                    last.hash,
+                    self.hash
+                );
+            }
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+/// What a node asks a signer to sign.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum SignerMessage {
+    /// Header of a consensus message, with the borsh-encoded message
+    ConsensusHeader {
+        timestamp: u128,
+        message: Vec<u8>,
+    },
+    /// Header of a mempool message, with the borsh-encoded message
+    MempoolHeader {
+        timestamp: u128,
+        message: Vec<u8>,
+    },
+    /// Vote or timeout of a round, signed as described by its record
+    Round(SignedRecord),
+    /// Vote for a data proposal, with the size of its lane up to it
+    DataVote(DataProposalHash, LaneBytesSize),
+    Candidacy(ValidatorCandidacy),
+    /// Borsh-encoded data of a P2P handshake
+    Handshake(Vec<u8>),
+}
+
+/// How a signer process decodes the messages it is sent.
+pub trait SigningPolicy: Send {
+    /// Bytes to sign for the message, and the record of it to check first.
+    /// Fails for messages that can't be decoded or must not be signed.
+    fn signable(&self, message: &SignerMessage) -> Result<(Vec<u8>, Option<SignedRecord>)>;
+}
+
+/// Last record of each kind signed by a signer process, persisted before each signature is returned.
+#[derive(Default)]
+pub struct SlashingProtection {
+    /// No file in tests, the records are then only kept in memory
+    path: Option<PathBuf>,
+    last: BTreeMap<SignedKind, SignedRecord>,
+}
+
+impl SlashingProtection {
+    pub fn open(path: &Path) -> Result<Self> {
+        let last = if path.exists() {
+            borsh::from_slice(&fs::read(path).context("Reading slashing protection")?)
+                .context("Decoding slashing protection")?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(SlashingProtection {
+            path: Some(path.to_path_buf()),
+            last,
+        })
+    }
+
+    pub fn last(&self, kind: SignedKind) -> Option<&SignedRecord> {
+        self.last.get(&kind)
+    }
+
+    /// Checks the record, and durably stores it if it is new.
+    pub fn record(&mut self, record: SignedRecord) -> Result<()> {
+        if !record.check_after(self.last.get(&record.kind))? {
+            return Ok(());
+        }
+        let mut last = self.last.clone();
+        last.insert(record.kind, record);
+        if let Some(path) = &self.path {
+            let tmp = path.with_extension("tmp");
+            let mut file = File::create(&tmp)?;
+            file.write_all(&borsh::to_vec(&last)?)?;
+            file.sync_all()?;
+            fs::rename(&tmp, path).context("Storing slashing protection")?;
+        }
+        self.last = last;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: SignedKind, slot: Slot, view: View, hash: &str) -> SignedRecord {
+        SignedRecord {
+            kind,
+            slot,
+            view,
+            hash: ConsensusProposalHash(hash.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_protection_survives_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("slashing_protection.bin");
+
+        let mut protection = SlashingProtection::open(&path).unwrap();
+        protection
+            .record(record(SignedKind::Prepare, 4, 1, "a"))
+            .unwrap();
+        protection
+            .record(record(SignedKind::Prepare, 4, 1, "a"))
+            .unwrap();
+        assert!(protection
+            .record(record(SignedKind::Prepare, 4, 1, "b"))
+            .is_err());
+        drop(protection);
+
+        let mut protection = SlashingProtection::open(&path).unwrap();
+        assert_eq!(
+            protection.last(SignedKind::Prepare),
+            Some(&record(SignedKind::Prepare, 4, 1, "a"))
+        );
+        assert!(protection
+            .record(record(SignedKind::Prepare, 4, 0, "a"))
+            .is_err());
+        protection
+            .record(record(SignedKind::Prepare, 4, 2, "b"))
+            .unwrap();
+    }
+}
@@ -0,0 +1,762 @@
+//! Verification of Hyli block finality, for clients that don't follow consensus.
+//!
+//! Starting from a trusted validator set, the client follows a chain of validator set
+//! transitions, as served by the consensus API: each transition is a committed proposal
+//! that changes the consensus membership, certified by a quorum of the previous set.
+//! A block is then final if its commit certificate was signed by a quorum of the set
+//! in charge at its slot.
+//!
+//! Votes are weighted with the stakes frozen at the start of each epoch. The proposal ending
+//! an epoch lists the validators of the next one with their stake, when they change, so every
+//! transition is derived from the staking actions of its certified proposal. Every proposal
+//! ending an epoch is a transition, so that the client knows the set of each epoch it crosses.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec::Vec,
+};
+
+use anyhow::{bail, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(test, feature = "model"))]
+mod model;
+mod proposal;
+
+pub use proposal::*;
+
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Compressed BLS public key of a validator
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ValidatorKey(#[serde(with = "hex::serde")] pub Vec<u8>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct Validator {
+    pub pubkey: ValidatorKey,
+    pub stake: u128,
+}
+
+/// Validators bonded in consensus, whose quorum certifies proposals
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ValidatorSet {
+    pub validators: Vec<Validator>,
+    /// Sum of the validators' stakes
+    pub total_bond: u128,
+}
+
+/// Aggregated signature of the validators that committed a proposal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct CommitCertificate {
+    #[serde(with = "hex::serde")]
+    pub signature: Vec<u8>,
+    pub validators: Vec<ValidatorKey>,
+}
+
+/// Validator set a client trusts, and the epoch whose votes it weighs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct TrustedEpoch {
+    pub validators: ValidatorSet,
+    pub epoch: u64,
+    /// Number of slots of each epoch, as set by the genesis block
+    pub epoch_length: u64,
+}
+
+/// A committed proposal changing the consensus membership, or ending an epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ValidatorSetTransition {
+    pub proposal: ProposalHeader,
+    /// Signed by the validator set in charge before this transition
+    pub certificate: CommitCertificate,
+    /// Validator set in charge from the next slot on
+    pub next: ValidatorSet,
+}
+
+impl ValidatorSet {
+    /// Validators sorted by public key, the way consensus lists them
+    pub fn new(mut validators: Vec<Validator>) -> Result<Self> {
+        validators.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+        let total_bond = total_stake(&validators)?;
+        Ok(ValidatorSet {
+            validators,
+            total_bond,
+        })
+    }
+
+    pub fn contains(&self, pubkey: &ValidatorKey) -> bool {
+        self.validators.iter().any(|v| &v.pubkey == pubkey)
+    }
+
+    /// Stake needed for a quorum, as computed by consensus on the validators' stakes
+    pub fn quorum(&self) -> Result<u128> {
+        Ok(2 * total_stake(&self.validators)?.div_euclid(3) + 1)
+    }
+
+    /// Checks that the certificate was signed by a quorum of this set, for the proposal hash.
+    pub fn verify_certificate(
+        &self,
+        proposal_hash: &str,
+        certificate: &CommitCertificate,
+    ) -> Result<()> {
+        let signers: BTreeSet<&ValidatorKey> = certificate.validators.iter().collect();
+        let mut voting_power: u128 = 0;
+        for signer in &signers {
+            let Some(validator) = self.validators.iter().find(|v| &&v.pubkey == signer) else {
+                bail!(
+                    "Certificate signed by {}, which is not a validator",
+                    hex::encode(&signer.0)
+                );
+            };
+            voting_power = voting_power
+                .checked_add(validator.stake)
+                .ok_or_else(|| anyhow::anyhow!("Voting power overflows"))?;
+        }
+        let quorum = self.quorum()?;
+        if voting_power < quorum {
+            bail!(
+                "Certificate has a voting power of {}, {} is needed",
+                voting_power,
+                quorum
+            );
+        }
+
+        let keys = certificate
+            .validators
+            .iter()
+            .map(|v| {
+                blst::min_pk::PublicKey::uncompress(&v.0)
+                    .map_err(|e| anyhow::anyhow!("Could not parse public key: {:?}", e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let key_refs: Vec<&blst::min_pk::PublicKey> = keys.iter().collect();
+        let key = blst::min_pk::AggregatePublicKey::aggregate(&key_refs, true)
+            .map_err(|e| anyhow::anyhow!("Could not aggregate public keys: {:?}", e))?
+            .to_public_key();
+        let signature = blst::min_pk::Signature::uncompress(&certificate.signature)
+            .map_err(|e| anyhow::anyhow!("Could not parse signature: {:?}", e))?;
+
+        // Validators sign the borsh encoding of (proposal hash, ConfirmAckMarker), the marker being empty
+        let message = borsh::to_vec(&String::from(proposal_hash))?;
+        let res = signature.verify(true, &message, DST, &[], &key, true);
+        if res != blst::BLST_ERROR::BLST_SUCCESS {
+            bail!("Invalid certificate signature: {:?}", res);
+        }
+        Ok(())
+    }
+
+    /// Verifies the transition was certified by this set, and returns the next set,
+    /// as derived from the staking actions of its proposal.
+    pub fn apply(&self, transition: &ValidatorSetTransition) -> Result<ValidatorSet> {
+        let hash = transition.proposal.hash();
+        self.verify_certificate(&hash, &transition.certificate)?;
+
+        let mut stakes: BTreeMap<&ValidatorKey, u128> = self
+            .validators
+            .iter()
+            .map(|v| (&v.pubkey, v.stake))
+            .collect();
+        let mut members_change = false;
+        let mut joining = BTreeSet::new();
+        let mut epoch_stakes = None;
+        for action in &transition.proposal.staking_actions {
+            match action {
+                StakingChange::Bond(pubkey) => {
+                    members_change = true;
+                    joining.insert(pubkey);
+                }
+                StakingChange::Unbond(pubkey) => {
+                    members_change = true;
+                    stakes.remove(pubkey);
+                }
+                StakingChange::Slash { evidence } => {
+                    let offender = slash_offender(evidence)?;
+                    stakes.retain(|v, _| v.0 != offender);
+                }
+                StakingChange::EpochStakes(validators) => {
+                    if epoch_stakes.replace(validators).is_some() {
+                        bail!(
+                            "Proposal of slot {} lists the next epoch stakes twice",
+                            transition.proposal.slot
+                        );
+                    }
+                }
+                StakingChange::EpochLength(_) => {
+                    bail!(
+                        "Proposal of slot {} sets the epoch length, only the genesis block can",
+                        transition.proposal.slot
+                    );
+                }
+                StakingChange::PayFeesForDaDi { .. } => {}
+            }
+        }
+
+        let next = match epoch_stakes {
+            Some(validators) => {
+                let members: BTreeSet<&ValidatorKey> =
+                    stakes.keys().copied().chain(joining).collect();
+                let listed: BTreeSet<&ValidatorKey> =
+                    validators.iter().map(|v| &v.pubkey).collect();
+                if members != listed || listed.len() != validators.len() {
+                    bail!(
+                        "Validators of the epoch after slot {} don't match the staking actions of its proposal",
+                        transition.proposal.slot
+                    );
+                }
+                ValidatorSet::new(validators.clone())?
+            }
+            None if members_change => bail!(
+                "Proposal of slot {} changes validators without their stakes",
+                transition.proposal.slot
+            ),
+            None => ValidatorSet::new(
+                stakes
+                    .into_iter()
+                    .map(|(pubkey, stake)| Validator {
+                        pubkey: pubkey.clone(),
+                        stake,
+                    })
+                    .collect(),
+            )?,
+        };
+        if next != transition.next {
+            bail!(
+                "Validators after slot {} don't match the staking actions of its proposal",
+                transition.proposal.slot
+            );
+        }
+        Ok(next)
+    }
+}
+
+fn total_stake(validators: &[Validator]) -> Result<u128> {
+    validators.iter().try_fold(0u128, |total, v| {
+        total
+            .checked_add(v.stake)
+            .ok_or_else(|| anyhow::anyhow!("Total stake overflows"))
+    })
+}
+
+impl TrustedEpoch {
+    fn epoch_of(&self, slot: u64) -> u64 {
+        slot / self.epoch_length.max(1)
+    }
+
+    /// Checks that the proposal is in the epoch the validators are in charge of
+    fn check_epoch(&self, proposal: &ProposalHeader) -> Result<()> {
+        if proposal.epoch != self.epoch_of(proposal.slot) {
+            bail!(
+                "Proposal of slot {} claims epoch {}, but the slot is in epoch {}",
+                proposal.slot,
+                proposal.epoch,
+                self.epoch_of(proposal.slot)
+            );
+        }
+        if proposal.epoch > self.epoch {
+            bail!(
+                "Proposal of slot {} is in epoch {}, but the transitions only reach epoch {}",
+                proposal.slot,
+                proposal.epoch,
+                self.epoch
+            );
+        }
+        if proposal.epoch < self.epoch {
+            bail!(
+                "Proposal of slot {} is in epoch {}, before the trusted epoch {}",
+                proposal.slot,
+                proposal.epoch,
+                self.epoch
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Follows the transitions from the trusted set, and checks that the block was committed.
+/// Transitions must be sorted by slot, the ones from the block slot on are ignored. There must be
+/// one for each epoch that ends before the block's, and each must be in the epoch the set
+/// following it is in charge of.
+pub fn verify_finality(
+    trusted: &TrustedEpoch,
+    transitions: &[ValidatorSetTransition],
+    block: &ProposalHeader,
+    block_hash: &str,
+    certificate: &CommitCertificate,
+) -> Result<()> {
+    if block.hash() != block_hash {
+        bail!("Block header does not match hash {}", block_hash);
+    }
+
+    let mut current = trusted.clone();
+    let mut last_slot = None;
+    for transition in transitions
+        .iter()
+        .take_while(|t| t.proposal.slot < block.slot)
+    {
+        if last_slot.is_some_and(|slot| slot >= transition.proposal.slot) {
+            bail!("Transitions are not sorted by slot");
+        }
+        last_slot = Some(transition.proposal.slot);
+        current.check_epoch(&transition.proposal)?;
+        current.validators = current.validators.apply(transition)?;
+        if current.epoch_of(transition.proposal.slot + 1) > current.epoch {
+            current.epoch += 1;
+        }
+    }
+
+    current.check_epoch(block)?;
+    current
+        .validators
+        .verify_certificate(block_hash, certificate)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec};
+
+    use hyle_crypto::BlstCrypto;
+    use hyle_model::{
+        AggregateSignature, ConsensusProposal, ConsensusProposalHash, ConsensusStakingAction,
+        EquivocationEvidence, Hashed, HeaderSignableData, MsgHeader, SignedByValidator,
+        SignedDataProposal, SignedVote, ValidatorCandidacy, ValidatorPublicKey, VoteKind,
+    };
+
+    use super::*;
+
+    /// Epoch 0 is made of slots 0 to 2, epoch 1 of slots 3 to 5...
+    const EPOCH_LENGTH: u64 = 3;
+
+    fn validator_set(cryptos: &[&BlstCrypto]) -> ValidatorSet {
+        ValidatorSet::new(
+            cryptos
+                .iter()
+                .map(|c| Validator {
+                    pubkey: ValidatorKey(c.validator_pubkey().0.clone()),
+                    stake: 100,
+                })
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn trusted(validators: &ValidatorSet) -> TrustedEpoch {
+        TrustedEpoch {
+            validators: validators.clone(),
+            epoch: 0,
+            epoch_length: EPOCH_LENGTH,
+        }
+    }
+
+    fn epoch_stakes(set: &ValidatorSet) -> ConsensusStakingAction {
+        ConsensusStakingAction::EpochStakes {
+            stakes: set
+                .validators
+                .iter()
+                .map(|v| (ValidatorPublicKey(v.pubkey.0.clone()), v.stake))
+                .collect(),
+        }
+    }
+
+    fn commit(cp: &ConsensusProposal, signers: &[&BlstCrypto]) -> CommitCertificate {
+        // The empty ConfirmAckMarker doesn't change the signed bytes
+        let msg = cp.hashed();
+        let signed: Vec<SignedByValidator<ConsensusProposalHash>> = signers
+            .iter()
+            .map(|c| c.sign(msg.clone()).unwrap())
+            .collect();
+        let aggregate = BlstCrypto::aggregate(msg, &signed.iter().collect::<Vec<_>>()).unwrap();
+        (&aggregate.signature).into()
+    }
+
+    fn proposal(slot: u64, staking_actions: Vec<ConsensusStakingAction>) -> ConsensusProposal {
+        ConsensusProposal {
+            slot,
+            epoch: slot / EPOCH_LENGTH,
+            staking_actions,
+            ..ConsensusProposal::default()
+        }
+    }
+
+    #[test]
+    fn test_follows_validator_set_transitions() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|n| BlstCrypto::new(n).unwrap());
+        let genesis = validator_set(&[&a, &b, &c]);
+
+        // d joins at slot 2, certified by the genesis validators
+        let candidacy = d
+            .sign(ValidatorCandidacy {
+                peer_address: "d".into(),
+            })
+            .unwrap();
+        let with_d = validator_set(&[&a, &b, &c, &d]);
+        let bond = proposal(
+            2,
+            vec![
+                ConsensusStakingAction::Bond {
+                    candidate: Box::new(candidacy.clone()),
+                },
+                epoch_stakes(&with_d),
+            ],
+        );
+        let transition = ValidatorSetTransition {
+            proposal: (&bond).into(),
+            certificate: commit(&bond, &[&a, &b, &c]),
+            next: with_d.clone(),
+        };
+
+        let block = proposal(5, vec![]);
+        let hash = block.hashed().0;
+        // 3 out of 4 validators are needed after the transition
+        let certificate = commit(&block, &[&b, &c, &d]);
+        verify_finality(
+            &trusted(&genesis),
+            core::slice::from_ref(&transition),
+            &(&block).into(),
+            &hash,
+            &certificate,
+        )
+        .unwrap();
+
+        // Without the transition, d is unknown
+        assert!(verify_finality(
+            &trusted(&genesis),
+            &[],
+            &(&block).into(),
+            &hash,
+            &certificate
+        )
+        .is_err());
+        // Not enough voting power
+        assert!(verify_finality(
+            &trusted(&genesis),
+            core::slice::from_ref(&transition),
+            &(&block).into(),
+            &hash,
+            &commit(&block, &[&c, &d]),
+        )
+        .is_err());
+        // Another block
+        assert!(verify_finality(
+            &trusted(&genesis),
+            core::slice::from_ref(&transition),
+            &(&proposal(6, vec![])).into(),
+            &hash,
+            &certificate,
+        )
+        .is_err());
+
+        // Validators added without being bonded by the proposal are refused
+        let mut forged = transition.clone();
+        forged.next = validator_set(&[&a, &b, &d]);
+        assert!(genesis.apply(&forged).is_err());
+        // So are stakes that the proposal doesn't list
+        let mut forged = transition.clone();
+        forged.next.validators[0].stake = 1_000;
+        forged.next.total_bond += 900;
+        assert!(genesis.apply(&forged).is_err());
+        // And bonds without the stakes of the next epoch
+        let bond_only = proposal(
+            2,
+            vec![ConsensusStakingAction::Bond {
+                candidate: Box::new(candidacy),
+            }],
+        );
+        assert!(genesis
+            .apply(&ValidatorSetTransition {
+                proposal: (&bond_only).into(),
+                certificate: commit(&bond_only, &[&a, &b, &c]),
+                next: with_d,
+            })
+            .is_err());
+
+        // d leaves at the end of epoch 1
+        let unbond = proposal(
+            5,
+            vec![
+                ConsensusStakingAction::Unbond {
+                    validator: d.validator_pubkey().clone(),
+                },
+                epoch_stakes(&genesis),
+            ],
+        );
+        let leave = ValidatorSetTransition {
+            proposal: (&unbond).into(),
+            certificate: commit(&unbond, &[&a, &b, &d]),
+            next: validator_set(&[&a, &b, &c]),
+        };
+        let later = proposal(7, vec![]);
+        verify_finality(
+            &trusted(&genesis),
+            &[transition.clone(), leave.clone()],
+            &(&later).into(),
+            &later.hashed().0,
+            &commit(&later, &[&a, &b, &c]),
+        )
+        .unwrap();
+        assert!(verify_finality(
+            &trusted(&genesis),
+            &[leave, transition],
+            &(&later).into(),
+            &later.hashed().0,
+            &commit(&later, &[&a, &b, &c]),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_follows_epoch_stakes() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|n| BlstCrypto::new(n).unwrap());
+        let genesis = validator_set(&[&a, &b, &c, &d]);
+
+        // a gets more delegations from the next epoch on
+        let mut next = genesis.clone();
+        next.validators
+            .iter_mut()
+            .find(|v| v.pubkey.0 == a.validator_pubkey().0)
+            .unwrap()
+            .stake = 400;
+        let next = ValidatorSet::new(next.validators).unwrap();
+        assert_eq!(next.total_bond, 700);
+        let end_of_epoch = proposal(2, vec![epoch_stakes(&next)]);
+        let transition = ValidatorSetTransition {
+            proposal: (&end_of_epoch).into(),
+            certificate: commit(&end_of_epoch, &[&a, &b, &c]),
+            next: next.clone(),
+        };
+        assert_eq!(genesis.apply(&transition).unwrap(), next);
+
+        // a and b are now a quorum, which they weren't before
+        let block = proposal(5, vec![]);
+        let certificate = commit(&block, &[&a, &b]);
+        verify_finality(
+            &trusted(&genesis),
+            core::slice::from_ref(&transition),
+            &(&block).into(),
+            &block.hashed().0,
+            &certificate,
+        )
+        .unwrap();
+        assert!(genesis
+            .verify_certificate(&block.hashed().0, &certificate)
+            .is_err());
+    }
+
+    #[test]
+    fn test_requires_a_transition_per_epoch() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|n| BlstCrypto::new(n).unwrap());
+        let genesis = validator_set(&[&a, &b, &c]);
+        let with_d = validator_set(&[&a, &b, &c, &d]);
+
+        // d joins at the end of epoch 0, epoch 1 ends without changes
+        let bond = proposal(
+            2,
+            vec![
+                ConsensusStakingAction::Bond {
+                    candidate: Box::new(
+                        d.sign(ValidatorCandidacy {
+                            peer_address: "d".into(),
+                        })
+                        .unwrap(),
+                    ),
+                },
+                epoch_stakes(&with_d),
+            ],
+        );
+        let end_of_epoch = proposal(5, vec![]);
+        let transitions = [
+            ValidatorSetTransition {
+                proposal: (&bond).into(),
+                certificate: commit(&bond, &[&a, &b, &c]),
+                next: with_d.clone(),
+            },
+            ValidatorSetTransition {
+                proposal: (&end_of_epoch).into(),
+                certificate: commit(&end_of_epoch, &[&a, &b, &d]),
+                next: with_d.clone(),
+            },
+        ];
+
+        let block = proposal(7, vec![]);
+        let certificate = commit(&block, &[&b, &c, &d]);
+        verify_finality(
+            &trusted(&genesis),
+            &transitions,
+            &(&block).into(),
+            &block.hashed().0,
+            &certificate,
+        )
+        .unwrap();
+
+        // Omitting the end of epoch 1 leaves the client in epoch 1
+        assert!(verify_finality(
+            &trusted(&genesis),
+            &transitions[..1],
+            &(&block).into(),
+            &block.hashed().0,
+            &certificate,
+        )
+        .is_err());
+        // So does omitting the end of epoch 0, even if the next sets are the same
+        assert!(verify_finality(
+            &trusted(&genesis),
+            &transitions[1..],
+            &(&block).into(),
+            &block.hashed().0,
+            &certificate,
+        )
+        .is_err());
+
+        // Headers must be in the epoch of their slot
+        let mut wrong_epoch = proposal(4, vec![]);
+        wrong_epoch.epoch = 2;
+        assert!(verify_finality(
+            &trusted(&genesis),
+            &transitions,
+            &(&wrong_epoch).into(),
+            &wrong_epoch.hashed().0,
+            &commit(&wrong_epoch, &[&b, &c, &d]),
+        )
+        .is_err());
+        // And the trusted set can't vouch for blocks of previous epochs
+        let mut later = trusted(&with_d);
+        later.epoch = 2;
+        assert!(verify_finality(
+            &later,
+            &[],
+            &(&end_of_epoch).into(),
+            &end_of_epoch.hashed().0,
+            &transitions[1].certificate,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_voting_power_overflow() {
+        let [a, b] = ["a", "b"].map(|n| BlstCrypto::new(n).unwrap());
+        let set = ValidatorSet {
+            validators: [&a, &b]
+                .iter()
+                .map(|c| Validator {
+                    pubkey: ValidatorKey(c.validator_pubkey().0.clone()),
+                    stake: u128::MAX,
+                })
+                .collect(),
+            total_bond: u128::MAX,
+        };
+        let cp = proposal(1, vec![]);
+        assert!(set
+            .verify_certificate(&cp.hashed().0, &commit(&cp, &[&a, &b]))
+            .is_err());
+        assert!(ValidatorSet::new(set.validators).is_err());
+    }
+
+    #[test]
+    fn test_certificate_over_other_hash() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|n| BlstCrypto::new(n).unwrap());
+        let set = validator_set(&[&a, &b, &c, &d]);
+        let cp = proposal(1, vec![]);
+        let certificate = commit(&cp, &[&a, &b, &c]);
+        set.verify_certificate(&cp.hashed().0, &certificate)
+            .unwrap();
+        assert!(set
+            .verify_certificate(&proposal(2, vec![]).hashed().0, &certificate)
+            .is_err());
+
+        // Claiming more signers than the signature holds
+        let claimed = AggregateSignature {
+            signature: hyle_model::Signature(certificate.signature.clone()),
+            validators: [&a, &b, &c, &d]
+                .iter()
+                .map(|v| v.validator_pubkey().clone())
+                .collect(),
+        };
+        assert!(set
+            .verify_certificate(&cp.hashed().0, &(&claimed).into())
+            .is_err());
+    }
+
+    #[test]
+    fn test_slash_offender() {
+        let [a, b] = ["a", "b"].map(|n| BlstCrypto::new(n).unwrap());
+        let header = |timestamp| {
+            b.sign(MsgHeader {
+                timestamp,
+                hash: HeaderSignableData(vec![1, 2, 3]),
+            })
+            .unwrap()
+        };
+        let evidence = EquivocationEvidence::DataProposal(
+            SignedDataProposal {
+                header: header(1),
+                parent_data_proposal_hash: None,
+                tx_hashes: vec![],
+            },
+            SignedDataProposal {
+                header: header(2),
+                parent_data_proposal_hash: None,
+                tx_hashes: vec![],
+            },
+        );
+        let encoded = borsh::to_vec(&evidence).unwrap();
+        assert_eq!(
+            slash_offender(&encoded).unwrap(),
+            b.validator_pubkey().0.as_slice()
+        );
+        assert!(slash_offender(&encoded[..20]).is_err());
+
+        let vote = |proposal_hash: &str| {
+            let mut vote = SignedVote {
+                header: header(3),
+                kind: VoteKind::PrepareVote,
+                proposal_hash: ConsensusProposalHash(proposal_hash.into()),
+                slot: 1,
+                view: 0,
+            };
+            vote.header = b
+                .sign(MsgHeader {
+                    timestamp: 3,
+                    hash: vote.signable_data(),
+                })
+                .unwrap();
+            vote
+        };
+        let vote_evidence = EquivocationEvidence::Vote(vote("a"), vote("b"));
+        assert_eq!(
+            slash_offender(&borsh::to_vec(&vote_evidence).unwrap()).unwrap(),
+            b.validator_pubkey().0.as_slice()
+        );
+
+        let set = validator_set(&[&a, &b]);
+        let slash = proposal(3, vec![evidence.into()]);
+        let transition = ValidatorSetTransition {
+            proposal: (&slash).into(),
+            certificate: commit(&slash, &[&a, &b]),
+            next: validator_set(&[&a]),
+        };
+        assert_eq!(set.apply(&transition).unwrap(), validator_set(&[&a]));
+    }
+}
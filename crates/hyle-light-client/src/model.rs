@@ -0,0 +1,81 @@
+//! Conversions from the node data model.
+
+use alloc::vec::Vec;
+
+use hyle_model::{
+    AggregateSignature, ConsensusProposal, ConsensusStakingAction, ValidatorPublicKey,
+};
+
+use crate::{CommitCertificate, ProposalHeader, StakingChange, Validator, ValidatorKey};
+
+impl From<&ValidatorPublicKey> for ValidatorKey {
+    fn from(pubkey: &ValidatorPublicKey) -> Self {
+        ValidatorKey(pubkey.0.clone())
+    }
+}
+
+impl From<&ConsensusProposal> for ProposalHeader {
+    fn from(cp: &ConsensusProposal) -> Self {
+        ProposalHeader {
+            slot: cp.slot,
+            epoch: cp.epoch,
+            parent_hash: cp.parent_hash.0.clone(),
+            cut: cp
+                .cut
+                .iter()
+                .map(|(lane_id, hash, _, _)| ((&lane_id.0).into(), hash.0.clone()))
+                .collect(),
+            staking_actions: cp
+                .staking_actions
+                .iter()
+                .map(|action| match action {
+                    ConsensusStakingAction::Bond { candidate } => {
+                        StakingChange::Bond((&candidate.signature.validator).into())
+                    }
+                    ConsensusStakingAction::PayFeesForDaDi {
+                        lane_id,
+                        cumul_size,
+                    } => StakingChange::PayFeesForDaDi {
+                        lane: (&lane_id.0).into(),
+                        cumul_size: cumul_size.0,
+                    },
+                    ConsensusStakingAction::Slash { evidence } => StakingChange::Slash {
+                        evidence: borsh::to_vec(evidence).unwrap_or_default(),
+                    },
+                    ConsensusStakingAction::Unbond { validator } => {
+                        StakingChange::Unbond(validator.into())
+                    }
+                    ConsensusStakingAction::EpochStakes { stakes } => StakingChange::EpochStakes(
+                        stakes
+                            .iter()
+                            .map(|(pubkey, stake)| Validator {
+                                pubkey: pubkey.into(),
+                                stake: *stake,
+                            })
+                            .collect(),
+                    ),
+                    ConsensusStakingAction::EpochLength { slots } => {
+                        StakingChange::EpochLength(*slots)
+                    }
+                })
+                .collect(),
+            timestamp: cp.timestamp.0,
+            tx_root: cp.tx_root,
+            parent_commit_qc: (&cp.parent_commit_qc).into(),
+            parent_contract_registry_root: cp.parent_contract_registry_root,
+        }
+    }
+}
+
+impl From<&AggregateSignature> for CommitCertificate {
+    fn from(signature: &AggregateSignature) -> Self {
+        CommitCertificate {
+            signature: signature.signature.0.clone(),
+            validators: signature
+                .validators
+                .iter()
+                .map(Into::into)
+                .collect::<Vec<_>>(),
+        }
+    }
+}
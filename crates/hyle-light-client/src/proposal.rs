@@ -0,0 +1,122 @@
+use alloc::{string::String, vec::Vec};
+
+use anyhow::{bail, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::{CommitCertificate, Validator, ValidatorKey};
+
+/// The parts of a consensus proposal its hash is computed from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub struct ProposalHeader {
+    pub slot: u64,
+    pub epoch: u64,
+    pub parent_hash: String,
+    /// Lane owner and data proposal hash of each lane
+    pub cut: Vec<(ValidatorKey, String)>,
+    pub staking_actions: Vec<StakingChange>,
+    pub timestamp: u128,
+    /// Merkle root over the ids of the block's transactions
+    pub tx_root: [u8; 32],
+    /// Commit certificate of the parent proposal, seeding the leader election
+    pub parent_commit_qc: CommitCertificate,
+    /// Merkle root over the contract registry after the parent block
+    pub parent_contract_registry_root: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+pub enum StakingChange {
+    Bond(ValidatorKey),
+    PayFeesForDaDi {
+        lane: ValidatorKey,
+        cumul_size: u64,
+    },
+    /// Borsh-encoded equivocation evidence
+    Slash {
+        #[serde(with = "hex::serde")]
+        evidence: Vec<u8>,
+    },
+    Unbond(ValidatorKey),
+    /// Validators of the next epoch, with the stake their votes are weighted with
+    EpochStakes(Vec<Validator>),
+    /// Number of slots of each epoch, only set by the genesis block
+    EpochLength(u64),
+}
+
+impl ProposalHeader {
+    /// Same as the consensus proposal hash, as signed by validators
+    pub fn hash(&self) -> String {
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.slot.to_le_bytes());
+        hasher.update(self.epoch.to_le_bytes());
+        self.cut.iter().for_each(|(lane, hash)| {
+            hasher.update(&lane.0);
+            hasher.update(hash.as_bytes());
+        });
+        self.staking_actions.iter().for_each(|action| match action {
+            StakingChange::Bond(pubkey) => hasher.update(&pubkey.0),
+            StakingChange::PayFeesForDaDi { lane, cumul_size } => {
+                hasher.update(&lane.0);
+                hasher.update(cumul_size.to_le_bytes())
+            }
+            StakingChange::Slash { evidence } => hasher.update(evidence),
+            StakingChange::Unbond(pubkey) => {
+                hasher.update(b"unbond");
+                hasher.update(&pubkey.0)
+            }
+            StakingChange::EpochStakes(validators) => {
+                hasher.update(b"epoch_stakes");
+                validators.iter().for_each(|validator| {
+                    hasher.update(&validator.pubkey.0);
+                    hasher.update(validator.stake.to_le_bytes());
+                });
+            }
+            StakingChange::EpochLength(slots) => {
+                hasher.update(b"epoch_length");
+                hasher.update(slots.to_le_bytes());
+            }
+        });
+        hasher.update(self.timestamp.to_le_bytes());
+        hasher.update(self.parent_hash.as_bytes());
+        hasher.update(self.tx_root);
+        hasher.update(&self.parent_commit_qc.signature);
+        self.parent_commit_qc
+            .validators
+            .iter()
+            .for_each(|validator| hasher.update(&validator.0));
+        hasher.update(self.parent_contract_registry_root);
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Reads the offender out of borsh-encoded equivocation evidence.
+/// All kinds of evidence start with the offender's signed header:
+/// variant, timestamp, signed data, signature and then the validator public key.
+pub fn slash_offender(evidence: &[u8]) -> Result<&[u8]> {
+    fn take<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if rest.len() < len {
+            bail!("Truncated equivocation evidence");
+        }
+        let (taken, remaining) = rest.split_at(len);
+        *rest = remaining;
+        Ok(taken)
+    }
+    fn take_vec<'a>(rest: &mut &'a [u8]) -> Result<&'a [u8]> {
+        let mut len = [0u8; 4];
+        len.copy_from_slice(take(rest, 4)?);
+        take(rest, u32::from_le_bytes(len) as usize)
+    }
+
+    let mut rest = evidence;
+    let variant = take(&mut rest, 1)?;
+    if !matches!(variant, [0..=2]) {
+        bail!("Unknown equivocation evidence");
+    }
+    take(&mut rest, 16)?;
+    take_vec(&mut rest)?;
+    take_vec(&mut rest)?;
+    take_vec(&mut rest)
+}
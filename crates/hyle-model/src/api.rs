@@ -1,14 +1,17 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use strum::IntoDiscriminant;
+use strum_macros::EnumDiscriminants;
 use utoipa::ToSchema;
 
 use crate::{
-    utils::TimestampMs, BlockHash, BlockHeight, ConsensusProposalHash, ContractName,
-    DataProposalHash, Identity, LaneBytesSize, LaneId, ProgramId, StateCommitment, TimeoutWindow,
-    Transaction, TransactionKind, TxHash, ValidatorPublicKey, Verifier,
+    utils::TimestampMs, BlobTransaction, BlockHash, BlockHeight, ConsensusProposalHash,
+    ContractCodeVersion, ContractName, DataProposalHash, HyleOutput, Identity, LaneBytesSize,
+    LaneId, PendingContractUpgrade, ProgramId, ProofTransaction, RegisterContractEffect,
+    StateCommitment, TimeoutWindow, Transaction, TransactionKind, TransactionStateEvent, TxHash,
+    ValidatorPublicKey, Verifier,
 };
 
 #[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
@@ -58,11 +61,29 @@ pub struct APIStaking {
     /// List of validators that are part of consensus
     pub bonded: Vec<ValidatorPublicKey>,
     pub total_bond: u128,
+    /// Validators that asked to leave consensus, with the height from which their stake can be withdrawn
+    pub unbonding: BTreeMap<ValidatorPublicKey, BlockHeight>,
+    /// Delegators that asked a validator to leave consensus, until they hold a majority of its stake
+    #[serde(default)]
+    pub unbond_requests: BTreeMap<ValidatorPublicKey, BTreeSet<Identity>>,
+    /// Identity operating each validator, which can unbond it on its own
+    #[serde(default)]
+    pub operators: BTreeMap<ValidatorPublicKey, Identity>,
+    /// Validators of the current epoch, with the stake their votes are weighted with
+    #[serde(default)]
+    pub epoch: Option<APIEpochValidators>,
 
     /// Struct to handle fees
     pub fees: APIFees,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+pub struct APIEpochValidators {
+    pub epoch: u64,
+    pub stakes: BTreeMap<ValidatorPublicKey, u128>,
+    pub total_bond: u128,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
 pub struct APIFeesBalance {
     pub balance: i128,
@@ -268,6 +289,36 @@ impl<'de> Deserialize<'de> for APINodeContract {
     }
 }
 
+#[test]
+fn test_tx_status_progress() {
+    let sequenced = TxStatus::Sequenced {
+        block_height: BlockHeight(1),
+    };
+    assert!(!sequenced.is_final());
+    assert!(sequenced.has_reached(TxStatusKind::InDataProposal));
+    assert!(!sequenced.has_reached(TxStatusKind::Settled));
+
+    let failed = TxStatus::Failed {
+        block_height: BlockHeight(2),
+    };
+    assert!(failed.is_final());
+    assert!(failed.has_reached(TxStatusKind::Settled));
+
+    assert!(!TxStatus::Evicted.is_final());
+    assert!(TxStatus::Evicted.has_reached(TxStatusKind::Settled));
+    assert!(TxStatus::Evicted.kind() < TxStatusKind::Pending);
+
+    let json = serde_json::to_value(APITxStatus {
+        tx_hash: TxHash("abc".to_string()),
+        version: 3,
+        status: failed.clone(),
+    })
+    .unwrap();
+    assert_eq!(json["status"]["kind"], "Failed");
+    let status: APITxStatus = serde_json::from_value(json).unwrap();
+    assert_eq!(status.status, failed);
+}
+
 #[test]
 fn test_deserialize_api_node_contract() {
     // Test old format
@@ -305,6 +356,131 @@ fn test_deserialize_api_node_contract() {
     assert_eq!(new_contract.timeout_window, Some(123));
 }
 
+/// A blob transaction and candidate outputs to run through a copy of the node state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct APISettlementSimulationRequest {
+    pub tx: BlobTransaction,
+    /// Candidate outputs, as they would be proven. `tx_ctx` is checked only if set,
+    /// which is only meaningful for an already sequenced transaction.
+    #[serde(default)]
+    pub hyle_outputs: Vec<HyleOutput>,
+    /// Proofs, verified against the current contracts. Their outputs are used along with `hyle_outputs`.
+    #[serde(default)]
+    pub proofs: Vec<ProofTransaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub enum SimulatedSettlementStatus {
+    Success,
+    Failure,
+    /// The transaction would still be waiting for proofs, or for earlier transactions to settle.
+    NotSettled,
+}
+
+/// Predicted outcome of including the transaction (and its proofs) in the next block.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct APISettlementSimulation {
+    pub tx_hash: TxHash,
+    pub status: SimulatedSettlementStatus,
+    /// Last error or settlement message recorded for the transaction, if it didn't settle as success.
+    pub failure_reason: Option<String>,
+    pub events: Vec<TransactionStateEvent>,
+    // Effects of the simulated block, which may include transactions unblocked by this one.
+    pub registered_contracts: BTreeMap<ContractName, RegisterContractEffect>,
+    pub deleted_contracts: Vec<ContractName>,
+    pub updated_states: BTreeMap<ContractName, StateCommitment>,
+    pub updated_program_ids: BTreeMap<ContractName, ProgramId>,
+    pub updated_timeout_windows: BTreeMap<ContractName, TimeoutWindow>,
+    pub scheduled_contract_upgrades: BTreeMap<ContractName, PendingContractUpgrade>,
+}
+
+/// Code history of a contract, as known by the node state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct APIContractHistory {
+    pub contract_name: ContractName,
+    pub pending_upgrade: Option<PendingContractUpgrade>,
+    /// Oldest first. The last entry is the code the contract currently runs.
+    pub history: Vec<ContractCodeVersion>,
+}
+
+/// Where a transaction is in its lifecycle, as seen by the node.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq, EnumDiscriminants)]
+#[strum_discriminants(derive(PartialOrd, Ord))]
+#[strum_discriminants(name(TxStatusKind))]
+#[serde(tag = "kind")]
+pub enum TxStatus {
+    /// Never seen by this node, or forgotten since.
+    Unknown,
+    /// Refused by admission control. The transaction can be submitted again.
+    Rejected {
+        code: String,
+    },
+    /// Dropped from a full mempool. The transaction can be submitted again.
+    Evicted,
+    /// Waiting in the mempool to be put in a data proposal.
+    Pending,
+    InDataProposal {
+        lane_id: LaneId,
+        data_proposal_hash: DataProposalHash,
+    },
+    Sequenced {
+        block_height: BlockHeight,
+    },
+    Settled {
+        block_height: BlockHeight,
+    },
+    Failed {
+        block_height: BlockHeight,
+    },
+    TimedOut {
+        block_height: BlockHeight,
+    },
+    /// Already sequenced in an earlier block.
+    DroppedDuplicate {
+        block_height: BlockHeight,
+    },
+}
+
+impl TxStatus {
+    pub fn kind(&self) -> TxStatusKind {
+        self.discriminant()
+    }
+
+    /// Whether the status can't change anymore.
+    pub fn is_final(&self) -> bool {
+        self.kind() >= TxStatusKind::Settled
+    }
+
+    /// Whether the mempool dropped the transaction. Unlike final statuses, it can be submitted again.
+    pub fn is_dropped(&self) -> bool {
+        matches!(self.kind(), TxStatusKind::Rejected | TxStatusKind::Evicted)
+    }
+
+    /// Whether the transaction got to `kind`, or finished or was dropped before reaching it.
+    pub fn has_reached(&self, kind: TxStatusKind) -> bool {
+        self.is_final() || self.is_dropped() || self.kind() >= kind
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct APITxStatus {
+    pub tx_hash: TxHash,
+    /// Incremented each time the status of the transaction changes, 0 while unknown.
+    pub version: u64,
+    pub status: TxStatus,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct APIContractCodeVersion {
+    // Struct for the contract_history table
+    pub contract_name: String, // Contract name
+    pub verifier: String,      // Verifier used from effective_height on
+    #[serde_as(as = "serde_with::hex::Hex")]
+    pub program_id: Vec<u8>, // Program ID used from effective_height on
+    pub effective_height: BlockHeight, // First block where this code is used
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct APIContractState {
     // Struct for the contract_state table
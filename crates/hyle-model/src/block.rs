@@ -6,6 +6,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use derive_more::derive::Display;
 use serde::{Deserialize, Serialize};
 use utils::TimestampMs;
+use utoipa::ToSchema;
 
 use crate::{staking::*, *};
 
@@ -14,6 +15,8 @@ use crate::{staking::*, *};
 )]
 pub struct Block {
     pub parent_hash: ConsensusProposalHash,
+    /// Commit QC carried by the proposal of this block, see [ConsensusProposal::parent_commit_qc].
+    pub parent_commit_qc: AggregateSignature,
     pub hash: ConsensusProposalHash,
     pub block_height: BlockHeight,
     pub block_timestamp: TimestampMs,
@@ -27,6 +30,14 @@ pub struct Block {
     pub blob_proof_outputs: Vec<HandledBlobProofOutput>,
     pub verified_blobs: Vec<(TxHash, BlobIndex, Option<usize>)>,
     pub new_bounded_validators: Vec<ValidatorPublicKey>,
+    /// Validators slashed in this block for signing conflicting messages
+    pub slashed_validators: Vec<ValidatorPublicKey>,
+    /// Validators that left consensus in this block, see [StakingAction::Unbond]
+    pub unbonded_validators: Vec<ValidatorPublicKey>,
+    /// Stakes of the next epoch, when this block ends an epoch and changes them
+    pub next_epoch_stakes: Option<Vec<(ValidatorPublicKey, u128)>>,
+    /// Number of slots of each epoch, set by the genesis block
+    pub epoch_length: Option<EpochLength>,
     pub staking_actions: Vec<(Identity, StakingAction)>,
     pub registered_contracts:
         BTreeMap<ContractName, (TxHash, RegisterContractEffect, Option<Vec<u8>>)>,
@@ -34,7 +45,17 @@ pub struct Block {
     pub updated_states: BTreeMap<ContractName, StateCommitment>,
     pub updated_program_ids: BTreeMap<ContractName, ProgramId>,
     pub updated_timeout_windows: BTreeMap<ContractName, TimeoutWindow>,
+    /// Upgrades scheduled in this block, see [PendingContractUpgrade].
+    pub scheduled_contract_upgrades: BTreeMap<ContractName, PendingContractUpgrade>,
+    /// Pending upgrades cancelled in this block.
+    pub cancelled_contract_upgrades: Vec<ContractName>,
+    /// New program ID / verifier of contracts registered or upgraded in this block.
+    pub contract_code_versions: BTreeMap<ContractName, ContractCodeVersion>,
     pub transactions_events: BTreeMap<TxHash, Vec<TransactionStateEvent>>,
+    /// Merkle root over the contract registry after this block, see [Contract::registry_leaf].
+    /// Computed by the node state, validators sign it in the proposal of the next slot,
+    /// see [ConsensusProposal::parent_contract_registry_root].
+    pub contract_registry_root: MerkleHash,
 }
 
 impl Block {
@@ -137,6 +158,57 @@ impl SignedBlock {
                 .map(move |tx| (lane_id.clone(), TxId(dp_hash.clone(), tx.hashed()), tx))
         })
     }
+
+    /// Root the consensus proposal commits to, see [ConsensusProposal::tx_root]
+    pub fn tx_merkle_root(&self) -> MerkleHash {
+        let leaves: Vec<_> = self
+            .iter_txs_with_id()
+            .map(|(_, tx_id, _)| tx_merkle_leaf(&tx_id))
+            .collect();
+        merkle_root(&leaves)
+    }
+
+    /// Builds the inclusion proof of the transaction, or None if it isn't in this block.
+    pub fn tx_inclusion_proof(&self, tx_hash: &TxHash) -> Option<TxInclusionProof> {
+        let tx_ids: Vec<_> = self.iter_txs_with_id().map(|(_, tx_id, _)| tx_id).collect();
+        let index = tx_ids.iter().position(|tx_id| &tx_id.1 == tx_hash)?;
+        let leaves: Vec<_> = tx_ids.iter().map(tx_merkle_leaf).collect();
+        Some(TxInclusionProof {
+            tx_id: tx_ids.get(index)?.clone(),
+            proof: MerkleProof::new(&leaves, index)?,
+            consensus_proposal: self.consensus_proposal.clone(),
+        })
+    }
+}
+
+/// Leaf of a transaction in the Merkle tree over a block's transactions, in block order.
+pub fn tx_merkle_leaf(tx_id: &TxId) -> MerkleHash {
+    hash_merkle_leaf(&borsh::to_vec(tx_id).unwrap_or_default())
+}
+
+/// A transaction id along with its inclusion proof in the transaction root of a block.
+/// The proposal is included so that the root can be checked against the block hash.
+#[derive(
+    Debug, Clone, Serialize, Deserialize, ToSchema, BorshSerialize, BorshDeserialize, PartialEq, Eq,
+)]
+pub struct TxInclusionProof {
+    #[schema(value_type = Object)]
+    pub tx_id: TxId,
+    pub proof: MerkleProof,
+    #[schema(value_type = Object)]
+    pub consensus_proposal: ConsensusProposal,
+}
+
+impl TxInclusionProof {
+    /// Checks that the transaction is part of the block with this hash
+    pub fn verify(&self, tx_hash: &TxHash, block_hash: &ConsensusProposalHash) -> bool {
+        &self.tx_id.1 == tx_hash
+            && &self.consensus_proposal.hashed() == block_hash
+            && self.proof.verify(
+                &tx_merkle_leaf(&self.tx_id),
+                &self.consensus_proposal.tx_root,
+            )
+    }
 }
 
 impl Hashed<ConsensusProposalHash> for SignedBlock {
@@ -177,3 +249,50 @@ impl std::default::Default for SignedBlock {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob_tx(data: u8) -> Transaction {
+        BlobTransaction::new(
+            "alice@hydentity",
+            vec![Blob {
+                contract_name: "hyllar".into(),
+                data: BlobData(vec![data]),
+            }],
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_tx_inclusion_proof() {
+        let first_dp = DataProposal::new(None, vec![blob_tx(1), blob_tx(2)]);
+        let second_dp = DataProposal::new(Some(first_dp.hashed()), vec![blob_tx(3)]);
+        let mut block = SignedBlock {
+            data_proposals: vec![
+                (LaneId(ValidatorPublicKey(vec![1])), vec![first_dp]),
+                (LaneId(ValidatorPublicKey(vec![2])), vec![second_dp]),
+            ],
+            ..Default::default()
+        };
+        block.consensus_proposal.tx_root = block.tx_merkle_root();
+        let block_hash = block.hashed();
+
+        for data in 1..=3 {
+            let tx_hash = blob_tx(data).hashed();
+            let proof = block.tx_inclusion_proof(&tx_hash).unwrap();
+            assert!(proof.verify(&tx_hash, &block_hash));
+            assert!(!proof.verify(&blob_tx(4).hashed(), &block_hash));
+            assert!(!proof.verify(&tx_hash, &ConsensusProposalHash::default()));
+        }
+        assert!(block.tx_inclusion_proof(&blob_tx(4).hashed()).is_none());
+
+        // A proof against a proposal committing to another root does not verify
+        let tx_hash = blob_tx(1).hashed();
+        let mut proof = block.tx_inclusion_proof(&tx_hash).unwrap();
+        proof.consensus_proposal.tx_root = EMPTY_MERKLE_ROOT;
+        let other_hash = proof.consensus_proposal.hashed();
+        assert!(!proof.verify(&tx_hash, &other_hash));
+    }
+}
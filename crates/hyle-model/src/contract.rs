@@ -560,6 +560,10 @@ impl Hashed<ProofDataHash> for ProofData {
 pub enum OnchainEffect {
     RegisterContract(RegisterContractEffect),
     DeleteContract(ContractName),
+    /// Schedules an upgrade of a contract owned by the proving contract.
+    UpgradeContract(UpgradeContractEffect),
+    /// Cancels the pending upgrade of a contract owned by the proving contract.
+    CancelContractUpgrade(CancelContractUpgradeEffect),
 }
 
 /// This struct has to be the zkvm committed output. It will be used by
@@ -595,7 +599,8 @@ pub struct HyleOutput {
     pub success: bool,
 
     /// List of other contracts used by the proof. Each state commitment will be checked
-    /// against the contract's state when settling.
+    /// against the contract's state when settling, or against the states it had within
+    /// the node's recent state read window.
     pub state_reads: Vec<(ContractName, StateCommitment)>,
 
     /// Optional - if empty, these won't be checked, but also can't be used inside the program.
@@ -895,6 +900,37 @@ impl ContractAction for UpdateContractProgramIdAction {
     }
 }
 
+#[derive(
+    Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+/// Used as a blob action to cancel a pending program ID upgrade before it takes effect.
+/// The upgrade is only cancelled if it is still the one pending for the contract.
+pub struct CancelContractUpgradeAction {
+    pub contract_name: ContractName,
+    /// Program ID of the pending upgrade.
+    pub program_id: ProgramId,
+    /// Height at which the pending upgrade would take effect.
+    pub effective_height: BlockHeight,
+}
+
+impl ContractAction for CancelContractUpgradeAction {
+    fn as_blob(
+        &self,
+        contract_name: ContractName,
+        caller: Option<BlobIndex>,
+        callees: Option<Vec<BlobIndex>>,
+    ) -> Blob {
+        Blob {
+            contract_name,
+            data: BlobData::from(StructuredBlobData {
+                caller,
+                callees,
+                parameters: self.clone(),
+            }),
+        }
+    }
+}
+
 #[derive(
     Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize,
 )]
@@ -981,6 +1017,74 @@ impl From<RegisterContractAction> for RegisterContractEffect {
     }
 }
 
+/// Output this in your HyleOutput onchain effects, alongside an [UpdateContractProgramIdAction] blob,
+/// to schedule an upgrade of a contract you own. The upgrade takes effect after the node's upgrade delay.
+#[derive(
+    Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+#[cfg_attr(feature = "full", derive(utoipa::ToSchema))]
+pub struct UpgradeContractEffect {
+    /// Name of the contract to upgrade.
+    pub contract_name: ContractName,
+    /// Program ID the contract will use once the upgrade is effective.
+    pub program_id: ProgramId,
+    /// Verifier the contract will use once the upgrade is effective.
+    pub verifier: Verifier,
+}
+
+/// Output this in your HyleOutput onchain effects, alongside a [CancelContractUpgradeAction] blob
+/// with the same fields, to cancel the pending upgrade of a contract you own.
+/// Nothing is cancelled unless these still match the contract's pending upgrade.
+#[derive(
+    Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+#[cfg_attr(feature = "full", derive(utoipa::ToSchema))]
+pub struct CancelContractUpgradeEffect {
+    pub contract_name: ContractName,
+    /// Program ID of the pending upgrade.
+    pub program_id: ProgramId,
+    /// Height at which the pending upgrade would take effect.
+    pub effective_height: BlockHeight,
+}
+
+impl From<CancelContractUpgradeAction> for CancelContractUpgradeEffect {
+    fn from(action: CancelContractUpgradeAction) -> Self {
+        CancelContractUpgradeEffect {
+            contract_name: action.contract_name,
+            program_id: action.program_id,
+            effective_height: action.effective_height,
+        }
+    }
+}
+
+#[cfg(feature = "full")]
+impl Hashed<TxHash> for CancelContractUpgradeEffect {
+    fn hashed(&self) -> TxHash {
+        use sha3::{Digest, Sha3_256};
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.program_id.0.clone());
+        hasher.update(self.effective_height.0.to_le_bytes());
+        hasher.update(self.contract_name.0.clone());
+        let hash_bytes = hasher.finalize();
+        TxHash(hex::encode(hash_bytes))
+    }
+}
+
+#[cfg(feature = "full")]
+impl Hashed<TxHash> for UpgradeContractEffect {
+    fn hashed(&self) -> TxHash {
+        use sha3::{Digest, Sha3_256};
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.verifier.0.clone());
+        hasher.update(self.program_id.0.clone());
+        hasher.update(self.contract_name.0.clone());
+        let hash_bytes = hasher.finalize();
+        TxHash(hex::encode(hash_bytes))
+    }
+}
+
 #[cfg(feature = "full")]
 impl Hashed<TxHash> for RegisterContractEffect {
     fn hashed(&self) -> TxHash {
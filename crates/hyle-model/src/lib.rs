@@ -1,6 +1,8 @@
 #[cfg(feature = "full")]
 mod block;
 #[cfg(feature = "full")]
+mod merkle;
+#[cfg(feature = "full")]
 mod node;
 #[cfg(feature = "full")]
 mod transaction;
@@ -11,6 +13,8 @@ pub mod verifiers;
 #[cfg(feature = "full")]
 pub use block::*;
 #[cfg(feature = "full")]
+pub use merkle::*;
+#[cfg(feature = "full")]
 pub use node::*;
 #[cfg(feature = "full")]
 pub use transaction::*;
@@ -0,0 +1,261 @@
+//! Binary Merkle tree over a list of leaves, with inclusion proofs.
+//!
+//! Leaves and inner nodes are domain-separated (`0x00` / `0x01` prefix) so that an inner node
+//! can't be passed off as a leaf. When a level has an odd number of nodes, the last one is
+//! promoted to the next level as is (it is not duplicated).
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use utoipa::ToSchema;
+
+pub type MerkleHash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Root of an empty tree.
+pub const EMPTY_MERKLE_ROOT: MerkleHash = [0; 32];
+
+pub fn hash_merkle_leaf(data: &[u8]) -> MerkleHash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+fn hash_merkle_node(left: &MerkleHash, right: &MerkleHash) -> MerkleHash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[MerkleHash]) -> Vec<MerkleHash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_merkle_node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Computes the root over already hashed leaves (see [hash_merkle_leaf]).
+pub fn merkle_root(leaves: &[MerkleHash]) -> MerkleHash {
+    if leaves.is_empty() {
+        return EMPTY_MERKLE_ROOT;
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied().unwrap_or(EMPTY_MERKLE_ROOT)
+}
+
+/// Merkle tree keeping every level, so that changing a leaf only rehashes its path to the root.
+/// Builds the same root and proofs as [merkle_root] and [MerkleProof::new].
+#[derive(Debug, Clone, Default)]
+pub struct MerkleTree {
+    /// `levels[0]` holds the leaves, the last level holds the root.
+    levels: Vec<Vec<MerkleHash>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<MerkleHash>) -> Self {
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = next_level(level);
+            levels.push(next);
+        }
+        MerkleTree { levels }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.first().map_or(0, Vec::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn root(&self) -> MerkleHash {
+        match self.levels.last() {
+            Some(level) if level.len() == 1 => level[0],
+            _ => EMPTY_MERKLE_ROOT,
+        }
+    }
+
+    /// Replaces the leaf at `index` and updates its path to the root. Returns false if out of bounds.
+    pub fn set_leaf(&mut self, index: usize, leaf: MerkleHash) -> bool {
+        let Some(slot) = self.levels.first_mut().and_then(|l| l.get_mut(index)) else {
+            return false;
+        };
+        *slot = leaf;
+        let mut position = index;
+        for depth in 1..self.levels.len() {
+            let level = &self.levels[depth - 1];
+            let left = position & !1;
+            let hash = match level.get(left + 1) {
+                Some(right) => hash_merkle_node(&level[left], right),
+                None => level[left],
+            };
+            position /= 2;
+            self.levels[depth][position] = hash;
+        }
+        true
+    }
+
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+        let mut position = index;
+        let mut siblings = vec![];
+        for level in self.levels.iter().take(self.levels.len() - 1) {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+        Some(MerkleProof {
+            index: index as u64,
+            leaf_count: self.len() as u64,
+            siblings,
+        })
+    }
+}
+
+/// Proof that a leaf is at position `index` in a tree of `leaf_count` leaves.
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Serialize,
+    Deserialize,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    ToSchema,
+)]
+pub struct MerkleProof {
+    pub index: u64,
+    pub leaf_count: u64,
+    /// Sibling hashes, from the leaf level up to the root.
+    #[schema(value_type = Vec<Vec<u8>>)]
+    pub siblings: Vec<MerkleHash>,
+}
+
+impl MerkleProof {
+    /// Builds the proof for the leaf at `index`, or None if out of bounds.
+    pub fn new(leaves: &[MerkleHash], index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut siblings = vec![];
+        let mut level = leaves.to_vec();
+        let mut position = index;
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        Some(MerkleProof {
+            index: index as u64,
+            leaf_count: leaves.len() as u64,
+            siblings,
+        })
+    }
+
+    /// Recomputes the root from the hashed leaf, or None if the proof is malformed.
+    pub fn compute_root(&self, leaf: &MerkleHash) -> Option<MerkleHash> {
+        if self.index >= self.leaf_count {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut hash = *leaf;
+        let mut position = self.index;
+        let mut width = self.leaf_count;
+        while width > 1 {
+            let sibling_position = position ^ 1;
+            if sibling_position < width {
+                let sibling = siblings.next()?;
+                hash = if position % 2 == 0 {
+                    hash_merkle_node(&hash, sibling)
+                } else {
+                    hash_merkle_node(sibling, &hash)
+                };
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+        if siblings.next().is_some() {
+            return None;
+        }
+        Some(hash)
+    }
+
+    pub fn verify(&self, leaf: &MerkleHash, root: &MerkleHash) -> bool {
+        self.compute_root(leaf).as_ref() == Some(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(n: u8) -> Vec<MerkleHash> {
+        (0..n).map(|i| hash_merkle_leaf(&[i])).collect()
+    }
+
+    #[test]
+    fn test_merkle_proofs() {
+        for n in 1..=9 {
+            let leaves = leaves(n);
+            let root = merkle_root(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = MerkleProof::new(&leaves, i).unwrap();
+                assert!(proof.verify(leaf, &root), "leaf {i} of {n}");
+                assert!(!proof.verify(&hash_merkle_leaf(b"other"), &root));
+            }
+            assert!(MerkleProof::new(&leaves, n as usize).is_none());
+        }
+    }
+
+    #[test]
+    fn test_merkle_proof_tampered() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let mut proof = MerkleProof::new(&leaves, 2).unwrap();
+        proof.index = 3;
+        assert!(!proof.verify(&leaves[2], &root));
+
+        let mut proof = MerkleProof::new(&leaves, 2).unwrap();
+        proof.siblings.push([0; 32]);
+        assert!(!proof.verify(&leaves[2], &root));
+
+        assert_eq!(merkle_root(&[]), EMPTY_MERKLE_ROOT);
+    }
+
+    #[test]
+    fn test_merkle_tree_set_leaf() {
+        assert_eq!(MerkleTree::new(vec![]).root(), EMPTY_MERKLE_ROOT);
+        for n in 1..=9 {
+            let mut leaves = leaves(n);
+            let mut tree = MerkleTree::new(leaves.clone());
+            assert_eq!(tree.root(), merkle_root(&leaves));
+            for i in 0..leaves.len() {
+                leaves[i] = hash_merkle_leaf(&[100 + i as u8]);
+                assert!(tree.set_leaf(i, leaves[i]));
+                assert_eq!(tree.root(), merkle_root(&leaves), "leaf {i} of {n}");
+                assert_eq!(tree.proof(i), MerkleProof::new(&leaves, i));
+            }
+            assert!(!tree.set_leaf(n as usize, [0; 32]));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{collections::BTreeMap, fmt::Display};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
@@ -10,14 +10,45 @@ use crate::{staking::*, *};
 
 pub type Slot = u64;
 pub type View = u64;
+/// Period during which the validator set doesn't change, bonds and unbonds being applied at its end
+pub type Epoch = u64;
+
+/// Number of slots of each epoch, set once for the network by its genesis block
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq,
+)]
+pub struct EpochLength(pub u64);
+
+impl Default for EpochLength {
+    fn default() -> Self {
+        EpochLength(1)
+    }
+}
+
+impl EpochLength {
+    pub fn epoch_of(&self, slot: Slot) -> Epoch {
+        slot / self.0.max(1)
+    }
+
+    /// Whether the proposal of this slot is the last one of its epoch
+    pub fn ends_epoch(&self, slot: Slot) -> bool {
+        self.epoch_of(slot + 1) > self.epoch_of(slot)
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize, ToSchema, Debug)]
 pub struct ConsensusInfo {
     pub slot: Slot,
     pub view: View,
+    pub epoch: Epoch,
     pub round_leader: ValidatorPublicKey,
     pub last_timestamp: TimestampMs,
+    /// Validators of the current epoch
     pub validators: Vec<ValidatorPublicKey>,
+    /// Stake of the validators, frozen at the start of the epoch
+    pub stakes: BTreeMap<ValidatorPublicKey, u128>,
+    /// Sum of the stakes of the epoch, the voting power quorums are computed on
+    pub total_bond: u128,
 }
 
 // -----------------------------
@@ -49,34 +80,47 @@ impl Display for SignedByValidator<ValidatorCandidacy> {
 }
 
 #[cfg(feature = "sqlx")]
-impl sqlx::Type<sqlx::Postgres> for ConsensusProposalHash {
-    fn type_info() -> sqlx::postgres::PgTypeInfo {
-        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+impl<DB: sqlx::Database> sqlx::Type<DB> for ConsensusProposalHash
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
     }
 }
 
 #[cfg(feature = "sqlx")]
-impl sqlx::Encode<'_, sqlx::Postgres> for ConsensusProposalHash {
+impl<'q, DB: sqlx::Database> sqlx::Encode<'q, DB> for ConsensusProposalHash
+where
+    String: sqlx::Encode<'q, DB>,
+{
     fn encode_by_ref(
         &self,
-        buf: &mut sqlx::postgres::PgArgumentBuffer,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
     ) -> std::result::Result<
         sqlx::encode::IsNull,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        <String as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&self.0, buf)
+        <String as sqlx::Encode<'q, DB>>::encode_by_ref(&self.0, buf)
     }
 }
 
 #[cfg(feature = "sqlx")]
-impl<'r> sqlx::Decode<'r, sqlx::Postgres> for ConsensusProposalHash {
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for ConsensusProposalHash
+where
+    String: sqlx::Decode<'r, DB>,
+{
     fn decode(
-        value: sqlx::postgres::PgValueRef<'r>,
+        value: <DB as sqlx::Database>::ValueRef<'r>,
     ) -> std::result::Result<
         ConsensusProposalHash,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        let inner = <String as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        let inner = <String as sqlx::Decode<'r, DB>>::decode(value)?;
         Ok(ConsensusProposalHash(inner))
     }
 }
@@ -96,10 +140,26 @@ impl<'r> sqlx::Decode<'r, sqlx::Postgres> for ConsensusProposalHash {
 )]
 pub struct ConsensusProposal {
     pub slot: Slot,
+    /// Part of the hash and of the borsh layout since P2P protocol version 3:
+    /// proposals of older nodes can't be decoded, their networks have to restart from a new genesis.
+    pub epoch: Epoch,
     pub parent_hash: ConsensusProposalHash,
     pub cut: Cut,
     pub staking_actions: Vec<ConsensusStakingAction>,
     pub timestamp: TimestampMs,
+    /// Merkle root over the ids of the block's transactions, see [SignedBlock::tx_merkle_root]
+    /// Part of the hash and of the borsh layout since P2P protocol version 4, which is a hard fork.
+    pub tx_root: MerkleHash,
+    /// Commit QC of the parent proposal, as in the ticket the proposal is prepared with,
+    /// or empty when proposed after a timeout without one. It seeds the leader election
+    /// of the next slot, every node holding it once the proposal is committed.
+    /// Part of the hash and of the borsh layout since P2P protocol version 5, which is a hard fork.
+    pub parent_commit_qc: AggregateSignature,
+    /// Contract registry root after the parent block, see [Block::contract_registry_root]:
+    /// proofs against the registry at a height are checked with the proposal of the next slot.
+    /// Empty for the genesis proposal, which has no parent.
+    /// Part of the hash and of the borsh layout since P2P protocol version 6, which is a hard fork.
+    pub parent_contract_registry_root: MerkleHash,
 }
 
 /// This is the hash of the proposal, signed by validators
@@ -108,6 +168,7 @@ impl Hashed<ConsensusProposalHash> for ConsensusProposal {
     fn hashed(&self) -> ConsensusProposalHash {
         let mut hasher = Sha3_256::new();
         hasher.update(self.slot.to_le_bytes());
+        hasher.update(self.epoch.to_le_bytes());
         self.cut.iter().for_each(|(lane_id, hash, _, _)| {
             hasher.update(&lane_id.0 .0);
             hasher.update(hash.0.as_bytes());
@@ -123,9 +184,35 @@ impl Hashed<ConsensusProposalHash> for ConsensusProposal {
                 hasher.update(&lane_id.0 .0);
                 hasher.update(cumul_size.0.to_le_bytes())
             }
+            ConsensusStakingAction::Slash { evidence } => {
+                hasher.update(borsh::to_vec(evidence).unwrap_or_default())
+            }
+            ConsensusStakingAction::Unbond { validator } => {
+                // Distinguishes leaving from joining, both hashing a public key
+                hasher.update(b"unbond");
+                hasher.update(&validator.0)
+            }
+            ConsensusStakingAction::EpochStakes { stakes } => {
+                hasher.update(b"epoch_stakes");
+                stakes.iter().for_each(|(validator, stake)| {
+                    hasher.update(&validator.0);
+                    hasher.update(stake.to_le_bytes());
+                });
+            }
+            ConsensusStakingAction::EpochLength { slots } => {
+                hasher.update(b"epoch_length");
+                hasher.update(slots.to_le_bytes());
+            }
         });
         hasher.update(self.timestamp.0.to_le_bytes());
         hasher.update(self.parent_hash.0.as_bytes());
+        hasher.update(self.tx_root);
+        hasher.update(&self.parent_commit_qc.signature.0);
+        self.parent_commit_qc
+            .validators
+            .iter()
+            .for_each(|validator| hasher.update(&validator.0));
+        hasher.update(self.parent_contract_registry_root);
         ConsensusProposalHash(hex::encode(hasher.finalize()))
     }
 }
@@ -182,6 +269,29 @@ pub enum ConsensusStakingAction {
         lane_id: LaneId,
         cumul_size: LaneBytesSize,
     },
+
+    /// Slashing a validator that signed conflicting messages
+    Slash { evidence: Box<EquivocationEvidence> },
+
+    /// Removing a validator that asked to leave consensus
+    Unbond { validator: ValidatorPublicKey },
+
+    /// Validators of the next epoch with the stake their votes are weighted with.
+    /// Part of the proposal ending an epoch, when they differ from the current epoch.
+    EpochStakes {
+        stakes: Vec<(ValidatorPublicKey, u128)>,
+    },
+
+    /// Number of slots of each epoch, only set by the genesis block
+    EpochLength { slots: u64 },
+}
+
+impl From<EquivocationEvidence> for ConsensusStakingAction {
+    fn from(evidence: EquivocationEvidence) -> Self {
+        ConsensusStakingAction::Slash {
+            evidence: Box::new(evidence),
+        }
+    }
 }
 
 impl From<SignedByValidator<ValidatorCandidacy>> for ConsensusStakingAction {
@@ -200,10 +310,14 @@ mod tests {
         use super::*;
         let proposal = ConsensusProposal {
             slot: 1,
+            epoch: 0,
             cut: Cut::default(),
             staking_actions: vec![],
             timestamp: TimestampMs(1),
             parent_hash: ConsensusProposalHash("".to_string()),
+            tx_root: EMPTY_MERKLE_ROOT,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
         };
         let hash = proposal.hashed();
         assert_eq!(hash.0.len(), 64);
@@ -213,6 +327,7 @@ mod tests {
         use super::*;
         let mut a = ConsensusProposal {
             slot: 1,
+            epoch: 0,
             cut: vec![(
                 LaneId(ValidatorPublicKey(vec![1])),
                 DataProposalHash("propA".to_string()),
@@ -231,9 +346,13 @@ mod tests {
             .into()],
             timestamp: TimestampMs(1),
             parent_hash: ConsensusProposalHash("parent".to_string()),
+            tx_root: EMPTY_MERKLE_ROOT,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
         };
         let mut b = ConsensusProposal {
             slot: 1,
+            epoch: 0,
             cut: vec![(
                 LaneId(ValidatorPublicKey(vec![1])),
                 DataProposalHash("propA".to_string()),
@@ -255,6 +374,9 @@ mod tests {
             .into()],
             timestamp: TimestampMs(1),
             parent_hash: ConsensusProposalHash("parent".to_string()),
+            tx_root: EMPTY_MERKLE_ROOT,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
         };
         assert_ne!(a.hashed(), b.hashed());
         if let ConsensusStakingAction::Bond { candidate: a } =
@@ -282,9 +404,29 @@ mod tests {
         b.slot = 2;
         assert_eq!(a.hashed(), b.hashed());
 
+        a.epoch = 1;
+        assert_ne!(a.hashed(), b.hashed());
+        b.epoch = 1;
+        assert_eq!(a.hashed(), b.hashed());
+
         a.parent_hash = ConsensusProposalHash("different".to_string());
         assert_ne!(a.hashed(), b.hashed());
         b.parent_hash = ConsensusProposalHash("different".to_string());
         assert_eq!(a.hashed(), b.hashed());
+
+        a.tx_root = [1; 32];
+        assert_ne!(a.hashed(), b.hashed());
+        b.tx_root = [1; 32];
+        assert_eq!(a.hashed(), b.hashed());
+
+        a.parent_commit_qc.signature = Signature(vec![1, 2, 3]);
+        assert_ne!(a.hashed(), b.hashed());
+        b.parent_commit_qc.signature = Signature(vec![1, 2, 3]);
+        assert_eq!(a.hashed(), b.hashed());
+
+        a.parent_contract_registry_root = [1; 32];
+        assert_ne!(a.hashed(), b.hashed());
+        b.parent_contract_registry_root = [1; 32];
+        assert_eq!(a.hashed(), b.hashed());
     }
 }
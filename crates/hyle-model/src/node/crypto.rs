@@ -75,6 +75,40 @@ pub struct AggregateSignature {
     pub validators: Vec<ValidatorPublicKey>,
 }
 
+/// Data covered by the signature of a p2p message header.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+pub struct HeaderSignableData(pub Vec<u8>);
+
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+pub struct MsgHeader {
+    pub timestamp: u128,
+    pub hash: HeaderSignableData,
+}
+
 impl From<BlstSignature> for Signature {
     fn from(sig: BlstSignature) -> Self {
         Signature(sig.compress().as_slice().to_vec())
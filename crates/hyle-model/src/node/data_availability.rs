@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
@@ -23,6 +23,172 @@ pub struct Contract {
     pub timeout_window: TimeoutWindow,
 }
 
+impl Contract {
+    /// Leaf of this contract in the contract registry Merkle tree: the hash of its borsh encoding.
+    /// Leaves are ordered by contract name.
+    pub fn registry_leaf(&self) -> MerkleHash {
+        hash_merkle_leaf(&borsh::to_vec(self).unwrap_or_default())
+    }
+}
+
+/// Program ID and verifier a contract used from `effective_height` on, until the next version.
+#[derive(
+    Debug, Clone, Serialize, Deserialize, ToSchema, BorshSerialize, BorshDeserialize, PartialEq, Eq,
+)]
+pub struct ContractCodeVersion {
+    pub program_id: ProgramId,
+    pub verifier: Verifier,
+    pub effective_height: BlockHeight,
+}
+
+/// A program ID / verifier change recorded by the node state, that isn't applied yet.
+/// It can be cancelled by the owner of the contract until `effective_height`.
+#[derive(
+    Debug, Clone, Serialize, Deserialize, ToSchema, BorshSerialize, BorshDeserialize, PartialEq, Eq,
+)]
+pub struct PendingContractUpgrade {
+    pub program_id: ProgramId,
+    pub verifier: Verifier,
+    pub scheduled_at: BlockHeight,
+    pub effective_height: BlockHeight,
+}
+
+/// A contract entry along with its inclusion proof in the contract registry root of a block.
+///
+/// The root is signed by validators in the proposal of the next slot, see
+/// [ConsensusProposal::parent_contract_registry_root]: clients check the proof against that
+/// proposal once they verified its commit certificate, e.g. with the light client.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, BorshSerialize, BorshDeserialize)]
+pub struct ContractInclusionProof {
+    pub block_height: BlockHeight,
+    #[schema(value_type = Vec<u8>)]
+    pub contract_registry_root: MerkleHash,
+    pub contract: Contract,
+    pub proof: MerkleProof,
+}
+
+/// Leaves of the contract registry Merkle tree, ordered by contract name.
+fn contract_registry_leaves(
+    contracts: &HashMap<ContractName, Contract>,
+) -> Vec<(&ContractName, MerkleHash)> {
+    contracts
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(name, contract)| (name, contract.registry_leaf()))
+        .collect()
+}
+
+pub fn contract_registry_root(contracts: &HashMap<ContractName, Contract>) -> MerkleHash {
+    let leaves: Vec<_> = contract_registry_leaves(contracts)
+        .into_iter()
+        .map(|(_, leaf)| leaf)
+        .collect();
+    merkle_root(&leaves)
+}
+
+/// The contract registry Merkle tree, kept alongside the registry and updated only where it changed.
+#[derive(Debug, Clone, Default)]
+pub struct ContractRegistryTree {
+    /// Contract names, ordered like the leaves.
+    names: Vec<ContractName>,
+    tree: MerkleTree,
+}
+
+impl ContractRegistryTree {
+    pub fn new(contracts: &HashMap<ContractName, Contract>) -> Self {
+        let (names, leaves): (Vec<_>, Vec<_>) = contract_registry_leaves(contracts)
+            .into_iter()
+            .map(|(name, leaf)| (name.clone(), leaf))
+            .unzip();
+        ContractRegistryTree {
+            names,
+            tree: MerkleTree::new(leaves),
+        }
+    }
+
+    pub fn root(&self) -> MerkleHash {
+        self.tree.root()
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Updates the leaves of the `changed` contracts. Registering or deleting a contract shifts
+    /// the leaves after it, in which case the tree is rebuilt.
+    pub fn update<'a>(
+        &mut self,
+        contracts: &HashMap<ContractName, Contract>,
+        changed: impl IntoIterator<Item = &'a ContractName>,
+    ) {
+        if self.names.len() != contracts.len() {
+            *self = Self::new(contracts);
+            return;
+        }
+        for name in changed {
+            let (Ok(index), Some(contract)) = (self.names.binary_search(name), contracts.get(name))
+            else {
+                *self = Self::new(contracts);
+                return;
+            };
+            self.tree.set_leaf(index, contract.registry_leaf());
+        }
+    }
+
+    /// Builds the inclusion proof of `contract_name`, or None if it isn't registered.
+    pub fn proof(
+        &self,
+        contracts: &HashMap<ContractName, Contract>,
+        contract_name: &ContractName,
+        block_height: BlockHeight,
+    ) -> Option<ContractInclusionProof> {
+        let contract = contracts.get(contract_name)?.clone();
+        let index = self.names.binary_search(contract_name).ok()?;
+        Some(ContractInclusionProof {
+            block_height,
+            contract_registry_root: self.root(),
+            contract,
+            proof: self.tree.proof(index)?,
+        })
+    }
+}
+
+impl ContractInclusionProof {
+    /// Builds the inclusion proof of `contract_name` in the registry, or None if it isn't registered.
+    pub fn new(
+        contracts: &HashMap<ContractName, Contract>,
+        contract_name: &ContractName,
+        block_height: BlockHeight,
+    ) -> Option<Self> {
+        let contract = contracts.get(contract_name)?.clone();
+        let (names, leaves): (Vec<_>, Vec<_>) =
+            contract_registry_leaves(contracts).into_iter().unzip();
+        let index = names.iter().position(|name| *name == contract_name)?;
+        Some(ContractInclusionProof {
+            block_height,
+            contract_registry_root: merkle_root(&leaves),
+            contract,
+            proof: MerkleProof::new(&leaves, index)?,
+        })
+    }
+
+    /// Checks the proof against `header`, the proposal of the slot after `block_height`.
+    /// Only the registry root is checked here: the caller must have verified that `header`
+    /// was committed by validators.
+    pub fn verify(&self, header: &ConsensusProposal) -> bool {
+        header.slot == self.block_height.0 + 1
+            && header.parent_contract_registry_root == self.contract_registry_root
+            && self
+                .proof
+                .verify(&self.contract.registry_leaf(), &self.contract_registry_root)
+    }
+}
+
 #[derive(
     Default,
     Debug,
@@ -129,6 +295,8 @@ impl Hashed<HyleOutputHash> for HyleOutput {
         self.onchain_effects.iter().for_each(|c| match c {
             OnchainEffect::RegisterContract(c) => hasher.update(contract::Hashed::hashed(c).0),
             OnchainEffect::DeleteContract(cn) => hasher.update(cn.0.as_bytes()),
+            OnchainEffect::UpgradeContract(c) => hasher.update(contract::Hashed::hashed(c).0),
+            OnchainEffect::CancelContractUpgrade(c) => hasher.update(contract::Hashed::hashed(c).0),
         });
         hasher.update(&self.program_outputs);
         HyleOutputHash(hasher.finalize().to_vec())
@@ -0,0 +1,376 @@
+use anyhow::{bail, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// A Prepare message, as signed by its leader in the p2p message header.
+/// The header signs `borsh((proposal hash, ticket, view))`.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+pub struct SignedPrepare {
+    pub header: SignedByValidator<MsgHeader>,
+    pub proposal: ConsensusProposal,
+    /// Borsh-encoded ticket of the Prepare message
+    pub ticket: Vec<u8>,
+    pub view: View,
+}
+
+impl SignedPrepare {
+    /// The data the header must sign for this Prepare
+    pub fn signable_data(&self) -> HeaderSignableData {
+        let mut data = borsh::to_vec(&self.proposal.hashed()).unwrap_or_default();
+        data.extend_from_slice(&self.ticket);
+        data.extend_from_slice(&self.view.to_le_bytes());
+        HeaderSignableData(data)
+    }
+}
+
+/// A data proposal, as disseminated by the owner of its lane.
+/// The header signs the data proposal hash, which is recomputed from its parent and transaction hashes.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+pub struct SignedDataProposal {
+    pub header: SignedByValidator<MsgHeader>,
+    pub parent_data_proposal_hash: Option<DataProposalHash>,
+    pub tx_hashes: Vec<TxHash>,
+}
+
+impl SignedDataProposal {
+    pub fn new(header: SignedByValidator<MsgHeader>, data_proposal: &DataProposal) -> Self {
+        Self {
+            header,
+            parent_data_proposal_hash: data_proposal.parent_data_proposal_hash.clone(),
+            tx_hashes: data_proposal.txs.iter().map(|tx| tx.hashed()).collect(),
+        }
+    }
+
+    pub fn hashed(&self) -> DataProposalHash {
+        DataProposal::hash_from_parts(
+            self.parent_data_proposal_hash.as_ref(),
+            self.tx_hashes.iter().cloned(),
+        )
+    }
+
+    /// The data the header must sign for this data proposal
+    pub fn signable_data(&self) -> HeaderSignableData {
+        HeaderSignableData(self.hashed().0.into_bytes())
+    }
+}
+
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+pub enum VoteKind {
+    PrepareVote,
+    ConfirmAck,
+}
+
+/// A follower vote, as signed by its voter in the p2p message header.
+/// The header signs `borsh((kind, proposal hash, slot, view))`, which the vote itself does not bind.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+pub struct SignedVote {
+    pub header: SignedByValidator<MsgHeader>,
+    pub kind: VoteKind,
+    pub proposal_hash: ConsensusProposalHash,
+    pub slot: Slot,
+    pub view: View,
+}
+
+impl SignedVote {
+    /// The data the header of a vote message must sign
+    pub fn signable_data_of(
+        kind: &VoteKind,
+        proposal_hash: &ConsensusProposalHash,
+        slot: Slot,
+        view: View,
+    ) -> HeaderSignableData {
+        HeaderSignableData(borsh::to_vec(&(kind, proposal_hash, slot, view)).unwrap_or_default())
+    }
+
+    /// The data the header must sign for this vote
+    pub fn signable_data(&self) -> HeaderSignableData {
+        Self::signable_data_of(&self.kind, &self.proposal_hash, self.slot, self.view)
+    }
+}
+
+/// Proof that a validator signed two conflicting messages.
+/// Header signatures are checked by the node, as this crate has no signature verification.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Ord,
+    PartialOrd,
+)]
+#[allow(
+    clippy::large_enum_variant,
+    reason = "evidence is boxed wherever it is carried around"
+)]
+pub enum EquivocationEvidence {
+    /// A leader prepared two different proposals for the same slot and view
+    Prepare(SignedPrepare, SignedPrepare),
+    /// A lane owner disseminated two different data proposals on top of the same parent
+    DataProposal(SignedDataProposal, SignedDataProposal),
+    /// A follower voted for two different proposals in the same slot and view
+    Vote(SignedVote, SignedVote),
+}
+
+impl EquivocationEvidence {
+    pub fn offender(&self) -> &ValidatorPublicKey {
+        match self {
+            EquivocationEvidence::Prepare(a, _) => &a.header.signature.validator,
+            EquivocationEvidence::DataProposal(a, _) => &a.header.signature.validator,
+            EquivocationEvidence::Vote(a, _) => &a.header.signature.validator,
+        }
+    }
+
+    /// The two signed headers, to be checked against their signatures
+    pub fn headers(&self) -> [&SignedByValidator<MsgHeader>; 2] {
+        match self {
+            EquivocationEvidence::Prepare(a, b) => [&a.header, &b.header],
+            EquivocationEvidence::DataProposal(a, b) => [&a.header, &b.header],
+            EquivocationEvidence::Vote(a, b) => [&a.header, &b.header],
+        }
+    }
+
+    /// Checks that both messages come from the same validator, match what their headers sign, and conflict.
+    pub fn check_conflict(&self) -> Result<()> {
+        let [a, b] = self.headers();
+        if a.signature.validator != b.signature.validator {
+            bail!("Evidence messages are signed by different validators");
+        }
+        match self {
+            EquivocationEvidence::Prepare(a, b) => {
+                for prepare in [a, b] {
+                    if prepare.header.msg.hash != prepare.signable_data() {
+                        bail!("Prepare does not match its signed header");
+                    }
+                }
+                if a.proposal.slot != b.proposal.slot || a.view != b.view {
+                    bail!(
+                        "Prepares are for different rounds: slot {} view {} and slot {} view {}",
+                        a.proposal.slot,
+                        a.view,
+                        b.proposal.slot,
+                        b.view
+                    );
+                }
+                if a.proposal.hashed() == b.proposal.hashed() {
+                    bail!("Prepares are for the same proposal");
+                }
+            }
+            EquivocationEvidence::DataProposal(a, b) => {
+                for data_proposal in [a, b] {
+                    if data_proposal.header.msg.hash != data_proposal.signable_data() {
+                        bail!("Data proposal does not match its signed header");
+                    }
+                }
+                if a.parent_data_proposal_hash != b.parent_data_proposal_hash {
+                    bail!("Data proposals have different parents");
+                }
+                if a.hashed() == b.hashed() {
+                    bail!("Data proposals are the same");
+                }
+            }
+            EquivocationEvidence::Vote(a, b) => {
+                for vote in [a, b] {
+                    if vote.header.msg.hash != vote.signable_data() {
+                        bail!("Vote does not match its signed header");
+                    }
+                }
+                if a.kind != b.kind || a.slot != b.slot || a.view != b.view {
+                    bail!(
+                        "Votes are for different rounds: {:?} slot {} view {} and {:?} slot {} view {}",
+                        a.kind,
+                        a.slot,
+                        a.view,
+                        b.kind,
+                        b.slot,
+                        b.view
+                    );
+                }
+                if a.proposal_hash == b.proposal_hash {
+                    bail!("Votes are for the same proposal");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(validator: u8, hash: HeaderSignableData) -> SignedByValidator<MsgHeader> {
+        SignedByValidator {
+            msg: MsgHeader { timestamp: 0, hash },
+            signature: ValidatorSignature {
+                signature: Signature(vec![]),
+                validator: ValidatorPublicKey(vec![validator]),
+            },
+        }
+    }
+
+    fn prepare(validator: u8, slot: Slot, view: View, timestamp: u128) -> SignedPrepare {
+        let mut prepare = SignedPrepare {
+            header: header(validator, HeaderSignableData(vec![])),
+            proposal: ConsensusProposal {
+                slot,
+                timestamp: utils::TimestampMs(timestamp),
+                ..ConsensusProposal::default()
+            },
+            ticket: vec![1, 2, 3],
+            view,
+        };
+        prepare.header.msg.hash = prepare.signable_data();
+        prepare
+    }
+
+    fn data_proposal(validator: u8, parent: Option<&str>, txs: &[&str]) -> SignedDataProposal {
+        let mut data_proposal = SignedDataProposal {
+            header: header(validator, HeaderSignableData(vec![])),
+            parent_data_proposal_hash: parent.map(|p| DataProposalHash(p.to_string())),
+            tx_hashes: txs.iter().map(|tx| TxHash(tx.to_string())).collect(),
+        };
+        data_proposal.header.msg.hash = data_proposal.signable_data();
+        data_proposal
+    }
+
+    #[test]
+    fn test_prepare_conflict() {
+        let evidence = EquivocationEvidence::Prepare(prepare(1, 3, 0, 1), prepare(1, 3, 0, 2));
+        assert!(evidence.check_conflict().is_ok());
+        assert_eq!(evidence.offender(), &ValidatorPublicKey(vec![1]));
+
+        // Same proposal
+        let evidence = EquivocationEvidence::Prepare(prepare(1, 3, 0, 1), prepare(1, 3, 0, 1));
+        assert!(evidence.check_conflict().is_err());
+        // Different views
+        let evidence = EquivocationEvidence::Prepare(prepare(1, 3, 0, 1), prepare(1, 3, 1, 2));
+        assert!(evidence.check_conflict().is_err());
+        // Different validators
+        let evidence = EquivocationEvidence::Prepare(prepare(1, 3, 0, 1), prepare(2, 3, 0, 2));
+        assert!(evidence.check_conflict().is_err());
+        // Header signing something else
+        let mut tampered = prepare(1, 3, 0, 2);
+        tampered.proposal.timestamp = utils::TimestampMs(3);
+        let evidence = EquivocationEvidence::Prepare(prepare(1, 3, 0, 1), tampered);
+        assert!(evidence.check_conflict().is_err());
+    }
+
+    fn vote(kind: VoteKind, slot: Slot, view: View, hash: &str) -> SignedVote {
+        let mut vote = SignedVote {
+            header: header(1, HeaderSignableData(vec![])),
+            kind,
+            proposal_hash: ConsensusProposalHash(hash.to_string()),
+            slot,
+            view,
+        };
+        vote.header.msg.hash = vote.signable_data();
+        vote
+    }
+
+    #[test]
+    fn test_vote_conflict() {
+        let evidence = EquivocationEvidence::Vote(
+            vote(VoteKind::PrepareVote, 3, 0, "a"),
+            vote(VoteKind::PrepareVote, 3, 0, "b"),
+        );
+        assert!(evidence.check_conflict().is_ok());
+
+        // Voting again after a view change is fine
+        let evidence = EquivocationEvidence::Vote(
+            vote(VoteKind::PrepareVote, 3, 0, "a"),
+            vote(VoteKind::PrepareVote, 3, 1, "b"),
+        );
+        assert!(evidence.check_conflict().is_err());
+        let evidence = EquivocationEvidence::Vote(
+            vote(VoteKind::PrepareVote, 3, 0, "a"),
+            vote(VoteKind::ConfirmAck, 3, 0, "b"),
+        );
+        assert!(evidence.check_conflict().is_err());
+        let evidence = EquivocationEvidence::Vote(
+            vote(VoteKind::ConfirmAck, 3, 0, "a"),
+            vote(VoteKind::ConfirmAck, 3, 0, "a"),
+        );
+        assert!(evidence.check_conflict().is_err());
+
+        let mut tampered = vote(VoteKind::ConfirmAck, 3, 0, "b");
+        tampered.slot = 4;
+        let evidence = EquivocationEvidence::Vote(vote(VoteKind::ConfirmAck, 4, 0, "a"), tampered);
+        assert!(evidence.check_conflict().is_err());
+    }
+
+    #[test]
+    fn test_data_proposal_conflict() {
+        let evidence = EquivocationEvidence::DataProposal(
+            data_proposal(1, Some("parent"), &["a"]),
+            data_proposal(1, Some("parent"), &["b"]),
+        );
+        assert!(evidence.check_conflict().is_ok());
+
+        let evidence = EquivocationEvidence::DataProposal(
+            data_proposal(1, Some("parent"), &["a"]),
+            data_proposal(1, None, &["b"]),
+        );
+        assert!(evidence.check_conflict().is_err());
+
+        let mut tampered = data_proposal(1, Some("parent"), &["b"]);
+        tampered.tx_hashes.push(TxHash("c".to_string()));
+        let evidence =
+            EquivocationEvidence::DataProposal(data_proposal(1, Some("parent"), &["a"]), tampered);
+        assert!(evidence.check_conflict().is_err());
+    }
+}
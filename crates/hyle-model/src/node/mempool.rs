@@ -16,12 +16,29 @@ pub enum MempoolStatusEvent {
         data_proposal_hash: DataProposalHash,
         txs_metadatas: Vec<TransactionMetadata>,
     },
+    /// The transaction was dropped from a full pending queue before being disseminated.
+    Evicted {
+        tx_hash: TxHash,
+    },
+    /// The transaction was refused by admission control and never reached the mempool.
+    /// `code` is the one returned to the client.
+    Rejected {
+        tx_hash: TxHash,
+        code: String,
+    },
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[allow(
+    clippy::large_enum_variant,
+    reason = "blocks are the bulk of these events anyway"
+)]
 pub enum MempoolBlockEvent {
     BuiltSignedBlock(SignedBlock),
     StartedBuildingBlocks(BlockHeight),
+    /// Blocks up to this height need data proposals pruned by their lane operator,
+    /// so they are not built locally and have to be fetched from peers.
+    MissingBlocks(BlockHeight),
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
@@ -59,6 +76,21 @@ impl DataProposal {
         });
     }
 
+    /// Hash of a data proposal with this parent and these transactions, see [DataProposal::hashed]
+    pub fn hash_from_parts(
+        parent_data_proposal_hash: Option<&DataProposalHash>,
+        tx_hashes: impl IntoIterator<Item = TxHash>,
+    ) -> DataProposalHash {
+        let mut hasher = Sha3_256::new();
+        if let Some(parent_data_proposal_hash) = parent_data_proposal_hash {
+            hasher.update(parent_data_proposal_hash.0.as_bytes());
+        }
+        for tx_hash in tx_hashes {
+            hasher.update(tx_hash.0);
+        }
+        DataProposalHash(hex::encode(hasher.finalize()))
+    }
+
     /// This is used to set the hash of the DataProposal when we can trust we know it
     /// (specifically - deserializating from local storage)
     /// # Safety
@@ -130,14 +162,10 @@ impl Hashed<DataProposalHash> for DataProposal {
         if let Some(hash) = self.hash_cache.read().unwrap().as_ref() {
             return hash.clone();
         }
-        let mut hasher = Sha3_256::new();
-        if let Some(ref parent_data_proposal_hash) = self.parent_data_proposal_hash {
-            hasher.update(parent_data_proposal_hash.0.as_bytes());
-        }
-        for tx in self.txs.iter() {
-            hasher.update(tx.hashed().0);
-        }
-        let hash = DataProposalHash(hex::encode(hasher.finalize()));
+        let hash = DataProposal::hash_from_parts(
+            self.parent_data_proposal_hash.as_ref(),
+            self.txs.iter().map(|tx| tx.hashed()),
+        );
         *self.hash_cache.write().unwrap() = Some(hash.clone());
         hash
     }
@@ -29,6 +29,16 @@ pub enum StakingAction {
         holder: ValidatorPublicKey,
         amount: u128,
     },
+
+    /// A staker delegating to the validator asks it to leave consensus.
+    /// The stake delegated to it can be withdrawn once the unbonding delay is over.
+    Unbond {
+        validator: ValidatorPublicKey,
+    },
+    /// The whole stake of the caller is transferred back by the hyllar blob it calls
+    Withdraw {
+        amount: u128,
+    },
 }
 
 impl ContractAction for StakingAction {
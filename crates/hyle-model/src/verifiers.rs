@@ -5,15 +5,26 @@ use crate::{
 pub const RISC0_1: &str = "risc0-1";
 pub const NOIR: &str = "noir";
 pub const SP1_4: &str = "sp1-4";
+pub const GROTH16_BN254: &str = "groth16-bn254";
 
 #[derive(Debug, Copy, Clone)]
 pub enum NativeVerifiers {
     Blst,
     Sha3_256,
     Secp256k1,
+    Ed25519,
+    Secp256r1,
+    Keccak256,
 }
 
-pub const NATIVE_VERIFIERS_CONTRACT_LIST: &[&str] = &["blst", "sha3_256", "secp256k1"];
+pub const NATIVE_VERIFIERS_CONTRACT_LIST: &[&str] = &[
+    "blst",
+    "sha3_256",
+    "secp256k1",
+    "ed25519",
+    "secp256r1",
+    "keccak256",
+];
 
 impl From<NativeVerifiers> for ProgramId {
     fn from(value: NativeVerifiers) -> Self {
@@ -21,6 +32,9 @@ impl From<NativeVerifiers> for ProgramId {
             NativeVerifiers::Blst => ProgramId("blst".as_bytes().to_vec()),
             NativeVerifiers::Sha3_256 => ProgramId("sha3_256".as_bytes().to_vec()),
             NativeVerifiers::Secp256k1 => ProgramId("secp256k1".as_bytes().to_vec()),
+            NativeVerifiers::Ed25519 => ProgramId("ed25519".as_bytes().to_vec()),
+            NativeVerifiers::Secp256r1 => ProgramId("secp256r1".as_bytes().to_vec()),
+            NativeVerifiers::Keccak256 => ProgramId("keccak256".as_bytes().to_vec()),
         }
     }
 }
@@ -32,11 +46,25 @@ impl TryFrom<&Verifier> for NativeVerifiers {
             "blst" => Ok(Self::Blst),
             "sha3_256" => Ok(Self::Sha3_256),
             "secp256k1" => Ok(Self::Secp256k1),
+            "ed25519" => Ok(Self::Ed25519),
+            "secp256r1" => Ok(Self::Secp256r1),
+            "keccak256" => Ok(Self::Keccak256),
             _ => Err(format!("Unknown native verifier: {value}")),
         }
     }
 }
 
+/// Format of the ProofData for the "groth16-bn254" verifier.
+/// The contract's ProgramId is the arkworks-compressed verifying key.
+#[derive(Debug, Clone, PartialEq, Eq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Groth16Proof {
+    /// arkworks-compressed proof (A, B, C)
+    pub proof: Vec<u8>,
+    /// Public inputs as 32-byte big-endian field elements.
+    /// They encode the HyleOutput, see `hyle_verifiers::noir_utils::parse_hyle_output_fields`.
+    pub public_inputs: Vec<[u8; 32]>,
+}
+
 /// Format of the BlobData for native contract "blst"
 #[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
 pub struct BlstSignatureBlob {
@@ -68,7 +96,7 @@ impl ContractAction for BlstSignatureBlob {
     }
 }
 
-/// Format of the BlobData for native hash contract like "sha3_256"
+/// Format of the BlobData for native hash contract like "sha3_256" or "keccak256"
 #[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
 pub struct ShaBlob {
     pub identity: Identity,
@@ -161,3 +189,78 @@ impl ContractAction for Secp256k1Blob {
         }
     }
 }
+
+/// Format of the BlobData for native ed25519 contract
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Ed25519Blob {
+    pub identity: Identity,
+    /// The signed message, as is (ed25519 hashes it internally)
+    pub data: Vec<u8>,
+    pub public_key: [u8; 32],
+    pub signature: [u8; 64],
+}
+
+impl Ed25519Blob {
+    pub fn as_blob(&self) -> Blob {
+        <Self as ContractAction>::as_blob(self, "ed25519".into(), None, None)
+    }
+}
+
+impl ContractAction for Ed25519Blob {
+    fn as_blob(
+        &self,
+        contract_name: ContractName,
+        _caller: Option<BlobIndex>,
+        _callees: Option<Vec<BlobIndex>>,
+    ) -> Blob {
+        #[allow(clippy::expect_used)]
+        Blob {
+            contract_name,
+            data: BlobData(borsh::to_vec(self).expect("failed to encode Ed25519Blob")),
+        }
+    }
+}
+
+/// WebAuthn assertion data, as returned by `navigator.credentials.get()`.
+/// The authenticator signs `authenticator_data || sha256(client_data_json)`,
+/// and the client data embeds the challenge, which must be the blob's data.
+#[derive(Debug, Clone, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct WebAuthnAssertion {
+    pub authenticator_data: Vec<u8>,
+    pub client_data_json: Vec<u8>,
+}
+
+/// Format of the BlobData for native secp256r1 contract
+#[derive(Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct Secp256r1Blob {
+    pub identity: Identity,
+    /// Sha256 digest of the signed data. For WebAuthn, this is the challenge.
+    pub data: [u8; 32],
+    /// SEC1 compressed public key
+    pub public_key: [u8; 33],
+    pub signature: [u8; 64],
+    /// If set, the signature is a WebAuthn (passkey) assertion over `data` instead of
+    /// a plain ECDSA signature of `data`.
+    pub webauthn: Option<WebAuthnAssertion>,
+}
+
+impl Secp256r1Blob {
+    pub fn as_blob(&self) -> Blob {
+        <Self as ContractAction>::as_blob(self, "secp256r1".into(), None, None)
+    }
+}
+
+impl ContractAction for Secp256r1Blob {
+    fn as_blob(
+        &self,
+        contract_name: ContractName,
+        _caller: Option<BlobIndex>,
+        _callees: Option<Vec<BlobIndex>>,
+    ) -> Blob {
+        #[allow(clippy::expect_used)]
+        Blob {
+            contract_name,
+            data: BlobData(borsh::to_vec(self).expect("failed to encode Secp256r1Blob")),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
     bus::{metrics::BusMetrics, BusClientSender, SharedMessageBus},
     log_error, module_bus_client, module_handle_messages,
     modules::{signal::ShutdownModule, Module},
+    node_state::snapshot::{self, NodeStateSnapshotHeader},
 };
 use anyhow::{anyhow, Context, Result};
 pub use axum::Router;
@@ -13,9 +14,10 @@ use axum::{
     http::{header, StatusCode},
     response::{IntoResponse, Response},
     routing::{get, post},
+    Json,
 };
 use sdk::*;
-use std::path::PathBuf;
+use std::path::{Path as FsPath, PathBuf};
 use tokio_util::sync::CancellationToken;
 use tower_http::catch_panic::CatchPanicLayer;
 use tracing::info;
@@ -71,6 +73,8 @@ impl Module for AdminApi {
             Router::new()
                 .route("/v1/admin/persist", post(persist))
                 .route("/v1/admin/download/{file}", get(download))
+                .route("/v1/admin/snapshots", get(list_snapshots))
+                .route("/v1/admin/snapshots/{height}", get(download_snapshot))
                 .with_state(RouterState {
                     bus: AdminBusClient::new_from_bus(bus.new_handle()).await,
                     data_directory: ctx.data_directory,
@@ -117,6 +121,26 @@ pub async fn download(
         ));
     }
 
+    read_file(&full_path).await
+}
+
+pub async fn list_snapshots(
+    State(state): State<RouterState>,
+) -> Result<Json<Vec<NodeStateSnapshotHeader>>, AppError> {
+    let headers = snapshot::list_snapshots(&snapshot::snapshots_directory(&state.data_directory))?;
+    Ok(Json(headers))
+}
+
+pub async fn download_snapshot(
+    Path(height): Path<u64>,
+    State(state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    let full_path = snapshot::snapshots_directory(&state.data_directory)
+        .join(NodeStateSnapshotHeader::file_name(BlockHeight(height)));
+    read_file(&full_path).await
+}
+
+async fn read_file(full_path: &FsPath) -> Result<impl IntoResponse, AppError> {
     // Check if the file exists
     if !full_path.exists() {
         return Err(AppError(StatusCode::NOT_FOUND, anyhow!("File not found")));
@@ -131,7 +155,7 @@ pub async fn download(
     }
 
     // Read the file content
-    match tokio::fs::read(&full_path).await {
+    match tokio::fs::read(full_path).await {
         Ok(content) => {
             // Try to determine content type based on file extension
             let content_type = if let Some(ext) = full_path.extension() {
@@ -1,16 +1,16 @@
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-use anyhow::Result;
-use sdk::{BlockHeight, Hashed, MempoolStatusEvent, SignedBlock};
+use anyhow::{bail, Result};
+use sdk::{Block, BlockHeight, Hashed, MempoolStatusEvent, SignedBlock, ValidatorPublicKey};
 use tokio::task::yield_now;
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{
     bus::{BusClientSender, SharedMessageBus},
-    modules::{module_bus_client, Module},
+    modules::{da_sources::DaSources, module_bus_client, Module},
     node_state::{metrics::NodeStateMetrics, module::NodeStateEvent, NodeState, NodeStateStore},
-    utils::da_codec::{DataAvailabilityClient, DataAvailabilityEvent, DataAvailabilityRequest},
+    utils::da_codec::{DaSubscription, DataAvailabilityEvent},
 };
 use crate::{log_error, module_handle_messages};
 
@@ -29,13 +29,26 @@ pub struct DAListener {
     node_state: NodeState,
     start_block: BlockHeight,
     block_buffer: BTreeMap<BlockHeight, SignedBlock>,
+    processed_block_buffer: BTreeMap<BlockHeight, Block>,
 }
 
 pub struct DAListenerConf {
     pub data_directory: PathBuf,
     pub da_read_from: String,
+    /// DA servers to fail over to when `da_read_from` lags or goes down
+    pub da_fallback_read_from: Vec<String>,
+    /// Only process blocks once two DA servers sent the same block hash
+    pub cross_check: bool,
+    /// Validator set at the start block with the stake of each validator, the certificates of
+    /// signed blocks are checked against. When starting from genesis, the validators it bonds
+    /// must hold a quorum of this stake.
+    /// Only used by [crate::modules::signed_da_listener::SignedDAListener], not checked if empty.
+    pub trusted_validators: BTreeMap<ValidatorPublicKey, u128>,
     pub start_block: Option<BlockHeight>,
     pub timeout_client_secs: u64,
+    /// If set, the DA server sends blocks it already processed, filtered for this subscription,
+    /// instead of every signed block. Its `from` height is overridden.
+    pub subscription: Option<DaSubscription>,
 }
 
 impl Module for DAListener {
@@ -74,6 +87,7 @@ impl Module for DAListener {
             bus,
             node_state,
             block_buffer: BTreeMap::new(),
+            processed_block_buffer: BTreeMap::new(),
         })
     }
 
@@ -96,17 +110,12 @@ impl Module for DAListener {
 }
 
 impl DAListener {
-    async fn start_client(&self, block_height: BlockHeight) -> Result<DataAvailabilityClient> {
-        let mut client = DataAvailabilityClient::connect_with_opts(
-            "raw_da_listener".to_string(),
-            Some(1024 * 1024 * 1024),
-            self.config.da_read_from.clone(),
-        )
-        .await?;
-
-        client.send(DataAvailabilityRequest(block_height)).await?;
-
-        Ok(client)
+    /// Next block height to stream, see the start block in [DAListener::build]
+    fn next_height(&self) -> BlockHeight {
+        match self.node_state.current_height {
+            BlockHeight(0) => self.start_block,
+            height => height + 1,
+        }
     }
 
     async fn process_block(&mut self, block: SignedBlock) -> Result<()> {
@@ -213,6 +222,35 @@ impl DAListener {
         Ok(())
     }
 
+    /// Forwards blocks processed by the DA server in order, see [DAListenerConf::subscription]
+    async fn process_processed_block(&mut self, block: Block) -> Result<()> {
+        self.processed_block_buffer
+            .insert(block.block_height, block);
+
+        while let Some(entry) = self.processed_block_buffer.first_entry() {
+            let height = *entry.key();
+            let current_height = self.node_state.current_height;
+            let is_genesis = height == BlockHeight(0) && current_height == BlockHeight(0);
+            if !is_genesis && height <= current_height {
+                warn!("📦 Ignoring past processed block: {}", height);
+                entry.remove();
+                continue;
+            }
+            if !is_genesis && height != current_height + 1 {
+                debug!("📦 Buffering future processed block: {}", height);
+                break;
+            }
+            let block = entry.remove();
+            debug!("📦 Forwarding processed block: {} {}", height, block.hash);
+            self.node_state.current_height = height;
+            self.bus
+                .send_waiting_if_full(NodeStateEvent::NewBlock(Box::new(block)))
+                .await?;
+        }
+
+        Ok(())
+    }
+
     pub async fn start(&mut self) -> Result<()> {
         if let Some(folder) = self.config.da_read_from.strip_prefix("folder:") {
             info!("Reading blocks from folder {folder}");
@@ -246,25 +284,17 @@ impl DAListener {
                 on_self self,
             };
         } else {
-            let mut client = self.start_client(self.start_block).await?;
+            let mut sources = DaSources::new(
+                "raw_da_listener",
+                &self.config,
+                self.config.subscription.clone(),
+            );
+            log_error!(sources.start(self.start_block).await, "Starting DA client")?;
 
             module_handle_messages! {
                 on_self self,
-                _ = tokio::time::sleep(tokio::time::Duration::from_secs(self.config.timeout_client_secs)) => {
-                    warn!("No blocks received in the last {} seconds, restarting client", self.config.timeout_client_secs);
-                    client = self.start_client(self.node_state.current_height + 1).await?;
-                }
-                frame = client.recv() => {
-                    if let Some(streamed_signed_block) = frame {
-                        let _ = log_error!(self.processing_next_frame(streamed_signed_block).await, "Consuming da stream");
-                        if let Err(e) = client.ping().await {
-                            warn!("Ping failed: {}. Restarting client...", e);
-                            client = self.start_client(self.node_state.current_height + 1).await?;
-                        }
-                    } else {
-                        warn!("DA stream connection lost. Reconnecting...");
-                        client = self.start_client(self.node_state.current_height + 1).await?;
-                    }
+                event = sources.recv(self.next_height()) => {
+                    self.on_da_event(&mut sources, event).await;
                 }
             };
         }
@@ -272,14 +302,43 @@ impl DAListener {
         Ok(())
     }
 
+    async fn on_da_event(&mut self, sources: &mut DaSources, event: Result<DataAvailabilityEvent>) {
+        let Ok(event) = log_error!(event, "Streaming from DA servers") else {
+            return;
+        };
+        if let Err(e) = self.processing_next_frame(event).await {
+            let reason = format!("could not consume its stream: {e:#}");
+            let _ = log_error!(
+                sources.fail_over(self.next_height(), &reason).await,
+                "Failing over to another DA server"
+            );
+        }
+    }
+
     async fn processing_next_frame(&mut self, event: DataAvailabilityEvent) -> Result<()> {
         match event {
             DataAvailabilityEvent::SignedBlock(block) => {
                 self.process_block(block).await?;
             }
+            DataAvailabilityEvent::ProcessedBlock(block) => {
+                self.process_processed_block(*block).await?;
+            }
             DataAvailabilityEvent::MempoolStatusEvent(mempool_status_event) => {
                 self.bus.send_waiting_if_full(mempool_status_event).await?;
             }
+            DataAvailabilityEvent::BlocksPruned {
+                requested,
+                first_available,
+            } => {
+                bail!(
+                    "DA server pruned blocks below {}, cannot stream from {}",
+                    first_available,
+                    requested
+                );
+            }
+            DataAvailabilityEvent::ProcessedBlockMissing(height) => {
+                bail!("DA server never processed block {}", height);
+            }
         }
 
         Ok(())
@@ -0,0 +1,446 @@
+//! Data availability servers the DA listeners stream blocks from.
+//!
+//! Sources are scored on how well they stream: each block received raises the score
+//! of the source it came from, and each timeout, lost connection or rejected frame lowers it.
+//! When the source we read from fails, we fail over to the healthiest other one.
+//! With cross-checking, blocks are only handed out once a second source sent the same hash.
+//! A witness that doesn't confirm a block within the timeout, or lets too many blocks pile up
+//! unconfirmed, is replaced like a failing source.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use sdk::{BlockHeight, ConsensusProposalHash, Hashed};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    modules::da_listener::DAListenerConf,
+    utils::da_codec::{
+        DaSubscription, DataAvailabilityClient, DataAvailabilityEvent, DataAvailabilityRequest,
+    },
+};
+
+/// Score a source gains for each block it streams, up to this value
+const MAX_SCORE: i64 = 100;
+/// Score a source loses when it times out, disconnects or sends a frame we reject
+const FAILURE_PENALTY: i64 = 20;
+/// Blocks from the primary kept waiting for the witness before it's considered lagging
+const MAX_PENDING_BLOCKS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DaSource {
+    address: String,
+    score: i64,
+}
+
+/// Picks the healthiest source other than `excluded`.
+/// Ties go to the first source after `excluded`, so that sources are tried in turn.
+fn healthiest(sources: &[DaSource], excluded: Option<usize>) -> Option<usize> {
+    let start = excluded.map(|i| i + 1).unwrap_or(0);
+    (0..sources.len())
+        .map(|offset| (start + offset) % sources.len())
+        .filter(|index| Some(*index) != excluded)
+        .max_by_key(|index| {
+            (
+                sources.get(*index).map(|s| s.score),
+                // Earlier in turn wins ties
+                std::cmp::Reverse((*index + sources.len() - start) % sources.len()),
+            )
+        })
+}
+
+/// Height and hash of the block carried by a frame, if any
+fn block_id(event: &DataAvailabilityEvent) -> Option<(BlockHeight, ConsensusProposalHash)> {
+    match event {
+        DataAvailabilityEvent::SignedBlock(block) => Some((block.height(), block.hashed())),
+        DataAvailabilityEvent::ProcessedBlock(block) => {
+            Some((block.block_height, block.hash.clone()))
+        }
+        DataAvailabilityEvent::MempoolStatusEvent(_)
+        | DataAvailabilityEvent::BlocksPruned { .. }
+        | DataAvailabilityEvent::ProcessedBlockMissing(_) => None,
+    }
+}
+
+async fn recv_witness(
+    witness: &mut Option<(usize, DataAvailabilityClient)>,
+) -> Option<DataAvailabilityEvent> {
+    match witness {
+        Some((_, client)) => client.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Streams frames from the healthiest of the configured DA servers
+pub struct DaSources {
+    name: String,
+    sources: Vec<DaSource>,
+    subscription: Option<DaSubscription>,
+    timeout: Duration,
+    cross_check: bool,
+
+    primary: usize,
+    client: Option<DataAvailabilityClient>,
+    last_primary_frame: Instant,
+    /// Source the block hashes of the primary are checked against
+    witness: Option<(usize, DataAvailabilityClient)>,
+    /// Blocks from the primary waiting for the witness to send their hash, with when they arrived
+    pending: BTreeMap<BlockHeight, (ConsensusProposalHash, DataAvailabilityEvent, Instant)>,
+    /// Hashes sent by the witness, for blocks the primary hasn't sent yet
+    witnessed: BTreeMap<BlockHeight, ConsensusProposalHash>,
+    ready: VecDeque<DataAvailabilityEvent>,
+}
+
+impl DaSources {
+    /// Sources are `da_read_from` followed by the fallbacks.
+    /// With a subscription, processed blocks are requested instead of signed blocks.
+    pub fn new(name: &str, config: &DAListenerConf, subscription: Option<DaSubscription>) -> Self {
+        let mut sources: Vec<DaSource> = vec![];
+        for address in std::iter::once(&config.da_read_from).chain(&config.da_fallback_read_from) {
+            if !sources.iter().any(|s| &s.address == address) {
+                sources.push(DaSource {
+                    address: address.clone(),
+                    score: 0,
+                });
+            }
+        }
+        if config.cross_check && sources.len() < 2 {
+            warn!("Cross-checking DA sources needs at least two of them, blocks won't be checked");
+        }
+        DaSources {
+            name: name.to_string(),
+            cross_check: config.cross_check && sources.len() > 1,
+            sources,
+            subscription,
+            timeout: Duration::from_secs(config.timeout_client_secs),
+            primary: 0,
+            client: None,
+            last_primary_frame: Instant::now(),
+            witness: None,
+            pending: BTreeMap::new(),
+            witnessed: BTreeMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn address(&self, index: usize) -> &str {
+        self.sources
+            .get(index)
+            .map(|s| s.address.as_str())
+            .unwrap_or_default()
+    }
+
+    fn adjust_score(&mut self, index: usize, delta: i64) {
+        if let Some(source) = self.sources.get_mut(index) {
+            source.score = (source.score + delta).min(MAX_SCORE);
+        }
+    }
+
+    async fn connect(&self, index: usize, from: BlockHeight) -> Result<DataAvailabilityClient> {
+        let mut client = DataAvailabilityClient::connect_with_opts(
+            self.name.clone(),
+            Some(1024 * 1024 * 1024),
+            self.address(index).to_string(),
+        )
+        .await?;
+
+        let request = match &self.subscription {
+            Some(subscription) => DataAvailabilityRequest::Subscribe(DaSubscription {
+                from,
+                ..subscription.clone()
+            }),
+            None => DataAvailabilityRequest::FromHeight(from),
+        };
+        client.send(request).await?;
+
+        Ok(client)
+    }
+
+    /// Connects to the healthiest source, and to a witness if cross-checking.
+    /// Frames received but not handed out yet are dropped, they'll be streamed again.
+    pub async fn start(&mut self, from: BlockHeight) -> Result<()> {
+        self.restart(from, None).await
+    }
+
+    /// Like [DaSources::start], trying `avoid` last
+    async fn restart(&mut self, from: BlockHeight, avoid: Option<usize>) -> Result<()> {
+        self.client = None;
+        self.witness = None;
+        self.pending.clear();
+        self.witnessed.clear();
+        self.ready.clear();
+
+        let mut excluded = avoid;
+        for _ in 0..self.sources.len() {
+            let Some(index) = healthiest(&self.sources, excluded).or(excluded) else {
+                break;
+            };
+            match self.connect(index, from).await {
+                Ok(client) => {
+                    info!(
+                        "📡 Streaming blocks from DA server {} at block {}",
+                        self.address(index),
+                        from
+                    );
+                    self.primary = index;
+                    self.client = Some(client);
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        "Could not connect to DA server {}: {}",
+                        self.address(index),
+                        e
+                    );
+                    self.adjust_score(index, -FAILURE_PENALTY);
+                    excluded = Some(index);
+                }
+            }
+        }
+        if self.client.is_none() {
+            bail!("Could not connect to any DA server");
+        }
+        self.last_primary_frame = Instant::now();
+
+        if self.cross_check {
+            self.connect_witness(from).await;
+        }
+        Ok(())
+    }
+
+    async fn connect_witness(&mut self, from: BlockHeight) {
+        let Some(index) = healthiest(&self.sources, Some(self.primary)) else {
+            return;
+        };
+        match self.connect(index, from).await {
+            Ok(client) => {
+                info!(
+                    "📡 Cross-checking blocks with DA server {}",
+                    self.address(index)
+                );
+                self.witness = Some((index, client));
+            }
+            Err(e) => {
+                warn!(
+                    "Could not connect to DA server {} to cross-check blocks: {}",
+                    self.address(index),
+                    e
+                );
+                self.adjust_score(index, -FAILURE_PENALTY);
+            }
+        }
+    }
+
+    /// Penalizes the source we read from, and streams from the healthiest other one.
+    pub async fn fail_over(&mut self, from: BlockHeight, reason: &str) -> Result<()> {
+        warn!(
+            "DA server {} failed: {}. Failing over...",
+            self.address(self.primary),
+            reason
+        );
+        self.adjust_score(self.primary, -FAILURE_PENALTY);
+        self.restart(from, Some(self.primary)).await
+    }
+
+    /// Penalizes the witness, and restarts with the healthiest other one.
+    async fn fail_over_witness(&mut self, from: BlockHeight, reason: &str) -> Result<()> {
+        let witness = self.witness.take().map(|(index, _)| index);
+        if let Some(index) = witness {
+            warn!(
+                "DA server {} lags behind to cross-check blocks: {}. Failing over...",
+                self.address(index),
+                reason
+            );
+            self.adjust_score(index, -FAILURE_PENALTY);
+        }
+        self.restart(from, witness).await
+    }
+
+    /// Next frame of the stream, `from` being the next block height the listener needs
+    pub async fn recv(&mut self, from: BlockHeight) -> Result<DataAvailabilityEvent> {
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Ok(event);
+            }
+            if self.client.is_none() || (self.cross_check && self.witness.is_none()) {
+                self.start(from).await?;
+                if self.cross_check && self.witness.is_none() {
+                    tokio::time::sleep(self.timeout).await;
+                    bail!("No DA server available to cross-check blocks with");
+                }
+            }
+            // The oldest block waiting for the witness
+            let witness_deadline = self
+                .pending
+                .values()
+                .map(|(_, _, received)| *received + self.timeout)
+                .min();
+            let Some(client) = self.client.as_mut() else {
+                continue;
+            };
+
+            tokio::select! {
+                _ = sleep_until(witness_deadline) => {
+                    let reason = format!("a block waits for confirmation for more than {} seconds", self.timeout.as_secs());
+                    self.fail_over_witness(from, &reason).await?;
+                }
+                _ = tokio::time::sleep_until(self.last_primary_frame + self.timeout) => {
+                    let reason = format!("no blocks received in the last {} seconds", self.timeout.as_secs());
+                    self.fail_over(from, &reason).await?;
+                }
+                frame = client.recv() => {
+                    self.last_primary_frame = Instant::now();
+                    match frame {
+                        Some(event) => {
+                            if let Err(e) = client.ping().await {
+                                self.fail_over(from, &format!("ping failed: {e}")).await?;
+                                continue;
+                            }
+                            self.on_primary_frame(event, from).await?;
+                        }
+                        None => self.fail_over(from, "connection lost").await?,
+                    }
+                }
+                frame = recv_witness(&mut self.witness) => {
+                    self.on_witness_frame(frame, from).await?;
+                }
+            }
+        }
+    }
+
+    async fn on_primary_frame(
+        &mut self,
+        event: DataAvailabilityEvent,
+        from: BlockHeight,
+    ) -> Result<()> {
+        let Some((height, hash)) = block_id(&event) else {
+            self.ready.push_back(event);
+            return Ok(());
+        };
+        self.adjust_score(self.primary, 1);
+        // The listener ignores past blocks anyway, no need to wait for the witness
+        if !self.cross_check || height < from {
+            self.ready.push_back(event);
+            return Ok(());
+        }
+        match self.witnessed.remove(&height) {
+            Some(witnessed) => {
+                self.cross_check_block(height, hash, witnessed, event, from)
+                    .await
+            }
+            None => {
+                debug!("📦 Block {} waiting for a second source", height);
+                self.pending.insert(height, (hash, event, Instant::now()));
+                if self.pending.len() > MAX_PENDING_BLOCKS {
+                    let reason =
+                        format!("more than {MAX_PENDING_BLOCKS} blocks wait for confirmation");
+                    return self.fail_over_witness(from, &reason).await;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn on_witness_frame(
+        &mut self,
+        frame: Option<DataAvailabilityEvent>,
+        from: BlockHeight,
+    ) -> Result<()> {
+        let pinged = match (&frame, self.witness.as_mut()) {
+            (Some(_), Some((_, client))) => client.ping().await.is_ok(),
+            _ => false,
+        };
+        let Some(event) = frame.filter(|_| pinged) else {
+            if let Some((index, _)) = self.witness.take() {
+                warn!("Lost connection to DA server {}", self.address(index));
+                self.adjust_score(index, -FAILURE_PENALTY);
+            }
+            self.connect_witness(from).await;
+            return Ok(());
+        };
+        let Some((height, witnessed)) = block_id(&event) else {
+            return Ok(());
+        };
+        if let Some((index, _)) = &self.witness {
+            self.adjust_score(*index, 1);
+        }
+        if height < from {
+            return Ok(());
+        }
+        match self.pending.remove(&height) {
+            Some((hash, event, _)) => {
+                self.cross_check_block(height, hash, witnessed, event, from)
+                    .await
+            }
+            None => {
+                self.witnessed.insert(height, witnessed);
+                Ok(())
+            }
+        }
+    }
+
+    async fn cross_check_block(
+        &mut self,
+        height: BlockHeight,
+        hash: ConsensusProposalHash,
+        witnessed: ConsensusProposalHash,
+        event: DataAvailabilityEvent,
+        from: BlockHeight,
+    ) -> Result<()> {
+        if hash == witnessed {
+            self.ready.push_back(event);
+            return Ok(());
+        }
+        let witness = self.witness.as_ref().map(|(index, _)| *index);
+        error!(
+            "DA servers {} and {} disagree on block {}: {} != {}",
+            self.address(self.primary),
+            witness.map(|index| self.address(index)).unwrap_or_default(),
+            height,
+            hash,
+            witnessed
+        );
+        if let Some(index) = witness {
+            self.adjust_score(index, -FAILURE_PENALTY);
+        }
+        self.fail_over(from, "block hash mismatch").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(scores: &[i64]) -> Vec<DaSource> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(i, score)| DaSource {
+                address: format!("da-{i}"),
+                score: *score,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_healthiest_source() {
+        assert_eq!(healthiest(&sources(&[]), None), None);
+        assert_eq!(healthiest(&sources(&[0]), Some(0)), None);
+        assert_eq!(healthiest(&sources(&[0, 0, 0]), None), Some(0));
+        assert_eq!(healthiest(&sources(&[0, 5, 3]), None), Some(1));
+        assert_eq!(healthiest(&sources(&[0, 5, 3]), Some(1)), Some(2));
+        // Ties go to the next source in turn
+        assert_eq!(healthiest(&sources(&[0, 0, 0]), Some(1)), Some(2));
+        assert_eq!(healthiest(&sources(&[0, 0, 0]), Some(2)), Some(0));
+        assert_eq!(healthiest(&sources(&[-20, 0, -20]), Some(1)), Some(2));
+    }
+}
@@ -24,10 +24,12 @@ pub mod admin;
 pub mod bus_ws_connector;
 pub mod contract_state_indexer;
 pub mod da_listener;
+pub mod da_sources;
 pub mod prover;
 pub mod prover_metrics;
 pub mod rest;
 pub mod signed_da_listener;
+pub mod tx_status;
 pub mod websocket;
 
 #[derive(Default)]
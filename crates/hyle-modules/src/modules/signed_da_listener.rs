@@ -1,15 +1,20 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use anyhow::Result;
-use sdk::{BlockHeight, DataEvent, Hashed, MempoolStatusEvent, SignedBlock};
+use anyhow::{bail, Context, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hyle_light_client::{Validator, ValidatorSet};
+use sdk::{
+    BlockHeight, ConsensusStakingAction, DataEvent, EpochLength, Hashed, MempoolStatusEvent,
+    SignedBlock, ValidatorPublicKey,
+};
 use tokio::task::yield_now;
 use tracing::{debug, info, warn};
 
 use crate::{
     bus::{BusClientSender, SharedMessageBus},
-    modules::{da_listener::DAListenerConf, module_bus_client, Module},
+    modules::{da_listener::DAListenerConf, da_sources::DaSources, module_bus_client, Module},
     node_state::module::NodeStateModule,
-    utils::da_codec::{DataAvailabilityClient, DataAvailabilityEvent, DataAvailabilityRequest},
+    utils::da_codec::DataAvailabilityEvent,
 };
 use crate::{log_error, module_handle_messages};
 
@@ -27,6 +32,151 @@ pub struct SignedDAListener {
     bus: SignedDAListenerBusClient,
     current_block: BlockHeight,
     block_buffer: BTreeMap<BlockHeight, SignedBlock>,
+    trusted_validators: TrustedValidators,
+}
+
+/// Validator set in charge of the next block to send, followed through the staking actions of
+/// the blocks sent, the way consensus does.
+///
+/// Votes are weighted with the stakes frozen at the start of each epoch: bonds and unbonds only
+/// take effect with the stakes listed by the proposal ending the epoch, while slashed validators
+/// lose their vote right away.
+#[derive(Debug, Default, Clone, BorshSerialize, BorshDeserialize)]
+struct TrustedValidators {
+    /// Stake each validator's vote is weighted with in the current epoch
+    stakes: BTreeMap<ValidatorPublicKey, u128>,
+    /// Set by the genesis block, unknown when starting after it
+    epoch_length: Option<EpochLength>,
+}
+
+impl TrustedValidators {
+    fn new(stakes: BTreeMap<ValidatorPublicKey, u128>) -> Self {
+        TrustedValidators {
+            stakes,
+            epoch_length: None,
+        }
+    }
+
+    fn validator_set(&self) -> Result<ValidatorSet> {
+        ValidatorSet::new(
+            self.stakes
+                .iter()
+                .map(|(pubkey, stake)| Validator {
+                    pubkey: pubkey.into(),
+                    stake: *stake,
+                })
+                .collect(),
+        )
+    }
+
+    /// Checks that the block was committed by the validators in charge, then applies its
+    /// changes to the validator set. Nothing is checked if no validators are trusted.
+    fn verify_and_follow(&mut self, block: &SignedBlock) -> Result<()> {
+        if self.stakes.is_empty() {
+            return Ok(());
+        }
+
+        if block.height() == BlockHeight(0) {
+            // The genesis block isn't certified, but bonds the initial validators
+            return self.follow_genesis(block);
+        }
+
+        self.validator_set()?
+            .verify_certificate(&block.hashed().0, &(&block.certificate).into())
+            .with_context(|| format!("Checking the certificate of block {}", block.height()))?;
+        self.follow(block)
+    }
+
+    /// Validators bonded at genesis must hold a quorum of the trusted stake. The ones that
+    /// aren't trusted weigh nothing until the end of the first epoch lists their stake.
+    fn follow_genesis(&mut self, block: &SignedBlock) -> Result<()> {
+        let mut bonded = BTreeSet::new();
+        let mut epoch_length = None;
+        for action in &block.consensus_proposal.staking_actions {
+            match action {
+                ConsensusStakingAction::Bond { candidate } => {
+                    bonded.insert(&candidate.signature.validator);
+                }
+                ConsensusStakingAction::EpochLength { slots } => {
+                    epoch_length = Some(EpochLength(*slots));
+                }
+                _ => {}
+            }
+        }
+
+        let quorum = self.validator_set()?.quorum()?;
+        let trusted_stake: u128 = self
+            .stakes
+            .iter()
+            .filter(|(validator, _)| bonded.contains(validator))
+            .map(|(_, stake)| stake)
+            .sum();
+        if trusted_stake < quorum {
+            bail!(
+                "Genesis block bonds {:?}, which hold {} of the trusted stake, {} is needed",
+                bonded,
+                trusted_stake,
+                quorum
+            );
+        }
+
+        self.stakes = bonded
+            .into_iter()
+            .map(|validator| {
+                let stake = self.stakes.get(validator).copied().unwrap_or(0);
+                (validator.clone(), stake)
+            })
+            .collect();
+        // Like consensus, epochs last one slot if the genesis block doesn't say otherwise
+        self.epoch_length = Some(epoch_length.unwrap_or_default());
+        Ok(())
+    }
+
+    /// Applies the staking actions of a certified block. Nothing changes if it is refused.
+    fn follow(&mut self, block: &SignedBlock) -> Result<()> {
+        let slot = block.consensus_proposal.slot;
+        let mut slashed = vec![];
+        let mut next_epoch_stakes = None;
+        for action in &block.consensus_proposal.staking_actions {
+            match action {
+                ConsensusStakingAction::Slash { evidence } => slashed.push(evidence.offender()),
+                ConsensusStakingAction::EpochStakes { stakes } => {
+                    if next_epoch_stakes.replace(stakes).is_some() {
+                        bail!("Block {} lists the stakes of the next epoch twice", slot);
+                    }
+                }
+                ConsensusStakingAction::EpochLength { .. } => {
+                    bail!(
+                        "Block {} sets the epoch length, only the genesis block can",
+                        slot
+                    );
+                }
+                ConsensusStakingAction::Bond { .. }
+                | ConsensusStakingAction::Unbond { .. }
+                | ConsensusStakingAction::PayFeesForDaDi { .. } => {}
+            }
+        }
+        if next_epoch_stakes.is_some()
+            && self
+                .epoch_length
+                .is_some_and(|epoch_length| !epoch_length.ends_epoch(slot))
+        {
+            bail!(
+                "Block {} lists the stakes of the next epoch but doesn't end its epoch",
+                slot
+            );
+        }
+
+        // Unlike bonds and unbonds, slashing doesn't wait for the end of the epoch
+        for offender in slashed {
+            self.stakes.remove(offender);
+        }
+        // Without stakes listed, the next epoch keeps the current ones
+        if let Some(stakes) = next_epoch_stakes {
+            self.stakes = stakes.iter().cloned().collect();
+        }
+        Ok(())
+    }
 }
 
 impl Module for SignedDAListener {
@@ -49,6 +199,21 @@ impl Module for SignedDAListener {
             current_block
         );
 
+        // The set followed so far is only known to be in charge of the block we resume from
+        let trusted_validators = if ctx.trusted_validators.is_empty() {
+            TrustedValidators::default()
+        } else {
+            let followed = match ctx.start_block {
+                Some(_) => None,
+                None => Self::load_from_disk::<TrustedValidators>(
+                    ctx.data_directory
+                        .join("da_trusted_validators.bin")
+                        .as_path(),
+                ),
+            };
+            followed.unwrap_or_else(|| TrustedValidators::new(ctx.trusted_validators.clone()))
+        };
+
         let bus = SignedDAListenerBusClient::new_from_bus(bus.new_handle()).await;
 
         Ok(SignedDAListener {
@@ -56,28 +221,29 @@ impl Module for SignedDAListener {
             current_block,
             bus,
             block_buffer: BTreeMap::new(),
+            trusted_validators,
         })
     }
 
     fn run(&mut self) -> impl futures::Future<Output = Result<()>> + Send {
         self.start()
     }
-}
 
-impl SignedDAListener {
-    async fn start_client(&self, block_height: BlockHeight) -> Result<DataAvailabilityClient> {
-        let mut client = DataAvailabilityClient::connect_with_opts(
-            "signed_da_listener".to_string(),
-            Some(1024 * 1024 * 1024),
-            self.config.da_read_from.clone(),
+    async fn persist(&mut self) -> Result<()> {
+        log_error!(
+            Self::save_on_disk::<TrustedValidators>(
+                self.config
+                    .data_directory
+                    .join("da_trusted_validators.bin")
+                    .as_path(),
+                &self.trusted_validators,
+            ),
+            "Saving trusted validators"
         )
-        .await?;
-
-        client.send(DataAvailabilityRequest(block_height)).await?;
-
-        Ok(client)
     }
+}
 
+impl SignedDAListener {
     async fn process_block(&mut self, block: SignedBlock) -> Result<()> {
         let block_height = block.height();
 
@@ -105,6 +271,7 @@ impl SignedDAListener {
                         block.consensus_proposal.hashed()
                     );
                 }
+                self.trusted_validators.verify_and_follow(&block)?;
                 self.bus
                     .send_waiting_if_full(DataEvent::OrderedSignedBlock(block))
                     .await?;
@@ -142,6 +309,7 @@ impl SignedDAListener {
                     block.consensus_proposal.slot,
                     block.consensus_proposal.hashed()
                 );
+                self.trusted_validators.verify_and_follow(&block)?;
                 self.bus
                     .send_waiting_if_full(DataEvent::OrderedSignedBlock(block))
                     .await?;
@@ -195,7 +363,11 @@ impl SignedDAListener {
                 on_self self,
             };
         } else {
-            let mut client = self.start_client(self.current_block).await?;
+            let mut sources = DaSources::new("signed_da_listener", &self.config, None);
+            log_error!(
+                sources.start(self.current_block).await,
+                "Starting DA client"
+            )?;
 
             info!(
                 "Starting DA client for signed blocks at block {}",
@@ -204,37 +376,210 @@ impl SignedDAListener {
 
             module_handle_messages! {
                 on_self self,
-                _ = tokio::time::sleep(tokio::time::Duration::from_secs(self.config.timeout_client_secs)) => {
-                    warn!("No blocks received in the last {} seconds, restarting client", self.config.timeout_client_secs);
-                    client = self.start_client(self.current_block).await?;
-                }
-                frame = client.recv() => {
-                    if let Some(streamed_signed_block) = frame {
-                        let _ = log_error!(self.processing_next_frame(streamed_signed_block).await, "Consuming da stream");
-                        if let Err(e) = client.ping().await {
-                            warn!("Ping failed: {}. Restarting client...", e);
-                            client = self.start_client(self.current_block).await?;
-                        }
-                    } else {
-                        warn!("DA stream connection lost. Reconnecting...");
-                        client = self.start_client(self.current_block).await?;
-                    }
+                event = sources.recv(self.current_block) => {
+                    self.on_da_event(&mut sources, event).await;
                 }
             };
         }
         Ok(())
     }
 
+    async fn on_da_event(&mut self, sources: &mut DaSources, event: Result<DataAvailabilityEvent>) {
+        let Ok(event) = log_error!(event, "Streaming from DA servers") else {
+            return;
+        };
+        if let Err(e) = self.processing_next_frame(event).await {
+            let reason = format!("could not consume its stream: {e:#}");
+            let _ = log_error!(
+                sources.fail_over(self.current_block, &reason).await,
+                "Failing over to another DA server"
+            );
+        }
+    }
+
     async fn processing_next_frame(&mut self, event: DataAvailabilityEvent) -> Result<()> {
         match event {
             DataAvailabilityEvent::SignedBlock(block) => {
                 self.process_block(block).await?;
             }
+            DataAvailabilityEvent::ProcessedBlock(block) => {
+                warn!(
+                    "Ignoring processed block {}, only signed blocks were requested",
+                    block.block_height
+                );
+            }
+            DataAvailabilityEvent::ProcessedBlockMissing(height) => {
+                warn!(
+                    "Ignoring missing processed block {}, only signed blocks were requested",
+                    height
+                );
+            }
             DataAvailabilityEvent::MempoolStatusEvent(status) => {
                 self.bus.send_waiting_if_full(status).await?;
             }
+            DataAvailabilityEvent::BlocksPruned {
+                requested,
+                first_available,
+            } => {
+                bail!(
+                    "DA server pruned blocks below {}, cannot stream from {}",
+                    first_available,
+                    requested
+                );
+            }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sdk::{
+        AggregateSignature, Signature, SignedByValidator, ValidatorCandidacy, ValidatorSignature,
+    };
+
+    use super::*;
+
+    fn bond(validator: &ValidatorPublicKey) -> ConsensusStakingAction {
+        SignedByValidator {
+            msg: ValidatorCandidacy {
+                peer_address: "".into(),
+            },
+            signature: ValidatorSignature {
+                signature: Signature("".into()),
+                validator: validator.clone(),
+            },
+        }
+        .into()
+    }
+
+    fn block(slot: u64, staking_actions: Vec<ConsensusStakingAction>) -> SignedBlock {
+        let mut block = SignedBlock::default();
+        block.consensus_proposal.slot = slot;
+        block.consensus_proposal.staking_actions = staking_actions;
+        block
+    }
+
+    fn epoch_stakes(stakes: &[(&ValidatorPublicKey, u128)]) -> ConsensusStakingAction {
+        ConsensusStakingAction::EpochStakes {
+            stakes: stakes
+                .iter()
+                .map(|(v, stake)| ((*v).clone(), *stake))
+                .collect(),
+        }
+    }
+
+    fn certified_by(mut block: SignedBlock, signers: &[&ValidatorPublicKey]) -> SignedBlock {
+        block.certificate = AggregateSignature {
+            signature: Signature("fake".into()),
+            validators: signers.iter().map(|v| (*v).clone()).collect(),
+        };
+        block
+    }
+
+    #[test]
+    fn test_trusted_validators() {
+        let (a, b, c, d) = (
+            ValidatorPublicKey::new_for_tests("a"),
+            ValidatorPublicKey::new_for_tests("b"),
+            ValidatorPublicKey::new_for_tests("c"),
+            ValidatorPublicKey::new_for_tests("d"),
+        );
+
+        // Nothing is checked without trusted validators
+        let mut untrusted = TrustedValidators::default();
+        assert!(untrusted.verify_and_follow(&block(1, vec![])).is_ok());
+        assert!(untrusted.stakes.is_empty());
+
+        // Genesis must bond a quorum of the trusted stake
+        let mut genesis = TrustedValidators::new(BTreeMap::from([
+            (a.clone(), 100),
+            (b.clone(), 100),
+            (c.clone(), 50),
+        ]));
+        assert!(genesis
+            .verify_and_follow(&block(0, vec![bond(&a), bond(&d)]))
+            .is_err());
+        let epoch_length = ConsensusStakingAction::EpochLength { slots: 3 };
+        genesis
+            .verify_and_follow(&block(0, vec![bond(&a), bond(&b), bond(&d), epoch_length]))
+            .unwrap();
+        // Trusted validators that aren't bonded are dropped, untrusted ones weigh nothing until
+        // their stake is known
+        assert_eq!(
+            genesis.stakes,
+            BTreeMap::from([(a.clone(), 100), (b.clone(), 100), (d.clone(), 0)])
+        );
+        assert_eq!(genesis.epoch_length, Some(EpochLength(3)));
+
+        // Certificates need a quorum of the stake, not of the validators
+        let mut weighted = TrustedValidators::new(BTreeMap::from([
+            (a.clone(), 300),
+            (b.clone(), 100),
+            (c.clone(), 100),
+        ]));
+        let err = weighted
+            .verify_and_follow(&certified_by(block(1, vec![]), &[&b, &c]))
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("voting power of 200"));
+        // Enough stake, the forged signature is what's refused
+        let err = weighted
+            .verify_and_follow(&certified_by(block(1, vec![bond(&d)]), &[&a, &b]))
+            .unwrap_err();
+        assert!(!format!("{err:#}").contains("voting power"));
+        // Rejected blocks don't change the set
+        assert_eq!(weighted.stakes.len(), 3);
+    }
+
+    #[test]
+    fn test_follows_epochs() {
+        let (a, b, c) = (
+            ValidatorPublicKey::new_for_tests("a"),
+            ValidatorPublicKey::new_for_tests("b"),
+            ValidatorPublicKey::new_for_tests("c"),
+        );
+        let mut trusted = TrustedValidators {
+            stakes: BTreeMap::from([(a.clone(), 100), (b.clone(), 100)]),
+            epoch_length: Some(EpochLength(3)),
+        };
+
+        // Bonds and unbonds wait for the end of the epoch
+        trusted
+            .follow(&block(
+                1,
+                vec![
+                    bond(&c),
+                    ConsensusStakingAction::Unbond {
+                        validator: b.clone(),
+                    },
+                ],
+            ))
+            .unwrap();
+        assert_eq!(
+            trusted.stakes,
+            BTreeMap::from([(a.clone(), 100), (b.clone(), 100)])
+        );
+
+        // Stakes are only listed at the end of an epoch, and by the genesis block for its length
+        let next = epoch_stakes(&[(&a, 100), (&c, 50)]);
+        assert!(trusted.follow(&block(1, vec![next.clone()])).is_err());
+        assert!(trusted
+            .follow(&block(
+                2,
+                vec![ConsensusStakingAction::EpochLength { slots: 3 }]
+            ))
+            .is_err());
+        assert_eq!(trusted.stakes.len(), 2);
+
+        trusted.follow(&block(2, vec![next])).unwrap();
+        assert_eq!(
+            trusted.stakes,
+            BTreeMap::from([(a.clone(), 100), (c.clone(), 50)])
+        );
+
+        // Without stakes listed, the next epoch keeps the current ones
+        trusted.follow(&block(5, vec![])).unwrap();
+        assert_eq!(trusted.stakes, BTreeMap::from([(a, 100), (c, 50)]));
+    }
+}
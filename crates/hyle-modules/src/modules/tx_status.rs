@@ -0,0 +1,398 @@
+//! Tracks the lifecycle of transactions submitted to this node, without needing an indexer.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Json,
+};
+use client_sdk::contract_indexer::AppError;
+use sdk::{
+    api::{APITxStatus, TxStatus, TxStatusKind},
+    *,
+};
+use serde::Deserialize;
+use tokio::sync::watch;
+use utoipa::{IntoParams, OpenApi};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    bus::SharedMessageBus, module_bus_client, module_handle_messages, modules::Module,
+    node_state::module::NodeStateEvent,
+};
+
+use super::SharedBuildApiCtx;
+
+/// Number of transactions whose status is remembered. The oldest ones are forgotten first.
+const MAX_TRACKED_TXS: usize = 100_000;
+/// Longest time a status request can be held open.
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+module_bus_client! {
+#[derive(Debug)]
+struct TxStatusBusClient {
+    receiver(MempoolStatusEvent),
+    receiver(NodeStateEvent),
+}
+}
+
+#[derive(Debug, Default)]
+struct TrackedTxs {
+    statuses: HashMap<TxHash, (u64, TxStatus)>,
+    insertion_order: VecDeque<TxHash>,
+}
+
+/// Statuses of recent transactions, shared with the REST API.
+#[derive(Debug)]
+pub struct TxStatusStore {
+    txs: Mutex<TrackedTxs>,
+    changes: watch::Sender<u64>,
+}
+
+impl Default for TxStatusStore {
+    fn default() -> Self {
+        Self {
+            txs: Mutex::new(TrackedTxs::default()),
+            changes: watch::Sender::new(0),
+        }
+    }
+}
+
+impl TxStatusStore {
+    pub fn get(&self, tx_hash: &TxHash) -> APITxStatus {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let txs = self.txs.lock().expect("logic issue");
+        let (version, status) = txs
+            .statuses
+            .get(tx_hash)
+            .cloned()
+            .unwrap_or((0, TxStatus::Unknown));
+        APITxStatus {
+            tx_hash: tx_hash.clone(),
+            version,
+            status,
+        }
+    }
+
+    /// Waits until the status of the transaction moves past `after_version`, or `wait` elapses.
+    pub async fn wait(&self, tx_hash: &TxHash, after_version: u64, wait: Duration) -> APITxStatus {
+        let mut changes = self.changes.subscribe();
+        let deadline = tokio::time::Instant::now() + wait.min(MAX_WAIT);
+        loop {
+            changes.borrow_and_update();
+            let status = self.get(tx_hash);
+            if status.version > after_version {
+                return status;
+            }
+            if tokio::time::timeout_at(deadline, changes.changed())
+                .await
+                .is_err()
+            {
+                return status;
+            }
+        }
+    }
+
+    fn kind(&self, tx_hash: &TxHash) -> TxStatusKind {
+        self.get(tx_hash).status.kind()
+    }
+
+    /// Moves the transaction forward in its lifecycle. Statuses never go backwards,
+    /// except that a transaction dropped by the mempool can be submitted again.
+    fn update(&self, tx_hash: &TxHash, status: TxStatus) {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let mut txs = self.txs.lock().expect("logic issue");
+        let version = match txs.statuses.get(tx_hash) {
+            Some((_, current)) if !moves_forward(current, &status) => {
+                return;
+            }
+            Some((version, _)) => version + 1,
+            None => {
+                txs.insertion_order.push_back(tx_hash.clone());
+                if txs.insertion_order.len() > MAX_TRACKED_TXS {
+                    if let Some(oldest) = txs.insertion_order.pop_front() {
+                        txs.statuses.remove(&oldest);
+                    }
+                }
+                1
+            }
+        };
+        txs.statuses.insert(tx_hash.clone(), (version, status));
+    }
+
+    fn notify(&self) {
+        self.changes.send_modify(|n| *n += 1);
+    }
+}
+
+fn moves_forward(current: &TxStatus, next: &TxStatus) -> bool {
+    if current.is_final() {
+        return false;
+    }
+    match next.kind() {
+        // Rejecting another copy of a pending transaction doesn't affect it.
+        TxStatusKind::Rejected => current.kind() < TxStatusKind::Pending,
+        TxStatusKind::Evicted => current.kind() <= TxStatusKind::Pending,
+        kind => current.kind() < kind,
+    }
+}
+
+pub struct TxStatusTracker {
+    bus: TxStatusBusClient,
+    store: Arc<TxStatusStore>,
+    lane_id: LaneId,
+}
+
+pub struct TxStatusTrackerCtx {
+    pub api: SharedBuildApiCtx,
+    /// Lane of this node, where the data proposals of the mempool are created.
+    pub lane_id: LaneId,
+}
+
+impl Module for TxStatusTracker {
+    type Context = TxStatusTrackerCtx;
+
+    async fn build(bus: SharedMessageBus, ctx: Self::Context) -> Result<Self> {
+        let store = Arc::new(TxStatusStore::default());
+
+        let (router, api) = OpenApiRouter::with_openapi(TxStatusAPI::openapi())
+            .routes(routes!(get_tx_status))
+            .split_for_parts();
+        if let Ok(mut o) = ctx.api.openapi.lock() {
+            *o = o.clone().nest("/v1", api);
+        }
+        if let Ok(mut guard) = ctx.api.router.lock() {
+            if let Some(r) = guard.take() {
+                guard.replace(r.nest("/v1/", router.with_state(RouterState(Arc::clone(&store)))));
+            }
+        }
+
+        Ok(TxStatusTracker {
+            bus: TxStatusBusClient::new_from_bus(bus.new_handle()).await,
+            store,
+            lane_id: ctx.lane_id,
+        })
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        module_handle_messages! {
+            on_self self,
+            listen<MempoolStatusEvent> event => {
+                self.handle_mempool_status_event(event);
+            }
+            listen<NodeStateEvent> event => {
+                let NodeStateEvent::NewBlock(block) = event;
+                self.handle_block(&block);
+            }
+        };
+
+        Ok(())
+    }
+}
+
+impl TxStatusTracker {
+    fn handle_mempool_status_event(&self, event: MempoolStatusEvent) {
+        match event {
+            MempoolStatusEvent::WaitingDissemination { tx, .. } => {
+                self.store.update(&tx.hashed(), TxStatus::Pending);
+            }
+            MempoolStatusEvent::DataProposalCreated {
+                data_proposal_hash,
+                txs_metadatas,
+                ..
+            } => {
+                for metadata in txs_metadatas {
+                    self.store.update(
+                        &metadata.id.1,
+                        TxStatus::InDataProposal {
+                            lane_id: self.lane_id.clone(),
+                            data_proposal_hash: data_proposal_hash.clone(),
+                        },
+                    );
+                }
+            }
+            MempoolStatusEvent::Evicted { tx_hash } => {
+                self.store.update(&tx_hash, TxStatus::Evicted);
+            }
+            MempoolStatusEvent::Rejected { tx_hash, code } => {
+                self.store.update(&tx_hash, TxStatus::Rejected { code });
+            }
+        }
+        self.store.notify();
+    }
+
+    fn handle_block(&self, block: &Block) {
+        let block_height = block.block_height;
+        let dropped: HashSet<&TxId> = block.dropped_duplicate_txs.iter().collect();
+
+        for (tx_id, _) in &block.txs {
+            if !dropped.contains(tx_id) {
+                self.store
+                    .update(&tx_id.1, TxStatus::Sequenced { block_height });
+            }
+        }
+        // Only the resubmitted copy was dropped if the transaction was already sequenced.
+        for TxId(_, tx_hash) in dropped {
+            if self.store.kind(tx_hash) < TxStatusKind::Sequenced {
+                self.store
+                    .update(tx_hash, TxStatus::DroppedDuplicate { block_height });
+            }
+        }
+        for tx_hash in &block.successful_txs {
+            self.store
+                .update(tx_hash, TxStatus::Settled { block_height });
+        }
+        for tx_hash in &block.failed_txs {
+            self.store
+                .update(tx_hash, TxStatus::Failed { block_height });
+        }
+        for tx_hash in &block.timed_out_txs {
+            self.store
+                .update(tx_hash, TxStatus::TimedOut { block_height });
+        }
+        self.store.notify();
+    }
+}
+
+#[derive(Clone)]
+struct RouterState(Arc<TxStatusStore>);
+
+#[derive(OpenApi)]
+struct TxStatusAPI;
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct TxStatusParams {
+    /// Wait for the status to move past this version (long polling)
+    after_version: Option<u64>,
+    /// How long to wait, in milliseconds (at most 30s)
+    wait_ms: Option<u64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/tx/{tx_hash}/status",
+    params(
+        ("tx_hash" = String, Path, description = "Transaction hash"),
+        TxStatusParams
+    ),
+    tag = "Transactions",
+    responses(
+        (status = OK, body = APITxStatus)
+    )
+)]
+async fn get_tx_status(
+    Path(tx_hash): Path<TxHash>,
+    Query(params): Query<TxStatusParams>,
+    State(RouterState(store)): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    let status = match params.after_version {
+        Some(after_version) => {
+            let wait = Duration::from_millis(params.wait_ms.unwrap_or_default());
+            store.wait(&tx_hash, after_version, wait).await
+        }
+        None => store.get(&tx_hash),
+    };
+    Ok(Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx_hash(s: &str) -> TxHash {
+        TxHash(s.to_string())
+    }
+
+    #[test]
+    fn test_status_never_goes_backwards() {
+        let store = TxStatusStore::default();
+        let hash = tx_hash("a");
+        assert_eq!(store.get(&hash).status, TxStatus::Unknown);
+
+        store.update(
+            &hash,
+            TxStatus::Sequenced {
+                block_height: BlockHeight(1),
+            },
+        );
+        store.update(&hash, TxStatus::Pending);
+        assert_eq!(store.kind(&hash), TxStatusKind::Sequenced);
+
+        store.update(
+            &hash,
+            TxStatus::Settled {
+                block_height: BlockHeight(2),
+            },
+        );
+        store.update(
+            &hash,
+            TxStatus::TimedOut {
+                block_height: BlockHeight(3),
+            },
+        );
+        let status = store.get(&hash);
+        assert_eq!(
+            status.status,
+            TxStatus::Settled {
+                block_height: BlockHeight(2)
+            }
+        );
+        assert_eq!(status.version, 2);
+    }
+
+    #[test]
+    fn test_dropped_tx_can_be_resubmitted() {
+        let store = TxStatusStore::default();
+        let hash = tx_hash("a");
+        let rejected = TxStatus::Rejected {
+            code: "identity_rate_limited".to_string(),
+        };
+
+        store.update(&hash, rejected.clone());
+        assert_eq!(store.get(&hash).status, rejected);
+        store.update(&hash, TxStatus::Pending);
+        // A rejected copy doesn't hide the pending one
+        store.update(&hash, rejected);
+        assert_eq!(store.kind(&hash), TxStatusKind::Pending);
+
+        store.update(&hash, TxStatus::Evicted);
+        assert_eq!(store.kind(&hash), TxStatusKind::Evicted);
+        store.update(&hash, TxStatus::Pending);
+        store.update(
+            &hash,
+            TxStatus::Sequenced {
+                block_height: BlockHeight(1),
+            },
+        );
+        // Too late to be evicted
+        store.update(&hash, TxStatus::Evicted);
+        assert_eq!(store.kind(&hash), TxStatusKind::Sequenced);
+        assert_eq!(store.get(&hash).version, 5);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_change() {
+        let store = Arc::new(TxStatusStore::default());
+        let hash = tx_hash("a");
+
+        let waiting = tokio::spawn({
+            let store = Arc::clone(&store);
+            let hash = hash.clone();
+            async move { store.wait(&hash, 0, Duration::from_secs(5)).await }
+        });
+        tokio::task::yield_now().await;
+        store.update(&hash, TxStatus::Pending);
+        store.notify();
+        assert_eq!(waiting.await.unwrap().status, TxStatus::Pending);
+
+        // Times out on the current status if nothing changes
+        let status = store.wait(&hash, 1, Duration::from_millis(10)).await;
+        assert_eq!(status.version, 1);
+    }
+}
@@ -4,9 +4,13 @@ use anyhow::{bail, Context, Error, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
 use contract_registration::validate_contract_registration_metadata;
 use contract_registration::{validate_contract_name_registration, validate_state_commitment_size};
+use contract_upgrades::ContractUpgrades;
 use hyle_tld::{handle_blob_for_hyle_tld, validate_hyle_contract_blobs};
+use hyle_verifiers::validate_program_id;
 use metrics::NodeStateMetrics;
 use ordered_tx_map::OrderedTxMap;
+use recent_states::RecentStates;
+use sdk::api::{APISettlementSimulation, SimulatedSettlementStatus};
 use sdk::verifiers::{NativeVerifiers, NATIVE_VERIFIERS_CONTRACT_LIST};
 use sdk::*;
 use serde::{Deserialize, Serialize};
@@ -16,10 +20,13 @@ use tracing::{debug, error, info, trace};
 
 mod api;
 pub mod contract_registration;
+pub mod contract_upgrades;
 mod hyle_tld;
 pub mod metrics;
 pub mod module;
 mod ordered_tx_map;
+pub mod recent_states;
+pub mod snapshot;
 mod timeouts;
 
 #[derive(Debug, Clone)]
@@ -27,7 +34,9 @@ mod timeouts;
 enum SideEffect {
     Register(Option<Vec<u8>>),
     UpdateState,
-    UpdateProgramId,
+    ScheduleUpgrade(ProgramId, Verifier),
+    /// Program ID and effective height of the pending upgrade to cancel.
+    CancelUpgrade(ProgramId, BlockHeight),
     UpdateTimeoutWindow,
     Delete,
 }
@@ -158,6 +167,97 @@ pub struct NodeStateStore {
     // This field is public for testing purposes
     pub contracts: HashMap<ContractName, Contract>,
     unsettled_transactions: OrderedTxMap,
+    contract_upgrades: ContractUpgrades,
+    recent_states: RecentStates,
+    /// Hash of the last handled block, whose contract registry root the next proposal commits to.
+    last_block_hash: Option<ConsensusProposalHash>,
+    /// Derived from `contracts`, rebuilt after loading the store.
+    #[borsh(skip)]
+    contract_registry_tree: ContractRegistryTree,
+}
+
+impl NodeStateStore {
+    /// Sets the number of blocks a scheduled contract upgrade waits before being applied.
+    pub fn set_contract_upgrade_delay(&mut self, delay: BlockHeight) {
+        self.contract_upgrades.delay = delay;
+    }
+
+    /// Sets the number of blocks a superseded contract state can still be read by proofs.
+    pub fn set_state_read_window(&mut self, window: BlockHeight) {
+        self.recent_states.window = window;
+    }
+
+    /// Copies the parts of the store that settling `tx` with `hyle_outputs` in the next block can
+    /// read or change: the contracts named by its blobs and outputs, the unsettled txs queued on
+    /// them (see [OrderedTxMap::subset]), and their timeouts, upgrades and recent states.
+    /// This is proportional to what the settlement touches, not to the size of the store.
+    pub fn settlement_overlay(
+        &self,
+        tx: &BlobTransaction,
+        hyle_outputs: &[HyleOutput],
+    ) -> NodeStateStore {
+        let mut touched: BTreeSet<ContractName> =
+            OrderedTxMap::get_contracts_blocked_by_blobs(tx.blobs.iter())
+                .into_iter()
+                .collect();
+        touched.insert(hyle_contract_definition().name);
+        for output in hyle_outputs {
+            touched.extend(output.state_reads.iter().map(|(name, _)| name.clone()));
+            touched.extend(output.onchain_effects.iter().map(|effect| match effect {
+                OnchainEffect::RegisterContract(effect) => effect.contract_name.clone(),
+                OnchainEffect::DeleteContract(name) => name.clone(),
+                OnchainEffect::CancelContractUpgrade(effect) => effect.contract_name.clone(),
+                OnchainEffect::UpgradeContract(effect) => effect.contract_name.clone(),
+            }));
+        }
+
+        let unsettled_transactions = self.unsettled_transactions.subset(&mut touched);
+        NodeStateStore {
+            timeouts: self
+                .timeouts
+                .subset_due_at(self.current_height + 1, |tx_hash| {
+                    unsettled_transactions.get(tx_hash).is_some()
+                }),
+            current_height: self.current_height,
+            contracts: touched
+                .iter()
+                .filter_map(|name| Some((name.clone(), self.contracts.get(name)?.clone())))
+                .collect(),
+            unsettled_transactions,
+            contract_upgrades: self.contract_upgrades.subset(&touched),
+            recent_states: self.recent_states.subset(&touched),
+            last_block_hash: self.last_block_hash.clone(),
+            contract_registry_tree: ContractRegistryTree::default(),
+        }
+    }
+
+    /// Inclusion proof of a contract against the current contract registry root.
+    pub fn contract_proof(&self, contract_name: &ContractName) -> Option<ContractInclusionProof> {
+        if self.contract_registry_tree.len() == self.contracts.len() {
+            self.contract_registry_tree
+                .proof(&self.contracts, contract_name, self.current_height)
+        } else {
+            // The tree isn't built yet, e.g. right after loading the store from disk.
+            ContractInclusionProof::new(&self.contracts, contract_name, self.current_height)
+        }
+    }
+
+    /// Contract registry root after the block `block_hash`, see
+    /// [ConsensusProposal::parent_contract_registry_root]. None until that block is the last one
+    /// handled, and once a later one is.
+    pub fn contract_registry_root_after(
+        &self,
+        block_hash: &ConsensusProposalHash,
+    ) -> Option<MerkleHash> {
+        if self.last_block_hash.as_ref() != Some(block_hash) {
+            return None;
+        }
+        if self.contract_registry_tree.len() == self.contracts.len() {
+            Some(self.contract_registry_tree.root())
+        } else {
+            Some(contract_registry_root(&self.contracts))
+        }
+    }
 }
 
 /// Make sure we register the hyle contract with the same values before genesis, and in the genesis block
@@ -179,8 +279,14 @@ impl Default for NodeStateStore {
             current_height: BlockHeight(0),
             contracts: HashMap::new(),
             unsettled_transactions: OrderedTxMap::default(),
+            contract_upgrades: ContractUpgrades::default(),
+            recent_states: RecentStates::default(),
+            last_block_hash: None,
+            contract_registry_tree: ContractRegistryTree::default(),
         };
         let hyle_contract = hyle_contract_definition();
+        ret.contract_upgrades
+            .record(&hyle_contract, ret.current_height);
         ret.contracts
             .insert(hyle_contract.name.clone(), hyle_contract);
         ret
@@ -204,6 +310,7 @@ impl NodeState {
 
         let mut block_under_construction = Block {
             parent_hash: signed_block.parent_hash().clone(),
+            parent_commit_qc: signed_block.consensus_proposal.parent_commit_qc.clone(),
             hash: signed_block.hashed(),
             block_height: signed_block.height(),
             block_timestamp: signed_block.consensus_proposal.timestamp.clone(),
@@ -224,6 +331,40 @@ impl NodeState {
                     _ => None,
                 })
                 .collect(),
+            slashed_validators: signed_block
+                .consensus_proposal
+                .staking_actions
+                .iter()
+                .filter_map(|v| match v {
+                    ConsensusStakingAction::Slash { evidence } => Some(evidence.offender().clone()),
+                    _ => None,
+                })
+                .collect(),
+            unbonded_validators: signed_block
+                .consensus_proposal
+                .staking_actions
+                .iter()
+                .filter_map(|v| match v {
+                    ConsensusStakingAction::Unbond { validator } => Some(validator.clone()),
+                    _ => None,
+                })
+                .collect(),
+            next_epoch_stakes: signed_block
+                .consensus_proposal
+                .staking_actions
+                .iter()
+                .find_map(|v| match v {
+                    ConsensusStakingAction::EpochStakes { stakes } => Some(stakes.clone()),
+                    _ => None,
+                }),
+            epoch_length: signed_block
+                .consensus_proposal
+                .staking_actions
+                .iter()
+                .find_map(|v| match v {
+                    ConsensusStakingAction::EpochLength { slots } => Some(EpochLength(*slots)),
+                    _ => None,
+                }),
             timed_out_txs: vec![], // Added below as it needs the block
             dropped_duplicate_txs: vec![],
             registered_contracts: BTreeMap::new(),
@@ -231,12 +372,19 @@ impl NodeState {
             updated_states: BTreeMap::new(),
             updated_program_ids: BTreeMap::new(),
             updated_timeout_windows: BTreeMap::new(),
+            scheduled_contract_upgrades: BTreeMap::new(),
+            cancelled_contract_upgrades: vec![],
+            contract_code_versions: BTreeMap::new(),
             transactions_events: BTreeMap::new(),
             dp_parent_hashes: BTreeMap::new(),
             lane_ids: BTreeMap::new(),
+            contract_registry_root: EMPTY_MERKLE_ROOT, // Computed once all transactions are handled
         };
 
         self.clear_timeouts(&mut block_under_construction);
+        // Upgrades apply before the block's transactions, which must be proven with the new program.
+        self.apply_contract_upgrades(&mut block_under_construction);
+        self.recent_states.prune(self.current_height);
 
         let mut next_unsettled_txs = BTreeSet::new();
         // Handle all transactions
@@ -383,6 +531,19 @@ impl NodeState {
             block_under_construction.txs.push((tx_id, tx.clone()));
         }
 
+        let changed_contracts = block_under_construction
+            .registered_contracts
+            .keys()
+            .chain(block_under_construction.deleted_contracts.keys())
+            .chain(block_under_construction.updated_states.keys())
+            .chain(block_under_construction.updated_program_ids.keys())
+            .chain(block_under_construction.updated_timeout_windows.keys());
+        self.store
+            .contract_registry_tree
+            .update(&self.store.contracts, changed_contracts);
+        block_under_construction.contract_registry_root = self.contract_registry_tree.root();
+        self.store.last_block_hash = Some(block_under_construction.hash.clone());
+
         self.metrics.record_contracts(self.contracts.len() as u64);
 
         let schedule_timeouts_nb = self.timeouts.count_all() as u64;
@@ -397,6 +558,118 @@ impl NodeState {
         Ok(block_under_construction)
     }
 
+    /// Runs a blob transaction and candidate outputs through a [NodeStateStore::settlement_overlay],
+    /// as if they were all included in the next block. The actual state is left untouched.
+    /// If the transaction is already sequenced, only the outputs are added.
+    pub fn simulate_settlement(
+        &self,
+        metrics: NodeStateMetrics,
+        tx: BlobTransaction,
+        hyle_outputs: Vec<HyleOutput>,
+    ) -> Result<APISettlementSimulation> {
+        let tx_hash = tx.hashed();
+        let overlay = self.settlement_overlay(&tx, &hyle_outputs);
+
+        let mut proven_blobs: BTreeMap<ContractName, Vec<BlobProofOutput>> = BTreeMap::new();
+        for hyle_output in hyle_outputs {
+            let Some(blob) = tx.blobs.get(hyle_output.index.0) else {
+                bail!(
+                    "Output index {} is out of bounds for a transaction with {} blobs",
+                    hyle_output.index,
+                    tx.blobs.len()
+                );
+            };
+            // Outputs are taken as proven by the contract's current program.
+            let program_id = self
+                .contracts
+                .get(&blob.contract_name)
+                .map(|contract| contract.program_id.clone())
+                .unwrap_or_default();
+            proven_blobs
+                .entry(blob.contract_name.clone())
+                .or_default()
+                .push(BlobProofOutput {
+                    original_proof_hash: ProofDataHash::default(),
+                    blob_tx_hash: tx_hash.clone(),
+                    hyle_output,
+                    program_id,
+                });
+        }
+
+        let mut txs: Vec<Transaction> = vec![];
+        if self.unsettled_transactions.get(&tx_hash).is_none() {
+            txs.push(tx.into());
+        }
+        txs.extend(
+            proven_blobs
+                .into_iter()
+                .map(|(contract_name, proven_blobs)| {
+                    VerifiedProofTransaction {
+                        contract_name,
+                        proof: None,
+                        proof_hash: ProofDataHash::default(),
+                        proof_size: 0,
+                        proven_blobs,
+                        is_recursive: false,
+                    }
+                    .into()
+                }),
+        );
+
+        let signed_block = SignedBlock {
+            data_proposals: vec![(LaneId::default(), vec![DataProposal::new(None, txs)])],
+            consensus_proposal: ConsensusProposal {
+                slot: self.current_height.0 + 1,
+                ..ConsensusProposal::default()
+            },
+            certificate: AggregateSignature::default(),
+        };
+
+        let mut simulation = NodeState {
+            metrics,
+            store: overlay,
+        };
+        let block = simulation.handle_signed_block(&signed_block)?;
+
+        let status = if block.successful_txs.contains(&tx_hash) {
+            SimulatedSettlementStatus::Success
+        } else if block.failed_txs.contains(&tx_hash) || block.timed_out_txs.contains(&tx_hash) {
+            SimulatedSettlementStatus::Failure
+        } else {
+            SimulatedSettlementStatus::NotSettled
+        };
+        let events = block
+            .transactions_events
+            .get(&tx_hash)
+            .cloned()
+            .unwrap_or_default();
+        let failure_reason = match status {
+            SimulatedSettlementStatus::Success => None,
+            _ => events.iter().rev().find_map(|event| match event {
+                TransactionStateEvent::Error(reason)
+                | TransactionStateEvent::SettleEvent(reason) => Some(reason.clone()),
+                _ => None,
+            }),
+        };
+
+        Ok(APISettlementSimulation {
+            tx_hash,
+            status,
+            failure_reason,
+            events,
+            registered_contracts: block
+                .registered_contracts
+                .into_iter()
+                .map(|(name, (_, effect, _))| (name, effect))
+                .collect(),
+            deleted_contracts: block.deleted_contracts.into_keys().collect(),
+            updated_states: block.updated_states,
+            updated_program_ids: block.updated_program_ids,
+            updated_timeout_windows: block.updated_timeout_windows,
+            scheduled_contract_upgrades: block.scheduled_contract_upgrades,
+        })
+    }
+
     fn get_tx_timeout_window<'a, T: IntoIterator<Item = &'a Blob>>(
         &self,
         blobs: T,
@@ -749,6 +1022,7 @@ impl NodeState {
         } else {
             Self::settle_blobs_recursively(
                 &self.contracts,
+                &self.recent_states,
                 SettlementStatus::Unknown,
                 updated_contracts,
                 unsettled_tx.blobs.values(),
@@ -799,6 +1073,7 @@ impl NodeState {
 
     fn settle_blobs_recursively<'a>(
         contracts: &HashMap<ContractName, Contract>,
+        recent_states: &RecentStates,
         mut settlement_status: SettlementStatus,
         mut contract_changes: BTreeMap<ContractName, ModifiedContractData>,
         mut blob_iter: impl Iterator<Item = &'a UnsettledBlobMetadata> + Clone,
@@ -838,6 +1113,7 @@ impl NodeState {
                     tracing::trace!("Settlement - OK side effect");
                     Self::settle_blobs_recursively(
                         contracts,
+                        recent_states,
                         settlement_status,
                         contract_changes,
                         blob_iter.clone(),
@@ -869,6 +1145,7 @@ impl NodeState {
             let mut current_contracts = contract_changes.clone();
             if let Err(msg) = Self::process_proof(
                 contracts,
+                recent_states,
                 &mut current_contracts,
                 contract_name,
                 proof_metadata,
@@ -901,6 +1178,7 @@ impl NodeState {
             tracing::trace!("Settlement - OK blob");
             let settlement_result = Self::settle_blobs_recursively(
                 contracts,
+                recent_states,
                 settlement_status.clone(),
                 current_contracts,
                 blob_iter.clone(),
@@ -1062,6 +1340,18 @@ impl NodeState {
                         .registered_contracts
                         .remove(&contract_name);
 
+                    self.recent_states.remove(&contract_name);
+
+                    if self.contract_upgrades.remove(&contract_name).is_some() {
+                        debug!(
+                            "Dropping pending upgrade of deleted contract {}",
+                            contract_name
+                        );
+                        block_under_construction
+                            .scheduled_contract_upgrades
+                            .remove(&contract_name);
+                    }
+
                     block_under_construction
                         .deleted_contracts
                         .insert(contract_name, bth.clone());
@@ -1074,9 +1364,9 @@ impl NodeState {
                         info!("📝 Registering contract {}", contract_name);
 
                         // Let's find the metadata - for now it's unsupported to register the same contract twice in a single TX.
-                        let metadata = side_effects.into_iter().find_map(|se| {
+                        let metadata = side_effects.iter().find_map(|se| {
                             if let SideEffect::Register(m) = se {
-                                Some(m)
+                                Some(m.clone())
                             } else {
                                 None
                             }
@@ -1108,8 +1398,74 @@ impl NodeState {
                             .remove(&contract_name);
                     }
 
-                    self.contracts
+                    let previous = self
+                        .contracts
                         .insert(contract.name.clone(), contract.clone());
+                    if let Some(previous) = &previous {
+                        if previous.state != contract.state {
+                            self.recent_states.record(
+                                &contract_name,
+                                previous.state.clone(),
+                                self.current_height,
+                            );
+                        }
+                    }
+                    if previous.is_none_or(|previous| {
+                        previous.program_id != contract.program_id
+                            || previous.verifier != contract.verifier
+                    }) {
+                        self.record_contract_code_version(&contract, block_under_construction);
+                    }
+
+                    for side_effect in side_effects.iter() {
+                        match side_effect {
+                            SideEffect::ScheduleUpgrade(program_id, verifier) => {
+                                let upgrade = self.contract_upgrades.schedule(
+                                    &contract_name,
+                                    program_id.clone(),
+                                    verifier.clone(),
+                                    self.current_height,
+                                );
+                                info!(
+                                    "⏳ Scheduled upgrade of '{}' to program_id {} at height {}",
+                                    &contract_name,
+                                    hex::encode(&upgrade.program_id.0),
+                                    upgrade.effective_height
+                                );
+                                block_under_construction
+                                    .cancelled_contract_upgrades
+                                    .retain(|cn| cn != &contract_name);
+                                block_under_construction
+                                    .scheduled_contract_upgrades
+                                    .insert(contract_name.clone(), upgrade);
+                            }
+                            SideEffect::CancelUpgrade(program_id, effective_height) => {
+                                let matches = self
+                                    .contract_upgrades
+                                    .pending(&contract_name)
+                                    .is_some_and(|upgrade| {
+                                        &upgrade.program_id == program_id
+                                            && &upgrade.effective_height == effective_height
+                                    });
+                                if matches {
+                                    self.contract_upgrades.cancel(&contract_name);
+                                    info!("🚫 Cancelled pending upgrade of '{}'", &contract_name);
+                                    block_under_construction
+                                        .scheduled_contract_upgrades
+                                        .remove(&contract_name);
+                                    block_under_construction
+                                        .cancelled_contract_upgrades
+                                        .push(contract_name.clone());
+                                } else {
+                                    debug!(
+                                        "No matching pending upgrade to cancel for '{}'",
+                                        &contract_name
+                                    );
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
 
                     if fields.state {
                         debug!(
@@ -1321,10 +1677,52 @@ impl NodeState {
                     hyle_output.index
                 )
             }
-        } else if let Ok(_data) =
+        } else if let Ok(data) =
             StructuredBlobData::<UpdateContractProgramIdAction>::try_from(blob.data.clone())
         {
-            // FIXME: add checks?
+            let Some(eff) = hyle_output.onchain_effects.first() else {
+                bail!(
+                    "Proof for UpdateContractProgramIdAction blob #{} does not have any onchain effects",
+                    hyle_output.index
+                )
+            };
+            if let OnchainEffect::UpgradeContract(effect) = eff {
+                if effect.contract_name != data.parameters.contract_name
+                    || effect.program_id != data.parameters.program_id
+                {
+                    bail!(
+                        "Proof for UpdateContractProgramIdAction blob #{} does not match the onchain effect",
+                        hyle_output.index
+                    )
+                }
+            } else {
+                bail!(
+                    "Proof for UpdateContractProgramIdAction blob #{} does not have an upgrade onchain effect",
+                    hyle_output.index
+                )
+            }
+        } else if let Ok(data) =
+            StructuredBlobData::<CancelContractUpgradeAction>::try_from(blob.data.clone())
+        {
+            let Some(eff) = hyle_output.onchain_effects.first() else {
+                bail!(
+                    "Proof for CancelContractUpgradeAction blob #{} does not have any onchain effects",
+                    hyle_output.index
+                )
+            };
+            if let OnchainEffect::CancelContractUpgrade(effect) = eff {
+                if effect != &CancelContractUpgradeEffect::from(data.parameters) {
+                    bail!(
+                        "Proof for CancelContractUpgradeAction blob #{} does not match the onchain effect",
+                        hyle_output.index
+                    )
+                }
+            } else {
+                bail!(
+                    "Proof for CancelContractUpgradeAction blob #{} does not have a cancel upgrade onchain effect",
+                    hyle_output.index
+                )
+            }
         }
 
         Ok(())
@@ -1350,11 +1748,33 @@ impl NodeState {
         Ok(contract)
     }
 
+    // Notes a side effect on a contract without modifying it.
+    pub(self) fn push_contract_side_effect(
+        contracts: &HashMap<ContractName, Contract>,
+        contract_changes: &mut BTreeMap<ContractName, ModifiedContractData>,
+        contract_name: &ContractName,
+        side_effect: SideEffect,
+    ) -> Result<()> {
+        let contract = Self::get_contract(contracts, contract_changes, contract_name)?.clone();
+        contract_changes
+            .entry(contract_name.clone())
+            .and_modify(|c| c.2.push(side_effect.clone()))
+            .or_insert_with(|| {
+                (
+                    Some(contract),
+                    ModifiedContractFields::default(),
+                    vec![side_effect],
+                )
+            });
+        Ok(())
+    }
+
     // Called when trying to actually settle a blob TX - processes a proof for settlement.
     // verify_hyle_output has already been called at this point.
     // Not called for the Hyle TLD.
     fn process_proof(
         contracts: &HashMap<ContractName, Contract>,
+        recent_states: &RecentStates,
         contract_changes: &mut BTreeMap<ContractName, ModifiedContractData>,
         contract_name: &ContractName,
         proof_metadata: &(ProgramId, HyleOutput),
@@ -1385,9 +1805,12 @@ impl NodeState {
             )
         }
 
+        // Reads may also target a state the other contract had within the last few blocks.
         for state_read in &proof_metadata.1.state_reads {
             let other_contract = Self::get_contract(contracts, contract_changes, &state_read.0)?;
-            if state_read.1 != other_contract.state {
+            if state_read.1 != other_contract.state
+                && !recent_states.contains(&state_read.0, &state_read.1)
+            {
                 bail!(
                     "State read {:?} does not match other contract state {:?}",
                     state_read,
@@ -1448,6 +1871,31 @@ impl NodeState {
                             )
                         });
                 }
+                OnchainEffect::UpgradeContract(effect) => {
+                    validate_contract_name_registration(&contract.name, &effect.contract_name)?;
+                    validate_program_id(&effect.verifier, &effect.program_id)?;
+                    Self::push_contract_side_effect(
+                        contracts,
+                        contract_changes,
+                        &effect.contract_name,
+                        SideEffect::ScheduleUpgrade(
+                            effect.program_id.clone(),
+                            effect.verifier.clone(),
+                        ),
+                    )?;
+                }
+                OnchainEffect::CancelContractUpgrade(effect) => {
+                    validate_contract_name_registration(&contract.name, &effect.contract_name)?;
+                    Self::push_contract_side_effect(
+                        contracts,
+                        contract_changes,
+                        &effect.contract_name,
+                        SideEffect::CancelUpgrade(
+                            effect.program_id.clone(),
+                            effect.effective_height,
+                        ),
+                    )?;
+                }
             }
         }
 
@@ -1479,6 +1927,42 @@ impl NodeState {
         Ok(())
     }
 
+    /// Applies the pending contract upgrades that are due at the current height.
+    fn apply_contract_upgrades(&mut self, block_under_construction: &mut Block) {
+        for (contract_name, upgrade) in self.contract_upgrades.take_due(self.current_height) {
+            let Some(contract) = self.contracts.get_mut(&contract_name) else {
+                debug!("Dropping upgrade of unknown contract {}", contract_name);
+                continue;
+            };
+            info!(
+                "⬆️  Upgrade '{}' to program_id {} (scheduled at height {})",
+                &contract_name,
+                hex::encode(&upgrade.program_id.0),
+                upgrade.scheduled_at
+            );
+            contract.program_id = upgrade.program_id;
+            contract.verifier = upgrade.verifier;
+            let contract = contract.clone();
+
+            block_under_construction
+                .updated_program_ids
+                .insert(contract_name, contract.program_id.clone());
+            self.record_contract_code_version(&contract, block_under_construction);
+        }
+    }
+
+    fn record_contract_code_version(
+        &mut self,
+        contract: &Contract,
+        block_under_construction: &mut Block,
+    ) {
+        if let Some(version) = self.contract_upgrades.record(contract, self.current_height) {
+            block_under_construction
+                .contract_code_versions
+                .insert(contract.name.clone(), version);
+        }
+    }
+
     /// Clear timeouts for transactions that have timed out.
     /// This happens in four steps:
     ///    1. Retrieve the transactions that have timed out
@@ -1544,6 +2028,7 @@ impl NodeState {
 pub mod test {
     mod contract_registration_tests;
     mod node_state_tests;
+    mod snapshot_tests;
 
     use super::*;
     use hyle_net::clock::TimestampMsClock;
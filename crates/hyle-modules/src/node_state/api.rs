@@ -1,4 +1,4 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -6,7 +6,15 @@ use axum::{
     Json, Router,
 };
 use client_sdk::contract_indexer::AppError;
-use sdk::{api::APINodeContract, *};
+use sdk::{
+    api::{
+        APIContractHistory, APINodeContract, APISettlementSimulation,
+        APISettlementSimulationRequest,
+    },
+    *,
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tracing::error;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
@@ -19,10 +27,18 @@ use crate::{
         SharedMessageBus,
     },
     modules::signal::ShutdownModule,
-    node_state::module::{QueryBlockHeight, QueryUnsettledTx, QueryUnsettledTxCount},
+    node_state::module::{
+        QueryBlockHeight, QueryContractHistory, QueryContractProof, QuerySettlementOverlay,
+        QueryUnsettledTx, QueryUnsettledTxCount,
+    },
 };
 
+use super::metrics::NodeStateMetrics;
 use super::module::{NodeStateCtx, QuerySettledHeight};
+use super::{NodeState, NodeStateStore};
+
+/// Settlement simulations running at once, further requests are turned down until one is done.
+const MAX_CONCURRENT_SIMULATIONS: usize = 4;
 
 bus_client! {
 struct RestBusClient {
@@ -31,12 +47,18 @@ struct RestBusClient {
     sender(Query<QueryUnsettledTxCount, u64>),
     sender(Query<QueryBlockHeight, BlockHeight>),
     sender(Query<QueryUnsettledTx, UnsettledBlobTransaction>),
+    sender(Query<QueryContractProof, ContractInclusionProof>),
+    sender(Query<QuerySettlementOverlay, NodeStateStore>),
+    sender(Query<QueryContractHistory, APIContractHistory>),
     receiver(ShutdownModule),
 }
 }
 
 pub struct RouterState {
     bus: RestBusClient,
+    simulations: Arc<Semaphore>,
+    /// Kept apart so that simulated blocks don't show up in the node state metrics.
+    simulation_metrics: NodeStateMetrics,
 }
 
 #[derive(OpenApi)]
@@ -45,16 +67,21 @@ struct NodeStateAPI;
 pub async fn api(bus: SharedMessageBus, ctx: &NodeStateCtx) -> Router<()> {
     let state = RouterState {
         bus: RestBusClient::new_from_bus(bus).await,
+        simulations: Arc::new(Semaphore::new(MAX_CONCURRENT_SIMULATIONS)),
+        simulation_metrics: NodeStateMetrics::global(ctx.node_id.clone(), "node_state_simulation"),
     };
 
     let (router, api) = OpenApiRouter::with_openapi(NodeStateAPI::openapi())
         .routes(routes!(get_block_height))
         .routes(routes!(get_contract))
+        .routes(routes!(get_contract_proof))
         .routes(routes!(get_contract_settled_height))
         .routes(routes!(get_contract_unsettled_txs_count))
         .routes(routes!(get_unsettled_txs_count))
         // TODO: figure out if we want to rely on the indexer instead
         .routes(routes!(get_unsettled_tx))
+        .routes(routes!(simulate_settlement))
+        .routes(routes!(get_contract_history))
         .split_for_parts();
 
     if let Ok(mut o) = ctx.api.openapi.lock() {
@@ -111,6 +138,90 @@ pub async fn get_contract(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/contract/{name}/proof",
+    params(
+        ("name" = String, Path, description = "Contract name")
+    ),
+    description = "The contract entry with its inclusion proof against the contract registry root after the current block. Validators sign the root in the proposal of the next slot: check the proof against it",
+    tag = "Node State",
+    responses(
+        (status = OK, body = ContractInclusionProof)
+    )
+)]
+pub async fn get_contract_proof(
+    Path(name): Path<ContractName>,
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    let name_clone = name.clone();
+    match state
+        .bus
+        .shutdown_aware_request::<()>(QueryContractProof(name))
+        .await
+    {
+        Ok(proof) => Ok(Json(proof)),
+        err => {
+            if let Err(e) = err.as_ref() {
+                if e.to_string().contains("not found") {
+                    return Err(AppError(
+                        StatusCode::NOT_FOUND,
+                        anyhow!("Contract {} not found", name_clone),
+                    ));
+                }
+            }
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Error while getting proof for contract {}", name_clone),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/contract/{name}/history",
+    params(
+        ("name" = String, Path, description = "Contract name")
+    ),
+    description = "Program IDs and verifiers the contract used, and its pending upgrade if any",
+    tag = "Node State",
+    responses(
+        (status = OK, body = APIContractHistory)
+    )
+)]
+pub async fn get_contract_history(
+    Path(name): Path<ContractName>,
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    let name_clone = name.clone();
+    match state
+        .bus
+        .shutdown_aware_request::<()>(QueryContractHistory(name))
+        .await
+    {
+        Ok(history) => Ok(Json(history)),
+        err => {
+            if let Err(e) = err.as_ref() {
+                if e.to_string().contains("not found") {
+                    return Err(AppError(
+                        StatusCode::NOT_FOUND,
+                        anyhow!("Contract {} not found", name_clone),
+                    ));
+                }
+            }
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Error while getting history for contract {}", name_clone),
+            ))
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/contract/{name}/settled_height",
@@ -243,6 +354,84 @@ pub async fn get_unsettled_tx(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/tx/simulate_settlement",
+    description = "Runs a blob transaction and candidate outputs or proofs through a copy of the node state, without submitting anything. Only a few simulations run at once, others get 429",
+    tag = "Node State",
+    request_body = APISettlementSimulationRequest,
+    responses(
+        (status = OK, body = APISettlementSimulation),
+        (status = TOO_MANY_REQUESTS, description = "Too many simulations running")
+    )
+)]
+pub async fn simulate_settlement(
+    State(mut state): State<RouterState>,
+    Json(request): Json<APISettlementSimulationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let Ok(_permit) = state.simulations.clone().try_acquire_owned() else {
+        return Err(AppError(
+            StatusCode::TOO_MANY_REQUESTS,
+            anyhow!("Too many settlement simulations running, retry later"),
+        ));
+    };
+    let mut hyle_outputs = request.hyle_outputs;
+    for proof_tx in request.proofs {
+        let contract_name = proof_tx.contract_name.clone();
+        let Ok((_, contract)) = state
+            .bus
+            .shutdown_aware_request::<()>(contract_name.clone())
+            .await
+        else {
+            return Err(AppError(
+                StatusCode::NOT_FOUND,
+                anyhow!("Contract {} not found", contract_name),
+            ));
+        };
+        // Proof verification can be slow, keep it off the async runtime.
+        let outputs = tokio::task::spawn_blocking(move || {
+            hyle_verifiers::verify(&contract.verifier, &proof_tx.proof, &contract.program_id)
+        })
+        .await
+        .context("Verifying proof")?
+        .map_err(|e| {
+            AppError(
+                StatusCode::BAD_REQUEST,
+                anyhow!("Invalid proof for contract {}: {:#}", contract_name, e),
+            )
+        })?;
+        hyle_outputs.extend(outputs);
+    }
+
+    // The node state only copies what the settlement touches, the simulation itself runs here.
+    let overlay = state
+        .bus
+        .shutdown_aware_request::<()>(QuerySettlementOverlay {
+            tx: request.tx.clone(),
+            hyle_outputs: hyle_outputs.clone(),
+        })
+        .await
+        .context("Getting settlement overlay")?;
+    let metrics = state.simulation_metrics.clone();
+    let simulation = tokio::task::spawn_blocking(move || {
+        NodeState {
+            metrics: metrics.clone(),
+            store: overlay,
+        }
+        .simulate_settlement(metrics, request.tx, hyle_outputs)
+    })
+    .await
+    .context("Simulating settlement")?;
+
+    match simulation {
+        Ok(simulation) => Ok(Json(simulation)),
+        Err(e) => Err(AppError(
+            StatusCode::BAD_REQUEST,
+            anyhow!("Error while simulating settlement: {:#}", e),
+        )),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/da/block/height",
@@ -301,8 +490,28 @@ impl Clone for RouterState {
                     >,
                 >::get(&self.bus)
                 .clone(),
+                Pick::<
+                    tokio::sync::broadcast::Sender<
+                        Query<QueryContractProof, ContractInclusionProof>,
+                    >,
+                >::get(&self.bus)
+                .clone(),
+                Pick::<
+                    tokio::sync::broadcast::Sender<
+                        Query<QuerySettlementOverlay, NodeStateStore>,
+                    >,
+                >::get(&self.bus)
+                .clone(),
+                Pick::<
+                    tokio::sync::broadcast::Sender<
+                        Query<QueryContractHistory, APIContractHistory>,
+                    >,
+                >::get(&self.bus)
+                .clone(),
                 Pick::<tokio::sync::broadcast::Receiver<ShutdownModule>>::get(&self.bus).resubscribe(),
             ),
+            simulations: self.simulations.clone(),
+            simulation_metrics: self.simulation_metrics.clone(),
         }
     }
 }
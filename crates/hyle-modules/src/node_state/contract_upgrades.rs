@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sdk::{
+    BlockHeight, Contract, ContractCodeVersion, ContractName, PendingContractUpgrade, ProgramId,
+    Verifier,
+};
+
+/// Default number of blocks between the scheduling of a contract upgrade and its application.
+pub const DEFAULT_CONTRACT_UPGRADE_DELAY: BlockHeight = BlockHeight(100);
+
+/// Number of code versions kept per contract. Older versions are dropped first.
+pub const MAX_CODE_VERSIONS_PER_CONTRACT: usize = 32;
+
+/// Pending program ID / verifier upgrades, and the code history of every contract.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ContractUpgrades {
+    /// Number of blocks an upgrade waits before being applied.
+    /// This must be the same on every node, as it changes which program can settle transactions.
+    pub delay: BlockHeight,
+    pending: BTreeMap<ContractName, PendingContractUpgrade>,
+    history: HashMap<ContractName, Vec<ContractCodeVersion>>,
+}
+
+impl Default for ContractUpgrades {
+    fn default() -> Self {
+        ContractUpgrades {
+            delay: DEFAULT_CONTRACT_UPGRADE_DELAY,
+            pending: BTreeMap::new(),
+            history: HashMap::new(),
+        }
+    }
+}
+
+impl ContractUpgrades {
+    /// Schedule an upgrade, replacing any upgrade already pending for this contract.
+    pub fn schedule(
+        &mut self,
+        contract_name: &ContractName,
+        program_id: ProgramId,
+        verifier: Verifier,
+        current_height: BlockHeight,
+    ) -> PendingContractUpgrade {
+        let upgrade = PendingContractUpgrade {
+            program_id,
+            verifier,
+            scheduled_at: current_height,
+            effective_height: current_height + self.delay,
+        };
+        self.pending.insert(contract_name.clone(), upgrade.clone());
+        upgrade
+    }
+
+    pub fn cancel(&mut self, contract_name: &ContractName) -> Option<PendingContractUpgrade> {
+        self.pending.remove(contract_name)
+    }
+
+    pub fn pending(&self, contract_name: &ContractName) -> Option<&PendingContractUpgrade> {
+        self.pending.get(contract_name)
+    }
+
+    /// Removes and returns the upgrades that are due at `current_height`.
+    pub fn take_due(
+        &mut self,
+        current_height: BlockHeight,
+    ) -> Vec<(ContractName, PendingContractUpgrade)> {
+        let due: Vec<ContractName> = self
+            .pending
+            .iter()
+            .filter(|(_, upgrade)| upgrade.effective_height <= current_height)
+            .map(|(name, _)| name.clone())
+            .collect();
+        due.into_iter()
+            .filter_map(|name| self.pending.remove(&name).map(|upgrade| (name, upgrade)))
+            .collect()
+    }
+
+    /// Records the code the contract runs from `current_height` on.
+    /// Returns the new version, or None if the code is the same as the last recorded one.
+    pub fn record(
+        &mut self,
+        contract: &Contract,
+        current_height: BlockHeight,
+    ) -> Option<ContractCodeVersion> {
+        let history = self.history.entry(contract.name.clone()).or_default();
+        if history.last().is_some_and(|last| {
+            last.program_id == contract.program_id && last.verifier == contract.verifier
+        }) {
+            return None;
+        }
+        let version = ContractCodeVersion {
+            program_id: contract.program_id.clone(),
+            verifier: contract.verifier.clone(),
+            effective_height: current_height,
+        };
+        history.push(version.clone());
+        if history.len() > MAX_CODE_VERSIONS_PER_CONTRACT {
+            history.drain(..history.len() - MAX_CODE_VERSIONS_PER_CONTRACT);
+        }
+        Some(version)
+    }
+
+    /// Forgets the pending upgrade and the code history of a deleted contract.
+    /// Returns the pending upgrade, if any.
+    pub fn remove(&mut self, contract_name: &ContractName) -> Option<PendingContractUpgrade> {
+        self.history.remove(contract_name);
+        self.pending.remove(contract_name)
+    }
+
+    /// Copies the pending upgrades and history of `contracts` only.
+    pub fn subset(&self, contracts: &BTreeSet<ContractName>) -> ContractUpgrades {
+        ContractUpgrades {
+            delay: self.delay,
+            pending: contracts
+                .iter()
+                .filter_map(|name| Some((name.clone(), self.pending.get(name)?.clone())))
+                .collect(),
+            history: contracts
+                .iter()
+                .filter_map(|name| Some((name.clone(), self.history.get(name)?.clone())))
+                .collect(),
+        }
+    }
+
+    /// Code versions of the contract, oldest first.
+    pub fn history(&self, contract_name: &ContractName) -> &[ContractCodeVersion] {
+        self.history
+            .get(contract_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(program_id: u8) -> Contract {
+        Contract {
+            name: "c1".into(),
+            program_id: ProgramId(vec![program_id]),
+            verifier: "test".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_schedule_and_take_due() {
+        let mut upgrades = ContractUpgrades {
+            delay: BlockHeight(10),
+            ..Default::default()
+        };
+        let c1: ContractName = "c1".into();
+        let upgrade = upgrades.schedule(&c1, ProgramId(vec![1]), "test".into(), BlockHeight(5));
+        assert_eq!(upgrade.effective_height, BlockHeight(15));
+
+        assert!(upgrades.take_due(BlockHeight(14)).is_empty());
+        assert_eq!(
+            upgrades.take_due(BlockHeight(15)),
+            vec![(c1.clone(), upgrade)]
+        );
+        assert!(upgrades.pending(&c1).is_none());
+
+        upgrades.schedule(&c1, ProgramId(vec![2]), "test".into(), BlockHeight(20));
+        assert!(upgrades.cancel(&c1).is_some());
+        assert!(upgrades.take_due(BlockHeight(100)).is_empty());
+    }
+
+    #[test]
+    fn test_record_history() {
+        let mut upgrades = ContractUpgrades::default();
+        assert!(upgrades.record(&contract(0), BlockHeight(1)).is_some());
+        assert!(upgrades.record(&contract(0), BlockHeight(2)).is_none());
+        assert!(upgrades.record(&contract(1), BlockHeight(3)).is_some());
+        assert_eq!(
+            upgrades
+                .history(&"c1".into())
+                .iter()
+                .map(|v| v.effective_height)
+                .collect::<Vec<_>>(),
+            vec![BlockHeight(1), BlockHeight(3)]
+        );
+        assert!(upgrades.history(&"c2".into()).is_empty());
+
+        for program_id in 2..=(MAX_CODE_VERSIONS_PER_CONTRACT as u8 + 5) {
+            upgrades.record(&contract(program_id), BlockHeight(program_id as u64 + 2));
+        }
+        let history = upgrades.history(&"c1".into());
+        assert_eq!(history.len(), MAX_CODE_VERSIONS_PER_CONTRACT);
+        assert_eq!(
+            history.last().map(|v| v.program_id.clone()),
+            Some(ProgramId(vec![MAX_CODE_VERSIONS_PER_CONTRACT as u8 + 5]))
+        );
+
+        assert!(upgrades.remove(&"c1".into()).is_none());
+        assert!(upgrades.history(&"c1".into()).is_empty());
+    }
+}
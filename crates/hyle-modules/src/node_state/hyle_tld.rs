@@ -3,6 +3,7 @@ use crate::node_state::{
     ModifiedContractFields, NodeState, NukeTxAction,
 };
 use anyhow::{bail, Result};
+use hyle_verifiers::validate_program_id;
 use sdk::secp256k1::CheckSecp256k1;
 use sdk::*;
 use std::collections::{BTreeMap, HashMap};
@@ -31,6 +32,10 @@ pub fn handle_blob_for_hyle_tld(
         StructuredBlobData::<UpdateContractProgramIdAction>::try_from(current_blob.data.clone())
     {
         handle_update_program_id_blob(contracts, contract_changes, &reg.parameters)?;
+    } else if let Ok(reg) =
+        StructuredBlobData::<CancelContractUpgradeAction>::try_from(current_blob.data.clone())
+    {
+        handle_cancel_upgrade_blob(contracts, contract_changes, &reg.parameters)?;
     } else if let Ok(reg) =
         StructuredBlobData::<UpdateContractTimeoutWindowAction>::try_from(current_blob.data.clone())
     {
@@ -119,33 +124,31 @@ fn handle_update_program_id_blob(
         bail!("Cannot udpate Hyli contract");
     }
 
-    let contract =
-        NodeState::get_contract(contracts, contract_changes, &update.contract_name)?.clone();
+    let verifier = NodeState::get_contract(contracts, contract_changes, &update.contract_name)?
+        .verifier
+        .clone();
+    validate_program_id(&verifier, &update.program_id)?;
 
-    let new_update = SideEffect::UpdateProgramId;
-    contract_changes
-        .entry(update.contract_name.clone())
-        .and_modify(|c| {
-            if let Some(contract) = c.0.as_mut() {
-                contract.program_id = update.program_id.clone();
-            }
-            c.1.program_id = true;
-            c.2.push(new_update.clone());
-        })
-        .or_insert_with(|| {
-            (
-                Some(Contract {
-                    program_id: update.program_id.clone(),
-                    ..contract
-                }),
-                ModifiedContractFields {
-                    program_id: true,
-                    ..ModifiedContractFields::default()
-                },
-                vec![new_update],
-            )
-        });
-    Ok(())
+    // The program ID isn't changed here: the upgrade is applied once the delay has passed.
+    NodeState::push_contract_side_effect(
+        contracts,
+        contract_changes,
+        &update.contract_name,
+        SideEffect::ScheduleUpgrade(update.program_id.clone(), verifier),
+    )
+}
+
+fn handle_cancel_upgrade_blob(
+    contracts: &HashMap<ContractName, Contract>,
+    contract_changes: &mut BTreeMap<ContractName, ModifiedContractData>,
+    cancel: &CancelContractUpgradeAction,
+) -> Result<()> {
+    NodeState::push_contract_side_effect(
+        contracts,
+        contract_changes,
+        &cancel.contract_name,
+        SideEffect::CancelUpgrade(cancel.program_id.clone(), cancel.effective_height),
+    )
 }
 
 fn handle_update_timeout_window_blob(
@@ -202,6 +205,8 @@ pub fn validate_hyle_contract_blobs(tx: &BlobTransaction) -> Result<(), String>
             // Check identity authorization for privileged actions
             if StructuredBlobData::<UpdateContractProgramIdAction>::try_from(blob.data.clone())
                 .is_ok()
+                || StructuredBlobData::<CancelContractUpgradeAction>::try_from(blob.data.clone())
+                    .is_ok()
                 || StructuredBlobData::<DeleteContractAction>::try_from(blob.data.clone()).is_ok()
                 || StructuredBlobData::<UpdateContractTimeoutWindowAction>::try_from(
                     blob.data.clone(),
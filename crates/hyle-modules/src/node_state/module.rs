@@ -1,16 +1,19 @@
 //! State required for participation in consensus by the node.
 
 use super::metrics::NodeStateMetrics;
+use super::snapshot::{self, NodeStateSnapshotConf};
 use super::{NodeState, NodeStateStore};
 use crate::bus::SharedMessageBus;
 use crate::bus::{command_response::Query, BusClientSender};
 use crate::log_error;
 use crate::module_handle_messages;
 use crate::modules::{module_bus_client, Module, SharedBuildApiCtx};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use sdk::api::APIContractHistory;
 use sdk::*;
 use std::path::PathBuf;
-use tracing::info;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
 /// NodeStateModule maintains a NodeState,
 /// listens to DA, and sends events when it has processed blocks.
@@ -20,6 +23,9 @@ pub struct NodeStateModule {
     bus: NodeStateBusClient,
     inner: NodeState,
     data_directory: PathBuf,
+    snapshots: NodeStateSnapshotConf,
+    /// Snapshot being written in the background, if any.
+    snapshot_task: Option<JoinHandle<()>>,
 }
 
 pub use sdk::NodeStateEvent;
@@ -36,6 +42,25 @@ pub struct QueryUnsettledTxCount(pub Option<ContractName>);
 #[derive(Clone)]
 pub struct QueryUnsettledTx(pub TxHash);
 
+#[derive(Clone)]
+pub struct QueryContractProof(pub ContractName);
+
+#[derive(Clone)]
+pub struct QueryContractHistory(pub ContractName);
+
+/// Asks for the contract registry root after the given block, once it is the last one handled.
+/// Consensus commits to it in the proposal of the next slot.
+#[derive(Clone)]
+pub struct QueryContractRegistryRoot(pub ConsensusProposalHash);
+
+/// Asks for the [NodeStateStore::settlement_overlay] of a transaction, to simulate its settlement
+/// without holding up the node state.
+#[derive(Clone)]
+pub struct QuerySettlementOverlay {
+    pub tx: BlobTransaction,
+    pub hyle_outputs: Vec<HyleOutput>,
+}
+
 module_bus_client! {
 #[derive(Debug)]
 pub struct NodeStateBusClient {
@@ -46,6 +71,10 @@ pub struct NodeStateBusClient {
     receiver(Query<QueryUnsettledTxCount, u64>),
     receiver(Query<QueryBlockHeight , BlockHeight>),
     receiver(Query<QueryUnsettledTx, UnsettledBlobTransaction>),
+    receiver(Query<QueryContractProof, ContractInclusionProof>),
+    receiver(Query<QueryContractHistory, APIContractHistory>),
+    receiver(Query<QueryContractRegistryRoot, Option<MerkleHash>>),
+    receiver(Query<QuerySettlementOverlay, NodeStateStore>),
 }
 }
 
@@ -53,6 +82,11 @@ pub struct NodeStateCtx {
     pub node_id: String,
     pub data_directory: PathBuf,
     pub api: SharedBuildApiCtx,
+    pub snapshots: NodeStateSnapshotConf,
+    /// Number of blocks before a scheduled contract upgrade is applied.
+    pub contract_upgrade_delay: u64,
+    /// Number of blocks a superseded contract state remains readable by proofs.
+    pub state_read_window: u64,
 }
 
 impl Module for NodeStateModule {
@@ -67,21 +101,42 @@ impl Module for NodeStateModule {
         }
         let metrics = NodeStateMetrics::global(ctx.node_id.clone(), "node_state");
 
-        let store = Self::load_from_disk_or_default::<NodeStateStore>(
-            ctx.data_directory.join("node_state.bin").as_path(),
-        );
+        let node_state_file = ctx.data_directory.join("node_state.bin");
+        let store = match Self::load_from_disk::<NodeStateStore>(&node_state_file) {
+            Some(store) => store,
+            None => match ctx
+                .snapshots
+                .bootstrap_snapshot()
+                .context("Loading bootstrap snapshot")?
+            {
+                Some((header, store)) => {
+                    info!(
+                        "📸 Bootstrapping node state from snapshot at height {} ({})",
+                        header.block_height, header.block_hash
+                    );
+                    // Save right away so that a restart doesn't bootstrap again while DA has moved on.
+                    Self::save_on_disk(&node_state_file, &store)?;
+                    store
+                }
+                None => NodeStateStore::default(),
+            },
+        };
 
         for name in store.contracts.keys() {
             info!("📝 Loaded contract state for {}", name);
         }
 
-        let node_state = NodeState { store, metrics };
+        let mut node_state = NodeState { store, metrics };
+        node_state.set_contract_upgrade_delay(BlockHeight(ctx.contract_upgrade_delay));
+        node_state.set_state_read_window(BlockHeight(ctx.state_read_window));
         let bus = NodeStateBusClient::new_from_bus(bus.new_handle()).await;
 
         Ok(Self {
             bus,
             inner: node_state,
             data_directory: ctx.data_directory,
+            snapshots: ctx.snapshots,
+            snapshot_task: None,
         })
     }
 
@@ -98,6 +153,27 @@ impl Module for NodeStateModule {
                     None => Err(anyhow::anyhow!("Contract {} not found", cmd)),
                 }
             }
+            command_response<QueryContractProof, ContractInclusionProof> cmd => {
+                self.inner.contract_proof(&cmd.0)
+                    .ok_or_else(|| anyhow::anyhow!("Contract {} not found", cmd.0))
+            }
+            command_response<QueryContractHistory, APIContractHistory> cmd => {
+                let history = self.inner.contract_upgrades.history(&cmd.0).to_vec();
+                if history.is_empty() && !self.inner.contracts.contains_key(&cmd.0) {
+                    return Err(anyhow::anyhow!("Contract {} not found", cmd.0));
+                }
+                Ok(APIContractHistory {
+                    contract_name: cmd.0.clone(),
+                    pending_upgrade: self.inner.contract_upgrades.pending(&cmd.0).cloned(),
+                    history,
+                })
+            }
+            command_response<QueryContractRegistryRoot, Option<MerkleHash>> cmd => {
+                Ok(self.inner.contract_registry_root_after(&cmd.0))
+            }
+            command_response<QuerySettlementOverlay, NodeStateStore> cmd => {
+                Ok(self.inner.settlement_overlay(&cmd.tx, &cmd.hyle_outputs))
+            }
             command_response<QuerySettledHeight, BlockHeight> cmd => {
                 if !self.inner.contracts.contains_key(&cmd.0) {
                     return Err(anyhow::anyhow!("Contract {} not found", cmd.0));
@@ -124,6 +200,9 @@ impl Module for NodeStateModule {
                     DataEvent::OrderedSignedBlock(block) => {
                         // TODO: If we are in a broken state, this will likely kill the node every time.
                         let node_state_block = self.inner.handle_signed_block(&block)?;
+                        if self.snapshots.interval > 0 && node_state_block.block_height.0 % self.snapshots.interval == 0 {
+                            self.write_snapshot(node_state_block.hash.clone());
+                        }
                         _ = log_error!(self
                             .bus
                             .send(NodeStateEvent::NewBlock(Box::new(node_state_block))), "Sending DataEvent while processing SignedBlock");
@@ -136,6 +215,9 @@ impl Module for NodeStateModule {
     }
 
     async fn persist(&mut self) -> Result<()> {
+        if let Some(task) = self.snapshot_task.take() {
+            _ = log_error!(task.await, "Waiting for node state snapshot");
+        }
         log_error!(
             Self::save_on_disk::<NodeStateStore>(
                 self.data_directory.join("node_state.bin").as_path(),
@@ -145,3 +227,35 @@ impl Module for NodeStateModule {
         )
     }
 }
+
+impl NodeStateModule {
+    /// Writes a snapshot of the current store from a blocking task, so that serializing it doesn't
+    /// hold up block processing. Skipped if the previous snapshot is still being written.
+    fn write_snapshot(&mut self, block_hash: ConsensusProposalHash) {
+        if self
+            .snapshot_task
+            .as_ref()
+            .is_some_and(|task| !task.is_finished())
+        {
+            warn!(
+                "Skipping node state snapshot at {}: previous snapshot is still being written",
+                self.inner.current_height
+            );
+            return;
+        }
+        let directory = snapshot::snapshots_directory(&self.data_directory);
+        let store = self.inner.store.clone();
+        let keep = self.snapshots.keep;
+        self.snapshot_task = Some(tokio::task::spawn_blocking(move || {
+            _ = log_error!(
+                snapshot::write_snapshot(&directory, &store, block_hash).and_then(|_| {
+                    if keep > 0 {
+                        snapshot::prune_snapshots(&directory, keep)?;
+                    }
+                    Ok(())
+                }),
+                "Writing node state snapshot"
+            );
+        }));
+    }
+}
@@ -73,17 +73,23 @@ impl OrderedTxMap {
     }
 
     pub fn get_contracts_blocked_by_tx(tx: &UnsettledBlobTransaction) -> HashSet<ContractName> {
+        Self::get_contracts_blocked_by_blobs(tx.blobs.values().map(|b| &b.blob))
+    }
+
+    pub fn get_contracts_blocked_by_blobs<'a>(
+        blobs: impl Iterator<Item = &'a Blob>,
+    ) -> HashSet<ContractName> {
         // Collect into a hashset for unicity
         let mut contract_names = HashSet::new();
-        for blob in tx.blobs.values() {
-            contract_names.insert(blob.blob.contract_name.clone());
+        for blob in blobs {
+            contract_names.insert(blob.contract_name.clone());
             // This is a bit horrible, we should try to be leaner.
             if let Ok(data) =
-                StructuredBlobData::<RegisterContractAction>::try_from(blob.blob.data.clone())
+                StructuredBlobData::<RegisterContractAction>::try_from(blob.data.clone())
             {
                 contract_names.insert(data.parameters.contract_name.clone());
             } else if let Ok(data) =
-                StructuredBlobData::<DeleteContractAction>::try_from(blob.blob.data.clone())
+                StructuredBlobData::<DeleteContractAction>::try_from(blob.data.clone())
             {
                 contract_names.insert(data.parameters.contract_name.clone());
             }
@@ -91,6 +97,35 @@ impl OrderedTxMap {
         contract_names
     }
 
+    /// Copies the txs queued on `contracts`, and transitively on every other contract those txs
+    /// block, so that each copied queue is complete. `contracts` is extended with the contracts
+    /// the copy covers.
+    pub fn subset(&self, contracts: &mut BTreeSet<ContractName>) -> OrderedTxMap {
+        let mut subset = OrderedTxMap::default();
+        let mut to_visit: Vec<ContractName> = contracts.iter().cloned().collect();
+        while let Some(contract) = to_visit.pop() {
+            let Some(queue) = self.tx_order.get(&contract) else {
+                continue;
+            };
+            for tx_hash in queue {
+                if subset.map.contains_key(tx_hash) {
+                    continue;
+                }
+                let Some(tx) = self.map.get(tx_hash) else {
+                    continue;
+                };
+                for blocked in Self::get_contracts_blocked_by_tx(tx) {
+                    if contracts.insert(blocked.clone()) {
+                        to_visit.push(blocked);
+                    }
+                }
+                subset.map.insert(tx_hash.clone(), tx.clone());
+            }
+            subset.tx_order.insert(contract, queue.clone());
+        }
+        subset
+    }
+
     pub fn get_next_txs_blocked_by_tx(&self, tx: &UnsettledBlobTransaction) -> BTreeSet<TxHash> {
         let mut blocked_txs = BTreeSet::new();
         for contract in Self::get_contracts_blocked_by_tx(tx) {
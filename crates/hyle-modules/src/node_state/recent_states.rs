@@ -0,0 +1,137 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use sdk::{BlockHeight, ContractName, StateCommitment};
+
+/// Default number of blocks a superseded contract state stays readable by proofs.
+pub const DEFAULT_STATE_READ_WINDOW: BlockHeight = BlockHeight(10);
+
+/// Maximum number of superseded states kept per contract, whatever the window.
+const MAX_RECENT_STATES_PER_CONTRACT: usize = 64;
+
+/// Ring of the recently superseded states of each contract.
+/// A proof may read any of these states instead of the current one,
+/// so that cross-contract reads don't get invalidated as soon as the other contract moves.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct RecentStates {
+    /// Number of blocks a superseded state remains valid for state reads.
+    /// This must be the same on every node, as it changes which proofs can settle.
+    pub window: BlockHeight,
+    states: HashMap<ContractName, VecDeque<(StateCommitment, BlockHeight)>>,
+}
+
+impl Default for RecentStates {
+    fn default() -> Self {
+        RecentStates {
+            window: DEFAULT_STATE_READ_WINDOW,
+            states: HashMap::new(),
+        }
+    }
+}
+
+impl RecentStates {
+    /// Records that `state` stopped being the state of the contract at `current_height`.
+    pub fn record(
+        &mut self,
+        contract_name: &ContractName,
+        state: StateCommitment,
+        current_height: BlockHeight,
+    ) {
+        if self.window.0 == 0 {
+            return;
+        }
+        let ring = self.states.entry(contract_name.clone()).or_default();
+        if ring.len() == MAX_RECENT_STATES_PER_CONTRACT {
+            ring.pop_front();
+        }
+        ring.push_back((state, current_height));
+    }
+
+    /// Drops the states that were superseded outside of the window.
+    /// Called once per block, so that every state left is readable at `current_height`.
+    pub fn prune(&mut self, current_height: BlockHeight) {
+        let window = self.window;
+        self.states.retain(|_, ring| {
+            while ring
+                .front()
+                .is_some_and(|(_, superseded_at)| *superseded_at + window < current_height)
+            {
+                ring.pop_front();
+            }
+            !ring.is_empty()
+        });
+    }
+
+    /// Copies the states of `contracts` only.
+    pub fn subset(&self, contracts: &BTreeSet<ContractName>) -> RecentStates {
+        RecentStates {
+            window: self.window,
+            states: contracts
+                .iter()
+                .filter_map(|name| Some((name.clone(), self.states.get(name)?.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn remove(&mut self, contract_name: &ContractName) {
+        self.states.remove(contract_name);
+    }
+
+    pub fn contains(&self, contract_name: &ContractName, state: &StateCommitment) -> bool {
+        self.states
+            .get(contract_name)
+            .is_some_and(|ring| ring.iter().any(|(s, _)| s == state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prune_outside_window() {
+        let mut recent = RecentStates {
+            window: BlockHeight(2),
+            ..Default::default()
+        };
+        let c1: ContractName = "c1".into();
+        recent.record(&c1, StateCommitment(vec![1]), BlockHeight(1));
+        recent.record(&c1, StateCommitment(vec![2]), BlockHeight(2));
+
+        recent.prune(BlockHeight(3));
+        assert!(recent.contains(&c1, &StateCommitment(vec![1])));
+
+        recent.prune(BlockHeight(4));
+        assert!(!recent.contains(&c1, &StateCommitment(vec![1])));
+        assert!(recent.contains(&c1, &StateCommitment(vec![2])));
+
+        recent.prune(BlockHeight(5));
+        assert!(!recent.contains(&c1, &StateCommitment(vec![2])));
+        assert!(recent.states.is_empty());
+    }
+
+    #[test]
+    fn test_ring_is_bounded() {
+        let mut recent = RecentStates {
+            window: BlockHeight(1000),
+            ..Default::default()
+        };
+        let c1: ContractName = "c1".into();
+        for i in 0..=MAX_RECENT_STATES_PER_CONTRACT {
+            recent.record(&c1, StateCommitment(vec![i as u8]), BlockHeight(1));
+        }
+        assert!(!recent.contains(&c1, &StateCommitment(vec![0])));
+        assert!(recent.contains(&c1, &StateCommitment(vec![1])));
+    }
+
+    #[test]
+    fn test_zero_window_disables() {
+        let mut recent = RecentStates {
+            window: BlockHeight(0),
+            ..Default::default()
+        };
+        let c1: ContractName = "c1".into();
+        recent.record(&c1, StateCommitment(vec![1]), BlockHeight(1));
+        assert!(!recent.contains(&c1, &StateCommitment(vec![1])));
+    }
+}
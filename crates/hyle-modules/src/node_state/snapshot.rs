@@ -0,0 +1,264 @@
+//! Versioned, checksummed snapshots of the node state.
+//!
+//! A snapshot file is the borsh-encoded [NodeStateSnapshotHeader] followed by the
+//! borsh-encoded [NodeStateStore]. The header can be read on its own, which lets
+//! us list snapshots without decoding the whole store.
+//!
+//! The checksum only catches corrupted files. What ties a snapshot's contents to the chain is
+//! its state root (see [state_root]), recomputed from the decoded store on load: operators
+//! bootstrapping from an untrusted source should get it from a node they trust and set it as
+//! `trusted_state_root`.
+
+use std::{
+    fs,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use sdk::{
+    contract_registry_root, hash_merkle_leaf, merkle_root, BlockHeight, ConsensusProposalHash,
+    MerkleHash,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use tracing::{debug, info, warn};
+
+use super::NodeStateStore;
+
+/// Bumped whenever the layout of the snapshot file (or of NodeStateStore) changes.
+pub const NODE_STATE_SNAPSHOT_VERSION: u32 = 5;
+
+/// Name of the directory, inside the data directory, where snapshots are written.
+pub const SNAPSHOTS_DIRECTORY: &str = "node_state_snapshots";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NodeStateSnapshotConf {
+    /// Write a snapshot every `interval` blocks. 0 disables snapshots.
+    pub interval: u64,
+    /// Number of snapshots kept on disk, older ones are removed first. 0 keeps all of them.
+    pub keep: usize,
+    /// Snapshot file to bootstrap from when the node has no local state.
+    pub bootstrap_from: Option<PathBuf>,
+    /// If set, the bootstrap snapshot must have been taken at this block.
+    pub trusted_block_hash: Option<ConsensusProposalHash>,
+    /// If set, the state root (hex) the bootstrap snapshot's store must hash to.
+    pub trusted_state_root: Option<String>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NodeStateSnapshotHeader {
+    pub version: u32,
+    /// Height of the last block processed by the node state.
+    pub block_height: BlockHeight,
+    /// Hash of the last block processed by the node state.
+    pub block_hash: ConsensusProposalHash,
+    /// Hex-encoded [state_root] of the store following the header.
+    pub state_root: String,
+    /// Sha3-256 of the borsh-encoded store following the header.
+    pub checksum: Vec<u8>,
+}
+
+impl NodeStateSnapshotHeader {
+    pub fn file_name(block_height: BlockHeight) -> String {
+        format!("node_state_{:020}.bin", block_height.0)
+    }
+}
+
+pub fn snapshots_directory(data_directory: &Path) -> PathBuf {
+    data_directory.join(SNAPSHOTS_DIRECTORY)
+}
+
+fn checksum(store_bytes: &[u8]) -> Vec<u8> {
+    Sha3_256::digest(store_bytes).to_vec()
+}
+
+/// Merkle root over the parts of the store: the contract registry root (the same one blocks
+/// expose), then the hashes of the height, unsettled transactions, timeouts, pending upgrades
+/// and recent states. Every node that processed the same blocks computes the same root.
+pub fn state_root(store: &NodeStateStore) -> Result<MerkleHash> {
+    let part = |bytes: Vec<u8>| hash_merkle_leaf(&bytes);
+    let leaves = vec![
+        hash_merkle_leaf(&contract_registry_root(&store.contracts)),
+        part(borsh::to_vec(&store.current_height)?),
+        part(borsh::to_vec(&store.unsettled_transactions)?),
+        part(borsh::to_vec(&store.timeouts)?),
+        part(borsh::to_vec(&store.contract_upgrades)?),
+        part(borsh::to_vec(&store.recent_states)?),
+    ];
+    Ok(merkle_root(&leaves))
+}
+
+/// Writes a snapshot of the store in `directory`, and returns its header.
+/// This serializes the whole store: call it off the node state loop.
+pub fn write_snapshot(
+    directory: &Path,
+    store: &NodeStateStore,
+    block_hash: ConsensusProposalHash,
+) -> Result<NodeStateSnapshotHeader> {
+    let store_bytes = borsh::to_vec(store).context("Serializing node state")?;
+    let header = NodeStateSnapshotHeader {
+        version: NODE_STATE_SNAPSHOT_VERSION,
+        block_height: store.current_height,
+        block_hash,
+        state_root: hex::encode(state_root(store)?),
+        checksum: checksum(&store_bytes),
+    };
+
+    fs::create_dir_all(directory).context("Creating snapshots directory")?;
+    let file = directory.join(NodeStateSnapshotHeader::file_name(header.block_height));
+    let tmp = file.with_extension("tmp");
+
+    let mut writer = BufWriter::new(fs::File::create(&tmp).context("Creating snapshot file")?);
+    borsh::to_writer(&mut writer, &header).context("Writing snapshot header")?;
+    writer
+        .write_all(&store_bytes)
+        .context("Writing snapshot store")?;
+    writer.flush().context("Flushing snapshot file")?;
+    fs::rename(&tmp, &file).context("Renaming snapshot file")?;
+
+    info!(
+        "📸 Wrote node state snapshot at height {} ({})",
+        header.block_height, header.block_hash
+    );
+    Ok(header)
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<NodeStateSnapshotHeader> {
+    let header =
+        NodeStateSnapshotHeader::deserialize_reader(reader).context("Decoding snapshot header")?;
+    if header.version != NODE_STATE_SNAPSHOT_VERSION {
+        bail!(
+            "Unsupported snapshot version {} (expected {})",
+            header.version,
+            NODE_STATE_SNAPSHOT_VERSION
+        );
+    }
+    Ok(header)
+}
+
+/// Reads only the header of a snapshot file.
+pub fn read_snapshot_header(file: &Path) -> Result<NodeStateSnapshotHeader> {
+    let mut reader = BufReader::new(
+        fs::File::open(file).context(format!("Opening snapshot {}", file.display()))?,
+    );
+    read_header(&mut reader)
+}
+
+/// Reads a snapshot file, checking its version, checksum, height and state root.
+pub fn read_snapshot(file: &Path) -> Result<(NodeStateSnapshotHeader, NodeStateStore)> {
+    let mut reader = BufReader::new(
+        fs::File::open(file).context(format!("Opening snapshot {}", file.display()))?,
+    );
+    let header = read_header(&mut reader)?;
+
+    let mut store_bytes = vec![];
+    reader
+        .read_to_end(&mut store_bytes)
+        .context("Reading snapshot store")?;
+    if checksum(&store_bytes) != header.checksum {
+        bail!("Snapshot checksum mismatch for {}", file.display());
+    }
+
+    let store: NodeStateStore =
+        borsh::from_slice(&store_bytes).context("Decoding snapshot store")?;
+    if store.current_height != header.block_height {
+        bail!(
+            "Snapshot header height {} does not match store height {}",
+            header.block_height,
+            store.current_height
+        );
+    }
+    let store_root = hex::encode(state_root(&store)?);
+    if store_root != header.state_root {
+        bail!(
+            "Snapshot header state root {} does not match store state root {}",
+            header.state_root,
+            store_root
+        );
+    }
+    Ok((header, store))
+}
+
+/// Lists the snapshots available in `directory`, sorted by height.
+pub fn list_snapshots(directory: &Path) -> Result<Vec<NodeStateSnapshotHeader>> {
+    if !directory.exists() {
+        return Ok(vec![]);
+    }
+    let mut headers = vec![];
+    for entry in fs::read_dir(directory).context("Reading snapshots directory")? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "bin") {
+            continue;
+        }
+        match read_snapshot_header(&path) {
+            Ok(header) => headers.push(header),
+            Err(e) => debug!("Skipping snapshot {}: {:#}", path.display(), e),
+        }
+    }
+    headers.sort_by_key(|h| h.block_height);
+    Ok(headers)
+}
+
+/// Removes the oldest snapshots in `directory`, keeping at most `keep` of them.
+pub fn prune_snapshots(directory: &Path, keep: usize) -> Result<()> {
+    let headers = list_snapshots(directory)?;
+    let to_remove = headers.len().saturating_sub(keep);
+    for header in headers.iter().take(to_remove) {
+        let file = directory.join(NodeStateSnapshotHeader::file_name(header.block_height));
+        debug!("Removing node state snapshot {}", file.display());
+        fs::remove_file(&file).context(format!("Removing snapshot {}", file.display()))?;
+    }
+    Ok(())
+}
+
+impl NodeStateSnapshotConf {
+    /// Returns the header of the bootstrap snapshot, if any, after checking it against the trusted values.
+    /// Only [Self::bootstrap_snapshot] checks that the store actually hashes to the header's state root.
+    pub fn bootstrap_header(&self) -> Result<Option<NodeStateSnapshotHeader>> {
+        let Some(file) = &self.bootstrap_from else {
+            return Ok(None);
+        };
+        let header = read_snapshot_header(file)?;
+        self.check_trusted(&header)?;
+        Ok(Some(header))
+    }
+
+    /// Reads the bootstrap snapshot, if any, after checking it against the trusted values.
+    pub fn bootstrap_snapshot(&self) -> Result<Option<(NodeStateSnapshotHeader, NodeStateStore)>> {
+        let Some(file) = &self.bootstrap_from else {
+            return Ok(None);
+        };
+        let (header, store) = read_snapshot(file)?;
+        self.check_trusted(&header)?;
+        Ok(Some((header, store)))
+    }
+
+    fn check_trusted(&self, header: &NodeStateSnapshotHeader) -> Result<()> {
+        if let Some(trusted) = &self.trusted_block_hash {
+            if trusted != &header.block_hash {
+                bail!(
+                    "Snapshot block hash {} does not match trusted hash {}",
+                    header.block_hash,
+                    trusted
+                );
+            }
+        }
+        if let Some(trusted) = &self.trusted_state_root {
+            if !trusted.eq_ignore_ascii_case(&header.state_root) {
+                bail!(
+                    "Snapshot state root {} does not match trusted state root {}",
+                    header.state_root,
+                    trusted
+                );
+            }
+        }
+        if self.trusted_state_root.is_none() {
+            warn!(
+                "No trusted state root configured, the snapshot contents are only as trusted as its source"
+            );
+        }
+        Ok(())
+    }
+}
@@ -1102,3 +1102,326 @@ async fn domino_settlement_after_contract_delete() {
     // This should domino through and settle the tx_ab and tx_a if not already settled
     assert!(block_b.successful_txs.contains(&tx_a_id));
 }
+
+fn make_hyli_tx(blob: Blob) -> BlobTransaction {
+    BlobTransaction::new(
+        HYLI_TLD_ID.to_string(),
+        vec![
+            HydentityAction::VerifyIdentity {
+                nonce: 0,
+                account: HYLI_TLD_ID.to_string(),
+            }
+            .as_blob(HYLI_WALLET.into()),
+            blob,
+        ],
+    )
+}
+
+#[test_log::test(tokio::test)]
+async fn test_hyle_contract_upgrade_is_timelocked() {
+    let mut state = new_node_state().await;
+    state.set_contract_upgrade_delay(BlockHeight(3));
+
+    let register_wallet = make_register_tx("hyle@hyle".into(), "hyle".into(), "wallet".into());
+    let register_hyli_at_wallet = make_register_hyli_wallet_identity_tx();
+    let output = make_hyle_output(register_hyli_at_wallet.clone(), BlobIndex(0));
+    let register_hyli_at_wallet_proof =
+        new_proof_tx(&"wallet".into(), &output, &register_hyli_at_wallet.hashed());
+    let register_contract = make_register_tx("hyle@hyle".into(), "hyle".into(), "contract".into());
+
+    let block = state.craft_block_and_handle(
+        1,
+        vec![
+            register_wallet.into(),
+            register_hyli_at_wallet.into(),
+            register_hyli_at_wallet_proof.into(),
+            register_contract.into(),
+        ],
+    );
+    let contract_name = ContractName::new("contract");
+    assert!(block.contract_code_versions.contains_key(&contract_name));
+
+    let upgrade_tx = make_hyli_tx(
+        UpdateContractProgramIdAction {
+            contract_name: contract_name.clone(),
+            program_id: ProgramId(vec![1, 2, 3]),
+        }
+        .as_blob("hyle".into(), None, None),
+    );
+    let output = make_hyle_output_bis(upgrade_tx.clone(), BlobIndex(0));
+    let verify_hyli_proof = new_proof_tx(&"wallet".into(), &output, &upgrade_tx.hashed());
+
+    let block = state.craft_block_and_handle(2, vec![upgrade_tx.into(), verify_hyli_proof.into()]);
+    assert_eq!(
+        block
+            .scheduled_contract_upgrades
+            .get(&contract_name)
+            .unwrap()
+            .effective_height,
+        BlockHeight(5)
+    );
+    assert!(block.updated_program_ids.is_empty());
+
+    for height in 3..5 {
+        state.craft_block_and_handle(height, vec![]);
+        assert_eq!(
+            state.contracts.get(&contract_name).unwrap().program_id,
+            ProgramId(vec![])
+        );
+    }
+
+    let block = state.craft_block_and_handle(5, vec![]);
+    assert_eq!(
+        block.updated_program_ids.get(&contract_name),
+        Some(&ProgramId(vec![1, 2, 3]))
+    );
+    assert_eq!(
+        state.contracts.get(&contract_name).unwrap().program_id,
+        ProgramId(vec![1, 2, 3])
+    );
+    assert!(state.contract_upgrades.pending(&contract_name).is_none());
+    assert_eq!(
+        state
+            .contract_upgrades
+            .history(&contract_name)
+            .iter()
+            .map(|v| (v.program_id.clone(), v.effective_height))
+            .collect::<Vec<_>>(),
+        vec![
+            (ProgramId(vec![]), BlockHeight(1)),
+            (ProgramId(vec![1, 2, 3]), BlockHeight(5))
+        ]
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_hyle_contract_upgrade_cancel() {
+    let mut state = new_node_state().await;
+    state.set_contract_upgrade_delay(BlockHeight(3));
+
+    let register_wallet = make_register_tx("hyle@hyle".into(), "hyle".into(), "wallet".into());
+    let register_hyli_at_wallet = make_register_hyli_wallet_identity_tx();
+    let output = make_hyle_output(register_hyli_at_wallet.clone(), BlobIndex(0));
+    let register_hyli_at_wallet_proof =
+        new_proof_tx(&"wallet".into(), &output, &register_hyli_at_wallet.hashed());
+    let register_contract = make_register_tx("hyle@hyle".into(), "hyle".into(), "contract".into());
+    state.craft_block_and_handle(
+        1,
+        vec![
+            register_wallet.into(),
+            register_hyli_at_wallet.into(),
+            register_hyli_at_wallet_proof.into(),
+            register_contract.into(),
+        ],
+    );
+    let contract_name = ContractName::new("contract");
+
+    let upgrade_tx = make_hyli_tx(
+        UpdateContractProgramIdAction {
+            contract_name: contract_name.clone(),
+            program_id: ProgramId(vec![1, 2, 3]),
+        }
+        .as_blob("hyle".into(), None, None),
+    );
+    let output = make_hyle_output_bis(upgrade_tx.clone(), BlobIndex(0));
+    let verify_hyli_proof = new_proof_tx(&"wallet".into(), &output, &upgrade_tx.hashed());
+    state.craft_block_and_handle(2, vec![upgrade_tx.into(), verify_hyli_proof.into()]);
+
+    // Cancelling another upgrade than the pending one does nothing.
+    let cancel_tx = make_hyli_tx(
+        CancelContractUpgradeAction {
+            contract_name: contract_name.clone(),
+            program_id: ProgramId(vec![1, 2, 3]),
+            effective_height: BlockHeight(4),
+        }
+        .as_blob("hyle".into(), None, None),
+    );
+    let output = make_hyle_output_ter(cancel_tx.clone(), BlobIndex(0));
+    let verify_hyli_proof = new_proof_tx(&"wallet".into(), &output, &cancel_tx.hashed());
+    let block = state.craft_block_and_handle(3, vec![cancel_tx.into(), verify_hyli_proof.into()]);
+    assert!(block.cancelled_contract_upgrades.is_empty());
+    assert!(state.contract_upgrades.pending(&contract_name).is_some());
+
+    let cancel_tx = make_hyli_tx(
+        CancelContractUpgradeAction {
+            contract_name: contract_name.clone(),
+            program_id: ProgramId(vec![1, 2, 3]),
+            effective_height: BlockHeight(5),
+        }
+        .as_blob("hyle".into(), None, None),
+    );
+    let mut output = make_hyle_output_ter(cancel_tx.clone(), BlobIndex(0));
+    output.initial_state = StateCommitment(vec![10, 11, 12]);
+    let verify_hyli_proof = new_proof_tx(&"wallet".into(), &output, &cancel_tx.hashed());
+    let block = state.craft_block_and_handle(4, vec![cancel_tx.into(), verify_hyli_proof.into()]);
+    assert_eq!(
+        block.cancelled_contract_upgrades,
+        vec![contract_name.clone()]
+    );
+
+    let block = state.craft_block_and_handle(5, vec![]);
+    assert!(block.updated_program_ids.is_empty());
+    assert_eq!(
+        state.contracts.get(&contract_name).unwrap().program_id,
+        ProgramId(vec![])
+    );
+    assert_eq!(state.contract_upgrades.history(&contract_name).len(), 1);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_contract_upgrade_by_owner() {
+    let mut state = new_node_state().await;
+    state.set_contract_upgrade_delay(BlockHeight(2));
+
+    let owner = ContractName::new("c1");
+    let other = ContractName::new("c2");
+    let sub = ContractName::new("sub.c1");
+    state.handle_register_contract_effect(&make_register_contract_effect(owner.clone()));
+    state.handle_register_contract_effect(&make_register_contract_effect(other.clone()));
+    state.handle_register_contract_effect(&make_register_contract_effect(sub.clone()));
+
+    let upgrade_effect = OnchainEffect::UpgradeContract(UpgradeContractEffect {
+        contract_name: sub.clone(),
+        program_id: ProgramId(vec![4, 2]),
+        verifier: "test".into(),
+    });
+
+    // Only the owner of a contract can upgrade it.
+    let upgrade_tx = BlobTransaction::new(
+        "test@c2",
+        vec![UpdateContractProgramIdAction {
+            contract_name: sub.clone(),
+            program_id: ProgramId(vec![4, 2]),
+        }
+        .as_blob(other.clone(), None, None)],
+    );
+    let mut output = make_hyle_output(upgrade_tx.clone(), BlobIndex(0));
+    output.onchain_effects.push(upgrade_effect.clone());
+    let proof = new_proof_tx(&other, &output, &upgrade_tx.hashed());
+    let block = state.craft_block_and_handle(1, vec![upgrade_tx.into(), proof.into()]);
+    assert!(block.scheduled_contract_upgrades.is_empty());
+
+    // The proof must carry the upgrade effect matching the blob.
+    let upgrade_tx = BlobTransaction::new(
+        "test@c1",
+        vec![UpdateContractProgramIdAction {
+            contract_name: sub.clone(),
+            program_id: ProgramId(vec![4, 2]),
+        }
+        .as_blob(owner.clone(), None, None)],
+    );
+    let output = make_hyle_output(upgrade_tx.clone(), BlobIndex(0));
+    let proof = new_proof_tx(&owner, &output, &upgrade_tx.hashed());
+    let block = state.craft_block_and_handle(2, vec![upgrade_tx.clone().into(), proof.into()]);
+    assert!(block.scheduled_contract_upgrades.is_empty());
+
+    let mut output = make_hyle_output(upgrade_tx.clone(), BlobIndex(0));
+    output.onchain_effects.push(upgrade_effect);
+    let proof = new_proof_tx(&owner, &output, &upgrade_tx.hashed());
+    let block = state.craft_block_and_handle(3, vec![proof.into()]);
+    assert!(block.successful_txs.contains(&upgrade_tx.hashed()));
+    assert_eq!(
+        block
+            .scheduled_contract_upgrades
+            .get(&sub)
+            .unwrap()
+            .effective_height,
+        BlockHeight(5)
+    );
+
+    state.craft_block_and_handle(4, vec![]);
+    let block = state.craft_block_and_handle(5, vec![]);
+    assert_eq!(
+        block.contract_code_versions.get(&sub).unwrap().program_id,
+        ProgramId(vec![4, 2])
+    );
+    assert_eq!(
+        state.contracts.get(&sub).unwrap().program_id,
+        ProgramId(vec![4, 2])
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_contract_upgrade_cancel_by_owner() {
+    let mut state = new_node_state().await;
+    state.set_contract_upgrade_delay(BlockHeight(10));
+
+    let owner = ContractName::new("c1");
+    let sub = ContractName::new("sub.c1");
+    state.handle_register_contract_effect(&make_register_contract_effect(owner.clone()));
+    state.handle_register_contract_effect(&make_register_contract_effect(sub.clone()));
+
+    let upgrade_tx = BlobTransaction::new(
+        "test@c1",
+        vec![UpdateContractProgramIdAction {
+            contract_name: sub.clone(),
+            program_id: ProgramId(vec![4, 2]),
+        }
+        .as_blob(owner.clone(), None, None)],
+    );
+    let mut output = make_hyle_output(upgrade_tx.clone(), BlobIndex(0));
+    output
+        .onchain_effects
+        .push(OnchainEffect::UpgradeContract(UpgradeContractEffect {
+            contract_name: sub.clone(),
+            program_id: ProgramId(vec![4, 2]),
+            verifier: "test".into(),
+        }));
+    let proof = new_proof_tx(&owner, &output, &upgrade_tx.hashed());
+    state.craft_block_and_handle(1, vec![upgrade_tx.into(), proof.into()]);
+    let pending = state.contract_upgrades.pending(&sub).unwrap().clone();
+
+    let cancel_tx = |effective_height: BlockHeight| {
+        BlobTransaction::new(
+            "test@c1",
+            vec![CancelContractUpgradeAction {
+                contract_name: sub.clone(),
+                program_id: pending.program_id.clone(),
+                effective_height,
+            }
+            .as_blob(owner.clone(), None, None)],
+        )
+    };
+    let cancel_effect = |program_id: ProgramId, effective_height: BlockHeight| {
+        OnchainEffect::CancelContractUpgrade(CancelContractUpgradeEffect {
+            contract_name: sub.clone(),
+            program_id,
+            effective_height,
+        })
+    };
+
+    // Cancelling another upgrade than the pending one settles, but cancels nothing.
+    let other_height = pending.effective_height + 1;
+    let tx = cancel_tx(other_height);
+    let mut output = make_hyle_output_bis(tx.clone(), BlobIndex(0));
+    output
+        .onchain_effects
+        .push(cancel_effect(pending.program_id.clone(), other_height));
+    let proof = new_proof_tx(&owner, &output, &tx.hashed());
+    let block = state.craft_block_and_handle(2, vec![tx.clone().into(), proof.into()]);
+    assert!(block.successful_txs.contains(&tx.hashed()));
+    assert!(block.cancelled_contract_upgrades.is_empty());
+
+    // The effect must match the blob's program ID and height, not just the contract name.
+    let tx = cancel_tx(pending.effective_height);
+    let mut output = make_hyle_output_ter(tx.clone(), BlobIndex(0));
+    output.onchain_effects.push(cancel_effect(
+        ProgramId(vec![6, 6]),
+        pending.effective_height,
+    ));
+    let proof = new_proof_tx(&owner, &output, &tx.hashed());
+    let block = state.craft_block_and_handle(3, vec![tx.clone().into(), proof.into()]);
+    assert!(!block.successful_txs.contains(&tx.hashed()));
+    assert!(state.contract_upgrades.pending(&sub).is_some());
+
+    let mut output = make_hyle_output_ter(tx.clone(), BlobIndex(0));
+    output.onchain_effects.push(cancel_effect(
+        pending.program_id.clone(),
+        pending.effective_height,
+    ));
+    let proof = new_proof_tx(&owner, &output, &tx.hashed());
+    let block = state.craft_block_and_handle(4, vec![proof.into()]);
+    assert!(block.successful_txs.contains(&tx.hashed()));
+    assert_eq!(block.cancelled_contract_upgrades, vec![sub.clone()]);
+    assert!(state.contract_upgrades.pending(&sub).is_none());
+}
@@ -716,6 +716,72 @@ async fn settle_with_multiple_state_reads() {
     assert_eq!(state.contracts.get(&c1).unwrap().state.0, vec![4, 5, 6]);
 }
 
+#[test_log::test(tokio::test)]
+async fn settle_with_recent_state_read() {
+    let mut state = new_node_state().await;
+    state.set_state_read_window(BlockHeight(2));
+    let c1 = ContractName::new("c1");
+    let c2 = ContractName::new("c2");
+
+    state.craft_block_and_handle(
+        10,
+        vec![
+            make_register_contract_tx(c1.clone()).into(),
+            make_register_contract_tx(c2.clone()).into(),
+        ],
+    );
+    let c2_initial_state = state.contracts.get(&c2).unwrap().state.clone();
+
+    // Move c2 away from the state c1 will read
+    let c2_tx = BlobTransaction::new(Identity::new("test@c2"), vec![new_blob(&c2.0)]);
+    let c2_ho = make_hyle_output(c2_tx.clone(), BlobIndex(0));
+    state.craft_block_and_handle(
+        11,
+        vec![
+            c2_tx.clone().into(),
+            new_proof_tx(&c2, &c2_ho, &c2_tx.hashed()).into(),
+        ],
+    );
+    assert_ne!(state.contracts.get(&c2).unwrap().state, c2_initial_state);
+
+    // The superseded state is still within the window
+    let blob_tx = BlobTransaction::new(Identity::new("test@c1"), vec![new_blob(&c1.0)]);
+    let mut ho = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+    ho.state_reads.push((c2.clone(), c2_initial_state.clone()));
+    let effects = state.craft_block_and_handle(
+        13,
+        vec![
+            blob_tx.clone().into(),
+            new_proof_tx(&c1, &ho, &blob_tx.hashed()).into(),
+        ],
+    );
+    assert_eq!(effects.successful_txs, vec![blob_tx.hashed()]);
+
+    // Once out of the window, the read is rejected
+    let blob_tx = BlobTransaction::new(Identity::new("test2@c1"), vec![new_blob(&c1.0)]);
+    let mut ho = make_hyle_output_bis(blob_tx.clone(), BlobIndex(0));
+    ho.state_reads.push((c2.clone(), c2_initial_state));
+    let effects = state.craft_block_and_handle(
+        14,
+        vec![
+            blob_tx.clone().into(),
+            new_proof_tx(&c1, &ho, &blob_tx.hashed()).into(),
+        ],
+    );
+    assert!(effects.successful_txs.is_empty());
+    assert!(effects
+        .transactions_events
+        .get(&blob_tx.hashed())
+        .unwrap()
+        .iter()
+        .any(|e| {
+            let TransactionStateEvent::SettleEvent(errmsg) = e else {
+                return false;
+            };
+            errmsg.contains("does not match other contract state")
+        }));
+}
+
 #[test_log::test(tokio::test)]
 async fn change_same_contract_state_multiple_times_in_same_tx() {
     let mut state = new_node_state().await;
@@ -1787,3 +1853,188 @@ async fn test_nuke_transaction_forces_target_to_fail() {
     // Verify that tx2 is no longer in unsettled transactions
     assert!(state.unsettled_transactions.get(&tx2_hash).is_none());
 }
+
+#[test_log::test(tokio::test)]
+async fn test_contract_registry_root_and_proof() {
+    let mut state = new_node_state().await;
+    let c1 = ContractName::new("c1");
+    let c2 = ContractName::new("c2");
+    state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+    state.handle_register_contract_effect(&make_register_contract_effect(c2.clone()));
+
+    let block_1 = state.craft_block_and_handle(1, vec![]);
+    assert_eq!(
+        block_1.contract_registry_root,
+        contract_registry_root(&state.contracts)
+    );
+
+    let blob_tx = BlobTransaction::new(Identity::new("test@c1"), vec![new_blob("c1")]);
+    let blob_tx_hash = blob_tx.hashed();
+    let proof_tx = new_proof_tx(
+        &c1,
+        &make_hyle_output(blob_tx.clone(), BlobIndex(0)),
+        &blob_tx_hash,
+    );
+    let block_2 = state.craft_block_and_handle(2, vec![blob_tx.into(), proof_tx.into()]);
+    assert!(block_2.successful_txs.contains(&blob_tx_hash));
+    // c1's state changed, so the root must change too.
+    assert_ne!(
+        block_1.contract_registry_root,
+        block_2.contract_registry_root
+    );
+    // The incrementally updated root matches a full recomputation.
+    assert_eq!(
+        block_2.contract_registry_root,
+        contract_registry_root(&state.contracts)
+    );
+
+    // Only the last handled block's root is served, for the next proposal to commit to.
+    assert_eq!(state.contract_registry_root_after(&block_1.hash), None);
+    assert_eq!(
+        state.contract_registry_root_after(&block_2.hash),
+        Some(block_2.contract_registry_root)
+    );
+    let header = ConsensusProposal {
+        slot: 3,
+        parent_hash: block_2.hash.clone(),
+        parent_contract_registry_root: block_2.contract_registry_root,
+        ..ConsensusProposal::default()
+    };
+
+    for name in [&c1, &c2, &"hyle".into()] {
+        let proof =
+            ContractInclusionProof::new(&state.contracts, name, state.current_height).unwrap();
+        assert_eq!(proof.contract_registry_root, block_2.contract_registry_root);
+        assert!(proof.verify(&header));
+        let served = state.contract_proof(name).unwrap();
+        assert_eq!(served.proof, proof.proof);
+        assert_eq!(served.contract_registry_root, proof.contract_registry_root);
+    }
+
+    // A tampered entry doesn't verify.
+    let proof = ContractInclusionProof::new(&state.contracts, &c1, state.current_height).unwrap();
+    let mut tampered = proof.clone();
+    tampered.contract.state = StateCommitment(vec![0, 1, 2, 3]);
+    assert!(!tampered.verify(&header));
+
+    // The proof must match the root committed by the proposal of the next slot.
+    assert!(!proof.verify(&ConsensusProposal {
+        slot: 2,
+        ..header.clone()
+    }));
+    assert!(!proof.verify(&ConsensusProposal {
+        parent_contract_registry_root: block_1.contract_registry_root,
+        ..header
+    }));
+
+    assert!(
+        ContractInclusionProof::new(&state.contracts, &"unknown".into(), state.current_height)
+            .is_none()
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_simulate_settlement() {
+    let mut state = new_node_state().await;
+    let c1 = ContractName::new("c1");
+    state.handle_register_contract_effect(&make_register_contract_effect(c1.clone()));
+    state.craft_block_and_handle(1, vec![]);
+
+    let blob_tx = BlobTransaction::new(Identity::new("test@c1"), vec![new_blob("c1")]);
+    let blob_tx_hash = blob_tx.hashed();
+    let metrics = NodeStateMetrics::global("test".to_string(), "test_simulation");
+
+    // A valid output settles the transaction.
+    let simulation = state
+        .simulate_settlement(
+            metrics.clone(),
+            blob_tx.clone(),
+            vec![make_hyle_output(blob_tx.clone(), BlobIndex(0))],
+        )
+        .unwrap();
+    assert_eq!(simulation.tx_hash, blob_tx_hash);
+    assert_eq!(simulation.status, SimulatedSettlementStatus::Success);
+    assert_eq!(simulation.failure_reason, None);
+    assert_eq!(
+        simulation.updated_states.get(&c1),
+        Some(&StateCommitment(vec![4, 5, 6]))
+    );
+
+    // Without outputs, the transaction just waits for proofs.
+    let simulation = state
+        .simulate_settlement(metrics.clone(), blob_tx.clone(), vec![])
+        .unwrap();
+    assert_eq!(simulation.status, SimulatedSettlementStatus::NotSettled);
+
+    // A failing output fails the transaction.
+    let mut failing_output = make_hyle_output(blob_tx.clone(), BlobIndex(0));
+    failing_output.success = false;
+    let simulation = state
+        .simulate_settlement(metrics.clone(), blob_tx.clone(), vec![failing_output])
+        .unwrap();
+    assert_eq!(simulation.status, SimulatedSettlementStatus::Failure);
+
+    // Out of bounds outputs are rejected.
+    assert_err!(state.simulate_settlement(
+        metrics,
+        blob_tx.clone(),
+        vec![make_hyle_output(blob_tx, BlobIndex(1))],
+    ));
+
+    // The actual state is untouched.
+    assert_eq!(state.current_height, BlockHeight(1));
+    assert!(state.unsettled_transactions.get(&blob_tx_hash).is_none());
+    assert_eq!(
+        state.contracts.get(&c1).unwrap().state,
+        StateCommitment(vec![0, 1, 2, 3])
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_settlement_overlay_only_copies_touched_parts() {
+    let mut state = new_node_state().await;
+    let c1 = ContractName::new("c1");
+    let c2 = ContractName::new("c2");
+    let c3 = ContractName::new("c3");
+    for c in [&c1, &c2, &c3] {
+        state.handle_register_contract_effect(&make_register_contract_effect(c.clone()));
+    }
+    // Unsettled txs: one on c1 and c2, one on c3 only.
+    let queued_tx = BlobTransaction::new(
+        Identity::new("test@c1"),
+        vec![new_blob("c1"), new_blob("c2")],
+    );
+    let unrelated_tx = BlobTransaction::new(Identity::new("test@c3"), vec![new_blob("c3")]);
+    state.craft_block_and_handle(
+        1,
+        vec![queued_tx.clone().into(), unrelated_tx.clone().into()],
+    );
+
+    let blob_tx = BlobTransaction::new(Identity::new("test@c1"), vec![new_blob("c1")]);
+    let overlay = state.settlement_overlay(&blob_tx, &[]);
+
+    // c2 is copied because the tx queued before ours on c1 also blocks it.
+    assert_eq!(
+        overlay.contracts.keys().cloned().collect::<BTreeSet<_>>(),
+        BTreeSet::from([c1.clone(), c2.clone(), "hyle".into()])
+    );
+    assert!(overlay
+        .unsettled_transactions
+        .get(&queued_tx.hashed())
+        .is_some());
+    assert!(overlay
+        .unsettled_transactions
+        .get(&unrelated_tx.hashed())
+        .is_none());
+
+    // The simulation on the overlay agrees with the queue: ours waits behind the queued tx.
+    let metrics = NodeStateMetrics::global("test".to_string(), "test_simulation");
+    let simulation = state
+        .simulate_settlement(
+            metrics,
+            blob_tx.clone(),
+            vec![make_hyle_output(blob_tx, BlobIndex(0))],
+        )
+        .unwrap();
+    assert_eq!(simulation.status, SimulatedSettlementStatus::NotSettled);
+}
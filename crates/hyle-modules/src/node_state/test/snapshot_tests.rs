@@ -0,0 +1,169 @@
+#![cfg(test)]
+
+use assertables::assert_err;
+use tempfile::tempdir;
+
+use borsh::BorshDeserialize;
+use sha3::{Digest, Sha3_256};
+
+use crate::node_state::snapshot::{
+    list_snapshots, prune_snapshots, read_snapshot, state_root, write_snapshot,
+    NodeStateSnapshotConf, NodeStateSnapshotHeader,
+};
+
+use super::*;
+
+async fn node_state_at_height(height: u64) -> NodeState {
+    let mut state = new_node_state().await;
+    let c1 = ContractName::new("c1");
+    state.handle_register_contract_effect(&make_register_contract_effect(c1));
+    for h in 1..=height {
+        state.craft_block_and_handle(h, vec![]);
+    }
+    state
+}
+
+#[test_log::test(tokio::test)]
+async fn test_snapshot_roundtrip() {
+    let dir = tempdir().unwrap();
+    let state = node_state_at_height(3).await;
+
+    let header = write_snapshot(
+        dir.path(),
+        &state,
+        ConsensusProposalHash("block_3".to_string()),
+    )
+    .unwrap();
+    assert_eq!(header.block_height, BlockHeight(3));
+
+    let (read_header, store) = read_snapshot(
+        &dir.path()
+            .join(NodeStateSnapshotHeader::file_name(BlockHeight(3))),
+    )
+    .unwrap();
+    assert_eq!(read_header, header);
+    assert_eq!(store.current_height, BlockHeight(3));
+    assert_eq!(
+        store.contracts.keys().collect::<HashSet<_>>(),
+        state.contracts.keys().collect::<HashSet<_>>()
+    );
+}
+
+#[test_log::test(tokio::test)]
+async fn test_snapshot_corrupted() {
+    let dir = tempdir().unwrap();
+    let state = node_state_at_height(1).await;
+
+    write_snapshot(
+        dir.path(),
+        &state,
+        ConsensusProposalHash("block_1".to_string()),
+    )
+    .unwrap();
+
+    let file = dir
+        .path()
+        .join(NodeStateSnapshotHeader::file_name(BlockHeight(1)));
+    let mut bytes = std::fs::read(&file).unwrap();
+    *bytes.last_mut().unwrap() ^= 0xFF;
+    std::fs::write(&file, bytes).unwrap();
+
+    assert_err!(read_snapshot(&file));
+}
+
+#[test_log::test(tokio::test)]
+async fn test_snapshot_prune() {
+    let dir = tempdir().unwrap();
+    let mut state = node_state_at_height(0).await;
+
+    for h in 1..=4 {
+        state.craft_block_and_handle(h, vec![]);
+        write_snapshot(
+            dir.path(),
+            &state,
+            ConsensusProposalHash(format!("block_{h}")),
+        )
+        .unwrap();
+    }
+    assert_eq!(list_snapshots(dir.path()).unwrap().len(), 4);
+
+    prune_snapshots(dir.path(), 2).unwrap();
+    let heights: Vec<_> = list_snapshots(dir.path())
+        .unwrap()
+        .into_iter()
+        .map(|h| h.block_height)
+        .collect();
+    assert_eq!(heights, vec![BlockHeight(3), BlockHeight(4)]);
+}
+
+#[test_log::test(tokio::test)]
+async fn test_snapshot_bootstrap_trusted_hash() {
+    let dir = tempdir().unwrap();
+    let state = node_state_at_height(2).await;
+    write_snapshot(
+        dir.path(),
+        &state,
+        ConsensusProposalHash("block_2".to_string()),
+    )
+    .unwrap();
+
+    let mut conf = NodeStateSnapshotConf {
+        bootstrap_from: Some(
+            dir.path()
+                .join(NodeStateSnapshotHeader::file_name(BlockHeight(2))),
+        ),
+        trusted_block_hash: Some(ConsensusProposalHash("block_2".to_string())),
+        ..Default::default()
+    };
+    let (header, store) = conf.bootstrap_snapshot().unwrap().unwrap();
+    assert_eq!(
+        header.block_hash,
+        ConsensusProposalHash("block_2".to_string())
+    );
+    assert_eq!(store.current_height, BlockHeight(2));
+
+    conf.trusted_block_hash = Some(ConsensusProposalHash("other".to_string()));
+    assert_err!(conf.bootstrap_snapshot());
+    assert_err!(conf.bootstrap_header());
+
+    conf.trusted_block_hash = None;
+    conf.trusted_state_root = Some(hex::encode(state_root(&state).unwrap()));
+    assert!(conf.bootstrap_snapshot().unwrap().is_some());
+
+    conf.trusted_state_root = Some(hex::encode([1; 32]));
+    assert_err!(conf.bootstrap_snapshot());
+    assert_err!(conf.bootstrap_header());
+}
+
+#[test_log::test(tokio::test)]
+async fn test_snapshot_tampered_store() {
+    let dir = tempdir().unwrap();
+    let state = node_state_at_height(2).await;
+    write_snapshot(
+        dir.path(),
+        &state,
+        ConsensusProposalHash("block_2".to_string()),
+    )
+    .unwrap();
+    let file = dir
+        .path()
+        .join(NodeStateSnapshotHeader::file_name(BlockHeight(2)));
+
+    // Swap a contract's state and fix up the checksum: only the state root catches it.
+    let bytes = std::fs::read(&file).unwrap();
+    let mut reader = bytes.as_slice();
+    let mut header = NodeStateSnapshotHeader::deserialize_reader(&mut reader).unwrap();
+    let mut store: NodeStateStore = borsh::from_slice(reader).unwrap();
+    store
+        .contracts
+        .get_mut(&ContractName::new("c1"))
+        .unwrap()
+        .state = StateCommitment(vec![42]);
+    let store_bytes = borsh::to_vec(&store).unwrap();
+    header.checksum = Sha3_256::digest(&store_bytes).to_vec();
+    let mut tampered = borsh::to_vec(&header).unwrap();
+    tampered.extend(store_bytes);
+    std::fs::write(&file, tampered).unwrap();
+
+    assert_err!(read_snapshot(&file));
+}
@@ -22,6 +22,24 @@ impl Timeouts {
             .push(tx);
     }
 
+    /// Copies the timeouts due at `block_height` for the txs `keep` accepts.
+    pub fn subset_due_at(
+        &self,
+        block_height: BlockHeight,
+        keep: impl Fn(&TxHash) -> bool,
+    ) -> Timeouts {
+        let due: Vec<TxHash> = self
+            .by_block
+            .get(&block_height)
+            .map(|txs| txs.iter().filter(|tx| keep(tx)).cloned().collect())
+            .unwrap_or_default();
+        let mut by_block = HashMap::new();
+        if !due.is_empty() {
+            by_block.insert(block_height, due);
+        }
+        Timeouts { by_block }
+    }
+
     pub fn count_all(&self) -> usize {
         self.by_block.values().map(|v| v.len()).sum()
     }
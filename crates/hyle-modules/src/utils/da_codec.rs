@@ -1,17 +1,265 @@
+use std::collections::BTreeSet;
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use hyle_net::tcp::{tcp_client::TcpClient, tcp_server::TcpServer};
-use sdk::{BlockHeight, MempoolStatusEvent, SignedBlock};
+use sdk::{
+    Block, BlockHeight, ContractName, MempoolStatusEvent, ProofData, SignedBlock, Transaction,
+    TransactionData,
+};
 
 // Da Listener
 //
-#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq, Eq)]
-pub struct DataAvailabilityRequest(pub BlockHeight);
+/// Requests are encoded by hand to stay readable by servers and clients predating subscriptions:
+/// [DataAvailabilityRequest::FromHeight] is a bare height, as it always was, while the other
+/// requests start with a version byte and are never as long as a height.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DataAvailabilityRequest {
+    /// Streams every signed block from this height
+    FromHeight(BlockHeight),
+    /// Streams the blocks processed by the node state from this height, filtered
+    Subscribe(DaSubscription),
+}
+
+/// Version byte of [DataAvailabilityRequest::Subscribe]
+const SUBSCRIBE_REQUEST_V1: u8 = 1;
+/// Length of an encoded [BlockHeight], that of [DataAvailabilityRequest::FromHeight]
+const HEIGHT_REQUEST_LEN: usize = 8;
+
+impl BorshSerialize for DataAvailabilityRequest {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            DataAvailabilityRequest::FromHeight(height) => height.serialize(writer),
+            DataAvailabilityRequest::Subscribe(subscription) => {
+                SUBSCRIBE_REQUEST_V1.serialize(writer)?;
+                subscription.serialize(writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for DataAvailabilityRequest {
+    /// Reads the whole input, requests being decoded one frame at a time
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() == HEIGHT_REQUEST_LEN {
+            return Ok(DataAvailabilityRequest::FromHeight(borsh::from_slice(
+                &bytes,
+            )?));
+        }
+        match bytes.split_first() {
+            Some((&SUBSCRIBE_REQUEST_V1, subscription)) => Ok(DataAvailabilityRequest::Subscribe(
+                borsh::from_slice(subscription)?,
+            )),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Unknown data availability request",
+            )),
+        }
+    }
+}
+
+/// Subscription to the processed blocks of some contracts, see [DataAvailabilityRequest::Subscribe]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DaSubscription {
+    pub from: BlockHeight,
+    /// Only the transactions involving these contracts are sent, all of them if empty
+    pub contracts: BTreeSet<ContractName>,
+    /// Leave out proof transactions
+    pub blob_txs_only: bool,
+    /// Send proof transactions without their proof
+    pub strip_proofs: bool,
+}
+
+impl DaSubscription {
+    fn follows(&self, contract_name: &ContractName) -> bool {
+        self.contracts.is_empty() || self.contracts.contains(contract_name)
+    }
+
+    fn keeps(&self, tx: &Transaction) -> bool {
+        match &tx.transaction_data {
+            TransactionData::Blob(blob_tx) => blob_tx
+                .blobs
+                .iter()
+                .any(|blob| self.follows(&blob.contract_name)),
+            TransactionData::Proof(proof_tx) => {
+                !self.blob_txs_only && self.follows(&proof_tx.contract_name)
+            }
+            TransactionData::VerifiedProof(proof_tx) => {
+                !self.blob_txs_only && self.follows(&proof_tx.contract_name)
+            }
+        }
+    }
+
+    /// Strips a processed block down to what the subscriber asked for.
+    /// Tx ids, parent data proposal hashes and settlement outcomes are kept for every transaction,
+    /// as the transactions settled in a block may have been sequenced in earlier ones.
+    pub fn filter(&self, mut block: Block) -> Block {
+        block.txs.retain(|(_, tx)| self.keeps(tx));
+        if self.strip_proofs {
+            for (_, tx) in block.txs.iter_mut() {
+                match &mut tx.transaction_data {
+                    TransactionData::Proof(proof_tx) => proof_tx.proof = ProofData::default(),
+                    TransactionData::VerifiedProof(proof_tx) => proof_tx.proof = None,
+                    TransactionData::Blob(_) => {}
+                }
+            }
+        }
+        block
+            .blob_proof_outputs
+            .retain(|output| self.follows(&output.contract_name));
+        block
+            .registered_contracts
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+            .deleted_contracts
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+            .updated_states
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+            .updated_program_ids
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+            .updated_timeout_windows
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+            .scheduled_contract_upgrades
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+            .cancelled_contract_upgrades
+            .retain(|contract_name| self.follows(contract_name));
+        block
+            .contract_code_versions
+            .retain(|contract_name, _| self.follows(contract_name));
+        block
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub enum DataAvailabilityEvent {
     SignedBlock(SignedBlock),
+    /// A block processed by the node state, filtered for a [DaSubscription]
+    ProcessedBlock(Box<Block>),
     MempoolStatusEvent(MempoolStatusEvent),
+    /// Answer to a request for blocks the server no longer has
+    BlocksPruned {
+        requested: BlockHeight,
+        first_available: BlockHeight,
+    },
+    /// Sent to a subscriber in place of a block the server has, but never processed
+    ProcessedBlockMissing(BlockHeight),
 }
 
 pub type DataAvailabilityServer = TcpServer<DataAvailabilityRequest, DataAvailabilityEvent>;
 pub type DataAvailabilityClient = TcpClient<DataAvailabilityRequest, DataAvailabilityEvent>;
+
+#[cfg(test)]
+mod tests {
+    use sdk::{
+        Blob, BlobData, BlobTransaction, DataProposalHash, Hashed, ProofDataHash, TxId,
+        VerifiedProofTransaction,
+    };
+
+    use super::*;
+
+    fn blob_tx(contract_name: &str) -> Transaction {
+        BlobTransaction::new(
+            "alice@hydentity",
+            vec![Blob {
+                contract_name: contract_name.into(),
+                data: BlobData(vec![1, 2, 3]),
+            }],
+        )
+        .into()
+    }
+
+    fn proof_tx(contract_name: &str) -> Transaction {
+        VerifiedProofTransaction {
+            contract_name: contract_name.into(),
+            proof: Some(ProofData(vec![4, 5, 6])),
+            proof_hash: ProofDataHash::default(),
+            proof_size: 3,
+            proven_blobs: vec![],
+            is_recursive: false,
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        // Height requests are encoded as they were before subscriptions
+        let request = DataAvailabilityRequest::FromHeight(BlockHeight(42));
+        let encoded = borsh::to_vec(&request).unwrap();
+        assert_eq!(encoded, borsh::to_vec(&BlockHeight(42)).unwrap());
+        assert_eq!(
+            borsh::from_slice::<DataAvailabilityRequest>(&encoded).unwrap(),
+            request
+        );
+
+        let request = DataAvailabilityRequest::Subscribe(DaSubscription {
+            from: BlockHeight(42),
+            contracts: BTreeSet::from(["hyllar".into()]),
+            ..Default::default()
+        });
+        let encoded = borsh::to_vec(&request).unwrap();
+        assert_eq!(encoded.first(), Some(&SUBSCRIBE_REQUEST_V1));
+        assert_eq!(
+            borsh::from_slice::<DataAvailabilityRequest>(&encoded).unwrap(),
+            request
+        );
+
+        assert!(borsh::from_slice::<DataAvailabilityRequest>(&[2, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_subscription_filter() {
+        let txs = vec![
+            blob_tx("hyllar"),
+            blob_tx("hydentity"),
+            proof_tx("hyllar"),
+            proof_tx("hydentity"),
+        ];
+        let mut block = Block {
+            txs: txs
+                .iter()
+                .map(|tx| (TxId(DataProposalHash::default(), tx.hashed()), tx.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        block
+            .updated_states
+            .insert("hyllar".into(), Default::default());
+        block
+            .updated_states
+            .insert("hydentity".into(), Default::default());
+        for tx in &txs {
+            block
+                .dp_parent_hashes
+                .insert(tx.hashed(), DataProposalHash::default());
+        }
+
+        let mut subscription = DaSubscription {
+            contracts: BTreeSet::from(["hyllar".into()]),
+            strip_proofs: true,
+            ..Default::default()
+        };
+        let filtered = subscription.filter(block.clone());
+        let kept: Vec<_> = filtered.txs.iter().map(|(_, tx)| tx.clone()).collect();
+        let mut stripped = txs.get(2).cloned().unwrap();
+        if let TransactionData::VerifiedProof(proof_tx) = &mut stripped.transaction_data {
+            proof_tx.proof = None;
+        }
+        assert_eq!(kept, vec![txs.first().cloned().unwrap(), stripped]);
+        assert_eq!(filtered.updated_states.len(), 1);
+        assert!(filtered.updated_states.contains_key(&"hyllar".into()));
+        // Settlement metadata is left untouched
+        assert_eq!(filtered.dp_parent_hashes, block.dp_parent_hashes);
+
+        subscription.blob_txs_only = true;
+        subscription.contracts.clear();
+        let filtered = subscription.filter(block);
+        assert_eq!(filtered.txs.len(), 2);
+        assert_eq!(filtered.updated_states.len(), 2);
+    }
+}
@@ -28,6 +28,9 @@ impl TxExecutorHandler for NativeVerifierHandler {
                 "blst" => NativeVerifiers::Blst,
                 "sha3_256" => NativeVerifiers::Sha3_256,
                 "secp256k1" => NativeVerifiers::Secp256k1,
+                "ed25519" => NativeVerifiers::Ed25519,
+                "secp256r1" => NativeVerifiers::Secp256r1,
+                "keccak256" => NativeVerifiers::Keccak256,
                 _ => anyhow::bail!("Unknown native verifier: {}", blob.contract_name),
             },
         ))
@@ -1,13 +1,21 @@
 use crate::tcp::{tcp_client::TcpClient, tcp_server::TcpServer};
 use borsh::{BorshDeserialize, BorshSerialize};
-use sdk::Transaction;
+use sdk::{Transaction, TxHash};
 
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize, Eq, PartialEq)]
 pub enum TcpServerMessage {
     NewTx(Transaction),
 }
+/// Sent back to a client whose message was not accepted.
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize, Eq, PartialEq)]
-pub struct TcpServerResponse;
+pub enum TcpServerResponse {
+    /// The transaction was refused by admission control, with the same code as the REST API.
+    Rejected {
+        tx_hash: TxHash,
+        code: String,
+        message: String,
+    },
+}
 
 pub type TcpApiServer = TcpServer<TcpServerMessage, TcpServerResponse>;
 pub type TcpApiClient = TcpClient<TcpServerMessage, TcpServerResponse>;
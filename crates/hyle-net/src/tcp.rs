@@ -111,6 +111,17 @@ impl Display for Canal {
     }
 }
 
+/// Version advertised in the handshake, bumped when nodes gain a capability peers rely on.
+/// Version 2 nodes accept compressed data proposals.
+/// Version 3 consensus proposals carry their epoch, changing their hash and layout.
+/// Version 4 consensus proposals commit to the root of their transactions, changing them again.
+/// Version 5 consensus proposals carry the commit QC of their parent, seeding the leader election.
+/// Version 6 consensus proposals commit to the contract registry root after their parent block.
+pub const P2P_PROTOCOL_VERSION: u16 = 6;
+
+/// Oldest version peers can speak: older ones can't decode our consensus messages, nor we theirs.
+pub const MIN_P2P_PROTOCOL_VERSION: u16 = 6;
+
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize, PartialEq)]
 pub struct NodeConnectionData {
     pub version: u16,
@@ -8,7 +8,7 @@ use std::{
 
 use anyhow::{bail, Context};
 use borsh::{BorshDeserialize, BorshSerialize};
-use hyle_crypto::BlstCrypto;
+use hyle_crypto::{signer::SignerMessage, BlstCrypto};
 use sdk::{hyle_model_utils::TimestampMs, SignedByValidator, ValidatorPublicKey};
 use tokio::{
     task::{AbortHandle, JoinSet},
@@ -23,7 +23,10 @@ use crate::{
     tcp::{tcp_client::TcpClient, Handshake},
 };
 
-use super::{tcp_server::TcpServer, Canal, NodeConnectionData, P2PTcpMessage, TcpEvent};
+use super::{
+    tcp_server::TcpServer, Canal, NodeConnectionData, P2PTcpMessage, TcpEvent,
+    MIN_P2P_PROTOCOL_VERSION, P2P_PROTOCOL_VERSION,
+};
 
 #[derive(Debug)]
 pub enum P2PServerEvent<Msg> {
@@ -32,6 +35,8 @@ pub enum P2PServerEvent<Msg> {
         pubkey: ValidatorPublicKey,
         height: u64,
         da_address: String,
+        /// Handshake version of the peer
+        version: u16,
     },
     P2PMessage {
         msg: Msg,
@@ -360,6 +365,7 @@ where
 
                 // Verify message signature
                 BlstCrypto::verify(&v).context("Error verifying Hello message")?;
+                check_protocol_version(&v.msg)?;
 
                 info!(
                     "👋 [{}] Processing Hello handshake message {:?}",
@@ -401,6 +407,7 @@ where
 
                 // Verify message signature
                 BlstCrypto::verify(&v).context("Error verifying Verack message")?;
+                check_protocol_version(&v.msg)?;
 
                 info!(
                     "👋 [{}] Processing Verack handshake message {:?}",
@@ -490,6 +497,7 @@ where
                 pubkey: v.signature.validator.clone(),
                 da_address: v.msg.da_public_address.clone(),
                 height: v.msg.current_height,
+                version: v.msg.version,
             })
         }
     }
@@ -509,13 +517,17 @@ where
         &self,
     ) -> anyhow::Result<SignedByValidator<NodeConnectionData>> {
         let node_connection_data = NodeConnectionData {
-            version: 1,
+            version: P2P_PROTOCOL_VERSION,
             name: self.node_id.clone(),
             current_height: self.current_height,
             p2p_public_address: self.node_p2p_public_address.clone(),
             da_public_address: self.node_da_public_address.clone(),
         };
-        self.crypto.sign(node_connection_data)
+        self.crypto.sign_typed(node_connection_data.clone(), || {
+            Ok(SignerMessage::Handshake(borsh::to_vec(
+                &node_connection_data,
+            )?))
+        })
     }
 
     fn try_start_connection_for_peer(
@@ -724,6 +736,25 @@ where
         jobs.spawn(async move { (peers, borsh::to_vec(&P2PTcpMessage::Data(msg))) });
     }
 
+    pub fn broadcast_except(
+        &mut self,
+        except: &HashSet<ValidatorPublicKey>,
+        canal: Canal,
+        msg: Msg,
+    ) {
+        let Some(jobs) = self.canal_jobs.get_mut(&canal) else {
+            error!("Canal {:?} does not exist in P2P server", canal);
+            return;
+        };
+        let peers = self
+            .peers
+            .keys()
+            .filter(|pubkey| !except.contains(*pubkey))
+            .cloned()
+            .collect();
+        jobs.spawn(async move { (peers, borsh::to_vec(&P2PTcpMessage::Data(msg))) });
+    }
+
     async fn actually_send_to(
         &mut self,
         only_for: HashSet<ValidatorPublicKey>,
@@ -761,6 +792,19 @@ where
     }
 }
 
+/// Refuses peers speaking a protocol we can't decode the messages of
+fn check_protocol_version(data: &NodeConnectionData) -> anyhow::Result<()> {
+    if data.version < MIN_P2P_PROTOCOL_VERSION {
+        bail!(
+            "Peer {} speaks protocol version {}, at least {} is required",
+            data.name,
+            data.version,
+            MIN_P2P_PROTOCOL_VERSION
+        );
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::collections::HashSet;
@@ -25,6 +25,7 @@ pub fn verify(
         hyle_model::verifiers::NOIR => noir::verify(proof, program_id),
         #[cfg(feature = "sp1")]
         hyle_model::verifiers::SP1_4 => sp1_4::verify(proof, program_id),
+        hyle_model::verifiers::GROTH16_BN254 => groth16_bn254::verify(proof, program_id),
         _ => Err(anyhow::anyhow!("{} verifier not implemented yet", verifier)),
     }
 }
@@ -36,6 +37,7 @@ pub fn validate_program_id(verifier: &Verifier, program_id: &ProgramId) -> Resul
         hyle_model::verifiers::RISC0_1 => risc0_1::validate_program_id(program_id),
         #[cfg(feature = "sp1")]
         hyle_model::verifiers::SP1_4 => sp1_4::validate_program_id(program_id),
+        hyle_model::verifiers::GROTH16_BN254 => groth16_bn254::validate_program_id(program_id),
         _ => Ok(()),
     }
 }
@@ -253,6 +255,71 @@ pub mod sp1_4 {
     }
 }
 
+/// Groth16 proofs over BN254, e.g. from circom (snarkjs) or gnark circuits.
+/// The program ID is the arkworks-compressed verifying key, and the proof data is a borsh-encoded
+/// [Groth16Proof]. The public inputs encode the HyleOutput with the same layout as Noir,
+/// see [crate::noir_utils::parse_hyle_output_fields].
+pub mod groth16_bn254 {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_groth16::{Groth16, PreparedVerifyingKey, VerifyingKey};
+    use ark_serialize::CanonicalDeserialize;
+    use hyle_model::verifiers::Groth16Proof;
+
+    pub fn verify(proof: &ProofData, program_id: &ProgramId) -> Result<Vec<HyleOutput>, Error> {
+        let vk = parse_verifying_key(program_id)?;
+        let proof = borsh::from_slice::<Groth16Proof>(&proof.0)
+            .context("Error while decoding Groth16 proof")?;
+        let ark_proof = ark_groth16::Proof::<Bn254>::deserialize_compressed(proof.proof.as_slice())
+            .context("Invalid Groth16 proof")?;
+
+        if proof.public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+            bail!(
+                "Groth16 proof has {} public inputs, verifying key expects {}",
+                proof.public_inputs.len(),
+                vk.gamma_abc_g1.len().saturating_sub(1)
+            );
+        }
+        let public_inputs = proof
+            .public_inputs
+            .iter()
+            .map(|input| {
+                let field = Fr::from_be_bytes_mod_order(input);
+                // Reject non-canonical encodings, which would decode to a different HyleOutput.
+                if field.into_bigint().to_bytes_be() != input {
+                    bail!("Groth16 public input is not a canonical field element");
+                }
+                Ok(field)
+            })
+            .collect::<Result<Vec<Fr>, Error>>()?;
+
+        let pvk = PreparedVerifyingKey::from(vk);
+        let valid = Groth16::<Bn254>::verify_proof(&pvk, &ark_proof, &public_inputs)
+            .context("Groth16 proof verification failed")?;
+        if !valid {
+            bail!("Groth16 proof verification failed");
+        }
+
+        let hyle_output =
+            crate::noir_utils::parse_hyle_output_fields(&proof.public_inputs.concat())?;
+
+        tracing::info!("✅ Groth16 proof verified.");
+
+        Ok(vec![hyle_output])
+    }
+
+    pub fn validate_program_id(program_id: &ProgramId) -> Result<(), Error> {
+        parse_verifying_key(program_id)?;
+        Ok(())
+    }
+
+    fn parse_verifying_key(program_id: &ProgramId) -> Result<VerifyingKey<Bn254>, Error> {
+        VerifyingKey::<Bn254>::deserialize_compressed(program_id.0.as_slice())
+            .map_err(|e| anyhow::anyhow!("Invalid Groth16 verifying key: {}", e))
+    }
+}
+
 pub mod native {
     use super::*;
     use hyle_model::{
@@ -399,6 +466,139 @@ mod tests {
         }
     }
 
+    mod groth16 {
+        use ark_bn254::{Bn254, Fr};
+        use ark_ff::{BigInteger, PrimeField};
+        use ark_groth16::Groth16;
+        use ark_relations::{
+            lc,
+            r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+        };
+        use ark_serialize::CanonicalSerialize;
+        use ark_snark::SNARK;
+        use ark_std::rand::{rngs::StdRng, SeedableRng};
+        use hyle_model::{
+            verifiers::{Groth16Proof, GROTH16_BN254},
+            Blob, BlobData, BlobIndex, HyleOutput, Identity, IndexedBlobs, ProgramId, ProofData,
+            StateCommitment, TxHash, Verifier,
+        };
+
+        /// Exposes its inputs as public inputs, which is all the verifier cares about.
+        #[derive(Clone)]
+        struct PublicInputsCircuit(Vec<Fr>);
+
+        impl ConstraintSynthesizer<Fr> for PublicInputsCircuit {
+            fn generate_constraints(
+                self,
+                cs: ConstraintSystemRef<Fr>,
+            ) -> Result<(), SynthesisError> {
+                for input in self.0 {
+                    let v = cs.new_input_variable(|| Ok(input))?;
+                    cs.enforce_constraint(lc!() + v, lc!() + Variable::One, lc!() + v)?;
+                }
+                Ok(())
+            }
+        }
+
+        fn push_str(fields: &mut Vec<u64>, s: &str, padded_len: usize) {
+            fields.extend(s.bytes().map(u64::from));
+            fields.extend(std::iter::repeat_n(0, padded_len - s.len()));
+        }
+
+        fn encode(ho: &HyleOutput) -> Vec<Fr> {
+            let mut fields = vec![ho.version as u64];
+            for state in [&ho.initial_state, &ho.next_state] {
+                fields.push(state.0.len() as u64);
+                fields.extend(state.0.iter().map(|b| *b as u64));
+            }
+            fields.push(ho.identity.0.len() as u64);
+            push_str(&mut fields, &ho.identity.0, 256);
+            push_str(&mut fields, &ho.tx_hash.0, 64);
+            fields.push(ho.index.0 as u64);
+            fields.push(ho.blobs.len() as u64);
+            for (index, blob) in ho.blobs.iter() {
+                fields.push(index.0 as u64);
+                fields.push(blob.contract_name.0.len() as u64);
+                push_str(&mut fields, &blob.contract_name.0, 256);
+                fields.push(blob.data.0.len() as u64);
+                fields.push(blob.data.0.len() as u64);
+                fields.extend(blob.data.0.iter().map(|b| *b as u64));
+            }
+            fields.push(ho.tx_blob_count as u64);
+            fields.push(ho.success as u64);
+            fields.into_iter().map(Fr::from).collect()
+        }
+
+        #[test]
+        fn test_groth16_bn254_verifier() {
+            let hyle_output = HyleOutput {
+                version: 1,
+                initial_state: StateCommitment(vec![0, 1, 2, 3]),
+                next_state: StateCommitment(vec![4, 5, 6]),
+                identity: Identity::new("bob@circom"),
+                index: BlobIndex(0),
+                blobs: IndexedBlobs(vec![(
+                    BlobIndex(0),
+                    Blob {
+                        contract_name: "circom".into(),
+                        data: BlobData(vec![1, 2, 3]),
+                    },
+                )]),
+                tx_blob_count: 1,
+                success: true,
+                tx_hash: TxHash("ab".repeat(32)),
+                state_reads: vec![],
+                tx_ctx: None,
+                onchain_effects: vec![],
+                program_outputs: vec![],
+            };
+            let inputs = encode(&hyle_output);
+            let circuit = PublicInputsCircuit(inputs.clone());
+
+            let mut rng = StdRng::seed_from_u64(0);
+            let (pk, vk) =
+                Groth16::<Bn254>::circuit_specific_setup(circuit.clone(), &mut rng).unwrap();
+            let proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+
+            let mut program_id = vec![];
+            vk.serialize_compressed(&mut program_id).unwrap();
+            let program_id = ProgramId(program_id);
+            let verifier: Verifier = GROTH16_BN254.into();
+            assert!(super::super::validate_program_id(&verifier, &program_id).is_ok());
+            assert!(
+                super::super::validate_program_id(&verifier, &ProgramId(vec![1, 2, 3])).is_err()
+            );
+
+            let mut groth16_proof = Groth16Proof {
+                proof: vec![],
+                public_inputs: inputs
+                    .iter()
+                    .map(|f| f.into_bigint().to_bytes_be().try_into().unwrap())
+                    .collect(),
+            };
+            proof
+                .serialize_compressed(&mut groth16_proof.proof)
+                .unwrap();
+
+            let outputs = super::super::verify(
+                &verifier,
+                &ProofData(borsh::to_vec(&groth16_proof).unwrap()),
+                &program_id,
+            )
+            .unwrap();
+            assert_eq!(outputs, vec![hyle_output]);
+
+            // Tampering with the public inputs invalidates the proof
+            groth16_proof.public_inputs[0][31] = 2;
+            assert!(super::super::verify(
+                &verifier,
+                &ProofData(borsh::to_vec(&groth16_proof).unwrap()),
+                &program_id,
+            )
+            .is_err());
+        }
+    }
+
     #[test]
     #[cfg(feature = "risc0")]
     fn test_check_risc0_program_id() {
@@ -4,6 +4,10 @@ use hyle_model::*;
 use secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
 use sha3::Digest;
 
+/// Authenticator data starts with the RP ID hash (32 bytes), then the flags byte.
+const WEBAUTHN_FLAGS_OFFSET: usize = 32;
+const WEBAUTHN_FLAG_USER_PRESENT: u8 = 0x01;
+
 pub(crate) fn verify_native_impl(
     blob: &Blob,
     verifier: NativeVerifiers,
@@ -52,5 +56,239 @@ pub(crate) fn verify_native_impl(
 
             Ok((blob.identity, success))
         }
+        NativeVerifiers::Ed25519 => {
+            let blob = borsh::from_slice::<Ed25519Blob>(&blob.data.0)?;
+
+            let public_key = ed25519_dalek::VerifyingKey::from_bytes(&blob.public_key)
+                .map_err(|e| anyhow::anyhow!("Invalid public key: {}", e))?;
+            let signature = ed25519_dalek::Signature::from_bytes(&blob.signature);
+
+            let success = public_key.verify_strict(&blob.data, &signature).is_ok();
+
+            Ok((blob.identity, success))
+        }
+        NativeVerifiers::Secp256r1 => {
+            let blob = borsh::from_slice::<Secp256r1Blob>(&blob.data.0)?;
+
+            let public_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(&blob.public_key)
+                .map_err(|e| anyhow::anyhow!("Invalid public key: {}", e))?;
+            let signature = p256::ecdsa::Signature::from_slice(&blob.signature)
+                .map_err(|e| anyhow::anyhow!("Invalid signature: {}", e))?;
+
+            let success = match &blob.webauthn {
+                None => {
+                    use p256::ecdsa::signature::hazmat::PrehashVerifier;
+                    public_key.verify_prehash(&blob.data, &signature).is_ok()
+                }
+                Some(assertion) => {
+                    check_webauthn_assertion(assertion, &blob.data)?;
+                    use p256::ecdsa::signature::Verifier;
+                    let client_data_hash = sha2::Sha256::digest(&assertion.client_data_json);
+                    let message = [
+                        assertion.authenticator_data.as_slice(),
+                        client_data_hash.as_slice(),
+                    ]
+                    .concat();
+                    public_key.verify(&message, &signature).is_ok()
+                }
+            };
+
+            Ok((blob.identity, success))
+        }
+        NativeVerifiers::Keccak256 => {
+            let blob = borsh::from_slice::<ShaBlob>(&blob.data.0)?;
+
+            let mut hasher = sha3::Keccak256::new();
+            hasher.update(blob.data);
+            let res = hasher.finalize().to_vec();
+
+            Ok((blob.identity, res == blob.sha))
+        }
+    }
+}
+
+/// Checks that the WebAuthn assertion is an authentication ceremony for `challenge`,
+/// with the user present. The origin and RP ID are left to the contracts.
+fn check_webauthn_assertion(
+    assertion: &WebAuthnAssertion,
+    challenge: &[u8; 32],
+) -> anyhow::Result<()> {
+    use base64::Engine;
+
+    let flags = assertion
+        .authenticator_data
+        .get(WEBAUTHN_FLAGS_OFFSET)
+        .ok_or_else(|| anyhow::anyhow!("Authenticator data is too short"))?;
+    if flags & WEBAUTHN_FLAG_USER_PRESENT == 0 {
+        anyhow::bail!("User presence flag is not set");
+    }
+
+    let client_data: serde_json::Value = serde_json::from_slice(&assertion.client_data_json)?;
+    if client_data["type"] != "webauthn.get" {
+        anyhow::bail!("Client data type is not webauthn.get");
+    }
+    let expected_challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(challenge);
+    if client_data["challenge"] != expected_challenge.as_str() {
+        anyhow::bail!("Client data challenge does not match blob data");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use p256::ecdsa::signature::{hazmat::PrehashSigner, Signer};
+
+    use super::*;
+
+    fn identity() -> Identity {
+        Identity::new("bob@wallet")
+    }
+
+    fn blob_of(data: Vec<u8>) -> Blob {
+        Blob {
+            contract_name: "native".into(),
+            data: BlobData(data),
+        }
+    }
+
+    #[test]
+    fn test_ed25519() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let data = b"hello".to_vec();
+        let mut blob = Ed25519Blob {
+            identity: identity(),
+            signature: ed25519_dalek::Signer::sign(&signing_key, &data).to_bytes(),
+            public_key: signing_key.verifying_key().to_bytes(),
+            data,
+        };
+        let res = verify_native_impl(&blob.as_blob(), NativeVerifiers::Ed25519).unwrap();
+        assert_eq!(res, (identity(), true));
+
+        blob.data = b"hellp".to_vec();
+        let res = verify_native_impl(&blob.as_blob(), NativeVerifiers::Ed25519).unwrap();
+        assert_eq!(res, (identity(), false));
+    }
+
+    #[test]
+    fn test_secp256r1() {
+        let signing_key = p256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+        let public_key: [u8; 33] = signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .try_into()
+            .unwrap();
+        let data: [u8; 32] = sha2::Sha256::digest(b"hello").into();
+        let signature: p256::ecdsa::Signature = signing_key.sign_prehash(&data).unwrap();
+
+        let mut blob = Secp256r1Blob {
+            identity: identity(),
+            data,
+            public_key,
+            signature: signature.to_bytes().into(),
+            webauthn: None,
+        };
+        let res = verify_native_impl(&blob.as_blob(), NativeVerifiers::Secp256r1).unwrap();
+        assert_eq!(res, (identity(), true));
+
+        blob.data = [0; 32];
+        let res = verify_native_impl(&blob.as_blob(), NativeVerifiers::Secp256r1).unwrap();
+        assert_eq!(res, (identity(), false));
+    }
+
+    #[test]
+    fn test_secp256r1_webauthn() {
+        let signing_key = p256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap();
+        let public_key: [u8; 33] = signing_key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .try_into()
+            .unwrap();
+        let data: [u8; 32] = sha2::Sha256::digest(b"hello").into();
+
+        let assertion = |challenge: &[u8], flags: u8| {
+            let client_data_json = format!(
+                r#"{{"type":"webauthn.get","challenge":"{}","origin":"https://hyli.org","crossOrigin":false}}"#,
+                base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(challenge)
+            )
+            .into_bytes();
+            let mut authenticator_data = vec![0; 37];
+            authenticator_data[WEBAUTHN_FLAGS_OFFSET] = flags;
+            let message = [
+                authenticator_data.as_slice(),
+                sha2::Sha256::digest(&client_data_json).as_slice(),
+            ]
+            .concat();
+            let signature: p256::ecdsa::Signature = signing_key.sign(&message);
+            (
+                signature.to_bytes().into(),
+                WebAuthnAssertion {
+                    authenticator_data,
+                    client_data_json,
+                },
+            )
+        };
+
+        let (signature, webauthn) = assertion(&data, WEBAUTHN_FLAG_USER_PRESENT);
+        let blob = Secp256r1Blob {
+            identity: identity(),
+            data,
+            public_key,
+            signature,
+            webauthn: Some(webauthn),
+        };
+        let res = verify_native_impl(&blob.as_blob(), NativeVerifiers::Secp256r1).unwrap();
+        assert_eq!(res, (identity(), true));
+
+        // Challenge signed by the authenticator is not the blob data
+        let (signature, webauthn) = assertion(&[0; 32], WEBAUTHN_FLAG_USER_PRESENT);
+        let blob = Secp256r1Blob {
+            signature,
+            webauthn: Some(webauthn),
+            ..blob
+        };
+        assert!(verify_native_impl(&blob.as_blob(), NativeVerifiers::Secp256r1).is_err());
+
+        // User was not present
+        let (signature, webauthn) = assertion(&data, 0);
+        let blob = Secp256r1Blob {
+            signature,
+            webauthn: Some(webauthn),
+            ..blob
+        };
+        assert!(verify_native_impl(&blob.as_blob(), NativeVerifiers::Secp256r1).is_err());
+    }
+
+    #[test]
+    fn test_keccak256() {
+        let data = b"hello".to_vec();
+        let blob = ShaBlob {
+            identity: identity(),
+            sha: sha3::Keccak256::digest(&data).to_vec(),
+            data,
+        };
+        let res = verify_native_impl(
+            &blob.as_blob("keccak256".into()),
+            NativeVerifiers::Keccak256,
+        )
+        .unwrap();
+        assert_eq!(res, (identity(), true));
+
+        // A sha3_256 hash is not a keccak256 hash
+        let res = verify_native_impl(
+            &blob_of(
+                borsh::to_vec(&ShaBlob {
+                    identity: identity(),
+                    sha: sha3::Sha3_256::digest(&blob.data).to_vec(),
+                    data: blob.data.clone(),
+                })
+                .unwrap(),
+            ),
+            NativeVerifiers::Keccak256,
+        )
+        .unwrap();
+        assert_eq!(res, (identity(), false));
     }
 }
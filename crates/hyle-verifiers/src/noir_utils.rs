@@ -36,20 +36,40 @@ pub fn parse_noir_output(output: &[u8], vkey: &[u8]) -> Result<HyleOutput, Error
     let Some(public_inputs) = extract_public_inputs(output, vkey) else {
         return Err(anyhow::anyhow!("Failed to extract public inputs"));
     };
+    parse_hyle_output_fields(public_inputs)
+}
+
+/// Decodes a HyleOutput from flattened 32-byte big-endian field elements.
+/// This is the public inputs layout shared by the Noir and Groth16 verifiers.
+/// Each item below is one field, unless stated otherwise:
+/// - version
+/// - initial_state: length, then one field per byte
+/// - next_state: length, then one field per byte
+/// - identity: length, then 256 fields, one per character (zero-padded)
+/// - tx_hash: 64 fields, one per hex character
+/// - index
+/// - blobs: count, then for each blob:
+///   - index
+///   - contract name: length, then 256 fields, one per character (zero-padded)
+///   - capacity, length, then `capacity` fields, one per byte (zero-padded)
+/// - tx_blob_count
+/// - success: 1 for true
+/// - optionally, program_outputs: length, then one field per byte
+pub fn parse_hyle_output_fields(public_inputs: &[u8]) -> Result<HyleOutput, Error> {
     let mut vector = deflatten_fields(public_inputs);
 
-    let version = u32::from_str_radix(&vector.remove(0), 16)?;
+    let version = u32::from_str_radix(&next_field(&mut vector)?, 16)?;
     debug!("Parsed version: {}", version);
     let initial_state = parse_array(&mut vector)?;
     let next_state = parse_array(&mut vector)?;
     let identity = parse_string_with_len(&mut vector)?;
     let tx_hash = parse_sized_string(&mut vector, 64)?;
-    let index = u32::from_str_radix(&vector.remove(0), 16)?;
+    let index = u32::from_str_radix(&next_field(&mut vector)?, 16)?;
     debug!("Parsed index: {}", index);
     let blobs = parse_blobs(&mut vector)?;
-    let tx_blob_count = usize::from_str_radix(&vector.remove(0), 16)?;
+    let tx_blob_count = usize::from_str_radix(&next_field(&mut vector)?, 16)?;
     debug!("Parsed tx_blob_count: {}", tx_blob_count);
-    let success = u32::from_str_radix(&vector.remove(0), 16)? == 1;
+    let success = u32::from_str_radix(&next_field(&mut vector)?, 16)? == 1;
     debug!("Parsed success: {}", success);
 
     Ok(HyleOutput {
@@ -73,10 +93,17 @@ pub fn parse_noir_output(output: &[u8], vkey: &[u8]) -> Result<HyleOutput, Error
     })
 }
 
+fn next_field(vector: &mut Vec<String>) -> Result<String, Error> {
+    if vector.is_empty() {
+        return Err(anyhow::anyhow!("Not enough public inputs"));
+    }
+    Ok(vector.remove(0))
+}
+
 fn parse_sized_string(vector: &mut Vec<String>, length: usize) -> Result<String, Error> {
     let mut resp = String::with_capacity(length);
     for _ in 0..length {
-        let code = u32::from_str_radix(&vector.remove(0), 16)?;
+        let code = u32::from_str_radix(&next_field(vector)?, 16)?;
         let ch = std::char::from_u32(code)
             .ok_or_else(|| anyhow::anyhow!("Invalid char code: {}", code))?;
         resp.push(ch);
@@ -88,7 +115,7 @@ fn parse_sized_string(vector: &mut Vec<String>, length: usize) -> Result<String,
 /// Parse a string of variable length, up to a maximum size of 256 bytes.
 /// Returns the string without trailing zeros.
 fn parse_string_with_len(vector: &mut Vec<String>) -> Result<String, Error> {
-    let length = usize::from_str_radix(&vector.remove(0), 16)?;
+    let length = usize::from_str_radix(&next_field(vector)?, 16)?;
     if length > 256 {
         return Err(anyhow::anyhow!(
             "Invalid contract name length {length}. Max is 256."
@@ -101,10 +128,10 @@ fn parse_string_with_len(vector: &mut Vec<String>) -> Result<String, Error> {
 }
 
 fn parse_array(vector: &mut Vec<String>) -> Result<Vec<u8>, Error> {
-    let length = usize::from_str_radix(&vector.remove(0), 16)?;
+    let length = usize::from_str_radix(&next_field(vector)?, 16)?;
     let mut resp = Vec::with_capacity(length);
     for _ in 0..length {
-        let num = u8::from_str_radix(&vector.remove(0), 16)?;
+        let num = u8::from_str_radix(&next_field(vector)?, 16)?;
         resp.push(num);
     }
     debug!("Parsed array of len: {}", length);
@@ -112,25 +139,25 @@ fn parse_array(vector: &mut Vec<String>) -> Result<Vec<u8>, Error> {
 }
 
 fn parse_blobs(blob_data: &mut Vec<String>) -> Result<IndexedBlobs, Error> {
-    let blob_number = usize::from_str_radix(&blob_data.remove(0), 16)?;
+    let blob_number = usize::from_str_radix(&next_field(blob_data)?, 16)?;
     let mut blobs = IndexedBlobs::default();
 
     debug!("blob_number: {}", blob_number);
 
     for _ in 0..blob_number {
-        let index = usize::from_str_radix(&blob_data.remove(0), 16)?;
+        let index = usize::from_str_radix(&next_field(blob_data)?, 16)?;
         debug!("blob index: {}", index);
 
         let contract_name = parse_string_with_len(blob_data)?;
 
-        let blob_capacity = usize::from_str_radix(&blob_data.remove(0), 16)?;
-        let blob_len = usize::from_str_radix(&blob_data.remove(0), 16)?;
+        let blob_capacity = usize::from_str_radix(&next_field(blob_data)?, 16)?;
+        let blob_len = usize::from_str_radix(&next_field(blob_data)?, 16)?;
         debug!("blob len: {} (capacity: {})", blob_len, blob_capacity);
 
         let mut blob = Vec::with_capacity(blob_capacity);
 
         for i in 0..blob_capacity {
-            let v = &blob_data.remove(0);
+            let v = &next_field(blob_data)?;
             blob.push(
                 u8::from_str_radix(v, 16)
                     .context(format!("Failed to parse blob data at {i}/{blob_capacity}"))?,
@@ -171,8 +171,12 @@ async fn main() -> Result<()> {
             .build_module::<SignedDAListener>(DAListenerConf {
                 data_directory: PathBuf::from("data"),
                 da_read_from: "localhost:4141".to_string(),
+                da_fallback_read_from: vec![],
+                cross_check: false,
+                trusted_validators: Default::default(),
                 start_block: Some(BlockHeight(0)),
                 timeout_client_secs: 10,
+                subscription: None,
             })
             .await?;
     } else {
@@ -43,8 +43,12 @@ async fn main() -> Result<()> {
         .build_module::<SignedDAListener>(DAListenerConf {
             data_directory: config.data_directory.clone(),
             da_read_from: config.da_read_from.clone(),
+            da_fallback_read_from: vec![],
+            cross_check: false,
+            trusted_validators: Default::default(),
             start_block: Some(BlockHeight(0)),
             timeout_client_secs: 10,
+            subscription: None,
         })
         .await?;
 
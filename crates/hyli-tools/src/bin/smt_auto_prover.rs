@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
 
 use anyhow::{Context, Result};
 use clap::{Parser, command};
@@ -16,7 +16,7 @@ use hyle_modules::{
         prover::{AutoProver, AutoProverCtx},
         rest::{ApiDoc, RestApi, RestApiRunContext, Router},
     },
-    utils::logger::setup_tracing,
+    utils::{da_codec::DaSubscription, logger::setup_tracing},
 };
 use prometheus::Registry;
 use serde::{Deserialize, Serialize};
@@ -89,7 +89,16 @@ async fn main() -> Result<()> {
             start_block: None,
             data_directory: config.data_directory.clone(),
             da_read_from: config.da_read_from.clone(),
+            da_fallback_read_from: vec![],
+            cross_check: false,
+            trusted_validators: Default::default(),
             timeout_client_secs: 10,
+            // Only the blocks' transactions for our contract are needed to prove it
+            subscription: Some(DaSubscription {
+                contracts: BTreeSet::from([config.contract_name.clone().into()]),
+                strip_proofs: true,
+                ..Default::default()
+            }),
         })
         .await?;
 
@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use hyle::{entrypoint::RunPg, utils::conf};
+use clap::{Parser, Subcommand};
+use hyle::{
+    data_availability::chain_archive,
+    entrypoint::RunPg,
+    model::BlockHeight,
+    utils::conf::{self, Conf},
+};
 use hyle_crypto::BlstCrypto;
 use hyle_modules::{log_error, utils::logger::setup_tracing};
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 use tracing::info;
 
 #[derive(Parser, Debug)]
@@ -23,6 +28,58 @@ pub struct Args {
 
     #[clap(long, action)]
     pub pg: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Move blocks between the DA store and portable chain archives, the node must be stopped
+    #[command(subcommand)]
+    Chain(ChainCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ChainCommand {
+    /// Write blocks of the DA store to an archive
+    Export {
+        #[arg(long)]
+        output: PathBuf,
+
+        #[arg(long, default_value_t = 0)]
+        from: u64,
+
+        /// Last block to export, included. Defaults to the last stored block
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Store the blocks of an archive in the DA store, which must be empty or end right before
+    /// them, and process them with the node state
+    Import {
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Check the checksums of an archive, that its blocks chain, and its genesis if it holds it
+    Verify {
+        #[arg(long)]
+        input: PathBuf,
+    },
+}
+
+fn run_chain_command(command: ChainCommand, config: &Conf) -> Result<()> {
+    match command {
+        ChainCommand::Export { output, from, to } => {
+            chain_archive::export(config, &output, BlockHeight(from), to.map(BlockHeight))?;
+        }
+        ChainCommand::Import { input } => {
+            chain_archive::import(config, &input)?;
+        }
+        ChainCommand::Verify { input } => {
+            chain_archive::verify(config, &input)?;
+        }
+    }
+    Ok(())
 }
 
 #[cfg(feature = "dhat")]
@@ -49,6 +106,14 @@ async fn main() -> Result<()> {
     let mut config = conf::Conf::new(args.config_file, args.data_directory, args.run_indexer)
         .context("reading config file")?;
 
+    if let Some(Command::Chain(command)) = args.command {
+        setup_tracing(&config.log_format, "chain".to_string())?;
+        return log_error!(
+            run_chain_command(command, &config),
+            "Error running chain command"
+        );
+    }
+
     let crypto = Arc::new(BlstCrypto::new(&config.id).context("Could not create crypto")?);
     let pubkey = Some(crypto.validator_pubkey().clone());
 
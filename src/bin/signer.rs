@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use hyle_crypto::keystore::{self, Keystore};
+use hyle_modules::utils::logger::setup_tracing;
+use tracing::info;
+
+/// Holds a validator key and signs for a node connecting on a local socket.
+/// Keystore passphrases are read from HYLE_KEYSTORE_PASSPHRASE or HYLE_KEYSTORE_PASSPHRASE_FILE.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Create an encrypted keystore, with a new random key unless HYLE_VALIDATOR_SECRET is set
+    NewKeystore {
+        #[arg(long)]
+        keystore: PathBuf,
+    },
+    /// Sign requests from the node listening on the socket, with the key of the keystore
+    Run {
+        #[arg(long)]
+        keystore: PathBuf,
+
+        #[arg(long, default_value = "signer.sock")]
+        socket: PathBuf,
+
+        /// Where the last signed slot and view are stored, must be kept across restarts
+        #[arg(long, default_value = "slashing_protection.bin")]
+        slashing_protection: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    setup_tracing("full", "signer".to_string())?;
+
+    match args.command {
+        Command::NewKeystore { keystore } => {
+            let passphrase = keystore::passphrase_from_env()?;
+            let new_keystore = match std::env::var("HYLE_VALIDATOR_SECRET") {
+                Ok(secret) => Keystore::encrypt(&hex::decode(secret)?, &passphrase)?,
+                Err(_) => Keystore::generate(&passphrase)?,
+            };
+            new_keystore.save(&keystore)?;
+            info!(
+                "🔑 Keystore for validator {} written to {}",
+                new_keystore.pubkey,
+                keystore.display()
+            );
+        }
+        Command::Run {
+            keystore,
+            socket,
+            slashing_protection,
+        } => run(keystore, socket, slashing_protection)?,
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn run(keystore: PathBuf, socket: PathBuf, slashing_protection: PathBuf) -> Result<()> {
+    use std::os::unix::{fs::FileTypeExt, net::UnixListener};
+
+    use anyhow::{bail, Context};
+    use hyle::p2p::signing_policy::NodeSigningPolicy;
+    use hyle_crypto::{remote_signer::SignerServer, signer::SlashingProtection, BlstCrypto};
+
+    let crypto = BlstCrypto::from_keystore(
+        &Keystore::load(&keystore)?,
+        &keystore::passphrase_from_env()?,
+    )?;
+    let protection = SlashingProtection::open(&slashing_protection)?;
+
+    if socket.exists() {
+        if !std::fs::metadata(&socket)?.file_type().is_socket() {
+            bail!("{} exists and is not a socket", socket.display());
+        }
+        // Left over by a previous run
+        std::fs::remove_file(&socket)?;
+    }
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Listening on {}", socket.display()))?;
+    info!(
+        "🔏 Signing for validator {} on {}",
+        crypto.validator_pubkey(),
+        socket.display()
+    );
+    SignerServer::new(crypto, protection, NodeSigningPolicy)?.serve(listener)
+}
+
+/// The node reaches the signer on a unix socket, which other platforms don't have
+#[cfg(not(unix))]
+fn run(_keystore: PathBuf, _socket: PathBuf, _slashing_protection: PathBuf) -> Result<()> {
+    anyhow::bail!("The signer listens on a unix socket, it only runs on unix platforms")
+}
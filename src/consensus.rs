@@ -2,12 +2,12 @@
 
 use crate::bus::BusClientSender;
 use crate::model::*;
-use crate::node_state::module::NodeStateEvent;
+use crate::node_state::module::{NodeStateEvent, QueryContractRegistryRoot};
 use crate::p2p::network::HeaderSigner;
 use crate::{
-    bus::command_response::Query,
+    bus::command_response::{CmdRespClient, Query},
     genesis::GenesisEvent,
-    mempool::QueryNewCut,
+    mempool::{NewCut, QueryNewCut, QueryTxRoot},
     model::{Cut, Hashed, ValidatorPublicKey},
     p2p::{
         network::{MsgWithHeader, OutboundMessage},
@@ -17,11 +17,14 @@ use crate::{
 };
 use anyhow::{anyhow, bail, Context, Error, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
+use hyle_crypto::signer::SignerMessage;
 use hyle_crypto::BlstCrypto;
 use hyle_crypto::SharedBlstCrypto;
+use hyle_light_client::ValidatorSetTransition;
 use hyle_model::utils::TimestampMs;
 use hyle_modules::{log_error, module_bus_client, module_handle_messages, modules::Module};
 use hyle_net::clock::TimestampMsClock;
+use leader_election::LeaderElection;
 use metrics::ConsensusMetrics;
 use role_follower::FollowerState;
 use role_leader::LeaderState;
@@ -31,11 +34,19 @@ use staking::state::{Staking, MIN_STAKE};
 use std::ops::Deref;
 use std::ops::DerefMut;
 use std::time::Duration;
-use std::{collections::HashMap, default::Default, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    default::Default,
+    path::PathBuf,
+};
 use tokio::time::interval;
 use tracing::{debug, info, trace};
+use wal::{ConsensusWal, SignedKind};
 
 pub mod api;
+mod equivocation;
+pub mod leader_election;
+mod light_client;
 pub mod metrics;
 pub mod module;
 mod network;
@@ -43,8 +54,13 @@ pub mod role_follower;
 pub mod role_leader;
 pub mod role_sync;
 pub mod role_timeout;
+mod wal;
 
 pub use network::*;
+pub use wal::signed_record;
+
+/// How often the node state is asked again for the contract registry root after the parent block
+const REGISTRY_ROOT_RETRY_INTERVAL: Duration = Duration::from_millis(50);
 
 // -----------------------------
 // ------ Consensus bus --------
@@ -54,6 +70,8 @@ pub use network::*;
 pub enum ConsensusCommand {
     TimeoutTick,
     StartNewSlot(Option<TimestampMs>), // If none, may delay, if some, may delay up to that timestamp
+    /// Equivocation detected by another module, to be gossiped and included in a proposal
+    ReportEquivocation(Box<EquivocationEvidence>),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, BorshSerialize, BorshDeserialize)]
@@ -75,19 +93,27 @@ pub struct QueryConsensusInfo {}
 #[derive(Clone)]
 pub struct QueryConsensusStakingState {}
 
+#[derive(Clone)]
+pub struct QueryValidatorSetTransitions {
+    pub from_slot: Slot,
+}
+
 module_bus_client! {
 struct ConsensusBusClient {
 sender(OutboundMessage),
 sender(ConsensusEvent),
 sender(ConsensusCommand),
 sender(P2PCommand),
-sender(Query<QueryNewCut, Cut>),
+sender(Query<QueryNewCut, NewCut>),
+sender(Query<QueryTxRoot, Option<MerkleHash>>),
+sender(Query<QueryContractRegistryRoot, Option<MerkleHash>>),
 receiver(ConsensusCommand),
 receiver(GenesisEvent),
 receiver(NodeStateEvent),
 receiver(MsgWithHeader<ConsensusNetMessage>),
 receiver(Query<QueryConsensusInfo, ConsensusInfo>),
 receiver(Query<QueryConsensusStakingState, Staking>),
+receiver(Query<QueryValidatorSetTransitions, Vec<ValidatorSetTransition>>),
 }
 }
 
@@ -110,6 +136,11 @@ pub struct BFTRoundState {
     joining: JoiningState,
     genesis: GenesisState,
     state_tag: StateTag,
+
+    /// Seed of the leader election, from the parent proposal, see [leader_election::election_seed]
+    election_seed: Vec<u8>,
+    /// Number of slots of each epoch, as set by the genesis block
+    epoch_length: EpochLength,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Default, Debug)]
@@ -134,6 +165,16 @@ pub struct ConsensusStore {
     bft_round_state: BFTRoundState,
     /// Validators that asked to be part of consensus
     validator_candidates: Vec<SignedByValidator<ValidatorCandidacy>>,
+    /// Verified equivocation evidence waiting to be included in a proposal, by offender
+    pending_evidence: BTreeMap<ValidatorPublicKey, EquivocationEvidence>,
+    /// Prepares received recently, to detect leaders preparing conflicting proposals
+    #[borsh(skip)]
+    seen_prepares: HashMap<(ValidatorPublicKey, Slot, View), SignedPrepare>,
+    /// Votes received recently, to detect followers voting for conflicting proposals
+    #[borsh(skip)]
+    seen_votes: HashMap<(ValidatorPublicKey, VoteKind, Slot, View), SignedVote>,
+    /// Committed proposals that changed the validator set, for light clients
+    validator_set_transitions: Vec<ValidatorSetTransition>,
 }
 
 pub struct Consensus {
@@ -141,7 +182,8 @@ pub struct Consensus {
     bus: ConsensusBusClient,
     file: Option<PathBuf>,
     store: ConsensusStore,
-    #[allow(dead_code)]
+    /// Signed messages, logged before they are sent
+    wal: ConsensusWal,
     config: SharedConf,
     crypto: SharedBlstCrypto,
 }
@@ -175,21 +217,12 @@ macro_rules! with_metric {
 
 impl Consensus {
     fn round_leader(&self) -> Result<ValidatorPublicKey> {
-        // Find out who the next leader will be.
-        // For now, this is round-robin on slot + view for simplicity.
-        // (we remove 1 for backwards compatibility of the tests when making the change)
-        let index = (self.bft_round_state.slot as usize + self.bft_round_state.view as usize)
-            .wrapping_sub(1)
-            % self.bft_round_state.staking.bonded().len();
-
-        // Get the next leader based on the random index
-        Ok(self
-            .bft_round_state
-            .staking
-            .bonded()
-            .get(index)
-            .context("No next leader found")?
-            .clone())
+        self.config.consensus.leader_election.elect(
+            &self.bft_round_state.staking,
+            &self.bft_round_state.election_seed,
+            self.bft_round_state.slot,
+            self.bft_round_state.view,
+        )
     }
     fn next_view_leader(&mut self) -> Result<ValidatorPublicKey> {
         self.bft_round_state.view += 1;
@@ -211,11 +244,30 @@ impl Consensus {
         self.bft_round_state.timeout.requests.clear();
 
         match ticket {
+            // Without a commit certificate, light clients couldn't follow the change
+            Ticket::ForcedCommitQc if self.current_proposal_changes_voting_power() => {
+                bail!("Cannot force the commit of a proposal changing the validator set");
+            }
             // We finished the round with a committed proposal for the slot
             Ticket::CommitQC(_) | Ticket::ForcedCommitQc => {
+                let committed_slot = self.bft_round_state.current_proposal.slot;
+                // Light clients need the proposal ending each epoch, even without stake changes
+                let transition = match &ticket {
+                    Ticket::CommitQC(qc)
+                        if self.current_proposal_changes_voting_power()
+                            || self.bft_round_state.epoch_length.ends_epoch(committed_slot) =>
+                    {
+                        Some((self.bft_round_state.current_proposal.clone(), qc.clone()))
+                    }
+                    _ => None,
+                };
                 self.bft_round_state.slot += 1;
                 self.bft_round_state.view = 0;
                 self.bft_round_state.parent_hash = self.bft_round_state.current_proposal.hashed();
+                self.bft_round_state.election_seed = leader_election::election_seed(
+                    &self.bft_round_state.current_proposal.parent_hash,
+                    &self.bft_round_state.current_proposal.parent_commit_qc,
+                );
                 self.bft_round_state.parent_timestamp =
                     self.bft_round_state.current_proposal.timestamp.clone();
                 self.bft_round_state.parent_cut = self.bft_round_state.current_proposal.cut.clone();
@@ -226,6 +278,7 @@ impl Consensus {
                     Ticket::ForcedCommitQc => None,
                     _ => unreachable!(),
                 };
+                let mut next_epoch_stakes = None;
                 for action in
                     std::mem::take(&mut self.bft_round_state.current_proposal.staking_actions)
                 {
@@ -248,6 +301,30 @@ impl Consensus {
                             .staking
                             .pay_for_dadi(lane_id, cumul_size)
                             .map_err(|e| anyhow::anyhow!(e))?,
+                        ConsensusStakingAction::Slash { evidence } => {
+                            let offender = evidence.offender();
+                            info!("⚔️ Validator slashed for equivocation: {}", offender);
+                            self.store.pending_evidence.remove(offender);
+                            self.store
+                                .bft_round_state
+                                .staking
+                                .slash(offender)
+                                .map_err(|e| anyhow::anyhow!(e))?;
+                        }
+                        ConsensusStakingAction::Unbond { validator } => {
+                            info!("👋 Validator left consensus: {}", validator);
+                            self.store
+                                .bft_round_state
+                                .staking
+                                .unbond(&validator)
+                                .map_err(|e| anyhow::anyhow!(e))?;
+                        }
+                        ConsensusStakingAction::EpochStakes { stakes } => {
+                            next_epoch_stakes = Some(stakes);
+                        }
+                        ConsensusStakingAction::EpochLength { .. } => {
+                            bail!("Only the genesis block can set the epoch length");
+                        }
                     }
                 }
                 self.store
@@ -255,6 +332,10 @@ impl Consensus {
                     .staking
                     .distribute()
                     .map_err(|e| anyhow::anyhow!(e))?;
+                self.start_next_epoch(committed_slot, next_epoch_stakes);
+                if let Some((proposal, qc)) = transition {
+                    self.record_validator_set_transition(&proposal, &qc)?;
+                }
             }
             // We finished the round with a timeout
             Ticket::TimeoutQC(..) => {
@@ -401,8 +482,11 @@ impl Consensus {
 
     /// Connect to all validators & ask to be part of consensus
     fn send_candidacy(&mut self) -> Result<()> {
-        let candidacy = self.crypto.sign(ValidatorCandidacy {
+        let candidacy = ValidatorCandidacy {
             peer_address: self.config.p2p.public_address.clone(),
+        };
+        let candidacy = self.crypto.sign_typed(candidacy.clone(), || {
+            Ok(SignerMessage::Candidacy(candidacy))
         })?;
         info!(
             "📝 Sending candidacy message to be part of consensus. {}",
@@ -413,7 +497,44 @@ impl Consensus {
         Ok(())
     }
 
-    fn handle_net_message(&mut self, msg: MsgWithHeader<ConsensusNetMessage>) -> Result<(), Error> {
+    async fn handle_net_message(
+        &mut self,
+        msg: MsgWithHeader<ConsensusNetMessage>,
+    ) -> Result<(), Error> {
+        match &msg.msg {
+            ConsensusNetMessage::Prepare(consensus_proposal, ticket, view) => {
+                let _ = log_error!(
+                    self.record_prepare(&msg.header, consensus_proposal, ticket, *view),
+                    "Checking Prepare for equivocation"
+                );
+            }
+            ConsensusNetMessage::PrepareVote(prepare_vote, slot, view) => {
+                let _ = log_error!(
+                    self.record_vote(
+                        &msg.header,
+                        VoteKind::PrepareVote,
+                        &prepare_vote.msg.0,
+                        *slot,
+                        *view
+                    ),
+                    "Checking PrepareVote for equivocation"
+                );
+            }
+            ConsensusNetMessage::ConfirmAck(confirm_ack, slot, view) => {
+                let _ = log_error!(
+                    self.record_vote(
+                        &msg.header,
+                        VoteKind::ConfirmAck,
+                        &confirm_ack.msg.0,
+                        *slot,
+                        *view
+                    ),
+                    "Checking ConfirmAck for equivocation"
+                );
+            }
+            _ => {}
+        }
+
         let MsgWithHeader::<ConsensusNetMessage> {
             msg: net_message,
             header:
@@ -433,9 +554,10 @@ impl Consensus {
                     self.metrics,
                     "on_prepare",
                     self.on_prepare(sender, consensus_proposal, ticket, view)
+                        .await
                 )
             }
-            ConsensusNetMessage::PrepareVote(prepare_vote) => {
+            ConsensusNetMessage::PrepareVote(prepare_vote, ..) => {
                 with_metric!(
                     self.metrics,
                     "on_prepare_vote",
@@ -449,7 +571,7 @@ impl Consensus {
                     self.on_confirm(sender, prepare_quorum_certificate, proposal_hash_hint)
                 )
             }
-            ConsensusNetMessage::ConfirmAck(confirm_ack) => {
+            ConsensusNetMessage::ConfirmAck(confirm_ack, ..) => {
                 with_metric!(
                     self.metrics,
                     "on_confirm_ack",
@@ -488,7 +610,18 @@ impl Consensus {
                 )
             }
             ConsensusNetMessage::SyncReply(prepare) => {
-                with_metric!(self.metrics, "on_sync_reply", self.on_sync_reply(prepare))
+                with_metric!(
+                    self.metrics,
+                    "on_sync_reply",
+                    self.on_sync_reply(prepare).await
+                )
+            }
+            ConsensusNetMessage::Equivocation(evidence) => {
+                with_metric!(
+                    self.metrics,
+                    "on_equivocation",
+                    self.on_equivocation(*evidence, false)
+                )
             }
         }
     }
@@ -542,6 +675,89 @@ impl Consensus {
         )
     }
 
+    /// Freezes the validator set of the next epoch, if the committed slot ends the current one.
+    /// Without stakes listed by the committed proposal, the epoch keeps the current ones.
+    fn start_next_epoch(
+        &mut self,
+        committed_slot: Slot,
+        stakes: Option<Vec<(ValidatorPublicKey, u128)>>,
+    ) {
+        if self.bft_round_state.epoch_length.ends_epoch(committed_slot) {
+            let epoch = self
+                .bft_round_state
+                .epoch_length
+                .epoch_of(committed_slot + 1);
+            debug!("🗓️ Starting epoch {}", epoch);
+            let stakes = match stakes {
+                Some(stakes) => stakes.into_iter().collect(),
+                None => self.bft_round_state.staking.voting_stakes(),
+            };
+            self.bft_round_state.staking.start_epoch(epoch, stakes);
+        }
+    }
+
+    /// Stakes of the next epoch, when the slot ends the current one and they differ from its stakes.
+    /// Leader and followers compute them from the staking actions of the proposal.
+    fn next_epoch_stakes(
+        &self,
+        slot: Slot,
+        actions: &[ConsensusStakingAction],
+    ) -> Result<Option<Vec<(ValidatorPublicKey, u128)>>> {
+        if !self.bft_round_state.epoch_length.ends_epoch(slot) {
+            return Ok(None);
+        }
+        let mut staking = self.bft_round_state.staking.clone();
+        for action in actions {
+            match action {
+                ConsensusStakingAction::Bond { candidate } => {
+                    staking.bond(candidate.signature.validator.clone())
+                }
+                ConsensusStakingAction::Slash { evidence } => {
+                    staking.slash(evidence.offender()).map(|_| ())
+                }
+                ConsensusStakingAction::Unbond { validator } => staking.unbond(validator),
+                ConsensusStakingAction::PayFeesForDaDi { .. }
+                | ConsensusStakingAction::EpochStakes { .. }
+                | ConsensusStakingAction::EpochLength { .. } => Ok(()),
+            }
+            .map_err(|e| anyhow!(e))?;
+        }
+        let stakes = staking.bonded_stakes();
+        if staking
+            .frozen_epoch()
+            .is_some_and(|epoch| epoch.stakes == stakes)
+        {
+            return Ok(None);
+        }
+        Ok(Some(stakes.into_iter().collect()))
+    }
+
+    /// Contract registry root after the parent block, see
+    /// [ConsensusProposal::parent_contract_registry_root]. The node state handles the parent
+    /// block after it is committed, so we wait for it up to the slot duration.
+    async fn parent_contract_registry_root(
+        &mut self,
+        parent_hash: &ConsensusProposalHash,
+    ) -> Result<MerkleHash> {
+        let query = QueryContractRegistryRoot(parent_hash.clone());
+        let bus = &mut self.bus;
+        tokio::time::timeout(self.config.consensus.slot_duration, async move {
+            loop {
+                let root = bus
+                    .shutdown_aware_request::<Self>(query.clone())
+                    .await
+                    .context("Querying the contract registry root after the parent block")?;
+                if let Some(root) = root {
+                    return Ok::<_, anyhow::Error>(root);
+                }
+                trace!("Waiting for the node state to handle the parent block");
+                tokio::time::sleep(REGISTRY_ROOT_RETRY_INTERVAL).await;
+            }
+        })
+        .await
+        .context("Timeout while waiting for the node state to handle the parent block")?
+    }
+
     /// Emits an event that will make Mempool able to build a block
     fn emit_commit_event(&mut self, commit_quorum_certificate: &CommitQC) -> Result<()> {
         self.metrics.commit();
@@ -610,6 +826,10 @@ impl Consensus {
         match msg {
             NodeStateEvent::NewBlock(block) => {
                 let block_total_tx = block.total_txs();
+                if let Some(epoch_length) = block.epoch_length {
+                    debug!("🗓️ Epochs last {} slots", epoch_length.0);
+                    self.store.bft_round_state.epoch_length = epoch_length;
+                }
                 self.store
                     .bft_round_state
                     .staking
@@ -625,9 +845,17 @@ impl Consensus {
                         );
                         self.store.bft_round_state.joining.staking_updated_to =
                             block.block_height.0;
+                        self.start_next_epoch(
+                            block.block_height.0,
+                            block.next_epoch_stakes.clone(),
+                        );
                         self.store.bft_round_state.slot = block.block_height.0 + 1;
                         self.store.bft_round_state.view = 0;
                         self.store.bft_round_state.parent_hash = block.hash.clone();
+                        self.store.bft_round_state.election_seed = leader_election::election_seed(
+                            &block.parent_hash,
+                            &block.parent_commit_qc,
+                        );
                         // Some of our internal logic relies on BFT slot + 1 == cp slot to mean we have committed, so do that.
                         self.store.bft_round_state.current_proposal = ConsensusProposal {
                             slot: block.block_height.0,
@@ -649,11 +877,13 @@ impl Consensus {
                 self.start_round(TimestampMsClock::now(), may_delay).await?;
                 Ok(())
             }
+            ConsensusCommand::ReportEquivocation(evidence) => self.on_equivocation(*evidence, true),
         }
     }
 
     #[inline(always)]
     fn broadcast_net_message(&mut self, net_message: ConsensusNetMessage) -> Result<()> {
+        self.log_signed(&net_message)?;
         let signed_msg = self.sign_net_message(net_message)?;
         let enum_variant_name: &'static str = (&signed_msg.msg).into();
         self.bus
@@ -670,6 +900,7 @@ impl Consensus {
         to: ValidatorPublicKey,
         net_message: ConsensusNetMessage,
     ) -> Result<()> {
+        self.log_signed(&net_message)?;
         let signed_msg = self.sign_net_message(net_message)?;
         let enum_variant_name: &'static str = (&signed_msg.msg).into();
         self.bus
@@ -700,6 +931,14 @@ impl Consensus {
                         };
 
                         self.bft_round_state.parent_hash = signed_block.hashed();
+                        self.bft_round_state.election_seed = leader_election::election_seed(
+                            &signed_block.consensus_proposal.parent_hash,
+                            &signed_block.consensus_proposal.parent_commit_qc,
+                        );
+                        // The genesis validators make up the first epoch
+                        let epoch = self.bft_round_state.epoch_length.epoch_of(1);
+                        let stakes = self.bft_round_state.staking.bonded_stakes();
+                        self.bft_round_state.staking.start_epoch(epoch, stakes);
                         self.bft_round_state.slot = 1;
                         self.bft_round_state.view = 0;
                         let round_leader = self.round_leader()?;
@@ -775,19 +1014,25 @@ impl Consensus {
                 let _ = log_error!(self.handle_command(cmd).await, "Error while handling consensus command");
             }
             listen<MsgWithHeader<ConsensusNetMessage>> msg => {
-                let _ = log_error!(self.handle_net_message(msg), "Consensus message failed");
+                let _ = log_error!(self.handle_net_message(msg).await, "Consensus message failed");
             }
             command_response<QueryConsensusInfo, ConsensusInfo> _ => {
                 let slot = self.bft_round_state.slot;
                 let view = self.bft_round_state.view;
+                let epoch = self.bft_round_state.epoch_length.epoch_of(slot);
                 let round_leader = self.round_leader()?;
                 let last_timestamp = self.bft_round_state.parent_timestamp.clone();
-                let validators = self.bft_round_state.staking.bonded().clone();
-                Ok(ConsensusInfo { slot, view, round_leader, last_timestamp, validators })
+                let stakes = self.bft_round_state.staking.voting_stakes();
+                let validators = stakes.keys().cloned().collect();
+                let total_bond = stakes.values().sum();
+                Ok(ConsensusInfo { slot, view, epoch, round_leader, last_timestamp, validators, stakes, total_bond })
             }
             command_response<QueryConsensusStakingState, Staking> _ => {
                 Ok(self.bft_round_state.staking.clone())
             }
+            command_response<QueryValidatorSetTransitions, Vec<ValidatorSetTransition>> query => {
+                Ok(self.validator_set_transitions(query.from_slot))
+            }
             _ = timeout_ticker.tick() => {
                 log_error!(self.bus.send(ConsensusCommand::TimeoutTick), "Cannot send message over channel")?;
             }
@@ -834,9 +1079,10 @@ pub mod test {
         tests::autobahn_testing::{
             broadcast, build_tuple, send, simple_commit_round, AutobahnBusClient, AutobahnTestCtx,
         },
-        utils::conf::Conf,
+        utils::conf::{Conf, LeaderElectionStrategy},
     };
     use assertables::assert_contains;
+    use staking::state::UNBONDING_DELAY;
     use tokio::sync::broadcast::Receiver;
     use utils::TimestampMs;
 
@@ -887,6 +1133,7 @@ pub mod test {
                 bus,
                 file: None,
                 store,
+                wal: ConsensusWal::default(),
                 config: Arc::new(conf),
                 crypto: Arc::new(crypto),
             }
@@ -903,8 +1150,14 @@ pub mod test {
             tokio::spawn(async move {
                 handle_messages! {
                     on_bus new_cut_query_receiver,
-                    command_response<QueryNewCut, Cut> _ => {
-                        Ok(Cut::default())
+                    command_response<QueryNewCut, NewCut> _ => {
+                        Ok(NewCut::default())
+                    }
+                    command_response<QueryTxRoot, Option<MerkleHash>> _ => {
+                        Ok(Some(EMPTY_MERKLE_ROOT))
+                    }
+                    command_response<QueryContractRegistryRoot, Option<MerkleHash>> _ => {
+                        Ok(Some(EMPTY_MERKLE_ROOT))
                     }
                 };
             });
@@ -923,6 +1176,13 @@ pub mod test {
             for other_crypto in cryptos.iter() {
                 self.add_trusted_validator(other_crypto.validator_pubkey());
             }
+            // Like after genesis, the trusted validators make up the first epoch
+            let epoch = self.consensus.bft_round_state.epoch_length.epoch_of(1);
+            let stakes = self.consensus.bft_round_state.staking.bonded_stakes();
+            self.consensus
+                .bft_round_state
+                .staking
+                .start_epoch(epoch, stakes);
 
             self.consensus.bft_round_state.slot = 1;
             self.consensus.bft_round_state.parent_hash =
@@ -936,6 +1196,50 @@ pub mod test {
             }
         }
 
+        /// Switches the leader election strategy, and who leads the current round accordingly.
+        pub fn set_leader_election(&mut self, strategy: LeaderElectionStrategy) {
+            let mut conf = (*self.consensus.config).clone();
+            conf.consensus.leader_election = strategy;
+            self.consensus.config = Arc::new(conf);
+
+            if self.round_leader() == self.pubkey() {
+                self.consensus.bft_round_state.state_tag = StateTag::Leader;
+                self.consensus.bft_round_state.leader.pending_ticket = Some(Ticket::Genesis);
+            } else {
+                self.consensus.bft_round_state.state_tag = StateTag::Follower;
+                self.consensus.bft_round_state.leader.pending_ticket = None;
+            }
+        }
+
+        pub(crate) fn round_leader(&self) -> ValidatorPublicKey {
+            self.consensus
+                .round_leader()
+                .expect("No round leader could be elected")
+        }
+
+        /// Leader of the given view of the slot after the next one, seeded by the commit QC
+        /// of the last committed proposal, which the proposal of the next slot carries.
+        pub(crate) fn leader_after_next_slot(&self, view: View) -> ValidatorPublicKey {
+            let bft_round_state = &self.consensus.bft_round_state;
+            let commit_qc = bft_round_state
+                .follower
+                .buffered_quorum_certificate
+                .as_ref()
+                .expect("No commit QC for the next proposal to carry");
+            let seed = leader_election::election_seed(&bft_round_state.parent_hash, &commit_qc.0);
+            self.consensus
+                .config
+                .consensus
+                .leader_election
+                .elect(
+                    &bft_round_state.staking,
+                    &seed,
+                    bft_round_state.slot + 1,
+                    view,
+                )
+                .expect("No round leader could be elected")
+        }
+
         pub fn setup_for_joining(&mut self, nodes: &[&ConsensusTestCtx]) {
             for other_node in nodes.iter() {
                 self.add_trusted_validator(other_node.consensus.crypto.validator_pubkey());
@@ -953,6 +1257,10 @@ pub mod test {
             self.consensus.bft_round_state.staking.clone()
         }
 
+        pub fn parent_cut(&self) -> Cut {
+            self.consensus.bft_round_state.parent_cut.clone()
+        }
+
         pub async fn timeout(nodes: &mut [&mut ConsensusTestCtx]) {
             for n in nodes {
                 n.consensus
@@ -1016,13 +1324,45 @@ pub mod test {
             leader: usize,
             slot: u64,
             view: u64,
+        ) {
+            Self::setup_for_elected_round(nodes, leader, slot, view, &[(slot, view, leader)], 0);
+        }
+
+        /// Like [Self::setup_for_round], with an election seed under which the node at each index
+        /// leads the given slot and view, whatever the strategy. Only the leaders of the slot and of
+        /// the next one follow from the seed, `attempt` picks another seed fitting them.
+        pub fn setup_for_elected_round(
+            nodes: &mut [&mut ConsensusTestCtx],
+            leader: usize,
+            slot: u64,
+            view: u64,
+            leaders: &[(Slot, View, usize)],
+            attempt: usize,
         ) {
             // TODO: write a real one?
             let commit_qc = QuorumCertificate(AggregateSignature::default(), ConfirmAckMarker);
 
+            // The proposal of the slot carries that empty commit QC, so its parent hash seeds both slots
+            let strategy = nodes[0].consensus.config.consensus.leader_election;
+            let staking = nodes[0].staking();
+            let parent_hash = (0..100_000)
+                .map(|i| ConsensusProposalHash(format!("parent-{i}")))
+                .filter(|parent_hash| {
+                    let seed = leader_election::election_seed(parent_hash, &commit_qc.0);
+                    leaders.iter().all(|(slot, view, leader)| {
+                        strategy.elect(&staking, &seed, *slot, *view).ok()
+                            == Some(nodes[*leader].pubkey())
+                    })
+                })
+                .nth(attempt)
+                .expect("No election seed elects the expected leaders");
+            let election_seed = leader_election::election_seed(&parent_hash, &commit_qc.0);
+
             for (index, node) in nodes.iter_mut().enumerate() {
                 node.consensus.bft_round_state.slot = slot;
                 node.consensus.bft_round_state.view = view;
+                node.consensus.bft_round_state.parent_hash = parent_hash.clone();
+                node.consensus.bft_round_state.election_seed = election_seed.clone();
 
                 node.consensus
                     .bft_round_state
@@ -1045,7 +1385,10 @@ pub mod test {
             err: &str,
         ) {
             debug!("📥 {} Handling message: {:?}", self.name, msg);
-            self.consensus.handle_net_message(msg.clone()).expect(err);
+            self.consensus
+                .handle_net_message(msg.clone())
+                .await
+                .expect(err);
         }
 
         pub(crate) async fn handle_msg_err(
@@ -1053,7 +1396,11 @@ pub mod test {
             msg: &MsgWithHeader<ConsensusNetMessage>,
         ) -> Error {
             debug!("📥 {} Handling message expecting err: {:?}", self.name, msg);
-            let err = self.consensus.handle_net_message(msg.clone()).unwrap_err();
+            let err = self
+                .consensus
+                .handle_net_message(msg.clone())
+                .await
+                .unwrap_err();
             info!("Expected error: {:#}", err);
             err
         }
@@ -1277,9 +1624,9 @@ pub mod test {
         send! {
             description: "Follower - PrepareVote",
             from: [
-                node2; ConsensusNetMessage::PrepareVote(Signed { msg: (cp_hash, _), .. }) => { assert_eq!(cp_hash, cp_round_hash); },
-                node3; ConsensusNetMessage::PrepareVote(Signed { msg: (cp_hash, _), .. }) => { assert_eq!(cp_hash, cp_round_hash); },
-                node4; ConsensusNetMessage::PrepareVote(Signed { msg: (cp_hash, _), .. }) => { assert_eq!(cp_hash, cp_round_hash); }
+                node2; ConsensusNetMessage::PrepareVote(Signed { msg: (cp_hash, _), .. }, ..) => { assert_eq!(cp_hash, cp_round_hash); },
+                node3; ConsensusNetMessage::PrepareVote(Signed { msg: (cp_hash, _), .. }, ..) => { assert_eq!(cp_hash, cp_round_hash); },
+                node4; ConsensusNetMessage::PrepareVote(Signed { msg: (cp_hash, _), .. }, ..) => { assert_eq!(cp_hash, cp_round_hash); }
             ], to: node1
         };
 
@@ -1292,9 +1639,9 @@ pub mod test {
         send! {
             description: "Follower - Confirm Ack",
             from: [
-                node2; ConsensusNetMessage::ConfirmAck(Signed { msg: (cp_hash, _), .. }) => { assert_eq!(cp_hash, cp_round_hash); },
-                node3; ConsensusNetMessage::ConfirmAck(Signed { msg: (cp_hash, _), .. }) => { assert_eq!(cp_hash, cp_round_hash); },
-                node4; ConsensusNetMessage::ConfirmAck(Signed { msg: (cp_hash, _), .. }) => { assert_eq!(cp_hash, cp_round_hash); }
+                node2; ConsensusNetMessage::ConfirmAck(Signed { msg: (cp_hash, _), .. }, ..) => { assert_eq!(cp_hash, cp_round_hash); },
+                node3; ConsensusNetMessage::ConfirmAck(Signed { msg: (cp_hash, _), .. }, ..) => { assert_eq!(cp_hash, cp_round_hash); },
+                node4; ConsensusNetMessage::ConfirmAck(Signed { msg: (cp_hash, _), .. }, ..) => { assert_eq!(cp_hash, cp_round_hash); }
             ], to: node1
         };
 
@@ -1305,6 +1652,14 @@ pub mod test {
                 assert_eq!(cp_hash, cp_round_hash);
             }
         };
+
+        // The leader logged the votes it aggregated for itself
+        for kind in [SignedKind::PrepareVote, SignedKind::ConfirmAck] {
+            assert_eq!(
+                node1.consensus.wal.last(kind).map(|record| &record.hash),
+                Some(cp_round_hash)
+            );
+        }
     }
 
     #[test_log::test(tokio::test)]
@@ -1320,6 +1675,7 @@ pub mod test {
 
         let cp = ConsensusProposal {
             slot: 2,
+            epoch: 0,
             timestamp: TimestampMs(123),
             cut: vec![(
                 LaneId(node2.pubkey()),
@@ -1329,6 +1685,9 @@ pub mod test {
             )],
             staking_actions: vec![],
             parent_hash: ConsensusProposalHash("hash".into()),
+            tx_root: EMPTY_MERKLE_ROOT,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
         };
 
         // Create wrong prepare
@@ -1400,6 +1759,7 @@ pub mod test {
             .sign_net_message(ConsensusNetMessage::Prepare(
                 ConsensusProposal {
                     slot: 1,
+                    epoch: 0,
                     timestamp: TimestampMs(123),
                     cut: vec![(
                         LaneId(node2.pubkey()),
@@ -1409,6 +1769,9 @@ pub mod test {
                     )],
                     staking_actions: vec![],
                     parent_hash: ConsensusProposalHash("hash".into()),
+                    tx_root: EMPTY_MERKLE_ROOT,
+                    parent_commit_qc: AggregateSignature::default(),
+                    parent_contract_registry_root: EMPTY_MERKLE_ROOT,
                 },
                 Ticket::Genesis,
                 0,
@@ -1449,6 +1812,7 @@ pub mod test {
             .sign_net_message(ConsensusNetMessage::Prepare(
                 ConsensusProposal {
                     slot: 1,
+                    epoch: 0,
                     timestamp: TimestampMs(123),
                     cut: vec![(
                         LaneId(node2.pubkey()),
@@ -1458,6 +1822,9 @@ pub mod test {
                     )],
                     staking_actions: vec![],
                     parent_hash: ConsensusProposalHash("hash".into()),
+                    tx_root: EMPTY_MERKLE_ROOT,
+                    parent_commit_qc: AggregateSignature::default(),
+                    parent_contract_registry_root: EMPTY_MERKLE_ROOT,
                 },
                 Ticket::Genesis,
                 0,
@@ -1485,6 +1852,75 @@ pub mod test {
         );
     }
 
+    #[test_log::test(tokio::test)]
+    async fn prepare_wrong_tx_root() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        node1.start_round().await;
+
+        broadcast! {
+            description: "Prepare",
+            from: node1, to: [],
+            message_matches: ConsensusNetMessage::Prepare(cp, ticket, view) => {
+                // The cut doesn't move, so the block holds no transaction
+                let cp = ConsensusProposal {
+                    tx_root: [1; 32],
+                    ..cp.clone()
+                };
+                let prepare_msg = node1
+                    .consensus
+                    .sign_net_message(ConsensusNetMessage::Prepare(cp, ticket.clone(), *view))
+                    .unwrap();
+
+                for node in [&mut node2, &mut node3, &mut node4] {
+                    assert_contains!(
+                        node.handle_msg_err(&prepare_msg).await.to_string(),
+                        "Transaction root"
+                    );
+                }
+            }
+        };
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn prepare_wrong_contract_registry_root() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        node1.start_round().await;
+
+        broadcast! {
+            description: "Prepare",
+            from: node1, to: [],
+            message_matches: ConsensusNetMessage::Prepare(cp, ticket, view) => {
+                let cp = ConsensusProposal {
+                    parent_contract_registry_root: [1; 32],
+                    ..cp.clone()
+                };
+                let prepare_msg = node1
+                    .consensus
+                    .sign_net_message(ConsensusNetMessage::Prepare(cp, ticket.clone(), *view))
+                    .unwrap();
+
+                for node in [&mut node2, &mut node3, &mut node4] {
+                    assert_contains!(
+                        node.handle_msg_err(&prepare_msg).await.to_string(),
+                        "Contract registry root"
+                    );
+                }
+            }
+        };
+    }
+
     #[test_log::test(tokio::test)]
     async fn prepare_timestamp_too_old() {
         let (mut node1, mut node2, mut node3, mut node4): (
@@ -1696,6 +2132,395 @@ pub mod test {
         node4.handle_msg_err(&prepare_msg).await;
     }
 
+    #[test_log::test(tokio::test)]
+    async fn prepare_equivocation_slashes_leader() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        // Slot 1 - leader = node1
+        node1.start_round().await;
+        let (cp1, ticket1, view1) = simple_commit_round! {
+            leader: node1,
+            followers: [node2, node3, node4]
+        };
+
+        // node1 then signs another proposal for the same slot and view
+        let conflicting_cp = ConsensusProposal {
+            timestamp: TimestampMs(cp1.timestamp.0 + 1),
+            ..cp1.clone()
+        };
+        let conflicting_prepare = node1
+            .consensus
+            .sign_net_message(ConsensusNetMessage::Prepare(conflicting_cp, ticket1, view1))
+            .expect("Error while signing");
+        // The Prepare itself is outdated, but node2 still reports it
+        let _ = node2
+            .consensus
+            .handle_net_message(conflicting_prepare.clone())
+            .await;
+
+        broadcast! {
+            description: "Follower - Equivocation",
+            from: node2, to: [node1, node3, node4],
+            message_matches: ConsensusNetMessage::Equivocation(evidence) => {
+                assert_eq!(evidence.offender(), &node1.pubkey());
+            }
+        };
+        assert_eq!(node3.consensus.evidence_to_slash().len(), 1);
+
+        // Replayed evidence is not gossiped again
+        let _ = node2
+            .consensus
+            .handle_net_message(conflicting_prepare)
+            .await;
+        node2.assert_no_broadcast("Evidence already known");
+
+        // Slot 2 - leader = node2, which slashes node1
+        node2.start_round().await;
+        let (cp2, _, _) = simple_commit_round! {
+            leader: node2,
+            followers: [node1, node3, node4]
+        };
+        assert!(cp2.staking_actions.iter().any(|action| matches!(
+            action,
+            ConsensusStakingAction::Slash { evidence } if evidence.offender() == &node1.pubkey()
+        )));
+        assert!(!node3.staking().is_bonded(&node1.pubkey()));
+        assert!(node3.consensus.evidence_to_slash().is_empty());
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn vote_equivocation_is_reported() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        // Slot 1 - leader = node1, which receives the votes
+        node1.start_round().await;
+        let (cp1, _, view1) = simple_commit_round! {
+            leader: node1,
+            followers: [node2, node3, node4]
+        };
+        let conflicting_hash = ConsensusProposal {
+            timestamp: TimestampMs(cp1.timestamp.0 + 1),
+            ..cp1.clone()
+        }
+        .hashed();
+
+        // node4 then votes for another proposal in the same slot and view
+        let conflicting_vote = node4
+            .consensus
+            .sign_net_message(ConsensusNetMessage::PrepareVote(
+                node4
+                    .consensus
+                    .crypto
+                    .sign((conflicting_hash.clone(), PrepareVoteMarker))
+                    .expect("Error while signing"),
+                cp1.slot,
+                view1,
+            ))
+            .expect("Error while signing");
+        let _ = node1.consensus.handle_net_message(conflicting_vote).await;
+
+        broadcast! {
+            description: "Leader - Equivocation",
+            from: node1, to: [node2, node3, node4],
+            message_matches: ConsensusNetMessage::Equivocation(evidence) => {
+                assert!(matches!(evidence.as_ref(), EquivocationEvidence::Vote(..)));
+                assert_eq!(evidence.offender(), &node4.pubkey());
+            }
+        };
+
+        // And node3 acknowledges another proposal
+        let conflicting_ack = node3
+            .consensus
+            .sign_net_message(ConsensusNetMessage::ConfirmAck(
+                node3
+                    .consensus
+                    .crypto
+                    .sign((conflicting_hash.clone(), ConfirmAckMarker))
+                    .expect("Error while signing"),
+                cp1.slot,
+                view1,
+            ))
+            .expect("Error while signing");
+        let _ = node1.consensus.handle_net_message(conflicting_ack).await;
+
+        broadcast! {
+            description: "Leader - Equivocation",
+            from: node1, to: [node2, node3, node4],
+            message_matches: ConsensusNetMessage::Equivocation(evidence) => {
+                assert!(matches!(evidence.as_ref(), EquivocationEvidence::Vote(..)));
+                assert_eq!(evidence.offender(), &node3.pubkey());
+            }
+        };
+
+        // Votes far from the current slot are not remembered
+        let far_vote = node2
+            .consensus
+            .sign_net_message(ConsensusNetMessage::PrepareVote(
+                node2
+                    .consensus
+                    .crypto
+                    .sign((conflicting_hash, PrepareVoteMarker))
+                    .expect("Error while signing"),
+                Slot::MAX,
+                view1,
+            ))
+            .expect("Error while signing");
+        let _ = node1.consensus.handle_net_message(far_vote).await;
+        assert!(!node1
+            .consensus
+            .store
+            .seen_votes
+            .keys()
+            .any(|(_, _, slot, _)| *slot == Slot::MAX));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn unbonding_validator_leaves_consensus() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        // The staker of node4 asks it to leave consensus
+        let leaving = node4.pubkey();
+        let staker: Identity = hex::encode(&leaving.0).into();
+        let staking_block = |height, action| Block {
+            block_height: BlockHeight(height),
+            staking_actions: vec![(staker.clone(), action)],
+            ..Default::default()
+        };
+        for node in [&mut node1, &mut node2, &mut node3, &mut node4] {
+            node.handle_node_state_event(NodeStateEvent::NewBlock(Box::new(staking_block(
+                1,
+                StakingAction::Unbond {
+                    validator: leaving.clone(),
+                },
+            ))))
+            .await
+            .expect("Unbond request");
+        }
+
+        let validators = light_client::validator_set(&node2.staking()).unwrap();
+
+        // Slot 1 - leader = node1, which removes node4
+        node1.start_round().await;
+        let (cp, _, _) = simple_commit_round! {
+            leader: node1,
+            followers: [node2, node3, node4]
+        };
+        assert!(cp
+            .staking_actions
+            .contains(&ConsensusStakingAction::Unbond {
+                validator: leaving.clone()
+            }));
+        // The proposal lists the stakes of the next epoch, without node4
+        assert!(cp.staking_actions.iter().any(|action| matches!(
+            action,
+            ConsensusStakingAction::EpochStakes { stakes }
+                if stakes.len() == 3 && stakes.iter().all(|(v, _)| v != &leaving)
+        )));
+        for node in [&node1, &node2, &node3] {
+            assert!(!node.staking().is_bonded(&leaving));
+            assert_eq!(node.staking().bonded().len(), 3);
+        }
+
+        // Light clients can follow the removal from the previous validator set
+        let transitions = node2.consensus.validator_set_transitions(0);
+        let [transition] = transitions.as_slice() else {
+            panic!("Expected one transition, got {}", transitions.len());
+        };
+        assert_eq!(
+            validators.apply(transition).expect("Valid transition"),
+            light_client::validator_set(&node2.staking()).unwrap()
+        );
+        assert!(node2.consensus.validator_set_transitions(2).is_empty());
+
+        // The stake is only released once the unbonding delay is over
+        let withdraw = || StakingAction::Withdraw { amount: 100 };
+        assert!(node2
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(staking_block(
+                UNBONDING_DELAY,
+                withdraw()
+            ))))
+            .await
+            .is_err());
+        node2
+            .handle_node_state_event(NodeStateEvent::NewBlock(Box::new(staking_block(
+                1 + UNBONDING_DELAY,
+                withdraw(),
+            ))))
+            .await
+            .expect("Withdraw");
+        assert_eq!(node2.staking().get_stake(&leaving), None);
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn unbonding_waits_for_the_end_of_the_epoch() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        let leaving = node4.pubkey();
+        let staker: Identity = hex::encode(&leaving.0).into();
+        for node in [&mut node1, &mut node2, &mut node3, &mut node4] {
+            // Epoch 0 is made of slots 0 to 2
+            node.consensus.bft_round_state.epoch_length = EpochLength(3);
+
+            node.handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                block_height: BlockHeight(1),
+                staking_actions: vec![(
+                    staker.clone(),
+                    StakingAction::Unbond {
+                        validator: leaving.clone(),
+                    },
+                )],
+                ..Default::default()
+            })))
+            .await
+            .expect("Unbond request");
+        }
+
+        // Slot 1 - leader = node1, node4 stays until the end of the epoch
+        node1.start_round().await;
+        let (cp, _, _) = simple_commit_round! {
+            leader: node1,
+            followers: [node2, node3, node4]
+        };
+        assert_eq!(cp.epoch, 0);
+        assert!(cp.staking_actions.is_empty());
+        assert!(node2.staking().is_bonded(&leaving));
+
+        // Slot 2 - leader = node2, last slot of the epoch removes node4
+        node2.start_round().await;
+        let (cp, _, _) = simple_commit_round! {
+            leader: node2,
+            followers: [node1, node3, node4]
+        };
+        assert_eq!(cp.epoch, 0);
+        assert!(cp
+            .staking_actions
+            .contains(&ConsensusStakingAction::Unbond {
+                validator: leaving.clone()
+            }));
+        for node in [&node1, &node2, &node3] {
+            let staking = node.staking();
+            assert!(!staking.is_bonded(&leaving));
+            assert_eq!(staking.epoch(), 1);
+            assert_eq!(staking.compute_voting_power(&[leaving.clone()]), 0);
+        }
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn stakes_change_at_the_end_of_the_epoch() {
+        let (mut node1, mut node2, mut node3, mut node4): (
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+            ConsensusTestCtx,
+        ) = build_nodes!(4).await;
+
+        let candidate = ConsensusTestCtx::new_node("node-5").await;
+        let candidacy = candidate
+            .consensus
+            .crypto
+            .sign(ValidatorCandidacy {
+                peer_address: "node-5".into(),
+            })
+            .expect("Candidacy");
+        let whale = node1.pubkey();
+        let validators = light_client::validator_set(&node2.staking()).unwrap();
+
+        for node in [&mut node1, &mut node2, &mut node3, &mut node4] {
+            // Epoch 0 is made of slots 0 to 2
+            node.consensus.bft_round_state.epoch_length = EpochLength(3);
+
+            // Mid-epoch, node1 gets more stake and node-5 asks to join
+            node.handle_node_state_event(NodeStateEvent::NewBlock(Box::new(Block {
+                block_height: BlockHeight(1),
+                staking_actions: vec![
+                    ("whale".into(), StakingAction::Stake { amount: 200 }),
+                    (
+                        "whale".into(),
+                        StakingAction::Delegate {
+                            validator: whale.clone(),
+                        },
+                    ),
+                ],
+                ..Default::default()
+            })))
+            .await
+            .expect("Delegation");
+            node.add_staker(&candidate, 100, "Add staker").await;
+            node.consensus
+                .on_validator_candidacy(candidacy.clone())
+                .expect("Candidacy");
+        }
+
+        // Slot 1 - leader = node1, votes keep the stakes of the epoch start
+        node1.start_round().await;
+        let (cp, _, _) = simple_commit_round! {
+            leader: node1,
+            followers: [node2, node3, node4]
+        };
+        assert_eq!(cp.epoch, 0);
+        assert!(cp.staking_actions.is_empty());
+        for node in [&node1, &node2, &node3, &node4] {
+            let staking = node.staking();
+            assert_eq!(staking.compute_voting_power(&[whale.clone()]), 100);
+            assert!(!staking.is_bonded(&candidate.pubkey()));
+        }
+
+        // Slot 2 - leader = node2, the last slot of the epoch bonds node-5 and lists the new stakes
+        node2.start_round().await;
+        let (cp, _, _) = simple_commit_round! {
+            leader: node2,
+            followers: [node1, node3, node4]
+        };
+        assert!(cp.staking_actions.iter().any(|action| matches!(
+            action,
+            ConsensusStakingAction::Bond { candidate: c }
+                if c.signature.validator == candidate.pubkey()
+        )));
+        let Some(ConsensusStakingAction::EpochStakes { stakes }) = cp.staking_actions.last() else {
+            panic!("Expected the stakes of the next epoch");
+        };
+        assert_eq!(stakes.len(), 5);
+        assert!(stakes.contains(&(whale.clone(), 300)));
+        assert!(stakes.contains(&(candidate.pubkey(), 100)));
+        for node in [&node1, &node2, &node3, &node4] {
+            let staking = node.staking();
+            assert_eq!(staking.epoch(), 1);
+            assert_eq!(staking.compute_voting_power(&[whale.clone()]), 300);
+            assert_eq!(staking.compute_f(), 700 / 3);
+            assert!(staking.is_bonded(&candidate.pubkey()));
+        }
+
+        // Light clients follow the new stakes
+        let transitions = node2.consensus.validator_set_transitions(0);
+        let [transition] = transitions.as_slice() else {
+            panic!("Expected one transition, got {}", transitions.len());
+        };
+        assert_eq!(
+            validators.apply(transition).expect("Valid transition"),
+            light_client::validator_set(&node2.staking()).unwrap()
+        );
+    }
+
     #[test_log::test(tokio::test)]
     async fn timeout_only_one_4() {
         let (mut node1, mut node2, mut node3, mut node4): (
@@ -2162,7 +2987,7 @@ pub mod test {
             send! {
                 description: "Prepare Vote",
                 from: [node1], to: node2,
-                message_matches: ConsensusNetMessage::PrepareVote(_)
+                message_matches: ConsensusNetMessage::PrepareVote(..)
             };
             broadcast! {
                 description: "Leader Confirm",
@@ -2171,7 +2996,7 @@ pub mod test {
             send! {
                 description: "Confirm Ack",
                 from: [node1], to: node2,
-                message_matches: ConsensusNetMessage::ConfirmAck(_)
+                message_matches: ConsensusNetMessage::ConfirmAck(..)
             };
             broadcast! {
                 description: "Leader Commit",
@@ -2240,7 +3065,7 @@ pub mod test {
             send! {
                 description: "Prepare Vote",
                 from: [node1], to: node2,
-                message_matches: ConsensusNetMessage::PrepareVote(_)
+                message_matches: ConsensusNetMessage::PrepareVote(..)
             };
             broadcast! {
                 description: "Leader Confirm",
@@ -2249,7 +3074,7 @@ pub mod test {
             send! {
                 description: "Confirm Ack",
                 from: [node1], to: node2,
-                message_matches: ConsensusNetMessage::ConfirmAck(_)
+                message_matches: ConsensusNetMessage::ConfirmAck(..)
             };
             broadcast! {
                 description: "Leader Commit",
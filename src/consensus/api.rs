@@ -1,11 +1,19 @@
 use anyhow::anyhow;
-use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse, Json, Router};
+use axum::{
+    debug_handler,
+    extract::{Query as QueryParams, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json, Router,
+};
 use client_sdk::contract_indexer::AppError;
+use hyle_light_client::ValidatorSetTransition;
 use hyle_model::api::APIStaking;
 use hyle_modules::{bus::SharedMessageBus, modules::signal::ShutdownModule};
+use serde::Deserialize;
 use staking::state::Staking;
 use tracing::error;
-use utoipa::OpenApi;
+use utoipa::{IntoParams, OpenApi};
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
@@ -17,12 +25,13 @@ use crate::{
     model::{ConsensusInfo, SharedRunContext},
 };
 
-use super::{QueryConsensusInfo, QueryConsensusStakingState};
+use super::{QueryConsensusInfo, QueryConsensusStakingState, QueryValidatorSetTransitions};
 
 bus_client! {
 struct RestBusClient {
     sender(Query<QueryConsensusInfo, ConsensusInfo>),
     sender(Query<QueryConsensusStakingState, Staking>),
+    sender(Query<QueryValidatorSetTransitions, Vec<ValidatorSetTransition>>),
     receiver(ShutdownModule),
 }
 }
@@ -42,6 +51,7 @@ pub async fn api(bus: &SharedMessageBus, ctx: &SharedRunContext) -> Router<()> {
     let (router, api) = OpenApiRouter::with_openapi(ConsensusAPI::openapi())
         .routes(routes!(get_consensus_state))
         .routes(routes!(get_consensus_staking_state))
+        .routes(routes!(get_validator_set_transitions))
         .split_for_parts();
 
     if let Ok(mut o) = ctx.api.openapi.lock() {
@@ -112,6 +122,47 @@ pub async fn get_consensus_staking_state(
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ValidatorSetTransitionsParams {
+    /// Only return transitions committed from this slot on
+    from_slot: Option<u64>,
+}
+
+/// Committed proposals that changed the validator set or ended an epoch, with their commit
+/// certificates, for light clients to follow the validator set from a trusted one.
+#[utoipa::path(
+    get,
+    path = "/validator_set_transitions",
+    params(ValidatorSetTransitionsParams),
+    tag = "Consensus",
+    responses(
+        (status = OK, body = [ValidatorSetTransition])
+    )
+)]
+#[debug_handler]
+pub async fn get_validator_set_transitions(
+    QueryParams(params): QueryParams<ValidatorSetTransitionsParams>,
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    match state
+        .bus
+        .shutdown_aware_request::<()>(QueryValidatorSetTransitions {
+            from_slot: params.from_slot.unwrap_or_default(),
+        })
+        .await
+    {
+        Ok(transitions) => Ok(Json(transitions)),
+        Err(err) => {
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Error while getting validator set transitions: {err}"),
+            ))
+        }
+    }
+}
+
 impl Clone for RouterState {
     fn clone(&self) -> Self {
         use hyle_modules::utils::static_type_map::Pick;
@@ -126,6 +177,10 @@ impl Clone for RouterState {
                     &self.bus,
                 )
                 .clone(),
+                Pick::<tokio::sync::broadcast::Sender<Query<QueryValidatorSetTransitions, Vec<ValidatorSetTransition>>>>::get(
+                    &self.bus,
+                )
+                .clone(),
                 Pick::<tokio::sync::broadcast::Receiver<ShutdownModule>>::get(&self.bus).resubscribe()
             )
         }
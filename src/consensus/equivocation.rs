@@ -0,0 +1,167 @@
+//! Detection of leaders preparing conflicting proposals, and handling of equivocation evidence.
+
+use anyhow::{bail, Result};
+use hyle_crypto::BlstCrypto;
+use tracing::{debug, warn};
+
+use super::*;
+
+/// Signed messages are remembered for this many slots around the current one
+const SEEN_SLOTS: Slot = 10;
+
+/// Checks that the evidence messages conflict and are properly signed.
+pub fn verify_evidence(evidence: &EquivocationEvidence) -> Result<()> {
+    evidence.check_conflict()?;
+    for header in evidence.headers() {
+        if !BlstCrypto::verify(header)? {
+            bail!("Invalid signature in equivocation evidence");
+        }
+    }
+    Ok(())
+}
+
+impl Consensus {
+    /// Only messages from bonded validators, close to the current slot, are worth remembering.
+    fn should_record(&self, sender: &ValidatorPublicKey, slot: Slot) -> bool {
+        let current_slot = self.bft_round_state.slot;
+        self.bft_round_state.staking.is_bonded(sender)
+            && slot.saturating_add(SEEN_SLOTS) >= current_slot
+            && slot <= current_slot.saturating_add(SEEN_SLOTS)
+    }
+
+    /// Remembers the Prepare, and reports its sender if it already prepared another proposal for this slot and view.
+    pub(super) fn record_prepare(
+        &mut self,
+        header: &SignedByValidator<MsgHeader>,
+        consensus_proposal: &ConsensusProposal,
+        ticket: &Ticket,
+        view: View,
+    ) -> Result<()> {
+        let slot = consensus_proposal.slot;
+        if !self.should_record(&header.signature.validator, slot) {
+            return Ok(());
+        }
+        let current_slot = self.bft_round_state.slot;
+        self.store
+            .seen_prepares
+            .retain(|(_, seen_slot, _), _| seen_slot.saturating_add(SEEN_SLOTS) >= current_slot);
+
+        let prepare = SignedPrepare {
+            header: header.clone(),
+            proposal: consensus_proposal.clone(),
+            ticket: borsh::to_vec(ticket)?,
+            view,
+        };
+        let key = (header.signature.validator.clone(), slot, view);
+        match self.store.seen_prepares.get(&key) {
+            None => {
+                self.store.seen_prepares.insert(key, prepare);
+                Ok(())
+            }
+            Some(seen) if seen.proposal.hashed() == prepare.proposal.hashed() => Ok(()),
+            Some(seen) => {
+                warn!(
+                    "🚨 {} prepared two different proposals for slot {} view {}",
+                    key.0, slot, view
+                );
+                let evidence = EquivocationEvidence::Prepare(seen.clone(), prepare);
+                self.on_equivocation(evidence, true)
+            }
+        }
+    }
+
+    /// Remembers the vote, and reports its sender if it already voted for another proposal in this slot and view.
+    pub(super) fn record_vote(
+        &mut self,
+        header: &SignedByValidator<MsgHeader>,
+        kind: VoteKind,
+        proposal_hash: &ConsensusProposalHash,
+        slot: Slot,
+        view: View,
+    ) -> Result<()> {
+        if !self.should_record(&header.signature.validator, slot) {
+            return Ok(());
+        }
+        let current_slot = self.bft_round_state.slot;
+        self.store
+            .seen_votes
+            .retain(|(_, _, seen_slot, _), _| seen_slot.saturating_add(SEEN_SLOTS) >= current_slot);
+
+        let vote = SignedVote {
+            header: header.clone(),
+            kind,
+            proposal_hash: proposal_hash.clone(),
+            slot,
+            view,
+        };
+        let key = (
+            header.signature.validator.clone(),
+            vote.kind.clone(),
+            slot,
+            view,
+        );
+        match self.store.seen_votes.get(&key) {
+            None => {
+                self.store.seen_votes.insert(key, vote);
+                Ok(())
+            }
+            Some(seen) if seen.proposal_hash == vote.proposal_hash => Ok(()),
+            Some(seen) => {
+                warn!(
+                    "🚨 {} sent {:?} for two different proposals for slot {} view {}",
+                    key.0, key.1, slot, view
+                );
+                let evidence = EquivocationEvidence::Vote(seen.clone(), vote);
+                self.on_equivocation(evidence, true)
+            }
+        }
+    }
+
+    /// Keeps verified evidence against a bonded validator until a proposal slashes it.
+    /// Evidence found locally is gossiped to the other validators.
+    pub(super) fn on_equivocation(
+        &mut self,
+        evidence: EquivocationEvidence,
+        gossip: bool,
+    ) -> Result<()> {
+        let offender = evidence.offender().clone();
+        if self.store.pending_evidence.contains_key(&offender) {
+            debug!("Already holding equivocation evidence against {}", offender);
+            return Ok(());
+        }
+        if !self.bft_round_state.staking.is_bonded(&offender) {
+            debug!(
+                "Ignoring equivocation evidence against {}, which is not bonded",
+                offender
+            );
+            return Ok(());
+        }
+        verify_evidence(&evidence)?;
+
+        warn!("🚨 Equivocation evidence against validator {}", offender);
+        if gossip {
+            self.broadcast_net_message(ConsensusNetMessage::Equivocation(Box::new(
+                evidence.clone(),
+            )))?;
+        }
+        self.store.pending_evidence.insert(offender, evidence);
+        Ok(())
+    }
+
+    /// Evidence to include in the next proposal, against validators that are still bonded
+    pub(super) fn evidence_to_slash(&self) -> Vec<EquivocationEvidence> {
+        self.store
+            .pending_evidence
+            .values()
+            .filter(|evidence| self.bft_round_state.staking.is_bonded(evidence.offender()))
+            .cloned()
+            .collect()
+    }
+
+    pub(super) fn verify_slash(&self, evidence: &EquivocationEvidence) -> Result<()> {
+        if !self.bft_round_state.staking.is_bonded(evidence.offender()) {
+            bail!("Cannot slash {}, which is not bonded", evidence.offender());
+        }
+        verify_evidence(evidence)
+    }
+}
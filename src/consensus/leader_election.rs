@@ -0,0 +1,265 @@
+//! Selection of the leader of each slot and view.
+//!
+//! Every validator must elect the same leader from the same inputs, so strategies
+//! only depend on the (trusted) staking state of the epoch, a seed, the slot and the view.
+//! The seed comes from the parent proposal, which every node holds once it is committed:
+//! the commit QC it carries for its own parent, or that parent's hash when it carries none.
+//! Unlike the hash of the parent proposal, the leader can't grind it through the contents
+//! of its proposal, such as its timestamp. It is not unbiased either: the leader aggregating
+//! a commit QC picks which quorum of votes goes into it, and so can grind its signature to
+//! sway the election two slots later. A leader proposing after a timeout can also carry a
+//! commit QC or none.
+
+use anyhow::{bail, Context, Result};
+use sha3::{Digest, Sha3_256};
+use staking::state::Staking;
+
+use crate::{model::*, utils::conf::LeaderElectionStrategy};
+
+/// Seed of the election of the leaders following a proposal,
+/// from its parent hash and the commit QC it carries.
+pub fn election_seed(
+    parent_hash: &ConsensusProposalHash,
+    parent_commit_qc: &AggregateSignature,
+) -> Vec<u8> {
+    if parent_commit_qc.signature.0.is_empty() {
+        parent_hash.0.as_bytes().to_vec()
+    } else {
+        parent_commit_qc.signature.0.clone()
+    }
+}
+
+pub trait LeaderElection {
+    fn elect(
+        &self,
+        staking: &Staking,
+        seed: &[u8],
+        slot: Slot,
+        view: View,
+    ) -> Result<ValidatorPublicKey>;
+}
+
+impl LeaderElection for LeaderElectionStrategy {
+    fn elect(
+        &self,
+        staking: &Staking,
+        seed: &[u8],
+        slot: Slot,
+        view: View,
+    ) -> Result<ValidatorPublicKey> {
+        match self {
+            LeaderElectionStrategy::RoundRobin => RoundRobin.elect(staking, seed, slot, view),
+            LeaderElectionStrategy::StakeWeighted => StakeWeighted.elect(staking, seed, slot, view),
+        }
+    }
+}
+
+/// Bonded validators take turns on slot + view.
+pub struct RoundRobin;
+
+impl LeaderElection for RoundRobin {
+    fn elect(
+        &self,
+        staking: &Staking,
+        _seed: &[u8],
+        slot: Slot,
+        view: View,
+    ) -> Result<ValidatorPublicKey> {
+        let bonded = staking.bonded();
+        if bonded.is_empty() {
+            bail!("No bonded validator to elect a leader from");
+        }
+        // (we remove 1 for backwards compatibility of the tests when making the change)
+        let index = (slot as usize + view as usize).wrapping_sub(1) % bonded.len();
+        bonded.get(index).cloned().context("No next leader found")
+    }
+}
+
+/// Validators of the epoch are drawn with a probability proportional to their stake as of the start of the epoch.
+/// The draw is seeded by the parent proposal, see [election_seed], so the leader of a slot is
+/// only known once its parent is committed.
+pub struct StakeWeighted;
+
+impl StakeWeighted {
+    fn seed(seed: &[u8], slot: Slot, view: View) -> u128 {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"leader_election");
+        hasher.update(seed);
+        hasher.update(slot.to_le_bytes());
+        hasher.update(view.to_le_bytes());
+        let digest = hasher.finalize();
+        let mut seed = [0u8; 16];
+        seed.copy_from_slice(&digest[..16]);
+        u128::from_le_bytes(seed)
+    }
+}
+
+impl LeaderElection for StakeWeighted {
+    fn elect(
+        &self,
+        staking: &Staking,
+        seed: &[u8],
+        slot: Slot,
+        view: View,
+    ) -> Result<ValidatorPublicKey> {
+        // Validators of the epoch are sorted, so every node walks them in the same order
+        let stakes: Vec<(ValidatorPublicKey, u128)> = staking
+            .epoch_validators()
+            .into_iter()
+            .map(|validator| {
+                let stake = staking.epoch_stake(&validator).unwrap_or(0);
+                (validator, stake)
+            })
+            .filter(|(_, stake)| *stake > 0)
+            .collect();
+        let total = stakes
+            .iter()
+            .try_fold(0u128, |total, (_, stake)| total.checked_add(*stake))
+            .context("Total stake overflows")?;
+        if total == 0 {
+            bail!("No staked validator to elect a leader from");
+        }
+
+        let mut draw = Self::seed(seed, slot, view) % total;
+        for (validator, stake) in stakes {
+            if draw < stake {
+                return Ok(validator);
+            }
+            draw -= stake;
+        }
+        bail!("No next leader found")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyle_crypto::BlstCrypto;
+
+    use super::*;
+
+    fn staking(stakes: &[u128]) -> Staking {
+        let mut staking = Staking::new();
+        for (i, stake) in stakes.iter().enumerate() {
+            let pubkey = BlstCrypto::new(&format!("node-{i}"))
+                .unwrap()
+                .validator_pubkey()
+                .clone();
+            let staker: Identity = format!("staker-{i}").into();
+            staking.stake(staker.clone(), *stake).unwrap();
+            staking.delegate_to(staker, pubkey.clone()).unwrap();
+            staking.bond(pubkey).unwrap();
+        }
+        staking
+    }
+
+    fn leader_count(staking: &Staking, strategy: LeaderElectionStrategy) -> Vec<usize> {
+        let mut count = vec![0; staking.bonded().len()];
+        for slot in 1..3_001 {
+            let seed = format!("qc-{slot}");
+            let leader = strategy.elect(staking, seed.as_bytes(), slot, 0).unwrap();
+            let index = staking.bonded().iter().position(|v| v == &leader).unwrap();
+            count[index] += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let staking = staking(&[100, 100, 800]);
+        let elect = |slot, view| {
+            LeaderElectionStrategy::RoundRobin
+                .elect(&staking, b"genesis", slot, view)
+                .unwrap()
+        };
+        assert_eq!(elect(1, 0), staking.bonded()[0]);
+        assert_eq!(elect(2, 0), staking.bonded()[1]);
+        assert_eq!(elect(1, 2), staking.bonded()[2]);
+        assert_eq!(elect(4, 0), staking.bonded()[0]);
+        assert_eq!(
+            leader_count(&staking, LeaderElectionStrategy::RoundRobin),
+            vec![1_000; 3]
+        );
+    }
+
+    #[test]
+    fn test_stake_weighted_is_deterministic() {
+        let staking = staking(&[100, 100, 800]);
+        let strategy = LeaderElectionStrategy::StakeWeighted;
+        let leader = strategy.elect(&staking, b"qc", 5, 0).unwrap();
+        for _ in 0..10 {
+            assert_eq!(strategy.elect(&staking, b"qc", 5, 0).unwrap(), leader);
+        }
+        // Another seed, slot or view draws again
+        assert!((0..20).any(|view| strategy.elect(&staking, b"other", 5, view).unwrap() != leader));
+    }
+
+    #[test]
+    fn test_stake_weighted_follows_stake() {
+        let staking = staking(&[100, 100, 800]);
+        let heavy = BlstCrypto::new("node-2")
+            .unwrap()
+            .validator_pubkey()
+            .clone();
+        let heavy_index = staking.bonded().iter().position(|v| v == &heavy).unwrap();
+
+        let count = leader_count(&staking, LeaderElectionStrategy::StakeWeighted);
+        assert_eq!(count.iter().sum::<usize>(), 3_000);
+        // 80% of the stake, with a generous margin
+        assert!(count[heavy_index] > 2_100, "{count:?}");
+        assert!(count[heavy_index] < 2_700, "{count:?}");
+        for (index, c) in count.iter().enumerate() {
+            if index != heavy_index {
+                assert!(*c > 150, "{count:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stake_weighted_uses_epoch_stakes() {
+        let mut staking = staking(&[100, 100, 800]);
+        staking.start_epoch(1, staking.bonded_stakes());
+        let count = leader_count(&staking, LeaderElectionStrategy::StakeWeighted);
+
+        // Stake delegated mid-epoch only counts from the next epoch
+        let light = BlstCrypto::new("node-0")
+            .unwrap()
+            .validator_pubkey()
+            .clone();
+        let staker: Identity = "late-staker".into();
+        staking.stake(staker.clone(), 10_000).unwrap();
+        staking.delegate_to(staker, light.clone()).unwrap();
+        assert_eq!(
+            leader_count(&staking, LeaderElectionStrategy::StakeWeighted),
+            count
+        );
+
+        staking.start_epoch(2, staking.bonded_stakes());
+        let light_index = staking.bonded().iter().position(|v| v == &light).unwrap();
+        let count = leader_count(&staking, LeaderElectionStrategy::StakeWeighted);
+        assert!(count[light_index] > 2_400, "{count:?}");
+    }
+
+    #[test]
+    fn test_election_seed_falls_back_to_parent_hash() {
+        let parent_hash = ConsensusProposalHash("parent".to_string());
+        assert_eq!(
+            election_seed(&parent_hash, &AggregateSignature::default()),
+            b"parent".to_vec()
+        );
+        let commit_qc = AggregateSignature {
+            signature: Signature(vec![1, 2, 3]),
+            validators: vec![],
+        };
+        assert_eq!(election_seed(&parent_hash, &commit_qc), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_no_leader_without_bonded_validators() {
+        for strategy in [
+            LeaderElectionStrategy::RoundRobin,
+            LeaderElectionStrategy::StakeWeighted,
+        ] {
+            assert!(strategy.elect(&Staking::new(), b"genesis", 1, 0).is_err());
+        }
+    }
+}
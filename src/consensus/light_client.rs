@@ -0,0 +1,53 @@
+//! Validator set transitions, served to light clients following the consensus membership.
+
+use anyhow::Result;
+use hyle_light_client::{Validator, ValidatorSet, ValidatorSetTransition};
+use staking::state::Staking;
+
+use crate::model::{ConsensusProposal, Slot};
+
+use super::{CommitQC, Consensus};
+
+/// Validators votes are weighted with, as frozen at the start of the epoch
+pub(super) fn validator_set(staking: &Staking) -> Result<ValidatorSet> {
+    ValidatorSet::new(
+        staking
+            .voting_stakes()
+            .iter()
+            .map(|(pubkey, stake)| Validator {
+                pubkey: pubkey.into(),
+                stake: *stake,
+            })
+            .collect(),
+    )
+}
+
+impl Consensus {
+    /// Records a committed proposal changing the validator set or ending an epoch, once the next
+    /// epoch started.
+    pub(super) fn record_validator_set_transition(
+        &mut self,
+        proposal: &ConsensusProposal,
+        commit_quorum_certificate: &CommitQC,
+    ) -> Result<()> {
+        let next = validator_set(&self.bft_round_state.staking)?;
+        self.store
+            .validator_set_transitions
+            .push(ValidatorSetTransition {
+                proposal: proposal.into(),
+                certificate: (&commit_quorum_certificate.0).into(),
+                next,
+            });
+        Ok(())
+    }
+
+    /// Transitions committed from the given slot on.
+    /// Transitions committed before this node followed consensus are unknown.
+    pub(super) fn validator_set_transitions(&self, from_slot: Slot) -> Vec<ValidatorSetTransition> {
+        self.validator_set_transitions
+            .iter()
+            .filter(|t| t.proposal.slot >= from_slot)
+            .cloned()
+            .collect()
+    }
+}
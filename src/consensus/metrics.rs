@@ -27,6 +27,8 @@ pub struct ConsensusMetrics {
     pub on_sync_request_err: Counter<u64>,
     pub on_sync_reply_ok: Counter<u64>,
     pub on_sync_reply_err: Counter<u64>,
+    pub on_equivocation_ok: Counter<u64>,
+    pub on_equivocation_err: Counter<u64>,
 }
 
 macro_rules! build {
@@ -61,6 +63,8 @@ impl ConsensusMetrics {
             on_sync_request_err: build!(my_meter, counter, "on_sync_request_err"),
             on_sync_reply_ok: build!(my_meter, counter, "on_sync_reply_ok"),
             on_sync_reply_err: build!(my_meter, counter, "on_sync_reply_err"),
+            on_equivocation_ok: build!(my_meter, counter, "on_equivocation_ok"),
+            on_equivocation_err: build!(my_meter, counter, "on_equivocation_err"),
         }
     }
 
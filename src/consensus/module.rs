@@ -4,8 +4,8 @@ use hyle_modules::{bus::SharedMessageBus, log_error, modules::Module};
 use crate::model::SharedRunContext;
 
 use super::{
-    api, consensus_bus_client::ConsensusBusClient, metrics::ConsensusMetrics, Consensus,
-    ConsensusStore,
+    api, consensus_bus_client::ConsensusBusClient, metrics::ConsensusMetrics, wal::ConsensusWal,
+    Consensus, ConsensusStore,
 };
 
 impl Module for Consensus {
@@ -14,6 +14,7 @@ impl Module for Consensus {
     async fn build(bus: SharedMessageBus, ctx: Self::Context) -> Result<Self> {
         let file = ctx.config.data_directory.clone().join("consensus.bin");
         let store: ConsensusStore = Self::load_from_disk_or_default(file.as_path());
+        let wal = ConsensusWal::open(&ctx.config.data_directory.join("consensus_wal.bin"))?;
         let metrics = ConsensusMetrics::global(ctx.config.id.clone());
 
         let api = api::api(&bus, &ctx).await;
@@ -29,6 +30,7 @@ impl Module for Consensus {
             bus,
             file: Some(file),
             store,
+            wal,
             config: ctx.config.clone(),
             crypto: ctx.crypto.clone(),
         })
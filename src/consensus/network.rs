@@ -4,6 +4,7 @@ use sha3::{Digest, Sha3_256};
 use std::{fmt::Display, ops::Deref};
 use strum_macros::IntoStaticStr;
 
+use hyle_crypto::signer::SignerMessage;
 use hyle_model::*;
 
 use crate::p2p::network::{HeaderSignableData, IntoHeaderSignableData};
@@ -67,12 +68,6 @@ pub enum TimeoutKind {
 pub struct PrepareVoteMarker;
 pub type PrepareVote = SignedByValidator<(ConsensusProposalHash, PrepareVoteMarker)>;
 
-impl From<PrepareVote> for ConsensusNetMessage {
-    fn from(vote: PrepareVote) -> Self {
-        ConsensusNetMessage::PrepareVote(vote)
-    }
-}
-
 #[derive(
     Debug,
     Serialize,
@@ -89,12 +84,6 @@ impl From<PrepareVote> for ConsensusNetMessage {
 pub struct ConfirmAckMarker;
 pub type ConfirmAck = SignedByValidator<(ConsensusProposalHash, ConfirmAckMarker)>;
 
-impl From<ConfirmAck> for ConsensusNetMessage {
-    fn from(vote: ConfirmAck) -> Self {
-        ConsensusNetMessage::ConfirmAck(vote)
-    }
-}
-
 #[derive(
     Debug,
     Serialize,
@@ -140,15 +129,17 @@ impl From<ConsensusTimeout> for ConsensusNetMessage {
 )]
 pub enum ConsensusNetMessage {
     Prepare(ConsensusProposal, Ticket, View),
-    PrepareVote(PrepareVote),
+    /// Votes carry the slot and view they are cast in, for their header to sign
+    PrepareVote(PrepareVote, Slot, View),
     Confirm(PrepareQC, ConsensusProposalHash),
-    ConfirmAck(ConfirmAck),
+    ConfirmAck(ConfirmAck, Slot, View),
     Commit(CommitQC, ConsensusProposalHash),
     Timeout(ConsensusTimeout),
     TimeoutCertificate(TimeoutQC, TCKind, Slot, View),
     ValidatorCandidacy(SignedByValidator<ValidatorCandidacy>),
     SyncRequest(ConsensusProposalHash),
     SyncReply((ValidatorPublicKey, ConsensusProposal, Ticket, View)),
+    Equivocation(Box<EquivocationEvidence>),
 }
 
 impl<T> Hashed<QuorumCertificateHash> for QuorumCertificate<T> {
@@ -187,8 +178,12 @@ impl Display for ConsensusNetMessage {
             ConsensusNetMessage::Prepare(cp, ticket, view) => {
                 write!(f, "{enum_variant} CP: {cp}, ticket: {ticket}, view: {view}")
             }
-            ConsensusNetMessage::PrepareVote(cphash) => {
-                write!(f, "{} (CP hash: {})", enum_variant, cphash.msg.0)
+            ConsensusNetMessage::PrepareVote(cphash, slot, view) => {
+                write!(
+                    f,
+                    "{} (CP hash: {}, slot: {}, view: {})",
+                    enum_variant, cphash.msg.0, slot, view
+                )
             }
             ConsensusNetMessage::Confirm(cert, cphash) => {
                 _ = writeln!(f, "{enum_variant} (CP hash: {cphash})");
@@ -198,8 +193,12 @@ impl Display for ConsensusNetMessage {
                 }
                 write!(f, "")
             }
-            ConsensusNetMessage::ConfirmAck(cphash, ..) => {
-                write!(f, "{} (CP hash: {})", enum_variant, cphash.msg.0)
+            ConsensusNetMessage::ConfirmAck(cphash, slot, view) => {
+                write!(
+                    f,
+                    "{} (CP hash: {}, slot: {}, view: {})",
+                    enum_variant, cphash.msg.0, slot, view
+                )
             }
             ConsensusNetMessage::Commit(cert, cphash) => {
                 _ = writeln!(f, "{enum_variant} (CP hash: {cphash})");
@@ -266,6 +265,9 @@ impl Display for ConsensusNetMessage {
                     "{enum_variant} sender: {sender}, CP: {consensus_proposal}, ticket: {ticket}, view: {view}"
                 )
             }
+            ConsensusNetMessage::Equivocation(evidence) => {
+                write!(f, "{enum_variant} (offender: {})", evidence.offender())
+            }
         }
     }
 }
@@ -277,11 +279,15 @@ impl IntoHeaderSignableData for ConsensusNetMessage {
             ConsensusNetMessage::Prepare(cp, t, v) => {
                 borsh::to_vec(&(cp.hashed(), t, v)).unwrap_or_default()
             }
-            ConsensusNetMessage::PrepareVote(pv) => pv.msg.0.clone().0.into_bytes(),
+            ConsensusNetMessage::PrepareVote(pv, slot, view) => {
+                SignedVote::signable_data_of(&VoteKind::PrepareVote, &pv.msg.0, *slot, *view).0
+            }
             ConsensusNetMessage::Confirm(qc, cph) => {
                 borsh::to_vec(&(&qc.signature, cph)).unwrap_or_default()
             }
-            ConsensusNetMessage::ConfirmAck(ca) => ca.msg.0.clone().0.into_bytes(),
+            ConsensusNetMessage::ConfirmAck(ca, slot, view) => {
+                SignedVote::signable_data_of(&VoteKind::ConfirmAck, &ca.msg.0, *slot, *view).0
+            }
             ConsensusNetMessage::Commit(qc, cph) => borsh::to_vec(&(qc, cph)).unwrap_or_default(),
             ConsensusNetMessage::Timeout((SignedByValidator { signature, .. }, tk)) => match tk {
                 TimeoutKind::NilProposal(..) => borsh::to_vec(signature),
@@ -309,6 +315,16 @@ impl IntoHeaderSignableData for ConsensusNetMessage {
                 v,
             ))
             .unwrap_or_default(),
+            ConsensusNetMessage::Equivocation(evidence) => {
+                borsh::to_vec(evidence).unwrap_or_default()
+            }
+        })
+    }
+
+    fn to_signer_message(&self, timestamp: u128) -> anyhow::Result<SignerMessage> {
+        Ok(SignerMessage::ConsensusHeader {
+            timestamp,
+            message: borsh::to_vec(self)?,
         })
     }
 }
@@ -1,12 +1,16 @@
 use anyhow::{bail, Context, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
 use tracing::{debug, info, trace, warn};
 
 use super::*;
 use crate::{
-    bus::BusClientSender,
+    bus::{command_response::CmdRespClient, BusClientSender},
     consensus::StateTag,
+    mempool::QueryTxRoot,
     model::{Hashed, Signed, ValidatorPublicKey},
     p2p::P2PCommand,
     utils::conf::TimestampCheck,
@@ -15,9 +19,13 @@ use hyle_crypto::BlstCrypto;
 use hyle_model::{
     utils::TimestampMs, AggregateSignature, ConsensusProposal, ConsensusProposalHash,
     ConsensusStakingAction, Cut, LaneBytesSize, LaneId, SignedByValidator, ValidatorCandidacy,
-    View,
+    View, EMPTY_MERKLE_ROOT,
 };
 
+/// How often the mempool is asked again for the transaction root of a proposal
+/// while the data proposals of its cut are being fetched
+const TX_ROOT_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
 #[derive(BorshSerialize, BorshDeserialize, Default)]
 pub(super) struct FollowerState {
     pub(super) buffered_quorum_certificate: Option<CommitQC>, // if we receive a commit before the next prepare
@@ -30,7 +38,7 @@ enum TicketVerifyAndProcess {
 }
 
 impl Consensus {
-    pub(super) fn on_prepare(
+    pub(super) async fn on_prepare(
         &mut self,
         sender: ValidatorPublicKey,
         consensus_proposal: ConsensusProposal,
@@ -145,12 +153,32 @@ impl Consensus {
             );
         }
 
+        let epoch = self
+            .bft_round_state
+            .epoch_length
+            .epoch_of(consensus_proposal.slot);
+        if consensus_proposal.epoch != epoch {
+            bail!(
+                "Prepare message for slot {} is for epoch {}, expected {}",
+                consensus_proposal.slot,
+                consensus_proposal.epoch,
+                epoch
+            );
+        }
+
+        self.verify_parent_commit_qc(&consensus_proposal, &ticket)?;
+
         self.verify_poda(&consensus_proposal)?;
 
         self.verify_staking_actions(&consensus_proposal)?;
 
         self.verify_timestamp(&consensus_proposal)?;
 
+        self.verify_tx_root(&consensus_proposal).await?;
+
+        self.verify_contract_registry_root(&consensus_proposal)
+            .await?;
+
         // At this point we are OK with this new consensus proposal, update locally and vote.
         self.bft_round_state.current_proposal = consensus_proposal.clone();
         let cp_hash = self.bft_round_state.current_proposal.hashed();
@@ -175,7 +203,7 @@ impl Consensus {
             // FIXME? In theory, we could have a stackoverflow if we need to catchup a lot of prepares
             // Note: If we want to vote on the passed proposal even if it's too late,
             // we can just remove the "return" here and continue.
-            return self.on_prepare(prepare.0, prepare.1, prepare.2, prepare.3);
+            return Box::pin(self.on_prepare(prepare.0, prepare.1, prepare.2, prepare.3)).await;
         }
 
         // Responds PrepareVote message to leader with validator's vote on this proposal
@@ -188,7 +216,15 @@ impl Consensus {
             );
             self.send_net_message(
                 round_leader,
-                self.crypto.sign((cp_hash, PrepareVoteMarker))?.into(),
+                ConsensusNetMessage::PrepareVote(
+                    self.sign_for_round(
+                        SignedKind::PrepareVote,
+                        cp_hash.clone(),
+                        (cp_hash, PrepareVoteMarker),
+                    )?,
+                    self.bft_round_state.slot,
+                    self.bft_round_state.view,
+                ),
             )?;
         } else {
             info!(
@@ -254,9 +290,15 @@ impl Consensus {
             );
             self.send_net_message(
                 self.round_leader()?,
-                self.crypto
-                    .sign((proposal_hash_hint, ConfirmAckMarker))?
-                    .into(),
+                ConsensusNetMessage::ConfirmAck(
+                    self.sign_for_round(
+                        SignedKind::ConfirmAck,
+                        proposal_hash_hint.clone(),
+                        (proposal_hash_hint, ConfirmAckMarker),
+                    )?,
+                    self.bft_round_state.slot,
+                    self.bft_round_state.view,
+                ),
             )?;
         } else {
             info!("😥 Not part of consensus, not sending ConfirmAck");
@@ -397,6 +439,92 @@ impl Consensus {
         Ok(())
     }
 
+    /// The commit QC carried by the proposal seeds the election of the next leader:
+    /// it must be the one of the commit ticket, or a valid one when proposed after a timeout.
+    fn verify_parent_commit_qc(
+        &self,
+        consensus_proposal: &ConsensusProposal,
+        ticket: &Ticket,
+    ) -> Result<()> {
+        let carried = &consensus_proposal.parent_commit_qc;
+        match ticket {
+            Ticket::CommitQC(commit_qc) => {
+                if carried != &commit_qc.0 {
+                    bail!(
+                        "Proposal for slot {} does not carry the commit QC of its ticket. I won't vote for it.",
+                        consensus_proposal.slot
+                    );
+                }
+            }
+            // Reproposed, the prepare QC of the timeout certificate shows a quorum checked it already
+            Ticket::TimeoutQC(_, TCKind::PrepareQC(_)) => {}
+            _ if carried == &AggregateSignature::default() => {}
+            _ => self
+                .verify_quorum_certificate(
+                    (consensus_proposal.parent_hash.clone(), ConfirmAckMarker),
+                    &QuorumCertificate(carried.clone(), ConfirmAckMarker),
+                )
+                .context("Verifying the commit QC carried by the proposal")?,
+        }
+        Ok(())
+    }
+
+    /// Recomputes the transaction root of the proposal from the data proposals of its cut.
+    /// The mempool fetches the ones it lacks, PoDA only guaranteeing that f+1 validators hold them,
+    /// and we wait for them up to the slot duration. A cut that doesn't move holds no transaction.
+    async fn verify_tx_root(&mut self, consensus_proposal: &ConsensusProposal) -> Result<()> {
+        let parent_cut = self.bft_round_state.parent_cut.clone();
+        let tx_root = if consensus_proposal.cut == parent_cut {
+            EMPTY_MERKLE_ROOT
+        } else {
+            let query = QueryTxRoot {
+                parent_cut,
+                cut: consensus_proposal.cut.clone(),
+            };
+            let bus = &mut self.bus;
+            tokio::time::timeout(self.config.consensus.slot_duration, async move {
+                loop {
+                    let tx_root = bus
+                        .shutdown_aware_request::<Self>(query.clone())
+                        .await
+                        .context("Computing the transaction root of the proposal")?;
+                    if let Some(tx_root) = tx_root {
+                        return Ok::<_, anyhow::Error>(tx_root);
+                    }
+                    debug!("Waiting for the data proposals of the proposal to be fetched");
+                    tokio::time::sleep(TX_ROOT_RETRY_INTERVAL).await;
+                }
+            })
+            .await
+            .context("Timeout while waiting for the data proposals of the proposal")??
+        };
+        if tx_root != consensus_proposal.tx_root {
+            bail!(
+                "Transaction root of the proposal for slot {} does not match its cut. I won't vote for it.",
+                consensus_proposal.slot
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the contract registry root committed by the proposal against our node state
+    /// once it handled the parent block.
+    async fn verify_contract_registry_root(
+        &mut self,
+        consensus_proposal: &ConsensusProposal,
+    ) -> Result<()> {
+        let root = self
+            .parent_contract_registry_root(&consensus_proposal.parent_hash)
+            .await?;
+        if root != consensus_proposal.parent_contract_registry_root {
+            bail!(
+                "Contract registry root of the proposal for slot {} does not match our node state. I won't vote for it.",
+                consensus_proposal.slot
+            );
+        }
+        Ok(())
+    }
+
     fn verify_timestamp(
         &self,
         ConsensusProposal { timestamp, .. }: &ConsensusProposal,
@@ -459,12 +587,20 @@ impl Consensus {
         Ok(())
     }
 
-    fn current_proposal_changes_voting_power(&self) -> bool {
+    pub(super) fn current_proposal_changes_voting_power(&self) -> bool {
         self.bft_round_state
             .current_proposal
             .staking_actions
             .iter()
-            .filter(|sa| matches!(sa, ConsensusStakingAction::Bond { .. }))
+            .filter(|sa| {
+                matches!(
+                    sa,
+                    ConsensusStakingAction::Bond { .. }
+                        | ConsensusStakingAction::Slash { .. }
+                        | ConsensusStakingAction::Unbond { .. }
+                        | ConsensusStakingAction::EpochStakes { .. }
+                )
+            })
             .count()
             > 0
     }
@@ -765,7 +901,21 @@ impl Consensus {
     }
 
     fn verify_staking_actions(&mut self, proposal: &ConsensusProposal) -> Result<()> {
+        let ends_epoch = self.bft_round_state.epoch_length.ends_epoch(proposal.slot);
+        // Validators leaving consensus in this proposal
+        let mut removed = BTreeSet::new();
+        let mut epoch_stakes = None;
         for action in &proposal.staking_actions {
+            if matches!(
+                action,
+                ConsensusStakingAction::Bond { .. } | ConsensusStakingAction::Unbond { .. }
+            ) && !ends_epoch
+            {
+                bail!(
+                    "Validators can only join or leave at the end of an epoch, slot {} does not end one",
+                    proposal.slot
+                );
+            }
             match action {
                 ConsensusStakingAction::Bond { candidate } => {
                     self.verify_new_validators_to_bond(candidate)?;
@@ -774,8 +924,49 @@ impl Consensus {
                     lane_id,
                     cumul_size,
                 } => Self::verify_dadi_fees(&proposal.cut, lane_id, cumul_size)?,
+                ConsensusStakingAction::Slash { evidence } => {
+                    if !removed.insert(evidence.offender()) {
+                        bail!("Validator {} is removed twice", evidence.offender());
+                    }
+                    self.verify_slash(evidence)?;
+                }
+                ConsensusStakingAction::Unbond { validator } => {
+                    if !removed.insert(validator) {
+                        bail!("Validator {} is removed twice", validator);
+                    }
+                    self.verify_unbond(validator)?;
+                }
+                ConsensusStakingAction::EpochStakes { stakes } => {
+                    if epoch_stakes.replace(stakes).is_some() {
+                        bail!("Stakes of the next epoch are listed twice");
+                    }
+                }
+                ConsensusStakingAction::EpochLength { .. } => {
+                    bail!("Only the genesis block can set the epoch length");
+                }
             }
         }
+        // Votes of the next epoch are weighted with these stakes, they must be the ones we expect
+        let expected = self.next_epoch_stakes(proposal.slot, &proposal.staking_actions)?;
+        if epoch_stakes != expected.as_ref() {
+            bail!(
+                "Stakes of the next epoch {:?} don't match the expected {:?}",
+                epoch_stakes,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    /// Verify that the validator is bonded and asked to leave consensus
+    fn verify_unbond(&self, validator: &ValidatorPublicKey) -> Result<()> {
+        let staking = &self.bft_round_state.staking;
+        if !staking.is_bonded(validator) {
+            bail!("Cannot unbond {validator}, which is not bonded");
+        }
+        if !staking.is_unbonding(validator) {
+            bail!("Cannot unbond {validator}, which did not ask to unbond");
+        }
         Ok(())
     }
 
@@ -5,10 +5,13 @@ use std::collections::HashSet;
 use crate::{
     bus::command_response::CmdRespClient,
     consensus::*,
-    mempool::QueryNewCut,
+    mempool::{NewCut, QueryNewCut},
     model::{Hashed, ValidatorPublicKey},
 };
-use hyle_model::{utils::TimestampMs, ConsensusProposal, ConsensusStakingAction};
+use hyle_model::{
+    utils::TimestampMs, AggregateSignature, ConsensusProposal, ConsensusStakingAction,
+    EMPTY_MERKLE_ROOT,
+};
 use staking::state::MIN_STAKE;
 use tokio::sync::broadcast;
 use tracing::{debug, error, trace};
@@ -73,23 +76,24 @@ impl Consensus {
                 self.bft_round_state.staking
             );
 
-            let cut = match tokio::time::timeout(
+            let NewCut { cut, tx_root } = match tokio::time::timeout(
                 self.config.consensus.slot_duration,
                 self.bus.shutdown_aware_request::<Self>(QueryNewCut(
                     self.bft_round_state.staking.clone(),
+                    self.bft_round_state.parent_cut.clone(),
                 )),
             )
             .await
             .context("Timeout while querying Mempool")
             {
-                Ok(Ok(cut)) => {
+                Ok(Ok(new_cut)) => {
                     // If the cut is the same as before (and we didn't time out), then check if we should delay.
                     if may_delay
                         .as_ref()
                         .map(|ts| ts > &current_timestamp)
                         .unwrap_or(true)
                         && !matches!(ticket, Ticket::TimeoutQC(..))
-                        && cut == self.bft_round_state.parent_cut
+                        && new_cut.cut == self.bft_round_state.parent_cut
                     {
                         debug!("⏳ Delaying slot start");
                         self.bft_round_state.leader.pending_ticket = Some(ticket);
@@ -111,7 +115,7 @@ impl Consensus {
                         });
                         return Ok(());
                     }
-                    cut
+                    new_cut
                 }
                 Ok(Err(err)) | Err(err) => {
                     // In case of an error, we reuse the last cut to avoid being considered byzantine
@@ -120,12 +124,37 @@ impl Consensus {
                         "Could not get a new cut from Mempool {:?}. Reusing previous one...",
                         err
                     );
-                    self.bft_round_state.parent_cut.clone()
+                    // The block then holds no transaction
+                    NewCut {
+                        cut: self.bft_round_state.parent_cut.clone(),
+                        tx_root: EMPTY_MERKLE_ROOT,
+                    }
+                }
+            };
+
+            let parent_hash = self.bft_round_state.parent_hash.clone();
+            let parent_contract_registry_root = match self
+                .parent_contract_registry_root(&parent_hash)
+                .await
+            {
+                Ok(root) => root,
+                Err(err) => {
+                    // Followers won't vote without it: keep the ticket and let the slot time out
+                    self.bft_round_state.leader.pending_ticket = Some(ticket);
+                    return Err(err.context("Cannot propose without the contract registry root"));
                 }
             };
 
-            // TODO: keep candidates around?
-            let mut new_validators_to_bond = std::mem::take(&mut self.validator_candidates);
+            // Validators only join and leave at the end of an epoch, candidates wait until then
+            let ends_epoch = self
+                .bft_round_state
+                .epoch_length
+                .ends_epoch(self.bft_round_state.slot);
+            let mut new_validators_to_bond = if ends_epoch {
+                std::mem::take(&mut self.validator_candidates)
+            } else {
+                vec![]
+            };
             new_validators_to_bond.retain(|v| {
                 self.bft_round_state
                     .staking
@@ -152,6 +181,25 @@ impl Consensus {
                 .map(|v| v.into())
                 .collect();
 
+            // Validators that signed conflicting messages are slashed
+            staking_actions.extend(
+                self.evidence_to_slash()
+                    .into_iter()
+                    .map(ConsensusStakingAction::from),
+            );
+
+            // Validators that asked to leave consensus, unless they are slashed anyway
+            if ends_epoch {
+                staking_actions.extend(
+                    self.bft_round_state
+                        .staking
+                        .unbonding_validators()
+                        .into_iter()
+                        .filter(|v| !self.store.pending_evidence.contains_key(v))
+                        .map(|validator| ConsensusStakingAction::Unbond { validator }),
+                );
+            }
+
             for tx in cut.iter() {
                 debug!("📦 Lane {} cumulated size: {}", tx.0, tx.2);
                 staking_actions.push(ConsensusStakingAction::PayFeesForDaDi {
@@ -160,13 +208,31 @@ impl Consensus {
                 });
             }
 
+            // Stakes votes are weighted with during the next epoch
+            if let Some(stakes) =
+                self.next_epoch_stakes(self.bft_round_state.slot, &staking_actions)?
+            {
+                staking_actions.push(ConsensusStakingAction::EpochStakes { stakes });
+            }
+
             // Start Consensus with following cut
             self.bft_round_state.current_proposal = ConsensusProposal {
                 slot: self.bft_round_state.slot,
+                epoch: self
+                    .bft_round_state
+                    .epoch_length
+                    .epoch_of(self.bft_round_state.slot),
                 cut,
                 staking_actions,
                 timestamp: current_timestamp,
-                parent_hash: self.bft_round_state.parent_hash.clone(),
+                parent_hash,
+                tx_root,
+                // Without a commit ticket we have no commit QC all validators could check
+                parent_commit_qc: match &ticket {
+                    Ticket::CommitQC(commit_qc) => commit_qc.0.clone(),
+                    _ => AggregateSignature::default(),
+                },
+                parent_contract_registry_root,
             };
         }
         self.bft_round_state.leader.step = Step::PrepareVote;
@@ -261,15 +327,20 @@ impl Consensus {
         );
 
         if voting_power > 2 * f {
+            let proposal_hash_hint = self.bft_round_state.current_proposal.hashed();
+            self.log_own_vote(SignedKind::PrepareVote, proposal_hash_hint.clone())?;
+
             // Get all received signatures
             let aggregates: &Vec<&PrepareVote> =
                 &self.bft_round_state.leader.prepare_votes.iter().collect();
 
-            let proposal_hash_hint = self.bft_round_state.current_proposal.hashed();
             // Aggregates them into a *Prepare* Quorum Certificate
-            let prepvote_signed_aggregation = self
-                .crypto
-                .sign_aggregate((proposal_hash_hint.clone(), PrepareVoteMarker), aggregates)?;
+            let prepvote_signed_aggregation = self.aggregate_for_round(
+                SignedKind::PrepareVote,
+                proposal_hash_hint.clone(),
+                (proposal_hash_hint.clone(), PrepareVoteMarker),
+                aggregates,
+            )?;
 
             // Process the Confirm message locally, then send it to peers.
             self.bft_round_state.leader.step = Step::ConfirmAck;
@@ -363,16 +434,18 @@ impl Consensus {
         );
 
         if voting_power > 2 * f {
+            let proposal_hash = self.bft_round_state.current_proposal.hashed();
+            self.log_own_vote(SignedKind::ConfirmAck, proposal_hash.clone())?;
+
             // Get all signatures received and change ValidatorPublicKey for ValidatorPubKey
             let aggregates: &Vec<&ConfirmAck> =
                 &self.bft_round_state.leader.confirm_ack.iter().collect();
 
             // Aggregates them into a *Commit* Quorum Certificate
-            let commit_signed_aggregation = self.crypto.sign_aggregate(
-                (
-                    self.bft_round_state.current_proposal.hashed(),
-                    ConfirmAckMarker,
-                ),
+            let commit_signed_aggregation = self.aggregate_for_round(
+                SignedKind::ConfirmAck,
+                proposal_hash.clone(),
+                (proposal_hash, ConfirmAckMarker),
                 aggregates,
             )?;
 
@@ -26,8 +26,8 @@ impl Consensus {
         Ok(())
     }
 
-    pub(super) fn on_sync_reply(&mut self, prepare: Prepare) -> Result<()> {
+    pub(super) async fn on_sync_reply(&mut self, prepare: Prepare) -> Result<()> {
         let (sender, proposal, ticket, view) = prepare;
-        self.on_prepare(sender, proposal, ticket, view)
+        self.on_prepare(sender, proposal, ticket, view).await
     }
 }
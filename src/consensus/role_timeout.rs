@@ -382,17 +382,18 @@ impl Consensus {
                         // TODO: check current proposal matches QC.
                         Result::Ok((
                             QuorumCertificate(
-                                self.crypto
-                                    .sign_aggregate(
-                                        (
-                                            self.bft_round_state.slot,
-                                            self.bft_round_state.view,
-                                            self.bft_round_state.parent_hash.clone(),
-                                            ConsensusTimeoutMarker,
-                                        ),
-                                        signed_messages.as_slice(),
-                                    )?
-                                    .signature,
+                                self.aggregate_for_round(
+                                    SignedKind::Timeout,
+                                    self.bft_round_state.parent_hash.clone(),
+                                    (
+                                        self.bft_round_state.slot,
+                                        self.bft_round_state.view,
+                                        self.bft_round_state.parent_hash.clone(),
+                                        ConsensusTimeoutMarker,
+                                    ),
+                                    signed_messages.as_slice(),
+                                )?
+                                .signature,
                                 ConsensusTimeoutMarker,
                             ),
                             TCKind::PrepareQC((
@@ -417,17 +418,18 @@ impl Consensus {
                             .collect::<Result<_>>()?;
                         Result::Ok((
                             QuorumCertificate(
-                                self.crypto
-                                    .sign_aggregate(
-                                        (
-                                            self.bft_round_state.slot,
-                                            self.bft_round_state.view,
-                                            self.bft_round_state.parent_hash.clone(),
-                                            ConsensusTimeoutMarker,
-                                        ),
-                                        signed_nil_messages.as_slice(),
-                                    )?
-                                    .signature,
+                                self.aggregate_for_round(
+                                    SignedKind::Timeout,
+                                    self.bft_round_state.parent_hash.clone(),
+                                    (
+                                        self.bft_round_state.slot,
+                                        self.bft_round_state.view,
+                                        self.bft_round_state.parent_hash.clone(),
+                                        ConsensusTimeoutMarker,
+                                    ),
+                                    signed_nil_messages.as_slice(),
+                                )?
+                                .signature,
                                 ConsensusTimeoutMarker,
                             ),
                             TCKind::NilProposal,
@@ -465,12 +467,17 @@ impl Consensus {
     }
 
     fn get_timeout_message(&self) -> Result<ConsensusTimeout> {
-        let signed_timeout_metadata = self.crypto.sign((
+        let timeout = (
             self.bft_round_state.slot,
             self.bft_round_state.view,
             self.bft_round_state.parent_hash.clone(),
             ConsensusTimeoutMarker,
-        ))?;
+        );
+        let signed_timeout_metadata = self.sign_for_round(
+            SignedKind::Timeout,
+            self.bft_round_state.parent_hash.clone(),
+            timeout,
+        )?;
         tracing::debug!(
             "Sending timeout message for slot {} and view {}.\nHighest seen {:?}",
             self.bft_round_state.slot,
@@ -489,14 +496,10 @@ impl Consensus {
                         )),
                     )
                 }
+                // The nil proposal signs the same timeout, so its signature is the same
                 _ => (
-                    signed_timeout_metadata,
-                    TimeoutKind::NilProposal(self.crypto.sign((
-                        self.bft_round_state.slot,
-                        self.bft_round_state.view,
-                        self.bft_round_state.parent_hash.clone(),
-                        ConsensusTimeoutMarker,
-                    ))?),
+                    signed_timeout_metadata.clone(),
+                    TimeoutKind::NilProposal(signed_timeout_metadata),
                 ),
             },
         )
@@ -0,0 +1,316 @@
+//! Write-ahead log of the consensus messages this validator signs.
+//!
+//! Prepares, votes and timeouts are appended and synced to disk before they are sent,
+//! so a validator restarting mid-round cannot sign a conflicting message for a slot and
+//! view it already signed for. The consensus store itself is only persisted on shutdown.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use borsh::BorshSerialize;
+pub use hyle_crypto::signer::{SignedKind, SignedRecord};
+use hyle_crypto::{signer::SignerMessage, BlstCrypto};
+use tracing::{info, warn};
+
+use crate::model::{AggregateSignature, ConsensusProposalHash, Hashed, Signed, SignedByValidator};
+
+use super::{network::ConsensusNetMessage, Consensus};
+
+/// Records appended before the log is rewritten with only the last record of each kind
+const COMPACT_AFTER: usize = 1_000;
+
+pub fn signed_record(msg: &ConsensusNetMessage) -> Option<SignedRecord> {
+    let (kind, slot, view, hash) = match msg {
+        ConsensusNetMessage::Prepare(cp, _, view) => {
+            (SignedKind::Prepare, cp.slot, *view, cp.hashed())
+        }
+        ConsensusNetMessage::PrepareVote(vote, slot, view) => {
+            (SignedKind::PrepareVote, *slot, *view, vote.msg.0.clone())
+        }
+        ConsensusNetMessage::ConfirmAck(ack, slot, view) => {
+            (SignedKind::ConfirmAck, *slot, *view, ack.msg.0.clone())
+        }
+        ConsensusNetMessage::Timeout((signed_slot_view, _)) => {
+            let (slot, view, parent_hash, _) = &signed_slot_view.msg;
+            (SignedKind::Timeout, *slot, *view, parent_hash.clone())
+        }
+        _ => return None,
+    };
+    Some(SignedRecord {
+        kind,
+        slot,
+        view,
+        hash,
+    })
+}
+
+/// Last record of each kind, replayed from disk on startup.
+#[derive(Default)]
+pub struct ConsensusWal {
+    /// No file in tests, the log is then only kept in memory
+    file: Option<(PathBuf, File)>,
+    last: BTreeMap<SignedKind, SignedRecord>,
+    appended: usize,
+}
+
+impl ConsensusWal {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut wal = ConsensusWal::default();
+        let mut valid_len = 0;
+        if path.exists() {
+            let mut content = vec![];
+            File::open(path)
+                .context("Opening consensus WAL")?
+                .read_to_end(&mut content)?;
+            let mut rest = content.as_slice();
+            while let Some(record) = Self::read_record(&mut rest) {
+                wal.last.insert(record.kind, record);
+                wal.appended += 1;
+                valid_len = content.len() - rest.len();
+            }
+            if valid_len < content.len() {
+                warn!(
+                    "Truncating {} bytes of incomplete records at the end of the consensus WAL",
+                    content.len() - valid_len
+                );
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context("Opening consensus WAL")?;
+        file.set_len(valid_len as u64)?;
+        wal.file = Some((path.to_path_buf(), file));
+
+        if let Some(record) = wal.last.values().max_by_key(|r| (r.slot, r.view)) {
+            info!(
+                "📜 Consensus WAL restored, last signed slot {} view {}",
+                record.slot, record.view
+            );
+        }
+        Ok(wal)
+    }
+
+    fn read_record(rest: &mut &[u8]) -> Option<SignedRecord> {
+        let len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let record = borsh::from_slice(rest.get(4..4 + len)?).ok()?;
+        *rest = &rest[4 + len..];
+        Some(record)
+    }
+
+    fn encode_record(record: &SignedRecord) -> Result<Vec<u8>> {
+        let encoded = borsh::to_vec(record)?;
+        let mut bytes = (encoded.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(encoded);
+        Ok(bytes)
+    }
+
+    pub fn last(&self, kind: SignedKind) -> Option<&SignedRecord> {
+        self.last.get(&kind)
+    }
+
+    /// Fails if the record conflicts with, or goes back before, the last one of its kind.
+    /// Returns whether it still has to be logged.
+    pub fn check(&self, record: &SignedRecord) -> Result<bool> {
+        record.check_after(self.last.get(&record.kind))
+    }
+
+    /// Logs the record durably, to be called before sending the message.
+    pub fn record(&mut self, record: SignedRecord) -> Result<()> {
+        if !self.check(&record)? {
+            return Ok(());
+        }
+        if let Some((_, file)) = &mut self.file {
+            file.write_all(&Self::encode_record(&record)?)?;
+            file.sync_data().context("Syncing consensus WAL")?;
+        }
+        self.last.insert(record.kind, record);
+        self.appended += 1;
+
+        if self.appended > COMPACT_AFTER {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the log with the last record of each kind
+    fn compact(&mut self) -> Result<()> {
+        if let Some((path, file)) = &mut self.file {
+            let tmp = path.with_extension("tmp");
+            let mut tmp_file = File::create(&tmp)?;
+            for record in self.last.values() {
+                tmp_file.write_all(&Self::encode_record(record)?)?;
+            }
+            tmp_file.sync_all()?;
+            fs::rename(&tmp, &*path).context("Compacting consensus WAL")?;
+            // The rename itself is only durable once the directory is synced
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                File::open(dir)?
+                    .sync_all()
+                    .context("Syncing the consensus WAL directory")?;
+            }
+            *file = OpenOptions::new().append(true).open(&*path)?;
+        }
+        self.appended = self.last.len();
+        Ok(())
+    }
+}
+
+impl Consensus {
+    /// Logs prepares, votes and timeouts before they are signed and sent
+    pub(super) fn log_signed(&mut self, msg: &ConsensusNetMessage) -> Result<()> {
+        if let Some(record) = signed_record(msg) {
+            self.wal.record(record)?;
+        }
+        Ok(())
+    }
+
+    /// Logs the vote the leader aggregates for itself instead of sending it, before it is signed
+    pub(super) fn log_own_vote(
+        &mut self,
+        kind: SignedKind,
+        hash: ConsensusProposalHash,
+    ) -> Result<()> {
+        let record = self.round_record(kind, hash);
+        self.wal.record(record)
+    }
+
+    fn round_record(&self, kind: SignedKind, hash: ConsensusProposalHash) -> SignedRecord {
+        SignedRecord {
+            kind,
+            slot: self.bft_round_state.slot,
+            view: self.bft_round_state.view,
+            hash,
+        }
+    }
+
+    /// Signs a vote or timeout of the current round, which remote signers rebuild from its record
+    pub(super) fn sign_for_round<T: BorshSerialize>(
+        &self,
+        kind: SignedKind,
+        hash: ConsensusProposalHash,
+        msg: T,
+    ) -> Result<SignedByValidator<T>> {
+        let record = self.round_record(kind, hash);
+        self.crypto
+            .sign_typed(msg, || Ok(SignerMessage::Round(record)))
+    }
+
+    /// Aggregates the votes or timeouts received with our own, signed with [Self::sign_for_round].
+    /// Our own must already be logged, with [Self::log_signed] or [Self::log_own_vote].
+    pub(super) fn aggregate_for_round<T: BorshSerialize + Clone>(
+        &self,
+        kind: SignedKind,
+        hash: ConsensusProposalHash,
+        msg: T,
+        aggregates: &[&SignedByValidator<T>],
+    ) -> Result<Signed<T, AggregateSignature>> {
+        let record = self.round_record(kind, hash.clone());
+        if self.wal.check(&record)? {
+            bail!(
+                "Aggregating our {:?} of slot {} view {} before logging it",
+                kind,
+                record.slot,
+                record.view
+            );
+        }
+        let own = self.sign_for_round(kind, hash, msg.clone())?;
+        BlstCrypto::aggregate(msg, &[aggregates, &[&own]].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Slot, View};
+
+    fn record(kind: SignedKind, slot: Slot, view: View, hash: &str) -> SignedRecord {
+        SignedRecord {
+            kind,
+            slot,
+            view,
+            hash: ConsensusProposalHash(hash.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_refuses_conflicting_records() {
+        let mut wal = ConsensusWal::default();
+        wal.record(record(SignedKind::PrepareVote, 3, 0, "a"))
+            .unwrap();
+
+        // Signing the same again is fine
+        wal.record(record(SignedKind::PrepareVote, 3, 0, "a"))
+            .unwrap();
+        assert!(wal
+            .record(record(SignedKind::PrepareVote, 3, 0, "b"))
+            .is_err());
+        assert!(wal
+            .record(record(SignedKind::PrepareVote, 2, 5, "b"))
+            .is_err());
+
+        // Other kinds, later views and slots are independent
+        wal.record(record(SignedKind::ConfirmAck, 3, 0, "b"))
+            .unwrap();
+        wal.record(record(SignedKind::PrepareVote, 3, 1, "b"))
+            .unwrap();
+        wal.record(record(SignedKind::PrepareVote, 4, 0, "c"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_replay_and_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("consensus_wal.bin");
+
+        let mut wal = ConsensusWal::open(&path).unwrap();
+        for slot in 1..=(COMPACT_AFTER as u64 + 10) {
+            wal.record(record(SignedKind::PrepareVote, slot, 0, "a"))
+                .unwrap();
+        }
+        wal.record(record(SignedKind::Timeout, 5, 1, "p")).unwrap();
+        drop(wal);
+        // Compacted logs only hold a few records
+        assert!(fs::metadata(&path).unwrap().len() < 1_000);
+
+        // A torn write at the end is dropped
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[42, 0, 0, 0, 1, 2]).unwrap();
+        drop(file);
+
+        let mut wal = ConsensusWal::open(&path).unwrap();
+        assert_eq!(
+            wal.last(SignedKind::PrepareVote),
+            Some(&record(
+                SignedKind::PrepareVote,
+                COMPACT_AFTER as u64 + 10,
+                0,
+                "a"
+            ))
+        );
+        assert_eq!(
+            wal.last(SignedKind::Timeout),
+            Some(&record(SignedKind::Timeout, 5, 1, "p"))
+        );
+        assert!(wal
+            .record(record(SignedKind::Timeout, 5, 1, "other"))
+            .is_err());
+
+        // Records appended after a replay are kept
+        wal.record(record(SignedKind::ConfirmAck, 7, 0, "b"))
+            .unwrap();
+        drop(wal);
+        let wal = ConsensusWal::open(&path).unwrap();
+        assert_eq!(
+            wal.last(SignedKind::ConfirmAck),
+            Some(&record(SignedKind::ConfirmAck, 7, 0, "b"))
+        );
+    }
+}
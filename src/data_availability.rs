@@ -1,8 +1,12 @@
 //! Minimal block storage layer for data availability.
 
+pub mod api;
+mod archive;
 mod blocks_fjall;
 mod blocks_memory;
+pub mod chain_archive;
 mod module;
+pub mod retention;
 
 // Pick one of the two implementations
 use blocks_fjall::Blocks;
@@ -11,8 +15,9 @@ use blocks_fjall::Blocks;
 use hyle_modules::{
     log_error, module_bus_client, module_handle_messages,
     modules::Module,
+    node_state::snapshot::NodeStateSnapshotHeader,
     utils::da_codec::{
-        DataAvailabilityClient, DataAvailabilityEvent, DataAvailabilityRequest,
+        DaSubscription, DataAvailabilityClient, DataAvailabilityEvent, DataAvailabilityRequest,
         DataAvailabilityServer,
     },
 };
@@ -24,7 +29,7 @@ use tokio::{
 };
 
 use crate::{
-    bus::BusClientSender,
+    bus::{command_response::Query, BusClientSender},
     consensus::ConsensusCommand,
     genesis::GenesisEvent,
     model::*,
@@ -33,11 +38,17 @@ use crate::{
 };
 use anyhow::{Context, Error, Result};
 use core::str;
-use std::{collections::BTreeSet, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::Duration,
+};
 use tracing::{debug, info, trace, warn};
 
 pub mod codec;
 
+#[derive(Debug, Clone)]
+pub struct QueryTxInclusionProof(pub TxHash);
+
 module_bus_client! {
 #[derive(Debug)]
 struct DABusClient {
@@ -46,8 +57,10 @@ struct DABusClient {
     sender(ConsensusCommand),
     receiver(MempoolBlockEvent),
     receiver(MempoolStatusEvent),
+    receiver(NodeStateEvent),
     receiver(GenesisEvent),
     receiver(PeerEvent),
+    receiver(Query<QueryTxInclusionProof, Option<TxInclusionProof>>),
 }
 }
 
@@ -59,8 +72,14 @@ pub struct DataAvailability {
     config: SharedConf,
     bus: DABusClient,
     pub blocks: Blocks,
+    /// Snapshot the node state was bootstrapped from, if our chain starts after genesis.
+    bootstrap_snapshot: Option<NodeStateSnapshotHeader>,
 
     buffered_signed_blocks: BTreeSet<SignedBlock>,
+    /// Peers streaming processed blocks instead of signed blocks
+    subscriptions: HashMap<String, DaSubscription>,
+    /// Height of the last block the node state processed since we started
+    processed_height: Option<BlockHeight>,
 
     need_catchup: bool,
     catchup_task: Option<tokio::task::JoinHandle<()>>,
@@ -106,6 +125,10 @@ impl DataAvailability {
                 self.handle_mempool_status_event(evt, &mut server).await;
             }
 
+            listen<NodeStateEvent> NodeStateEvent::NewBlock(block) => {
+                _ = log_error!(self.handle_processed_block(*block, &mut server), "Handling processed block");
+            }
+
             listen<GenesisEvent> cmd => {
                 if let GenesisEvent::GenesisBlock(signed_block) = cmd {
                     debug!("🌱  Genesis block received with validators {:?}", signed_block.consensus_proposal.staking_actions.clone());
@@ -117,16 +140,20 @@ impl DataAvailability {
                 }
             }
             listen<PeerEvent> msg => {
-                if !self.need_catchup || self.catchup_task.as_ref().is_some_and(|t| !t.is_finished()) {
-                    continue;
-                }
                 match msg {
                     PeerEvent::NewPeer { da_address, .. } => {
+                        // Known peers are needed for catching up later on, e.g. after blocks went missing
                         peers.push(da_address.clone());
+                        if !self.need_catchup || self.catchup_task.as_ref().is_some_and(|t| !t.is_finished()) {
+                            continue;
+                        }
                         self.ask_for_catchup_blocks(da_address, catchup_block_sender.clone()).await?;
                     }
                 }
             }
+            command_response<QueryTxInclusionProof, Option<TxInclusionProof>> query => {
+                self.blocks.get_tx_inclusion_proof(&query.0)
+            }
 
             _ = catchup_task_checker_ticker.tick() => {
                 // Check if we need to revive the catchup task.
@@ -167,8 +194,14 @@ impl DataAvailability {
             }
 
             Some(tcp_event) = server.listen_next() => {
-                if let TcpEvent::Message { dest, data } = tcp_event {
-                    _ = self.start_streaming_to_peer(data.0, &mut catchup_joinset, &dest).await;
+                match tcp_event {
+                    TcpEvent::Message { dest, data } => {
+                        _ = self.start_streaming_to_peer(data, &mut catchup_joinset, &dest, &mut server).await;
+                    }
+                    TcpEvent::Closed { dest } => {
+                        self.subscriptions.remove(&dest);
+                    }
+                    TcpEvent::Error { .. } => {}
                 }
             }
 
@@ -183,11 +216,11 @@ impl DataAvailability {
 
                 if let Some(hash) = hash {
                     debug!("📡  Sending block {} to peer {}", &hash, &peer_ip);
-                    if let Ok(Some(signed_block)) = self.blocks.get(&hash)
+                    if let Ok(Some(event)) = self.catchup_event(&hash, &peer_ip)
                     {
                         // Errors will be handled when sending new blocks, ignore here.
                         if server
-                        .try_send(peer_ip.clone(), DataAvailabilityEvent::SignedBlock(signed_block))
+                        .try_send(peer_ip.clone(), event)
                         .is_ok() {
                             catchup_joinset.spawn(async move {
                                 (block_hashes, peer_ip, 0)
@@ -247,6 +280,16 @@ impl DataAvailability {
                     }
                 }
             }
+            MempoolBlockEvent::MissingBlocks(height) => {
+                info!(
+                    "📡 Mempool can't build blocks up to height {}, fetching them from peers",
+                    height
+                );
+                if self.catchup_height.is_none_or(|until| until < height) {
+                    self.catchup_height = Some(height);
+                }
+                self.need_catchup = true;
+            }
         }
 
         Ok(())
@@ -256,6 +299,10 @@ impl DataAvailability {
         evt: MempoolStatusEvent,
         tcp_server: &mut DaTcpServer,
     ) {
+        // Only the node that refused the transaction needs to know about it.
+        if matches!(evt, MempoolStatusEvent::Rejected { .. }) {
+            return;
+        }
         let errors = tcp_server
             .broadcast(DataAvailabilityEvent::MempoolStatusEvent(evt))
             .await;
@@ -266,6 +313,63 @@ impl DataAvailability {
         }
     }
 
+    /// The block to send to a catching up peer, depending on its subscription
+    fn catchup_event(
+        &self,
+        hash: &ConsensusProposalHash,
+        peer_ip: &str,
+    ) -> Result<Option<DataAvailabilityEvent>> {
+        Ok(match self.subscriptions.get(peer_ip) {
+            Some(subscription) => match self.blocks.get_processed(hash)? {
+                Some(block) => Some(DataAvailabilityEvent::ProcessedBlock(Box::new(
+                    subscription.filter(block),
+                ))),
+                // Stored before the node state processed blocks for subscribers, e.g. by an older version
+                None => self
+                    .blocks
+                    .get(hash)?
+                    .map(|block| DataAvailabilityEvent::ProcessedBlockMissing(block.height())),
+            },
+            None => self
+                .blocks
+                .get(hash)?
+                .map(DataAvailabilityEvent::SignedBlock),
+        })
+    }
+
+    /// Sends the block processed by the node state to the subscribed peers
+    fn handle_processed_block(&mut self, block: Block, tcp_server: &mut DaTcpServer) -> Result<()> {
+        if !self.blocks.contains(&block.hash) {
+            debug!(
+                "Ignoring processed block {} not stored by the DA",
+                block.block_height
+            );
+            return Ok(());
+        }
+        // Each peer has a bounded send queue: a subscriber too slow to empty it is dropped,
+        // instead of holding back the others, and can subscribe again from where it stopped.
+        let mut failed_peers = vec![];
+        for (peer, subscription) in self.subscriptions.iter() {
+            let event =
+                DataAvailabilityEvent::ProcessedBlock(Box::new(subscription.filter(block.clone())));
+            if let Err(e) = tcp_server.try_send(peer.clone(), event) {
+                warn!(
+                    "Error while sending processed block {}: {:#}",
+                    block.block_height, e
+                );
+                failed_peers.push(peer.clone());
+            }
+        }
+        for peer in failed_peers {
+            self.subscriptions.remove(&peer);
+            tcp_server.drop_peer_stream(peer);
+        }
+        self.processed_height = Some(block.block_height);
+        self.blocks
+            .put_processed(block)
+            .context("Storing processed block")
+    }
+
     /// if handled, returns the highest height of the processed blocks
     async fn handle_signed_block(
         &mut self,
@@ -282,6 +386,10 @@ impl DataAvailability {
             );
             return None;
         }
+        if self.blocks.is_pruned(block.height()) {
+            debug!("Ignoring block {} at a pruned height", block.height());
+            return None;
+        }
         // if new block is not the next block in the chain, buffer
         if !self.blocks.is_empty() {
             if !self.blocks.contains(block.parent_hash()) {
@@ -295,10 +403,10 @@ impl DataAvailability {
                 self.buffered_signed_blocks.insert(block);
                 return None;
             }
-        // if genesis block is missing, buffer
-        } else if block.height() != BlockHeight(0) {
+        // if first block (genesis, or the one following our bootstrap snapshot) is missing, buffer
+        } else if !self.is_first_block(&block) {
             trace!(
-                "Received block with height {} but genesis block is missing",
+                "Received block with height {} but first block is missing",
                 block.height()
             );
             trace!("Buffering block {}", block.hashed());
@@ -315,10 +423,23 @@ impl DataAvailability {
         );
         let highest_processed_height = self.pop_buffer(hash, tcp_server).await;
         _ = log_error!(self.blocks.persist(), "Persisting blocks");
+        _ = log_error!(self.blocks.apply_retention(), "Applying DA retention");
 
         Some(highest_processed_height.unwrap_or(block_height))
     }
 
+    /// Whether this block can start our local chain: either the genesis block,
+    /// or the block right after the snapshot the node was bootstrapped from.
+    fn is_first_block(&self, block: &SignedBlock) -> bool {
+        match &self.bootstrap_snapshot {
+            Some(snapshot) => {
+                block.height() == snapshot.block_height + 1
+                    && block.parent_hash() == &snapshot.block_hash
+            }
+            None => block.height() == BlockHeight(0),
+        }
+    }
+
     /// Returns the highest height of the processed blocks
     async fn pop_buffer(
         &mut self,
@@ -396,8 +517,17 @@ impl DataAvailability {
 
         // TODO: use retain once async closures are supported ?
         //
+        // Subscribed peers get the block once processed by the node state instead
+        let peers = tcp_server
+            .connected_clients()
+            .into_iter()
+            .filter(|peer| !self.subscriptions.contains_key(peer))
+            .collect();
         let errors = tcp_server
-            .broadcast(DataAvailabilityEvent::SignedBlock(block.clone()))
+            .raw_send_parallel(
+                peers,
+                borsh::to_vec(&DataAvailabilityEvent::SignedBlock(block.clone()))?,
+            )
             .await;
 
         for (peer, error) in errors {
@@ -422,26 +552,74 @@ impl DataAvailability {
 
     async fn start_streaming_to_peer(
         &mut self,
-        start_height: BlockHeight,
+        request: DataAvailabilityRequest,
         catchup_joinset: &mut JoinSet<(Vec<ConsensusProposalHash>, String, usize)>,
         peer_ip: &str,
+        tcp_server: &mut DaTcpServer,
     ) -> Result<()> {
+        let start_height = match request {
+            DataAvailabilityRequest::FromHeight(start_height) => {
+                self.subscriptions.remove(peer_ip);
+                start_height
+            }
+            DataAvailabilityRequest::Subscribe(subscription) => {
+                info!(
+                    "📡  Peer {} subscribed to contracts {:?} from {}",
+                    peer_ip, subscription.contracts, subscription.from
+                );
+                let start_height = subscription.from;
+                self.subscriptions.insert(peer_ip.to_string(), subscription);
+                start_height
+            }
+        };
+
+        if let Some(first_available) = self.blocks.pruned_below() {
+            if start_height < first_available {
+                warn!(
+                    "Peer {} asked for blocks from {}, pruned below {}",
+                    peer_ip, start_height, first_available
+                );
+                tcp_server.try_send(
+                    peer_ip.to_string(),
+                    DataAvailabilityEvent::BlocksPruned {
+                        requested: start_height,
+                        first_available,
+                    },
+                )?;
+                return Ok(());
+            }
+        }
+
         // Finally, stream past blocks as required.
         // We'll create a copy of the range so we don't stream everything.
         // We will safely stream everything as any new block will be sent
         // because we registered in the struct beforehand.
         // Like pings, this just sends a message processed in the main select! loop.
+        let subscribed = self.subscriptions.contains_key(peer_ip);
+        let mut end_height = self
+            .blocks
+            .last()
+            .map_or(start_height, |block| block.height())
+            + 1;
+        // Subscribers get the blocks the node state did not process yet once it does.
+        // Older blocks that were never processed are sent as missing, see `catchup_event`.
+        if let (true, Some(processed_height)) = (subscribed, self.processed_height) {
+            end_height = end_height.min(processed_height + 1);
+        }
         let mut processed_block_hashes: Vec<_> = self
             .blocks
-            .range(
-                start_height,
-                self.blocks
-                    .last()
-                    .map_or(start_height, |block| block.height())
-                    + 1,
-            )
+            .range(start_height, end_height)
             .filter_map(|item| item.ok())
             .collect();
+        // Until the node state processes a block, the last ones stored may still be processed
+        if subscribed && self.processed_height.is_none() {
+            while processed_block_hashes
+                .last()
+                .is_some_and(|hash| !self.blocks.has_processed(hash))
+            {
+                processed_block_hashes.pop();
+            }
+        }
         processed_block_hashes.reverse();
 
         let peer_ip = peer_ip.to_string();
@@ -461,13 +639,20 @@ impl DataAvailability {
             .blocks
             .last()
             .map(|block| block.height() + 1)
+            .or_else(|| {
+                self.bootstrap_snapshot
+                    .as_ref()
+                    .map(|snapshot| snapshot.block_height + 1)
+            })
             .unwrap_or(BlockHeight(0));
 
         let mut client = DataAvailabilityClient::connect("block_catcher".to_string(), ip)
             .await
             .context("Error occurred setting up the DA listener")?;
 
-        client.send(DataAvailabilityRequest(start)).await?;
+        client
+            .send(DataAvailabilityRequest::FromHeight(start))
+            .await?;
 
         if let Some(task) = self.catchup_task.take() {
             if !task.is_finished() {
@@ -508,6 +693,13 @@ impl DataAvailability {
                                 // Reset the timeout ONLY when a block is received
                                 deadline = Instant::now() + timeout_duration;
                             }
+                            Some(DataAvailabilityEvent::BlocksPruned { requested, first_available }) => {
+                                warn!(
+                                    "Peer pruned blocks below {}, cannot catch up from {}",
+                                    first_available, requested
+                                );
+                                break;
+                            }
                             Some(_) => {
                                 tracing::trace!("Dropped received message in catchup task");
                             }
@@ -571,7 +763,10 @@ pub mod tests {
                 config: config.into(),
                 bus,
                 blocks,
+                bootstrap_snapshot: None,
                 buffered_signed_blocks: Default::default(),
+                subscriptions: Default::default(),
+                processed_height: None,
                 need_catchup: false,
                 catchup_task: None,
                 catchup_height: None,
@@ -617,6 +812,168 @@ pub mod tests {
         Ok(())
     }
 
+    #[test_log::test]
+    fn test_processed_blocks() -> Result<()> {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let mut blocks = Blocks::new(tmpdir.path())?;
+
+        let tx: Transaction = BlobTransaction::new(
+            "alice@hydentity",
+            vec![Blob {
+                contract_name: "hyllar".into(),
+                data: BlobData(vec![1]),
+            }],
+        )
+        .into();
+        let signed_block = SignedBlock {
+            data_proposals: vec![(
+                LaneId::default(),
+                vec![DataProposal::new(None, vec![tx.clone()])],
+            )],
+            ..SignedBlock::default()
+        };
+        let processed = NodeState::create("test".to_string(), "data_availability")
+            .handle_signed_block(&signed_block)?;
+        assert!(!processed.txs.is_empty());
+
+        blocks.put(signed_block.clone())?;
+        assert!(!blocks.has_processed(&signed_block.hashed()));
+        blocks.put_processed(processed.clone())?;
+        assert!(blocks.has_processed(&signed_block.hashed()));
+        // Transactions are not stored twice but rebuilt from the signed block
+        assert_eq!(
+            blocks.get_processed(&signed_block.hashed())?,
+            Some(processed)
+        );
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_tx_inclusion_proof() -> Result<()> {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let mut blocks = Blocks::new(tmpdir.path())?;
+
+        let txs: Vec<Transaction> = (0..3)
+            .map(|i| {
+                BlobTransaction::new(
+                    "alice@hydentity",
+                    vec![Blob {
+                        contract_name: "hyllar".into(),
+                        data: BlobData(vec![i]),
+                    }],
+                )
+                .into()
+            })
+            .collect();
+        let mut signed_block = SignedBlock {
+            data_proposals: vec![(
+                LaneId::default(),
+                vec![DataProposal::new(None, txs.clone())],
+            )],
+            ..SignedBlock::default()
+        };
+        signed_block.consensus_proposal.tx_root = signed_block.tx_merkle_root();
+        blocks.put(signed_block.clone())?;
+
+        for tx in &txs {
+            let proof = blocks.get_tx_inclusion_proof(&tx.hashed())?.unwrap();
+            assert!(proof.verify(&tx.hashed(), &signed_block.hashed()));
+        }
+        assert!(blocks
+            .get_tx_inclusion_proof(&TxHash("unknown".into()))?
+            .is_none());
+        Ok(())
+    }
+
+    #[test_log::test]
+    fn test_blocks_retention() -> Result<()> {
+        use super::retention::{DaRetentionConf, DaRetentionMode};
+
+        for mode in [DaRetentionMode::Archive, DaRetentionMode::Prune] {
+            let tmpdir = tempfile::tempdir().unwrap();
+            let conf = DaRetentionConf {
+                mode,
+                keep_blocks: 5,
+                keep_days: 0,
+                segment_blocks: 4,
+            };
+            let db_path = tmpdir.path().join("da.db");
+            let archive_path = tmpdir.path().join("archive");
+            let mut blocks = Blocks::new_with_retention(&db_path, &archive_path, conf.clone())?;
+
+            let mut chain = vec![];
+            let mut block = SignedBlock::default();
+            for slot in 0..12 {
+                block.consensus_proposal.parent_hash = block.hashed();
+                block.consensus_proposal.slot = slot;
+                blocks.put(block.clone())?;
+                blocks.apply_retention()?;
+                chain.push(block.clone());
+            }
+            // Blocks 0 to 3 expired once block 9 was stored, 4 to 7 are still within keep_blocks
+            let (old, recent) = chain.split_at(4);
+            for block in recent {
+                assert!(blocks.get(&block.hashed())?.is_some());
+            }
+            match mode {
+                DaRetentionMode::Archive => {
+                    assert_eq!(blocks.pruned_below(), None);
+                    for block in old {
+                        assert_eq!(blocks.get(&block.hashed())?.as_ref(), Some(block));
+                        assert!(blocks.contains(&block.hashed()));
+                    }
+                    let served = blocks
+                        .range(BlockHeight(0), BlockHeight(12))
+                        .collect::<Result<Vec<_>>>()?;
+                    assert_eq!(served.len(), 12);
+                }
+                _ => {
+                    assert_eq!(blocks.pruned_below(), Some(BlockHeight(4)));
+                    assert!(blocks.is_pruned(BlockHeight(3)));
+                    assert!(!blocks.is_pruned(BlockHeight(4)));
+                    for block in old {
+                        assert!(blocks.get(&block.hashed())?.is_none());
+                    }
+                }
+            }
+
+            // The retention state survives a restart
+            drop(blocks);
+            let blocks = Blocks::new_with_retention(&db_path, &archive_path, conf)?;
+            assert_eq!(
+                blocks.is_pruned(BlockHeight(3)),
+                mode == DaRetentionMode::Prune
+            );
+            for block in &chain {
+                assert_eq!(
+                    blocks.get(&block.hashed())?.is_some(),
+                    mode == DaRetentionMode::Archive || block.height() >= BlockHeight(4)
+                );
+            }
+
+            // Switching from archiving to pruning deletes the archive
+            if mode == DaRetentionMode::Archive {
+                drop(blocks);
+                let conf = DaRetentionConf {
+                    mode: DaRetentionMode::Prune,
+                    keep_blocks: 5,
+                    keep_days: 0,
+                    segment_blocks: 4,
+                };
+                let blocks = Blocks::new_with_retention(&db_path, &archive_path, conf)?;
+                assert!(!archive_path.exists());
+                assert_eq!(blocks.pruned_below(), Some(BlockHeight(4)));
+                for block in &chain {
+                    assert_eq!(
+                        blocks.get_by_height(block.height())?.is_some(),
+                        block.height() >= BlockHeight(4)
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_pop_buffer_large() {
         let tmpdir = tempfile::tempdir().unwrap().keep();
@@ -634,7 +991,10 @@ pub mod tests {
             config: Default::default(),
             bus,
             blocks,
+            bootstrap_snapshot: None,
             buffered_signed_blocks: Default::default(),
+            subscriptions: Default::default(),
+            processed_height: None,
             need_catchup: false,
             catchup_task: None,
             catchup_height: None,
@@ -659,6 +1019,80 @@ pub mod tests {
         }
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_subscription_reports_unprocessed_blocks() {
+        let tmpdir = tempfile::tempdir().unwrap().keep();
+        let blocks = Blocks::new(&tmpdir).unwrap();
+
+        let mut server = DataAvailabilityServer::start(find_available_port().await, "DaServer")
+            .await
+            .unwrap();
+
+        let bus = super::DABusClient::new_from_bus(crate::bus::SharedMessageBus::new(
+            crate::bus::metrics::BusMetrics::global("global".to_string()),
+        ))
+        .await;
+        let mut da = super::DataAvailability {
+            config: Default::default(),
+            bus,
+            blocks,
+            bootstrap_snapshot: None,
+            buffered_signed_blocks: Default::default(),
+            subscriptions: Default::default(),
+            processed_height: None,
+            need_catchup: false,
+            catchup_task: None,
+            catchup_height: None,
+        };
+
+        // Blocks 0 to 3 are stored, the node state processed 0 and 2, and 3 is being processed
+        let mut block = SignedBlock::default();
+        for i in 1..5 {
+            da.blocks.put(block.clone()).unwrap();
+            if i == 1 || i == 3 {
+                da.handle_processed_block(
+                    Block {
+                        block_height: block.height(),
+                        hash: block.hashed(),
+                        ..Default::default()
+                    },
+                    &mut server,
+                )
+                .unwrap();
+            }
+            block.consensus_proposal.parent_hash = block.hashed();
+            block.consensus_proposal.slot = i;
+        }
+
+        let mut catchup_joinset = tokio::task::JoinSet::new();
+        da.start_streaming_to_peer(
+            DataAvailabilityRequest::Subscribe(super::DaSubscription::default()),
+            &mut catchup_joinset,
+            "subscriber",
+            &mut server,
+        )
+        .await
+        .unwrap();
+        let (mut hashes, _, _) = catchup_joinset.join_next().await.unwrap().unwrap();
+        hashes.reverse();
+
+        let events: Vec<_> = hashes
+            .iter()
+            .map(|hash| da.catchup_event(hash, "subscriber").unwrap().unwrap())
+            .collect();
+        assert_eq!(events.len(), 3);
+        assert!(
+            matches!(&events[0], DataAvailabilityEvent::ProcessedBlock(block) if block.block_height == BlockHeight(0))
+        );
+        assert_eq!(
+            events[1],
+            DataAvailabilityEvent::ProcessedBlockMissing(BlockHeight(1))
+        );
+        assert!(
+            matches!(&events[2], DataAvailabilityEvent::ProcessedBlock(block) if block.block_height == BlockHeight(2))
+        );
+    }
+
     module_bus_client! {
     #[derive(Debug)]
     struct TestBusClient {
@@ -684,7 +1118,10 @@ pub mod tests {
             config: config.clone().into(),
             bus,
             blocks,
+            bootstrap_snapshot: None,
             buffered_signed_blocks: Default::default(),
+            subscriptions: Default::default(),
+            processed_height: None,
             need_catchup: false,
             catchup_task: None,
             catchup_height: None,
@@ -710,7 +1147,7 @@ pub mod tests {
                 .unwrap();
 
         client
-            .send(DataAvailabilityRequest(BlockHeight(0)))
+            .send(DataAvailabilityRequest::FromHeight(BlockHeight(0)))
             .await
             .unwrap();
 
@@ -767,7 +1204,7 @@ pub mod tests {
                 .unwrap();
 
         client
-            .send(DataAvailabilityRequest(BlockHeight(0)))
+            .send(DataAvailabilityRequest::FromHeight(BlockHeight(0)))
             .await
             .unwrap();
 
@@ -0,0 +1,115 @@
+use anyhow::anyhow;
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json, Router,
+};
+use client_sdk::contract_indexer::AppError;
+use hyle_modules::{bus::SharedMessageBus, modules::signal::ShutdownModule};
+use tracing::error;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    bus::{
+        bus_client,
+        command_response::{CmdRespClient, Query},
+        metrics::BusMetrics,
+    },
+    model::{SharedRunContext, TxHash, TxInclusionProof},
+};
+
+use super::QueryTxInclusionProof;
+
+bus_client! {
+struct RestBusClient {
+    sender(Query<QueryTxInclusionProof, Option<TxInclusionProof>>),
+    receiver(ShutdownModule),
+}
+}
+
+pub struct RouterState {
+    bus: RestBusClient,
+}
+
+#[derive(OpenApi)]
+struct DataAvailabilityAPI;
+
+pub async fn api(bus: &SharedMessageBus, ctx: &SharedRunContext) -> Router<()> {
+    let state = RouterState {
+        bus: RestBusClient::new_from_bus(bus.new_handle()).await,
+    };
+
+    let (router, api) = OpenApiRouter::with_openapi(DataAvailabilityAPI::openapi())
+        .routes(routes!(get_tx_inclusion_proof))
+        .split_for_parts();
+
+    if let Ok(mut o) = ctx.api.openapi.lock() {
+        *o = o.clone().nest("/v1/da", api);
+    }
+
+    router.with_state(state)
+}
+
+/// Proof that a transaction is part of a block, to be checked against the block hash
+/// with [TxInclusionProof::verify].
+#[utoipa::path(
+    get,
+    path = "/tx/{tx_hash}/proof",
+    params(
+        ("tx_hash" = String, Path, description = "Tx hash")
+    ),
+    tag = "Data Availability",
+    responses(
+        (status = OK, body = TxInclusionProof)
+    )
+)]
+#[debug_handler]
+pub async fn get_tx_inclusion_proof(
+    Path(tx_hash): Path<TxHash>,
+    State(mut state): State<RouterState>,
+) -> Result<impl IntoResponse, AppError> {
+    match state
+        .bus
+        .shutdown_aware_request::<()>(QueryTxInclusionProof(tx_hash.clone()))
+        .await
+    {
+        Ok(Some(proof)) => Ok(Json(proof)),
+        Ok(None) => Err(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("Transaction {} not found", tx_hash),
+        )),
+        Err(err) => {
+            error!("{:?}", err);
+
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!(
+                    "Error while getting proof for transaction {}: {err}",
+                    tx_hash
+                ),
+            ))
+        }
+    }
+}
+
+impl Clone for RouterState {
+    fn clone(&self) -> Self {
+        use hyle_modules::utils::static_type_map::Pick;
+        Self {
+            bus: RestBusClient::new(
+                Pick::<BusMetrics>::get(&self.bus).clone(),
+                Pick::<
+                    tokio::sync::broadcast::Sender<
+                        Query<QueryTxInclusionProof, Option<TxInclusionProof>>,
+                    >,
+                >::get(&self.bus)
+                .clone(),
+                Pick::<tokio::sync::broadcast::Receiver<ShutdownModule>>::get(&self.bus)
+                    .resubscribe(),
+            ),
+        }
+    }
+}
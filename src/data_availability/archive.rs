@@ -0,0 +1,175 @@
+//! Cold storage of the blocks moved out of the fjall store.
+//!
+//! Blocks are archived in segments of consecutive heights. Each segment is a file of
+//! zstd-compressed borsh blocks, next to an index file holding the offset and length
+//! of each block, so that any archived block can be read without scanning its segment.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use tracing::{info, warn};
+
+use crate::model::{BlockHeight, SignedBlock};
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Offset and length of each block of a segment, the first one being at `first` height
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+struct SegmentIndex {
+    first: BlockHeight,
+    entries: Vec<(u64, u32)>,
+}
+
+impl SegmentIndex {
+    fn end(&self) -> BlockHeight {
+        self.first + self.entries.len() as u64
+    }
+}
+
+#[derive(Debug)]
+pub struct Archive {
+    dir: PathBuf,
+    segments: BTreeMap<BlockHeight, SegmentIndex>,
+}
+
+impl Archive {
+    fn segment_path(&self, first: BlockHeight) -> PathBuf {
+        self.dir.join(format!("blocks_{:020}.seg", first.0))
+    }
+
+    fn index_path(&self, first: BlockHeight) -> PathBuf {
+        self.dir.join(format!("blocks_{:020}.idx", first.0))
+    }
+
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).context("Creating DA archive directory")?;
+        let mut segments = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "idx") {
+                continue;
+            }
+            // Segments are written before their index, a segment without one is ignored
+            match Self::read_index(&path) {
+                Ok(index) => {
+                    segments.insert(index.first, index);
+                }
+                Err(e) => warn!("Ignoring DA archive index {}: {:#}", path.display(), e),
+            }
+        }
+        let archive = Archive {
+            dir: dir.to_path_buf(),
+            segments,
+        };
+        if let Some((first, last)) = archive.bounds() {
+            info!("🧊 DA archive holds blocks {} to {}", first, last);
+        }
+        Ok(archive)
+    }
+
+    fn read_index(path: &Path) -> Result<SegmentIndex> {
+        Ok(borsh::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Lowest and highest archived heights
+    pub fn bounds(&self) -> Option<(BlockHeight, BlockHeight)> {
+        let first = self.segments.values().next()?.first;
+        let last = self.segments.values().next_back()?.end() - 1;
+        Some((first, last))
+    }
+
+    /// Writes the blocks, of consecutive heights, as a new segment
+    pub fn write_segment(&mut self, blocks: &[SignedBlock]) -> Result<()> {
+        let Some(first) = blocks.first().map(|b| b.height()) else {
+            return Ok(());
+        };
+        let mut content = vec![];
+        let mut entries = vec![];
+        for (i, block) in blocks.iter().enumerate() {
+            if block.height() != first + i as u64 {
+                bail!(
+                    "Archived blocks must be consecutive, got {}",
+                    block.height()
+                );
+            }
+            let compressed = zstd::encode_all(borsh::to_vec(block)?.as_slice(), ZSTD_LEVEL)?;
+            entries.push((content.len() as u64, compressed.len() as u32));
+            content.extend(compressed);
+        }
+        let index = SegmentIndex { first, entries };
+
+        Self::write_synced(&self.segment_path(first), &content)?;
+        Self::write_synced(&self.index_path(first), &borsh::to_vec(&index)?)?;
+        self.segments.insert(first, index);
+        Ok(())
+    }
+
+    fn write_synced(path: &Path, content: &[u8]) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path).with_context(|| format!("Writing {}", path.display()))
+    }
+
+    /// Deletes all segments, along with the archive directory
+    pub fn delete(self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)
+            .with_context(|| format!("Deleting DA archive {}", self.dir.display()))
+    }
+
+    pub fn get(&self, height: BlockHeight) -> Result<Option<SignedBlock>> {
+        let Some((first, index)) = self.segments.range(..=height).next_back() else {
+            return Ok(None);
+        };
+        let Some((offset, len)) = index.entries.get((height.0 - first.0) as usize) else {
+            return Ok(None);
+        };
+        let mut file = File::open(self.segment_path(*first))?;
+        file.seek(SeekFrom::Start(*offset))?;
+        let mut compressed = vec![0u8; *len as usize];
+        file.read_exact(&mut compressed)?;
+        let block = borsh::from_slice(&zstd::decode_all(compressed.as_slice())?)?;
+        Ok(Some(block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Hashed;
+
+    #[test]
+    fn test_archive_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut archive = Archive::open(dir.path()).unwrap();
+
+        let mut blocks = vec![];
+        let mut block = SignedBlock::default();
+        for slot in 1..=20 {
+            block.consensus_proposal.parent_hash = block.hashed();
+            block.consensus_proposal.slot = slot;
+            blocks.push(block.clone());
+        }
+        let (first, second) = blocks.split_at(10);
+        archive.write_segment(first).unwrap();
+        archive.write_segment(second).unwrap();
+        let gap: Vec<_> = blocks.iter().step_by(2).take(2).cloned().collect();
+        assert!(archive.write_segment(&gap).is_err());
+
+        // Segments are found again once reopened
+        let archive = Archive::open(dir.path()).unwrap();
+        assert_eq!(archive.bounds(), Some((BlockHeight(1), BlockHeight(20))));
+        for block in &blocks {
+            assert_eq!(archive.get(block.height()).unwrap().as_ref(), Some(block));
+        }
+        assert_eq!(archive.get(BlockHeight(0)).unwrap(), None);
+        assert_eq!(archive.get(BlockHeight(21)).unwrap(), None);
+    }
+}
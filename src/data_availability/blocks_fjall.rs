@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use fjall::{
     Config, Keyspace, KvSeparationOptions, PartitionCreateOptions, PartitionHandle, Slice,
 };
@@ -7,9 +7,20 @@ use tracing::{error, info, trace};
 
 use crate::{
     model::ConsensusProposalHash,
-    model::{BlockHeight, Hashed, SignedBlock},
+    model::{Block, BlockHeight, Hashed, SignedBlock, TxHash, TxInclusionProof},
 };
 
+use crate::utils::conf::Conf;
+
+use super::{
+    archive::Archive,
+    retention::{DaRetentionConf, DaRetentionMode},
+};
+
+/// Keys of the retention partition
+const FIRST_HOT_KEY: &[u8] = b"first_hot";
+const PRUNED_BELOW_KEY: &[u8] = b"pruned_below";
+
 struct FjallHashKey(ConsensusProposalHash);
 struct FjallHeightKey([u8; 8]);
 struct FjallValue(Vec<u8>);
@@ -51,6 +62,18 @@ pub struct Blocks {
     db: Keyspace,
     by_hash: PartitionHandle,
     by_height: PartitionHandle,
+    /// Blocks as processed by the node state, without their transactions
+    processed: PartitionHandle,
+    /// Hash of the block each transaction was sequenced in
+    by_tx_hash: PartitionHandle,
+    /// Heights of the blocks moved to the archive, by hash
+    archived_heights: PartitionHandle,
+    /// First height still in `by_hash`, and height below which blocks were pruned
+    retention_state: PartitionHandle,
+    retention: DaRetentionConf,
+    archive: Option<Archive>,
+    first_hot: Option<BlockHeight>,
+    pruned_below: Option<BlockHeight>,
 }
 
 impl Blocks {
@@ -60,8 +83,32 @@ impl Blocks {
     fn decode_block_hash(item: Slice) -> Result<ConsensusProposalHash> {
         borsh::from_slice(&item).map_err(Into::into)
     }
+    fn decode_height(item: Slice) -> Result<BlockHeight> {
+        Ok(BlockHeight(u64::from_be_bytes(item.as_ref().try_into()?)))
+    }
 
     pub fn new(path: &Path) -> Result<Self> {
+        Self::new_with_retention(
+            path,
+            &path.with_extension("archive"),
+            DaRetentionConf::default(),
+        )
+    }
+
+    /// Opens the DA store in the data directory of the node
+    pub fn open(config: &Conf) -> Result<Self> {
+        Self::new_with_retention(
+            &config.data_directory.join("data_availability.db"),
+            &config.data_directory.join("data_availability_archive"),
+            config.da_retention.clone(),
+        )
+    }
+
+    pub fn new_with_retention(
+        path: &Path,
+        archive_path: &Path,
+        retention: DaRetentionConf,
+    ) -> Result<Self> {
         let db = Config::new(path)
             .cache_size(256 * 1024 * 1024)
             .max_journaling_size(512 * 1024 * 1024)
@@ -80,14 +127,98 @@ impl Blocks {
         )?;
         let by_height =
             db.open_partition("block_hashes_by_height", PartitionCreateOptions::default())?;
+        let processed = db.open_partition(
+            "processed_blocks_by_hash",
+            PartitionCreateOptions::default().with_kv_separation(KvSeparationOptions::default()),
+        )?;
+        let by_tx_hash =
+            db.open_partition("block_hashes_by_tx_hash", PartitionCreateOptions::default())?;
+        let archived_heights = db.open_partition(
+            "archived_heights_by_hash",
+            PartitionCreateOptions::default(),
+        )?;
+        let retention_state =
+            db.open_partition("retention_state", PartitionCreateOptions::default())?;
 
         info!("{} block(s) available", by_hash.len()?);
 
-        Ok(Blocks {
+        // Archived blocks are still served if archiving was turned off since, unless the store
+        // is now pruned
+        let archive = if retention.mode == DaRetentionMode::Archive
+            || !archived_heights.is_empty()?
+            || archive_path.exists()
+        {
+            Some(Archive::open(archive_path)?)
+        } else {
+            None
+        };
+        let first_hot = retention_state
+            .get(FIRST_HOT_KEY)?
+            .map(Self::decode_height)
+            .transpose()?;
+        let pruned_below = retention_state
+            .get(PRUNED_BELOW_KEY)?
+            .map(Self::decode_height)
+            .transpose()?;
+
+        let mut blocks = Blocks {
             db,
             by_hash,
             by_height,
-        })
+            processed,
+            by_tx_hash,
+            archived_heights,
+            retention_state,
+            retention,
+            archive,
+            first_hot,
+            pruned_below,
+        };
+        if blocks.retention.mode == DaRetentionMode::Prune {
+            blocks.prune_archive()?;
+        }
+        Ok(blocks)
+    }
+
+    /// Deletes the blocks archived before retention switched to pruning, and their segment files.
+    /// They are all older than the blocks of the fjall store, which are only pruned once expired.
+    fn prune_archive(&mut self) -> Result<()> {
+        let Some(bounds) = self.archive.as_ref().map(Archive::bounds) else {
+            return Ok(());
+        };
+        if let Some((first, last)) = bounds {
+            for height in first.0..=last.0 {
+                let Some(hash) = self
+                    .by_height
+                    .get(FjallHeightKey::new(BlockHeight(height)))?
+                    .map(Self::decode_block_hash)
+                    .transpose()?
+                else {
+                    continue;
+                };
+                self.remove_tx_hashes(&hash)?;
+                self.processed.remove(FjallHashKey(hash.clone()).as_ref())?;
+                self.archived_heights.remove(FjallHashKey(hash).as_ref())?;
+                self.by_height
+                    .remove(FjallHeightKey::new(BlockHeight(height)).as_ref())?;
+            }
+            let end = last + 1;
+            if self.pruned_below.is_none_or(|below| below < end) {
+                self.pruned_below = Some(end);
+                self.retention_state
+                    .insert(PRUNED_BELOW_KEY, FjallHeightKey::new(end).as_ref())?;
+            }
+            self.db.persist(fjall::PersistMode::SyncAll)?;
+            info!(
+                "✂️ Pruned archived blocks {} to {}, retention is now pruning",
+                first, last
+            );
+        }
+        // The segments go last, so that an interrupted pruning is resumed on the next start
+        if let Some(archive) = self.archive.take() {
+            archive.delete()?;
+        }
+        Ok(())
     }
 
     pub fn is_empty(&self) -> bool {
@@ -114,18 +245,205 @@ impl Blocks {
             FjallHeightKey::new(block.height()).as_ref(),
             FjallValue::new_with_block_hash(&block.hashed())?.as_ref(),
         )?;
+        for (_, tx_id, _) in block.iter_txs_with_id() {
+            self.by_tx_hash.insert(
+                tx_id.1 .0.as_bytes(),
+                FjallValue::new_with_block_hash(&block.hashed())?.as_ref(),
+            )?;
+        }
         Ok(())
     }
 
     pub fn get(&self, block_hash: &ConsensusProposalHash) -> Result<Option<SignedBlock>> {
-        let item = self.by_hash.get(FjallHashKey(block_hash.clone()))?;
-        item.map(Self::decode_block).transpose()
+        if let Some(item) = self.by_hash.get(FjallHashKey(block_hash.clone()))? {
+            return Self::decode_block(item).map(Some);
+        }
+        let Some(archive) = &self.archive else {
+            return Ok(None);
+        };
+        match self
+            .archived_heights
+            .get(FjallHashKey(block_hash.clone()))?
+        {
+            Some(height) => archive.get(Self::decode_height(height)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Block at this height, be it in the fjall store or archived
+    pub fn get_by_height(&self, height: BlockHeight) -> Result<Option<SignedBlock>> {
+        match self.by_height.get(FjallHeightKey::new(height))? {
+            Some(item) => self.get(&Self::decode_block_hash(item)?),
+            None => Ok(None),
+        }
     }
 
     pub fn contains(&mut self, block: &ConsensusProposalHash) -> bool {
         self.by_hash
             .contains_key(FjallHashKey(block.clone()))
             .unwrap_or(false)
+            || self
+                .archived_heights
+                .contains_key(FjallHashKey(block.clone()))
+                .unwrap_or(false)
+    }
+
+    /// Proves that a transaction is part of the block it was sequenced in
+    pub fn get_tx_inclusion_proof(&self, tx_hash: &TxHash) -> Result<Option<TxInclusionProof>> {
+        let Some(item) = self.by_tx_hash.get(tx_hash.0.as_bytes())? else {
+            return Ok(None);
+        };
+        let Some(block) = self.get(&Self::decode_block_hash(item)?)? else {
+            return Ok(None);
+        };
+        Ok(block.tx_inclusion_proof(tx_hash))
+    }
+
+    /// Stores what the node state computed for a stored block. Its transactions are left out,
+    /// they are rebuilt from the signed block.
+    pub fn put_processed(&mut self, mut block: Block) -> Result<()> {
+        block.txs.clear();
+        self.processed.insert(
+            FjallHashKey(block.hash.clone()).as_ref(),
+            borsh::to_vec(&block)?.as_slice(),
+        )?;
+        Ok(())
+    }
+
+    pub fn has_processed(&self, block_hash: &ConsensusProposalHash) -> bool {
+        self.processed
+            .contains_key(FjallHashKey(block_hash.clone()))
+            .unwrap_or(false)
+    }
+
+    pub fn get_processed(&self, block_hash: &ConsensusProposalHash) -> Result<Option<Block>> {
+        let Some(item) = self.processed.get(FjallHashKey(block_hash.clone()))? else {
+            return Ok(None);
+        };
+        let Some(signed_block) = self.get(block_hash)? else {
+            return Ok(None);
+        };
+        let mut block: Block = borsh::from_slice(&item)?;
+        block.txs = signed_block
+            .iter_txs_with_id()
+            .map(|(_, tx_id, tx)| (tx_id, tx.clone()))
+            .collect();
+        Ok(Some(block))
+    }
+
+    /// Whether blocks at this height were deleted by pruning
+    pub fn is_pruned(&self, height: BlockHeight) -> bool {
+        self.pruned_below.is_some_and(|below| height < below)
+    }
+
+    pub fn pruned_below(&self) -> Option<BlockHeight> {
+        self.pruned_below
+    }
+
+    /// Archives or prunes the oldest batch of blocks of the fjall store, once all of them expired.
+    /// Called after each stored block, so at most one batch is moved at a time.
+    pub fn apply_retention(&mut self) -> Result<()> {
+        if self.retention.mode == DaRetentionMode::KeepAll {
+            return Ok(());
+        }
+        let Some(last) = self.last() else {
+            return Ok(());
+        };
+        let first_hot = match self.first_hot {
+            Some(first_hot) => first_hot,
+            None => match self.by_height.first_key_value()? {
+                Some((height, _)) => Self::decode_height(height)?,
+                None => return Ok(()),
+            },
+        };
+        let end = first_hot + self.retention.segment_blocks.max(1);
+        let hashes = self
+            .range(first_hot, end)
+            .collect::<Result<Vec<ConsensusProposalHash>>>()?;
+        // The last block of the batch is its most recent one
+        let Some(batch_last) = hashes
+            .last()
+            .map(|hash| self.get(hash))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(());
+        };
+        if hashes.len() as u64 != end.0 - first_hot.0
+            || !self.retention.is_expired(&batch_last, &last)
+        {
+            return Ok(());
+        }
+
+        match (&mut self.archive, self.retention.mode) {
+            (Some(archive), DaRetentionMode::Archive) => {
+                let mut blocks = vec![];
+                for hash in &hashes {
+                    let block = match self.by_hash.get(FjallHashKey(hash.clone()))? {
+                        Some(item) => Self::decode_block(item)?,
+                        // Already archived, before a restart interrupted the move
+                        None => self
+                            .archived_heights
+                            .get(FjallHashKey(hash.clone()))?
+                            .map(Self::decode_height)
+                            .transpose()?
+                            .and_then(|height| archive.get(height).transpose())
+                            .transpose()?
+                            .context(format!("Block {hash} to archive not found"))?,
+                    };
+                    blocks.push(block);
+                }
+                archive.write_segment(&blocks)?;
+                for block in &blocks {
+                    self.archived_heights.insert(
+                        FjallHashKey(block.hashed()).as_ref(),
+                        FjallHeightKey::new(block.height()).as_ref(),
+                    )?;
+                }
+                for hash in &hashes {
+                    self.by_hash.remove(FjallHashKey(hash.clone()).as_ref())?;
+                }
+                info!("🧊 Archived blocks {} to {}", first_hot, end - 1);
+            }
+            _ => {
+                for (height, hash) in (first_hot.0..end.0).zip(&hashes) {
+                    self.remove_tx_hashes(hash)?;
+                    self.by_hash.remove(FjallHashKey(hash.clone()).as_ref())?;
+                    self.processed.remove(FjallHashKey(hash.clone()).as_ref())?;
+                    self.archived_heights
+                        .remove(FjallHashKey(hash.clone()).as_ref())?;
+                    self.by_height
+                        .remove(FjallHeightKey::new(BlockHeight(height)).as_ref())?;
+                }
+                self.pruned_below = Some(end);
+                self.retention_state
+                    .insert(PRUNED_BELOW_KEY, FjallHeightKey::new(end).as_ref())?;
+                info!("✂️ Pruned blocks {} to {}", first_hot, end - 1);
+            }
+        }
+        self.first_hot = Some(end);
+        self.retention_state
+            .insert(FIRST_HOT_KEY, FjallHeightKey::new(end).as_ref())?;
+        Ok(())
+    }
+
+    /// Removes the transactions of a pruned block from the index,
+    /// unless they were sequenced again in a later block
+    fn remove_tx_hashes(&mut self, block_hash: &ConsensusProposalHash) -> Result<()> {
+        let Some(block) = self.get(block_hash)? else {
+            return Ok(());
+        };
+        for (_, tx_id, _) in block.iter_txs_with_id() {
+            let indexed = self
+                .by_tx_hash
+                .get(tx_id.1 .0.as_bytes())?
+                .map(Self::decode_block_hash)
+                .transpose()?;
+            if indexed.as_ref() == Some(block_hash) {
+                self.by_tx_hash.remove(tx_id.1 .0.as_bytes())?;
+            }
+        }
+        Ok(())
     }
 
     pub fn last(&self) -> Option<SignedBlock> {
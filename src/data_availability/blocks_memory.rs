@@ -1,9 +1,10 @@
 #![allow(unused)]
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 
 use crate::{
     model::ConsensusProposalHash,
-    model::{BlockHeight, Hashed, SignedBlock},
+    model::{Block, BlockHeight, Hashed, SignedBlock, TxHash, TxInclusionProof},
+    utils::conf::Conf,
 };
 use anyhow::Result;
 use indexmap::IndexMap;
@@ -12,15 +13,23 @@ use tracing::{info, trace};
 #[derive(Debug)]
 pub struct Blocks {
     data: IndexMap<ConsensusProposalHash, SignedBlock>,
+    processed: HashMap<ConsensusProposalHash, Block>,
+    by_tx_hash: HashMap<TxHash, ConsensusProposalHash>,
 }
 
 impl Blocks {
     pub fn new(_: &Path) -> Result<Self> {
         Ok(Self {
             data: IndexMap::new(),
+            processed: HashMap::new(),
+            by_tx_hash: HashMap::new(),
         })
     }
 
+    pub fn open(config: &Conf) -> Result<Self> {
+        Self::new(&config.data_directory)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
@@ -35,6 +44,9 @@ impl Blocks {
             return Ok(());
         }
         trace!("📦 storing block {}", data.height());
+        for (_, tx_id, _) in data.iter_txs_with_id() {
+            self.by_tx_hash.insert(tx_id.1, block_hash.clone());
+        }
         self.data.insert(block_hash, data);
         Ok(())
     }
@@ -43,10 +55,51 @@ impl Blocks {
         Ok(self.data.get(block_hash).cloned())
     }
 
+    pub fn get_by_height(&self, height: BlockHeight) -> Result<Option<SignedBlock>> {
+        Ok(self
+            .data
+            .values()
+            .find(|block| block.height() == height)
+            .cloned())
+    }
+
     pub fn contains(&mut self, block_hash: &ConsensusProposalHash) -> bool {
         self.data.contains_key(block_hash)
     }
 
+    pub fn get_tx_inclusion_proof(&self, tx_hash: &TxHash) -> Result<Option<TxInclusionProof>> {
+        Ok(self
+            .by_tx_hash
+            .get(tx_hash)
+            .and_then(|block_hash| self.data.get(block_hash))
+            .and_then(|block| block.tx_inclusion_proof(tx_hash)))
+    }
+
+    pub fn put_processed(&mut self, block: Block) -> Result<()> {
+        self.processed.insert(block.hash.clone(), block);
+        Ok(())
+    }
+
+    pub fn has_processed(&self, block_hash: &ConsensusProposalHash) -> bool {
+        self.processed.contains_key(block_hash)
+    }
+
+    pub fn get_processed(&self, block_hash: &ConsensusProposalHash) -> Result<Option<Block>> {
+        Ok(self.processed.get(block_hash).cloned())
+    }
+
+    pub fn is_pruned(&self, _: BlockHeight) -> bool {
+        false
+    }
+
+    pub fn pruned_below(&self) -> Option<BlockHeight> {
+        None
+    }
+
+    pub fn apply_retention(&mut self) -> Result<()> {
+        Ok(())
+    }
+
     pub fn last(&self) -> Option<SignedBlock> {
         self.data.last().map(|(_, block)| block.clone())
     }
@@ -0,0 +1,495 @@
+//! Portable archive of a segment of the chain, to seed the DA store of a node offline.
+//!
+//! An archive is a magic number and a header, followed by chunks of consecutive borsh
+//! [SignedBlock]s. Each chunk carries the sha3 checksum of its blocks, and blocks must
+//! chain through their parent hash, so a corrupted or tampered archive is rejected.
+//!
+//! Importing an archive has the node state process its blocks as well, so that the node
+//! restarts with a node state at the last block of its DA store.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use borsh::{BorshDeserialize, BorshSerialize};
+use hyle_modules::{
+    modules::Module,
+    node_state::{module::NodeStateModule, NodeState, NodeStateStore},
+};
+use sha3::{Digest, Sha3_256};
+use tracing::{info, warn};
+
+use crate::{
+    model::{BlockHeight, ConsensusProposalHash, Hashed, SignedBlock},
+    utils::conf::Conf,
+};
+
+use super::Blocks;
+
+const MAGIC: &[u8; 8] = b"HYLECHN\0";
+const VERSION: u32 = 1;
+/// Blocks per chunk of exported archives
+const CHUNK_BLOCKS: u64 = 1_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ChainArchiveHeader {
+    pub version: u32,
+    pub chain_id: u128,
+    /// Hash of the block at height 0 of the chain the blocks belong to
+    pub genesis_hash: ConsensusProposalHash,
+    pub first: BlockHeight,
+    pub last: BlockHeight,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct ChainArchiveChunk {
+    checksum: [u8; 32],
+    /// Borsh encoded `Vec<SignedBlock>`
+    blocks: Vec<u8>,
+}
+
+fn checksum(data: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(data).into()
+}
+
+/// Writes the blocks from `first` to `last`, both included, as chunks of consecutive blocks
+pub fn write_archive(
+    writer: &mut impl Write,
+    chain_id: u128,
+    genesis_hash: ConsensusProposalHash,
+    first: BlockHeight,
+    last: BlockHeight,
+    mut get_block: impl FnMut(BlockHeight) -> Result<Option<SignedBlock>>,
+) -> Result<ChainArchiveHeader> {
+    if last < first {
+        bail!("Empty height range {} to {}", first, last);
+    }
+    let header = ChainArchiveHeader {
+        version: VERSION,
+        chain_id,
+        genesis_hash,
+        first,
+        last,
+    };
+    writer.write_all(MAGIC)?;
+    header.serialize(writer)?;
+
+    let mut chunk_first = first;
+    while chunk_first <= last {
+        let chunk_last = (chunk_first + (CHUNK_BLOCKS - 1)).min(last);
+        let blocks = (chunk_first.0..=chunk_last.0)
+            .map(|height| {
+                get_block(BlockHeight(height))?.with_context(|| format!("Block {height} not found"))
+            })
+            .collect::<Result<Vec<SignedBlock>>>()?;
+        let blocks = borsh::to_vec(&blocks)?;
+        ChainArchiveChunk {
+            checksum: checksum(&blocks),
+            blocks,
+        }
+        .serialize(writer)?;
+        chunk_first = chunk_last + 1;
+    }
+    writer.flush()?;
+
+    Ok(header)
+}
+
+/// Reads the chunks of an archive, checking them against the header and each other
+pub struct ChainArchiveReader<R> {
+    reader: R,
+    pub header: ChainArchiveHeader,
+    next: BlockHeight,
+    parent: Option<ConsensusProposalHash>,
+}
+
+impl<R: Read> ChainArchiveReader<R> {
+    /// Reads the header of an archive, which must be of the given chain
+    pub fn new(mut reader: R, chain_id: u128) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .context("Reading archive header")?;
+        if &magic != MAGIC {
+            bail!("Not a chain archive");
+        }
+        let header = ChainArchiveHeader::deserialize_reader(&mut reader)
+            .context("Reading archive header")?;
+        if header.version != VERSION {
+            bail!("Unsupported chain archive version {}", header.version);
+        }
+        if header.chain_id != chain_id {
+            bail!(
+                "Archive of chain {:#x}, but the node is on chain {:#x}",
+                header.chain_id,
+                chain_id
+            );
+        }
+        if header.last < header.first {
+            bail!(
+                "Archive of empty height range {} to {}",
+                header.first,
+                header.last
+            );
+        }
+        Ok(ChainArchiveReader {
+            reader,
+            next: header.first,
+            header,
+            parent: None,
+        })
+    }
+
+    /// Next chunk of blocks, or None once the last height of the header was read
+    pub fn next_chunk(&mut self) -> Result<Option<Vec<SignedBlock>>> {
+        if self.next > self.header.last {
+            return Ok(None);
+        }
+        let chunk = ChainArchiveChunk::deserialize_reader(&mut self.reader)
+            .with_context(|| format!("Reading chunk starting at block {}", self.next))?;
+        if checksum(&chunk.blocks) != chunk.checksum {
+            bail!("Checksum mismatch of chunk starting at block {}", self.next);
+        }
+        let blocks: Vec<SignedBlock> = borsh::from_slice(&chunk.blocks)?;
+        if blocks.is_empty() {
+            bail!("Empty chunk at block {}", self.next);
+        }
+        for block in &blocks {
+            self.check_next(block)?;
+        }
+        Ok(Some(blocks))
+    }
+
+    fn check_next(&mut self, block: &SignedBlock) -> Result<()> {
+        if block.height() != self.next {
+            bail!("Expected block {}, got block {}", self.next, block.height());
+        }
+        if block.height() > self.header.last {
+            bail!("Block {} is past the end of the archive", block.height());
+        }
+        let hash = block.hashed();
+        if block.height() == BlockHeight(0) && hash != self.header.genesis_hash {
+            bail!(
+                "Genesis block {} doesn't match the header, {} expected",
+                hash,
+                self.header.genesis_hash
+            );
+        }
+        if let Some(parent) = &self.parent {
+            if block.parent_hash() != parent {
+                bail!(
+                    "Block {} has parent {}, but follows block {}",
+                    block.height(),
+                    block.parent_hash(),
+                    parent
+                );
+            }
+        }
+        self.parent = Some(hash);
+        self.next = self.next + 1;
+        Ok(())
+    }
+}
+
+fn open_archive(config: &Conf, path: &Path) -> Result<ChainArchiveReader<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("Opening {}", path.display()))?;
+    ChainArchiveReader::new(BufReader::new(file), config.chain_id)
+}
+
+/// Exports blocks of the DA store, from `first` to `last` included, the last stored block by default
+pub fn export(
+    config: &Conf,
+    output: &Path,
+    first: BlockHeight,
+    last: Option<BlockHeight>,
+) -> Result<ChainArchiveHeader> {
+    let blocks = Blocks::open(config)?;
+    let last = match last {
+        Some(last) => last,
+        None => blocks.last().context("The DA store is empty")?.height(),
+    };
+    let genesis_hash = blocks
+        .get_by_height(BlockHeight(0))?
+        .context("The genesis block isn't in the DA store anymore")?
+        .hashed();
+
+    let file = File::create(output).with_context(|| format!("Creating {}", output.display()))?;
+    let mut writer = BufWriter::new(file);
+    let header = write_archive(
+        &mut writer,
+        config.chain_id,
+        genesis_hash,
+        first,
+        last,
+        |height| blocks.get_by_height(height),
+    )?;
+    writer.into_inner()?.sync_all()?;
+
+    info!(
+        "📦 Exported blocks {} to {} to {}",
+        header.first,
+        header.last,
+        output.display()
+    );
+    Ok(header)
+}
+
+/// Reads the whole archive, checking its chunks and that its blocks chain.
+/// The genesis hash of the header can only be checked if the archive starts at genesis.
+pub fn verify(config: &Conf, input: &Path) -> Result<ChainArchiveHeader> {
+    let mut reader = open_archive(config, input)?;
+    while reader.next_chunk()?.is_some() {}
+
+    info!(
+        "✅ Archive holds valid blocks {} to {}",
+        reader.header.first, reader.header.last
+    );
+    if reader.header.first == BlockHeight(0) {
+        info!(
+            "✅ Blocks start from genesis {}",
+            reader.header.genesis_hash
+        );
+    } else {
+        warn!(
+            "Archive starts at block {}, its genesis {} could not be checked",
+            reader.header.first, reader.header.genesis_hash
+        );
+    }
+    Ok(reader.header)
+}
+
+/// Stores the blocks of the archive in the DA store, and has the node state process them.
+/// The archive must start at genesis if the store is empty, in which case the node has no node
+/// state yet. Otherwise it must start right after the last block of the store, which the node
+/// state must be at.
+pub fn import(config: &Conf, input: &Path) -> Result<ChainArchiveHeader> {
+    let mut blocks = Blocks::open(config)?;
+    let mut reader = open_archive(config, input)?;
+    let node_state_file = config.data_directory.join("node_state.bin");
+    let mut node_state = NodeState::create(config.id.clone(), "chain_archive");
+    if node_state_file.exists() {
+        node_state.store = NodeStateModule::load_from_disk::<NodeStateStore>(&node_state_file)
+            .with_context(|| format!("Reading the node state {}", node_state_file.display()))?;
+    }
+    node_state.set_contract_upgrade_delay(BlockHeight(config.contract_upgrade_delay));
+    node_state.set_state_read_window(BlockHeight(config.state_read_window));
+
+    if let Some(genesis) = blocks.get_by_height(BlockHeight(0))? {
+        if genesis.hashed() != reader.header.genesis_hash {
+            bail!(
+                "Archive of chain {}, but the DA store holds chain {}",
+                reader.header.genesis_hash,
+                genesis.hashed()
+            );
+        }
+    }
+    match blocks.last() {
+        Some(last) => {
+            if reader.header.first != last.height() + 1 {
+                bail!(
+                    "Archive starts at block {}, but the DA store ends at block {}",
+                    reader.header.first,
+                    last.height()
+                );
+            }
+            if !node_state_file.exists() || node_state.current_height != last.height() {
+                bail!(
+                    "The node state must be at block {}, the last one of the DA store",
+                    last.height()
+                );
+            }
+            // The first block of the archive is checked to follow our last one
+            reader.parent = Some(last.hashed());
+        }
+        None if reader.header.first != BlockHeight(0) => {
+            bail!(
+                "The DA store is empty, the archive must start at genesis, not at block {}",
+                reader.header.first
+            );
+        }
+        None if node_state_file.exists() => {
+            bail!(
+                "The DA store is empty, but the node state is at block {}",
+                node_state.current_height
+            );
+        }
+        None => {}
+    }
+
+    // The node state processes a whole chunk before any of its blocks is stored, so that a
+    // failing block leaves the DA store and the saved node state at the same height
+    while let Some(chunk) = reader.next_chunk()? {
+        for block in &chunk {
+            node_state
+                .handle_signed_block(block)
+                .with_context(|| format!("Processing block {}", block.height()))?;
+        }
+        for block in chunk {
+            blocks.put(block)?;
+            blocks.apply_retention()?;
+        }
+        blocks.persist()?;
+        NodeStateModule::save_on_disk(&node_state_file, &node_state.store)?;
+    }
+
+    info!(
+        "📦 Imported blocks {} to {} from {}",
+        reader.header.first,
+        reader.header.last,
+        input.display()
+    );
+    Ok(reader.header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::HYLE_TESTNET_CHAIN_ID;
+
+    fn chain(len: u64) -> Vec<SignedBlock> {
+        let mut blocks = vec![];
+        let mut block = SignedBlock::default();
+        for slot in 0..len {
+            block.consensus_proposal.parent_hash = block.hashed();
+            block.consensus_proposal.slot = slot;
+            blocks.push(block.clone());
+        }
+        blocks
+    }
+
+    fn archive(blocks: &[SignedBlock]) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_archive(
+            &mut bytes,
+            HYLE_TESTNET_CHAIN_ID,
+            blocks[0].hashed(),
+            blocks[0].height(),
+            blocks[blocks.len() - 1].height(),
+            |height| Ok(blocks.iter().find(|b| b.height() == height).cloned()),
+        )
+        .unwrap();
+        bytes
+    }
+
+    fn read_all(bytes: &[u8]) -> Result<Vec<SignedBlock>> {
+        let mut reader = ChainArchiveReader::new(bytes, HYLE_TESTNET_CHAIN_ID)?;
+        let mut blocks = vec![];
+        while let Some(chunk) = reader.next_chunk()? {
+            blocks.extend(chunk);
+        }
+        Ok(blocks)
+    }
+
+    #[test]
+    fn test_chain_archive_roundtrip() {
+        let blocks = chain(CHUNK_BLOCKS + 10);
+        let bytes = archive(&blocks);
+        assert_eq!(read_all(&bytes).unwrap(), blocks);
+
+        let reader = ChainArchiveReader::new(bytes.as_slice(), HYLE_TESTNET_CHAIN_ID).unwrap();
+        assert_eq!(reader.header.genesis_hash, blocks[0].hashed());
+        assert_eq!(reader.header.last, BlockHeight(CHUNK_BLOCKS + 9));
+        // Archives of other chains are refused
+        assert!(ChainArchiveReader::new(bytes.as_slice(), 1).is_err());
+
+        // Missing blocks can't be exported
+        let mut missing = vec![];
+        assert!(write_archive(
+            &mut missing,
+            HYLE_TESTNET_CHAIN_ID,
+            blocks[0].hashed(),
+            BlockHeight(0),
+            BlockHeight(CHUNK_BLOCKS + 10),
+            |height| Ok(blocks.get(height.0 as usize).cloned()),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_chain_archive_rejects_corruption() {
+        let blocks = chain(20);
+        let mut bytes = archive(&blocks);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        assert!(read_all(&bytes).is_err());
+
+        // Truncated
+        let bytes = archive(&blocks);
+        assert!(read_all(&bytes[..bytes.len() - 10]).is_err());
+
+        // Not chaining through parent hashes
+        let mut forked = blocks.clone();
+        forked[10].consensus_proposal.parent_hash = ConsensusProposalHash("fork".into());
+        assert!(read_all(&archive(&forked)).is_err());
+
+        // Not starting from the genesis in the header
+        let mut bytes = vec![];
+        write_archive(
+            &mut bytes,
+            HYLE_TESTNET_CHAIN_ID,
+            ConsensusProposalHash("other".into()),
+            BlockHeight(0),
+            BlockHeight(19),
+            |height| Ok(blocks.get(height.0 as usize).cloned()),
+        )
+        .unwrap();
+        assert!(read_all(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_chain_archive_import() -> Result<()> {
+        let blocks = chain(30);
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("chain.bin");
+        let mut config = Conf::new(
+            vec![],
+            Some(dir.path().join("node").display().to_string()),
+            None,
+        )?;
+
+        std::fs::write(&path, archive(&blocks[10..]))?;
+        assert!(import(&config, &path).is_err(), "must start at genesis");
+
+        std::fs::write(&path, archive(&blocks[..20]))?;
+        import(&config, &path)?;
+
+        // Appended to the existing chain, then exported again
+        let mut bytes = vec![];
+        write_archive(
+            &mut bytes,
+            HYLE_TESTNET_CHAIN_ID,
+            blocks[0].hashed(),
+            BlockHeight(20),
+            BlockHeight(29),
+            |height| Ok(blocks.get(height.0 as usize).cloned()),
+        )?;
+        std::fs::write(&path, bytes)?;
+        assert_eq!(verify(&config, &path)?.first, BlockHeight(20));
+
+        // The node state must have processed the blocks of the DA store
+        let node_state_file = config.data_directory.join("node_state.bin");
+        let node_state = std::fs::read(&node_state_file)?;
+        std::fs::remove_file(&node_state_file)?;
+        assert!(import(&config, &path).is_err(), "node state missing");
+        std::fs::write(&node_state_file, node_state)?;
+
+        import(&config, &path)?;
+        assert!(import(&config, &path).is_err(), "already imported");
+        let store: NodeStateStore = borsh::from_slice(&std::fs::read(&node_state_file)?)?;
+        assert_eq!(store.current_height, BlockHeight(29));
+
+        export(&config, &path, BlockHeight(0), None)?;
+        assert_eq!(verify(&config, &path)?.last, BlockHeight(29));
+
+        config.data_directory = dir.path().join("other");
+        import(&config, &path)?;
+        assert_eq!(
+            Blocks::open(&config)?.last().map(|b| b.hashed()),
+            Some(blocks[29].hashed())
+        );
+        Ok(())
+    }
+}
@@ -1,22 +1,43 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
 
+use anyhow::Context;
 use hyle_modules::{bus::SharedMessageBus, modules::Module};
 
 use crate::model::SharedRunContext;
 
-use super::{blocks_fjall::Blocks, d_a_bus_client::DABusClient, DataAvailability};
+use super::{api, blocks_fjall::Blocks, d_a_bus_client::DABusClient, DataAvailability};
 
 impl Module for DataAvailability {
     type Context = SharedRunContext;
 
     async fn build(bus: SharedMessageBus, ctx: Self::Context) -> anyhow::Result<Self> {
+        let api = api::api(&bus, &ctx).await;
+        if let Ok(mut guard) = ctx.api.router.lock() {
+            if let Some(router) = guard.take() {
+                guard.replace(router.nest("/v1/da", api));
+            }
+        }
         let bus = DABusClient::new_from_bus(bus.new_handle()).await;
+        let blocks = Blocks::open(&ctx.config)?;
+
+        // The snapshot only matters for a fresh node, otherwise our local chain is already anchored.
+        let bootstrap_snapshot = if blocks.is_empty() {
+            ctx.config
+                .node_state_snapshots
+                .bootstrap_header()
+                .context("Reading bootstrap snapshot")?
+        } else {
+            None
+        };
 
         Ok(DataAvailability {
             config: ctx.config.clone(),
             bus,
-            blocks: Blocks::new(&ctx.config.data_directory.join("data_availability.db"))?,
+            blocks,
+            bootstrap_snapshot,
             buffered_signed_blocks: BTreeSet::new(),
+            subscriptions: HashMap::new(),
+            processed_height: None,
             need_catchup: false,
             catchup_task: None,
             catchup_height: None,
@@ -0,0 +1,88 @@
+//! How long blocks are kept in the fjall store, and what happens to older ones.
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::SignedBlock;
+
+const MS_PER_DAY: u128 = 24 * 60 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DaRetentionMode {
+    /// Every block stays in the fjall store
+    #[default]
+    KeepAll,
+    /// Older blocks are moved to archive segment files, and still served to peers
+    Archive,
+    /// Older blocks are deleted, peers asking for them get an error
+    Prune,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DaRetentionConf {
+    pub mode: DaRetentionMode,
+    /// Blocks this close to the last one are kept in the fjall store
+    pub keep_blocks: u64,
+    /// Blocks less than this many days older than the last one are kept in the fjall store too
+    pub keep_days: u64,
+    /// Blocks are archived or pruned by batches of this many consecutive heights
+    pub segment_blocks: u64,
+}
+
+impl Default for DaRetentionConf {
+    fn default() -> Self {
+        Self {
+            mode: DaRetentionMode::KeepAll,
+            keep_blocks: 100_000,
+            keep_days: 0,
+            segment_blocks: 1_000,
+        }
+    }
+}
+
+impl DaRetentionConf {
+    /// Whether the block can leave the fjall store, now that `last` was stored
+    pub fn is_expired(&self, block: &SignedBlock, last: &SignedBlock) -> bool {
+        if self.mode == DaRetentionMode::KeepAll {
+            return false;
+        }
+        let kept_height = block.height().0 + self.keep_blocks >= last.height().0;
+        let kept_time = block.consensus_proposal.timestamp.0 + self.keep_days as u128 * MS_PER_DAY
+            >= last.consensus_proposal.timestamp.0;
+        !kept_height && (self.keep_days == 0 || !kept_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::utils::TimestampMs;
+
+    fn block(height: u64, day: u128) -> SignedBlock {
+        let mut block = SignedBlock::default();
+        block.consensus_proposal.slot = height;
+        block.consensus_proposal.timestamp = TimestampMs(day * MS_PER_DAY);
+        block
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let mut conf = DaRetentionConf {
+            mode: DaRetentionMode::Prune,
+            keep_blocks: 10,
+            keep_days: 0,
+            segment_blocks: 5,
+        };
+        let last = block(100, 30);
+        assert!(conf.is_expired(&block(89, 30), &last));
+        assert!(!conf.is_expired(&block(90, 0), &last));
+
+        // Blocks are kept if they are either recent enough or close enough to the last one
+        conf.keep_days = 7;
+        assert!(!conf.is_expired(&block(50, 23), &last));
+        assert!(conf.is_expired(&block(50, 22), &last));
+        assert!(!conf.is_expired(&block(95, 1), &last));
+
+        conf.mode = DaRetentionMode::KeepAll;
+        assert!(!conf.is_expired(&block(0, 0), &last));
+    }
+}
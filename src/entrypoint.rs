@@ -7,7 +7,7 @@ use crate::{
     genesis::Genesis,
     indexer::Indexer,
     mempool::Mempool,
-    model::{api::NodeInfo, SharedRunContext},
+    model::{api::NodeInfo, LaneId, SharedRunContext},
     node_state::module::NodeStateModule,
     p2p::P2P,
     rest::{ApiDoc, RestApi, RestApiRunContext},
@@ -29,6 +29,7 @@ use hyle_modules::{
         contract_state_indexer::{ContractStateIndexer, ContractStateIndexerCtx},
         da_listener::DAListenerConf,
         signed_da_listener::SignedDAListener,
+        tx_status::{TxStatusTracker, TxStatusTrackerCtx},
         websocket::WebSocketModule,
         BuildApiContextInner,
     },
@@ -314,6 +315,9 @@ async fn common_main(
                 node_id: config.id.clone(),
                 data_directory: config.data_directory.clone(),
                 api: build_api_ctx.clone(),
+                snapshots: config.node_state_snapshots.clone(),
+                contract_upgrade_delay: config.contract_upgrade_delay,
+                state_read_window: config.state_read_window,
             })
             .await?;
 
@@ -323,6 +327,13 @@ async fn common_main(
 
         handler.build_module::<Mempool>(ctx.clone()).await?;
 
+        handler
+            .build_module::<TxStatusTracker>(TxStatusTrackerCtx {
+                api: build_api_ctx.clone(),
+                lane_id: LaneId(ctx.crypto.validator_pubkey().clone()),
+            })
+            .await?;
+
         handler.build_module::<Genesis>(ctx.clone()).await?;
 
         if config.p2p.mode == conf::P2pMode::FullValidator {
@@ -341,8 +352,16 @@ async fn common_main(
             .build_module::<SignedDAListener>(DAListenerConf {
                 data_directory: config.data_directory.clone(),
                 da_read_from: config.da_read_from.clone(),
+                da_fallback_read_from: config.da_fallback_read_from.clone(),
+                cross_check: config.da_cross_check,
+                trusted_validators: config
+                    .da_trusted_validators
+                    .iter()
+                    .map(|(validator, stake)| (validator.clone(), *stake as u128))
+                    .collect(),
                 start_block: None,
                 timeout_client_secs: config.da_timeout_client_secs,
+                subscription: None,
             })
             .await?;
     }
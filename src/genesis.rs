@@ -35,6 +35,7 @@ use utils::TimestampMs;
 use verifiers::NativeVerifiers;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+#[allow(clippy::large_enum_variant, reason = "sent once, when the node starts")]
 pub enum GenesisEvent {
     NoGenesis,
     GenesisBlock(SignedBlock),
@@ -528,6 +529,9 @@ impl Genesis {
         map.insert("blst".into(), NativeVerifiers::Blst.into());
         map.insert("sha3_256".into(), NativeVerifiers::Sha3_256.into());
         map.insert("secp256k1".into(), NativeVerifiers::Secp256k1.into());
+        map.insert("ed25519".into(), NativeVerifiers::Ed25519.into());
+        map.insert("secp256r1".into(), NativeVerifiers::Secp256r1.into());
+        map.insert("keccak256".into(), NativeVerifiers::Keccak256.into());
         map.insert("hyllar".into(), ProgramId(hyllar_program_id.clone()));
         map.insert("oranj".into(), ProgramId(smt_token_program_id.clone()));
         map.insert("oxygen".into(), ProgramId(smt_token_program_id.clone()));
@@ -587,6 +591,39 @@ impl Genesis {
         )
         .expect("register secp256k1");
 
+        register_hyle_contract(
+            &mut register_tx,
+            "ed25519".into(),
+            "ed25519".into(),
+            NativeVerifiers::Ed25519.into(),
+            StateCommitment::default(),
+            Some(TimeoutWindow::NoTimeout),
+            None,
+        )
+        .expect("register ed25519");
+
+        register_hyle_contract(
+            &mut register_tx,
+            "secp256r1".into(),
+            "secp256r1".into(),
+            NativeVerifiers::Secp256r1.into(),
+            StateCommitment::default(),
+            Some(TimeoutWindow::NoTimeout),
+            None,
+        )
+        .expect("register secp256r1");
+
+        register_hyle_contract(
+            &mut register_tx,
+            "keccak256".into(),
+            "keccak256".into(),
+            NativeVerifiers::Keccak256.into(),
+            StateCommitment::default(),
+            Some(TimeoutWindow::NoTimeout),
+            None,
+        )
+        .expect("register keccak256");
+
         register_hyle_contract(
             &mut register_tx,
             "staking".into(),
@@ -665,7 +702,7 @@ impl Genesis {
             .expect("must have round leader")
             .clone();
 
-        SignedBlock {
+        let mut signed_block = SignedBlock {
             data_proposals: vec![(LaneId(round_leader.clone()), vec![dp.clone()])],
             certificate: AggregateSignature {
                 signature: Signature("fake".into()),
@@ -673,6 +710,7 @@ impl Genesis {
             },
             consensus_proposal: ConsensusProposal {
                 slot: 0,
+                epoch: 0,
                 // TODO: genesis block should have a consistent, up-to-date timestamp
                 timestamp: TimestampMs((self.config.consensus.genesis_timestamp * 1000) as u128),
                 // TODO: We aren't actually storing the data proposal above, so we cannot store it here,
@@ -698,10 +736,19 @@ impl Genesis {
                         }
                         .into()
                     })
+                    // All nodes follow the epochs of the genesis block, whatever their config
+                    .chain(std::iter::once(ConsensusStakingAction::EpochLength {
+                        slots: self.config.genesis.epoch_length,
+                    }))
                     .collect(),
                 parent_hash: ConsensusProposalHash("genesis".into()),
+                tx_root: EMPTY_MERKLE_ROOT,
+                parent_commit_qc: AggregateSignature::default(),
+                parent_contract_registry_root: EMPTY_MERKLE_ROOT,
             },
-        }
+        };
+        signed_block.consensus_proposal.tx_root = signed_block.tx_merkle_root();
+        signed_block
     }
 }
 
@@ -713,6 +760,7 @@ mod tests {
     use crate::bus::{BusClientReceiver, SharedMessageBus};
     use crate::utils::conf::Conf;
     use hyle_crypto::BlstCrypto;
+    use hyle_net::tcp::P2P_PROTOCOL_VERSION;
     use std::sync::Arc;
 
     bus_client! {
@@ -777,7 +825,7 @@ mod tests {
         assert_matches!(rec, GenesisEvent::GenesisBlock(..));
         if let GenesisEvent::GenesisBlock(signed_block) = rec {
             assert!(signed_block.has_txs());
-            assert_eq!(signed_block.consensus_proposal.staking_actions.len(), 1);
+            assert_eq!(signed_block.consensus_proposal.staking_actions.len(), 2);
         }
     }
 
@@ -799,6 +847,7 @@ mod tests {
             name: "node-2".into(),
             pubkey: ValidatorPublicKey("aaa".into()),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
             height: BlockHeight(0),
         })
         .expect("send");
@@ -812,7 +861,7 @@ mod tests {
         assert_matches!(rec, GenesisEvent::GenesisBlock(..));
         if let GenesisEvent::GenesisBlock(signed_block) = rec {
             assert!(signed_block.has_txs());
-            assert_eq!(signed_block.consensus_proposal.staking_actions.len(), 2);
+            assert_eq!(signed_block.consensus_proposal.staking_actions.len(), 3);
         }
     }
 
@@ -837,6 +886,7 @@ mod tests {
             pubkey: node_1_pubkey.clone(),
             height: BlockHeight(0),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
         })
         .expect("send");
 
@@ -849,7 +899,7 @@ mod tests {
         assert_matches!(rec, GenesisEvent::GenesisBlock(..));
         if let GenesisEvent::GenesisBlock(signed_block) = rec {
             assert!(signed_block.has_txs());
-            assert_eq!(signed_block.consensus_proposal.staking_actions.len(), 2);
+            assert_eq!(signed_block.consensus_proposal.staking_actions.len(), 3);
         }
     }
 
@@ -875,6 +925,7 @@ mod tests {
             pubkey: BlstCrypto::new(name).unwrap().validator_pubkey().clone(),
             height: BlockHeight(height),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
         };
         let rec1 = {
             let (mut genesis, mut bus) = new(config.clone()).await;
@@ -921,6 +972,7 @@ mod tests {
             pubkey: BlstCrypto::new(name).unwrap().validator_pubkey().clone(),
             height: BlockHeight(height),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
         };
 
         let rec1 = {
@@ -969,6 +1021,7 @@ mod tests {
                 .clone(),
             height: BlockHeight(1),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
         })
         .expect("send");
 
@@ -1009,6 +1062,7 @@ mod tests {
                 .clone(),
             height: BlockHeight(0),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
         })
         .expect("send");
 
@@ -1021,6 +1075,7 @@ mod tests {
                 .clone(),
             height: BlockHeight(0),
             da_address: "".into(),
+            version: P2P_PROTOCOL_VERSION,
         })
         .expect("send");
 
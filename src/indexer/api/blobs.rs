@@ -1,4 +1,4 @@
-use super::{IndexerApiState, TxHashDb};
+use super::{with_db, IndexerApiState, TxHashDb};
 use api::APIBlob;
 use axum::{
     extract::{Path, State},
@@ -17,7 +17,7 @@ pub struct BlobDb {
     pub identity: String,  // Identity of the blob
     pub contract_name: String, // Contract name associated with the blob
     pub data: Vec<u8>,     // Actual blob data
-    pub proof_outputs: Vec<serde_json::Value>, // outputs of proofs
+    pub proof_outputs: sqlx::types::Json<Vec<serde_json::Value>>, // outputs of proofs
     pub verified: bool,    // Verification status
 }
 
@@ -29,7 +29,7 @@ impl From<BlobDb> for APIBlob {
             identity: value.identity,
             contract_name: value.contract_name,
             data: value.data,
-            proof_outputs: value.proof_outputs,
+            proof_outputs: value.proof_outputs.0,
             verified: value.verified,
         }
     }
@@ -50,9 +50,8 @@ pub async fn get_blobs_by_tx_hash(
     Path(tx_hash): Path<String>,
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APIBlob>>, StatusCode> {
-    let blobs = log_error!(
-        sqlx::query_as::<_, BlobDb>(
-            r#"
+    let query = format!(
+        r#"
 WITH latest_height_for_this_tx_hash AS (
   SELECT MAX(block_height) as max_height
   FROM transactions
@@ -62,7 +61,7 @@ WITH latest_height_for_this_tx_hash AS (
 
 SELECT 
       blobs.*,
-      array_remove(ARRAY_AGG(blob_proof_outputs.hyle_output), NULL) AS proof_outputs
+      {proof_outputs} AS proof_outputs
 FROM blobs
 LEFT JOIN
      blob_proof_outputs
@@ -80,10 +79,15 @@ GROUP BY
       blobs.blob_index,
       blobs.identity
 "#,
-        )
-        .bind(tx_hash)
-        .fetch_all(&state.db)
-        .await
+        proof_outputs = state.db.json_doc_agg("blob_proof_outputs.hyle_output")
+    );
+    let blobs = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, BlobDb>(&query)
+                .bind(tx_hash)
+                .fetch_all(pool)
+                .await
+        })
         .map(|db| db.into_iter().map(Into::<APIBlob>::into).collect()),
         "Failed to fetch blobs by tx hash"
     )
@@ -108,12 +112,11 @@ pub async fn get_blob(
     Path((tx_hash, blob_index)): Path<(String, i32)>,
     State(state): State<IndexerApiState>,
 ) -> Result<Json<APIBlob>, StatusCode> {
-    let blob = log_error!(
-        sqlx::query_as::<_, BlobDb>(
-            r#"
+    let query = format!(
+        r#"
 SELECT 
   blobs.*, 
-  array_remove(ARRAY_AGG(blob_proof_outputs.hyle_output), NULL) AS proof_outputs
+  {proof_outputs} AS proof_outputs
 FROM blobs
 LEFT JOIN blob_proof_outputs 
   ON blobs.parent_dp_hash = blob_proof_outputs.blob_parent_dp_hash
@@ -132,11 +135,16 @@ GROUP BY
 ORDER BY transactions.block_height DESC
 LIMIT 1;
 "#,
-        )
-        .bind(tx_hash)
-        .bind(blob_index)
-        .fetch_optional(&state.db)
-        .await
+        proof_outputs = state.db.json_doc_agg("blob_proof_outputs.hyle_output")
+    );
+    let blob = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, BlobDb>(&query)
+                .bind(tx_hash)
+                .bind(blob_index)
+                .fetch_optional(pool)
+                .await
+        })
         .map(|db| db.map(Into::<APIBlob>::into)),
         "Failed to fetch blob"
     )
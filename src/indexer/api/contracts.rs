@@ -1,5 +1,5 @@
-use super::{IndexerApiState, TxHashDb};
-use api::{APIContract, APIContractState};
+use super::{with_db, IndexerApiState, TxHashDb};
+use api::{APIContract, APIContractCodeVersion, APIContractState};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -69,8 +69,9 @@ pub async fn list_contracts(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APIContract>>, StatusCode> {
     let contract = log_error!(
-        sqlx::query_as::<_, ContractDb>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, ContractDb>(
+                r#"
 WITH sequenced_tx AS (
     SELECT parent_dp_hash, tx_hash, block_hash
     FROM transactions
@@ -108,10 +109,11 @@ FROM contracts c
 LEFT JOIN contract_stats cs ON c.contract_name = cs.contract_name
 LEFT JOIN earliest_unsettled eu ON c.contract_name = eu.contract_name
 ORDER BY c.contract_name;
-"#
-        )
-        .fetch_all(&state.db)
-        .await
+"#,
+            )
+            .fetch_all(pool)
+            .await
+        })
         .map(|db| db.into_iter().map(Into::<APIContract>::into).collect()),
         "Failed to fetch contracts"
     )
@@ -136,12 +138,13 @@ pub async fn get_contract(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<APIContract>, StatusCode> {
     let contract = log_error!(
-        sqlx::query_as::<_, ContractDb>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, ContractDb>(
+                r#"
         SELECT
           c.*,
-          COUNT(t.*)                             			          AS total_tx,
-          COUNT(t.*)
+          COUNT(t.tx_hash)                             			          AS total_tx,
+          COUNT(t.tx_hash)
             FILTER (WHERE t.transaction_status = 'sequenced')   AS unsettled_tx,
           (
             SELECT min(bl.height)
@@ -163,11 +166,12 @@ pub async fn get_contract(
          AND t.tx_hash       = tx_c.tx_hash
         WHERE c.contract_name = $1
         GROUP BY c.contract_name;
-        "#
-        )
-        .bind(contract_name)
-        .fetch_optional(&state.db)
-        .await
+        "#,
+            )
+            .bind(contract_name)
+            .fetch_optional(pool)
+            .await
+        })
         .map(|db| db.map(Into::<APIContract>::into)),
         "Failed to fetch contract"
     )
@@ -179,6 +183,65 @@ pub async fn get_contract(
     }
 }
 
+#[derive(sqlx::FromRow, Debug)]
+pub struct ContractCodeVersionDb {
+    // Struct for the contract_history table
+    pub contract_name: String,
+    pub verifier: String,
+    pub program_id: Vec<u8>,
+    pub effective_height: i64,
+}
+
+impl From<ContractCodeVersionDb> for APIContractCodeVersion {
+    fn from(value: ContractCodeVersionDb) -> Self {
+        APIContractCodeVersion {
+            contract_name: value.contract_name,
+            verifier: value.verifier,
+            program_id: value.program_id,
+            effective_height: BlockHeight(value.effective_height as u64),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    tag = "Indexer",
+    params(
+        ("contract_name" = String, Path, description = "Contract name"),
+    ),
+    path = "/contract/{contract_name}/history",
+    responses(
+        (status = OK, body = [APIContractCodeVersion])
+    )
+)]
+pub async fn get_contract_history(
+    Path(contract_name): Path<String>,
+    State(state): State<IndexerApiState>,
+) -> Result<Json<Vec<APIContractCodeVersion>>, StatusCode> {
+    let history = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, ContractCodeVersionDb>(
+                r#"
+        SELECT contract_name, verifier, program_id, effective_height
+        FROM contract_history
+        WHERE contract_name = $1
+        ORDER BY effective_height ASC"#,
+            )
+            .bind(contract_name)
+            .fetch_all(pool)
+            .await
+        })
+        .map(|db| db
+            .into_iter()
+            .map(Into::<APIContractCodeVersion>::into)
+            .collect()),
+        "Failed to fetch contract history"
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(history))
+}
+
 #[utoipa::path(
     get,
     tag = "Indexer",
@@ -196,17 +259,19 @@ pub async fn get_contract_state_by_height(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<APIContractState>, StatusCode> {
     let contract = log_error!(
-        sqlx::query_as::<_, ContractStateDb>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, ContractStateDb>(
+                r#"
         SELECT cs.*
         FROM contract_state cs
         JOIN blocks b ON cs.block_hash = b.hash
         WHERE contract_name = $1 AND height = $2"#,
-        )
-        .bind(contract_name)
-        .bind(height)
-        .fetch_optional(&state.db)
-        .await
+            )
+            .bind(contract_name)
+            .bind(height)
+            .fetch_optional(pool)
+            .await
+        })
         .map(|db| db.map(Into::<APIContractState>::into)),
         "Failed to fetch contract state by height"
     )
@@ -233,20 +298,26 @@ impl From<TimeoutWindowDb> for TimeoutWindow {
     }
 }
 
-impl sqlx::Type<sqlx::Postgres> for TimeoutWindowDb {
-    fn type_info() -> sqlx::postgres::PgTypeInfo {
-        <i64 as sqlx::Type<sqlx::Postgres>>::type_info()
+impl<DB: sqlx::Database> sqlx::Type<DB> for TimeoutWindowDb
+where
+    i64: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <i64 as sqlx::Type<DB>>::type_info()
     }
 
-    fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
-        <i64 as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <i64 as sqlx::Type<DB>>::compatible(ty)
     }
 }
 
-impl sqlx::Encode<'_, sqlx::Postgres> for TimeoutWindowDb {
+impl<'q, DB: sqlx::Database> sqlx::Encode<'q, DB> for TimeoutWindowDb
+where
+    i64: sqlx::Encode<'q, DB>,
+{
     fn encode_by_ref(
         &self,
-        buf: &mut sqlx::postgres::PgArgumentBuffer,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
     ) -> Result<sqlx::encode::IsNull, Box<dyn std::error::Error + Send + Sync + 'static>> {
         match &self.0 {
             TimeoutWindow::NoTimeout => Ok(sqlx::encode::IsNull::Yes),
@@ -255,17 +326,20 @@ impl sqlx::Encode<'_, sqlx::Postgres> for TimeoutWindowDb {
                     .0
                     .try_into()
                     .map_err(|_| format!("BlockHeight value {} overflows i64", height.0))?;
-                <i64 as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&val, buf)
+                <i64 as sqlx::Encode<DB>>::encode_by_ref(&val, buf)
             }
         }
     }
 }
 
-impl<'r> sqlx::Decode<'r, sqlx::Postgres> for TimeoutWindowDb {
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for TimeoutWindowDb
+where
+    Option<i64>: sqlx::Decode<'r, DB>,
+{
     fn decode(
-        value: sqlx::postgres::PgValueRef<'r>,
+        value: <DB as sqlx::Database>::ValueRef<'r>,
     ) -> Result<TimeoutWindowDb, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let opt_val: Option<i64> = sqlx::Decode::<sqlx::Postgres>::decode(value)?;
+        let opt_val: Option<i64> = sqlx::Decode::<DB>::decode(value)?;
         let tw = match opt_val {
             None => TimeoutWindow::NoTimeout,
             Some(val) => {
@@ -1,4 +1,4 @@
-use super::IndexerApiState;
+use super::{with_db, IndexerApiState};
 use utoipa::OpenApi;
 
 #[derive(Debug, serde::Deserialize)]
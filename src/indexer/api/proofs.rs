@@ -1,4 +1,4 @@
-use super::{BlockPagination, IndexerApiState, TransactionDb};
+use super::{with_db, BlockPagination, IndexerApiState, TransactionDb};
 use api::APITransaction;
 use axum::{
     extract::{Path, Query, State},
@@ -25,34 +25,36 @@ pub async fn get_proofs(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APITransaction>>, StatusCode> {
     let transactions = log_error!(
-        match pagination.start_block {
-            Some(start_block) => sqlx::query_as::<_, TransactionDb>(
-                r#"
+        with_db!(&state.db, pool => {
+            match pagination.start_block {
+                Some(start_block) => sqlx::query_as::<_, TransactionDb>(
+                    r#"
             SELECT t.*, b.timestamp
             FROM transactions t
             LEFT JOIN blocks b ON t.block_hash = b.hash
             WHERE b.height <= $1 and b.height > $2 AND t.transaction_type = 'proof_transaction'
-            ORDER BY b.height DESC, t.index DESC
+            ORDER BY b.height DESC, t."index" DESC
             LIMIT $3
             "#,
-            )
-            .bind(start_block)
-            .bind(start_block - pagination.nb_results.unwrap_or(10)) // Fine if this goes negative
-            .bind(pagination.nb_results.unwrap_or(10)),
-            None => sqlx::query_as::<_, TransactionDb>(
-                r#"
+                )
+                .bind(start_block)
+                .bind(start_block - pagination.nb_results.unwrap_or(10)) // Fine if this goes negative
+                .bind(pagination.nb_results.unwrap_or(10)),
+                None => sqlx::query_as::<_, TransactionDb>(
+                    r#"
             SELECT t.*, b.timestamp
             FROM transactions t
             LEFT JOIN blocks b ON t.block_hash = b.hash
             WHERE t.transaction_type = 'proof_transaction'
-            ORDER BY b.height DESC, t.index DESC
+            ORDER BY b.height DESC, t."index" DESC
             LIMIT $1
             "#,
-            )
-            .bind(pagination.nb_results.unwrap_or(10)),
-        }
-        .fetch_all(&state.db)
-        .await
+                )
+                .bind(pagination.nb_results.unwrap_or(10)),
+            }
+            .fetch_all(pool)
+            .await
+        })
         .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect()),
         "Failed to fetch proofs"
     )
@@ -77,18 +79,20 @@ pub async fn get_proofs_by_height(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APITransaction>>, StatusCode> {
     let transactions = log_error!(
-        sqlx::query_as::<_, TransactionDb>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, TransactionDb>(
+                r#"
         SELECT t.*, b.timestamp
         FROM transactions t
         JOIN blocks b ON t.block_hash = b.hash
         WHERE b.height = $1 AND t.transaction_type = 'proof_transaction'
-        ORDER BY t.index DESC
+        ORDER BY t."index" DESC
         "#,
-        )
-        .bind(height)
-        .fetch_all(&state.db)
-        .await
+            )
+            .bind(height)
+            .fetch_all(pool)
+            .await
+        })
         .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect()),
         "Failed to fetch proofs by height"
     )
@@ -112,23 +116,22 @@ pub async fn get_proof_with_hash(
     Path(tx_hash): Path<String>,
     State(state): State<IndexerApiState>,
 ) -> Result<Json<APIProofDetails>, StatusCode> {
-    let transaction: Result<APIProofDetails, sqlx::Error> = log_error!(
-        sqlx::query(
-            r#"
+    let query = format!(
+        r#"
 SELECT 
     t.tx_hash,
     t.parent_dp_hash,
     t.block_hash,
-    t.index,
+    t."index",
     t.version,
     t.transaction_type,
     t.transaction_status,
     b.timestamp,
     t.lane_id,
-    array_remove(ARRAY_AGG(bpo.blob_tx_hash), NULL) AS blob_tx_hashes,
-    array_remove(ARRAY_AGG(bpo.blob_index), NULL) AS blob_tx_indexes,
-    array_remove(ARRAY_AGG(bpo.blob_proof_output_index), NULL) AS blob_proof_output_indexes,
-    array_remove(ARRAY_AGG(bpo.hyle_output), NULL) AS proof_outputs
+    {blob_tx_hashes} AS blob_tx_hashes,
+    {blob_tx_indexes} AS blob_tx_indexes,
+    {blob_proof_output_indexes} AS blob_proof_output_indexes,
+    {proof_outputs} AS proof_outputs
 FROM transactions t
 LEFT JOIN blocks b 
     ON t.block_hash = b.hash
@@ -139,45 +142,58 @@ WHERE
     t.tx_hash = $1
     AND t.transaction_type = 'proof_transaction'
 GROUP BY t.parent_dp_hash, t.tx_hash, b.height, b.timestamp
-ORDER BY b.height DESC, t.index DESC
+ORDER BY b.height DESC, t."index" DESC
 LIMIT 1;
 "#,
-        )
-        .bind(tx_hash)
-        .fetch_optional(&state.db)
-        .await
-        .map(|row| {
-            let row = row.ok_or(sqlx::Error::RowNotFound)?;
-            let api_tx: TransactionDb = FromRow::from_row(&row)?;
-            let blob_tx_hashes: Vec<String> = row.try_get("blob_tx_hashes")?;
-            let blob_tx_indexes: Vec<i32> = row.try_get("blob_tx_indexes")?;
-            let blob_proof_output_indexes: Vec<i32> = row.try_get("blob_proof_output_indexes")?;
-            let proof_outputs: Vec<serde_json::Value> = row.try_get("proof_outputs")?;
+        blob_tx_hashes = state.db.json_agg("bpo.blob_tx_hash"),
+        blob_tx_indexes = state.db.json_agg("bpo.blob_index"),
+        blob_proof_output_indexes = state.db.json_agg("bpo.blob_proof_output_index"),
+        proof_outputs = state.db.json_doc_agg("bpo.hyle_output"),
+    );
+    let transaction: Result<APIProofDetails, sqlx::Error> = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query(&query)
+                .bind(tx_hash)
+                .fetch_optional(pool)
+                .await
+                .map(|row| -> Result<APIProofDetails, sqlx::Error> {
+                    let row = row.ok_or(sqlx::Error::RowNotFound)?;
+                    let api_tx: TransactionDb = FromRow::from_row(&row)?;
+                    let blob_tx_hashes: sqlx::types::Json<Vec<String>> =
+                        row.try_get("blob_tx_hashes")?;
+                    let blob_tx_indexes: sqlx::types::Json<Vec<i32>> =
+                        row.try_get("blob_tx_indexes")?;
+                    let blob_proof_output_indexes: sqlx::types::Json<Vec<i32>> =
+                        row.try_get("blob_proof_output_indexes")?;
+                    let proof_outputs: sqlx::types::Json<Vec<serde_json::Value>> =
+                        row.try_get("proof_outputs")?;
 
-            let proof_outputs: Vec<(TxHash, u32, u32, serde_json::Value)> = blob_tx_hashes
-                .into_iter()
-                .zip(blob_tx_indexes)
-                .zip(blob_proof_output_indexes)
-                .zip(proof_outputs)
-                .map(|(((tx_hash, tx_idx), proof_idx), output)| {
-                    (tx_hash.into(), tx_idx as u32, proof_idx as u32, output)
-                })
-                .collect();
+                    let proof_outputs: Vec<(TxHash, u32, u32, serde_json::Value)> = blob_tx_hashes
+                        .0
+                        .into_iter()
+                        .zip(blob_tx_indexes.0)
+                        .zip(blob_proof_output_indexes.0)
+                        .zip(proof_outputs.0)
+                        .map(|(((tx_hash, tx_idx), proof_idx), output)| {
+                            (tx_hash.into(), tx_idx as u32, proof_idx as u32, output)
+                        })
+                        .collect();
 
-            Ok(APIProofDetails {
-                tx_hash: api_tx.tx_hash.0,
-                parent_dp_hash: api_tx.parent_dp_hash,
-                block_hash: api_tx.block_hash,
-                index: api_tx.index,
-                version: api_tx.version,
-                transaction_type: api_tx.transaction_type,
-                transaction_status: api_tx.transaction_status,
-                timestamp: api_tx
-                    .timestamp
-                    .map(|t| TimestampMs(t.and_utc().timestamp_millis() as u128)),
-                lane_id: api_tx.lane_id.map(|l| l.0),
-                proof_outputs,
-            })
+                    Ok(APIProofDetails {
+                        tx_hash: api_tx.tx_hash.0,
+                        parent_dp_hash: api_tx.parent_dp_hash,
+                        block_hash: api_tx.block_hash,
+                        index: api_tx.index,
+                        version: api_tx.version,
+                        transaction_type: api_tx.transaction_type,
+                        transaction_status: api_tx.transaction_status,
+                        timestamp: api_tx
+                            .timestamp
+                            .map(|t| TimestampMs(t.and_utc().timestamp_millis() as u128)),
+                        lane_id: api_tx.lane_id.map(|l| l.0),
+                        proof_outputs,
+                    })
+                })
         }),
         "Failed to fetch proof by hash"
     )
@@ -1,4 +1,4 @@
-use super::IndexerApiState;
+use super::{with_db, IndexerApiState};
 use axum::{extract::State, http::StatusCode, Json};
 use hyle_model::api::{NetworkStats, ProofStat};
 
@@ -28,26 +28,29 @@ pub async fn get_stats(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<NetworkStats>, StatusCode> {
     let total_transactions = log_error!(
-        sqlx::query_scalar("SELECT count(*) as txs FROM transactions")
-            .fetch_optional(&state.db)
-            .await,
+        with_db!(&state.db, pool => {
+            sqlx::query_scalar::<_, i64>("SELECT count(*) as txs FROM transactions")
+                .fetch_optional(pool)
+                .await
+        }),
         "Failed to fetch stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .unwrap_or(0);
 
     let total_contracts = log_error!(
-        sqlx::query_scalar("SELECT count(*) as contracts FROM contracts")
-            .fetch_optional(&state.db)
-            .await,
+        with_db!(&state.db, pool => {
+            sqlx::query_scalar::<_, i64>("SELECT count(*) as contracts FROM contracts")
+                .fetch_optional(pool)
+                .await
+        }),
         "Failed to fetch stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .unwrap_or(0);
 
-    let txs_last_day = log_error!(
-        sqlx::query_scalar(
-            "
+    let query = state.db.sql(
+        "
 WITH recent_blocks AS (
   SELECT hash
   FROM blocks
@@ -56,36 +59,59 @@ WITH recent_blocks AS (
 SELECT count(*)
 FROM transactions
 JOIN recent_blocks b ON transactions.block_hash = b.hash
-            "
-        )
-        .fetch_optional(&state.db)
-        .await,
+            ",
+        "
+WITH recent_blocks AS (
+  SELECT hash
+  FROM blocks
+  WHERE unixepoch(timestamp) > unixepoch('now', '-1 day')
+)
+SELECT count(*)
+FROM transactions
+JOIN recent_blocks b ON transactions.block_hash = b.hash
+            ",
+    );
+    let txs_last_day = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_scalar::<_, i64>(query)
+                .fetch_optional(pool)
+                .await
+        }),
         "Failed to fetch stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .unwrap_or(0);
 
-    let contracts_last_day = log_error!(
-        sqlx::query_scalar(
-            "
+    let query = state.db.sql(
+        "
             SELECT count(*) as contracts FROM contracts
             LEFT JOIN transactions t ON contracts.tx_hash = t.tx_hash
             LEFT JOIN blocks b ON t.block_hash = b.hash
             WHERE b.timestamp > now() - interval '1 day'
             OR b.timestamp IS NULL
-            "
-        )
-        .fetch_optional(&state.db)
-        .await,
+            ",
+        "
+            SELECT count(*) as contracts FROM contracts
+            LEFT JOIN transactions t ON contracts.tx_hash = t.tx_hash
+            LEFT JOIN blocks b ON t.block_hash = b.hash
+            WHERE unixepoch(b.timestamp) > unixepoch('now', '-1 day')
+            OR b.timestamp IS NULL
+            ",
+    );
+    let contracts_last_day = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_scalar::<_, i64>(query)
+                .fetch_optional(pool)
+                .await
+        }),
         "Failed to fetch stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .unwrap_or(0);
 
     // graph is number of txs per hour for the last 6 hours
-    let graph_tx_volume = log_error!(
-        sqlx::query_as::<_, Point>(
-            "
+    let query = state.db.sql(
+        "
 WITH hours AS (
   SELECT generate_series(
     date_trunc('hour', now()) - interval '5 hours',
@@ -103,10 +129,29 @@ SELECT
       AND b.timestamp < h.hour_start + interval '1 hour'
   )::bigint AS y
 FROM hours h
-ORDER BY h.hour_start;            "
-        )
-        .fetch_all(&state.db)
-        .await,
+ORDER BY h.hour_start;            ",
+        "
+WITH RECURSIVE hours(hour_start) AS (
+  SELECT unixepoch('now') / 3600 * 3600 - 5 * 3600
+  UNION ALL
+  SELECT hour_start + 3600 FROM hours WHERE hour_start < unixepoch('now') / 3600 * 3600
+)
+SELECT
+  h.hour_start AS x,
+  (
+    SELECT count(*)
+    FROM transactions t
+    JOIN blocks b ON t.block_hash = b.hash
+    WHERE unixepoch(b.timestamp) >= h.hour_start
+      AND unixepoch(b.timestamp) < h.hour_start + 3600
+  ) AS y
+FROM hours h
+ORDER BY h.hour_start;            ",
+    );
+    let graph_tx_volume = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, Point>(query).fetch_all(pool).await
+        }),
         "Failed to fetch tx stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -116,9 +161,8 @@ ORDER BY h.hour_start;            "
         .map(|point| (point.x, point.y.unwrap_or(0)))
         .collect::<Vec<(i64, i64)>>();
 
-    let graph_block_time = log_error!(
-        sqlx::query_as::<_, Point<f64>>(
-            "
+    let query = state.db.sql(
+        "
             WITH hours AS (
                 SELECT generate_series(
                     date_trunc('hour', now()) - interval '5 hours',
@@ -144,10 +188,36 @@ ORDER BY h.hour_start;            "
             LEFT JOIN block_deltas bd ON bd.bucket = h.hour_start
             GROUP BY h.hour_start
             ORDER BY h.hour_start;
-            "
-        )
-        .fetch_all(&state.db)
-        .await,
+            ",
+        "
+            WITH RECURSIVE hours(hour_start) AS (
+                SELECT unixepoch('now') / 3600 * 3600 - 5 * 3600
+                UNION ALL
+                SELECT hour_start + 3600 FROM hours
+                WHERE hour_start < unixepoch('now') / 3600 * 3600
+            ),
+            block_deltas AS (
+                SELECT
+                    (julianday(b.timestamp) - julianday(bp.timestamp)) * 86400.0 AS delta,
+                    unixepoch(b.timestamp) / 3600 * 3600 AS bucket
+                FROM blocks b
+                JOIN blocks bp ON b.parent_hash = bp.hash
+                WHERE unixepoch(b.timestamp) > unixepoch('now', '-6 hours')
+                AND bp.height > 0
+            )
+            SELECT
+                h.hour_start AS x,
+                AVG(bd.delta) AS y
+            FROM hours h
+            LEFT JOIN block_deltas bd ON bd.bucket = h.hour_start
+            GROUP BY h.hour_start
+            ORDER BY h.hour_start;
+            ",
+    );
+    let graph_block_time = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, Point<f64>>(query).fetch_all(pool).await
+        }),
         "Failed to fetch block stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -157,9 +227,8 @@ ORDER BY h.hour_start;            "
         .map(|point| (point.x, point.y.unwrap_or(0.)))
         .collect::<Vec<(i64, f64)>>();
 
-    let peak_txs = log_error!(
-        sqlx::query_as::<_, PeakStat>(
-            "
+    let query = state.db.sql(
+        "
 WITH recent_blocks AS (
   SELECT hash, date_trunc('minute', timestamp) AS minute
   FROM blocks
@@ -176,10 +245,32 @@ tx_counts AS (
 SELECT *
 FROM tx_counts
 ORDER BY tx_count DESC
-LIMIT 1;            "
-        )
-        .fetch_optional(&state.db)
-        .await,
+LIMIT 1;            ",
+        "
+WITH recent_blocks AS (
+  SELECT hash, unixepoch(timestamp) / 60 * 60 AS minute
+  FROM blocks
+  WHERE unixepoch(timestamp) >= unixepoch('now', '-24 hours')
+),
+tx_counts AS (
+  SELECT
+    rb.minute AS minute_bucket,
+    count(*) AS tx_count
+  FROM recent_blocks rb
+  JOIN transactions t ON t.block_hash = rb.hash
+  GROUP BY rb.minute
+)
+SELECT *
+FROM tx_counts
+ORDER BY tx_count DESC
+LIMIT 1;            ",
+    );
+    let peak_txs = log_error!(
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, PeakStat>(query)
+                .fetch_optional(pool)
+                .await
+        }),
         "Failed to fetch peak TPM"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
@@ -213,8 +304,9 @@ pub async fn get_proof_stats(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<ProofStat>>, StatusCode> {
     let transactions = log_error!(
-        sqlx::query_as::<_, ProofStat>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, ProofStat>(
+                r#"
 WITH bpo_distinct AS (
   SELECT DISTINCT contract_name, proof_tx_hash
   FROM blob_proof_outputs
@@ -227,9 +319,10 @@ JOIN contracts c ON b.contract_name = c.contract_name
 GROUP BY c.verifier
 ORDER BY proof_count DESC;
         "#,
-        )
-        .fetch_all(&state.db)
-        .await,
+            )
+            .fetch_all(pool)
+            .await
+        }),
         "Failed to fetch proof stats"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
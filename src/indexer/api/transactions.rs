@@ -1,6 +1,6 @@
 use std::num::TryFromIntError;
 
-use super::{BlockPagination, IndexerApiState};
+use super::{with_db, BlockPagination, IndexerApiState};
 use api::{
     APITransaction, APITransactionEvents, BlobWithStatus, TransactionStatusDb, TransactionTypeDb,
     TransactionWithBlobs,
@@ -11,11 +11,10 @@ use axum::{
     Json,
 };
 use hyle_model::utils::TimestampMs;
-use sqlx::postgres::PgRow;
 use sqlx::types::chrono::NaiveDateTime;
 use sqlx::FromRow;
 use sqlx::Row;
-use sqlx::{prelude::Type, Postgres};
+use sqlx::{prelude::Type, ColumnIndex, Database};
 
 use crate::model::*;
 use hyle_modules::log_error;
@@ -29,31 +28,44 @@ impl From<DataProposalHash> for DataProposalHashDb {
     }
 }
 
-impl Type<Postgres> for DataProposalHashDb {
-    fn type_info() -> sqlx::postgres::PgTypeInfo {
-        <String as Type<Postgres>>::type_info()
+impl<DB: Database> Type<DB> for DataProposalHashDb
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
     }
 }
-impl sqlx::Encode<'_, sqlx::Postgres> for DataProposalHashDb {
+impl<'q, DB: Database> sqlx::Encode<'q, DB> for DataProposalHashDb
+where
+    String: sqlx::Encode<'q, DB>,
+{
     fn encode_by_ref(
         &self,
-        buf: &mut sqlx::postgres::PgArgumentBuffer,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
     ) -> std::result::Result<
         sqlx::encode::IsNull,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        <String as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&self.0 .0, buf)
+        <String as sqlx::Encode<'q, DB>>::encode_by_ref(&self.0 .0, buf)
     }
 }
 
-impl<'r> sqlx::Decode<'r, sqlx::Postgres> for DataProposalHashDb {
+impl<'r, DB: Database> sqlx::Decode<'r, DB> for DataProposalHashDb
+where
+    String: sqlx::Decode<'r, DB>,
+{
     fn decode(
-        value: sqlx::postgres::PgValueRef<'r>,
+        value: <DB as Database>::ValueRef<'r>,
     ) -> std::result::Result<
         DataProposalHashDb,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        let inner = <String as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        let inner = <String as sqlx::Decode<'r, DB>>::decode(value)?;
         Ok(DataProposalHashDb(DataProposalHash(inner)))
     }
 }
@@ -67,31 +79,44 @@ impl From<TxHash> for TxHashDb {
     }
 }
 
-impl Type<Postgres> for TxHashDb {
-    fn type_info() -> sqlx::postgres::PgTypeInfo {
-        <String as Type<Postgres>>::type_info()
+impl<DB: Database> Type<DB> for TxHashDb
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
     }
 }
-impl sqlx::Encode<'_, sqlx::Postgres> for TxHashDb {
+impl<'q, DB: Database> sqlx::Encode<'q, DB> for TxHashDb
+where
+    String: sqlx::Encode<'q, DB>,
+{
     fn encode_by_ref(
         &self,
-        buf: &mut sqlx::postgres::PgArgumentBuffer,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
     ) -> std::result::Result<
         sqlx::encode::IsNull,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        <String as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&self.0 .0, buf)
+        <String as sqlx::Encode<'q, DB>>::encode_by_ref(&self.0 .0, buf)
     }
 }
 
-impl<'r> sqlx::Decode<'r, sqlx::Postgres> for TxHashDb {
+impl<'r, DB: Database> sqlx::Decode<'r, DB> for TxHashDb
+where
+    String: sqlx::Decode<'r, DB>,
+{
     fn decode(
-        value: sqlx::postgres::PgValueRef<'r>,
+        value: <DB as Database>::ValueRef<'r>,
     ) -> std::result::Result<
         TxHashDb,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        let inner = <String as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        let inner = <String as sqlx::Decode<'r, DB>>::decode(value)?;
         Ok(TxHashDb(TxHash(inner)))
     }
 }
@@ -105,31 +130,44 @@ impl From<LaneId> for LaneIdDb {
     }
 }
 
-impl Type<Postgres> for LaneIdDb {
-    fn type_info() -> sqlx::postgres::PgTypeInfo {
-        <String as Type<Postgres>>::type_info()
+impl<DB: Database> Type<DB> for LaneIdDb
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
     }
 }
-impl sqlx::Encode<'_, sqlx::Postgres> for LaneIdDb {
+impl<'q, DB: Database> sqlx::Encode<'q, DB> for LaneIdDb
+where
+    String: sqlx::Encode<'q, DB>,
+{
     fn encode_by_ref(
         &self,
-        buf: &mut sqlx::postgres::PgArgumentBuffer,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
     ) -> std::result::Result<
         sqlx::encode::IsNull,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        <String as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&hex::encode(&self.0 .0 .0), buf)
+        <String as sqlx::Encode<'q, DB>>::encode_by_ref(&hex::encode(&self.0 .0 .0), buf)
     }
 }
 
-impl<'r> sqlx::Decode<'r, sqlx::Postgres> for LaneIdDb {
+impl<'r, DB: Database> sqlx::Decode<'r, DB> for LaneIdDb
+where
+    String: sqlx::Decode<'r, DB>,
+{
     fn decode(
-        value: sqlx::postgres::PgValueRef<'r>,
+        value: <DB as Database>::ValueRef<'r>,
     ) -> std::result::Result<
         LaneIdDb,
         std::boxed::Box<(dyn std::error::Error + std::marker::Send + std::marker::Sync + 'static)>,
     > {
-        let inner = <String as sqlx::Decode<sqlx::Postgres>>::decode(value)?;
+        let inner = <String as sqlx::Decode<'r, DB>>::decode(value)?;
         Ok(LaneIdDb(LaneId(ValidatorPublicKey(hex::decode(inner)?))))
     }
 }
@@ -149,8 +187,16 @@ pub struct TransactionDb {
     pub identity: Option<String>, // Identity of the transaction sender (null for proofs)
 }
 
-impl<'r> FromRow<'r, PgRow> for TransactionDb {
-    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+impl<'r, R: Row> FromRow<'r, R> for TransactionDb
+where
+    &'static str: ColumnIndex<R>,
+    String: Type<R::Database> + sqlx::Decode<'r, R::Database>,
+    i32: Type<R::Database> + sqlx::Decode<'r, R::Database>,
+    NaiveDateTime: Type<R::Database> + sqlx::Decode<'r, R::Database>,
+    TransactionTypeDb: Type<R::Database> + sqlx::Decode<'r, R::Database>,
+    TransactionStatusDb: Type<R::Database> + sqlx::Decode<'r, R::Database>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
         let tx_hash = row.try_get("tx_hash")?;
         let block_hash = row.try_get("block_hash")?;
         let dp_hash_db: DataProposalHashDb = row.try_get("parent_dp_hash")?;
@@ -222,42 +268,44 @@ pub async fn get_transactions(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APITransaction>>, StatusCode> {
     let transactions = log_error!(
-        match pagination.start_block {
-            Some(start_block) => sqlx::query_as::<_, TransactionDb>(
-                r#"
+        with_db!(&state.db, pool => {
+            match pagination.start_block {
+                Some(start_block) => sqlx::query_as::<_, TransactionDb>(
+                    r#"
                 SELECT t.*, b.timestamp
                 FROM (
                     SELECT *
                     FROM transactions t
                     WHERE t.transaction_type = 'blob_transaction'
                         AND t.block_height <= $1 AND t.block_height > $2
-                    ORDER BY t.block_height DESC, t.index DESC
+                    ORDER BY t.block_height DESC, t."index" DESC
                     LIMIT $3
                 ) t
                 LEFT JOIN blocks b ON t.block_hash = b.hash
                 "#,
-            )
-            .bind(start_block)
-            .bind(start_block - pagination.nb_results.unwrap_or(10))
-            .bind(pagination.nb_results.unwrap_or(10)),
-            None => sqlx::query_as::<_, TransactionDb>(
-                r#"
+                )
+                .bind(start_block)
+                .bind(start_block - pagination.nb_results.unwrap_or(10))
+                .bind(pagination.nb_results.unwrap_or(10)),
+                None => sqlx::query_as::<_, TransactionDb>(
+                    r#"
                 SELECT t.*, b.timestamp
                 FROM (
                     SELECT *
                     FROM transactions t
                     WHERE t.transaction_type = 'blob_transaction'
-                    ORDER BY t.block_height DESC, t.index DESC
+                    ORDER BY t.block_height DESC, t."index" DESC
                     LIMIT $1
                 ) t
                 LEFT JOIN blocks b ON t.block_hash = b.hash
                 "#,
-            )
-            .bind(pagination.nb_results.unwrap_or(10)),
-        }
-        .fetch_all(&state.db)
-        .await
-        .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect()),
+                )
+                .bind(pagination.nb_results.unwrap_or(10)),
+            }
+            .fetch_all(pool)
+            .await
+            .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect())
+        }),
         "Failed to fetch transactions"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -281,40 +329,44 @@ pub async fn get_transactions_by_contract(
     Query(pagination): Query<BlockPagination>,
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APITransaction>>, StatusCode> {
-    let transactions = log_error!(match pagination.start_block {
-        Some(start_block) => sqlx::query_as::<_, TransactionDb>(
-            r#"
+    let transactions = log_error!(
+        with_db!(&state.db, pool => {
+            match pagination.start_block {
+                Some(start_block) => sqlx::query_as::<_, TransactionDb>(
+                    r#"
             SELECT t.* , bl.timestamp
             FROM transactions t
             JOIN blobs b ON t.tx_hash = b.tx_hash
             LEFT JOIN blocks bl ON t.block_hash = bl.hash
             WHERE b.contract_name = $1 AND bl.height <= $2 AND bl.height > $3 AND t.transaction_type = 'blob_transaction'
-            ORDER BY bl.height DESC, t.index DESC
+            ORDER BY bl.height DESC, t."index" DESC
             LIMIT $4
             "#,
-        )
-        .bind(contract_name)
-        .bind(start_block)
-        .bind(start_block - pagination.nb_results.unwrap_or(10)) // Fine if this goes negative
-        .bind(pagination.nb_results.unwrap_or(10)),
-        None => sqlx::query_as::<_, TransactionDb>(
-            r#"
+                )
+                .bind(contract_name)
+                .bind(start_block)
+                .bind(start_block - pagination.nb_results.unwrap_or(10)) // Fine if this goes negative
+                .bind(pagination.nb_results.unwrap_or(10)),
+                None => sqlx::query_as::<_, TransactionDb>(
+                    r#"
             SELECT t.*, bl.timestamp
             FROM transactions t
             JOIN blobs b ON t.tx_hash = b.tx_hash AND t.parent_dp_hash = b.parent_dp_hash
             LEFT JOIN blocks bl ON t.block_hash = bl.hash
             WHERE b.contract_name = $1 AND t.transaction_type = 'blob_transaction'
-            ORDER BY bl.height DESC, t.index DESC
+            ORDER BY bl.height DESC, t."index" DESC
             LIMIT $2
             "#,
-        )
-        .bind(contract_name)
-        .bind(pagination.nb_results.unwrap_or(10)),
-    }
-    .fetch_all(&state.db)
-    .await
-    .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect()),
-    "Failed to fetch transactions by contract")
+                )
+                .bind(contract_name)
+                .bind(pagination.nb_results.unwrap_or(10)),
+            }
+            .fetch_all(pool)
+            .await
+        })
+        .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect()),
+        "Failed to fetch transactions by contract"
+    )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(transactions))
@@ -336,19 +388,21 @@ pub async fn get_transactions_by_height(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APITransaction>>, StatusCode> {
     let transactions = log_error!(
-        sqlx::query_as::<_, TransactionDb>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, TransactionDb>(
+                r#"
         SELECT t.*, b.timestamp
         FROM transactions t
         JOIN blocks b ON t.block_hash = b.hash
         WHERE b.height = $1 AND t.transaction_type = 'blob_transaction'
-        ORDER BY t.index DESC
+        ORDER BY t."index" DESC
         "#,
-        )
-        .bind(height)
-        .fetch_all(&state.db)
-        .await
-        .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect()),
+            )
+            .bind(height)
+            .fetch_all(pool)
+            .await
+            .map(|db| db.into_iter().map(Into::<APITransaction>::into).collect())
+        }),
         "Failed to fetch transactions by height"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -372,8 +426,9 @@ pub async fn get_transaction_with_hash(
     State(state): State<IndexerApiState>,
 ) -> Result<Json<APITransaction>, StatusCode> {
     let transaction = log_error!(
-        sqlx::query_as::<_, TransactionDb>(
-            r#"
+        with_db!(&state.db, pool => {
+            sqlx::query_as::<_, TransactionDb>(
+                r#"
 SELECT
     tx_hash,
     version,
@@ -381,21 +436,22 @@ SELECT
     transaction_status,
     parent_dp_hash,
     block_hash,
-    index,
+    "index",
     b.timestamp,
     lane_id,
     identity
 FROM transactions t
 LEFT JOIN blocks b ON t.block_hash = b.hash
 WHERE t.tx_hash = $1 AND transaction_type='blob_transaction'
-ORDER BY block_height DESC, index DESC
+ORDER BY block_height DESC, "index" DESC
 LIMIT 1;
         "#,
-        )
-        .bind(tx_hash)
-        .fetch_optional(&state.db)
-        .await
-        .map(|db| db.map(|test| { Into::<APITransaction>::into(test) })),
+            )
+            .bind(tx_hash)
+            .fetch_optional(pool)
+            .await
+            .map(|db| db.map(|test| Into::<APITransaction>::into(test)))
+        }),
         "Failed to fetch transaction by hash"
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -421,21 +477,22 @@ pub async fn get_transaction_events(
     Path(tx_hash): Path<String>,
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<APITransactionEvents>>, StatusCode> {
-    let rows = log_error!(
-        sqlx::query(
-            r#"
+    let transactions: Result<Vec<APITransactionEvents>, anyhow::Error> = with_db!(&state.db, pool => {
+        let rows = log_error!(
+            sqlx::query(
+                r#"
 with filtered as (
-    SELECT 
+    SELECT
         t.block_hash,
         b.height,
         t.tx_hash,
         t.parent_dp_hash,
         t.events,
-        t.index
+        t."index"
     FROM transaction_state_events t
-    LEFT JOIN blocks b 
+    LEFT JOIN blocks b
         ON t.block_hash = b.hash
-    WHERE 
+    WHERE
         t.tx_hash = $1
         AND t.block_height = b.height
 )
@@ -445,35 +502,35 @@ SELECT
     tx_hash,
     events
 FROM
-    (SELECT filtered.*, parent_dp_hash = FIRST_VALUE(parent_dp_hash) OVER (ORDER BY height DESC, index DESC) as first_res FROM filtered)
+    (SELECT filtered.*, parent_dp_hash = FIRST_VALUE(parent_dp_hash) OVER (ORDER BY height DESC, "index" DESC) as first_res FROM filtered)
 WHERE first_res = TRUE
-ORDER BY 
+ORDER BY
     height DESC,
-    index DESC;
+    "index" DESC;
 "#,
+            )
+            .bind(tx_hash)
+            .fetch_all(pool)
+            .await,
+            "Failed to fetch transaction events"
         )
-        .bind(tx_hash)
-        .fetch_all(&state.db)
-        .await,
-        "Failed to fetch transaction events"
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let transactions: Result<Vec<APITransactionEvents>, anyhow::Error> = rows
-        .into_iter()
-        .map(|row| {
-            let block_hash = row.try_get("block_hash")?;
-            let block_height: i64 = row.try_get("height")?;
-            let block_height = BlockHeight(block_height.try_into()?);
-            let events: serde_json::Value = row.try_get("events")?;
-            let events: Vec<serde_json::Value> = serde_json::from_value(events)?;
-            Ok(APITransactionEvents {
-                block_hash,
-                block_height,
-                events,
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        rows.into_iter()
+            .map(|row| {
+                let block_hash = row.try_get("block_hash")?;
+                let block_height: i64 = row.try_get("height")?;
+                let block_height = BlockHeight(block_height.try_into()?);
+                let events: serde_json::Value = row.try_get("events")?;
+                let events: Vec<serde_json::Value> = serde_json::from_value(events)?;
+                Ok(APITransactionEvents {
+                    block_hash,
+                    block_height,
+                    events,
+                })
             })
-        })
-        .collect();
+            .collect()
+    });
 
     match transactions {
         Ok(transactions) => Ok(Json(transactions)),
@@ -499,10 +556,15 @@ pub async fn get_blob_transactions_by_contract(
     Path(contract_name): Path<String>,
     State(state): State<IndexerApiState>,
 ) -> Result<Json<Vec<TransactionWithBlobs>>, StatusCode> {
-    let rows = log_error!(sqlx::query(
+    // Blobs are aggregated as JSON [contract_name, hex data, proof outputs] arrays
+    let blobs = state.db.sql(
+        "jsonb_agg(jsonb_build_array(b.contract_name, encode(b.data, 'hex'), b.proof_outputs))",
+        "json_group_array(json_array(b.contract_name, hex(b.data), json(b.proof_outputs)))",
+    );
+    let query = format!(
         r#"
-        with blobs as (
-            SELECT blobs.*, array_remove(ARRAY_AGG(blob_proof_outputs.hyle_output), NULL) AS proof_outputs
+        with contract_blobs as (
+            SELECT blobs.*, {proof_outputs} AS proof_outputs
             FROM blobs
             LEFT JOIN blob_proof_outputs ON blobs.parent_dp_hash = blob_proof_outputs.blob_parent_dp_hash AND blobs.tx_hash = blob_proof_outputs.blob_tx_hash AND blobs.blob_index = blob_proof_outputs.blob_index
             WHERE blobs.contract_name = $1
@@ -512,67 +574,78 @@ pub async fn get_blob_transactions_by_contract(
             t.tx_hash,
             t.parent_dp_hash,
             t.block_hash,
-            t.index,
+            t."index",
             t.version,
             t.transaction_type,
             t.transaction_status,
             t.identity,
-            array_agg(ROW(b.contract_name, b.data, b.proof_outputs)) AS blobs
-        FROM blobs b
+            {blobs} AS blobs
+        FROM contract_blobs b
         JOIN transactions t on t.tx_hash = b.tx_hash AND t.parent_dp_hash = b.parent_dp_hash
         GROUP BY
             t.tx_hash,
             t.parent_dp_hash,
             t.block_hash,
-            t.index,
+            t."index",
             t.version,
             t.transaction_type,
             t.transaction_status,
             t.identity
         "#,
-    )
-    .bind(contract_name.clone())
-    .fetch_all(&state.db)
-    .await, "Failed to fetch blob transactions by contract")
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let transactions: Result<Vec<TransactionWithBlobs>, _> = rows
-        .into_iter()
-        .map(|row| {
-            let api_tx: TransactionDb = FromRow::from_row(&row)?;
-            let Some(block_hash) = api_tx.block_hash else {
-                return Err(sqlx::Error::RowNotFound);
-            };
-            let blobs: Vec<(String, Vec<u8>, Vec<serde_json::Value>)> = row.try_get("blobs")?;
-            let blobs = blobs
-                .into_iter()
-                .map(|(contract_name, data, proof_outputs)| BlobWithStatus {
-                    contract_name,
-                    data,
-                    proof_outputs,
+        proof_outputs = state.db.json_doc_agg("blob_proof_outputs.hyle_output"),
+    );
+
+    let transactions: Result<Vec<TransactionWithBlobs>, _> = with_db!(&state.db, pool => {
+        let rows = log_error!(
+            sqlx::query(&query)
+                .bind(contract_name.clone())
+                .fetch_all(pool)
+                .await,
+            "Failed to fetch blob transactions by contract"
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        rows.into_iter()
+            .map(|row| {
+                let api_tx: TransactionDb = FromRow::from_row(&row)?;
+                let Some(block_hash) = api_tx.block_hash else {
+                    return Err(sqlx::Error::RowNotFound);
+                };
+                let blobs: sqlx::types::Json<Vec<(String, String, Vec<serde_json::Value>)>> =
+                    row.try_get("blobs")?;
+                let blobs = blobs
+                    .0
+                    .into_iter()
+                    .map(|(contract_name, data, proof_outputs)| {
+                        Ok(BlobWithStatus {
+                            contract_name,
+                            data: hex::decode(data).map_err(|e| sqlx::Error::Decode(e.into()))?,
+                            proof_outputs,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, sqlx::Error>>()?;
+                let Some(identity) = api_tx.identity else {
+                    return Err(sqlx::Error::RowNotFound);
+                };
+
+                Ok(TransactionWithBlobs {
+                    tx_hash: api_tx.tx_hash.0,
+                    parent_dp_hash: api_tx.parent_dp_hash,
+                    block_hash,
+                    index: api_tx.index.unwrap_or(0),
+                    version: api_tx.version,
+                    transaction_type: api_tx.transaction_type,
+                    transaction_status: api_tx.transaction_status,
+                    timestamp: api_tx
+                        .timestamp
+                        .map(|t| TimestampMs(t.and_utc().timestamp_millis() as u128)),
+                    lane_id: api_tx.lane_id.map(|l| l.0),
+                    identity,
+                    blobs,
                 })
-                .collect();
-            let Some(identity) = api_tx.identity else {
-                return Err(sqlx::Error::RowNotFound);
-            };
-
-            Ok(TransactionWithBlobs {
-                tx_hash: api_tx.tx_hash.0,
-                parent_dp_hash: api_tx.parent_dp_hash,
-                block_hash,
-                index: api_tx.index.unwrap_or(0),
-                version: api_tx.version,
-                transaction_type: api_tx.transaction_type,
-                transaction_status: api_tx.transaction_status,
-                timestamp: api_tx
-                    .timestamp
-                    .map(|t| TimestampMs(t.and_utc().timestamp_millis() as u128)),
-                lane_id: api_tx.lane_id.map(|l| l.0),
-                identity,
-                blobs,
             })
-        })
-        .collect();
+            .collect()
+    });
     match transactions {
         Ok(transactions) => Ok(Json(transactions)),
         Err(e) => {
@@ -0,0 +1,122 @@
+//! Databases the indexer can store its data in.
+
+use super::{MIGRATOR, SQLITE_MIGRATOR};
+use anyhow::{Context, Result};
+use sqlx::{
+    postgres::PgPoolOptions,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    PgPool, SqlitePool,
+};
+use std::str::FromStr;
+
+/// Pool of connections to the indexer database: a PostgreSQL server, or an embedded SQLite file.
+///
+/// Queries are written once and run against whichever pool is in use with `with_db!`;
+/// the few that depend on the SQL dialect go through the helpers below.
+#[derive(Debug, Clone)]
+pub enum IndexerDb {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
+}
+
+/// Runs `$body` with `$pool` bound to the pool behind `$db`.
+///
+/// The body is expanded once per backend, so the queries it builds are typed for that database.
+macro_rules! with_db {
+    ($db:expr, $pool:ident => $body:expr) => {
+        match $db {
+            $crate::indexer::IndexerDb::Postgres($pool) => $body,
+            $crate::indexer::IndexerDb::Sqlite($pool) => $body,
+        }
+    };
+}
+pub(crate) use with_db;
+
+impl IndexerDb {
+    /// Connects to `database_url`: `sqlite:` URLs open (and create if needed) a SQLite database,
+    /// anything else is a PostgreSQL connection string.
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        if !database_url.starts_with("sqlite:") {
+            let pool = PgPoolOptions::new()
+                .max_connections(20)
+                .acquire_timeout(std::time::Duration::from_secs(1))
+                .connect(database_url)
+                .await
+                .context("Failed to connect to the database")?;
+            return Ok(IndexerDb::Postgres(pool));
+        }
+
+        let options = SqliteConnectOptions::from_str(database_url)
+            .context("Parsing the SQLite database URL")?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(std::time::Duration::from_secs(5));
+        let pool_options = if database_url.contains(":memory:") {
+            // An in-memory database only lives as long as its connections
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            SqlitePoolOptions::new().max_connections(20)
+        };
+        let pool = pool_options
+            .connect_with(options)
+            .await
+            .context("Failed to open the SQLite database")?;
+        Ok(IndexerDb::Sqlite(pool))
+    }
+
+    /// Creates or upgrades the indexer tables.
+    pub async fn migrate(&self) -> Result<()> {
+        match self {
+            IndexerDb::Postgres(pool) => MIGRATOR.run(pool).await?,
+            IndexerDb::Sqlite(pool) => SQLITE_MIGRATOR.run(pool).await?,
+        }
+        Ok(())
+    }
+
+    /// Picks the variant of a query written for this database.
+    pub fn sql<'a>(&self, postgres: &'a str, sqlite: &'a str) -> &'a str {
+        match self {
+            IndexerDb::Postgres(_) => postgres,
+            IndexerDb::Sqlite(_) => sqlite,
+        }
+    }
+
+    /// Maximum number of bind parameters in a single statement, with a security margin
+    /// (Postgres allows 65535, SQLite 32766).
+    pub fn max_bind_params(&self) -> usize {
+        match self {
+            IndexerDb::Postgres(_) => 65000,
+            IndexerDb::Sqlite(_) => 32000,
+        }
+    }
+
+    /// Suffix turning a bound string into a value of a JSON column.
+    pub fn json_cast(&self) -> &'static str {
+        self.sql("::jsonb", "")
+    }
+
+    /// Aggregates the non-NULL values of `expr` into a JSON array, `[]` if there are none.
+    pub fn json_agg(&self, expr: &str) -> String {
+        match self {
+            IndexerDb::Postgres(_) => {
+                format!("COALESCE(jsonb_agg({expr}) FILTER (WHERE {expr} IS NOT NULL), '[]')")
+            }
+            IndexerDb::Sqlite(_) => {
+                format!("json_group_array({expr}) FILTER (WHERE {expr} IS NOT NULL)")
+            }
+        }
+    }
+
+    /// Same as [`Self::json_agg`] for a JSON column, whose documents are nested as is.
+    pub fn json_doc_agg(&self, expr: &str) -> String {
+        match self {
+            IndexerDb::Postgres(_) => self.json_agg(expr),
+            IndexerDb::Sqlite(_) => {
+                format!("json_group_array(json({expr})) FILTER (WHERE {expr} IS NOT NULL)")
+            }
+        }
+    }
+}
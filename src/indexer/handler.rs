@@ -8,7 +8,6 @@ use hyle_model::api::{TransactionStatusDb, TransactionTypeDb};
 use hyle_model::utils::TimestampMs;
 use hyle_modules::{log_error, log_warn};
 use hyle_net::clock::TimestampMsClock;
-use sqlx::Postgres;
 use sqlx::QueryBuilder;
 use sqlx::Row;
 use std::collections::{HashMap, HashSet};
@@ -16,10 +15,9 @@ use std::ops::DerefMut;
 use std::sync::Arc;
 use tracing::{debug, info, trace};
 
-use super::Indexer;
+use super::{with_db, Indexer};
 
-fn calculate_optimal_batch_size(params_per_item: usize) -> usize {
-    let max_params: usize = 65000; // Security margin
+fn calculate_optimal_batch_size(max_params: usize, params_per_item: usize) -> usize {
     if params_per_item == 0 {
         return 1;
     }
@@ -75,6 +73,15 @@ pub struct TxContractStateStore {
     pub state_commitment: Vec<u8>,
 }
 
+#[derive(Debug)]
+pub struct ContractCodeVersionStore {
+    pub contract_name: String,
+    pub block_hash: ConsensusProposalHash,
+    pub effective_height: i64,
+    pub verifier: String,
+    pub program_id: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct TxBlobProofOutputStore {
     pub proof_tx_hash: TxHashDb,
@@ -88,6 +95,44 @@ pub struct TxBlobProofOutputStore {
     pub settled: bool,
 }
 
+/// Row updates applied once a dump's inserts are done, in the order they were recorded.
+#[derive(Debug)]
+enum SqlUpdate {
+    TransactionStatus {
+        tx_hash: TxHashDb,
+        parent_dp_hash: DataProposalHashDb,
+        status: TransactionStatusDb,
+    },
+    BlobVerified {
+        tx_hash: TxHashDb,
+        parent_dp_hash: DataProposalHashDb,
+        blob_index: i32,
+    },
+    BlobProofOutputSettled {
+        blob_tx_hash: TxHashDb,
+        blob_parent_dp_hash: DataProposalHashDb,
+        blob_index: i32,
+        blob_proof_output_index: i32,
+    },
+    ContractStateAtBlock {
+        contract_name: String,
+        block_hash: ConsensusProposalHash,
+        state_commitment: Vec<u8>,
+    },
+    ContractStateCommitment {
+        contract_name: String,
+        state_commitment: Vec<u8>,
+    },
+    ContractProgramId {
+        contract_name: String,
+        program_id: Vec<u8>,
+    },
+    ContractTimeoutWindow {
+        contract_name: String,
+        timeout_window: TimeoutWindowDb,
+    },
+}
+
 #[derive(Default)]
 pub(crate) struct IndexerHandlerStore {
     blocks: Vec<Arc<Block>>,
@@ -95,15 +140,10 @@ pub(crate) struct IndexerHandlerStore {
     tx_data: Vec<TxDataStore>,
     tx_data_proofs: Vec<TxProofStore>,
     transactions_events: Vec<TxEventStore>,
-    sql_updates: Vec<
-        sqlx::query::Query<
-            'static,
-            Postgres,
-            <sqlx::Postgres as sqlx::Database>::Arguments<'static>,
-        >,
-    >,
+    sql_updates: Vec<SqlUpdate>,
     contracts: HashMap<ContractName, TxContractStore>,
     contract_states: Vec<TxContractStateStore>,
+    contract_code_versions: Vec<ContractCodeVersionStore>,
     deleted_contracts: HashSet<ContractName>,
     blob_proof_outputs: Vec<TxBlobProofOutputStore>,
 }
@@ -119,6 +159,7 @@ impl std::fmt::Debug for IndexerHandlerStore {
             .field("sql_updates", &self.sql_updates.len())
             .field("contracts", &self.contracts.len())
             .field("contract_states", &self.contract_states.len())
+            .field("contract_code_versions", &self.contract_code_versions.len())
             .field("blob_proof_outputs", &self.blob_proof_outputs.len())
             .finish()
     }
@@ -157,6 +198,7 @@ impl Indexer {
             && self.handler_store.sql_updates.is_empty()
             && self.handler_store.contracts.is_empty()
             && self.handler_store.contract_states.is_empty()
+            && self.handler_store.contract_code_versions.is_empty()
             && self.handler_store.deleted_contracts.is_empty()
             && self.handler_store.blob_proof_outputs.is_empty()
     }
@@ -166,696 +208,810 @@ impl Indexer {
             return Ok(());
         }
 
-        let mut transaction = self.state.db.begin().await?;
+        let max_params = self.state.db.max_bind_params();
+        let json_cast = self.state.db.json_cast();
+        with_db!(self.state.db.clone(), pool => {
+            let mut transaction = pool.begin().await?;
 
-        // Insert blocks into the database
-        if !self.handler_store.blocks.is_empty() {
-            let mut query_builder = QueryBuilder::<Postgres>::new(
-                "INSERT INTO blocks (hash, parent_hash, height, timestamp, total_txs) ",
-            );
-            query_builder
-                .push_values(self.handler_store.blocks.drain(..), |mut b, block| {
-                    let block_hash = block.hash.clone();
-                    let block_height = log_error!(
-                        i64::try_from(block.block_height.0).map_err(|_| anyhow::anyhow!(
-                            "Block height is too large to fit into an i64"
-                        )),
-                        "Converting block height into i64"
-                    )
-                    .unwrap_or_default();
-                    let total_txs = block.txs.len() as i64;
-
-                    let block_timestamp = into_utc_date_time(&block.block_timestamp)
-                        .context("Block's timestamp is incorrect")
-                        .unwrap_or_else(|_| {
-                            // If the timestamp is incorrect, we can use the current time as a fallback.
-                            Utc::now()
-                        });
-
-                    b.push_bind(block_hash)
-                        .push_bind(block.parent_hash.clone())
-                        .push_bind(block_height)
-                        .push_bind(block_timestamp)
-                        .push_bind(total_txs);
-                })
-                .build()
-                .execute(&mut *transaction)
-                .await
-                .context("Inserting blocks")?;
-        }
-
-        // Insert transactions into the database with batching
-        if !self.handler_store.block_txs.is_empty() {
-            const TRANSACTIONS_PARAMS: usize = 10; // tx_hash, parent_dp_hash, version, transaction_type, transaction_status, block_hash, block_height, index, lane_id, identity
-            let transactions_batch_size = calculate_optimal_batch_size(TRANSACTIONS_PARAMS);
-            let block_txs = std::mem::take(&mut self.handler_store.block_txs);
-            let block_txs_vec: Vec<_> = block_txs.into_iter().collect();
-            let chunks: Vec<_> = block_txs_vec.chunks(transactions_batch_size).collect();
-
-            info!(
-                "Inserting {} transactions in {} batches of up to {} items each (calculated from {} params per item)",
-                block_txs_vec.len(),
-                chunks.len(),
-                transactions_batch_size,
-                TRANSACTIONS_PARAMS
-            );
-
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO transactions (tx_hash, parent_dp_hash, version, transaction_type, transaction_status, block_hash, block_height, index, lane_id, identity) ",
+            // Insert blocks into the database
+            if !self.handler_store.blocks.is_empty() {
+                let mut query_builder = QueryBuilder::new(
+                    "INSERT INTO blocks (hash, parent_hash, height, timestamp, total_txs) ",
                 );
-                query_builder.push_values(chunk.iter(), |mut b, (tx_id, (index, block, tx))| {
-                    let version = log_error!(
-                        i32::try_from(tx.version).map_err(|_| anyhow::anyhow!(
-                            "Tx version is too large to fit into an i32"
-                        )),
-                        "Converting tx version into i32"
-                    )
-                    .unwrap_or_default();
+                query_builder
+                    .push_values(self.handler_store.blocks.drain(..), |mut b, block| {
+                        let block_hash = block.hash.clone();
+                        let block_height = log_error!(
+                            i64::try_from(block.block_height.0).map_err(|_| anyhow::anyhow!(
+                                "Block height is too large to fit into an i64"
+                            )),
+                            "Converting block height into i64"
+                        )
+                        .unwrap_or_default();
+                        let total_txs = block.txs.len() as i64;
 
-                    let block_height = log_error!(
-                        i64::try_from(block.block_height.0).map_err(|_| anyhow::anyhow!(
-                            "Block height is too large to fit into an i64"
-                        )),
-                        "Converting block height into i64"
-                    )
-                    .unwrap_or_default();
-
-                    let tx_type = TransactionTypeDb::from(tx);
-                    let tx_status = match tx.transaction_data {
-                        TransactionData::Blob(_) => TransactionStatusDb::Sequenced,
-                        TransactionData::Proof(_) => TransactionStatusDb::Success,
-                        TransactionData::VerifiedProof(_) => TransactionStatusDb::Success,
-                    };
-
-                    let lane_id: LaneIdDb = log_error!(
-                        block
-                            .lane_ids
-                            .get(&tx_id.1)
-                            .context(format!("No lane id present for tx {tx_id}")),
-                        "Getting lane id for tx"
-                    )
-                    .unwrap_or(&LaneId::default())
-                    .clone()
-                    .into();
-
-                    let identity = match tx.transaction_data {
-                        TransactionData::Blob(ref tx) => Some(tx.identity.0.clone()),
-                        _ => None,
-                    };
-
-                    let parent_data_proposal_hash: DataProposalHashDb = tx_id.0.clone().into();
-                    let tx_hash: TxHashDb = tx_id.1.clone().into();
-
-                    b.push_bind(tx_hash)
-                        .push_bind(parent_data_proposal_hash)
-                        .push_bind(version)
-                        .push_bind(tx_type)
-                        .push_bind(tx_status)
-                        .push_bind(block.hash.clone())
-                        .push_bind(block_height)
-                        .push_bind(index)
-                        .push_bind(lane_id)
-                        .push_bind(identity);
-                });
-                query_builder.push(" ON CONFLICT(tx_hash, parent_dp_hash) DO UPDATE SET ");
-                query_builder.push("block_hash=EXCLUDED.block_hash, block_height=EXCLUDED.block_height, index=EXCLUDED.index, lane_id=EXCLUDED.lane_id, identity=EXCLUDED.identity,");
-                // This data comes from post-consensus so always erase earlier statuses, but not later ones.
-                query_builder.push(
-                    "transaction_status = case
-                        when transactions.transaction_status IS NULL THEN EXCLUDED.transaction_status
-                        when transactions.transaction_status = 'waiting_dissemination' THEN EXCLUDED.transaction_status
-                        when transactions.transaction_status = 'data_proposal_created' THEN EXCLUDED.transaction_status
-                        else transactions.transaction_status
-                    end",
-                );
+                        let block_timestamp = into_utc_date_time(&block.block_timestamp)
+                            .context("Block's timestamp is incorrect")
+                            .unwrap_or_else(|_| {
+                                // If the timestamp is incorrect, we can use the current time as a fallback.
+                                Utc::now()
+                            });
 
-                query_builder
+                        b.push_bind(block_hash)
+                            .push_bind(block.parent_hash.clone())
+                            .push_bind(block_height)
+                            .push_bind(block_timestamp)
+                            .push_bind(total_txs);
+                    })
                     .build()
                     .execute(&mut *transaction)
                     .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting transactions batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
+                    .context("Inserting blocks")?;
             }
-        }
 
-        // Insert blobs into the database with batching
-        if !self.handler_store.tx_data.is_empty() {
-            const TX_DATA_PARAMS: usize = 7; // tx_hash, parent_dp_hash, blob_index, identity, contract_name, data, verified
-            let blob_batch_size = calculate_optimal_batch_size(TX_DATA_PARAMS);
+            // Insert transactions into the database with batching
+            if !self.handler_store.block_txs.is_empty() {
+                const TRANSACTIONS_PARAMS: usize = 10; // tx_hash, parent_dp_hash, version, transaction_type, transaction_status, block_hash, block_height, index, lane_id, identity
+                let transactions_batch_size =
+                    calculate_optimal_batch_size(max_params, TRANSACTIONS_PARAMS);
+                let block_txs = std::mem::take(&mut self.handler_store.block_txs);
+                let block_txs_vec: Vec<_> = block_txs.into_iter().collect();
+                let chunks: Vec<_> = block_txs_vec.chunks(transactions_batch_size).collect();
 
-            let tx_data = std::mem::take(&mut self.handler_store.tx_data);
-            let chunks: Vec<_> = tx_data.chunks(blob_batch_size).collect();
+                info!(
+                    "Inserting {} transactions in {} batches of up to {} items each (calculated from {} params per item)",
+                    block_txs_vec.len(),
+                    chunks.len(),
+                    transactions_batch_size,
+                    TRANSACTIONS_PARAMS
+                );
 
-            info!(
-                "Inserting {} blobs in {} batches of up to {} items each (calculated from {} params per item)",
-                tx_data.len(),
-                chunks.len(),
-                blob_batch_size,
-                TX_DATA_PARAMS
-            );
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO transactions (tx_hash, parent_dp_hash, version, transaction_type, transaction_status, block_hash, block_height, \"index\", lane_id, identity) ",
+                    );
+                    query_builder.push_values(
+                        chunk.iter(),
+                        |mut b, (tx_id, (index, block, tx))| {
+                            let version = log_error!(
+                                i32::try_from(tx.version).map_err(|_| anyhow::anyhow!(
+                                    "Tx version is too large to fit into an i32"
+                                )),
+                                "Converting tx version into i32"
+                            )
+                            .unwrap_or_default();
+
+                            let block_height = log_error!(
+                                i64::try_from(block.block_height.0).map_err(|_| anyhow::anyhow!(
+                                    "Block height is too large to fit into an i64"
+                                )),
+                                "Converting block height into i64"
+                            )
+                            .unwrap_or_default();
+
+                            let tx_type = TransactionTypeDb::from(tx);
+                            let tx_status = match tx.transaction_data {
+                                TransactionData::Blob(_) => TransactionStatusDb::Sequenced,
+                                TransactionData::Proof(_) => TransactionStatusDb::Success,
+                                TransactionData::VerifiedProof(_) => TransactionStatusDb::Success,
+                            };
+
+                            let lane_id: LaneIdDb = log_error!(
+                                block
+                                    .lane_ids
+                                    .get(&tx_id.1)
+                                    .context(format!("No lane id present for tx {tx_id}")),
+                                "Getting lane id for tx"
+                            )
+                            .unwrap_or(&LaneId::default())
+                            .clone()
+                            .into();
+
+                            let identity = match tx.transaction_data {
+                                TransactionData::Blob(ref tx) => Some(tx.identity.0.clone()),
+                                _ => None,
+                            };
+
+                            let parent_data_proposal_hash: DataProposalHashDb =
+                                tx_id.0.clone().into();
+                            let tx_hash: TxHashDb = tx_id.1.clone().into();
+
+                            b.push_bind(tx_hash)
+                                .push_bind(parent_data_proposal_hash)
+                                .push_bind(version)
+                                .push_bind(tx_type)
+                                .push_bind(tx_status)
+                                .push_bind(block.hash.clone())
+                                .push_bind(block_height)
+                                .push_bind(index)
+                                .push_bind(lane_id)
+                                .push_bind(identity);
+                        },
+                    );
+                    query_builder.push(" ON CONFLICT(tx_hash, parent_dp_hash) DO UPDATE SET ");
+                    query_builder.push("block_hash=EXCLUDED.block_hash, block_height=EXCLUDED.block_height, \"index\"=EXCLUDED.\"index\", lane_id=EXCLUDED.lane_id, identity=EXCLUDED.identity,");
+                    // This data comes from post-consensus so always erase earlier statuses, but not later ones.
+                    query_builder.push(
+                        "transaction_status = case
+                            when transactions.transaction_status IS NULL THEN EXCLUDED.transaction_status
+                            when transactions.transaction_status = 'waiting_dissemination' THEN EXCLUDED.transaction_status
+                            when transactions.transaction_status = 'data_proposal_created' THEN EXCLUDED.transaction_status
+                            else transactions.transaction_status
+                        end",
+                    );
 
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO blobs (tx_hash, parent_dp_hash, blob_index, identity, contract_name, data, verified) ",
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting transactions batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
+            }
+
+            // Insert blobs into the database with batching
+            if !self.handler_store.tx_data.is_empty() {
+                const TX_DATA_PARAMS: usize = 7; // tx_hash, parent_dp_hash, blob_index, identity, contract_name, data, verified
+                let blob_batch_size = calculate_optimal_batch_size(max_params, TX_DATA_PARAMS);
+
+                let tx_data = std::mem::take(&mut self.handler_store.tx_data);
+                let chunks: Vec<_> = tx_data.chunks(blob_batch_size).collect();
+
+                info!(
+                    "Inserting {} blobs in {} batches of up to {} items each (calculated from {} params per item)",
+                    tx_data.len(),
+                    chunks.len(),
+                    blob_batch_size,
+                    TX_DATA_PARAMS
                 );
 
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxDataStore {
-                        tx_hash,
-                        parent_data_proposal_hash,
-                        blob_index,
-                        identity,
-                        contract_name,
-                        blob_data,
-                        verified,
-                    } = s;
-
-                    b.push_bind(tx_hash)
-                        .push_bind(parent_data_proposal_hash)
-                        .push_bind(blob_index)
-                        .push_bind(identity)
-                        .push_bind(contract_name)
-                        .push_bind(blob_data)
-                        .push_bind(verified);
-                });
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO blobs (tx_hash, parent_dp_hash, blob_index, identity, contract_name, data, verified) ",
+                    );
 
-                query_builder.push(" ON CONFLICT DO NOTHING");
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxDataStore {
+                            tx_hash,
+                            parent_data_proposal_hash,
+                            blob_index,
+                            identity,
+                            contract_name,
+                            blob_data,
+                            verified,
+                        } = s;
+
+                        b.push_bind(tx_hash)
+                            .push_bind(parent_data_proposal_hash)
+                            .push_bind(blob_index)
+                            .push_bind(identity)
+                            .push_bind(contract_name)
+                            .push_bind(blob_data)
+                            .push_bind(verified);
+                    });
 
-                query_builder
-                    .build()
-                    .execute(&mut *transaction)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting blobs batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
-            }
+                    query_builder.push(" ON CONFLICT DO NOTHING");
 
-            // Insert txs_contracts with batching
-            const TX_CONTRACTS_PARAMS: usize = 3; // tx_hash, parent_dp_hash, contract_name
-            let tx_contracts_batch_size = calculate_optimal_batch_size(TX_CONTRACTS_PARAMS);
-            let tx_contracts_chunks: Vec<_> = tx_data.chunks(tx_contracts_batch_size).collect();
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting blobs batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
 
-            for (batch_idx, chunk) in tx_contracts_chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO txs_contracts (tx_hash, parent_dp_hash, contract_name) ",
-                );
+                // Insert txs_contracts with batching
+                const TX_CONTRACTS_PARAMS: usize = 3; // tx_hash, parent_dp_hash, contract_name
+                let tx_contracts_batch_size =
+                    calculate_optimal_batch_size(max_params, TX_CONTRACTS_PARAMS);
+                let tx_contracts_chunks: Vec<_> = tx_data.chunks(tx_contracts_batch_size).collect();
 
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxDataStore {
-                        tx_hash,
-                        parent_data_proposal_hash,
-                        contract_name,
-                        ..
-                    } = s;
+                for (batch_idx, chunk) in tx_contracts_chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO txs_contracts (tx_hash, parent_dp_hash, contract_name) ",
+                    );
 
-                    b.push_bind(tx_hash)
-                        .push_bind(parent_data_proposal_hash)
-                        .push_bind(contract_name);
-                });
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxDataStore {
+                            tx_hash,
+                            parent_data_proposal_hash,
+                            contract_name,
+                            ..
+                        } = s;
 
-                query_builder.push(" ON CONFLICT DO NOTHING");
-                query_builder
-                    .build()
-                    .execute(&mut *transaction)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting txs_contracts batch {} of {}",
-                            batch_idx + 1,
-                            tx_contracts_chunks.len()
-                        )
-                    })?;
-            }
-        }
+                        b.push_bind(tx_hash)
+                            .push_bind(parent_data_proposal_hash)
+                            .push_bind(contract_name);
+                    });
 
-        // Insert proofs into the database with batching
-        if !self.handler_store.tx_data_proofs.is_empty() {
-            const PROOFS_PARAMS: usize = 3; // parent_dp_hash, tx_hash, proof
-            let proofs_batch_size = calculate_optimal_batch_size(PROOFS_PARAMS);
-            let tx_data_proofs = std::mem::take(&mut self.handler_store.tx_data_proofs);
-            let chunks: Vec<_> = tx_data_proofs.chunks(proofs_batch_size).collect();
+                    query_builder.push(" ON CONFLICT DO NOTHING");
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting txs_contracts batch {} of {}",
+                                batch_idx + 1,
+                                tx_contracts_chunks.len()
+                            )
+                        })?;
+                }
+            }
 
-            info!(
-                "Inserting {} proofs in {} batches of up to {} items each (calculated from {} params per item)",
-                tx_data_proofs.len(),
-                chunks.len(),
-                proofs_batch_size,
-                PROOFS_PARAMS
-            );
+            // Insert proofs into the database with batching
+            if !self.handler_store.tx_data_proofs.is_empty() {
+                const PROOFS_PARAMS: usize = 3; // parent_dp_hash, tx_hash, proof
+                let proofs_batch_size = calculate_optimal_batch_size(max_params, PROOFS_PARAMS);
+                let tx_data_proofs = std::mem::take(&mut self.handler_store.tx_data_proofs);
+                let chunks: Vec<_> = tx_data_proofs.chunks(proofs_batch_size).collect();
 
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO proofs (parent_dp_hash, tx_hash, proof) ",
+                info!(
+                    "Inserting {} proofs in {} batches of up to {} items each (calculated from {} params per item)",
+                    tx_data_proofs.len(),
+                    chunks.len(),
+                    proofs_batch_size,
+                    PROOFS_PARAMS
                 );
 
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxProofStore {
-                        tx_hash,
-                        parent_data_proposal_hash,
-                        proof,
-                    } = s;
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder =
+                        QueryBuilder::new("INSERT INTO proofs (parent_dp_hash, tx_hash, proof) ");
 
-                    b.push_bind(parent_data_proposal_hash)
-                        .push_bind(tx_hash)
-                        .push_bind(proof);
-                });
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxProofStore {
+                            tx_hash,
+                            parent_data_proposal_hash,
+                            proof,
+                        } = s;
 
-                query_builder.push(" ON CONFLICT(parent_dp_hash, tx_hash) DO NOTHING");
+                        b.push_bind(parent_data_proposal_hash)
+                            .push_bind(tx_hash)
+                            .push_bind(proof);
+                    });
 
-                query_builder
-                    .build()
-                    .execute(&mut *transaction)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting proofs batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
-            }
-        }
+                    query_builder.push(" ON CONFLICT(parent_dp_hash, tx_hash) DO NOTHING");
 
-        // Insert transaction events into the database with batching
-        if !self.handler_store.transactions_events.is_empty() {
-            const TX_EVENTS_PARAMS: usize = 6; // block_hash, block_height, index, tx_hash, parent_dp_hash, events
-            let tx_events_batch_size = calculate_optimal_batch_size(TX_EVENTS_PARAMS);
-            let transactions_events = std::mem::take(&mut self.handler_store.transactions_events);
-            let chunks: Vec<_> = transactions_events.chunks(tx_events_batch_size).collect();
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting proofs batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
+            }
 
-            info!(
-                "Inserting {} transaction events in {} batches of up to {} items each (calculated from {} params per item)",
-                transactions_events.len(),
-                chunks.len(),
-                tx_events_batch_size,
-                TX_EVENTS_PARAMS
-            );
+            // Insert transaction events into the database with batching
+            if !self.handler_store.transactions_events.is_empty() {
+                const TX_EVENTS_PARAMS: usize = 6; // block_hash, block_height, index, tx_hash, parent_dp_hash, events
+                let tx_events_batch_size =
+                    calculate_optimal_batch_size(max_params, TX_EVENTS_PARAMS);
+                let transactions_events =
+                    std::mem::take(&mut self.handler_store.transactions_events);
+                let chunks: Vec<_> = transactions_events.chunks(tx_events_batch_size).collect();
 
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO transaction_state_events (block_hash, block_height, index, tx_hash, parent_dp_hash, events) ",
+                info!(
+                    "Inserting {} transaction events in {} batches of up to {} items each (calculated from {} params per item)",
+                    transactions_events.len(),
+                    chunks.len(),
+                    tx_events_batch_size,
+                    TX_EVENTS_PARAMS
                 );
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxEventStore {
-                        block_hash,
-                        block_height,
-                        index,
-                        tx_hash,
-                        parent_data_proposal_hash,
-                        events,
-                    } = s;
-
-                    b.push_bind(block_hash)
-                        .push_bind(block_height)
-                        .push_bind(index)
-                        .push_bind(tx_hash)
-                        .push_bind(parent_data_proposal_hash)
-                        .push_bind(events)
-                        .push_unseparated("::jsonb");
-                });
 
-                query_builder
-                    .build()
-                    .execute(&mut *transaction)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting transaction events batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
-            }
-        }
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO transaction_state_events (block_hash, block_height, \"index\", tx_hash, parent_dp_hash, events) ",
+                    );
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxEventStore {
+                            block_hash,
+                            block_height,
+                            index,
+                            tx_hash,
+                            parent_data_proposal_hash,
+                            events,
+                        } = s;
+
+                        b.push_bind(block_hash)
+                            .push_bind(block_height)
+                            .push_bind(index)
+                            .push_bind(tx_hash)
+                            .push_bind(parent_data_proposal_hash)
+                            .push_bind(events)
+                            .push_unseparated(json_cast);
+                    });
 
-        // Insert contracts into the database with batching
-        if !self.handler_store.contracts.is_empty() {
-            const CONTRACTS_PARAMS: usize = 6; // tx_hash, parent_dp_hash, verifier, program_id, state_commitment, contract_name
-            let contracts_batch_size = calculate_optimal_batch_size(CONTRACTS_PARAMS);
-            let contracts = std::mem::take(&mut self.handler_store.contracts);
-            let contracts_vec: Vec<_> = contracts.into_values().collect();
-            let chunks: Vec<_> = contracts_vec.chunks(contracts_batch_size).collect();
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting transaction events batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
+            }
 
-            info!(
-                "Inserting {} contracts in {} batches of up to {} items each (calculated from {} params per item)",
-                contracts_vec.len(),
-                chunks.len(),
-                contracts_batch_size,
-                CONTRACTS_PARAMS
-            );
+            // Insert contracts into the database with batching
+            if !self.handler_store.contracts.is_empty() {
+                const CONTRACTS_PARAMS: usize = 6; // tx_hash, parent_dp_hash, verifier, program_id, state_commitment, contract_name
+                let contracts_batch_size =
+                    calculate_optimal_batch_size(max_params, CONTRACTS_PARAMS);
+                let contracts = std::mem::take(&mut self.handler_store.contracts);
+                let contracts_vec: Vec<_> = contracts.into_values().collect();
+                let chunks: Vec<_> = contracts_vec.chunks(contracts_batch_size).collect();
 
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO contracts (tx_hash, parent_dp_hash, verifier, program_id, timeout_window, state_commitment, contract_name) ",
+                info!(
+                    "Inserting {} contracts in {} batches of up to {} items each (calculated from {} params per item)",
+                    contracts_vec.len(),
+                    chunks.len(),
+                    contracts_batch_size,
+                    CONTRACTS_PARAMS
                 );
 
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxContractStore {
-                        tx_hash,
-                        parent_data_proposal_hash,
-                        verifier,
-                        program_id,
-                        timeout_window,
-                        state_commitment,
-                        contract_name,
-                    } = s;
-
-                    b.push_bind(tx_hash)
-                        .push_bind(parent_data_proposal_hash)
-                        .push_bind(verifier)
-                        .push_bind(program_id)
-                        .push_bind(timeout_window)
-                        .push_bind(state_commitment)
-                        .push_bind(contract_name);
-                });
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO contracts (tx_hash, parent_dp_hash, verifier, program_id, timeout_window, state_commitment, contract_name) ",
+                    );
 
-                query_builder.push(" ON CONFLICT (contract_name) DO UPDATE SET ");
-                query_builder.push("tx_hash = EXCLUDED.tx_hash, ");
-                query_builder.push("parent_dp_hash = EXCLUDED.parent_dp_hash, ");
-                query_builder.push("verifier = EXCLUDED.verifier, ");
-                query_builder.push("program_id = EXCLUDED.program_id, ");
-                query_builder.push("timeout_window = EXCLUDED.timeout_window, ");
-                query_builder.push("state_commitment = EXCLUDED.state_commitment ");
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxContractStore {
+                            tx_hash,
+                            parent_data_proposal_hash,
+                            verifier,
+                            program_id,
+                            timeout_window,
+                            state_commitment,
+                            contract_name,
+                        } = s;
+
+                        b.push_bind(tx_hash)
+                            .push_bind(parent_data_proposal_hash)
+                            .push_bind(verifier)
+                            .push_bind(program_id)
+                            .push_bind(timeout_window)
+                            .push_bind(state_commitment)
+                            .push_bind(contract_name);
+                    });
 
-                query_builder
-                    .build()
-                    .execute(&mut *transaction)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting contracts batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
-            }
-        }
+                    query_builder.push(" ON CONFLICT (contract_name) DO UPDATE SET ");
+                    query_builder.push("tx_hash = EXCLUDED.tx_hash, ");
+                    query_builder.push("parent_dp_hash = EXCLUDED.parent_dp_hash, ");
+                    query_builder.push("verifier = EXCLUDED.verifier, ");
+                    query_builder.push("program_id = EXCLUDED.program_id, ");
+                    query_builder.push("timeout_window = EXCLUDED.timeout_window, ");
+                    query_builder.push("state_commitment = EXCLUDED.state_commitment ");
 
-        // Insert contract states into the database with batching
-        if !self.handler_store.contract_states.is_empty() {
-            const CONTRACT_STATES_PARAMS: usize = 3; // contract_name, block_hash, state_commitment
-            let contract_states_batch_size = calculate_optimal_batch_size(CONTRACT_STATES_PARAMS);
-            let contract_states = std::mem::take(&mut self.handler_store.contract_states);
-            let chunks: Vec<_> = contract_states.chunks(contract_states_batch_size).collect();
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting contracts batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
+            }
 
-            info!(
-                "Inserting {} contract states in {} batches of up to {} items each (calculated from {} params per item)",
-                contract_states.len(),
-                chunks.len(),
-                contract_states_batch_size,
-                CONTRACT_STATES_PARAMS
-            );
+            // Insert contract states into the database with batching
+            if !self.handler_store.contract_states.is_empty() {
+                const CONTRACT_STATES_PARAMS: usize = 3; // contract_name, block_hash, state_commitment
+                let contract_states_batch_size =
+                    calculate_optimal_batch_size(max_params, CONTRACT_STATES_PARAMS);
+                let contract_states = std::mem::take(&mut self.handler_store.contract_states);
+                let chunks: Vec<_> = contract_states.chunks(contract_states_batch_size).collect();
 
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO contract_state (contract_name, block_hash, state_commitment) ",
+                info!(
+                    "Inserting {} contract states in {} batches of up to {} items each (calculated from {} params per item)",
+                    contract_states.len(),
+                    chunks.len(),
+                    contract_states_batch_size,
+                    CONTRACT_STATES_PARAMS
                 );
 
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxContractStateStore {
-                        contract_name,
-                        block_hash,
-                        state_commitment,
-                    } = s;
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO contract_state (contract_name, block_hash, state_commitment) ",
+                    );
 
-                    b.push_bind(contract_name)
-                        .push_bind(block_hash)
-                        .push_bind(state_commitment);
-                });
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxContractStateStore {
+                            contract_name,
+                            block_hash,
+                            state_commitment,
+                        } = s;
+
+                        b.push_bind(contract_name)
+                            .push_bind(block_hash)
+                            .push_bind(state_commitment);
+                    });
 
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting contract states batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
+            }
+
+            if !self.handler_store.contract_code_versions.is_empty() {
+                let mut query_builder = QueryBuilder::new(
+                    "INSERT INTO contract_history (contract_name, block_hash, effective_height, verifier, program_id) ",
+                );
+                query_builder.push_values(
+                    self.handler_store.contract_code_versions.drain(..),
+                    |mut b, version| {
+                        b.push_bind(version.contract_name)
+                            .push_bind(version.block_hash)
+                            .push_bind(version.effective_height)
+                            .push_bind(version.verifier)
+                            .push_bind(version.program_id);
+                    },
+                );
                 query_builder
+                    .push(" ON CONFLICT (contract_name, effective_height) DO NOTHING")
                     .build()
                     .execute(&mut *transaction)
                     .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting contract states batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
+                    .context("Inserting contract history")?;
             }
-        }
 
-        // Then delete contracts that were deleted (slightly inefficient but we don't expect many deletions)
-        for contract_name in self.handler_store.deleted_contracts.drain() {
-            sqlx::query("DELETE FROM contracts WHERE contract_name = $1")
-                .bind(contract_name.0)
-                .execute(&mut *transaction)
-                .await
-                .context("Deleting contracts")?;
-        }
-
-        // Insert blob proof outputs into the database with batching
-        if !self.handler_store.blob_proof_outputs.is_empty() {
-            const BLOB_PROOF_OUTPUT_PARAMS: usize = 9; // proof_tx_hash, proof_parent_dp_hash, blob_tx_hash, blob_parent_dp_hash, blob_index, blob_proof_output_index, contract_name, hyle_output, settled
-            let blob_proof_outputs_batch_size =
-                calculate_optimal_batch_size(BLOB_PROOF_OUTPUT_PARAMS);
+            // Then delete contracts that were deleted (slightly inefficient but we don't expect many deletions)
+            for contract_name in self.handler_store.deleted_contracts.drain() {
+                sqlx::query("DELETE FROM contracts WHERE contract_name = $1")
+                    .bind(contract_name.0)
+                    .execute(&mut *transaction)
+                    .await
+                    .context("Deleting contracts")?;
+            }
 
-            let blob_proof_outputs = std::mem::take(&mut self.handler_store.blob_proof_outputs);
-            let chunks: Vec<_> = blob_proof_outputs
-                .chunks(blob_proof_outputs_batch_size)
-                .collect();
+            // Insert blob proof outputs into the database with batching
+            if !self.handler_store.blob_proof_outputs.is_empty() {
+                const BLOB_PROOF_OUTPUT_PARAMS: usize = 9; // proof_tx_hash, proof_parent_dp_hash, blob_tx_hash, blob_parent_dp_hash, blob_index, blob_proof_output_index, contract_name, hyle_output, settled
+                let blob_proof_outputs_batch_size =
+                    calculate_optimal_batch_size(max_params, BLOB_PROOF_OUTPUT_PARAMS);
 
-            info!(
-                "Inserting {} blob proof outputs in {} batches of up to {} items each (calculated from {} params per item)",
-                blob_proof_outputs.len(),
-                chunks.len(),
-                blob_proof_outputs_batch_size,
-                BLOB_PROOF_OUTPUT_PARAMS
-            );
+                let blob_proof_outputs = std::mem::take(&mut self.handler_store.blob_proof_outputs);
+                let chunks: Vec<_> = blob_proof_outputs
+                    .chunks(blob_proof_outputs_batch_size)
+                    .collect();
 
-            for (batch_idx, chunk) in chunks.iter().enumerate() {
-                let mut query_builder = QueryBuilder::<Postgres>::new(
-                    "INSERT INTO blob_proof_outputs (proof_tx_hash, proof_parent_dp_hash, blob_tx_hash, blob_parent_dp_hash, blob_index, blob_proof_output_index, contract_name, hyle_output, settled) ",
+                info!(
+                    "Inserting {} blob proof outputs in {} batches of up to {} items each (calculated from {} params per item)",
+                    blob_proof_outputs.len(),
+                    chunks.len(),
+                    blob_proof_outputs_batch_size,
+                    BLOB_PROOF_OUTPUT_PARAMS
                 );
 
-                query_builder.push_values(chunk.iter(), |mut b, s| {
-                    let TxBlobProofOutputStore {
-                        proof_tx_hash,
-                        proof_parent_dp_hash,
+                for (batch_idx, chunk) in chunks.iter().enumerate() {
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO blob_proof_outputs (proof_tx_hash, proof_parent_dp_hash, blob_tx_hash, blob_parent_dp_hash, blob_index, blob_proof_output_index, contract_name, hyle_output, settled) ",
+                    );
+
+                    query_builder.push_values(chunk.iter(), |mut b, s| {
+                        let TxBlobProofOutputStore {
+                            proof_tx_hash,
+                            proof_parent_dp_hash,
+                            blob_tx_hash,
+                            blob_parent_dp_hash,
+                            blob_index,
+                            blob_proof_output_index,
+                            contract_name,
+                            hyle_output,
+                            settled,
+                        } = s;
+
+                        b.push_bind(proof_tx_hash)
+                            .push_bind(proof_parent_dp_hash)
+                            .push_bind(blob_tx_hash)
+                            .push_bind(blob_parent_dp_hash)
+                            .push_bind(blob_index)
+                            .push_bind(blob_proof_output_index)
+                            .push_bind(contract_name)
+                            .push_bind(hyle_output)
+                            .push_unseparated(json_cast)
+                            .push_bind(settled);
+                    });
+
+                    query_builder
+                        .build()
+                        .execute(&mut *transaction)
+                        .await
+                        .with_context(|| {
+                            format!(
+                                "Inserting blob proof outputs batch {} of {}",
+                                batch_idx + 1,
+                                chunks.len()
+                            )
+                        })?;
+                }
+            }
+
+            for sql_update in self.handler_store.sql_updates.drain(..) {
+                let query = match sql_update {
+                    SqlUpdate::TransactionStatus {
+                        tx_hash,
+                        parent_dp_hash,
+                        status,
+                    } => sqlx::query("UPDATE transactions SET transaction_status = $1 WHERE tx_hash = $2 AND parent_dp_hash = $3")
+                        .bind(status)
+                        .bind(tx_hash)
+                        .bind(parent_dp_hash),
+                    SqlUpdate::BlobVerified {
+                        tx_hash,
+                        parent_dp_hash,
+                        blob_index,
+                    } => sqlx::query("UPDATE blobs SET verified = true WHERE tx_hash = $1 AND parent_dp_hash = $2 AND blob_index = $3")
+                        .bind(tx_hash)
+                        .bind(parent_dp_hash)
+                        .bind(blob_index),
+                    SqlUpdate::BlobProofOutputSettled {
                         blob_tx_hash,
                         blob_parent_dp_hash,
                         blob_index,
                         blob_proof_output_index,
+                    } => sqlx::query("UPDATE blob_proof_outputs SET settled = true WHERE blob_tx_hash = $1 AND blob_parent_dp_hash = $2 AND blob_index = $3 AND blob_proof_output_index = $4")
+                        .bind(blob_tx_hash)
+                        .bind(blob_parent_dp_hash)
+                        .bind(blob_index)
+                        .bind(blob_proof_output_index),
+                    SqlUpdate::ContractStateAtBlock {
                         contract_name,
-                        hyle_output,
-                        settled,
-                    } = s;
-
-                    b.push_bind(proof_tx_hash)
-                        .push_bind(proof_parent_dp_hash)
-                        .push_bind(blob_tx_hash)
-                        .push_bind(blob_parent_dp_hash)
-                        .push_bind(blob_index)
-                        .push_bind(blob_proof_output_index)
-                        .push_bind(contract_name)
-                        .push_bind(hyle_output)
-                        .push_unseparated("::jsonb")
-                        .push_bind(settled);
-                });
-
-                query_builder
-                    .build()
-                    .execute(&mut *transaction)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Inserting blob proof outputs batch {} of {}",
-                            batch_idx + 1,
-                            chunks.len()
-                        )
-                    })?;
-            }
-        }
-
-        if !self.handler_store.sql_updates.is_empty() {
-            for sql_update in self.handler_store.sql_updates.drain(..) {
-                sql_update
+                        block_hash,
+                        state_commitment,
+                    } => sqlx::query("UPDATE contract_state SET state_commitment = $1 WHERE contract_name = $2 AND block_hash = $3")
+                        .bind(state_commitment)
+                        .bind(contract_name)
+                        .bind(block_hash),
+                    SqlUpdate::ContractStateCommitment {
+                        contract_name,
+                        state_commitment,
+                    } => sqlx::query("UPDATE contracts SET state_commitment = $1 WHERE contract_name = $2")
+                        .bind(state_commitment)
+                        .bind(contract_name),
+                    SqlUpdate::ContractProgramId {
+                        contract_name,
+                        program_id,
+                    } => sqlx::query("UPDATE contracts SET program_id = $1 WHERE contract_name = $2")
+                        .bind(program_id)
+                        .bind(contract_name),
+                    SqlUpdate::ContractTimeoutWindow {
+                        contract_name,
+                        timeout_window,
+                    } => sqlx::query("UPDATE contracts SET timeout_window = $1 WHERE contract_name = $2")
+                        .bind(timeout_window)
+                        .bind(contract_name),
+                };
+                query
                     .execute(&mut *transaction)
                     .await
                     .context("Executing SQL update")?;
             }
-        }
 
-        transaction.commit().await?;
+            transaction.commit().await?;
+        });
 
         Ok(())
     }
 
     pub async fn handle_mempool_status_event(&mut self, event: MempoolStatusEvent) -> Result<()> {
-        let mut transaction = self.state.db.begin().await?;
-        match event {
-            MempoolStatusEvent::WaitingDissemination {
-                parent_data_proposal_hash,
-                tx,
-            } => {
-                let parent_data_proposal_hash_db: DataProposalHashDb =
-                    parent_data_proposal_hash.into();
-                let tx_hash: TxHash = tx.hashed();
-                let version = i32::try_from(tx.version)
-                    .map_err(|_| anyhow::anyhow!("Tx version is too large to fit into an i32"))?;
-
-                // Insert the transaction into the transactions table
-                let tx_type = TransactionTypeDb::from(&tx);
-                let tx_hash: &TxHashDb = &tx_hash.into();
+        with_db!(self.state.db.clone(), pool => {
+            let mut transaction = pool.begin().await?;
+            match event {
+                MempoolStatusEvent::WaitingDissemination {
+                    parent_data_proposal_hash,
+                    tx,
+                } => {
+                    let parent_data_proposal_hash_db: DataProposalHashDb =
+                        parent_data_proposal_hash.into();
+                    let tx_hash: TxHash = tx.hashed();
+                    let version = i32::try_from(tx.version).map_err(|_| {
+                        anyhow::anyhow!("Tx version is too large to fit into an i32")
+                    })?;
 
-                info!(
-                    "Inserting waiting_dissemination TX {} with parent data proposal hash {}",
-                    tx_hash.0, parent_data_proposal_hash_db.0
-                );
+                    // Insert the transaction into the transactions table
+                    let tx_type = TransactionTypeDb::from(&tx);
+                    let tx_hash: &TxHashDb = &tx_hash.into();
 
-                // If the TX is already present, we can assume it's more up-to-date so do nothing.
-                sqlx::query(
-                    "INSERT INTO transactions (tx_hash, parent_dp_hash, version, transaction_type, transaction_status)
-                    VALUES ($1, $2, $3, $4, 'waiting_dissemination')
-                    ON CONFLICT(tx_hash, parent_dp_hash) DO NOTHING",
-                )
-                    .bind(tx_hash)
-                    .bind(parent_data_proposal_hash_db.clone())
-                    .bind(version)
-                    .bind(tx_type)
-                    .execute(&mut *transaction)
-                    .await?;
+                    info!(
+                        "Inserting waiting_dissemination TX {} with parent data proposal hash {}",
+                        tx_hash.0, parent_data_proposal_hash_db.0
+                    );
 
-                _ = log_warn!(
-                    self.insert_tx_data(tx_hash, &tx, parent_data_proposal_hash_db,),
-                    "Inserting tx data at status 'waiting dissemination'"
-                );
-            }
+                    // If the TX is already present, we can assume it's more up-to-date so do nothing.
+                    sqlx::query(
+                        "INSERT INTO transactions (tx_hash, parent_dp_hash, version, transaction_type, transaction_status)
+                        VALUES ($1, $2, $3, $4, 'waiting_dissemination')
+                        ON CONFLICT(tx_hash, parent_dp_hash) DO NOTHING",
+                    )
+                        .bind(tx_hash)
+                        .bind(parent_data_proposal_hash_db.clone())
+                        .bind(version)
+                        .bind(tx_type)
+                        .execute(&mut *transaction)
+                        .await?;
+
+                    _ = log_warn!(
+                        self.insert_tx_data(tx_hash, &tx, parent_data_proposal_hash_db,),
+                        "Inserting tx data at status 'waiting dissemination'"
+                    );
+                }
 
-            MempoolStatusEvent::DataProposalCreated {
-                parent_data_proposal_hash,
-                data_proposal_hash,
-                txs_metadatas,
-            } => {
-                let mut seen = HashSet::new();
-                let unique_txs_metadatas: Vec<_> = txs_metadatas
-                    .into_iter()
-                    .filter(|value| {
-                        let key = (value.id.1.clone(), value.id.0.clone());
-                        seen.insert(key)
-                    })
-                    .collect();
+                MempoolStatusEvent::DataProposalCreated {
+                    parent_data_proposal_hash,
+                    data_proposal_hash,
+                    txs_metadatas,
+                } => {
+                    let mut seen = HashSet::new();
+                    let unique_txs_metadatas: Vec<_> = txs_metadatas
+                        .into_iter()
+                        .filter(|value| {
+                            let key = (value.id.1.clone(), value.id.0.clone());
+                            seen.insert(key)
+                        })
+                        .collect();
+
+                    let mut query_builder = QueryBuilder::new(
+                        "INSERT INTO transactions (tx_hash, parent_dp_hash, version, transaction_type, transaction_status)",
+                    );
 
-                let mut query_builder = QueryBuilder::new(
-                    "INSERT INTO transactions (tx_hash, parent_dp_hash, version, transaction_type, transaction_status)",
-                );
+                    query_builder.push_values(unique_txs_metadatas, |mut b, value| {
+                        let tx_type: TransactionTypeDb = value.transaction_kind.into();
+                        let version = log_error!(
+                            i32::try_from(value.version).map_err(|_| anyhow::anyhow!(
+                                "Tx version is too large to fit into an i32"
+                            )),
+                            "Converting version number into i32"
+                        )
+                        .unwrap_or(0);
 
-                query_builder.push_values(unique_txs_metadatas, |mut b, value| {
-                    let tx_type: TransactionTypeDb = value.transaction_kind.into();
-                    let version = log_error!(
-                        i32::try_from(value.version).map_err(|_| anyhow::anyhow!(
-                            "Tx version is too large to fit into an i32"
-                        )),
-                        "Converting version number into i32"
-                    )
-                    .unwrap_or(0);
+                        let tx_hash: TxHashDb = value.id.1.into();
+                        let parent_data_proposal_hash_db: DataProposalHashDb = value.id.0.into();
 
-                    let tx_hash: TxHashDb = value.id.1.into();
-                    let parent_data_proposal_hash_db: DataProposalHashDb = value.id.0.into();
+                        info!(
+                            "Inserting data_proposal_created TX {} with parent data proposal hash {}",
+                            tx_hash.0, parent_data_proposal_hash_db.0
+                        );
 
-                    info!(
-                        "Inserting data_proposal_created TX {} with parent data proposal hash {}",
-                        tx_hash.0, parent_data_proposal_hash_db.0
-                    );
+                        b.push_bind(tx_hash)
+                            .push_bind(parent_data_proposal_hash_db)
+                            .push_bind(version)
+                            .push_bind(tx_type)
+                            .push_bind(TransactionStatusDb::DataProposalCreated);
+                    });
 
-                    b.push_bind(tx_hash)
-                        .push_bind(parent_data_proposal_hash_db)
-                        .push_bind(version)
-                        .push_bind(tx_type)
-                        .push_bind(TransactionStatusDb::DataProposalCreated);
-                });
+                    // If the TX is already present, we try to update its status, only if the status is lower ('waiting_dissemination').
+                    query_builder.push(" ON CONFLICT(tx_hash, parent_dp_hash) DO UPDATE SET ");
 
-                // If the TX is already present, we try to update its status, only if the status is lower ('waiting_dissemination').
-                query_builder.push(" ON CONFLICT(tx_hash, parent_dp_hash) DO UPDATE SET ");
+                    query_builder.push("transaction_status=");
+                    query_builder.push_bind(TransactionStatusDb::DataProposalCreated);
+                    query_builder
+                        .push(" WHERE transactions.transaction_status='waiting_dissemination'");
 
-                query_builder.push("transaction_status=");
-                query_builder.push_bind(TransactionStatusDb::DataProposalCreated);
-                query_builder
-                    .push(" WHERE transactions.transaction_status='waiting_dissemination'");
+                    query_builder
+                        .build()
+                        .execute(transaction.deref_mut())
+                        .await
+                        .context("Upserting data at status data_proposal_created")?;
 
-                query_builder
-                    .build()
-                    .execute(transaction.deref_mut())
-                    .await
-                    .context("Upserting data at status data_proposal_created")?;
-
-                // Second step - any TX that was skipped for this DP needs to have its ID updated
-                // (this should be all TXs with the same parent as us still in waiting dissemination).
-                let mut query_builder =
-                    QueryBuilder::new("UPDATE transactions SET parent_dp_hash = ");
-                let parent_data_proposal_hash_db: DataProposalHashDb =
-                    parent_data_proposal_hash.clone().into();
-                let data_proposal_hash_db: DataProposalHashDb = data_proposal_hash.clone().into();
-                query_builder
-                    .push_bind(data_proposal_hash_db.clone())
-                    .push(" WHERE parent_dp_hash = ")
-                    .push_bind(parent_data_proposal_hash_db)
-                    .push(" AND transaction_status = 'waiting_dissemination' RETURNING tx_hash");
-                let txs = query_builder
-                    .build()
-                    .fetch_all(transaction.deref_mut())
-                    .await
-                    .context("Updating parent data proposal hash")?;
-                // Then we need to update blobs
-                let tx_hashes = txs
-                    .iter()
-                    .filter_map(|row| {
-                        if let Ok(tx_hash) = row.try_get::<TxHashDb, _>(0) {
-                            self.handler_store.tx_data.iter_mut().for_each(|tx_data| {
-                                if tx_data.tx_hash == tx_hash
-                                    && tx_data.parent_data_proposal_hash.0
-                                        == parent_data_proposal_hash
-                                {
-                                    tx_data.parent_data_proposal_hash =
-                                        data_proposal_hash_db.clone();
-                                }
-                            });
-                            return Some(tx_hash);
-                        }
-                        None
-                    })
-                    .collect::<Vec<_>>();
-                if !tx_hashes.is_empty() {
-                    let mut query_builder = QueryBuilder::new("UPDATE blobs SET parent_dp_hash = ");
+                    // Second step - any TX that was skipped for this DP needs to have its ID updated
+                    // (this should be all TXs with the same parent as us still in waiting dissemination).
+                    let mut query_builder =
+                        QueryBuilder::new("UPDATE transactions SET parent_dp_hash = ");
                     let parent_data_proposal_hash_db: DataProposalHashDb =
-                        parent_data_proposal_hash.into();
-                    let data_proposal_hash_db: DataProposalHashDb = data_proposal_hash.into();
-
-                    info!(
-                        "Updating skipped TXs with parent data proposal hash {} to new DP hash {}: {:?}",
-                        parent_data_proposal_hash_db.0, data_proposal_hash_db.0, tx_hashes
-                    );
-
+                        parent_data_proposal_hash.clone().into();
+                    let data_proposal_hash_db: DataProposalHashDb =
+                        data_proposal_hash.clone().into();
                     query_builder
                         .push_bind(data_proposal_hash_db.clone())
                         .push(" WHERE parent_dp_hash = ")
                         .push_bind(parent_data_proposal_hash_db)
-                        .push(" AND tx_hash in ");
-                    query_builder.push_tuples(tx_hashes, |mut b, tx_hash| {
-                        b.push_bind(tx_hash);
-                    });
-                    query_builder
+                        .push(
+                            " AND transaction_status = 'waiting_dissemination' RETURNING tx_hash",
+                        );
+                    let txs = query_builder
                         .build()
-                        .execute(transaction.deref_mut())
+                        .fetch_all(transaction.deref_mut())
                         .await
                         .context("Updating parent data proposal hash")?;
+                    // Then we need to update blobs
+                    let tx_hashes = txs
+                        .iter()
+                        .filter_map(|row| {
+                            if let Ok(tx_hash) = row.try_get::<TxHashDb, _>(0) {
+                                self.handler_store.tx_data.iter_mut().for_each(|tx_data| {
+                                    if tx_data.tx_hash == tx_hash
+                                        && tx_data.parent_data_proposal_hash.0
+                                            == parent_data_proposal_hash
+                                    {
+                                        tx_data.parent_data_proposal_hash =
+                                            data_proposal_hash_db.clone();
+                                    }
+                                });
+                                return Some(tx_hash);
+                            }
+                            None
+                        })
+                        .collect::<Vec<_>>();
+                    if !tx_hashes.is_empty() {
+                        let mut query_builder =
+                            QueryBuilder::new("UPDATE blobs SET parent_dp_hash = ");
+                        let parent_data_proposal_hash_db: DataProposalHashDb =
+                            parent_data_proposal_hash.into();
+                        let data_proposal_hash_db: DataProposalHashDb = data_proposal_hash.into();
+
+                        info!(
+                            "Updating skipped TXs with parent data proposal hash {} to new DP hash {}: {:?}",
+                            parent_data_proposal_hash_db.0, data_proposal_hash_db.0, tx_hashes
+                        );
+
+                        query_builder
+                            .push_bind(data_proposal_hash_db.clone())
+                            .push(" WHERE parent_dp_hash = ")
+                            .push_bind(parent_data_proposal_hash_db)
+                            .push(" AND tx_hash in ");
+                        query_builder.push_tuples(tx_hashes, |mut b, tx_hash| {
+                            b.push_bind(tx_hash);
+                        });
+                        query_builder
+                            .build()
+                            .execute(transaction.deref_mut())
+                            .await
+                            .context("Updating parent data proposal hash")?;
+                    }
+                }
+
+                // Never reached the mempool, so was never inserted.
+                MempoolStatusEvent::Rejected { .. } => {}
+
+                MempoolStatusEvent::Evicted { tx_hash } => {
+                    let tx_hash: TxHashDb = tx_hash.into();
+                    info!("Removing TX {} evicted from the mempool", tx_hash.0);
+
+                    self.handler_store
+                        .tx_data
+                        .retain(|tx_data| tx_data.tx_hash != tx_hash);
+                    // Blobs and other tx data are removed by cascade.
+                    sqlx::query(
+                        "DELETE FROM transactions WHERE tx_hash = $1 AND transaction_status = 'waiting_dissemination'",
+                    )
+                    .bind(tx_hash)
+                    .execute(&mut *transaction)
+                    .await
+                    .context("Deleting evicted TX")?;
                 }
             }
-        }
 
-        transaction.commit().await?;
+            transaction.commit().await?;
+        });
 
         Ok(())
     }
@@ -1017,12 +1173,13 @@ impl Indexer {
                 .clone()
                 .into();
             let tx_hash: TxHashDb = settled_blob_tx_hash.into();
-            self.handler_store.sql_updates.push(
-                sqlx::query("UPDATE transactions SET transaction_status = $1 WHERE tx_hash = $2 AND parent_dp_hash = $3")
-                    .bind(TransactionStatusDb::Success)
-                    .bind(tx_hash)
-                    .bind(dp_hash_db)
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::TransactionStatus {
+                    tx_hash,
+                    parent_dp_hash: dp_hash_db,
+                    status: TransactionStatusDb::Success,
+                });
         }
 
         for failed_blob_tx_hash in block.failed_txs {
@@ -1036,12 +1193,13 @@ impl Indexer {
                 .clone()
                 .into();
             let tx_hash: TxHashDb = failed_blob_tx_hash.into();
-            self.handler_store.sql_updates.push(
-                sqlx::query("UPDATE transactions SET transaction_status = $1 WHERE tx_hash = $2 AND parent_dp_hash = $3")
-                    .bind(TransactionStatusDb::Failure)
-                    .bind(tx_hash)
-                    .bind(dp_hash_db)
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::TransactionStatus {
+                    tx_hash,
+                    parent_dp_hash: dp_hash_db,
+                    status: TransactionStatusDb::Failure,
+                });
         }
 
         // Handling timed out blob transactions
@@ -1056,12 +1214,13 @@ impl Indexer {
                 .clone()
                 .into();
             let tx_hash: TxHashDb = timed_out_tx_hash.into();
-            self.handler_store.sql_updates.push(
-                sqlx::query("UPDATE transactions SET transaction_status = $1 WHERE tx_hash = $2 AND parent_dp_hash = $3")
-                    .bind(TransactionStatusDb::TimedOut)
-                    .bind(tx_hash)
-                    .bind(dp_hash_db)
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::TransactionStatus {
+                    tx_hash,
+                    parent_dp_hash: dp_hash_db,
+                    status: TransactionStatusDb::TimedOut,
+                });
         }
 
         for handled_blob_proof_output in block.blob_proof_outputs {
@@ -1124,12 +1283,13 @@ impl Indexer {
             let blob_index = i32::try_from(blob_index.0)
                 .map_err(|_| anyhow::anyhow!("Blob index is too large to fit into an i32"))?;
 
-            self.handler_store.sql_updates.push(
-                sqlx::query("UPDATE blobs SET verified = true WHERE tx_hash = $1 AND parent_dp_hash = $2 AND blob_index = $3")
-                    .bind(blob_tx_hash.clone())
-                    .bind(blob_tx_parent_dp_hash.clone())
-                    .bind(blob_index)
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::BlobVerified {
+                    tx_hash: blob_tx_hash.clone(),
+                    parent_dp_hash: blob_tx_parent_dp_hash.clone(),
+                    blob_index,
+                });
 
             if let Some(blob_proof_output_index) = blob_proof_output_index {
                 let blob_proof_output_index =
@@ -1137,13 +1297,14 @@ impl Indexer {
                         anyhow::anyhow!("Blob proof output index is too large to fit into an i32")
                     })?;
 
-                self.handler_store.sql_updates.push(
-                    sqlx::query("UPDATE blob_proof_outputs SET settled = true WHERE blob_tx_hash = $1 AND blob_parent_dp_hash = $2 AND blob_index = $3 AND blob_proof_output_index = $4")
-                        .bind(blob_tx_hash)
-                        .bind(blob_tx_parent_dp_hash)
-                        .bind(blob_index)
-                        .bind(blob_proof_output_index)
-                );
+                self.handler_store
+                    .sql_updates
+                    .push(SqlUpdate::BlobProofOutputSettled {
+                        blob_tx_hash,
+                        blob_parent_dp_hash: blob_tx_parent_dp_hash,
+                        blob_index,
+                        blob_proof_output_index,
+                    });
             }
         }
 
@@ -1203,22 +1364,34 @@ impl Indexer {
         for (contract_name, state_commitment) in block.updated_states {
             let contract_name = contract_name.0;
             let state_commitment = state_commitment.0;
-            self.handler_store.sql_updates.push(
-                sqlx::query(
-                    "UPDATE contract_state SET state_commitment = $1 WHERE contract_name = $2 AND block_hash = $3",
-                )
-                    .bind(state_commitment.clone())
-                    .bind(contract_name.clone())
-                    .bind(block.hash.clone())
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::ContractStateAtBlock {
+                    contract_name: contract_name.clone(),
+                    block_hash: block.hash.clone(),
+                    state_commitment: state_commitment.clone(),
+                });
 
-            self.handler_store.sql_updates.push(
-                sqlx::query::<Postgres>(
-                    "UPDATE contracts SET state_commitment = $1 WHERE contract_name = $2",
-                )
-                .bind(state_commitment)
-                .bind(contract_name),
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::ContractStateCommitment {
+                    contract_name,
+                    state_commitment,
+                });
+        }
+
+        for (contract_name, version) in block.contract_code_versions.iter() {
+            let effective_height = i64::try_from(version.effective_height.0)
+                .map_err(|_| anyhow::anyhow!("Effective height is too large to fit into an i64"))?;
+            self.handler_store
+                .contract_code_versions
+                .push(ContractCodeVersionStore {
+                    contract_name: contract_name.0.clone(),
+                    block_hash: block.hash.clone(),
+                    effective_height,
+                    verifier: version.verifier.0.clone(),
+                    program_id: version.program_id.0.clone(),
+                });
         }
 
         // Handling updated contract program ids
@@ -1226,13 +1399,12 @@ impl Indexer {
             let contract_name = contract_name.0;
             let program_id = program_id.0;
 
-            self.handler_store.sql_updates.push(
-                sqlx::query::<Postgres>(
-                    "UPDATE contracts SET program_id = $1 WHERE contract_name = $2",
-                )
-                .bind(program_id)
-                .bind(contract_name),
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::ContractProgramId {
+                    contract_name,
+                    program_id,
+                });
         }
 
         // Handling updated contract program ids
@@ -1240,13 +1412,12 @@ impl Indexer {
             let contract_name = contract_name.0;
 
             let timeout_window_db: TimeoutWindowDb = timeout_window.into();
-            self.handler_store.sql_updates.push(
-                sqlx::query::<Postgres>(
-                    "UPDATE contracts SET timeout_window = $1 WHERE contract_name = $2",
-                )
-                .bind(timeout_window_db)
-                .bind(contract_name),
-            );
+            self.handler_store
+                .sql_updates
+                .push(SqlUpdate::ContractTimeoutWindow {
+                    contract_name,
+                    timeout_window: timeout_window_db,
+                });
         }
 
         Ok(())
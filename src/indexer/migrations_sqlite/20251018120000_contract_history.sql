@@ -0,0 +1,9 @@
+-- One line per program ID / verifier a contract used, from the block where it became effective
+CREATE TABLE contract_history (
+    contract_name TEXT NOT NULL,
+    block_hash TEXT NOT NULL REFERENCES blocks(hash) ON DELETE CASCADE,    -- Block where this version became effective
+    effective_height BIGINT NOT NULL,
+    verifier TEXT NOT NULL,
+    program_id BLOB NOT NULL,
+    PRIMARY KEY (contract_name, effective_height)
+);
@@ -2,29 +2,35 @@
 
 use crate::{
     bus::{command_response::Query, BusClientSender},
-    consensus::{CommittedConsensusProposal, ConsensusEvent},
+    consensus::{CommittedConsensusProposal, ConsensusCommand, ConsensusEvent},
     genesis::GenesisEvent,
     model::*,
     node_state::module::NodeStateEvent,
     p2p::network::{
         HeaderSignableData, HeaderSigner, IntoHeaderSignableData, MsgWithHeader, OutboundMessage,
+        PeerEvent,
     },
+    tcp_server::{TcpServerReply, TcpServerRequest},
     utils::{
         conf::SharedConf,
         serialize::{arc_rwlock_borsh, BorshableIndexMap},
     },
 };
+use admission::AdmissionControl;
 use anyhow::{bail, Context, Result};
 use api::RestApiMessage;
-use block_construction::BlockUnderConstruction;
+use block_construction::{cut_tx_root, BlockUnderConstruction, TxRootOrMissing};
 use borsh::{BorshDeserialize, BorshSerialize};
-use client_sdk::tcp_client::TcpServerMessage;
+use compression::DataProposalCompression;
+use equivocation::LaneHeaders;
 use hyle_contract_sdk::{ContractName, ProgramId, Verifier};
-use hyle_crypto::SharedBlstCrypto;
+use hyle_crypto::{signer::SignerMessage, BlstCrypto, SharedBlstCrypto};
 use hyle_modules::{log_warn, module_bus_client, utils::static_type_map::Pick};
 use hyle_net::{logged_task::logged_task, ordered_join_set::OrderedJoinSet};
 use indexmap::IndexSet;
 use metrics::MempoolMetrics;
+use policy::{ConfMempoolPolicy, MempoolPolicy, PendingIndex};
+use retention::LaneRetention;
 use serde::{Deserialize, Serialize};
 use staking::state::Staking;
 use std::{
@@ -45,11 +51,16 @@ use storage_fjall::LanesStorage;
 use strum_macros::IntoStaticStr;
 use tracing::{debug, info, trace, warn};
 
+pub mod admission;
 pub mod api;
 pub mod block_construction;
+pub mod compression;
+pub mod equivocation;
 pub mod metrics;
 pub mod module;
 pub mod own_lane;
+pub mod policy;
+pub mod retention;
 pub mod storage;
 pub mod storage_fjall;
 pub mod storage_memory;
@@ -57,8 +68,25 @@ pub mod sync_request_reply;
 pub mod verifiers;
 pub mod verify_tx;
 
+/// Asks for a new cut to propose, with the staking and the cut of the parent proposal
 #[derive(Debug, Clone)]
-pub struct QueryNewCut(pub Staking);
+pub struct QueryNewCut(pub Staking, pub Cut);
+
+/// A cut to propose, along with the transaction root of the block it would build
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewCut {
+    pub cut: Cut,
+    pub tx_root: MerkleHash,
+}
+
+/// Asks for the transaction root of the block going from `parent_cut` to `cut`,
+/// for followers to check a proposal before voting on it.
+/// Answers None while some of its data proposals are being fetched.
+#[derive(Debug, Clone)]
+pub struct QueryTxRoot {
+    pub parent_cut: Cut,
+    pub cut: Cut,
+}
 
 #[derive(Debug, Default, Clone, BorshSerialize, BorshDeserialize)]
 pub struct KnownContracts(pub HashMap<ContractName, (Verifier, ProgramId)>);
@@ -84,13 +112,17 @@ struct MempoolBusClient {
     sender(OutboundMessage),
     sender(MempoolBlockEvent),
     sender(MempoolStatusEvent),
+    sender(ConsensusCommand),
+    sender(TcpServerReply),
     receiver(MsgWithHeader<MempoolNetMessage>),
+    receiver(PeerEvent),
     receiver(RestApiMessage),
-    receiver(TcpServerMessage),
+    receiver(TcpServerRequest),
     receiver(ConsensusEvent),
     receiver(GenesisEvent),
     receiver(NodeStateEvent),
-    receiver(Query<QueryNewCut, Cut>),
+    receiver(Query<QueryNewCut, NewCut>),
+    receiver(Query<QueryTxRoot, Option<MerkleHash>>),
 }
 }
 
@@ -119,6 +151,10 @@ pub struct MempoolStore {
     #[borsh(skip)]
     cached_dp_votes: HashMap<(LaneId, DataProposalHash), DataProposalVerdict>,
 
+    // equivocation.rs
+    #[borsh(skip)]
+    lane_headers: LaneHeaders,
+
     // Dedicated thread pool for data proposal and tx hashing
     #[borsh(skip)]
     long_tasks_runtime: LongTasksRuntime,
@@ -189,7 +225,13 @@ pub struct Mempool {
     conf: SharedConf,
     crypto: SharedBlstCrypto,
     metrics: MempoolMetrics,
+    policy: Box<dyn MempoolPolicy>,
+    /// Priority index over `waiting_dissemination_txs`, rebuilt from the policy on startup.
+    pending_index: PendingIndex,
+    admission: Arc<AdmissionControl>,
+    compression: DataProposalCompression,
     lanes: LanesStorage,
+    retention: LaneRetention,
     inner: MempoolStore,
 }
 
@@ -224,6 +266,11 @@ pub enum MempoolNetMessage {
     PoDAUpdate(DataProposalHash, Vec<ValidatorDAG>),
     SyncRequest(Option<DataProposalHash>, Option<DataProposalHash>),
     SyncReply(LaneEntryMetadata, DataProposal),
+    /// zstd-compressed borsh encoding of a data proposal, sent to peers that support it
+    CompressedDataProposal(DataProposalHash, Vec<u8>),
+    CompressedSyncReply(LaneEntryMetadata, DataProposalHash, Vec<u8>),
+    /// The requested data proposal was pruned from the lane, and has to be fetched from DA
+    SyncReplyPruned(DataProposalHash),
 }
 
 /// Validator Data Availability Guarantee
@@ -231,6 +278,17 @@ pub enum MempoolNetMessage {
 /// It acts as proof the validator committed to making this DP available.
 pub type ValidatorDAG = SignedByValidator<(DataProposalHash, LaneBytesSize)>;
 
+/// Signs the [ValidatorDAG] of a data proposal
+pub fn sign_data_vote(
+    crypto: &BlstCrypto,
+    hash: DataProposalHash,
+    size: LaneBytesSize,
+) -> Result<ValidatorDAG> {
+    crypto.sign_typed((hash.clone(), size), || {
+        Ok(SignerMessage::DataVote(hash, size))
+    })
+}
+
 impl Display for MempoolNetMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let enum_variant: &'static str = self.into();
@@ -242,7 +300,8 @@ impl IntoHeaderSignableData for MempoolNetMessage {
     fn to_header_signable_data(&self) -> HeaderSignableData {
         match self {
             // We get away with only signing the hash - verification must check the hash is correct
-            MempoolNetMessage::DataProposal(hash, _) => {
+            MempoolNetMessage::DataProposal(hash, _)
+            | MempoolNetMessage::CompressedDataProposal(hash, _) => {
                 HeaderSignableData(hash.0.clone().into_bytes())
             }
             MempoolNetMessage::DataVote(vdag) => {
@@ -251,10 +310,20 @@ impl IntoHeaderSignableData for MempoolNetMessage {
             MempoolNetMessage::PoDAUpdate(_, vdags) => {
                 HeaderSignableData(borsh::to_vec(&vdags).unwrap_or_default())
             }
+            // Tagged, so these can't be mistaken for a signed data proposal header
+            MempoolNetMessage::SyncReplyPruned(hash) => HeaderSignableData(
+                [
+                    b"SyncReplyPruned".to_vec(),
+                    borsh::to_vec(&hash).unwrap_or_default(),
+                ]
+                .concat(),
+            ),
             MempoolNetMessage::SyncRequest(from, to) => HeaderSignableData(
-                [from.clone(), to.clone()]
-                    .map(|h| h.unwrap_or_default().0.into_bytes())
-                    .concat(),
+                [
+                    b"SyncRequest".to_vec(),
+                    borsh::to_vec(&(from, to)).unwrap_or_default(),
+                ]
+                .concat(),
             ),
             MempoolNetMessage::SyncReply(metadata, data_proposal) => {
                 let hash = [
@@ -263,8 +332,23 @@ impl IntoHeaderSignableData for MempoolNetMessage {
                 ];
                 HeaderSignableData(hash.concat())
             }
+            // Signed as the uncompressed sync reply - the hash is checked after decompression
+            MempoolNetMessage::CompressedSyncReply(metadata, data_proposal_hash, _) => {
+                let hash = [
+                    borsh::to_vec(&metadata).unwrap_or_default(),
+                    data_proposal_hash.0.clone().into_bytes(),
+                ];
+                HeaderSignableData(hash.concat())
+            }
         }
     }
+
+    fn to_signer_message(&self, timestamp: u128) -> Result<SignerMessage> {
+        Ok(SignerMessage::MempoolHeader {
+            timestamp,
+            message: borsh::to_vec(self)?,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -285,6 +369,7 @@ impl Mempool {
             self.lanes.new_handle(),
             self.crypto.clone(),
             self.metrics.clone(),
+            self.compression.clone(),
             net_sender,
             sync_request_receiver,
         );
@@ -335,30 +420,71 @@ impl Mempool {
     }
 
     /// Creates a cut with local material on QueryNewCut message reception (from consensus)
-    fn handle_querynewcut(&mut self, staking: &mut QueryNewCut) -> Result<Cut> {
-        self.metrics.query_new_cut(staking);
-        let previous_cut = self
-            .last_ccp
-            .as_ref()
-            .map(|ccp| ccp.consensus_proposal.cut.clone())
-            .unwrap_or_default();
+    /// The transaction root is computed from the transaction hashes kept in the lanes.
+    /// Lanes whose new data proposals are not all available locally keep their previous entry
+    /// until the missing ones are fetched.
+    fn handle_querynewcut(&mut self, query: &mut QueryNewCut) -> Result<NewCut> {
+        self.metrics.query_new_cut(query);
+        let QueryNewCut(staking, previous_cut) = query;
 
         // For each lane, we get the last CAR and put it in the cut
         let mut cut: Cut = vec![];
-        for lane_id in self.lanes.get_lane_ids() {
+        let mut leaves = vec![];
+        let lane_ids: Vec<LaneId> = self.lanes.get_lane_ids().cloned().collect();
+        for lane_id in lane_ids.iter() {
             let previous_entry = previous_cut
                 .iter()
                 .find(|(lane_id_, _, _, _)| lane_id_ == lane_id);
-            if let Some((dp_hash, cumul_size, poda)) =
+            let Some((dp_hash, cumul_size, poda)) =
                 self.lanes
-                    .get_latest_car(lane_id, &staking.0, previous_entry)?
-            {
-                cut.push((lane_id.clone(), dp_hash, cumul_size, poda));
-            } else if let Some(lane) = previous_entry {
-                cut.push(lane.clone());
+                    .get_latest_car(lane_id, staking, previous_entry)?
+            else {
+                if let Some(lane) = previous_entry {
+                    cut.push(lane.clone());
+                }
+                continue;
+            };
+            let from_hash = previous_entry.map(|entry| &entry.1);
+            let (tx_ids, missing_hash) = self
+                .lanes
+                .get_tx_ids_between_hashes(lane_id, from_hash, &dp_hash)?;
+            if let Some(missing_hash) = missing_hash {
+                debug!(
+                    "Data proposal {} not available locally for lane {}, fetching it",
+                    missing_hash, lane_id
+                );
+                self.send_sync_request(lane_id, from_hash, Some(&missing_hash))?;
+                if let Some(lane) = previous_entry {
+                    cut.push(lane.clone());
+                }
+                continue;
+            }
+            cut.push((lane_id.clone(), dp_hash, cumul_size, poda));
+            leaves.extend(tx_ids.iter().map(tx_merkle_leaf));
+        }
+
+        Ok(NewCut {
+            cut,
+            tx_root: merkle_root(&leaves),
+        })
+    }
+
+    /// Data proposals of the block missing locally are fetched, the follower asking again
+    /// until they are available.
+    fn handle_query_tx_root(&mut self, query: &QueryTxRoot) -> Result<Option<MerkleHash>> {
+        match cut_tx_root(&self.lanes, &query.parent_cut, &query.cut)? {
+            TxRootOrMissing::TxRoot(tx_root) => Ok(Some(tx_root)),
+            TxRootOrMissing::Missing(missing) => {
+                for (lane_id, from_hash, missing_hash) in missing {
+                    debug!(
+                        "Data proposal {} of the proposal not available locally for lane {}, fetching it",
+                        missing_hash, lane_id
+                    );
+                    self.send_sync_request(&lane_id, from_hash.as_ref(), Some(&missing_hash))?;
+                }
+                Ok(None)
             }
         }
-        Ok(cut)
     }
 
     fn handle_internal_event(&mut self, event: ProcessedDPEvent) -> Result<()> {
@@ -418,6 +544,15 @@ impl Mempool {
         match msg.msg {
             MempoolNetMessage::DataProposal(data_proposal_hash, data_proposal) => {
                 let lane_id = self.get_lane(validator);
+                let _ = log_warn!(
+                    self.check_lane_equivocation(
+                        &lane_id,
+                        &msg.header,
+                        &data_proposal_hash,
+                        &data_proposal
+                    ),
+                    "Checking lane equivocation"
+                );
                 self.on_data_proposal(&lane_id, data_proposal_hash, data_proposal)?;
             }
             MempoolNetMessage::DataVote(vdag) => {
@@ -440,6 +575,40 @@ impl Mempool {
                 self.on_sync_reply(validator, metadata, data_proposal)
                     .await?;
             }
+            MempoolNetMessage::SyncReplyPruned(data_proposal_hash) => {
+                self.on_sync_reply_pruned(validator, &data_proposal_hash)
+                    .await?;
+            }
+            MempoolNetMessage::CompressedDataProposal(data_proposal_hash, compressed) => {
+                let data_proposal = self
+                    .compression
+                    .decompress_from_wire_blocking(compressed)
+                    .await?;
+                let lane_id = self.get_lane(validator);
+                let _ = log_warn!(
+                    self.check_lane_equivocation(
+                        &lane_id,
+                        &msg.header,
+                        &data_proposal_hash,
+                        &data_proposal
+                    ),
+                    "Checking lane equivocation"
+                );
+                self.on_data_proposal(&lane_id, data_proposal_hash, data_proposal)?;
+            }
+            MempoolNetMessage::CompressedSyncReply(metadata, data_proposal_hash, compressed) => {
+                let data_proposal = self
+                    .compression
+                    .decompress_from_wire_blocking(compressed)
+                    .await?;
+                if data_proposal.hashed() != data_proposal_hash {
+                    bail!(
+                        "Compressed SyncReply from {validator} does not contain data proposal {data_proposal_hash}"
+                    );
+                }
+                self.on_sync_reply(validator, metadata, data_proposal)
+                    .await?;
+            }
         }
         Ok(())
     }
@@ -480,6 +649,67 @@ impl Mempool {
         Ok(())
     }
 
+    /// The lane operator pruned this data proposal, so the blocks needing it can't be built locally.
+    /// They were emitted by the rest of the network: skip them and let DA fetch them from peers.
+    async fn on_sync_reply_pruned(
+        &mut self,
+        sender_validator: &ValidatorPublicKey,
+        data_proposal_hash: &DataProposalHash,
+    ) -> Result<()> {
+        let lane_id = LaneId(sender_validator.clone());
+        self.metrics
+            .sync_reply_pruned_receive(&lane_id, self.crypto.validator_pubkey());
+
+        // Blocks are built in order, so every block up to the last one missing entries of this lane is skipped
+        let mut missing_until = None;
+        for (index, buc) in self.blocks_under_contruction.iter().enumerate() {
+            let Some((_, to_hash, _, _)) = buc
+                .ccp
+                .consensus_proposal
+                .cut
+                .iter()
+                .find(|(id, _, _, _)| id == &lane_id)
+            else {
+                continue;
+            };
+            let from_hash = buc
+                .from
+                .as_ref()
+                .and_then(|from| from.iter().find(|(id, _, _, _)| id == &lane_id))
+                .map(|(_, hash, _, _)| hash);
+            let (_, missing_hash) = self
+                .get_lane_dps_between(&lane_id, from_hash, to_hash)
+                .await?;
+            if missing_hash.is_some() {
+                missing_until = Some(index);
+            }
+        }
+
+        let Some(index) = missing_until else {
+            debug!(
+                "Data proposal {} of lane {} was pruned by {}, but no block is waiting for it",
+                data_proposal_hash, lane_id, sender_validator
+            );
+            return Ok(());
+        };
+        let Some(until) = self
+            .blocks_under_contruction
+            .drain(..=index)
+            .last()
+            .map(|buc| BlockHeight(buc.ccp.consensus_proposal.slot))
+        else {
+            return Ok(());
+        };
+        warn!(
+            "Data proposal {} of lane {} was pruned by {}, fetching blocks up to {} from DA",
+            data_proposal_hash, lane_id, sender_validator, until
+        );
+        self.bus
+            .send(MempoolBlockEvent::MissingBlocks(until))
+            .context("Sending MissingBlocks event")?;
+        Ok(())
+    }
+
     async fn on_sync_reply(
         &mut self,
         sender_validator: &ValidatorPublicKey,
@@ -652,6 +882,71 @@ impl Mempool {
         Ok(())
     }
 
+    #[inline(always)]
+    fn broadcast_except_net_message(
+        &mut self,
+        except: HashSet<ValidatorPublicKey>,
+        net_message: MempoolNetMessage,
+    ) -> Result<()> {
+        let enum_variant_name: &'static str = (&net_message).into();
+        let error_msg = format!(
+            "Broadcasting MempoolNetMessage::{enum_variant_name} msg except for: {except:?} on the bus"
+        );
+        self.bus
+            .send(OutboundMessage::broadcast_except(
+                except,
+                self.crypto.sign_msg_with_header(net_message)?,
+            ))
+            .context(error_msg)?;
+        Ok(())
+    }
+
+    /// Broadcasts a data proposal (to `only_for` if set), compressed for the peers that support it.
+    /// The recipients are the same as without compression.
+    fn broadcast_data_proposal(
+        &mut self,
+        only_for: Option<HashSet<ValidatorPublicKey>>,
+        data_proposal: &DataProposal,
+    ) -> Result<()> {
+        let compressed_for: HashSet<ValidatorPublicKey> = only_for
+            .clone()
+            .unwrap_or_else(|| self.compression.known_peers())
+            .into_iter()
+            .filter(|pubkey| self.compression.supports(pubkey))
+            .collect();
+        let compressed = if compressed_for.is_empty() {
+            None
+        } else {
+            self.compression.compress_for_wire(data_proposal)?
+        };
+
+        let message =
+            MempoolNetMessage::DataProposal(data_proposal.hashed(), data_proposal.clone());
+        let Some(compressed) = compressed else {
+            return match only_for {
+                Some(only_for) => self.broadcast_only_for_net_message(only_for, message),
+                None => self.broadcast_net_message(message),
+            };
+        };
+
+        self.broadcast_only_for_net_message(
+            compressed_for.clone(),
+            MempoolNetMessage::CompressedDataProposal(data_proposal.hashed(), compressed),
+        )?;
+        // The other recipients get the data proposal as is
+        match only_for {
+            Some(only_for) => {
+                let uncompressed_for: HashSet<ValidatorPublicKey> =
+                    only_for.difference(&compressed_for).cloned().collect();
+                if uncompressed_for.is_empty() {
+                    return Ok(());
+                }
+                self.broadcast_only_for_net_message(uncompressed_for, message)
+            }
+            None => self.broadcast_except_net_message(compressed_for, message),
+        }
+    }
+
     #[inline(always)]
     fn send_net_message(
         &mut self,
@@ -709,18 +1004,32 @@ pub mod test {
     impl MempoolTestCtx {
         pub async fn build_mempool(shared_bus: &SharedMessageBus, crypto: BlstCrypto) -> Mempool {
             let tmp_dir = tempfile::tempdir().unwrap().keep();
-            let lanes = LanesStorage::new(&tmp_dir, BTreeMap::default()).unwrap();
+            let metrics = MempoolMetrics::global("id".to_string());
+            let compression = DataProposalCompression::new(Default::default(), metrics.clone());
+            let lanes =
+                LanesStorage::new(&tmp_dir, BTreeMap::default(), compression.clone()).unwrap();
             let bus = MempoolBusClient::new_from_bus(shared_bus.new_handle()).await;
 
+            let inner = MempoolStore::default();
+            let admission = Arc::new(AdmissionControl::new(
+                Default::default(),
+                Arc::clone(&inner.known_contracts),
+            ));
+
             // Initialize Mempool
             Mempool {
                 bus,
                 file: None,
                 conf: SharedConf::default(),
                 crypto: Arc::new(crypto),
-                metrics: MempoolMetrics::global("id".to_string()),
+                metrics,
+                policy: Box::new(ConfMempoolPolicy::new(Default::default())),
+                pending_index: PendingIndex::default(),
+                admission,
+                compression,
                 lanes,
-                inner: MempoolStore::default(),
+                retention: LaneRetention::default(),
+                inner,
             }
         }
 
@@ -788,9 +1097,14 @@ pub mod test {
             self.mempool.crypto.sign_msg_with_header(msg)
         }
 
-        pub fn gen_cut(&mut self, staking: &Staking) -> Cut {
+        /// Another handle on the lanes, to read them while the mempool is used by the test
+        pub fn lanes_handle(&self) -> LanesStorage {
+            self.mempool.lanes.new_handle()
+        }
+
+        pub async fn gen_cut(&mut self, staking: &Staking, parent_cut: &Cut) -> NewCut {
             self.mempool
-                .handle_querynewcut(&mut QueryNewCut(staking.clone()))
+                .handle_querynewcut(&mut QueryNewCut(staking.clone(), parent_cut.clone()))
                 .unwrap()
         }
 
@@ -925,6 +1239,12 @@ pub mod test {
                         els
                     );
                     }
+                    OutboundMessage::BroadcastMessageExcept(_, els) => {
+                        panic!(
+                            "{description}: received broadcast except message instead of send {:?}",
+                            els
+                        );
+                    }
                 }
             })
         }
@@ -943,7 +1263,8 @@ pub mod test {
                         .expect(format!("{description}: No message broadcasted").as_str());
 
                 match rec {
-                    OutboundMessage::BroadcastMessage(net_msg) => {
+                    OutboundMessage::BroadcastMessage(net_msg)
+                    | OutboundMessage::BroadcastMessageExcept(_, net_msg) => {
                         if let NetMessage::MempoolMessage(msg) = net_msg {
                             msg
                         } else {
@@ -1096,10 +1417,14 @@ pub mod test {
                         staking: self.mempool.staking.clone(),
                         consensus_proposal: model::ConsensusProposal {
                             slot,
+                            epoch: 0,
                             cut: cut.clone(),
                             staking_actions: vec![],
                             timestamp: TimestampMs(777),
                             parent_hash: ConsensusProposalHash("test".to_string()),
+                            tx_root: EMPTY_MERKLE_ROOT,
+                            parent_commit_qc: AggregateSignature::default(),
+                            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
                         },
                         certificate: AggregateSignature::default(),
                     },
@@ -1115,7 +1440,9 @@ pub mod test {
         hash: DataProposalHash,
         size: LaneBytesSize,
     ) -> Result<MempoolNetMessage> {
-        Ok(MempoolNetMessage::DataVote(crypto.sign((hash, size))?))
+        Ok(MempoolNetMessage::DataVote(sign_data_vote(
+            crypto, hash, size,
+        )?))
     }
 
     pub fn make_register_contract_tx(name: ContractName) -> Transaction {
@@ -1223,6 +1550,58 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_receiving_sync_request_for_pruned_dp() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+
+        let data_proposal = ctx.create_data_proposal(
+            None,
+            &[make_register_contract_tx(ContractName::new("test1"))],
+        );
+        ctx.process_new_data_proposal(data_proposal.clone())?;
+        let lane_id = ctx.own_lane();
+        ctx.mempool.lanes.prune(&lane_id, &data_proposal.hashed())?;
+
+        let crypto2 = BlstCrypto::new("2").unwrap();
+        ctx.add_trusted_validator(crypto2.validator_pubkey());
+
+        let signed_msg = crypto2.sign_msg_with_header(MempoolNetMessage::SyncRequest(
+            None,
+            Some(data_proposal.hashed()),
+        ))?;
+        ctx.mempool
+            .handle_net_message(signed_msg, &ctx.mempool_sync_request_sender)
+            .await
+            .expect("should handle net message");
+
+        match ctx
+            .assert_send(crypto2.validator_pubkey(), "SyncReplyPruned")
+            .await
+            .msg
+        {
+            MempoolNetMessage::SyncReplyPruned(hash) => {
+                assert_eq!(hash, data_proposal.hashed());
+            }
+            _ => panic!("Expected SyncReplyPruned message"),
+        };
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_signable_data_is_not_a_data_proposal_header() {
+        let dp_hash = DataProposalHash("dp".to_string());
+        let dp_header =
+            MempoolNetMessage::DataProposal(dp_hash.clone(), DataProposal::new(None, vec![]))
+                .to_header_signable_data();
+        for msg in [
+            MempoolNetMessage::SyncReplyPruned(dp_hash.clone()),
+            MempoolNetMessage::SyncRequest(None, Some(dp_hash.clone())),
+        ] {
+            assert_ne!(msg.to_header_signable_data().0, dp_header.0);
+        }
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_receiving_sync_requests_multiple_dps() -> Result<()> {
         let mut ctx = MempoolTestCtx::new("mempool").await;
@@ -1558,10 +1937,11 @@ pub mod test {
         // Force some signature for f+1 check if needed:
         // This requires more advanced stubbing of Staking if you want a real test.
 
-        let cut = ctx
+        let new_cut = ctx
             .mempool
-            .handle_querynewcut(&mut QueryNewCut(staking))
+            .handle_querynewcut(&mut QueryNewCut(staking, vec![]))
             .unwrap();
-        assert_eq!(0, cut.len());
+        assert_eq!(0, new_cut.cut.len());
+        assert_eq!(EMPTY_MERKLE_ROOT, new_cut.tx_root);
     }
 }
@@ -0,0 +1,537 @@
+//! Checks run on transactions submitted through the REST API or the TCP server, before they reach the mempool.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex, RwLock},
+    time::Instant,
+};
+
+use hyle_model::{utils::TimestampMs, RegisterContractAction, StructuredBlobData};
+use hyle_modules::node_state::contract_registration::validate_contract_registration_metadata;
+use hyle_net::clock::TimestampMsClock;
+use serde::{Deserialize, Serialize};
+
+use crate::model::*;
+
+use super::KnownContracts;
+
+/// Beyond this many tracked identities or contracts, full buckets are forgotten.
+const MAX_TRACKED_BUCKETS: usize = 100_000;
+
+/// The mempool is synced once the last block it processed is this recent, it then saw the
+/// registration of the contracts in use.
+const SYNCED_BLOCK_AGE_MS: u128 = 60_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AdmissionConf {
+    /// Maximum number of blobs in a blob transaction
+    pub max_blobs: usize,
+    /// Maximum size of the data of a single blob, in bytes
+    pub max_blob_bytes: usize,
+    /// Maximum size of a proof, in bytes
+    pub max_proof_bytes: usize,
+    /// Transactions per second allowed for each identity (0 for no limit).
+    /// The identity is the one claimed by the transaction, which senders choose freely: this
+    /// only spreads the load of well-behaved clients, the contract limits bound spammers.
+    pub identity_rate: f64,
+    /// Transactions an identity can send at once before being rate limited
+    pub identity_burst: u32,
+    /// Transactions per second allowed on each contract (0 for no limit)
+    pub contract_rate: f64,
+    /// Transactions that can be sent at once on a contract before being rate limited
+    pub contract_burst: u32,
+    /// Reject transactions on contracts the mempool doesn't know about, unless they are
+    /// registered by the transaction itself. They are let through until the mempool is synced,
+    /// as a node catching up may not have seen their registration yet.
+    pub reject_unknown_contracts: bool,
+}
+
+impl Default for AdmissionConf {
+    fn default() -> Self {
+        Self {
+            max_blobs: 20,
+            max_blob_bytes: 1_000_000,
+            max_proof_bytes: 10_000_000,
+            identity_rate: 0.,
+            identity_burst: 10,
+            contract_rate: 0.,
+            contract_burst: 100,
+            reject_unknown_contracts: true,
+        }
+    }
+}
+
+/// Why a transaction was not admitted
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdmissionError {
+    TooManyBlobs {
+        count: usize,
+        max: usize,
+    },
+    BlobTooLarge {
+        index: usize,
+        size: usize,
+        max: usize,
+    },
+    ProofTooLarge {
+        size: usize,
+        max: usize,
+    },
+    InvalidIdentity(String),
+    InvalidContractRegistration(String),
+    UnknownContract(ContractName),
+    IdentityRateLimited(Identity),
+    ContractRateLimited(ContractName),
+}
+
+impl AdmissionError {
+    /// Stable identifier of the rejection reason, returned to clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AdmissionError::TooManyBlobs { .. } => "too_many_blobs",
+            AdmissionError::BlobTooLarge { .. } => "blob_too_large",
+            AdmissionError::ProofTooLarge { .. } => "proof_too_large",
+            AdmissionError::InvalidIdentity(_) => "invalid_identity",
+            AdmissionError::InvalidContractRegistration(_) => "invalid_contract_registration",
+            AdmissionError::UnknownContract(_) => "unknown_contract",
+            AdmissionError::IdentityRateLimited(_) => "identity_rate_limited",
+            AdmissionError::ContractRateLimited(_) => "contract_rate_limited",
+        }
+    }
+
+    pub fn is_rate_limit(&self) -> bool {
+        matches!(
+            self,
+            AdmissionError::IdentityRateLimited(_) | AdmissionError::ContractRateLimited(_)
+        )
+    }
+}
+
+impl std::fmt::Display for AdmissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdmissionError::TooManyBlobs { count, max } => {
+                write!(f, "Too many blobs in transaction: {count} (max {max})")
+            }
+            AdmissionError::BlobTooLarge { index, size, max } => {
+                write!(f, "Blob {index} is too large: {size} bytes (max {max})")
+            }
+            AdmissionError::ProofTooLarge { size, max } => {
+                write!(f, "Proof is too large: {size} bytes (max {max})")
+            }
+            AdmissionError::InvalidIdentity(e) => write!(f, "Invalid identity for blob tx: {e}"),
+            AdmissionError::InvalidContractRegistration(e) => {
+                write!(f, "Invalid contract registration: {e}")
+            }
+            AdmissionError::UnknownContract(contract_name) => {
+                write!(f, "Unknown contract {contract_name}")
+            }
+            AdmissionError::IdentityRateLimited(identity) => {
+                write!(f, "Too many transactions for identity {identity}")
+            }
+            AdmissionError::ContractRateLimited(contract_name) => {
+                write!(f, "Too many transactions on contract {contract_name}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AdmissionError {}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug)]
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64, burst: u32) -> Self {
+        Self {
+            rate,
+            burst: burst.max(1) as f64,
+            buckets: HashMap::new(),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.rate > 0.
+    }
+
+    fn refill(&mut self, key: &str, now: Instant) -> &mut TokenBucket {
+        let (rate, burst) = (self.rate, self.burst);
+        let bucket = self.buckets.entry(key.to_string()).or_insert(TokenBucket {
+            tokens: burst,
+            last_refill: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(burst);
+        bucket.last_refill = now;
+        bucket
+    }
+
+    fn has_token(&mut self, key: &str, now: Instant) -> bool {
+        !self.enabled() || self.refill(key, now).tokens >= 1.
+    }
+
+    fn take_token(&mut self, key: &str, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        self.refill(key, now).tokens -= 1.;
+        if self.buckets.len() > MAX_TRACKED_BUCKETS {
+            let (rate, burst) = (self.rate, self.burst);
+            self.buckets.retain(|_, bucket| {
+                let elapsed = now.saturating_duration_since(bucket.last_refill);
+                bucket.tokens + elapsed.as_secs_f64() * rate < burst
+            });
+        }
+    }
+}
+
+/// Admission checks shared by every transaction entry point of the node.
+#[derive(Debug)]
+pub struct AdmissionControl {
+    conf: AdmissionConf,
+    known_contracts: Arc<RwLock<KnownContracts>>,
+    identity_limiter: Mutex<RateLimiter>,
+    contract_limiter: Mutex<RateLimiter>,
+    /// Timestamp of the last block processed by the mempool
+    last_block_timestamp: Mutex<Option<TimestampMs>>,
+}
+
+impl AdmissionControl {
+    pub fn new(conf: AdmissionConf, known_contracts: Arc<RwLock<KnownContracts>>) -> Self {
+        Self {
+            identity_limiter: Mutex::new(RateLimiter::new(conf.identity_rate, conf.identity_burst)),
+            contract_limiter: Mutex::new(RateLimiter::new(conf.contract_rate, conf.contract_burst)),
+            conf,
+            known_contracts,
+            last_block_timestamp: Mutex::new(None),
+        }
+    }
+
+    /// Records that the mempool processed a block, and the contracts it registered
+    pub fn on_new_block(&self, block_timestamp: &TimestampMs) {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let mut last = self.last_block_timestamp.lock().expect("logic issue");
+        *last = Some(block_timestamp.clone());
+    }
+
+    fn is_synced(&self) -> bool {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let last = self.last_block_timestamp.lock().expect("logic issue");
+        last.as_ref()
+            .is_some_and(|last| last.0 + SYNCED_BLOCK_AGE_MS >= TimestampMsClock::now().0)
+    }
+
+    pub fn check(&self, tx: &TransactionData) -> Result<(), AdmissionError> {
+        self.check_at(tx, Instant::now())
+    }
+
+    fn check_at(&self, tx: &TransactionData, now: Instant) -> Result<(), AdmissionError> {
+        let mut registrations = vec![];
+        let (identity, contracts) = match tx {
+            TransactionData::Blob(blob_tx) => {
+                registrations = self.check_blob_tx(blob_tx)?;
+                (
+                    Some(&blob_tx.identity),
+                    blob_tx
+                        .blobs
+                        .iter()
+                        .map(|blob| &blob.contract_name)
+                        .collect::<BTreeSet<_>>(),
+                )
+            }
+            TransactionData::Proof(proof_tx) => {
+                if proof_tx.proof.0.len() > self.conf.max_proof_bytes {
+                    return Err(AdmissionError::ProofTooLarge {
+                        size: proof_tx.proof.0.len(),
+                        max: self.conf.max_proof_bytes,
+                    });
+                }
+                self.check_known_contract(&proof_tx.contract_name, &[])?;
+                (None, BTreeSet::from([&proof_tx.contract_name]))
+            }
+            // Only created by the mempool itself
+            TransactionData::VerifiedProof(_) => return Ok(()),
+        };
+
+        // Identities are not authenticated at this point, see [AdmissionConf::identity_rate]
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let mut identity_limiter = self.identity_limiter.lock().expect("logic issue");
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let mut contract_limiter = self.contract_limiter.lock().expect("logic issue");
+        if let Some(identity) = identity {
+            if !identity_limiter.has_token(&identity.0, now) {
+                return Err(AdmissionError::IdentityRateLimited(identity.clone()));
+            }
+        }
+        for contract_name in &contracts {
+            if !contract_limiter.has_token(&contract_name.0, now) {
+                return Err(AdmissionError::ContractRateLimited(
+                    (*contract_name).clone(),
+                ));
+            }
+        }
+        if let Some(identity) = identity {
+            identity_limiter.take_token(&identity.0, now);
+        }
+        for contract_name in contracts {
+            contract_limiter.take_token(&contract_name.0, now);
+        }
+
+        // Know the new contracts right away, so they can be used by the next transactions
+        // even before the mempool processed this one.
+        if !registrations.is_empty() {
+            #[allow(clippy::expect_used, reason = "not held across await")]
+            let mut known_contracts = self.known_contracts.write().expect("logic issue");
+            for action in registrations {
+                known_contracts.register_contract(
+                    &action.contract_name,
+                    &action.verifier,
+                    &action.program_id,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the contract registrations of the transaction.
+    fn check_blob_tx(
+        &self,
+        blob_tx: &BlobTransaction,
+    ) -> Result<Vec<RegisterContractAction>, AdmissionError> {
+        if blob_tx.blobs.len() > self.conf.max_blobs {
+            return Err(AdmissionError::TooManyBlobs {
+                count: blob_tx.blobs.len(),
+                max: self.conf.max_blobs,
+            });
+        }
+        if let Some((index, blob)) = blob_tx
+            .blobs
+            .iter()
+            .enumerate()
+            .find(|(_, blob)| blob.data.0.len() > self.conf.max_blob_bytes)
+        {
+            return Err(AdmissionError::BlobTooLarge {
+                index,
+                size: blob.data.0.len(),
+                max: self.conf.max_blob_bytes,
+            });
+        }
+        blob_tx
+            .validate_identity()
+            .map_err(|e| AdmissionError::InvalidIdentity(e.to_string()))?;
+
+        // Contracts registered by this very transaction can be used by its other blobs.
+        let mut registered = vec![];
+        for blob in blob_tx.blobs.iter() {
+            if blob.contract_name.0 != "hyle" {
+                continue;
+            }
+            if let Ok(tx) =
+                StructuredBlobData::<RegisterContractAction>::try_from(blob.data.clone())
+            {
+                let parameters = tx.parameters;
+                validate_contract_registration_metadata(
+                    &"hyle".into(),
+                    &parameters.contract_name,
+                    &parameters.verifier,
+                    &parameters.program_id,
+                    &parameters.state_commitment,
+                )
+                .map_err(|e| AdmissionError::InvalidContractRegistration(e.to_string()))?;
+                registered.push(parameters);
+            }
+        }
+        for blob in blob_tx.blobs.iter() {
+            self.check_known_contract(&blob.contract_name, &registered)?;
+        }
+        Ok(registered)
+    }
+
+    fn check_known_contract(
+        &self,
+        contract_name: &ContractName,
+        registered: &[RegisterContractAction],
+    ) -> Result<(), AdmissionError> {
+        if !self.conf.reject_unknown_contracts
+            || registered
+                .iter()
+                .any(|action| &action.contract_name == contract_name)
+            || !self.is_synced()
+        {
+            return Ok(());
+        }
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let known = self
+            .known_contracts
+            .read()
+            .expect("logic issue")
+            .0
+            .contains_key(contract_name);
+        if known {
+            Ok(())
+        } else {
+            Err(AdmissionError::UnknownContract(contract_name.clone()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn admission(conf: AdmissionConf) -> AdmissionControl {
+        let mut known_contracts = KnownContracts::default();
+        known_contracts.0.insert(
+            "c1".into(),
+            (Verifier("test".to_string()), ProgramId(vec![])),
+        );
+        AdmissionControl::new(conf, Arc::new(RwLock::new(known_contracts)))
+    }
+
+    fn blob_tx(identity: &str, contract: &str, data: Vec<u8>) -> TransactionData {
+        TransactionData::Blob(BlobTransaction::new(
+            identity,
+            vec![Blob {
+                contract_name: contract.into(),
+                data: BlobData(data),
+            }],
+        ))
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let admission = admission(AdmissionConf {
+            max_blobs: 1,
+            max_blob_bytes: 4,
+            max_proof_bytes: 4,
+            ..Default::default()
+        });
+
+        assert_eq!(admission.check(&blob_tx("a@c1", "c1", vec![0; 4])), Ok(()));
+        assert_eq!(
+            admission
+                .check(&blob_tx("a@c1", "c1", vec![0; 5]))
+                .map_err(|e| e.code()),
+            Err("blob_too_large")
+        );
+
+        let two_blobs = TransactionData::Blob(BlobTransaction::new(
+            "a@c1",
+            vec![
+                Blob {
+                    contract_name: "c1".into(),
+                    data: BlobData(vec![]),
+                };
+                2
+            ],
+        ));
+        assert_eq!(
+            admission.check(&two_blobs).map_err(|e| e.code()),
+            Err("too_many_blobs")
+        );
+
+        let proof = TransactionData::Proof(ProofTransaction {
+            contract_name: "c1".into(),
+            proof: ProofData(vec![0; 5]),
+        });
+        assert_eq!(
+            admission.check(&proof).map_err(|e| e.code()),
+            Err("proof_too_large")
+        );
+    }
+
+    #[test]
+    fn test_unknown_contract() {
+        let admission = admission(AdmissionConf::default());
+
+        // Until the mempool caught up, contracts may have been registered in blocks it didn't see
+        assert_eq!(admission.check(&blob_tx("a@c2", "c2", vec![])), Ok(()));
+        admission.on_new_block(&TimestampMs(0));
+        assert_eq!(admission.check(&blob_tx("a@c2", "c2", vec![1])), Ok(()));
+
+        admission.on_new_block(&TimestampMsClock::now());
+        assert_eq!(
+            admission.check(&blob_tx("a@c2", "c2", vec![2])),
+            Err(AdmissionError::UnknownContract("c2".into()))
+        );
+        assert_eq!(admission.check(&blob_tx("a@c1", "c1", vec![2])), Ok(()));
+
+        let permissive = super::AdmissionControl::new(
+            AdmissionConf {
+                reject_unknown_contracts: false,
+                ..Default::default()
+            },
+            Default::default(),
+        );
+        permissive.on_new_block(&TimestampMsClock::now());
+        assert_eq!(permissive.check(&blob_tx("a@c2", "c2", vec![])), Ok(()));
+    }
+
+    #[test]
+    fn test_identity_rate_limit() {
+        let admission = admission(AdmissionConf {
+            identity_rate: 1.,
+            identity_burst: 2,
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert!(admission
+            .check_at(&blob_tx("a@c1", "c1", vec![1]), now)
+            .is_ok());
+        assert!(admission
+            .check_at(&blob_tx("a@c1", "c1", vec![2]), now)
+            .is_ok());
+        assert_eq!(
+            admission.check_at(&blob_tx("a@c1", "c1", vec![3]), now),
+            Err(AdmissionError::IdentityRateLimited("a@c1".into()))
+        );
+        // Other identities are not affected
+        assert!(admission
+            .check_at(&blob_tx("b@c1", "c1", vec![3]), now)
+            .is_ok());
+        // Tokens come back over time
+        assert!(admission
+            .check_at(
+                &blob_tx("a@c1", "c1", vec![3]),
+                now + Duration::from_secs(1)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_contract_rate_limit_does_not_consume_identity_tokens() {
+        let admission = admission(AdmissionConf {
+            identity_rate: 1.,
+            identity_burst: 1,
+            contract_rate: 1.,
+            contract_burst: 1,
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert!(admission
+            .check_at(&blob_tx("a@c1", "c1", vec![1]), now)
+            .is_ok());
+        assert_eq!(
+            admission.check_at(&blob_tx("b@c1", "c1", vec![1]), now),
+            Err(AdmissionError::ContractRateLimited("c1".into()))
+        );
+        let later = now + Duration::from_secs(1);
+        assert!(admission
+            .check_at(&blob_tx("b@c1", "c1", vec![2]), later)
+            .is_ok());
+    }
+}
@@ -1,12 +1,16 @@
+use std::sync::Arc;
+
 use anyhow::anyhow;
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json, Router};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json, Router,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use hyle_contract_sdk::TxHash;
-use hyle_model::{api::APIRegisterContract, RegisterContractAction, StructuredBlobData};
-use hyle_modules::{
-    bus::SharedMessageBus, modules::SharedBuildApiCtx,
-    node_state::contract_registration::validate_contract_registration_metadata,
-};
+use hyle_model::api::APIRegisterContract;
+use hyle_modules::{bus::SharedMessageBus, log_warn, modules::SharedBuildApiCtx};
 use serde::{Deserialize, Serialize};
 use tracing::info;
 use utoipa::OpenApi;
@@ -14,10 +18,14 @@ use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
     bus::{bus_client, metrics::BusMetrics, BusClientSender},
-    model::{BlobTransaction, Hashed, ProofTransaction, Transaction, TransactionData},
+    model::{
+        BlobTransaction, Hashed, MempoolStatusEvent, ProofTransaction, Transaction, TransactionData,
+    },
     rest::AppError,
 };
 
+use super::admission::{AdmissionControl, AdmissionError};
+
 #[derive(Debug, Serialize, Deserialize, Clone, BorshSerialize, BorshDeserialize)]
 pub enum RestApiMessage {
     NewTx(Transaction),
@@ -26,19 +34,58 @@ pub enum RestApiMessage {
 bus_client! {
 struct RestBusClient {
     sender(RestApiMessage),
+    sender(MempoolStatusEvent),
 }
 }
 
 pub struct RouterState {
     bus: RestBusClient,
+    admission: Arc<AdmissionControl>,
+}
+
+/// Body of the response when a transaction is rejected by admission control
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TxRejection {
+    pub code: String,
+    pub message: String,
+}
+
+pub enum SendTxError {
+    Rejected(AdmissionError),
+    Internal(AppError),
+}
+
+impl IntoResponse for SendTxError {
+    fn into_response(self) -> Response {
+        match self {
+            SendTxError::Rejected(err) => {
+                let status = if err.is_rate_limit() {
+                    StatusCode::TOO_MANY_REQUESTS
+                } else {
+                    StatusCode::BAD_REQUEST
+                };
+                let body = TxRejection {
+                    code: err.code().to_string(),
+                    message: err.to_string(),
+                };
+                (status, Json(body)).into_response()
+            }
+            SendTxError::Internal(err) => err.into_response(),
+        }
+    }
 }
 
 #[derive(OpenApi)]
 struct MempoolAPI;
 
-pub async fn api(bus: &SharedMessageBus, ctx: &SharedBuildApiCtx) -> Router<()> {
+pub async fn api(
+    bus: &SharedMessageBus,
+    ctx: &SharedBuildApiCtx,
+    admission: Arc<AdmissionControl>,
+) -> Router<()> {
     let state = RouterState {
         bus: RestBusClient::new_from_bus(bus.new_handle()).await,
+        admission,
     };
 
     let (router, api) = OpenApiRouter::with_openapi(MempoolAPI::openapi())
@@ -56,15 +103,27 @@ pub async fn api(bus: &SharedMessageBus, ctx: &SharedBuildApiCtx) -> Router<()>
 async fn handle_send(
     mut state: RouterState,
     payload: TransactionData,
-) -> Result<Json<TxHash>, AppError> {
+) -> Result<Json<TxHash>, SendTxError> {
     let tx: Transaction = payload.into();
     let tx_hash = tx.hashed();
+    if let Err(err) = state.admission.check(&tx.transaction_data) {
+        _ = log_warn!(
+            state.bus.send(MempoolStatusEvent::Rejected {
+                tx_hash,
+                code: err.code().to_string(),
+            }),
+            "Sending rejected tx status"
+        );
+        return Err(SendTxError::Rejected(err));
+    }
     state
         .bus
         .send(RestApiMessage::NewTx(tx))
         .map(|_| tx_hash)
         .map(Json)
-        .map_err(|err| AppError(StatusCode::INTERNAL_SERVER_ERROR, anyhow!(err)))
+        .map_err(|err| {
+            SendTxError::Internal(AppError(StatusCode::INTERNAL_SERVER_ERROR, anyhow!(err)))
+        })
 }
 
 #[utoipa::path(
@@ -72,48 +131,16 @@ async fn handle_send(
     path = "/tx/send/blob",
     tag = "Mempool",
     responses(
-        (status = OK, description = "Send blob transaction", body = TxHash)
+        (status = OK, description = "Send blob transaction", body = TxHash),
+        (status = BAD_REQUEST, description = "Transaction rejected", body = TxRejection),
+        (status = TOO_MANY_REQUESTS, description = "Rate limited", body = TxRejection)
     )
 )]
 pub async fn send_blob_transaction(
     State(state): State<RouterState>,
     Json(payload): Json<BlobTransaction>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<impl IntoResponse, SendTxError> {
     info!("Got blob transaction {}", payload.hashed());
-
-    // Filter out incorrect contract-registring transactions
-    for blob in payload.blobs.iter() {
-        if blob.contract_name.0 != "hyle" {
-            continue;
-        }
-        if let Ok(tx) = StructuredBlobData::<RegisterContractAction>::try_from(blob.data.clone()) {
-            let parameters = tx.parameters;
-            validate_contract_registration_metadata(
-                &"hyle".into(),
-                &parameters.contract_name,
-                &parameters.verifier,
-                &parameters.program_id,
-                &parameters.state_commitment,
-            )
-            .map_err(|err| AppError(StatusCode::BAD_REQUEST, anyhow!(err)))?;
-        }
-    }
-
-    // Filter out transactions with incorrect identity
-    if let Err(e) = payload.validate_identity() {
-        return Err(AppError(
-            StatusCode::BAD_REQUEST,
-            anyhow!("Invalid identity for blob tx: {}", e),
-        ));
-    }
-
-    // Filter out transactions with too many blobs
-    if payload.blobs.len() > 20 {
-        return Err(AppError(
-            StatusCode::BAD_REQUEST,
-            anyhow!("Too many blobs in transaction"),
-        ));
-    }
     handle_send(state, TransactionData::Blob(payload)).await
 }
 
@@ -122,13 +149,15 @@ pub async fn send_blob_transaction(
     path = "/tx/send/proof",
     tag = "Mempool",
     responses(
-        (status = OK, description = "Send proof transaction", body = TxHash)
+        (status = OK, description = "Send proof transaction", body = TxHash),
+        (status = BAD_REQUEST, description = "Transaction rejected", body = TxRejection),
+        (status = TOO_MANY_REQUESTS, description = "Rate limited", body = TxRejection)
     )
 )]
 pub async fn send_proof_transaction(
     State(state): State<RouterState>,
     Json(payload): Json<ProofTransaction>,
-) -> Result<impl IntoResponse, AppError> {
+) -> Result<impl IntoResponse, SendTxError> {
     info!("Got proof transaction {}", payload.hashed());
     handle_send(state, TransactionData::Proof(payload)).await
 }
@@ -138,23 +167,16 @@ pub async fn send_proof_transaction(
     path = "/contract/register",
     tag = "Mempool",
     responses(
-        (status = OK, description = "Register contract", body = TxHash)
+        (status = OK, description = "Register contract", body = TxHash),
+        (status = BAD_REQUEST, description = "Transaction rejected", body = TxRejection),
+        (status = TOO_MANY_REQUESTS, description = "Rate limited", body = TxRejection)
     )
 )]
 pub async fn register_contract(
     State(state): State<RouterState>,
     Json(payload): Json<APIRegisterContract>,
-) -> Result<impl IntoResponse, AppError> {
-    let owner = "hyle".into();
-    validate_contract_registration_metadata(
-        &owner,
-        &payload.contract_name,
-        &payload.verifier,
-        &payload.program_id,
-        &payload.state_commitment,
-    )
-    .map_err(|err| AppError(StatusCode::BAD_REQUEST, anyhow!(err)))?;
-
+) -> Result<impl IntoResponse, SendTxError> {
+    // Admission control validates the registration like for any other blob transaction.
     let tx = BlobTransaction::from(payload);
 
     handle_send(state, TransactionData::Blob(tx)).await
@@ -167,7 +189,9 @@ impl Clone for RouterState {
             bus: RestBusClient::new(
                 Pick::<BusMetrics>::get(&self.bus).clone(),
                 Pick::<tokio::sync::broadcast::Sender<RestApiMessage>>::get(&self.bus).clone(),
+                Pick::<tokio::sync::broadcast::Sender<MempoolStatusEvent>>::get(&self.bus).clone(),
             ),
+            admission: Arc::clone(&self.admission),
         }
     }
 }
@@ -4,12 +4,49 @@ use crate::{
 };
 use futures::StreamExt;
 use hyle_modules::{log_error, log_warn};
+use std::collections::BTreeSet;
 
 use super::storage::Storage;
 use anyhow::{bail, Context, Result};
 use borsh::{BorshDeserialize, BorshSerialize};
 use tracing::{debug, error, trace, warn};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TxRootOrMissing {
+    TxRoot(MerkleHash),
+    /// Data proposals of the block not available locally,
+    /// as their lane, the hash the lane is read from in the parent cut, and the missing hash
+    Missing(Vec<(LaneId, Option<DataProposalHash>, DataProposalHash)>),
+}
+
+/// Transaction root of the block going from `parent_cut` to `cut`, see [ConsensusProposal::tx_root].
+/// Only transaction hashes are read from the lanes, the data proposals are not loaded.
+pub(crate) fn cut_tx_root(
+    lanes: &impl Storage,
+    parent_cut: &Cut,
+    cut: &Cut,
+) -> Result<TxRootOrMissing> {
+    let mut leaves = vec![];
+    let mut missing = vec![];
+    for (lane_id, dp_hash, _, _) in cut.iter() {
+        let from_hash = parent_cut
+            .iter()
+            .find(|entry| &entry.0 == lane_id)
+            .map(|entry| &entry.1);
+        let (tx_ids, missing_hash) =
+            lanes.get_tx_ids_between_hashes(lane_id, from_hash, dp_hash)?;
+        if let Some(missing_hash) = missing_hash {
+            missing.push((lane_id.clone(), from_hash.cloned(), missing_hash));
+            continue;
+        }
+        leaves.extend(tx_ids.iter().map(tx_merkle_leaf));
+    }
+    if !missing.is_empty() {
+        return Ok(TxRootOrMissing::Missing(missing));
+    }
+    Ok(TxRootOrMissing::TxRoot(merkle_root(&leaves)))
+}
+
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct BlockUnderConstruction {
     pub from: Option<Cut>,
@@ -60,40 +97,13 @@ impl super::Mempool {
                 .and_then(|f| f.iter().find(|el| &el.0 == lane_id))
                 .map(|el| &el.1);
 
-            // iterate over the lane entries between from_hash and to_hash of the lane
-            let (dps, missing_hash) = {
-                trace!("Fetching data proposals for lane {}", lane_id);
-                let mut missing_hash = None;
-                let mut dps = vec![];
-
-                let mut entries = Box::pin(self.lanes.get_entries_between_hashes(
-                    lane_id,
-                    from_hash.cloned(),
-                    Some(to_hash.clone()),
-                ));
-
-                while let Some(entry) = entries.next().await {
-                    trace!("Processing lane entry {:?}", entry);
-                    let entry_or_missing = entry.context(format!(
-                        "Lane entries from {:?} to {:?} not available locally",
-                        buc.from, buc.ccp.consensus_proposal.cut
-                    ))?;
-
-                    match entry_or_missing {
-                        EntryOrMissingHash::Entry(_, dp) => dps.insert(0, dp),
-                        EntryOrMissingHash::MissingHash(hash) => {
-                            debug!(
-                                "Data proposal {} not available locally for lane {}",
-                                hash, lane_id
-                            );
-                            missing_hash = Some(hash.clone());
-                            break;
-                        }
-                    }
-                }
-
-                (dps, missing_hash)
-            };
+            let (dps, missing_hash) = self
+                .get_lane_dps_between(lane_id, from_hash, to_hash)
+                .await
+                .context(format!(
+                    "Lane entries from {:?} to {:?} not available locally",
+                    buc.from, buc.ccp.consensus_proposal.cut
+                ))?;
 
             if let Some(missing_hash) = missing_hash {
                 self.send_sync_request(lane_id, from_hash, Some(&missing_hash))?;
@@ -110,6 +120,41 @@ impl super::Mempool {
         Ok(result)
     }
 
+    /// Data proposals of a lane after `from_hash` and up to `to_hash`, oldest first.
+    /// Stops at the first data proposal not available locally, and returns its hash.
+    pub(super) async fn get_lane_dps_between(
+        &self,
+        lane_id: &LaneId,
+        from_hash: Option<&DataProposalHash>,
+        to_hash: &DataProposalHash,
+    ) -> Result<(Vec<DataProposal>, Option<DataProposalHash>)> {
+        trace!("Fetching data proposals for lane {}", lane_id);
+        let mut dps = vec![];
+
+        // iterate over the lane entries between from_hash and to_hash of the lane
+        let mut entries = Box::pin(self.lanes.get_entries_between_hashes(
+            lane_id,
+            from_hash.cloned(),
+            Some(to_hash.clone()),
+        ));
+
+        while let Some(entry) = entries.next().await {
+            trace!("Processing lane entry {:?}", entry);
+            match entry? {
+                EntryOrMissingHash::Entry(_, dp) => dps.insert(0, dp),
+                EntryOrMissingHash::MissingHash(hash) => {
+                    debug!(
+                        "Data proposal {} not available locally for lane {}",
+                        hash, lane_id
+                    );
+                    return Ok((dps, Some(hash)));
+                }
+            }
+        }
+
+        Ok((dps, None))
+    }
+
     pub async fn build_signed_block_and_emit(
         &mut self,
         buc: &BlockUnderConstruction,
@@ -121,22 +166,91 @@ impl super::Mempool {
 
         self.metrics.constructed_block.add(1, &[]);
 
+        let signed_block = SignedBlock {
+            data_proposals: block_data,
+            certificate: buc.ccp.certificate.clone(),
+            consensus_proposal: buc.ccp.consensus_proposal.clone(),
+        };
+        if signed_block.tx_merkle_root() != signed_block.consensus_proposal.tx_root {
+            // Followers check the root before voting, so a committed block can't mismatch
+            error!(
+                "Transaction root of block {} does not match its data proposals",
+                signed_block.consensus_proposal.slot
+            );
+        }
+
         debug!(
             "🚧 Built signed block for slot {} with {} data proposals",
             buc.ccp.consensus_proposal.slot,
-            block_data.len()
+            signed_block.data_proposals.len()
         );
 
+        let committed: Vec<(LaneId, Vec<DataProposalHash>)> = if self.conf.mempool.retention.enabled
+        {
+            signed_block
+                .data_proposals
+                .iter()
+                .map(|(lane_id, dps)| (lane_id.clone(), dps.iter().map(|dp| dp.hashed()).collect()))
+                .collect()
+        } else {
+            vec![]
+        };
+
         self.bus
-            .send(MempoolBlockEvent::BuiltSignedBlock(SignedBlock {
-                data_proposals: block_data,
-                certificate: buc.ccp.certificate.clone(),
-                consensus_proposal: buc.ccp.consensus_proposal.clone(),
-            }))?;
+            .send(MempoolBlockEvent::BuiltSignedBlock(signed_block))?;
+
+        if self.conf.mempool.retention.enabled {
+            let block_height = BlockHeight(buc.ccp.consensus_proposal.slot);
+            let mut touched_lanes: BTreeSet<LaneId> = BTreeSet::new();
+            for (lane_id, dp_hashes) in committed {
+                touched_lanes.insert(lane_id.clone());
+                self.retention
+                    .record_committed(block_height, lane_id, dp_hashes);
+            }
+            touched_lanes.extend(self.prune_committed_lane_entries(block_height));
+            self.persist_retention(touched_lanes);
+        }
 
         Ok(())
     }
 
+    /// Removes the committed lane entries that fall out of the retention window.
+    /// Their blocks were already emitted, so they remain available through DA.
+    /// Returns the lanes that were pruned.
+    fn prune_committed_lane_entries(&mut self, block_height: BlockHeight) -> BTreeSet<LaneId> {
+        let mut pruned_lanes = BTreeSet::new();
+        for (lane_id, dp_hash) in self
+            .retention
+            .take_prunable(&self.conf.mempool.retention, block_height)
+        {
+            if log_warn!(
+                self.lanes.prune(&lane_id, &dp_hash),
+                "Pruning data proposal {} of lane {}",
+                dp_hash,
+                lane_id
+            )
+            .is_ok()
+            {
+                self.metrics.add_pruned_dp(&lane_id);
+            }
+            pruned_lanes.insert(lane_id);
+        }
+        pruned_lanes
+    }
+
+    /// Saves the retention of these lanes with the lanes storage, so it survives a crash.
+    fn persist_retention(&mut self, lane_ids: BTreeSet<LaneId>) {
+        let empty = Default::default();
+        for lane_id in lane_ids {
+            let committed = self.retention.committed(&lane_id).unwrap_or(&empty);
+            _ = log_error!(
+                self.lanes.put_retention(&lane_id, committed),
+                "Persisting retention of lane {}",
+                lane_id
+            );
+        }
+    }
+
     /// Send an event if none was broadcast before
     fn set_ccp_build_start_height(&mut self, slot: Slot) {
         if self.buc_build_start_height.is_none()
@@ -246,8 +360,11 @@ pub mod test {
     use staking::state::Staking;
     use utils::TimestampMs;
 
-    use crate::mempool::MempoolNetMessage;
+    use std::sync::Arc;
+
+    use crate::mempool::{retention::RetentionConf, MempoolNetMessage, QueryTxRoot};
     use crate::tests::autobahn_testing::assert_chanmsg_matches;
+    use crate::utils::conf::Conf;
 
     use super::super::test::*;
     use super::*;
@@ -342,6 +459,71 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    async fn signed_block_prunes_committed_entries() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let mut conf = Conf::default();
+        conf.mempool.retention = RetentionConf {
+            enabled: true,
+            keep_dps: 0,
+            keep_blocks: 0,
+        };
+        ctx.mempool.conf = Arc::new(conf);
+
+        let dp1 = ctx.create_data_proposal(
+            None,
+            &[make_register_contract_tx(ContractName::new("test1"))],
+        );
+        ctx.process_new_data_proposal(dp1.clone())?;
+        let dp2 = ctx.create_data_proposal(
+            Some(dp1.hashed()),
+            &[make_register_contract_tx(ContractName::new("test2"))],
+        );
+        ctx.process_new_data_proposal(dp2.clone())?;
+        let cumul_size = LaneBytesSize((dp1.estimate_size() + dp2.estimate_size()) as u64);
+
+        let key = ctx.validator_pubkey().clone();
+        let lane_id = LaneId(key.clone());
+        ctx.add_trusted_validator(&key);
+
+        ctx.process_cut_with_dp(&key, &dp2.hashed(), cumul_size, 1)
+            .await?;
+        assert!(ctx.mempool.lanes.contains(&lane_id, &dp1.hashed()));
+
+        // Once the block is old enough, everything but the last committed entry is pruned
+        ctx.process_cut_with_dp(&key, &dp2.hashed(), cumul_size, 2)
+            .await?;
+        assert!(!ctx.mempool.lanes.contains(&lane_id, &dp1.hashed()));
+        assert!(ctx.mempool.lanes.is_pruned(&lane_id, &dp1.hashed()));
+        assert!(ctx.mempool.lanes.contains(&lane_id, &dp2.hashed()));
+        assert!(!ctx.mempool.lanes.is_pruned(&lane_id, &dp2.hashed()));
+        assert_eq!(
+            ctx.mempool
+                .lanes
+                .get_pruned_watermark(&lane_id)
+                .map(|(hash, _)| hash),
+            Some(dp1.hashed())
+        );
+
+        // The entries left to prune are stored with the lanes
+        let retention = ctx.mempool.lanes.get_retention()?;
+        assert_eq!(
+            retention
+                .committed(&lane_id)
+                .map(|committed| committed.iter().map(|(_, hash)| hash.clone()).collect()),
+            Some(vec![dp2.hashed()])
+        );
+
+        // New data proposals can still be built on top of the lane
+        let dp3 = ctx.create_data_proposal(
+            Some(dp2.hashed()),
+            &[make_register_contract_tx(ContractName::new("test3"))],
+        );
+        ctx.process_new_data_proposal(dp3)?;
+
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     async fn signed_block_start_building_later() -> Result<()> {
         let mut ctx = MempoolTestCtx::new("mempool").await;
@@ -433,6 +615,7 @@ pub mod test {
         // Create a ConsensusProposal that references both lanes
         let ccp = ConsensusProposal {
             slot: 1,
+            epoch: 0,
             cut: vec![
                 (
                     lane_id1.clone(),
@@ -450,6 +633,9 @@ pub mod test {
             staking_actions: vec![],
             timestamp: TimestampMs(0),
             parent_hash: ConsensusProposalHash("test".to_string()),
+            tx_root: EMPTY_MERKLE_ROOT,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
         };
 
         // Add the block to mempool 1
@@ -550,6 +736,7 @@ pub mod test {
         // Create a ConsensusProposal that references dp3
         let ccp = ConsensusProposal {
             slot: 1,
+            epoch: 0,
             cut: vec![(
                 lane_id.clone(),
                 dp3_hash,
@@ -559,6 +746,9 @@ pub mod test {
             staking_actions: vec![],
             timestamp: TimestampMs(0),
             parent_hash: ConsensusProposalHash("test".to_string()),
+            tx_root: EMPTY_MERKLE_ROOT,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
         };
 
         // Add the block to the mempool
@@ -592,4 +782,119 @@ pub mod test {
 
         Ok(())
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_query_tx_root_fetches_missing_dp() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let ctx_owner = MempoolTestCtx::new("mempool_owner").await;
+        let lane_id = ctx_owner.mempool.own_lane_id().clone();
+        let crypto = ctx_owner.mempool.crypto.clone();
+
+        let register_tx = make_register_contract_tx(ContractName::new("test1"));
+        let dp1 = DataProposal::new(None, vec![register_tx.clone()]);
+        let dp2 = DataProposal::new(Some(dp1.hashed()), vec![register_tx]);
+        ctx.mempool
+            .lanes
+            .store_data_proposal(&crypto, &lane_id, dp1.clone())?;
+
+        let query = QueryTxRoot {
+            parent_cut: vec![],
+            cut: vec![(
+                lane_id.clone(),
+                dp2.hashed(),
+                LaneBytesSize(100),
+                AggregateSignature::default(),
+            )],
+        };
+
+        // dp2 is missing, it's fetched from the lane operator
+        assert_eq!(ctx.mempool.handle_query_tx_root(&query)?, None);
+        match ctx
+            .assert_send(crypto.validator_pubkey(), "SyncRequest")
+            .await
+            .msg
+        {
+            MempoolNetMessage::SyncRequest(from, to) => {
+                assert_eq!(from, None);
+                assert_eq!(to, Some(dp2.hashed()));
+            }
+            _ => panic!("Expected SyncRequest message"),
+        };
+
+        ctx.mempool
+            .lanes
+            .store_data_proposal(&crypto, &lane_id, dp2.clone())?;
+        let block = SignedBlock {
+            data_proposals: vec![(lane_id, vec![dp1, dp2])],
+            ..SignedBlock::default()
+        };
+        assert_eq!(
+            ctx.mempool.handle_query_tx_root(&query)?,
+            Some(block.tx_merkle_root())
+        );
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_sync_reply_pruned_skips_blocks() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let ctx_owner = MempoolTestCtx::new("mempool_owner").await;
+        let lane_id = ctx_owner.mempool.own_lane_id().clone();
+        let crypto = ctx_owner.mempool.crypto.clone();
+
+        // dp1 was pruned by the lane operator, only dp2 is available
+        let dp1 = DataProposal::new(None, vec![]);
+        let dp2 = DataProposal::new(Some(dp1.hashed()), vec![]);
+        ctx.mempool
+            .lanes
+            .store_data_proposal(&crypto, &lane_id, dp1.clone())?;
+        ctx.mempool
+            .lanes
+            .store_data_proposal(&crypto, &lane_id, dp2.clone())?;
+        ctx.mempool.lanes.remove_lane_entry(&lane_id, &dp1.hashed());
+
+        for slot in [1, 2] {
+            ctx.mempool
+                .blocks_under_contruction
+                .push_back(BlockUnderConstruction {
+                    from: None,
+                    ccp: CommittedConsensusProposal {
+                        consensus_proposal: ConsensusProposal {
+                            slot,
+                            epoch: 0,
+                            cut: vec![(
+                                lane_id.clone(),
+                                dp2.hashed(),
+                                LaneBytesSize(100),
+                                AggregateSignature::default(),
+                            )],
+                            staking_actions: vec![],
+                            timestamp: TimestampMs(0),
+                            parent_hash: ConsensusProposalHash("test".to_string()),
+                            tx_root: EMPTY_MERKLE_ROOT,
+                            parent_commit_qc: AggregateSignature::default(),
+                            parent_contract_registry_root: EMPTY_MERKLE_ROOT,
+                        },
+                        staking: Staking::default(),
+                        certificate: AggregateSignature::default(),
+                    },
+                });
+        }
+
+        let reply =
+            crypto.sign_msg_with_header(MempoolNetMessage::SyncReplyPruned(dp1.hashed()))?;
+        ctx.handle_msg(&reply, "SyncReplyPruned").await;
+
+        // Both blocks are fetched from DA instead
+        assert!(ctx.mempool.blocks_under_contruction.is_empty());
+        assert_chanmsg_matches!(
+            ctx.mempool_event_receiver,
+            MempoolBlockEvent::MissingBlocks(height) => {
+                assert_eq!(height, BlockHeight(2));
+            }
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,266 @@
+//! zstd compression of data proposals, on the wire for peers that support it and in lane storage.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::Read,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::model::*;
+
+use super::metrics::MempoolMetrics;
+
+/// First P2P handshake version of nodes that accept compressed data proposals.
+pub const COMPRESSION_MIN_PEER_VERSION: u16 = 2;
+
+/// Magic number starting every zstd frame. Borsh-encoded data proposals start with 0 or 1.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CompressionConf {
+    /// Send compressed data proposals to the peers that support it
+    pub wire: bool,
+    /// Compress data proposals in the lanes storage
+    pub storage: bool,
+    /// zstd compression level
+    pub level: i32,
+    /// Data proposals smaller than this, once serialized, are left uncompressed
+    pub min_bytes: usize,
+    /// Compressed data proposals inflating beyond this size are rejected
+    pub max_decompressed_bytes: u64,
+}
+
+impl Default for CompressionConf {
+    fn default() -> Self {
+        Self {
+            wire: true,
+            storage: true,
+            level: 3,
+            min_bytes: 16_384,
+            // Room for a full data proposal (40MB by default) overshot by its last transaction
+            max_decompressed_bytes: 64_000_000,
+        }
+    }
+}
+
+/// Compresses data proposals and keeps track of which peers can receive them compressed.
+/// Cloned handles share the known peers.
+#[derive(Clone)]
+pub struct DataProposalCompression {
+    conf: CompressionConf,
+    metrics: MempoolMetrics,
+    peer_versions: Arc<RwLock<HashMap<ValidatorPublicKey, u16>>>,
+}
+
+impl DataProposalCompression {
+    pub fn new(conf: CompressionConf, metrics: MempoolMetrics) -> Self {
+        Self {
+            conf,
+            metrics,
+            peer_versions: Default::default(),
+        }
+    }
+
+    /// Records the handshake version a peer advertised.
+    pub fn on_new_peer(&self, pubkey: ValidatorPublicKey, version: u16) {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        self.peer_versions
+            .write()
+            .expect("logic issue")
+            .insert(pubkey, version);
+    }
+
+    pub fn known_peers(&self) -> HashSet<ValidatorPublicKey> {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        self.peer_versions
+            .read()
+            .expect("logic issue")
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Whether data proposals sent to this peer can be compressed
+    pub fn supports(&self, pubkey: &ValidatorPublicKey) -> bool {
+        #[allow(clippy::expect_used, reason = "not held across await")]
+        let peer_versions = self.peer_versions.read().expect("logic issue");
+        self.conf.wire
+            && peer_versions
+                .get(pubkey)
+                .is_some_and(|version| *version >= COMPRESSION_MIN_PEER_VERSION)
+    }
+
+    /// Returns None when the data proposal is too small to be worth compressing.
+    pub fn compress_for_wire(&self, data_proposal: &DataProposal) -> Result<Option<Vec<u8>>> {
+        if !self.conf.wire {
+            return Ok(None);
+        }
+        let encoded = borsh::to_vec(data_proposal)?;
+        if encoded.len() < self.conf.min_bytes {
+            return Ok(None);
+        }
+        self.compress(&encoded, "wire").map(Some)
+    }
+
+    pub fn decompress_from_wire(&self, compressed: &[u8]) -> Result<DataProposal> {
+        let encoded = self.decompress(compressed, "wire")?;
+        borsh::from_slice(&encoded).context("Decoding compressed data proposal")
+    }
+
+    /// Decompresses on the blocking pool, so large data proposals don't stall the caller.
+    pub async fn decompress_from_wire_blocking(&self, compressed: Vec<u8>) -> Result<DataProposal> {
+        let compression = self.clone();
+        tokio::task::spawn_blocking(move || compression.decompress_from_wire(&compressed))
+            .await
+            .context("Decompressing data proposal")?
+    }
+
+    pub fn encode_for_storage(&self, data_proposal: &DataProposal) -> Result<Vec<u8>> {
+        let encoded = borsh::to_vec(data_proposal)?;
+        if !self.conf.storage || encoded.len() < self.conf.min_bytes {
+            return Ok(encoded);
+        }
+        self.compress(&encoded, "storage")
+    }
+
+    /// Items stored uncompressed, including those written by older versions, are plain borsh.
+    pub fn decode_from_storage(&self, item: &[u8]) -> Result<DataProposal> {
+        if item.starts_with(&ZSTD_MAGIC) {
+            let encoded = self.decompress(item, "storage")?;
+            return borsh::from_slice(&encoded).map_err(Into::into);
+        }
+        borsh::from_slice(item).map_err(Into::into)
+    }
+
+    fn compress(&self, encoded: &[u8], target: &'static str) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let compressed = zstd::bulk::compress(encoded, self.conf.level)?;
+        self.metrics.record_dp_compression(
+            "compress",
+            target,
+            encoded.len(),
+            compressed.len(),
+            start.elapsed(),
+        );
+        Ok(compressed)
+    }
+
+    fn decompress(&self, compressed: &[u8], target: &'static str) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let mut encoded = vec![];
+        zstd::stream::read::Decoder::with_buffer(compressed)?
+            .take(self.conf.max_decompressed_bytes + 1)
+            .read_to_end(&mut encoded)?;
+        if encoded.len() as u64 > self.conf.max_decompressed_bytes {
+            bail!(
+                "Compressed data proposal inflates beyond {} bytes",
+                self.conf.max_decompressed_bytes
+            );
+        }
+        self.metrics.record_dp_compression(
+            "decompress",
+            target,
+            encoded.len(),
+            compressed.len(),
+            start.elapsed(),
+        );
+        Ok(encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyle_crypto::BlstCrypto;
+
+    use super::*;
+
+    fn new_compression(conf: CompressionConf) -> DataProposalCompression {
+        DataProposalCompression::new(conf, MempoolMetrics::global("test".to_string()))
+    }
+
+    fn large_data_proposal() -> DataProposal {
+        let blob = Blob {
+            contract_name: "test".into(),
+            data: BlobData(vec![42; 100_000]),
+        };
+        DataProposal::new(
+            None,
+            vec![BlobTransaction::new("id@test", vec![blob]).into()],
+        )
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let compression = new_compression(CompressionConf::default());
+        let dp = large_data_proposal();
+
+        let compressed = compression.compress_for_wire(&dp).unwrap().unwrap();
+        assert!(compressed.len() < dp.estimate_size() / 10);
+        assert_eq!(compression.decompress_from_wire(&compressed).unwrap(), dp);
+
+        let item = compression.encode_for_storage(&dp).unwrap();
+        assert!(item.starts_with(&ZSTD_MAGIC));
+        assert_eq!(compression.decode_from_storage(&item).unwrap(), dp);
+
+        // Small data proposals are left alone
+        let small = DataProposal::new(None, vec![]);
+        assert_eq!(compression.compress_for_wire(&small).unwrap(), None);
+    }
+
+    #[test]
+    fn test_reads_uncompressed_items() {
+        let dp = large_data_proposal();
+        let legacy_item = borsh::to_vec(&dp).unwrap();
+
+        let compression = new_compression(CompressionConf::default());
+        assert_eq!(compression.decode_from_storage(&legacy_item).unwrap(), dp);
+
+        // And compressed items stay readable once storage compression is turned off
+        let item = compression.encode_for_storage(&dp).unwrap();
+        let disabled = new_compression(CompressionConf {
+            storage: false,
+            ..Default::default()
+        });
+        assert_eq!(disabled.encode_for_storage(&dp).unwrap(), legacy_item);
+        assert_eq!(disabled.decode_from_storage(&item).unwrap(), dp);
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let dp = large_data_proposal();
+        let compressed = new_compression(CompressionConf::default())
+            .compress_for_wire(&dp)
+            .unwrap()
+            .unwrap();
+
+        let limited = new_compression(CompressionConf {
+            max_decompressed_bytes: 1_000,
+            ..Default::default()
+        });
+        assert!(limited.decompress_from_wire(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_peer_negotiation() {
+        let compression = new_compression(CompressionConf::default());
+        let old_peer = BlstCrypto::new("old").unwrap().validator_pubkey().clone();
+        let new_peer = BlstCrypto::new("new").unwrap().validator_pubkey().clone();
+        compression.on_new_peer(old_peer.clone(), 1);
+        compression.on_new_peer(new_peer.clone(), COMPRESSION_MIN_PEER_VERSION);
+
+        assert!(!compression.supports(&old_peer));
+        assert!(compression.supports(&new_peer));
+        assert_eq!(compression.known_peers().len(), 2);
+
+        let disabled = new_compression(CompressionConf {
+            wire: false,
+            ..Default::default()
+        });
+        disabled.on_new_peer(new_peer.clone(), COMPRESSION_MIN_PEER_VERSION);
+        assert!(!disabled.supports(&new_peer));
+    }
+}
@@ -0,0 +1,187 @@
+//! Detection of lane owners disseminating conflicting data proposals.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use anyhow::{bail, Result};
+use tracing::{debug, warn};
+
+use crate::{bus::BusClientSender, consensus::ConsensusCommand, model::*};
+
+use super::{storage::Storage, Mempool};
+
+/// Headers remembered for each lane
+const SEEN_DATA_PROPOSALS_PER_LANE: usize = 64;
+
+struct SeenDataProposal {
+    parent_data_proposal_hash: Option<DataProposalHash>,
+    data_proposal_hash: DataProposalHash,
+    header: SignedByValidator<MsgHeader>,
+}
+
+/// Signed headers of the last data proposals received on each lane.
+#[derive(Default)]
+pub struct LaneHeaders {
+    lanes: BTreeMap<LaneId, VecDeque<SeenDataProposal>>,
+}
+
+impl LaneHeaders {
+    /// Remembers the header once the data proposal matches its hash,
+    /// and returns the one of another data proposal seen on top of the same parent.
+    pub fn record(
+        &mut self,
+        lane_id: &LaneId,
+        data_proposal_hash: &DataProposalHash,
+        data_proposal: &DataProposal,
+        header: &SignedByValidator<MsgHeader>,
+    ) -> Result<Option<(DataProposalHash, SignedByValidator<MsgHeader>)>> {
+        // The hash is cached, processing the data proposal afterwards won't compute it again
+        if &data_proposal.hashed() != data_proposal_hash {
+            bail!("Data proposal {data_proposal_hash} of lane {lane_id} does not match its hash");
+        }
+        let seen = self.lanes.entry(lane_id.clone()).or_default();
+        if let Some(sibling) = seen
+            .iter()
+            .find(|s| s.parent_data_proposal_hash == data_proposal.parent_data_proposal_hash)
+        {
+            if &sibling.data_proposal_hash == data_proposal_hash {
+                return Ok(None);
+            }
+            return Ok(Some((
+                sibling.data_proposal_hash.clone(),
+                sibling.header.clone(),
+            )));
+        }
+        if seen.len() >= SEEN_DATA_PROPOSALS_PER_LANE {
+            seen.pop_front();
+        }
+        seen.push_back(SeenDataProposal {
+            parent_data_proposal_hash: data_proposal.parent_data_proposal_hash.clone(),
+            data_proposal_hash: data_proposal_hash.clone(),
+            header: header.clone(),
+        });
+        Ok(None)
+    }
+}
+
+impl Mempool {
+    /// Reports the lane owner to consensus if it already disseminated another data proposal on top of the same parent.
+    pub(super) fn check_lane_equivocation(
+        &mut self,
+        lane_id: &LaneId,
+        header: &SignedByValidator<MsgHeader>,
+        data_proposal_hash: &DataProposalHash,
+        data_proposal: &DataProposal,
+    ) -> Result<()> {
+        // Only bonded validators can be slashed, other lanes are not worth remembering
+        if !self.staking.is_bonded(&header.signature.validator) {
+            return Ok(());
+        }
+        let Some((seen_hash, seen_header)) =
+            self.lane_headers
+                .record(lane_id, data_proposal_hash, data_proposal, header)?
+        else {
+            return Ok(());
+        };
+
+        // Only verified data proposals are stored, the evidence can't be built from the other one.
+        let Some(seen_data_proposal) = self.lanes.get_dp_by_hash(lane_id, &seen_hash)? else {
+            debug!(
+                "Data proposal {} of lane {} conflicts with {}, which was not stored",
+                data_proposal_hash, lane_id, seen_hash
+            );
+            return Ok(());
+        };
+
+        let evidence = EquivocationEvidence::DataProposal(
+            SignedDataProposal::new(seen_header, &seen_data_proposal),
+            SignedDataProposal::new(header.clone(), data_proposal),
+        );
+        evidence.check_conflict()?;
+        warn!(
+            "🚨 Lane owner {} disseminated data proposals {} and {} on top of {:?}",
+            lane_id, seen_hash, data_proposal_hash, data_proposal.parent_data_proposal_hash
+        );
+        self.bus
+            .send(ConsensusCommand::ReportEquivocation(Box::new(evidence)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mempool::test::make_register_contract_tx;
+
+    fn header(hash: &DataProposalHash) -> SignedByValidator<MsgHeader> {
+        SignedByValidator {
+            msg: MsgHeader {
+                timestamp: 0,
+                hash: HeaderSignableData(hash.0.clone().into_bytes()),
+            },
+            signature: ValidatorSignature {
+                signature: Signature(vec![]),
+                validator: ValidatorPublicKey(vec![0]),
+            },
+        }
+    }
+
+    fn data_proposal(
+        parent: &Option<DataProposalHash>,
+        tx: &str,
+    ) -> (DataProposalHash, DataProposal) {
+        let data_proposal = DataProposal::new(
+            parent.clone(),
+            vec![make_register_contract_tx(ContractName::new(tx))],
+        );
+        (data_proposal.hashed(), data_proposal)
+    }
+
+    #[test]
+    fn test_record_conflicting_headers() {
+        let lane_id = LaneId::default();
+        let parent = Some(DataProposalHash("parent".to_string()));
+        let (a, dp_a) = data_proposal(&parent, "a");
+        let (b, dp_b) = data_proposal(&parent, "b");
+        let mut headers = LaneHeaders::default();
+
+        assert!(headers
+            .record(&lane_id, &a, &dp_a, &header(&a))
+            .unwrap()
+            .is_none());
+        // Receiving the same data proposal again is fine
+        assert!(headers
+            .record(&lane_id, &a, &dp_a, &header(&a))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            headers.record(&lane_id, &b, &dp_b, &header(&b)).unwrap(),
+            Some((a.clone(), header(&a)))
+        );
+        // On another lane, or another parent, there is no conflict
+        let other_lane = LaneId(ValidatorPublicKey(vec![1]));
+        assert!(headers
+            .record(&other_lane, &b, &dp_b, &header(&b))
+            .unwrap()
+            .is_none());
+        let (c, dp_c) = data_proposal(&None, "b");
+        assert!(headers
+            .record(&lane_id, &c, &dp_c, &header(&c))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_record_checks_the_hash() {
+        let lane_id = LaneId::default();
+        let (a, dp_a) = data_proposal(&None, "a");
+        let (b, _) = data_proposal(&None, "b");
+        let mut headers = LaneHeaders::default();
+
+        // A data proposal sent under another hash is not remembered
+        assert!(headers.record(&lane_id, &b, &dp_a, &header(&b)).is_err());
+        assert!(headers
+            .record(&lane_id, &a, &dp_a, &header(&a))
+            .unwrap()
+            .is_none());
+    }
+}
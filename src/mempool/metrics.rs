@@ -1,12 +1,14 @@
+use std::time::Duration;
+
 use hyle_model::LaneId;
 use opentelemetry::{
-    metrics::{Counter, Gauge},
+    metrics::{Counter, Gauge, Histogram},
     InstrumentationScope, KeyValue,
 };
 
 use crate::model::ValidatorPublicKey;
 
-use super::QueryNewCut;
+use super::{policy::PendingIndex, QueryNewCut};
 
 #[derive(Clone)]
 pub struct MempoolMetrics {
@@ -21,11 +23,16 @@ pub struct MempoolMetrics {
     received_dp: Counter<u64>,
     hashed_dp: Counter<u64>,
     processed_dp: Counter<u64>,
+    pruned_dp: Counter<u64>,
     pub constructed_block: Counter<u64>,
     pub on_data_vote: Counter<u64>,
     // Number of individual DPs sent (counting one per validator)
     pub dp_disseminations: Counter<u64>,
     pub created_data_proposals: Counter<u64>,
+
+    dp_compression_ratio: Histogram<f64>,
+    dp_compression_time: Histogram<f64>,
+    dp_compression_bytes: Counter<u64>,
 }
 
 impl MempoolMetrics {
@@ -57,6 +64,7 @@ impl MempoolMetrics {
             processed_dp: my_meter
                 .u64_counter(format!("{mempool}_processed_dp"))
                 .build(),
+            pruned_dp: my_meter.u64_counter(format!("{mempool}_pruned_dp")).build(),
             constructed_block: my_meter
                 .u64_counter(format!("{mempool}_constructed_block"))
                 .build(),
@@ -69,6 +77,16 @@ impl MempoolMetrics {
             created_data_proposals: my_meter
                 .u64_counter(format!("{mempool}_created_data_proposals"))
                 .build(),
+
+            dp_compression_ratio: my_meter
+                .f64_histogram(format!("{mempool}_dp_compression_ratio"))
+                .build(),
+            dp_compression_time: my_meter
+                .f64_histogram(format!("{mempool}_dp_compression_time_seconds"))
+                .build(),
+            dp_compression_bytes: my_meter
+                .u64_counter(format!("{mempool}_dp_compression_bytes"))
+                .build(),
         }
     }
 
@@ -82,9 +100,17 @@ impl MempoolMetrics {
         )
     }
 
-    pub fn snapshot_pending_tx(&self, nb: usize) {
-        self.tx_waiting_dissemination
-            .record(nb as u64, &[KeyValue::new("status", "pending")])
+    /// Records the depth of the pending queue, per policy class.
+    pub fn snapshot_pending_tx(&self, index: &PendingIndex) {
+        for (class, nb) in index.depth() {
+            self.tx_waiting_dissemination.record(
+                nb,
+                &[
+                    KeyValue::new("status", "pending"),
+                    KeyValue::new("class", class.to_string()),
+                ],
+            )
+        }
     }
 
     pub fn add_api_tx(&self, kind: &'static str) {
@@ -105,6 +131,57 @@ impl MempoolMetrics {
             ],
         );
     }
+    pub fn reject_api_tx(&self, kind: &'static str, reason: &'static str) {
+        self.api_tx.add(
+            1,
+            &[
+                KeyValue::new("status", "rejected"),
+                KeyValue::new("tx_kind", kind),
+                KeyValue::new("reason", reason),
+            ],
+        );
+    }
+    pub fn evict_api_tx(&self, kind: &'static str) {
+        self.api_tx.add(
+            1,
+            &[
+                KeyValue::new("status", "evicted"),
+                KeyValue::new("tx_kind", kind),
+            ],
+        );
+    }
+
+    /// Ratio is uncompressed over compressed size, for `op` "compress" or "decompress" on `target` "wire" or "storage"
+    pub fn record_dp_compression(
+        &self,
+        op: &'static str,
+        target: &'static str,
+        uncompressed: usize,
+        compressed: usize,
+        elapsed: Duration,
+    ) {
+        let labels = [KeyValue::new("op", op), KeyValue::new("target", target)];
+        self.dp_compression_ratio
+            .record(uncompressed as f64 / compressed.max(1) as f64, &labels);
+        self.dp_compression_time
+            .record(elapsed.as_secs_f64(), &labels);
+        self.dp_compression_bytes.add(
+            uncompressed as u64,
+            &[
+                KeyValue::new("op", op),
+                KeyValue::new("target", target),
+                KeyValue::new("size", "uncompressed"),
+            ],
+        );
+        self.dp_compression_bytes.add(
+            compressed as u64,
+            &[
+                KeyValue::new("op", op),
+                KeyValue::new("target", target),
+                KeyValue::new("size", "compressed"),
+            ],
+        );
+    }
 
     pub fn add_dp_vote(&self, sender: &ValidatorPublicKey, dest: &ValidatorPublicKey) {
         self.dp_vote.add(
@@ -131,6 +208,11 @@ impl MempoolMetrics {
             .add(1, &[KeyValue::new("lane_id", format!("{lane_id}"))])
     }
 
+    pub fn add_pruned_dp(&self, lane_id: &LaneId) {
+        self.pruned_dp
+            .add(1, &[KeyValue::new("lane_id", format!("{lane_id}"))])
+    }
+
     /// *emitted* a sync request
     pub fn sync_request_send(&self, lane: &LaneId, requester: &ValidatorPublicKey) {
         self.sync_request.add(
@@ -164,6 +246,30 @@ impl MempoolMetrics {
         );
     }
 
+    /// *received* a sync reply for a pruned entry
+    pub fn sync_reply_pruned_receive(&self, lane: &LaneId, requester: &ValidatorPublicKey) {
+        self.sync_reply.add(
+            1,
+            &[
+                KeyValue::new("lane", format!("{lane}")),
+                KeyValue::new("requester", format!("{requester}")),
+                KeyValue::new("status", "pruned"),
+            ],
+        )
+    }
+
+    /// MempoolSync: Reply that the requested entry was pruned
+    pub fn mempool_sync_pruned(&self, lane: &LaneId, requester: &ValidatorPublicKey) {
+        self.mempool_sync.add(
+            1,
+            &[
+                KeyValue::new("lane", format!("{lane}")),
+                KeyValue::new("requester", format!("{requester}")),
+                KeyValue::new("status", "pruned"),
+            ],
+        );
+    }
+
     /// MempoolSync: Prepare a sync reply to *send*
     pub fn mempool_sync_failure(&self, lane: &LaneId, requester: &ValidatorPublicKey) {
         self.mempool_sync.add(
@@ -2,43 +2,69 @@ use hyle_modules::{log_error, module_handle_messages};
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 use crate::{
-    consensus::ConsensusEvent, model::*, node_state::module::NodeStateEvent,
-    p2p::network::MsgWithHeader, utils::conf::P2pMode,
+    consensus::ConsensusEvent,
+    model::*,
+    node_state::module::NodeStateEvent,
+    p2p::network::{MsgWithHeader, PeerEvent},
+    tcp_server::TcpServerRequest,
+    utils::conf::P2pMode,
 };
 
-use client_sdk::tcp_client::TcpServerMessage;
 use hyle_model::{DataProposalHash, LaneBytesSize, LaneId, ProgramId, Verifier};
 use hyle_modules::{bus::SharedMessageBus, modules::Module};
 use tracing::warn;
 
-use super::{api::RestApiMessage, MempoolNetMessage, QueryNewCut};
+use super::{api::RestApiMessage, MempoolNetMessage, NewCut, QueryNewCut, QueryTxRoot};
 
 use crate::model::SharedRunContext;
 
 use super::{
-    api, mempool_bus_client::MempoolBusClient, metrics::MempoolMetrics,
-    storage_fjall::LanesStorage, Mempool, MempoolStore,
+    admission::AdmissionControl,
+    api,
+    compression::DataProposalCompression,
+    mempool_bus_client::MempoolBusClient,
+    metrics::MempoolMetrics,
+    policy::{ConfMempoolPolicy, PendingIndex},
+    storage::Storage,
+    storage_fjall::LanesStorage,
+    Mempool, MempoolStore,
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 impl Module for Mempool {
     type Context = SharedRunContext;
 
     async fn build(bus: SharedMessageBus, ctx: Self::Context) -> Result<Self> {
         let metrics = MempoolMetrics::global(ctx.config.id.clone());
-        let api = api::api(&bus, &ctx.api).await;
-        if let Ok(mut guard) = ctx.api.router.lock() {
-            if let Some(router) = guard.take() {
-                guard.replace(router.nest("/v1/", api));
-            }
-        }
-        let bus = MempoolBusClient::new_from_bus(bus.new_handle()).await;
 
-        let attributes = Self::load_from_disk::<MempoolStore>(
+        let attributes = match Self::load_from_disk::<MempoolStore>(
             ctx.config.data_directory.join("mempool.bin").as_path(),
-        )
-        .unwrap_or_default();
+        ) {
+            Some(attributes) => attributes,
+            None => {
+                let attributes = MempoolStore::default();
+                // A node bootstrapped from a snapshot never sees the registration of its contracts
+                if let Some((_, store)) = ctx
+                    .config
+                    .node_state_snapshots
+                    .bootstrap_snapshot()
+                    .context("Loading bootstrap snapshot")?
+                {
+                    #[allow(clippy::expect_used, reason = "not held across await")]
+                    let mut known_contracts =
+                        attributes.known_contracts.write().expect("logic issue");
+                    for contract in store.contracts.values() {
+                        known_contracts.register_contract(
+                            &contract.name,
+                            &contract.verifier,
+                            &contract.program_id,
+                        );
+                    }
+                }
+                attributes
+            }
+        };
 
         let lanes_tip =
             Self::load_from_disk::<BTreeMap<LaneId, (DataProposalHash, LaneBytesSize)>>(
@@ -64,13 +90,38 @@ impl Module for Mempool {
             .entry("hyle".into())
             .or_insert_with(|| (Verifier("hyle".to_owned()), ProgramId(vec![])));
 
+        let admission = Arc::new(AdmissionControl::new(
+            ctx.config.mempool.admission.clone(),
+            Arc::clone(&attributes.known_contracts),
+        ));
+        let api = api::api(&bus, &ctx.api, Arc::clone(&admission)).await;
+        if let Ok(mut guard) = ctx.api.router.lock() {
+            if let Some(router) = guard.take() {
+                guard.replace(router.nest("/v1/", api));
+            }
+        }
+        let bus = MempoolBusClient::new_from_bus(bus.new_handle()).await;
+        let compression =
+            DataProposalCompression::new(ctx.config.mempool.compression.clone(), metrics.clone());
+
+        let lanes = LanesStorage::new(&ctx.config.data_directory, lanes_tip, compression.clone())?;
+        let retention = lanes.get_retention()?;
+
+        let policy = ConfMempoolPolicy::new(ctx.config.mempool.clone());
+        let pending_index = PendingIndex::new(&policy, &attributes.waiting_dissemination_txs);
+
         Ok(Mempool {
             bus,
             file: Some(ctx.config.data_directory.clone()),
             conf: ctx.config.clone(),
             crypto: Arc::clone(&ctx.crypto),
             metrics,
-            lanes: LanesStorage::new(&ctx.config.data_directory, lanes_tip)?,
+            policy: Box::new(policy),
+            pending_index,
+            admission,
+            lanes,
+            compression,
+            retention,
             inner: attributes,
         })
     }
@@ -100,10 +151,13 @@ impl Module for Mempool {
             listen<MsgWithHeader<MempoolNetMessage>> cmd => {
                 let _ = log_error!(self.handle_net_message(cmd, &sync_request_sender).await, "Handling MempoolNetMessage in Mempool");
             }
+            listen<PeerEvent> PeerEvent::NewPeer { pubkey, version, .. } => {
+                self.compression.on_new_peer(pubkey, version);
+            }
             listen<RestApiMessage> cmd => {
                 let _ = log_error!(self.handle_api_message(cmd), "Handling API Message in Mempool");
             }
-            listen<TcpServerMessage> cmd => {
+            listen<TcpServerRequest> cmd => {
                 let _ = log_error!(self.handle_tcp_server_message(cmd), "Handling TCP Server message in Mempool");
             }
             listen<ConsensusEvent> cmd => {
@@ -123,11 +177,15 @@ impl Module for Mempool {
                 for (contract_name, program_id) in block.updated_program_ids.into_iter() {
                     self.handle_contract_update(contract_name, program_id);
                 }
+                self.admission.on_new_block(&block.block_timestamp);
 
             }
-            command_response<QueryNewCut, Cut> staking => {
+            command_response<QueryNewCut, NewCut> staking => {
                 self.handle_querynewcut(staking)
             }
+            command_response<QueryTxRoot, Option<MerkleHash>> query => {
+                self.handle_query_tx_root(query)
+            }
             Some(event) = self.inner.processing_dps.join_next() => {
                 if let Ok(event) = log_error!(event, "Processing DPs from JoinSet") {
                     if let Ok(event) = log_error!(event, "Error in running task") {
@@ -1,10 +1,11 @@
 //! Logic for processing the API inbound TXs in the mempool.
 
 use crate::mempool::storage::MetadataOrMissingHash;
+use crate::tcp_server::{TcpServerReply, TcpServerRequest};
 use crate::{bus::BusClientSender, model::*};
 
 use anyhow::{bail, Context, Result};
-use client_sdk::tcp_client::TcpServerMessage;
+use client_sdk::tcp_client::{TcpServerMessage, TcpServerResponse};
 use futures::StreamExt;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -192,10 +193,7 @@ impl super::Mempool {
             self.metrics
                 .dp_disseminations
                 .add(self.staking.bonded().len() as u64, &[]);
-            self.broadcast_net_message(MempoolNetMessage::DataProposal(
-                data_proposal.hashed(),
-                data_proposal.clone(),
-            ))?;
+            self.broadcast_data_proposal(None, &data_proposal)?;
         } else {
             // If None, rebroadcast it to every validator that has not yet signed it
             let validator_that_has_signed: HashSet<&ValidatorPublicKey> = entry_metadata
@@ -237,42 +235,32 @@ impl super::Mempool {
                 .dp_disseminations
                 .add(only_for.len() as u64, &[]);
 
-            self.broadcast_only_for_net_message(
-                only_for,
-                MempoolNetMessage::DataProposal(data_proposal.hashed(), data_proposal.clone()),
-            )?;
+            self.broadcast_data_proposal(Some(only_for), &data_proposal)?;
         }
         Ok(true)
     }
 
     /// Inits DataProposal preparation if there are pending transactions
     fn init_dp_preparation_if_pending(&mut self) -> Result<Option<DataProposal>> {
-        self.metrics
-            .snapshot_pending_tx(self.waiting_dissemination_txs.len());
+        self.metrics.snapshot_pending_tx(&self.pending_index);
         if self.waiting_dissemination_txs.is_empty()
             || !self.own_data_proposal_in_preparation.is_empty()
         {
             return Ok(None);
         }
 
-        let mut cumulative_size = 0;
-        let mut current_idx = 0;
-        while cumulative_size < 40_000_000 && current_idx < self.waiting_dissemination_txs.len() {
-            if let Some((_tx_hash, tx)) = self.waiting_dissemination_txs.get_index(current_idx) {
-                cumulative_size += tx.estimate_size();
-                current_idx += 1;
-            }
-        }
-        let collected_txs: Vec<Transaction> = self
-            .waiting_dissemination_txs
-            .drain(0..current_idx)
-            .map(|(_tx_hash, tx)| tx)
-            .collect();
+        let collected_txs = self.policy.select_for_data_proposal(
+            &mut self.inner.waiting_dissemination_txs,
+            &mut self.pending_index,
+        );
 
         debug!(
             "🌝 Creating new data proposals with {} txs (est. size {}). {} tx remain.",
             collected_txs.len(),
-            cumulative_size,
+            collected_txs
+                .iter()
+                .map(|tx| tx.estimate_size())
+                .sum::<usize>(),
             self.waiting_dissemination_txs.len()
         );
 
@@ -331,9 +319,34 @@ impl super::Mempool {
         Ok(())
     }
 
-    pub(super) fn handle_tcp_server_message(&mut self, command: TcpServerMessage) -> Result<()> {
-        match command {
-            TcpServerMessage::NewTx(tx) => self.on_new_api_tx(tx)?,
+    pub(super) fn handle_tcp_server_message(&mut self, request: TcpServerRequest) -> Result<()> {
+        match request.message {
+            TcpServerMessage::NewTx(tx) => {
+                if let Err(err) = self.admission.check(&tx.transaction_data) {
+                    let tx_hash = tx.hashed();
+                    debug!("Rejecting tx {} from TCP server: {}", tx_hash, err);
+                    self.metrics
+                        .reject_api_tx((&tx.transaction_data).into(), err.code());
+                    self.bus
+                        .send(MempoolStatusEvent::Rejected {
+                            tx_hash: tx_hash.clone(),
+                            code: err.code().to_string(),
+                        })
+                        .context("Sending Status event for rejected TX")?;
+                    self.bus
+                        .send(TcpServerReply {
+                            dest: request.dest,
+                            response: TcpServerResponse::Rejected {
+                                tx_hash,
+                                code: err.code().to_string(),
+                                message: err.to_string(),
+                            },
+                        })
+                        .context("Sending TCP server reply")?;
+                    return Ok(());
+                }
+                self.on_new_api_tx(tx)?
+            }
         }
         Ok(())
     }
@@ -408,6 +421,8 @@ impl super::Mempool {
         } else {
             self.waiting_dissemination_txs
                 .insert(tx_hash.clone(), tx.clone());
+            self.pending_index
+                .insert(self.policy.class(&tx), tx_hash.clone());
 
             // dbg!(&self.waiting);
 
@@ -423,10 +438,22 @@ impl super::Mempool {
             self.bus
                 .send(status_event)
                 .context("Sending Status event for TX")?;
+
+            for evicted in self.policy.evict_overflow(
+                &mut self.inner.waiting_dissemination_txs,
+                &mut self.pending_index,
+            ) {
+                let tx_hash = evicted.hashed();
+                debug!("Evicting tx {} from full mempool", tx_hash);
+                self.metrics
+                    .evict_api_tx((&evicted.transaction_data).into());
+                self.bus
+                    .send(MempoolStatusEvent::Evicted { tx_hash })
+                    .context("Sending Status event for evicted TX")?;
+            }
         }
 
-        self.metrics
-            .snapshot_pending_tx(self.waiting_dissemination_txs.len());
+        self.metrics.snapshot_pending_tx(&self.pending_index);
 
         Ok(())
     }
@@ -507,7 +534,14 @@ pub mod test {
 
     use super::*;
     use crate::{
-        mempool::storage::LaneEntryMetadata, p2p::network::HeaderSigner,
+        bus::{dont_use_this::get_receiver, metrics::BusMetrics, SharedMessageBus},
+        mempool::{
+            admission::{AdmissionConf, AdmissionControl},
+            compression::COMPRESSION_MIN_PEER_VERSION,
+            policy::{ConfMempoolPolicy, MempoolConf},
+            storage::LaneEntryMetadata,
+        },
+        p2p::network::{HeaderSigner, NetMessage, OutboundMessage},
         tests::autobahn_testing::assert_chanmsg_matches,
     };
     use anyhow::Result;
@@ -600,6 +634,81 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_tcp_rejection_reply() -> Result<()> {
+        let shared_bus = SharedMessageBus::new(BusMetrics::global("global".to_string()));
+        let mut reply_receiver = get_receiver::<TcpServerReply>(&shared_bus).await;
+        let mut mempool =
+            MempoolTestCtx::build_mempool(&shared_bus, BlstCrypto::new("mempool").unwrap()).await;
+        mempool.admission = Arc::new(AdmissionControl::new(
+            AdmissionConf {
+                max_blobs: 0,
+                ..Default::default()
+            },
+            Arc::clone(&mempool.known_contracts),
+        ));
+
+        let tx = make_register_contract_tx(ContractName::new("test1"));
+        mempool.handle_tcp_server_message(TcpServerRequest {
+            dest: "client".to_string(),
+            message: TcpServerMessage::NewTx(tx.clone()),
+        })?;
+
+        assert_chanmsg_matches!(
+            reply_receiver,
+            TcpServerReply { dest, response: TcpServerResponse::Rejected { tx_hash, code, .. } } => {
+                assert_eq!(dest, "client");
+                assert_eq!(tx_hash, tx.hashed());
+                assert_eq!(code, "too_many_blobs");
+            }
+        );
+        assert!(mempool.waiting_dissemination_txs.is_empty());
+
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn test_evicted_tx_status_event() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        ctx.mempool.policy = Box::new(ConfMempoolPolicy::new(MempoolConf {
+            max_pending_txs: 1,
+            ..Default::default()
+        }));
+
+        let register_tx = make_register_contract_tx(ContractName::new("test1"));
+        let register_tx_2 = make_register_contract_tx(ContractName::new("test2"));
+        ctx.submit_tx(&register_tx);
+        ctx.submit_tx(&register_tx_2);
+
+        assert_chanmsg_matches!(
+            ctx.mempool_status_event_receiver,
+            MempoolStatusEvent::WaitingDissemination { tx, .. } => {
+                assert_eq!(tx, register_tx);
+            }
+        );
+        assert_chanmsg_matches!(
+            ctx.mempool_status_event_receiver,
+            MempoolStatusEvent::WaitingDissemination { tx, .. } => {
+                assert_eq!(tx, register_tx_2);
+            }
+        );
+        assert_chanmsg_matches!(
+            ctx.mempool_status_event_receiver,
+            MempoolStatusEvent::Evicted { tx_hash } => {
+                assert_eq!(tx_hash, register_tx_2.hashed());
+            }
+        );
+        assert_eq!(
+            ctx.mempool
+                .waiting_dissemination_txs
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&register_tx.hashed()]
+        );
+
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     async fn test_send_poda_update() -> Result<()> {
         let mut ctx = MempoolTestCtx::new("mempool").await;
@@ -816,4 +925,93 @@ pub mod test {
 
         Ok(())
     }
+
+    #[test_log::test(tokio::test)]
+    async fn test_compressed_dissemination() -> Result<()> {
+        let mut ctx = MempoolTestCtx::new("mempool").await;
+        let crypto2 = BlstCrypto::new("validator2").unwrap();
+        let crypto3 = BlstCrypto::new("validator3").unwrap();
+        ctx.setup_node(&[
+            (*ctx.mempool.crypto).clone(),
+            crypto2.clone(),
+            crypto3.clone(),
+        ]);
+        // Only validator2 advertised compression support
+        ctx.mempool.compression.on_new_peer(
+            crypto2.validator_pubkey().clone(),
+            COMPRESSION_MIN_PEER_VERSION,
+        );
+        ctx.mempool
+            .compression
+            .on_new_peer(crypto3.validator_pubkey().clone(), 1);
+
+        let blob = Blob {
+            contract_name: "test".into(),
+            data: BlobData(vec![42; 100_000]),
+        };
+        let dp =
+            ctx.create_data_proposal(None, &[BlobTransaction::new("id@test", vec![blob]).into()]);
+        ctx.process_new_data_proposal(dp.clone())?;
+        ctx.timer_tick().await?;
+
+        // Compressed for validator2, as is for every other peer
+        let mut compressed_msg = None;
+        let mut uncompressed_sent = false;
+        while let Ok(rec) = ctx.out_receiver.try_recv() {
+            match rec {
+                OutboundMessage::BroadcastMessageOnlyFor(
+                    only_for,
+                    NetMessage::MempoolMessage(msg),
+                ) => {
+                    let MempoolNetMessage::CompressedDataProposal(hash, compressed) = &msg.msg
+                    else {
+                        panic!("Expected CompressedDataProposal message");
+                    };
+                    assert_eq!(
+                        only_for,
+                        HashSet::from([crypto2.validator_pubkey().clone()])
+                    );
+                    assert_eq!(hash, &dp.hashed());
+                    assert!(compressed.len() < dp.estimate_size() / 10);
+                    compressed_msg = Some(msg);
+                }
+                OutboundMessage::BroadcastMessageExcept(
+                    except,
+                    NetMessage::MempoolMessage(msg),
+                ) => {
+                    let MempoolNetMessage::DataProposal(hash, _) = &msg.msg else {
+                        panic!("Expected DataProposal message");
+                    };
+                    assert_eq!(except, HashSet::from([crypto2.validator_pubkey().clone()]));
+                    assert_eq!(hash, &dp.hashed());
+                    uncompressed_sent = true;
+                }
+                _ => {}
+            }
+        }
+        assert!(uncompressed_sent);
+
+        // The receiving end votes on the uncompressed size
+        let mut ctx2 = MempoolTestCtx::new("validator2").await;
+        ctx2.setup_node(&[(*ctx.mempool.crypto).clone(), crypto2.clone(), crypto3]);
+        ctx2.handle_msg(&compressed_msg.expect("compressed DataProposal"), "")
+            .await;
+        ctx2.handle_processed_data_proposals().await;
+
+        match ctx2
+            .assert_send(ctx.mempool.crypto.validator_pubkey(), "DataVote")
+            .await
+            .msg
+        {
+            MempoolNetMessage::DataVote(SignedByValidator {
+                msg: (hash, size), ..
+            }) => {
+                assert_eq!(hash, dp.hashed());
+                assert_eq!(size, LaneBytesSize(dp.estimate_size() as u64));
+            }
+            _ => panic!("Expected DataVote message"),
+        };
+
+        Ok(())
+    }
 }
@@ -0,0 +1,548 @@
+//! Ordering of pending transactions into data proposals, and eviction when the queue is full.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+
+use crate::model::*;
+
+use super::{admission::AdmissionConf, compression::CompressionConf, retention::RetentionConf};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub enum MempoolOrdering {
+    /// Arrival order
+    #[default]
+    Fifo,
+    /// Verified proofs before blob transactions, so that pending blobs settle sooner
+    ProofsFirst,
+    /// Transactions on contracts with a higher weight first
+    ContractWeights,
+    /// Round-robin between identities (proofs are grouped by contract)
+    IdentityFairness,
+}
+
+/// Configuration of the mempool policy and of transaction admission
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MempoolConf {
+    /// How pending transactions are ordered into data proposals
+    pub ordering: MempoolOrdering,
+    /// Weights used by the `ContractWeights` ordering. Unlisted contracts have a weight of 1.
+    pub contract_weights: HashMap<String, u64>,
+    /// Estimated size, in bytes, after which no more transactions are added to a data proposal
+    pub max_dp_bytes: usize,
+    /// Maximum number of transactions in a data proposal (0 for no limit)
+    pub max_dp_txs: usize,
+    /// Maximum number of transactions waiting for dissemination (0 for no limit).
+    /// When full, the lowest priority transactions are evicted.
+    pub max_pending_txs: usize,
+    /// Limits applied to transactions submitted to this node
+    pub admission: AdmissionConf,
+    /// Compression of data proposals on the wire and in storage
+    pub compression: CompressionConf,
+    /// Pruning of committed lane entries
+    pub retention: RetentionConf,
+}
+
+impl Default for MempoolConf {
+    fn default() -> Self {
+        Self {
+            ordering: MempoolOrdering::default(),
+            contract_weights: HashMap::new(),
+            max_dp_bytes: 40_000_000,
+            max_dp_txs: 0,
+            max_pending_txs: 0,
+            admission: AdmissionConf::default(),
+            compression: CompressionConf::default(),
+            retention: RetentionConf::default(),
+        }
+    }
+}
+
+/// Decides which pending transactions go into the next data proposal of our lane,
+/// and which ones to drop when too many are waiting.
+pub trait MempoolPolicy: Send + Sync {
+    /// Indices into `pending`, highest priority first. Must be a permutation of `0..pending.len()`.
+    fn order(&self, pending: &IndexMap<TxHash, Transaction>) -> Vec<usize>;
+
+    /// Priority class of a pending transaction, used for eviction and metrics.
+    fn class(&self, tx: &Transaction) -> PolicyClass;
+
+    fn max_dp_bytes(&self) -> usize;
+    fn max_dp_txs(&self) -> Option<usize>;
+    fn max_pending_txs(&self) -> Option<usize>;
+
+    /// Removes the transactions of the next data proposal from `pending` and `index`, in inclusion order.
+    /// Transactions left over keep their arrival order.
+    fn select_for_data_proposal(
+        &self,
+        pending: &mut IndexMap<TxHash, Transaction>,
+        index: &mut PendingIndex,
+    ) -> Vec<Transaction> {
+        let mut cumulative_size = 0;
+        let mut selected = vec![];
+        for idx in self.order(pending) {
+            if cumulative_size >= self.max_dp_bytes()
+                || self.max_dp_txs().is_some_and(|max| selected.len() >= max)
+            {
+                break;
+            }
+            if let Some((_, tx)) = pending.get_index(idx) {
+                cumulative_size += tx.estimate_size();
+                selected.push(idx);
+            }
+        }
+
+        let mut positions = vec![None; pending.len()];
+        for (position, idx) in selected.iter().enumerate() {
+            if let Some(p) = positions.get_mut(*idx) {
+                *p = Some(position);
+            }
+        }
+        let mut txs: Vec<Option<Transaction>> = (0..selected.len()).map(|_| None).collect();
+        for (idx, (tx_hash, tx)) in std::mem::take(pending).into_iter().enumerate() {
+            match positions.get(idx).copied().flatten() {
+                Some(position) => {
+                    index.remove(&self.class(&tx), &tx_hash);
+                    if let Some(slot) = txs.get_mut(position) {
+                        *slot = Some(tx);
+                    }
+                }
+                None => {
+                    pending.insert(tx_hash, tx);
+                }
+            }
+        }
+        txs.into_iter().flatten().collect()
+    }
+
+    /// Removes the lowest priority transactions until `pending` fits in the queue, and returns them.
+    fn evict_overflow(
+        &self,
+        pending: &mut IndexMap<TxHash, Transaction>,
+        index: &mut PendingIndex,
+    ) -> Vec<Transaction> {
+        let Some(max) = self.max_pending_txs() else {
+            return vec![];
+        };
+        let mut evicted = vec![];
+        while pending.len() > max {
+            let Some(tx_hash) = index.pop_lowest_priority() else {
+                break;
+            };
+            if let Some(tx) = pending.shift_remove(&tx_hash) {
+                evicted.push(tx);
+            }
+        }
+        evicted
+    }
+}
+
+/// Priority class of a pending transaction.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PolicyClass {
+    /// Classes with a higher rank are evicted first.
+    pub rank: u64,
+    /// Name of the class in metrics. Must only take a few distinct values.
+    pub label: String,
+    /// Within a class, the newest transaction of the largest group is evicted first.
+    pub group: String,
+}
+
+/// Pending transactions grouped by [PolicyClass], so that eviction and metrics don't scan the queue.
+#[derive(Debug, Default)]
+pub struct PendingIndex {
+    /// Transactions of each group, in arrival order.
+    groups: HashMap<PolicyClass, IndexSet<TxHash>>,
+    /// (rank, size, class) of each non-empty group. The last one is evicted from first.
+    eviction_order: BTreeSet<(u64, usize, PolicyClass)>,
+    /// Number of pending transactions per class label.
+    depth: BTreeMap<String, u64>,
+}
+
+impl PendingIndex {
+    pub fn new(policy: &dyn MempoolPolicy, pending: &IndexMap<TxHash, Transaction>) -> Self {
+        let mut index = Self::default();
+        for (tx_hash, tx) in pending {
+            index.insert(policy.class(tx), tx_hash.clone());
+        }
+        index
+    }
+
+    pub fn insert(&mut self, class: PolicyClass, tx_hash: TxHash) {
+        let group = self.groups.entry(class.clone()).or_default();
+        let previous_len = group.len();
+        if group.insert(tx_hash) {
+            *self.depth.entry(class.label.clone()).or_default() += 1;
+            self.reorder(&class, previous_len);
+        }
+    }
+
+    pub fn remove(&mut self, class: &PolicyClass, tx_hash: &TxHash) {
+        let Some(group) = self.groups.get_mut(class) else {
+            return;
+        };
+        let previous_len = group.len();
+        if group.shift_remove(tx_hash) {
+            if let Some(depth) = self.depth.get_mut(&class.label) {
+                *depth = depth.saturating_sub(1);
+            }
+            self.reorder(class, previous_len);
+        }
+    }
+
+    /// Removes and returns the newest transaction of the lowest priority group.
+    pub fn pop_lowest_priority(&mut self) -> Option<TxHash> {
+        let (_, _, class) = self.eviction_order.last()?.clone();
+        let tx_hash = self.groups.get(&class)?.last()?.clone();
+        self.remove(&class, &tx_hash);
+        Some(tx_hash)
+    }
+
+    /// Number of pending transactions per class label. Labels stay listed once they are empty.
+    pub fn depth(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.depth
+            .iter()
+            .map(|(label, depth)| (label.as_str(), *depth))
+    }
+
+    fn reorder(&mut self, class: &PolicyClass, previous_len: usize) {
+        self.eviction_order
+            .remove(&(class.rank, previous_len, class.clone()));
+        match self.groups.get(class).map_or(0, IndexSet::len) {
+            0 => {
+                self.groups.remove(class);
+            }
+            len => {
+                self.eviction_order.insert((class.rank, len, class.clone()));
+            }
+        }
+    }
+}
+
+/// Policy configured from [MempoolConf].
+pub struct ConfMempoolPolicy {
+    conf: MempoolConf,
+}
+
+impl ConfMempoolPolicy {
+    pub fn new(conf: MempoolConf) -> Self {
+        Self { conf }
+    }
+
+    fn contract_weight(&self, contract_name: &ContractName) -> u64 {
+        self.conf
+            .contract_weights
+            .get(&contract_name.0)
+            .copied()
+            .unwrap_or(1)
+    }
+
+    fn weight(&self, tx: &Transaction) -> u64 {
+        match &tx.transaction_data {
+            TransactionData::Blob(blob_tx) => blob_tx
+                .blobs
+                .iter()
+                .map(|blob| self.contract_weight(&blob.contract_name))
+                .max()
+                .unwrap_or(1),
+            TransactionData::Proof(proof_tx) => self.contract_weight(&proof_tx.contract_name),
+            TransactionData::VerifiedProof(proof_tx) => {
+                self.contract_weight(&proof_tx.contract_name)
+            }
+        }
+    }
+}
+
+/// Who a transaction is accounted to for fairness. Proofs don't have an identity,
+/// so they are grouped by contract.
+fn sender(tx: &Transaction) -> &str {
+    match &tx.transaction_data {
+        TransactionData::Blob(blob_tx) => &blob_tx.identity.0,
+        TransactionData::Proof(proof_tx) => &proof_tx.contract_name.0,
+        TransactionData::VerifiedProof(proof_tx) => &proof_tx.contract_name.0,
+    }
+}
+
+impl MempoolPolicy for ConfMempoolPolicy {
+    fn order(&self, pending: &IndexMap<TxHash, Transaction>) -> Vec<usize> {
+        let pending_blob_txs: HashMap<&TxHash, usize> = pending
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, tx))| matches!(tx.transaction_data, TransactionData::Blob(_)))
+            .map(|(idx, (tx_hash, _))| (tx_hash, idx))
+            .collect();
+        let mut sent_by: HashMap<&str, u64> = HashMap::new();
+
+        // Lower ranks go first, ties keep arrival order.
+        let mut ranks: Vec<u64> = Vec::with_capacity(pending.len());
+        for (idx, tx) in pending.values().enumerate() {
+            let mut rank = match self.conf.ordering {
+                MempoolOrdering::Fifo => 0,
+                MempoolOrdering::ProofsFirst => match tx.transaction_data {
+                    TransactionData::Blob(_) => 1,
+                    _ => 0,
+                },
+                MempoolOrdering::ContractWeights => u64::MAX - self.weight(tx),
+                MempoolOrdering::IdentityFairness => {
+                    let count = sent_by.entry(sender(tx)).or_default();
+                    *count += 1;
+                    *count - 1
+                }
+            };
+            // A proof must not overtake the blob transaction it proves.
+            if let TransactionData::VerifiedProof(proof_tx) = &tx.transaction_data {
+                for proven_blob in &proof_tx.proven_blobs {
+                    if let Some(blob_rank) = pending_blob_txs
+                        .get(&proven_blob.blob_tx_hash)
+                        .filter(|blob_idx| **blob_idx < idx)
+                        .and_then(|blob_idx| ranks.get(*blob_idx))
+                    {
+                        rank = rank.max(*blob_rank);
+                    }
+                }
+            }
+            ranks.push(rank);
+        }
+
+        let mut order: Vec<usize> = (0..pending.len()).collect();
+        order.sort_by_key(|idx| ranks.get(*idx).copied().unwrap_or(u64::MAX));
+        order
+    }
+
+    fn class(&self, tx: &Transaction) -> PolicyClass {
+        let (rank, label) = match self.conf.ordering {
+            MempoolOrdering::Fifo | MempoolOrdering::IdentityFairness => (0, "default".to_string()),
+            MempoolOrdering::ProofsFirst => match tx.transaction_data {
+                TransactionData::Blob(_) => (1, "blob".to_string()),
+                _ => (0, "proof".to_string()),
+            },
+            MempoolOrdering::ContractWeights => {
+                let weight = self.weight(tx);
+                (u64::MAX - weight, format!("weight_{weight}"))
+            }
+        };
+        let group = match self.conf.ordering {
+            MempoolOrdering::IdentityFairness => sender(tx).to_string(),
+            _ => String::new(),
+        };
+        PolicyClass { rank, label, group }
+    }
+
+    fn max_dp_bytes(&self) -> usize {
+        self.conf.max_dp_bytes
+    }
+
+    fn max_dp_txs(&self) -> Option<usize> {
+        (self.conf.max_dp_txs > 0).then_some(self.conf.max_dp_txs)
+    }
+
+    fn max_pending_txs(&self) -> Option<usize> {
+        (self.conf.max_pending_txs > 0).then_some(self.conf.max_pending_txs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob_tx(identity: &str, contract: &str) -> Transaction {
+        BlobTransaction::new(
+            identity,
+            vec![Blob {
+                contract_name: contract.into(),
+                data: BlobData(identity.as_bytes().to_vec()),
+            }],
+        )
+        .into()
+    }
+
+    fn proof_tx(contract: &str, proven: &Transaction) -> Transaction {
+        VerifiedProofTransaction {
+            contract_name: contract.into(),
+            proof: None,
+            proof_hash: ProofDataHash(contract.to_string()),
+            proof_size: 0,
+            proven_blobs: vec![BlobProofOutput {
+                original_proof_hash: ProofDataHash::default(),
+                blob_tx_hash: proven.hashed(),
+                hyle_output: HyleOutput::default(),
+                program_id: ProgramId::default(),
+            }],
+            is_recursive: false,
+        }
+        .into()
+    }
+
+    fn queue(txs: &[&Transaction]) -> IndexMap<TxHash, Transaction> {
+        txs.iter().map(|tx| (tx.hashed(), (*tx).clone())).collect()
+    }
+
+    fn policy(conf: MempoolConf) -> ConfMempoolPolicy {
+        ConfMempoolPolicy::new(conf)
+    }
+
+    fn select(
+        policy: &ConfMempoolPolicy,
+        pending: &mut IndexMap<TxHash, Transaction>,
+    ) -> Vec<Transaction> {
+        let mut index = PendingIndex::new(policy, pending);
+        let selected = policy.select_for_data_proposal(pending, &mut index);
+        assert_eq!(
+            index.depth().map(|(_, depth)| depth).sum::<u64>(),
+            pending.len() as u64
+        );
+        selected
+    }
+
+    #[test]
+    fn test_fifo_with_tx_budget() {
+        let (a, b, c) = (
+            blob_tx("a@c1", "c1"),
+            blob_tx("b@c1", "c1"),
+            blob_tx("c@c1", "c1"),
+        );
+        let mut pending = queue(&[&a, &b, &c]);
+        let policy = policy(MempoolConf {
+            max_dp_txs: 2,
+            ..Default::default()
+        });
+
+        assert_eq!(select(&policy, &mut pending), vec![a, b]);
+        assert_eq!(select(&policy, &mut pending), vec![c]);
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn test_proofs_first_keeps_proof_after_its_blob() {
+        let old_blob = blob_tx("old@c1", "c1");
+        let (a, b) = (blob_tx("a@c1", "c1"), blob_tx("b@c1", "c1"));
+        let old_proof = proof_tx("c1", &old_blob);
+        let proof_of_b = proof_tx("c1", &b);
+        let mut pending = queue(&[&a, &b, &proof_of_b, &old_proof]);
+        let policy = policy(MempoolConf {
+            ordering: MempoolOrdering::ProofsFirst,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            select(&policy, &mut pending),
+            vec![old_proof, a, b, proof_of_b]
+        );
+    }
+
+    #[test]
+    fn test_contract_weights() {
+        let (a, b, c) = (
+            blob_tx("a@c1", "c1"),
+            blob_tx("b@c2", "c2"),
+            blob_tx("c@c3", "c3"),
+        );
+        let mut pending = queue(&[&a, &b, &c]);
+        let policy = policy(MempoolConf {
+            ordering: MempoolOrdering::ContractWeights,
+            contract_weights: HashMap::from([("c2".to_string(), 10), ("c3".to_string(), 5)]),
+            ..Default::default()
+        });
+
+        assert_eq!(select(&policy, &mut pending), vec![b, c, a]);
+    }
+
+    #[test]
+    fn test_identity_fairness() {
+        let spam: Vec<Transaction> = (0..3)
+            .map(|i| {
+                BlobTransaction::new(
+                    "spammer@c1",
+                    vec![Blob {
+                        contract_name: "c1".into(),
+                        data: BlobData(vec![i]),
+                    }],
+                )
+                .into()
+            })
+            .collect();
+        let alice = blob_tx("alice@c1", "c1");
+        let mut pending = queue(&[&spam[0], &spam[1], &spam[2], &alice]);
+        let policy = policy(MempoolConf {
+            ordering: MempoolOrdering::IdentityFairness,
+            max_dp_txs: 2,
+            ..Default::default()
+        });
+
+        assert_eq!(select(&policy, &mut pending), vec![spam[0].clone(), alice]);
+        // Leftovers keep their arrival order
+        assert_eq!(
+            pending.values().cloned().collect::<Vec<_>>(),
+            vec![spam[1].clone(), spam[2].clone()]
+        );
+    }
+
+    #[test]
+    fn test_evict_lowest_priority() {
+        let (a, b) = (blob_tx("a@c1", "c1"), blob_tx("b@c1", "c1"));
+        let proof = proof_tx("c1", &blob_tx("old@c1", "c1"));
+        let pending = queue(&[&a, &b, &proof]);
+
+        let fifo = policy(MempoolConf {
+            max_pending_txs: 2,
+            ..Default::default()
+        });
+        let mut fifo_pending = pending.clone();
+        let mut index = PendingIndex::new(&fifo, &fifo_pending);
+        assert_eq!(
+            fifo.evict_overflow(&mut fifo_pending, &mut index),
+            vec![proof.clone()]
+        );
+        assert_eq!(index.depth().collect::<Vec<_>>(), vec![("default", 2)]);
+
+        let proofs_first = policy(MempoolConf {
+            ordering: MempoolOrdering::ProofsFirst,
+            max_pending_txs: 1,
+            ..Default::default()
+        });
+        let mut pending = pending;
+        let mut index = PendingIndex::new(&proofs_first, &pending);
+        assert_eq!(
+            proofs_first.evict_overflow(&mut pending, &mut index),
+            vec![b, a]
+        );
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            index.depth().collect::<Vec<_>>(),
+            vec![("blob", 0), ("proof", 1)]
+        );
+    }
+
+    #[test]
+    fn test_evict_largest_sender_first() {
+        let spam: Vec<Transaction> = (0..3)
+            .map(|i| {
+                BlobTransaction::new(
+                    "spammer@c1",
+                    vec![Blob {
+                        contract_name: "c1".into(),
+                        data: BlobData(vec![i]),
+                    }],
+                )
+                .into()
+            })
+            .collect();
+        let alice = blob_tx("alice@c1", "c1");
+        let policy = policy(MempoolConf {
+            ordering: MempoolOrdering::IdentityFairness,
+            max_pending_txs: 3,
+            ..Default::default()
+        });
+        let mut pending = queue(&[&spam[0], &spam[1], &alice]);
+        let mut index = PendingIndex::new(&policy, &pending);
+
+        pending.insert(spam[2].hashed(), spam[2].clone());
+        index.insert(policy.class(&spam[2]), spam[2].hashed());
+        assert_eq!(
+            policy.evict_overflow(&mut pending, &mut index),
+            vec![spam[2].clone()]
+        );
+        assert!(pending.contains_key(&alice.hashed()));
+    }
+}
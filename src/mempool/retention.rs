@@ -0,0 +1,124 @@
+//! Pruning of lane entries that were committed in a cut and emitted in a signed block.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RetentionConf {
+    /// Prune committed lane entries. When disabled, every data proposal is kept forever.
+    pub enabled: bool,
+    /// Number of committed data proposals kept in each lane, behind the last committed one.
+    /// The last committed data proposal is always kept, as new ones are built on top of it.
+    pub keep_dps: usize,
+    /// Committed data proposals are kept for at least this many blocks
+    pub keep_blocks: u64,
+}
+
+impl Default for RetentionConf {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keep_dps: 1_000,
+            keep_blocks: 1_000,
+        }
+    }
+}
+
+/// Committed data proposals of a lane not pruned yet, oldest first, with the height of the block they were emitted in.
+pub type CommittedEntries = VecDeque<(BlockHeight, DataProposalHash)>;
+
+/// Committed data proposals of each lane. Stored with the lanes, see [`super::storage::Storage::put_retention`].
+#[derive(Debug, Default, Clone)]
+pub struct LaneRetention {
+    committed: BTreeMap<LaneId, CommittedEntries>,
+}
+
+impl FromIterator<(LaneId, CommittedEntries)> for LaneRetention {
+    fn from_iter<T: IntoIterator<Item = (LaneId, CommittedEntries)>>(iter: T) -> Self {
+        Self {
+            committed: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl LaneRetention {
+    pub fn committed(&self, lane_id: &LaneId) -> Option<&CommittedEntries> {
+        self.committed.get(lane_id)
+    }
+
+    /// `dp_hashes` are in lane order, oldest first, as in a signed block.
+    pub fn record_committed(
+        &mut self,
+        block_height: BlockHeight,
+        lane_id: LaneId,
+        dp_hashes: impl IntoIterator<Item = DataProposalHash>,
+    ) {
+        self.committed
+            .entry(lane_id)
+            .or_default()
+            .extend(dp_hashes.into_iter().map(|dp_hash| (block_height, dp_hash)));
+    }
+
+    /// Removes and returns the entries that fall out of the retention window at `current_height`.
+    pub fn take_prunable(
+        &mut self,
+        conf: &RetentionConf,
+        current_height: BlockHeight,
+    ) -> Vec<(LaneId, DataProposalHash)> {
+        let keep_dps = conf.keep_dps + 1;
+        let mut prunable = vec![];
+        for (lane_id, committed) in self.committed.iter_mut() {
+            while committed.len() > keep_dps {
+                match committed.front() {
+                    Some((height, _)) if height.0 + conf.keep_blocks < current_height.0 => {}
+                    _ => break,
+                }
+                if let Some((_, dp_hash)) = committed.pop_front() {
+                    prunable.push((lane_id.clone(), dp_hash));
+                }
+            }
+        }
+        prunable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_prunable() {
+        let lane_id = LaneId::default();
+        let conf = RetentionConf {
+            enabled: true,
+            keep_dps: 2,
+            keep_blocks: 5,
+        };
+        let hashes: Vec<DataProposalHash> =
+            (0..6).map(|n| DataProposalHash(format!("{n}"))).collect();
+        let mut retention = LaneRetention::default();
+        retention.record_committed(BlockHeight(1), lane_id.clone(), hashes[0..4].to_vec());
+        retention.record_committed(BlockHeight(2), lane_id.clone(), hashes[4..6].to_vec());
+
+        // Everything is recent enough
+        assert!(retention.take_prunable(&conf, BlockHeight(6)).is_empty());
+
+        // Only the entries of block 1 are old enough, and the last 3 entries are kept
+        let pruned = retention.take_prunable(&conf, BlockHeight(7));
+        assert_eq!(
+            pruned,
+            vec![
+                (lane_id.clone(), hashes[0].clone()),
+                (lane_id.clone(), hashes[1].clone())
+            ]
+        );
+        assert_eq!(
+            retention.take_prunable(&conf, BlockHeight(100)),
+            vec![(lane_id.clone(), hashes[2].clone())]
+        );
+        assert!(retention.take_prunable(&conf, BlockHeight(100)).is_empty());
+    }
+}
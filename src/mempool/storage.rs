@@ -10,10 +10,14 @@ use std::{future::Future, vec};
 use tracing::error;
 
 use crate::model::{
-    Cut, DataProposal, DataProposalHash, Hashed, PoDA, SignedByValidator, ValidatorPublicKey,
+    Cut, DataProposal, DataProposalHash, Hashed, PoDA, SignedByValidator, TxHash, TxId,
+    ValidatorPublicKey,
 };
 
-use super::ValidatorDAG;
+use super::{
+    retention::{CommittedEntries, LaneRetention},
+    sign_data_vote, ValidatorDAG,
+};
 
 pub use hyle_model::LaneBytesSize;
 
@@ -57,6 +61,17 @@ pub trait Storage {
         lane_id: &LaneId,
         dp_hash: &DataProposalHash,
     ) -> Result<Option<DataProposal>>;
+    /// Hashes of the transactions of a data proposal, in order.
+    /// Storages keeping them apart from the data proposals don't have to decode it.
+    fn get_tx_hashes_by_hash(
+        &self,
+        lane_id: &LaneId,
+        dp_hash: &DataProposalHash,
+    ) -> Result<Option<Vec<TxHash>>> {
+        Ok(self
+            .get_dp_by_hash(lane_id, dp_hash)?
+            .map(|dp| dp.txs.iter().map(|tx| tx.hashed()).collect()))
+    }
 
     fn pop(
         &mut self,
@@ -75,6 +90,18 @@ pub trait Storage {
         vote_msgs: T,
     ) -> Result<Vec<ValidatorDAG>>;
 
+    /// Removes a committed lane entry, moving the pruning watermark of the lane up to it.
+    fn prune(&mut self, lane_id: &LaneId, dp_hash: &DataProposalHash) -> Result<()>;
+    /// Last pruned entry of the lane, with its cumulated size
+    fn get_pruned_watermark(&self, lane_id: &LaneId) -> Option<(DataProposalHash, LaneBytesSize)>;
+    /// Entries are pruned oldest first, so a missing entry of a pruned lane is below the watermark.
+    fn is_pruned(&self, lane_id: &LaneId, dp_hash: &DataProposalHash) -> bool {
+        !self.contains(lane_id, dp_hash) && self.get_pruned_watermark(lane_id).is_some()
+    }
+    /// Saves the committed entries of a lane waiting to be pruned, along with the lanes.
+    fn put_retention(&mut self, lane_id: &LaneId, committed: &CommittedEntries) -> Result<()>;
+    fn get_retention(&self) -> Result<LaneRetention>;
+
     fn get_lane_ids(&self) -> impl Iterator<Item = &LaneId>;
     fn get_lane_hash_tip(&self, lane_id: &LaneId) -> Option<&DataProposalHash>;
     fn get_lane_size_tip(&self, lane_id: &LaneId) -> Option<&LaneBytesSize>;
@@ -193,8 +220,11 @@ pub trait Storage {
                 let lane_size = self.get_lane_size_tip(lane_id).cloned().unwrap_or_default();
                 let cumul_size = lane_size + dp_size;
 
-                let msg = (data_proposal_hash.clone(), cumul_size);
-                let signatures = vec![crypto.sign(msg)?];
+                let signatures = vec![sign_data_vote(
+                    crypto,
+                    data_proposal_hash.clone(),
+                    cumul_size,
+                )?];
 
                 self.put_no_verification(
                     lane_id.clone(),
@@ -218,8 +248,11 @@ pub trait Storage {
                 // Store it anyways - this ensures caller end up in a consistent state.
                 // The lane_size already contains the size of the current data proposal, so we don't need to adjust it.
                 let cumul_size = self.get_lane_size_tip(lane_id).cloned().unwrap_or_default();
-                let msg = (data_proposal_hash.clone(), cumul_size);
-                let signatures = vec![crypto.sign(msg)?];
+                let signatures = vec![sign_data_vote(
+                    crypto,
+                    data_proposal_hash.clone(),
+                    cumul_size,
+                )?];
                 self.put_no_verification(
                     lane_id.clone(),
                     (
@@ -281,6 +314,38 @@ pub trait Storage {
         }
     }
 
+    /// Ids of the transactions of a lane after `from_hash` and up to `to_hash`, oldest first.
+    /// Only hashes are read, the data proposals are not loaded.
+    /// Stops at the first data proposal not available locally, and returns its hash.
+    fn get_tx_ids_between_hashes(
+        &self,
+        lane_id: &LaneId,
+        from_hash: Option<&DataProposalHash>,
+        to_hash: &DataProposalHash,
+    ) -> Result<(Vec<TxId>, Option<DataProposalHash>)> {
+        let mut tx_ids = vec![];
+        let mut dp_hash = to_hash.clone();
+        while Some(&dp_hash) != from_hash {
+            let Some(metadata) = self.get_metadata_by_hash(lane_id, &dp_hash)? else {
+                return Ok((tx_ids, Some(dp_hash)));
+            };
+            let Some(tx_hashes) = self.get_tx_hashes_by_hash(lane_id, &dp_hash)? else {
+                return Ok((tx_ids, Some(dp_hash)));
+            };
+            tx_ids.splice(
+                0..0,
+                tx_hashes
+                    .into_iter()
+                    .map(|tx_hash| TxId(dp_hash.clone(), tx_hash)),
+            );
+            match metadata.parent_data_proposal_hash {
+                Some(parent_dp_hash) => dp_hash = parent_dp_hash,
+                None => break,
+            }
+        }
+        Ok((tx_ids, None))
+    }
+
     fn get_lane_size_at(
         &self,
         lane_id: &LaneId,
@@ -411,14 +476,21 @@ mod tests {
     use std::collections::BTreeMap;
 
     use super::*;
-    use crate::mempool::storage_memory::LanesStorage;
+    use crate::mempool::{
+        compression::DataProposalCompression, metrics::MempoolMetrics,
+        storage_memory::LanesStorage, test::make_register_contract_tx,
+    };
     use futures::StreamExt;
     use hyle_model::{DataSized, Signature, Transaction, ValidatorSignature};
     use staking::state::Staking;
 
     fn setup_storage() -> LanesStorage {
         let tmp_dir = tempfile::tempdir().unwrap().keep();
-        LanesStorage::new(&tmp_dir, BTreeMap::default()).unwrap()
+        let compression = DataProposalCompression::new(
+            Default::default(),
+            MempoolMetrics::global("test".to_string()),
+        );
+        LanesStorage::new(&tmp_dir, BTreeMap::default(), compression).unwrap()
     }
 
     #[test_log::test(tokio::test)]
@@ -651,6 +723,50 @@ mod tests {
         assert_eq!(0, entries_from_1_to_1.len());
     }
 
+    #[test_log::test(tokio::test)]
+    async fn test_get_tx_ids_between_hashes() {
+        let crypto: BlstCrypto = BlstCrypto::new("1").unwrap();
+        let lane_id = &LaneId(crypto.validator_pubkey().clone());
+        let mut storage = setup_storage();
+        let tx = |name: &str| make_register_contract_tx(name.into());
+        let dp1 = DataProposal::new(None, vec![tx("c1")]);
+        let dp2 = DataProposal::new(Some(dp1.hashed()), vec![tx("c2"), tx("c3")]);
+        let dp3 = DataProposal::new(Some(dp2.hashed()), vec![tx("c4")]);
+        for dp in [&dp1, &dp2, &dp3] {
+            storage
+                .store_data_proposal(&crypto, lane_id, dp.clone())
+                .unwrap();
+        }
+
+        // ]1, 3] in lane order
+        let (tx_ids, missing) = storage
+            .get_tx_ids_between_hashes(lane_id, Some(&dp1.hashed()), &dp3.hashed())
+            .unwrap();
+        assert_eq!(missing, None);
+        assert_eq!(
+            tx_ids,
+            vec![
+                TxId(dp2.hashed(), tx("c2").hashed()),
+                TxId(dp2.hashed(), tx("c3").hashed()),
+                TxId(dp3.hashed(), tx("c4").hashed()),
+            ]
+        );
+
+        // ]1, 1] == []
+        let (tx_ids, missing) = storage
+            .get_tx_ids_between_hashes(lane_id, Some(&dp1.hashed()), &dp1.hashed())
+            .unwrap();
+        assert!(tx_ids.is_empty());
+        assert_eq!(missing, None);
+
+        // A missing data proposal is reported
+        storage.remove_lane_entry(lane_id, &dp2.hashed());
+        let (_, missing) = storage
+            .get_tx_ids_between_hashes(lane_id, None, &dp3.hashed())
+            .unwrap();
+        assert_eq!(missing, Some(dp2.hashed()));
+    }
+
     // Test to get oldest pending entry in a lane containing 3 DataProposals
     #[test_log::test(tokio::test)]
     async fn test_get_oldest_pending_entry() {
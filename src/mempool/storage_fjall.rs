@@ -11,11 +11,13 @@ use tracing::info;
 
 use crate::{
     mempool::storage::MetadataOrMissingHash,
-    model::{DataProposal, DataProposalHash, Hashed},
+    model::{DataProposal, DataProposalHash, Hashed, TxHash},
 };
 use hyle_modules::log_warn;
 
 use super::{
+    compression::DataProposalCompression,
+    retention::{CommittedEntries, LaneRetention},
     storage::{EntryOrMissingHash, LaneEntryMetadata, Storage},
     ValidatorDAG,
 };
@@ -28,6 +30,13 @@ pub struct LanesStorage {
     db: Keyspace,
     pub by_hash_metadata: PartitionHandle,
     pub by_hash_data: PartitionHandle,
+    /// Transaction hashes of each data proposal, so that blocks can be hashed without decoding it
+    pub by_hash_tx_hashes: PartitionHandle,
+    /// Pruning watermark of each lane: the last entry removed by the retention policy
+    pub pruned: PartitionHandle,
+    /// Committed entries of each lane waiting to be pruned
+    pub retention: PartitionHandle,
+    compression: DataProposalCompression,
 }
 
 impl LanesStorage {
@@ -38,12 +47,17 @@ impl LanesStorage {
             db: self.db.clone(),
             by_hash_metadata: self.by_hash_metadata.clone(),
             by_hash_data: self.by_hash_data.clone(),
+            by_hash_tx_hashes: self.by_hash_tx_hashes.clone(),
+            pruned: self.pruned.clone(),
+            retention: self.retention.clone(),
+            compression: self.compression.clone(),
         }
     }
 
     pub fn new(
         path: &Path,
         lanes_tip: BTreeMap<LaneId, (DataProposalHash, LaneBytesSize)>,
+        compression: DataProposalCompression,
     ) -> Result<Self> {
         let db = Config::new(path)
             .cache_size(256 * 1024 * 1024)
@@ -73,6 +87,21 @@ impl LanesStorage {
                 .max_memtable_size(128 * 1024 * 1024),
         )?;
 
+        let by_hash_tx_hashes = db.open_partition(
+            "dp_tx_hashes",
+            PartitionCreateOptions::default().manual_journal_persist(true),
+        )?;
+
+        let pruned = db.open_partition(
+            "lanes_pruned",
+            PartitionCreateOptions::default().manual_journal_persist(true),
+        )?;
+
+        let retention = db.open_partition(
+            "lanes_retention",
+            PartitionCreateOptions::default().manual_journal_persist(true),
+        )?;
+
         info!("{} DP(s) available", by_hash_metadata.len()?);
 
         Ok(LanesStorage {
@@ -80,6 +109,10 @@ impl LanesStorage {
             db,
             by_hash_metadata,
             by_hash_data,
+            by_hash_tx_hashes,
+            pruned,
+            retention,
+            compression,
         })
     }
 }
@@ -123,7 +156,7 @@ impl Storage for LanesStorage {
             lane_id
         )?;
         item.map(|s| {
-            decode_data_proposal_from_item(s).map(|mut dp| {
+            self.compression.decode_from_storage(&s).map(|mut dp| {
                 // SAFETY: we trust our own fjall storage
                 unsafe {
                     dp.unsafe_set_hash(dp_hash);
@@ -134,6 +167,20 @@ impl Storage for LanesStorage {
         .transpose()
     }
 
+    fn get_tx_hashes_by_hash(
+        &self,
+        lane_id: &LaneId,
+        dp_hash: &DataProposalHash,
+    ) -> Result<Option<Vec<TxHash>>> {
+        match self.by_hash_tx_hashes.get(format!("{lane_id}:{dp_hash}"))? {
+            Some(item) => Ok(Some(borsh::from_slice(&item)?)),
+            // Entries stored before the hashes were kept apart
+            None => Ok(self
+                .get_dp_by_hash(lane_id, dp_hash)?
+                .map(|dp| dp.txs.iter().map(|tx| tx.hashed()).collect())),
+        }
+    }
+
     fn pop(
         &mut self,
         lane_id: LaneId,
@@ -152,6 +199,8 @@ impl Storage for LanesStorage {
                 };
                 self.by_hash_data
                     .remove(format!("{lane_id}:{lane_hash_tip}"))?;
+                self.by_hash_tx_hashes
+                    .remove(format!("{lane_id}:{lane_hash_tip}"))?;
                 self.update_lane_tip(lane_id, lane_hash_tip.clone(), lane_entry.cumul_size);
                 return Ok(Some((lane_hash_tip, (lane_entry, dp))));
             }
@@ -171,8 +220,11 @@ impl Storage for LanesStorage {
         )?;
         self.by_hash_data.insert(
             format!("{lane_id}:{dp_hash}"),
-            encode_data_proposal_to_item(data_proposal)?,
+            Slice::from(self.compression.encode_for_storage(&data_proposal)?),
         )?;
+        let tx_hashes: Vec<TxHash> = data_proposal.txs.iter().map(|tx| tx.hashed()).collect();
+        self.by_hash_tx_hashes
+            .insert(format!("{lane_id}:{dp_hash}"), borsh::to_vec(&tx_hashes)?)?;
         Ok(())
     }
 
@@ -223,6 +275,62 @@ impl Storage for LanesStorage {
         Ok(signatures)
     }
 
+    fn prune(&mut self, lane_id: &LaneId, dp_hash: &DataProposalHash) -> Result<()> {
+        let Some(metadata) = self.get_metadata_by_hash(lane_id, dp_hash)? else {
+            // Already pruned
+            return Ok(());
+        };
+        let key = format!("{lane_id}:{dp_hash}");
+        self.by_hash_metadata.remove(key.clone())?;
+        self.by_hash_data.remove(key.clone())?;
+        self.by_hash_tx_hashes.remove(key)?;
+        if self
+            .get_pruned_watermark(lane_id)
+            .is_none_or(|(_, size)| size < metadata.cumul_size)
+        {
+            self.pruned.insert(
+                lane_id.to_string(),
+                borsh::to_vec(&(dp_hash, metadata.cumul_size))?,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn get_pruned_watermark(&self, lane_id: &LaneId) -> Option<(DataProposalHash, LaneBytesSize)> {
+        let item = log_warn!(
+            self.pruned.get(lane_id.to_string()),
+            "Can't find pruning watermark of lane {}",
+            lane_id
+        )
+        .ok()??;
+        log_warn!(
+            borsh::from_slice(&item),
+            "Decoding pruning watermark of lane {}",
+            lane_id
+        )
+        .ok()
+    }
+
+    fn put_retention(&mut self, lane_id: &LaneId, committed: &CommittedEntries) -> Result<()> {
+        if committed.is_empty() {
+            self.retention.remove(lane_id.to_string())?;
+        } else {
+            self.retention
+                .insert(lane_id.to_string(), borsh::to_vec(&(lane_id, committed))?)?;
+        }
+        Ok(())
+    }
+
+    fn get_retention(&self) -> Result<LaneRetention> {
+        self.retention
+            .iter()
+            .map(|item| {
+                let (_, value) = item?;
+                Ok(borsh::from_slice(&value)?)
+            })
+            .collect()
+    }
+
     fn get_lane_ids(&self) -> impl Iterator<Item = &LaneId> {
         self.lanes_tip.keys()
     }
@@ -295,6 +403,9 @@ impl Storage for LanesStorage {
         self.by_hash_data
             .remove(format!("{lane_id}:{dp_hash}"))
             .unwrap();
+        self.by_hash_tx_hashes
+            .remove(format!("{lane_id}:{dp_hash}"))
+            .unwrap();
     }
 }
 
@@ -307,13 +418,3 @@ fn encode_metadata_to_item(metadata: LaneEntryMetadata) -> Result<Slice> {
         .map(Slice::from)
         .map_err(Into::into)
 }
-
-fn decode_data_proposal_from_item(item: Slice) -> Result<DataProposal> {
-    borsh::from_slice(&item).map_err(Into::into)
-}
-
-fn encode_data_proposal_to_item(data_proposal: DataProposal) -> Result<Slice> {
-    borsh::to_vec(&data_proposal)
-        .map(Slice::from)
-        .map_err(Into::into)
-}
@@ -10,6 +10,8 @@ use hyle_model::{LaneBytesSize, LaneId};
 use tracing::info;
 
 use super::{
+    compression::DataProposalCompression,
+    retention::{CommittedEntries, LaneRetention},
     storage::{EntryOrMissingHash, LaneEntryMetadata, Storage},
     ValidatorDAG,
 };
@@ -23,6 +25,8 @@ pub struct LanesStorage {
     pub lanes_tip: BTreeMap<LaneId, (DataProposalHash, LaneBytesSize)>,
     // NB: do not iterate on these as they're unordered
     pub by_hash: HashMap<LaneId, HashMap<DataProposalHash, (LaneEntryMetadata, DataProposal)>>,
+    pub pruned: HashMap<LaneId, (DataProposalHash, LaneBytesSize)>,
+    pub retention: BTreeMap<LaneId, CommittedEntries>,
 }
 
 impl LanesStorage {
@@ -32,13 +36,19 @@ impl LanesStorage {
     pub fn new(
         _path: &Path,
         lanes_tip: BTreeMap<LaneId, (DataProposalHash, LaneBytesSize)>,
+        _compression: DataProposalCompression,
     ) -> Result<Self> {
         // FIXME: load from disk
         let by_hash = HashMap::default();
 
         info!("{} DP(s) available", by_hash.len());
 
-        Ok(LanesStorage { lanes_tip, by_hash })
+        Ok(LanesStorage {
+            lanes_tip,
+            by_hash,
+            pruned: HashMap::default(),
+            retention: BTreeMap::default(),
+        })
     }
 }
 
@@ -140,6 +150,46 @@ impl Storage for LanesStorage {
         Ok(signatures)
     }
 
+    fn prune(&mut self, lane_id: &LaneId, dp_hash: &DataProposalHash) -> Result<()> {
+        let Some((metadata, _)) = self
+            .by_hash
+            .get_mut(lane_id)
+            .and_then(|lane| lane.remove(dp_hash))
+        else {
+            // Already pruned
+            return Ok(());
+        };
+        if self
+            .get_pruned_watermark(lane_id)
+            .is_none_or(|(_, size)| size < metadata.cumul_size)
+        {
+            self.pruned
+                .insert(lane_id.clone(), (dp_hash.clone(), metadata.cumul_size));
+        }
+        Ok(())
+    }
+
+    fn get_pruned_watermark(&self, lane_id: &LaneId) -> Option<(DataProposalHash, LaneBytesSize)> {
+        self.pruned.get(lane_id).cloned()
+    }
+
+    fn put_retention(&mut self, lane_id: &LaneId, committed: &CommittedEntries) -> Result<()> {
+        if committed.is_empty() {
+            self.retention.remove(lane_id);
+        } else {
+            self.retention.insert(lane_id.clone(), committed.clone());
+        }
+        Ok(())
+    }
+
+    fn get_retention(&self) -> Result<LaneRetention> {
+        Ok(self
+            .retention
+            .iter()
+            .map(|(lane_id, committed)| (lane_id.clone(), committed.clone()))
+            .collect())
+    }
+
     fn get_lane_ids(&self) -> impl Iterator<Item = &LaneId> {
         self.lanes_tip.keys()
     }
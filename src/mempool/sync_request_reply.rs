@@ -5,7 +5,9 @@ use std::{
 
 use futures::StreamExt;
 use hyle_crypto::SharedBlstCrypto;
-use hyle_model::{utils::TimestampMs, DataProposalHash, LaneId, ValidatorPublicKey};
+use hyle_model::{
+    utils::TimestampMs, DataProposal, DataProposalHash, Hashed, LaneId, ValidatorPublicKey,
+};
 use hyle_modules::{log_error, log_warn};
 use hyle_net::clock::TimestampMsClock;
 use tokio::pin;
@@ -17,6 +19,7 @@ use crate::{
 };
 
 use super::{
+    compression::DataProposalCompression,
     metrics::MempoolMetrics,
     storage::{LaneEntryMetadata, Storage},
     storage_fjall::LanesStorage,
@@ -39,6 +42,8 @@ pub struct MempoolSync {
     crypto: SharedBlstCrypto,
     /// Metrics handle
     metrics: MempoolMetrics,
+    /// Compression of replies to peers that support it
+    compression: DataProposalCompression,
     /// Keeping track of last time we sent a reply to the validator and the data proposal hash
     by_pubkey_by_dp_hash: HashMap<ValidatorPublicKey, HashMap<DataProposalHash, TimestampMs>>,
     /// Map containing per data proposal, which validators are interested in a sync reply
@@ -55,6 +60,7 @@ impl MempoolSync {
         lanes: LanesStorage,
         crypto: SharedBlstCrypto,
         metrics: MempoolMetrics,
+        compression: DataProposalCompression,
         net_sender: tokio::sync::broadcast::Sender<OutboundMessage>,
         sync_request_receiver: tokio::sync::mpsc::Receiver<SyncRequest>,
     ) -> MempoolSync {
@@ -63,6 +69,7 @@ impl MempoolSync {
             lanes,
             crypto,
             metrics,
+            compression,
             net_sender,
             sync_request_receiver,
             by_pubkey_by_dp_hash: Default::default(),
@@ -132,16 +139,23 @@ impl MempoolSync {
         };
 
         while let Some(entry) = stream.next().await {
-            if let Ok(MetadataOrMissingHash::Metadata(metadata, dp_hash)) =
-                log_warn!(entry, "Getting entry metada to prepare sync replies")
-            {
-                self.todo
-                    .entry(dp_hash)
-                    .or_insert((metadata, Default::default()))
-                    .1
-                    .insert(validator.clone());
-            } else {
-                warn!("Could not get entry metadata to prepare sync replies for SyncRequest: from: {:?}, to: {}, validator: {}", from, to, validator);
+            match log_warn!(entry, "Getting entry metada to prepare sync replies") {
+                Ok(MetadataOrMissingHash::Metadata(metadata, dp_hash)) => {
+                    self.todo
+                        .entry(dp_hash)
+                        .or_insert((metadata, Default::default()))
+                        .1
+                        .insert(validator.clone());
+                }
+                Ok(MetadataOrMissingHash::MissingHash(dp_hash))
+                    if self.lanes.is_pruned(&self.lane_id, &dp_hash) =>
+                {
+                    // Older entries are gone as well, the requester has to use DA
+                    self.send_pruned_reply(&validator, dp_hash);
+                }
+                _ => {
+                    warn!("Could not get entry metadata to prepare sync replies for SyncRequest: from: {:?}, to: {}, validator: {}", from, to, validator);
+                }
             }
 
             // If from is None, we are just looking for the 'to' entry
@@ -154,6 +168,47 @@ impl MempoolSync {
         Ok(())
     }
 
+    fn send_pruned_reply(&self, validator: &ValidatorPublicKey, dp_hash: DataProposalHash) {
+        debug!(
+            "Replying that DP Hash: {} was pruned to: {}",
+            &dp_hash, validator
+        );
+        self.metrics.mempool_sync_pruned(&self.lane_id, validator);
+        if let Ok(signed_reply) = log_error!(
+            self.crypto
+                .sign_msg_with_header(MempoolNetMessage::SyncReplyPruned(dp_hash)),
+            "Signing MempoolNetMessage::SyncReplyPruned"
+        ) {
+            _ = log_error!(
+                self.net_sender
+                    .send(OutboundMessage::send(validator.clone(), signed_reply)),
+                "Sending MempoolNetMessage::SyncReplyPruned msg on the bus"
+            );
+        }
+    }
+
+    /// Compresses the data proposal if the validator supports it, falling back to a plain reply
+    fn build_reply(
+        &self,
+        validator: &ValidatorPublicKey,
+        metadata: LaneEntryMetadata,
+        data_proposal: DataProposal,
+    ) -> MempoolNetMessage {
+        if self.compression.supports(validator) {
+            if let Ok(Some(compressed)) = log_warn!(
+                self.compression.compress_for_wire(&data_proposal),
+                "Compressing data proposal for a SyncReply"
+            ) {
+                return MempoolNetMessage::CompressedSyncReply(
+                    metadata,
+                    data_proposal.hashed(),
+                    compressed,
+                );
+            }
+        }
+        MempoolNetMessage::SyncReply(metadata, data_proposal)
+    }
+
     /// Try to send replies based on what is stored in the todo hashmap. Every time a reply is sent, it stored a timestamp to throttle upcoming SyncRequests, and remove it from the todo hashmap
     async fn send_replies(&mut self) {
         if self.todo.is_empty() {
@@ -188,12 +243,8 @@ impl MempoolSync {
                         self.lanes.get_dp_by_hash(&self.lane_id, &dp_hash),
                         "Getting data proposal for to prepare a SyncReply"
                     ) {
-                        let signed_reply =
-                            self.crypto
-                                .sign_msg_with_header(MempoolNetMessage::SyncReply(
-                                    metadata.clone(),
-                                    data_proposal,
-                                ));
+                        let reply = self.build_reply(&validator, metadata.clone(), data_proposal);
+                        let signed_reply = self.crypto.sign_msg_with_header(reply);
 
                         if let Ok(signed_reply) = signed_reply {
                             if log_error!(
@@ -13,7 +13,7 @@ use crate::consensus::ConsensusEvent;
 use crate::genesis::{Genesis, GenesisEvent};
 use crate::mempool::api::RestApiMessage;
 use crate::mempool::test::NodeStateEvent;
-use crate::mempool::{MempoolNetMessage, QueryNewCut};
+use crate::mempool::{MempoolNetMessage, NewCut, QueryNewCut};
 use crate::model::*;
 use crate::p2p::network::{HeaderSigner, MsgWithHeader};
 use crate::rest::RestApi;
@@ -24,7 +24,7 @@ bus_client! {
         sender(GenesisEvent),
         sender(RestApiMessage),
         sender(MsgWithHeader<MempoolNetMessage>),
-        sender(Query<QueryNewCut, Cut>),
+        sender(Query<QueryNewCut, NewCut>),
         receiver(NodeStateEvent),
         receiver(ConsensusEvent),
     }
@@ -12,7 +12,7 @@ use crate::{
     model::{BlobProofOutput, DataProposal, Hashed, Transaction, TransactionData},
 };
 
-use super::KnownContracts;
+use super::{sign_data_vote, KnownContracts};
 use super::{
     storage::{CanBePutOnTop, Storage},
     verifiers::{verify_proof, verify_recursive_proof},
@@ -489,7 +489,7 @@ impl super::Mempool {
         debug!("🗳️ Sending vote for DataProposal {data_proposal_hash} to {validator} (lane size: {size})");
         self.send_net_message(
             validator.clone(),
-            MempoolNetMessage::DataVote(self.crypto.sign((data_proposal_hash, size))?),
+            MempoolNetMessage::DataVote(sign_data_vote(&self.crypto, data_proposal_hash, size)?),
         )?;
         Ok(())
     }
@@ -28,6 +28,8 @@ use opentelemetry::{metrics::Histogram, InstrumentationScope};
 use tracing::{info, trace, warn};
 
 pub mod network;
+#[cfg(unix)]
+pub mod signing_policy;
 
 #[derive(Debug, Clone)]
 pub enum P2PCommand {
@@ -142,18 +144,23 @@ impl P2P {
                         let canal = Self::choose_canal(&message);
                         p2p_server.broadcast_only_for(&only_for, canal, message.clone())
                     }
+                    OutboundMessage::BroadcastMessageExcept(except, message) => {
+                        let canal = Self::choose_canal(&message);
+                        p2p_server.broadcast_except(&except, canal, message.clone())
+                    }
                 };
             }
 
             p2p_tcp_event = p2p_server.listen_next() => {
                 if let Ok(Some(p2p_server_event)) = log_warn!(p2p_server.handle_p2p_tcp_event(p2p_tcp_event).await, "Handling P2PTcpEvent") {
                     match p2p_server_event {
-                        P2PServerEvent::NewPeer { name, pubkey, da_address, height } => {
+                        P2PServerEvent::NewPeer { name, pubkey, da_address, height, version } => {
                             let _ = log_warn!(self.bus.send(PeerEvent::NewPeer {
                                 name,
                                 pubkey,
                                 da_address,
-                                height: BlockHeight(height)
+                                height: BlockHeight(height),
+                                version,
                             }), "Sending new peer event");
                         },
                         P2PServerEvent::P2PMessage { msg: net_message } => {
@@ -3,8 +3,9 @@ use crate::mempool::MempoolNetMessage;
 use crate::model::ValidatorPublicKey;
 use anyhow::Context;
 use borsh::{BorshDeserialize, BorshSerialize};
-use hyle_crypto::BlstCrypto;
+use hyle_crypto::{signer::SignerMessage, BlstCrypto};
 use hyle_model::{BlockHeight, SignedByValidator};
+pub use hyle_model::{HeaderSignableData, MsgHeader};
 use hyle_net::clock::TimestampMsClock;
 use hyle_net::tcp::P2PTcpMessage;
 use serde::{Deserialize, Serialize};
@@ -20,6 +21,8 @@ pub enum OutboundMessage {
     },
     BroadcastMessage(NetMessage),
     BroadcastMessageOnlyFor(HashSet<ValidatorPublicKey>, NetMessage),
+    /// Broadcast to every connected peer but these ones.
+    BroadcastMessageExcept(HashSet<ValidatorPublicKey>, NetMessage),
 }
 
 impl OutboundMessage {
@@ -32,6 +35,12 @@ impl OutboundMessage {
     ) -> Self {
         OutboundMessage::BroadcastMessageOnlyFor(only_for, msg.into())
     }
+    pub fn broadcast_except<T: Into<NetMessage>>(
+        except: HashSet<ValidatorPublicKey>,
+        msg: T,
+    ) -> Self {
+        OutboundMessage::BroadcastMessageExcept(except, msg.into())
+    }
     pub fn send<T: Into<NetMessage>>(validator_id: ValidatorPublicKey, msg: T) -> Self {
         OutboundMessage::SendMessage {
             validator_id,
@@ -47,6 +56,8 @@ pub enum PeerEvent {
         pubkey: ValidatorPublicKey,
         da_address: String,
         height: BlockHeight,
+        /// P2P handshake version, telling which optional features the peer supports
+        version: u16,
     },
 }
 
@@ -110,17 +121,12 @@ impl NetMessage {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, BorshSerialize, BorshDeserialize, Eq, PartialEq)]
-pub struct HeaderSignableData(pub Vec<u8>);
-
 // Can't be regular Into as I don't want to take ownership
 pub trait IntoHeaderSignableData {
     fn to_header_signable_data(&self) -> HeaderSignableData;
-}
-#[derive(Debug, Serialize, Deserialize, Clone, BorshSerialize, BorshDeserialize, Eq, PartialEq)]
-pub struct MsgHeader {
-    pub timestamp: u128,
-    pub hash: HeaderSignableData,
+
+    /// Message remote signers derive the header from, they never sign a header as is
+    fn to_signer_message(&self, timestamp: u128) -> anyhow::Result<SignerMessage>;
 }
 
 #[derive(Serialize, Deserialize, Clone, BorshSerialize, BorshDeserialize, Eq, PartialEq)]
@@ -167,7 +173,8 @@ impl HeaderSigner for BlstCrypto {
             timestamp: TimestampMsClock::now().0,
             hash: msg.to_header_signable_data(),
         };
-        let signature = self.sign(header)?;
+        let timestamp = header.timestamp;
+        let signature = self.sign_typed(header, || msg.to_signer_message(timestamp))?;
         Ok(MsgWithHeader::<T> {
             msg,
             header: signature,
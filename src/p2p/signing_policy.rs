@@ -0,0 +1,167 @@
+//! What a signer process signs for a node, see [hyle_crypto::remote_signer].
+//!
+//! The signer decodes the messages of the node and derives what it signs from them: headers
+//! from the message they are sent with, votes and timeouts from their record. The record of a
+//! consensus message header is derived from the message itself, so it can't be forged.
+
+use anyhow::{bail, Result};
+use hyle_crypto::signer::{SignedKind, SignedRecord, SignerMessage, SigningPolicy};
+use hyle_net::tcp::NodeConnectionData;
+
+use crate::{
+    consensus::{
+        signed_record, ConfirmAckMarker, ConsensusNetMessage, ConsensusTimeoutMarker,
+        PrepareVoteMarker,
+    },
+    mempool::MempoolNetMessage,
+};
+
+use super::network::{IntoHeaderSignableData, MsgHeader};
+
+pub struct NodeSigningPolicy;
+
+impl SigningPolicy for NodeSigningPolicy {
+    fn signable(&self, message: &SignerMessage) -> Result<(Vec<u8>, Option<SignedRecord>)> {
+        Ok(match message {
+            SignerMessage::ConsensusHeader { timestamp, message } => {
+                let msg: ConsensusNetMessage = borsh::from_slice(message)?;
+                (signable_header(*timestamp, &msg)?, signed_record(&msg))
+            }
+            SignerMessage::MempoolHeader { timestamp, message } => {
+                let msg: MempoolNetMessage = borsh::from_slice(message)?;
+                (signable_header(*timestamp, &msg)?, None)
+            }
+            SignerMessage::Round(record) => {
+                let hash = record.hash.clone();
+                let data = match record.kind {
+                    SignedKind::PrepareVote => borsh::to_vec(&(hash, PrepareVoteMarker))?,
+                    SignedKind::ConfirmAck => borsh::to_vec(&(hash, ConfirmAckMarker))?,
+                    SignedKind::Timeout => {
+                        borsh::to_vec(&(record.slot, record.view, hash, ConsensusTimeoutMarker))?
+                    }
+                    SignedKind::Prepare => {
+                        bail!("Prepares are only signed in the header of their message")
+                    }
+                };
+                (data, Some(record.clone()))
+            }
+            SignerMessage::DataVote(hash, size) => (borsh::to_vec(&(hash, size))?, None),
+            SignerMessage::Candidacy(candidacy) => (borsh::to_vec(candidacy)?, None),
+            SignerMessage::Handshake(data) => {
+                let data: NodeConnectionData = borsh::from_slice(data)?;
+                (borsh::to_vec(&data)?, None)
+            }
+        })
+    }
+}
+
+fn signable_header<T: IntoHeaderSignableData>(timestamp: u128, msg: &T) -> Result<Vec<u8>> {
+    Ok(borsh::to_vec(&MsgHeader {
+        timestamp,
+        hash: msg.to_header_signable_data(),
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use hyle_crypto::{remote_signer::SignerServer, signer::SlashingProtection, BlstCrypto};
+
+    use super::*;
+    use crate::{
+        consensus::{Ticket, TimeoutKind},
+        model::{utils::TimestampMs, ConsensusProposal, ConsensusProposalHash, Hashed},
+        p2p::network::HeaderSigner,
+    };
+
+    #[test]
+    fn test_signs_what_the_node_signs() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let local = BlstCrypto::new_random().unwrap();
+        let mut server =
+            SignerServer::new(local, SlashingProtection::default(), NodeSigningPolicy).unwrap();
+        std::thread::spawn(move || server.serve(listener));
+        let remote = BlstCrypto::remote(&socket).unwrap();
+
+        let round = |kind, hash: &ConsensusProposalHash| {
+            let record = SignedRecord {
+                kind,
+                slot: 2,
+                view: 0,
+                hash: hash.clone(),
+            };
+            move || Ok(SignerMessage::Round(record))
+        };
+
+        // Prepare headers are bound to the proposal they carry
+        let cp = ConsensusProposal {
+            slot: 2,
+            ..ConsensusProposal::default()
+        };
+        let prepare = ConsensusNetMessage::Prepare(cp.clone(), Ticket::Genesis, 0);
+        let signed = remote.sign_msg_with_header(prepare.clone()).unwrap();
+        assert!(BlstCrypto::verify(&signed.header).unwrap());
+        let conflicting = ConsensusProposal {
+            timestamp: TimestampMs(1),
+            ..cp.clone()
+        };
+        assert!(remote
+            .sign_msg_with_header(ConsensusNetMessage::Prepare(
+                conflicting,
+                Ticket::Genesis,
+                0
+            ))
+            .is_err());
+
+        // A vote and the header of its message share their record
+        let hash = cp.hashed();
+        let vote = remote
+            .sign_typed(
+                (hash.clone(), PrepareVoteMarker),
+                round(SignedKind::PrepareVote, &hash),
+            )
+            .unwrap();
+        assert!(BlstCrypto::verify(&vote).unwrap());
+        let signed = remote
+            .sign_msg_with_header(ConsensusNetMessage::PrepareVote(vote, 2, 0))
+            .unwrap();
+        assert!(BlstCrypto::verify(&signed.header).unwrap());
+        let other = ConsensusProposalHash("other".to_string());
+        assert!(remote
+            .sign_typed(
+                (other.clone(), PrepareVoteMarker),
+                round(SignedKind::PrepareVote, &other),
+            )
+            .is_err());
+
+        // Timeouts too
+        let parent = ConsensusProposalHash::default();
+        let timeout = remote
+            .sign_typed(
+                (2, 0, parent.clone(), ConsensusTimeoutMarker),
+                round(SignedKind::Timeout, &parent),
+            )
+            .unwrap();
+        assert!(BlstCrypto::verify(&timeout).unwrap());
+        let signed = remote
+            .sign_msg_with_header(ConsensusNetMessage::Timeout((
+                timeout.clone(),
+                TimeoutKind::NilProposal(timeout),
+            )))
+            .unwrap();
+        assert!(BlstCrypto::verify(&signed.header).unwrap());
+
+        // Prepares are never signed on their own
+        assert!(remote
+            .sign_typed(hash.clone(), round(SignedKind::Prepare, &hash))
+            .is_err());
+
+        let signed = remote
+            .sign_msg_with_header(MempoolNetMessage::SyncRequest(None, None))
+            .unwrap();
+        assert!(BlstCrypto::verify(&signed.header).unwrap());
+    }
+}
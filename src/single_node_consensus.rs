@@ -1,16 +1,20 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use crate::bus::command_response::{CmdRespClient, Query};
 use crate::bus::BusClientSender;
 use crate::consensus::ConfirmAckMarker;
 use crate::consensus::{CommittedConsensusProposal, ConsensusEvent, QueryConsensusInfo};
 use crate::genesis::GenesisEvent;
-use crate::mempool::QueryNewCut;
+use crate::mempool::{NewCut, QueryNewCut};
 use crate::model::*;
+use crate::node_state::module::QueryContractRegistryRoot;
 use crate::utils::conf::SharedConf;
 use anyhow::Result;
 use borsh::{BorshDeserialize, BorshSerialize};
-use hyle_crypto::SharedBlstCrypto;
+use hyle_crypto::{
+    signer::{SignedKind, SignedRecord, SignerMessage},
+    BlstCrypto, SharedBlstCrypto,
+};
 use hyle_modules::bus::SharedMessageBus;
 use hyle_modules::modules::module_bus_client;
 use hyle_modules::modules::Module;
@@ -22,7 +26,8 @@ use tracing::{debug, warn};
 module_bus_client! {
 struct SingleNodeConsensusBusClient {
     sender(ConsensusEvent),
-    sender(Query<QueryNewCut, Cut>),
+    sender(Query<QueryNewCut, NewCut>),
+    sender(Query<QueryContractRegistryRoot, Option<MerkleHash>>),
     receiver(Query<QueryConsensusInfo, ConsensusInfo>),
     receiver(GenesisEvent),
 }
@@ -35,6 +40,8 @@ struct SingleNodeConsensusStore {
     last_consensus_proposal_hash: ConsensusProposalHash,
     last_slot: u64,
     last_cut: Cut,
+    /// Number of slots of each epoch, as set by the genesis block
+    epoch_length: EpochLength,
 }
 
 pub struct SingleNodeConsensus {
@@ -110,6 +117,11 @@ impl SingleNodeConsensus {
                     match msg {
                         GenesisEvent::GenesisBlock (signed_block) => {
                             self.store.last_consensus_proposal_hash = signed_block.hashed();
+                            for action in &signed_block.consensus_proposal.staking_actions {
+                                if let ConsensusStakingAction::EpochLength { slots } = action {
+                                    self.store.epoch_length = EpochLength(*slots);
+                                }
+                            }
                             // TODO: handle this from the block?
                             self.store
                                 .staking
@@ -152,10 +164,13 @@ impl SingleNodeConsensus {
             command_response<QueryConsensusInfo, ConsensusInfo> _ => {
                 let slot = self.store.last_slot;
                 let view = 0;
+                let epoch = self.store.epoch_length.epoch_of(slot);
                 let round_leader = self.crypto.validator_pubkey().clone();
                 let last_timestamp = TimestampMsClock::now();
                 let validators = vec![];
-                Ok(ConsensusInfo { slot, view, round_leader, last_timestamp, validators })
+                let stakes = BTreeMap::new();
+                let total_bond = 0;
+                Ok(ConsensusInfo { slot, view, epoch, round_leader, last_timestamp, validators, stakes, total_bond })
             },
             _ = interval.tick() => {
                 self.handle_new_slot_tick().await?;
@@ -166,34 +181,73 @@ impl SingleNodeConsensus {
     }
     async fn handle_new_slot_tick(&mut self) -> Result<()> {
         debug!("New slot tick");
+        // The proposal commits to the contract registry root after the last block
+        let parent_contract_registry_root = match self
+            .bus
+            .shutdown_aware_request::<Self>(QueryContractRegistryRoot(
+                self.store.last_consensus_proposal_hash.clone(),
+            ))
+            .await
+        {
+            Ok(Some(root)) => root,
+            Ok(None) => {
+                debug!("Node state hasn't handled the last block yet, waiting for the next tick");
+                return Ok(());
+            }
+            Err(err) => {
+                warn!(
+                    "Error while requesting the contract registry root: {:?}",
+                    err
+                );
+                return Ok(());
+            }
+        };
         // Query a new cut to Mempool in order to create a new CommitCut
-        match self
+        let tx_root = match self
             .bus
-            .shutdown_aware_request::<Self>(QueryNewCut(self.store.staking.clone()))
+            .shutdown_aware_request::<Self>(QueryNewCut(
+                self.store.staking.clone(),
+                self.store.last_cut.clone(),
+            ))
             .await
         {
-            Ok(cut) => {
-                self.store.last_cut = cut.clone();
+            Ok(NewCut { cut, tx_root }) => {
+                self.store.last_cut = cut;
+                tx_root
             }
             Err(err) => {
                 // In case of an error, we reuse the last cut to avoid being considered byzantine
+                // The block then holds no transaction
                 tracing::error!("Error while requesting new cut: {:?}", err);
+                EMPTY_MERKLE_ROOT
             }
         };
         let new_slot = self.store.last_slot + 1;
         let consensus_proposal = ConsensusProposal {
             slot: new_slot,
+            epoch: self.store.epoch_length.epoch_of(new_slot),
             timestamp: TimestampMsClock::now(),
             cut: self.store.last_cut.clone(),
             staking_actions: vec![],
             parent_hash: std::mem::take(&mut self.store.last_consensus_proposal_hash),
+            tx_root,
+            parent_commit_qc: AggregateSignature::default(),
+            parent_contract_registry_root,
         };
 
         self.store.last_consensus_proposal_hash = consensus_proposal.hashed();
 
-        let certificate = self
-            .crypto
-            .sign_aggregate((consensus_proposal.hashed(), ConfirmAckMarker), &[])?;
+        let hash = consensus_proposal.hashed();
+        let record = SignedRecord {
+            kind: SignedKind::ConfirmAck,
+            slot: new_slot,
+            view: 0,
+            hash: hash.clone(),
+        };
+        let ack = self.crypto.sign_typed((hash, ConfirmAckMarker), || {
+            Ok(SignerMessage::Round(record))
+        })?;
+        let certificate = BlstCrypto::aggregate(ack.msg.clone(), &[&ack])?;
 
         self.bus.send(ConsensusEvent::CommitConsensusProposal(
             CommittedConsensusProposal {
@@ -233,7 +287,8 @@ mod tests {
 
     bus_client!(
         struct TestBusClient {
-            receiver(Query<QueryNewCut, Cut>),
+            receiver(Query<QueryNewCut, NewCut>),
+            receiver(Query<QueryContractRegistryRoot, Option<MerkleHash>>),
         }
     );
 
@@ -261,8 +316,14 @@ mod tests {
             tokio::spawn(async move {
                 handle_messages! {
                     on_bus new_cut_query_receiver,
-                    command_response<QueryNewCut, Cut> _ => {
-                        Ok(vec![(LaneId::default(), DataProposalHash::default(), LaneBytesSize::default(), AggregateSignature::default())])
+                    command_response<QueryNewCut, NewCut> _ => {
+                        Ok(NewCut {
+                            cut: vec![(LaneId::default(), DataProposalHash::default(), LaneBytesSize::default(), AggregateSignature::default())],
+                            tx_root: EMPTY_MERKLE_ROOT,
+                        })
+                    }
+                    command_response<QueryContractRegistryRoot, Option<MerkleHash>> _ => {
+                        Ok(Some(EMPTY_MERKLE_ROOT))
                     }
                 }
             });
@@ -1,19 +1,34 @@
 use crate::bus::BusClientSender;
 
 use anyhow::Result;
-use client_sdk::tcp_client::{TcpApiServer, TcpServerMessage};
+use client_sdk::tcp_client::{TcpApiServer, TcpServerMessage, TcpServerResponse};
 use hyle_modules::{
     bus::SharedMessageBus,
-    log_error, module_handle_messages,
+    log_error, log_warn, module_handle_messages,
     modules::{module_bus_client, Module},
 };
 use hyle_net::tcp::TcpEvent;
 use tracing::info;
 
+/// Message received from a TCP client, with the address to reply to.
+#[derive(Debug, Clone)]
+pub struct TcpServerRequest {
+    pub dest: String,
+    pub message: TcpServerMessage,
+}
+
+/// Reply to a TCP client.
+#[derive(Debug, Clone)]
+pub struct TcpServerReply {
+    pub dest: String,
+    pub response: TcpServerResponse,
+}
+
 module_bus_client! {
 #[derive(Debug)]
 struct TcpServerBusClient {
-    sender(TcpServerMessage),
+    sender(TcpServerRequest),
+    receiver(TcpServerReply),
 }
 }
 
@@ -54,10 +69,14 @@ impl TcpServer {
         module_handle_messages! {
             on_self self,
             Some(tcp_event) = server.listen_next() => {
-                if let TcpEvent::Message { dest: _, data } = tcp_event {
-                    _ = log_error!(self.bus.send(data), "Sending message on TcpServerMessage topic from connection pool");
+                if let TcpEvent::Message { dest, data } = tcp_event {
+                    _ = log_error!(self.bus.send(TcpServerRequest { dest, message: data }), "Sending message on TcpServerRequest topic from connection pool");
                 }
             }
+            listen<TcpServerReply> reply => {
+                // Don't wait on slow clients, replies are only informative.
+                _ = log_warn!(server.try_send(reply.dest, reply.response), "Replying to TCP client");
+            }
         };
 
         Ok(())
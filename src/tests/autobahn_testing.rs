@@ -143,7 +143,7 @@ macro_rules! simple_commit_round {
         send! {
             description: "Follower - PrepareVote",
             from: [$($follower),+], to: $leader,
-            message_matches: ConsensusNetMessage::PrepareVote(_)
+            message_matches: ConsensusNetMessage::PrepareVote(..)
         };
 
         broadcast! {
@@ -155,7 +155,7 @@ macro_rules! simple_commit_round {
         send! {
             description: "Follower - Confirm Ack",
             from: [$($follower),+], to: $leader,
-            message_matches: ConsensusNetMessage::ConfirmAck(_)
+            message_matches: ConsensusNetMessage::ConfirmAck(..)
         };
 
         broadcast! {
@@ -237,7 +237,7 @@ macro_rules! assert_chanmsg_matches {
 }
 
 pub(crate) use assert_chanmsg_matches;
-use assertables::assert_matches;
+use assertables::{assert_contains, assert_matches};
 pub(crate) use broadcast;
 pub(crate) use build_tuple;
 use futures::future::join_all;
@@ -263,6 +263,36 @@ macro_rules! build_nodes {
                 nodes.push(autobahn_node);
             }
 
+            build_tuple!(nodes.remove(0), $count)
+        }
+    }};
+    ($count:tt, $leader_election:expr) => {{
+        async {
+            let cryptos: Vec<BlstCrypto> = AutobahnTestCtx::generate_cryptos($count);
+
+            let mut nodes = vec![];
+
+            for i in 0..$count {
+                let crypto = cryptos.get(i).unwrap().clone();
+                let mut autobahn_node =
+                    AutobahnTestCtx::new(format!("node-{i}").as_ref(), crypto).await;
+
+                autobahn_node.consensus_ctx.setup_node(i, &cryptos);
+                autobahn_node
+                    .consensus_ctx
+                    .set_leader_election($leader_election);
+                autobahn_node.mempool_ctx.setup_node(&cryptos);
+                nodes.push(autobahn_node);
+            }
+
+            // The tests expect the first node to lead the first round
+            let leader = nodes[0].consensus_ctx.round_leader();
+            let position = nodes
+                .iter()
+                .position(|node| node.consensus_ctx.pubkey() == leader)
+                .unwrap();
+            nodes.rotate_left(position);
+
             build_tuple!(nodes.remove(0), $count)
         }
     }};
@@ -274,19 +304,25 @@ use crate::bus::metrics::BusMetrics;
 use crate::bus::{bus_client, SharedMessageBus};
 use crate::consensus::test::ConsensusTestCtx;
 use crate::consensus::{ConsensusEvent, ConsensusNetMessage, TCKind, Ticket, TimeoutKind};
+use crate::mempool::block_construction::{cut_tx_root, TxRootOrMissing};
 use crate::mempool::test::{make_register_contract_tx, MempoolTestCtx};
-use crate::mempool::{MempoolNetMessage, QueryNewCut, ValidatorDAG};
+use crate::mempool::{MempoolNetMessage, NewCut, QueryNewCut, QueryTxRoot, ValidatorDAG};
 use crate::model::*;
-use crate::node_state::module::NodeStateEvent;
+use crate::node_state::module::{NodeStateEvent, QueryContractRegistryRoot};
 use crate::p2p::network::OutboundMessage;
 use crate::p2p::P2PCommand;
+use crate::utils::conf::LeaderElectionStrategy;
+use crate::utils::integration_test::find_available_port;
+use anyhow::Result;
 use hyle_crypto::BlstCrypto;
 use hyle_modules::handle_messages;
 use tracing::info;
 
 bus_client!(
     pub struct AutobahnBusClient {
-        receiver(Query<QueryNewCut, Cut>),
+        receiver(Query<QueryNewCut, NewCut>),
+        receiver(Query<QueryTxRoot, Option<MerkleHash>>),
+        receiver(Query<QueryContractRegistryRoot, Option<MerkleHash>>),
     }
 );
 
@@ -311,7 +347,7 @@ impl AutobahnTestCtx {
 
         let mempool_sync_request_sender = mempool.start_mempool_sync();
 
-        AutobahnTestCtx {
+        let ctx = AutobahnTestCtx {
             shared_bus,
             consensus_ctx: ConsensusTestCtx {
                 out_receiver: consensus_out_receiver,
@@ -328,7 +364,33 @@ impl AutobahnTestCtx {
                 mempool,
                 mempool_sync_request_sender,
             },
-        }
+        };
+        ctx.answer_tx_root_queries().await;
+        ctx
+    }
+
+    /// Spawn a coroutine to answer the transaction root queries of consensus, from the lanes of mempool.
+    /// Missing data proposals are not fetched, tests hand them over to mempool.
+    /// There is no node state: contract registry roots are all empty.
+    async fn answer_tx_root_queries(&self) {
+        let lanes = self.mempool_ctx.lanes_handle();
+        let mut autobahn_client_bus =
+            AutobahnBusClient::new_from_bus(self.shared_bus.new_handle()).await;
+
+        tokio::spawn(async move {
+            handle_messages! {
+                on_bus autobahn_client_bus,
+                command_response<QueryTxRoot, Option<MerkleHash>> query => {
+                    cut_tx_root(&lanes, &query.parent_cut, &query.cut).map(|tx_root| match tx_root {
+                        TxRootOrMissing::TxRoot(tx_root) => Some(tx_root),
+                        TxRootOrMissing::Missing(_) => None,
+                    })
+                }
+                command_response<QueryContractRegistryRoot, Option<MerkleHash>> _ => {
+                    Ok(Some(EMPTY_MERKLE_ROOT))
+                }
+            };
+        });
     }
 
     pub fn generate_cryptos(nb: usize) -> Vec<BlstCrypto> {
@@ -347,17 +409,23 @@ impl AutobahnTestCtx {
     /// Spawn a coroutine to answer the command response call of start_round, with the current of mempool
     async fn start_round_with_cut_from_mempool(&mut self, ts: TimestampMs) {
         let staking = self.consensus_ctx.staking();
-        let latest_cut: Cut = self.mempool_ctx.gen_cut(&staking);
+        let parent_cut = self.consensus_ctx.parent_cut();
+        let new_cut: NewCut = self.mempool_ctx.gen_cut(&staking, &parent_cut).await;
+
+        self.start_round_with_cut(new_cut, ts).await;
+    }
 
+    /// Spawn a coroutine to answer the command response call of start_round, with this cut
+    async fn start_round_with_cut(&mut self, new_cut: NewCut, ts: TimestampMs) {
         let mut autobahn_client_bus =
             AutobahnBusClient::new_from_bus(self.shared_bus.new_handle()).await;
 
         tokio::spawn(async move {
             handle_messages! {
                 on_bus autobahn_client_bus,
-                listen<Query<QueryNewCut, Cut>> qnc => {
+                listen<Query<QueryNewCut, NewCut>> qnc => {
                     if let Ok(value) = qnc.take() {
-                        value.answer(latest_cut.clone()).expect("error when injecting cut");
+                        value.answer(new_cut.clone()).expect("error when injecting cut");
 
                         break;
                     }
@@ -393,9 +461,40 @@ fn create_poda(
     BlstCrypto::aggregate((data_proposal_hash.clone(), line_size), &aggregates).unwrap()
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_basic_flow() {
-    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+/// Swaps the validator into `node` from among the candidates, false if none of them is it.
+/// The nodes must be in the same state, so that the scenario follows whoever is elected.
+fn swap_in(
+    validator: &ValidatorPublicKey,
+    node: &mut AutobahnTestCtx,
+    candidates: &mut [&mut AutobahnTestCtx],
+) -> bool {
+    if &node.consensus_ctx.pubkey() == validator {
+        return true;
+    }
+    match candidates
+        .iter_mut()
+        .find(|candidate| &candidate.consensus_ctx.pubkey() == validator)
+    {
+        Some(candidate) => {
+            std::mem::swap(node, &mut **candidate);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Runs the scenario from other election draws until they elect the leaders it needs.
+async fn with_fitting_draws<F: std::future::Future<Output = bool>>(scenario: impl Fn(usize) -> F) {
+    for attempt in 0..32 {
+        if scenario(attempt).await {
+            return;
+        }
+    }
+    panic!("The election never drew the leaders of the scenario");
+}
+
+async fn autobahn_basic_flow(leader_election: LeaderElectionStrategy) {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4, leader_election).await;
 
     let register_tx = make_register_contract_tx(ContractName::new("test1"));
     let register_tx_2 = make_register_contract_tx(ContractName::new("test2"));
@@ -473,22 +572,22 @@ async fn autobahn_basic_flow() {
     let (vote2, vote3, vote4) = send! {
         description: "PrepareVote",
         from: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     assert_matches!(
         vote2.msg,
-        ConsensusNetMessage::PrepareVote(Signed { msg: (cp, _), .. })
+        ConsensusNetMessage::PrepareVote(Signed { msg: (cp, _), .. }, ..)
         if cp == consensus_proposal.hashed()
     );
     assert_matches!(
         vote3.msg,
-        ConsensusNetMessage::PrepareVote(Signed { msg: (cp, _), .. })
+        ConsensusNetMessage::PrepareVote(Signed { msg: (cp, _), .. }, ..)
         if cp == consensus_proposal.hashed()
     );
     assert_matches!(
         vote4.msg,
-        ConsensusNetMessage::PrepareVote(Signed { msg: (cp, _), .. })
+        ConsensusNetMessage::PrepareVote(Signed { msg: (cp, _), .. }, ..)
         if cp == consensus_proposal.hashed()
     );
 
@@ -501,22 +600,22 @@ async fn autobahn_basic_flow() {
     let (vote2, vote3, vote4) = send! {
         description: "ConfirmAck",
         from: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     assert_matches!(
         vote2.msg,
-        ConsensusNetMessage::ConfirmAck(Signed { msg: (cp, _), .. })
+        ConsensusNetMessage::ConfirmAck(Signed { msg: (cp, _), .. }, ..)
         if cp == consensus_proposal.hashed()
     );
     assert_matches!(
         vote3.msg,
-        ConsensusNetMessage::ConfirmAck(Signed { msg: (cp, _), .. })
+        ConsensusNetMessage::ConfirmAck(Signed { msg: (cp, _), .. }, ..)
         if cp == consensus_proposal.hashed()
     );
     assert_matches!(
         vote4.msg,
-        ConsensusNetMessage::ConfirmAck(Signed { msg: (cp, _), .. })
+        ConsensusNetMessage::ConfirmAck(Signed { msg: (cp, _), .. }, ..)
         if cp == consensus_proposal.hashed()
     );
 
@@ -718,9 +817,10 @@ async fn mempool_podaupdate_too_early() {
     assert_nb_signatures(&node4, 4);
 }
 
-#[test_log::test(tokio::test)]
-async fn consensus_missed_prepare() {
-    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+/// A node misses a prepare and leads the next slot, so it gets timed out.
+/// The slot-1 timestamp of each attempt varies the commit QC seeding the next slot but one.
+async fn consensus_missed_prepare(leader_election: LeaderElectionStrategy, attempt: usize) -> bool {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4, leader_election).await;
 
     // First data proposal
 
@@ -733,7 +833,7 @@ async fn consensus_missed_prepare() {
     };
 
     node1
-        .start_round_with_cut_from_mempool(TimestampMs(1000))
+        .start_round_with_cut_from_mempool(TimestampMs(1000 + attempt as u128))
         .await;
 
     // Normal round from Genesis
@@ -742,6 +842,21 @@ async fn consensus_missed_prepare() {
         followers: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx]
     };
 
+    // Node 2 leads slot 2, node 3 leads slot 3 and node 4 its view 1
+    let next_leader = node1.consensus_ctx.round_leader();
+    let timed_out_leader = node1.consensus_ctx.leader_after_next_slot(0);
+    let view_1_leader = node1.consensus_ctx.leader_after_next_slot(1);
+    assert!(swap_in(
+        &next_leader,
+        &mut node2,
+        &mut [&mut node1, &mut node3, &mut node4]
+    ));
+    if !swap_in(&timed_out_leader, &mut node3, &mut [&mut node1, &mut node4])
+        || !swap_in(&view_1_leader, &mut node4, &mut [&mut node1])
+    {
+        return false;
+    }
+
     disseminate! {
         txs: [register_tx],
         owner: node2.mempool_ctx,
@@ -849,7 +964,7 @@ async fn consensus_missed_prepare() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node4.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -858,7 +973,13 @@ async fn consensus_missed_prepare() {
         message_matches: ConsensusNetMessage::Commit(..)
     };
 
-    // Weirdly, next leader is node 4 too on slot: 4 view: 0
+    // Whoever leads slot 4 view 0, node 4 with round robin
+    let leader = node1.consensus_ctx.round_leader();
+    assert!(swap_in(
+        &leader,
+        &mut node4,
+        &mut [&mut node1, &mut node2, &mut node3]
+    ));
 
     node4
         .start_round_with_cut_from_mempool(TimestampMs(5000))
@@ -870,6 +991,124 @@ async fn consensus_missed_prepare() {
         leader: node4.consensus_ctx,
         followers: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx]
     };
+    true
+}
+
+#[test_log::test(tokio::test)]
+async fn consensus_checks_tx_root() {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+
+    let register_tx = make_register_contract_tx(ContractName::new("test1"));
+
+    let (dp, _, _, _, _) = disseminate! {
+        txs: [register_tx],
+        owner: node1.mempool_ctx,
+        voters: [node2.mempool_ctx, node3.mempool_ctx, node4.mempool_ctx]
+    };
+
+    let staking = node1.consensus_ctx.staking();
+    let parent_cut = node1.consensus_ctx.parent_cut();
+    let new_cut = node1.mempool_ctx.gen_cut(&staking, &parent_cut).await;
+    let block = SignedBlock {
+        data_proposals: vec![(node1.mempool_ctx.own_lane(), vec![dp])],
+        ..SignedBlock::default()
+    };
+    assert_eq!(new_cut.tx_root, block.tx_merkle_root());
+
+    // The leader leaves the transactions of its cut out of the root
+    node1
+        .start_round_with_cut(
+            NewCut {
+                tx_root: EMPTY_MERKLE_ROOT,
+                ..new_cut
+            },
+            TimestampMs(1000),
+        )
+        .await;
+
+    let prepare = broadcast! {
+        description: "Prepare",
+        from: node1.consensus_ctx, to: [],
+        message_matches: ConsensusNetMessage::Prepare(..)
+    };
+
+    // Followers recompute the root from the data proposals they hold, and don't vote
+    for node in [&mut node2, &mut node3, &mut node4] {
+        assert_contains!(
+            node.consensus_ctx
+                .handle_msg_err(&prepare)
+                .await
+                .to_string(),
+            "Transaction root"
+        );
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn consensus_waits_for_missing_dp_before_voting() {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+
+    let register_tx = make_register_contract_tx(ContractName::new("test1"));
+    let lane_id = LaneId(node1.mempool_ctx.validator_pubkey().clone());
+    let dp = node1
+        .mempool_ctx
+        .create_data_proposal_on_top(lane_id, &[register_tx]);
+    node1
+        .mempool_ctx
+        .process_new_data_proposal(dp.clone())
+        .unwrap();
+    node1.mempool_ctx.timer_tick().await.unwrap();
+
+    // Node 4 misses the data proposal, its PoDA only needs f+1 validators
+    let dp_msg = broadcast! {
+        description: "Disseminate DataProposal",
+        from: node1.mempool_ctx, to: [node2.mempool_ctx, node3.mempool_ctx],
+        message_matches: MempoolNetMessage::DataProposal(_, _)
+    };
+    join_all(
+        [&mut node2.mempool_ctx, &mut node3.mempool_ctx]
+            .iter_mut()
+            .map(|ctx| ctx.handle_processed_data_proposals()),
+    )
+    .await;
+    send! {
+        description: "Disseminated DataProposal Vote",
+        from: [node2.mempool_ctx, node3.mempool_ctx], to: node1.mempool_ctx,
+        message_matches: MempoolNetMessage::DataVote(..)
+    };
+    node1.mempool_ctx.assert_broadcast("poda update f+1").await;
+    node1.mempool_ctx.assert_broadcast("poda update 2f+1").await;
+
+    let staking = node1.consensus_ctx.staking();
+    let parent_cut = node1.consensus_ctx.parent_cut();
+    let new_cut = node1.mempool_ctx.gen_cut(&staking, &parent_cut).await;
+    assert_eq!(new_cut.cut.len(), 1);
+    node1.start_round_with_cut(new_cut, TimestampMs(1000)).await;
+
+    let prepare = broadcast! {
+        description: "Prepare",
+        from: node1.consensus_ctx, to: [node2.consensus_ctx, node3.consensus_ctx],
+        message_matches: ConsensusNetMessage::Prepare(..)
+    };
+
+    // Node 4 waits for the data proposal to be fetched before checking the transaction root
+    let mempool4 = &mut node4.mempool_ctx;
+    tokio::join!(
+        node4
+            .consensus_ctx
+            .handle_msg(&prepare, "Prepare with a missing data proposal"),
+        async {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            mempool4.handle_msg(&dp_msg, "Fetched DataProposal").await;
+            mempool4.handle_processed_data_proposals().await;
+        }
+    );
+
+    send! {
+        description: "PrepareVote",
+        from: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node1.consensus_ctx,
+        message_matches: ConsensusNetMessage::PrepareVote(..)
+    };
 }
 
 #[test_log::test(tokio::test)]
@@ -1014,12 +1253,11 @@ async fn mempool_fail_to_vote_on_fork() {
     );
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_rejoin_flow() {
-    let mut server = DataAvailabilityServer::start(7890, "DaServer")
+async fn autobahn_rejoin_flow(leader_election: LeaderElectionStrategy) {
+    let mut server = DataAvailabilityServer::start(find_available_port().await, "DaServer")
         .await
         .unwrap();
-    let (mut node1, mut node2) = build_nodes!(2).await;
+    let (mut node1, mut node2) = build_nodes!(2, leader_election).await;
 
     // Let's setup the consensus so our joining node has some blocks to catch up.
     ConsensusTestCtx::setup_for_round(
@@ -1081,6 +1319,10 @@ async fn autobahn_rejoin_flow() {
     // Do a few rounds of consensus-with-lag and note that we don't actually catch up.
     // (this is expected because DA stopped receiving new blocks, as it did indeed catch up)
     for i in 1..4 {
+        // Swap so we handle leader changes correctly
+        let leader = node1.consensus_ctx.round_leader();
+        assert!(swap_in(&leader, &mut node1, &mut [&mut node2]));
+
         node1
             .start_round_with_cut_from_mempool(TimestampMs(1000 * i))
             .await;
@@ -1090,9 +1332,6 @@ async fn autobahn_rejoin_flow() {
             followers: [node2.consensus_ctx],
             joining: joining_node.consensus_ctx
         };
-
-        // Swap so we handle leader changes correctly
-        std::mem::swap(&mut node1, &mut node2);
     }
 
     // Now process block 2
@@ -1108,6 +1347,8 @@ async fn autobahn_rejoin_flow() {
     }
 
     // Process round
+    let leader = node1.consensus_ctx.round_leader();
+    assert!(swap_in(&leader, &mut node1, &mut [&mut node2]));
     node1
         .start_round_with_cut_from_mempool(TimestampMs(5000))
         .await;
@@ -1118,8 +1359,6 @@ async fn autobahn_rejoin_flow() {
         joining: joining_node.consensus_ctx
     };
 
-    std::mem::swap(&mut node1, &mut node2);
-
     // We still aren't caught up
     assert!(joining_node.consensus_ctx.is_joining());
 
@@ -1147,6 +1386,8 @@ async fn autobahn_rejoin_flow() {
     }
 
     // Process round
+    let leader = node1.consensus_ctx.round_leader();
+    assert!(swap_in(&leader, &mut node1, &mut [&mut node2]));
     node1
         .start_round_with_cut_from_mempool(TimestampMs(6000))
         .await;
@@ -1161,9 +1402,8 @@ async fn autobahn_rejoin_flow() {
     assert!(!joining_node.consensus_ctx.is_joining());
 }
 
-#[test_log::test(tokio::test)]
-async fn protocol_fees() {
-    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+async fn protocol_fees(leader_election: LeaderElectionStrategy) {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4, leader_election).await;
 
     // First data proposal
 
@@ -1284,11 +1524,10 @@ async fn protocol_fees() {
 /// Test case: P1 -> C1 -> P2 -> C2
 ///
 /// Confirm messages can be ignored if not received
-#[test_log::test(tokio::test)]
-async fn autobahn_missed_a_confirm_message() {
-    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+async fn autobahn_missed_a_confirm_message(leader_election: LeaderElectionStrategy) {
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4, leader_election).await;
 
-    ConsensusTestCtx::setup_for_round(
+    ConsensusTestCtx::setup_for_elected_round(
         &mut [
             &mut node1.consensus_ctx,
             &mut node2.consensus_ctx,
@@ -1298,6 +1537,8 @@ async fn autobahn_missed_a_confirm_message() {
         0,
         5,
         0,
+        &[(5, 0, 0), (6, 0, 1)],
+        0,
     );
 
     node1
@@ -1314,7 +1555,7 @@ async fn autobahn_missed_a_confirm_message() {
     send! {
         description: "PrepareVote",
         from: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     // Node 4 doesn't receive confirm
@@ -1327,7 +1568,7 @@ async fn autobahn_missed_a_confirm_message() {
     send! {
         description: "ConfirmAck",
         from: [node2.consensus_ctx, node3.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     // But Node 4 receive commit
@@ -1351,7 +1592,7 @@ async fn autobahn_missed_a_confirm_message() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1363,7 +1604,7 @@ async fn autobahn_missed_a_confirm_message() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1410,7 +1651,7 @@ async fn autobahn_missed_confirm_and_commit_messages() {
     send! {
         description: "PrepareVote",
         from: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1422,7 +1663,7 @@ async fn autobahn_missed_confirm_and_commit_messages() {
     send! {
         description: "ConfirmAck",
         from: [node2.consensus_ctx, node3.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1445,7 +1686,7 @@ async fn autobahn_missed_confirm_and_commit_messages() {
     send! {
         description: "PrepareVote - Node 4 has fast-forwarded to the next proposal",
         from: [node1.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1457,7 +1698,7 @@ async fn autobahn_missed_confirm_and_commit_messages() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1467,12 +1708,14 @@ async fn autobahn_missed_confirm_and_commit_messages() {
     };
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_buffer_early_messages() {
+async fn autobahn_buffer_early_messages(
+    leader_election: LeaderElectionStrategy,
+    attempt: usize,
+) -> bool {
     // node 4 got disconnected for a slot
-    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4).await;
+    let (mut node1, mut node2, mut node3, mut node4) = build_nodes!(4, leader_election).await;
 
-    ConsensusTestCtx::setup_for_round(
+    ConsensusTestCtx::setup_for_elected_round(
         &mut [
             &mut node1.consensus_ctx,
             &mut node2.consensus_ctx,
@@ -1482,6 +1725,8 @@ async fn autobahn_buffer_early_messages() {
         0,
         5,
         0,
+        &[(5, 0, 0), (6, 0, 1)],
+        attempt,
     );
 
     // Slot 5 starts, all nodes receive the prepare
@@ -1498,7 +1743,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "PrepareVote",
         from: [node2.consensus_ctx, node3.consensus_ctx, node4.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1510,7 +1755,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "ConfirmAck",
         from: [node2.consensus_ctx, node3.consensus_ctx], to: node1.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1533,7 +1778,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node3.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1545,7 +1790,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node3.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1554,7 +1799,11 @@ async fn autobahn_buffer_early_messages() {
         message_matches: ConsensusNetMessage::Commit(..)
     };
 
-    // Slot 5 starts with new leader but node4 is back online
+    // Slot 5 starts with new leader but node4 is back online, it can't lead while still behind
+    let leader = node1.consensus_ctx.round_leader();
+    if !swap_in(&leader, &mut node3, &mut [&mut node1, &mut node2]) {
+        return false;
+    }
     node3
         .start_round_with_cut_from_mempool(TimestampMs(3000))
         .await;
@@ -1568,7 +1817,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node2.consensus_ctx], to: node3.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     let confirm = node3.consensus_ctx.assert_broadcast("Confirm").await;
@@ -1588,7 +1837,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "PrepareVote - Node4 votes on slot 5",
         from: [node4.consensus_ctx], to: node3.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     node1
@@ -1616,7 +1865,7 @@ async fn autobahn_buffer_early_messages() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node2.consensus_ctx, node4.consensus_ctx], to: node3.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1625,7 +1874,13 @@ async fn autobahn_buffer_early_messages() {
         message_matches: ConsensusNetMessage::Commit(..)
     };
 
-    // Slot 6 starts with node4 as leader
+    // Slot 6 starts with the elected leader, node4 with round robin
+    let leader = node1.consensus_ctx.round_leader();
+    assert!(swap_in(
+        &leader,
+        &mut node4,
+        &mut [&mut node1, &mut node2, &mut node3]
+    ));
     node4
         .start_round_with_cut_from_mempool(TimestampMs(4000))
         .await;
@@ -1635,14 +1890,17 @@ async fn autobahn_buffer_early_messages() {
         from: node4.consensus_ctx, to: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx],
         message_matches: ConsensusNetMessage::Prepare(..)
     };
+    true
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_got_timed_out_during_sync() {
+async fn autobahn_got_timed_out_during_sync(
+    leader_election: LeaderElectionStrategy,
+    attempt: usize,
+) -> bool {
     // node1 is 2nd leader but got disconnected
-    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4).await;
+    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4, leader_election).await;
 
-    ConsensusTestCtx::setup_for_round(
+    ConsensusTestCtx::setup_for_elected_round(
         &mut [
             &mut node0.consensus_ctx,
             &mut node1.consensus_ctx,
@@ -1652,6 +1910,8 @@ async fn autobahn_got_timed_out_during_sync() {
         0,
         5,
         0,
+        &[(5, 0, 0), (6, 0, 1), (6, 1, 2)],
+        attempt,
     );
 
     // Slot 5 starts, all nodes receive the prepare
@@ -1668,7 +1928,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1680,7 +1940,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "ConfirmAck",
         from: [node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1738,7 +1998,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "PrepareVote",
         from: [node0.consensus_ctx, node3.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1750,7 +2010,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "ConfirmAck",
         from: [node0.consensus_ctx, node3.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1759,7 +2019,12 @@ async fn autobahn_got_timed_out_during_sync() {
         message_matches: ConsensusNetMessage::Commit(..)
     };
 
-    // Slot 5 starts but node1 is back online - leader is again node2
+    // Slot 5 starts but node1 is back online - leader is again node2 with round robin,
+    // and can't be node1 which is still behind
+    let leader = node0.consensus_ctx.round_leader();
+    if !swap_in(&leader, &mut node2, &mut [&mut node0, &mut node3]) {
+        return false;
+    }
     node2
         .start_round_with_cut_from_mempool(TimestampMs(3000))
         .await;
@@ -1773,7 +2038,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "PrepareVote",
         from: [node0.consensus_ctx, node3.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     let confirm = node2.consensus_ctx.assert_broadcast("Confirm").await;
@@ -1793,7 +2058,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "PrepareVote - Node1 votes on slot 7",
         from: [node1.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     node0
@@ -1821,7 +2086,7 @@ async fn autobahn_got_timed_out_during_sync() {
     send! {
         description: "ConfirmAck",
         from: [node0.consensus_ctx, node1.consensus_ctx, node3.consensus_ctx], to: node2.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     broadcast! {
@@ -1829,13 +2094,13 @@ async fn autobahn_got_timed_out_during_sync() {
         from: node2.consensus_ctx, to: [node0.consensus_ctx, node1.consensus_ctx, node3.consensus_ctx],
         message_matches: ConsensusNetMessage::Commit(..)
     };
+    true
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_commit_different_views_for_f() {
-    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4).await;
+async fn autobahn_commit_different_views_for_f(leader_election: LeaderElectionStrategy) {
+    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4, leader_election).await;
 
-    ConsensusTestCtx::setup_for_round(
+    ConsensusTestCtx::setup_for_elected_round(
         &mut [
             &mut node0.consensus_ctx,
             &mut node1.consensus_ctx,
@@ -1845,6 +2110,8 @@ async fn autobahn_commit_different_views_for_f() {
         0,
         5,
         0,
+        &[(5, 0, 0), (5, 1, 1), (6, 0, 1)],
+        0,
     );
 
     // Slot 5 starts, all nodes receive the prepare
@@ -1861,7 +2128,7 @@ async fn autobahn_commit_different_views_for_f() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1873,7 +2140,7 @@ async fn autobahn_commit_different_views_for_f() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     // At this point, node0 has committed S5V0. It now disconnects.
@@ -1944,9 +2211,8 @@ async fn autobahn_commit_different_views_for_f() {
     };
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_commit_different_views_for_fplusone() {
-    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4).await;
+async fn autobahn_commit_different_views_for_fplusone(leader_election: LeaderElectionStrategy) {
+    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4, leader_election).await;
 
     ConsensusTestCtx::setup_for_round(
         &mut [
@@ -1974,7 +2240,7 @@ async fn autobahn_commit_different_views_for_fplusone() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -1986,7 +2252,7 @@ async fn autobahn_commit_different_views_for_fplusone() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     // Simulate a weird case - node0 has committed the new proposal, and node3 has received the Commit message.
@@ -2022,11 +2288,10 @@ async fn autobahn_commit_different_views_for_fplusone() {
     // TODO: fix this by sending commit messages to the nodes that are timing out so they can unlock themselves.
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_commit_byzantine_across_views_attempts() {
-    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4).await;
+async fn autobahn_commit_byzantine_across_views_attempts(leader_election: LeaderElectionStrategy) {
+    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4, leader_election).await;
 
-    ConsensusTestCtx::setup_for_round(
+    ConsensusTestCtx::setup_for_elected_round(
         &mut [
             &mut node0.consensus_ctx,
             &mut node1.consensus_ctx,
@@ -2036,6 +2301,8 @@ async fn autobahn_commit_byzantine_across_views_attempts() {
         0,
         5,
         0,
+        &[(5, 0, 0), (5, 1, 1)],
+        0,
     );
 
     // Goal of the test: at slot 5 view 0, we have nodes voting on a prepare. A commit could in theory be created if someone side-channels the confirmacks
@@ -2058,7 +2325,7 @@ async fn autobahn_commit_byzantine_across_views_attempts() {
     send! {
         description: "PrepareVote",
         from: [node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     broadcast! {
@@ -2143,11 +2410,10 @@ async fn autobahn_commit_byzantine_across_views_attempts() {
     };
 }
 
-#[test_log::test(tokio::test)]
-async fn autobahn_commit_prepare_qc_across_multiple_views() {
-    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4).await;
+async fn autobahn_commit_prepare_qc_across_multiple_views(leader_election: LeaderElectionStrategy) {
+    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4, leader_election).await;
 
-    ConsensusTestCtx::setup_for_round(
+    ConsensusTestCtx::setup_for_elected_round(
         &mut [
             &mut node0.consensus_ctx,
             &mut node1.consensus_ctx,
@@ -2157,6 +2423,8 @@ async fn autobahn_commit_prepare_qc_across_multiple_views() {
         0,
         5,
         0,
+        &[(5, 0, 0), (5, 1, 1), (5, 2, 2)],
+        0,
     );
 
     // Slot 5 starts, all nodes receive the prepare
@@ -2176,7 +2444,7 @@ async fn autobahn_commit_prepare_qc_across_multiple_views() {
     send! {
         description: "PrepareVote",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::PrepareVote(_)
+        message_matches: ConsensusNetMessage::PrepareVote(..)
     };
 
     // Node0 gets the confirm message out but then crashes before sending commit
@@ -2189,7 +2457,7 @@ async fn autobahn_commit_prepare_qc_across_multiple_views() {
     send! {
         description: "ConfirmAck",
         from: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx], to: node0.consensus_ctx,
-        message_matches: ConsensusNetMessage::ConfirmAck(_)
+        message_matches: ConsensusNetMessage::ConfirmAck(..)
     };
 
     // First timeout - nodes 1,2,3 timeout and move to view 1
@@ -2281,3 +2549,185 @@ async fn autobahn_commit_prepare_qc_across_multiple_views() {
         }
     };
 }
+
+/// Commits a few slots, with whichever node is elected leader of each slot.
+async fn autobahn_elected_leaders_flow(leader_election: LeaderElectionStrategy) {
+    let (mut node0, mut node1, mut node2, mut node3) = build_nodes!(4, leader_election).await;
+
+    let mut leaders = vec![];
+    for slot in 1..9 {
+        // Every node elects the same leader
+        let leader = node0.consensus_ctx.round_leader();
+        for node in [&node1, &node2, &node3] {
+            assert_eq!(node.consensus_ctx.round_leader(), leader);
+        }
+
+        // Node0 always leads the round, whoever it is
+        if node1.consensus_ctx.pubkey() == leader {
+            std::mem::swap(&mut node0, &mut node1);
+        } else if node2.consensus_ctx.pubkey() == leader {
+            std::mem::swap(&mut node0, &mut node2);
+        } else if node3.consensus_ctx.pubkey() == leader {
+            std::mem::swap(&mut node0, &mut node3);
+        }
+        assert_eq!(node0.consensus_ctx.pubkey(), leader);
+
+        node0
+            .start_round_with_cut_from_mempool(TimestampMs(1000 * slot as u128))
+            .await;
+
+        let (cp, _, _) = simple_commit_round! {
+            leader: node0.consensus_ctx,
+            followers: [node1.consensus_ctx, node2.consensus_ctx, node3.consensus_ctx]
+        };
+        assert_eq!(cp.slot, slot);
+
+        leaders.push(leader);
+    }
+
+    if leader_election == LeaderElectionStrategy::RoundRobin {
+        let staking = node0.consensus_ctx.staking();
+        let bonded = staking.bonded();
+        for (slot, leader) in leaders.iter().enumerate() {
+            assert_eq!(leader, &bonded[slot % bonded.len()]);
+        }
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_round_robin_leaders_flow() {
+    autobahn_elected_leaders_flow(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_stake_weighted_leaders_flow() {
+    autobahn_elected_leaders_flow(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_basic_flow_round_robin() {
+    autobahn_basic_flow(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_basic_flow_stake_weighted() {
+    autobahn_basic_flow(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn protocol_fees_round_robin() {
+    protocol_fees(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn protocol_fees_stake_weighted() {
+    protocol_fees(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn consensus_missed_prepare_round_robin() {
+    with_fitting_draws(|attempt| {
+        consensus_missed_prepare(LeaderElectionStrategy::RoundRobin, attempt)
+    })
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn consensus_missed_prepare_stake_weighted() {
+    with_fitting_draws(|attempt| {
+        consensus_missed_prepare(LeaderElectionStrategy::StakeWeighted, attempt)
+    })
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_buffer_early_messages_round_robin() {
+    with_fitting_draws(|attempt| {
+        autobahn_buffer_early_messages(LeaderElectionStrategy::RoundRobin, attempt)
+    })
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_buffer_early_messages_stake_weighted() {
+    with_fitting_draws(|attempt| {
+        autobahn_buffer_early_messages(LeaderElectionStrategy::StakeWeighted, attempt)
+    })
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_got_timed_out_during_sync_round_robin() {
+    with_fitting_draws(|attempt| {
+        autobahn_got_timed_out_during_sync(LeaderElectionStrategy::RoundRobin, attempt)
+    })
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_got_timed_out_during_sync_stake_weighted() {
+    with_fitting_draws(|attempt| {
+        autobahn_got_timed_out_during_sync(LeaderElectionStrategy::StakeWeighted, attempt)
+    })
+    .await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_rejoin_flow_round_robin() {
+    autobahn_rejoin_flow(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_rejoin_flow_stake_weighted() {
+    autobahn_rejoin_flow(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_missed_a_confirm_message_round_robin() {
+    autobahn_missed_a_confirm_message(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_missed_a_confirm_message_stake_weighted() {
+    autobahn_missed_a_confirm_message(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_different_views_for_f_round_robin() {
+    autobahn_commit_different_views_for_f(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_different_views_for_f_stake_weighted() {
+    autobahn_commit_different_views_for_f(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_different_views_for_fplusone_round_robin() {
+    autobahn_commit_different_views_for_fplusone(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_different_views_for_fplusone_stake_weighted() {
+    autobahn_commit_different_views_for_fplusone(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_byzantine_across_views_attempts_round_robin() {
+    autobahn_commit_byzantine_across_views_attempts(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_byzantine_across_views_attempts_stake_weighted() {
+    autobahn_commit_byzantine_across_views_attempts(LeaderElectionStrategy::StakeWeighted).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_prepare_qc_across_multiple_views_round_robin() {
+    autobahn_commit_prepare_qc_across_multiple_views(LeaderElectionStrategy::RoundRobin).await;
+}
+
+#[test_log::test(tokio::test)]
+async fn autobahn_commit_prepare_qc_across_multiple_views_stake_weighted() {
+    autobahn_commit_prepare_qc_across_multiple_views(LeaderElectionStrategy::StakeWeighted).await;
+}
@@ -1,13 +1,17 @@
 use anyhow::{Context, Result};
 use config::{Config, Environment, File};
 use hyle_modules::modules::websocket::WebSocketConfig;
+use hyle_modules::node_state::snapshot::NodeStateSnapshotConf;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 use serde_with::DurationMilliSeconds;
 use std::{collections::HashMap, fmt::Debug, path::PathBuf, sync::Arc, time::Duration};
 use strum_macros::IntoStaticStr;
 
+use crate::data_availability::retention::DaRetentionConf;
 use crate::indexer::IndexerConf;
+use crate::mempool::policy::MempoolConf;
+use crate::model::ValidatorPublicKey;
 
 #[serde_as]
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -22,6 +26,9 @@ pub struct Consensus {
     pub solo: bool,
     /// The timestamp of the genesis block, in seconds since the Unix epoch.
     pub genesis_timestamp: u64,
+    /// How the leader of each slot and view is chosen among bonded validators.
+    /// All validators of a network must use the same strategy.
+    pub leader_election: LeaderElectionStrategy,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, IntoStaticStr)]
@@ -35,12 +42,25 @@ pub enum TimestampCheck {
     NoCheck,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, IntoStaticStr)]
+pub enum LeaderElectionStrategy {
+    /// Bonded validators take turns, regardless of their stake.
+    #[default]
+    RoundRobin,
+    /// Validators of the epoch are drawn proportionally to their stake, seeded by the commit QC its parent carries.
+    StakeWeighted,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct GenesisConf {
     /// Initial bonded stakers and their stakes
     pub stakers: HashMap<String, u64>,
     /// Used for testing - if true, token balance will remain in the faucet.
     pub keep_tokens_in_faucet: bool,
+    /// Number of slots during which the validator set is frozen.
+    /// Bonds and unbonds are only applied by the last proposal of each epoch.
+    /// Written in the genesis block, nodes joining later follow the value of the network.
+    pub epoch_length: u64,
 }
 
 /// Configuration for the P2P layer
@@ -108,6 +128,9 @@ pub struct Conf {
     pub log_format: String,
     /// Directory name to store node state.
     pub data_directory: PathBuf,
+    /// Identifier of the chain, hex encoded as it doesn't fit in a TOML integer
+    #[serde(with = "hex_chain_id")]
+    pub chain_id: u128,
 
     /// Peer-to-peer layer configuration
     pub p2p: P2pConf,
@@ -125,6 +148,8 @@ pub struct Conf {
     pub da_server_port: u16,
     /// Server port for the DA API
     pub da_max_frame_length: usize,
+    /// How long blocks are kept by the DA, and whether older ones are archived or pruned
+    pub da_retention: DaRetentionConf,
 
     pub run_rest_server: bool,
     /// Server port for the REST API
@@ -144,18 +169,38 @@ pub struct Conf {
 
     /// Whether to run the indexer
     pub run_indexer: bool,
-    /// If running the indexer, the postgres address to connect to
+    /// If running the indexer, the database to connect to: a postgres address,
+    /// or `sqlite://<path>` for an embedded SQLite file
     pub database_url: String,
     /// When running only the indexer, the address of the DA server to connect to
     pub da_read_from: String,
+    /// Other DA servers to stream blocks from when `da_read_from` lags or goes down
+    pub da_fallback_read_from: Vec<String>,
+    /// Only accept blocks once two DA servers sent the same block hash
+    pub da_cross_check: bool,
+    /// Validator set at the block we start streaming from and the stake of each validator,
+    /// used to check block certificates. Certificates aren't checked if empty.
+    pub da_trusted_validators: HashMap<ValidatorPublicKey, u64>,
     /// Timeout for DA client requests, in seconds, before it tries to reconnect to stream blocks
     pub da_timeout_client_secs: u64,
 
+    /// Node state snapshots, and optional bootstrap from a trusted snapshot
+    pub node_state_snapshots: NodeStateSnapshotConf,
+    /// Number of blocks between the scheduling of a contract upgrade and its application.
+    /// Must be the same on all nodes of the network.
+    pub contract_upgrade_delay: u64,
+    /// Number of blocks during which proofs can still read a superseded contract state.
+    /// Must be the same on all nodes of the network.
+    pub state_read_window: u64,
+
     /// Websocket configuration
     pub websocket: NodeWebSocketConfig,
 
     /// Configuration for the indexer module
     pub indexer: IndexerConf,
+
+    /// Ordering and limits of the transactions waiting to be disseminated in our lane
+    pub mempool: MempoolConf,
 }
 
 impl Conf {
@@ -179,6 +224,7 @@ impl Conf {
                     .prefix_separator("_")
                     .list_separator(",")
                     .with_list_parse_key("p2p.peers") // Parse this key into Vec<String>
+                    .with_list_parse_key("da_fallback_read_from")
                     .try_parsing(true),
             )
             .set_override_option("data_directory", data_directory)?
@@ -204,6 +250,20 @@ impl Conf {
     }
 }
 
+mod hex_chain_id {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(chain_id: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{chain_id:x}"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        let chain_id = String::deserialize(deserializer)?;
+        u128::from_str_radix(chain_id.trim_start_matches("0x"), 16)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assertables::assert_ok;
@@ -215,6 +275,12 @@ mod tests {
         assert_ok!(Conf::new(vec![], None, None));
     }
 
+    #[test]
+    fn test_default_chain_id() {
+        let conf = Conf::new(vec![], None, None).unwrap();
+        assert_eq!(conf.chain_id, crate::model::HYLE_TESTNET_CHAIN_ID);
+    }
+
     #[test]
     fn test_override_da_public_address() {
         let conf = Conf::new(vec![], None, None).unwrap();
@@ -293,6 +293,9 @@ impl NodeIntegrationCtx {
                 node_id: config.id.clone(),
                 data_directory: config.data_directory.clone(),
                 api: ctx.api.clone(),
+                snapshots: config.node_state_snapshots.clone(),
+                contract_upgrade_delay: config.contract_upgrade_delay,
+                state_read_window: config.state_read_window,
             },
             &mut mocks,
         )
@@ -10,7 +10,7 @@ use anyhow::{Context, Result};
 use api::APIContract;
 use assertables::assert_ok;
 use client_sdk::{rest_client::NodeApiClient, transaction_builder::ProvableBlobTx};
-use hyle_model::api::APINodeContract;
+use hyle_model::api::{APINodeContract, TxStatus, TxStatusKind};
 use testcontainers_modules::{
     postgres::Postgres,
     testcontainers::{runners::AsyncRunner, ContainerAsync, ImageExt},
@@ -383,20 +383,18 @@ impl E2ECtx {
         .as_blob("hyle".into(), None, None)];
 
         let tx = BlobTransaction::new(sender.clone(), blobs.clone());
-        assert_ok!(self.client().send_tx_blob(tx).await);
-
-        tokio::time::timeout(Duration::from_secs(30), async {
-            loop {
-                let resp = self.client().get_contract(name.into()).await;
-                if resp.is_err() || resp.is_err() {
-                    info!("⏰ Waiting for contract {name} state to be ready");
-                    tokio::time::sleep(Duration::from_millis(500)).await;
-                } else {
-                    return;
-                }
-            }
-        })
-        .await?;
+        let tx_hash = self.client().send_tx_blob(tx).await?;
+
+        info!("⏰ Waiting for contract {name} to be registered");
+        let status = self
+            .client()
+            .wait_for_tx_status(tx_hash, TxStatusKind::Settled, Duration::from_secs(30))
+            .await?;
+        anyhow::ensure!(
+            matches!(status.status, TxStatus::Settled { .. }),
+            "Registration of contract {name} did not settle: {:?}",
+            status.status
+        );
         Ok(())
     }
 